@@ -0,0 +1,84 @@
+//! # Static input-output characteristic
+//!
+//! Sweeps the input slowly up and then down and records the quasi-static
+//! output at each input level. Both branches differ for elements with memory
+//! such as hysteresis, and show dead zones and saturation of nonlinear blocks.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use cb_simulation_util::analysis::characteristic::static_characteristic;
+//! use cb_simulation_util::hysteresis::{HysteresisBuilder, LinearFn};
+//!
+//! fn main() {
+//!     let mut h = HysteresisBuilder::<f64>::new(LinearFn { m: 0.0, n: 0.0 }, LinearFn { m: 0.0, n: 1.0 })
+//!         .lower_x(-1.0)
+//!         .upper_x(1.0)
+//!         .build();
+//!     let c = static_characteristic(&mut h, (-2.0, 2.0), 5, 1);
+//!     // at input 0.0 the output depends on the sweep direction
+//!     assert_eq!(c.rising[2], 0.0);
+//!     assert_eq!(c.falling[2], 1.0);
+//! }
+//! ```
+
+use ndarray::Array1;
+
+use crate::plant::TransferTimeDomain;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct StaticCharacteristic {
+    /// Input levels, ascending
+    pub input: Array1<f64>,
+    /// Settled output while sweeping up
+    pub rising: Array1<f64>,
+    /// Settled output while sweeping down, same order as `input`
+    pub falling: Array1<f64>,
+}
+
+/// Sweep the input from `input_range.0` to `input_range.1` and back in `points` levels
+///
+/// Each level is held for `settle_samples` samples and the last output is recorded,
+/// so dynamic elements should get enough samples to settle.
+pub fn static_characteristic<E: TransferTimeDomain<f64> + ?Sized>(
+    plant: &mut E,
+    input_range: (f64, f64),
+    points: usize,
+    settle_samples: usize,
+) -> StaticCharacteristic {
+    let input = Array1::linspace(input_range.0, input_range.1, points);
+    let mut settle = |u: f64| {
+        let mut y = 0.0;
+        for _ in 0..settle_samples.max(1) {
+            y = plant.transfer_td(u);
+        }
+        y
+    };
+    let rising: Array1<f64> = input.iter().map(|u| settle(*u)).collect();
+    let mut falling: Array1<f64> = input.iter().rev().map(|u| settle(*u)).collect();
+    falling.invert_axis(ndarray::Axis(0));
+    StaticCharacteristic {
+        input,
+        rising,
+        falling,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::plant::pt1::PT1;
+
+    #[test]
+    fn test_static_characteristic_linear_lag() {
+        let mut plant = PT1::<f64>::default()
+            .set_kp(2.0)
+            .set_t1_time_or_default(2.0);
+        let c = static_characteristic(&mut plant, (0.0, 1.0), 3, 60);
+        for i in 0..3 {
+            assert!((c.rising[i] - 2.0 * c.input[i]).abs() < 1e-9);
+            assert!((c.falling[i] - 2.0 * c.input[i]).abs() < 1e-9);
+        }
+    }
+}
@@ -0,0 +1,170 @@
+//! # Batch comparison of controller candidates
+//!
+//! Simulates the same plant and setpoint scenario in closed loop with every
+//! candidate controller and ranks the candidates by a list of metrics.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use cb_simulation_util::analysis::comparison::{compare_controllers, Criterion};
+//! use cb_simulation_util::analysis::requirements::Metric;
+//! use cb_simulation_util::analysis::TimeWindow;
+//! use cb_simulation_util::plant::{BoxedTransferTimeDomain, pt0::PT0, pt1::PT1};
+//! use cb_simulation_util::signal::{StepFunction, TimeRange};
+//!
+//! fn main() {
+//!     let plant = PT1::<f64>::default().set_t1_time_or_default(5.0);
+//!     let candidates: Vec<(&str, BoxedTransferTimeDomain<f64>)> = vec![
+//!         ("P 0.5", Box::new(PT0::<f64>::default().set_kp(0.5))),
+//!         ("P 2", Box::new(PT0::<f64>::default().set_kp(2.0))),
+//!     ];
+//!     let table = compare_controllers(
+//!         &plant,
+//!         &StepFunction::default(),
+//!         TimeRange::default(),
+//!         &candidates,
+//!         &[Criterion::new("ISE", "error", Metric::Ise(TimeWindow::All))],
+//!     )
+//!     .unwrap();
+//!     assert_eq!(table.rows[0].candidate, "P 2");
+//! }
+//! ```
+
+use core::fmt::{self, Display};
+use std::string::String;
+use std::vec::Vec;
+
+use super::requirements::Metric;
+use crate::plant::{BoxedTransferTimeDomain, TransferTimeDomain};
+use crate::signal::{TimeRange, TimeSignal, TimeUnitError};
+use crate::sim::Simulation;
+
+/// A metric of one of the closed loop traces (`setpoint`, `error`, `control`, `output`)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Criterion {
+    pub name: String,
+    pub trace: String,
+    pub metric: Metric,
+}
+
+impl Criterion {
+    pub fn new(name: &str, trace: &str, metric: Metric) -> Self {
+        Criterion {
+            name: String::from(name),
+            trace: String::from(trace),
+            metric,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ComparisonRow {
+    pub rank: usize,
+    pub candidate: String,
+    /// One value per criterion
+    pub values: Vec<f64>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ComparisonTable {
+    pub criteria: Vec<String>,
+    /// Sorted by rank, best first
+    pub rows: Vec<ComparisonRow>,
+}
+
+impl Display for ComparisonTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "rank\tcandidate")?;
+        for c in &self.criteria {
+            write!(f, "\t{}", c)?;
+        }
+        for row in &self.rows {
+            write!(f, "\n{}\t{}", row.rank, row.candidate)?;
+            for v in &row.values {
+                write!(f, "\t{}", v)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Simulate each candidate in a unity feedback loop with a fresh copy of `plant`
+///
+/// Candidates are ranked by the first criterion (lower is better),
+/// ties are resolved by the following criteria. Time based metrics are
+/// evaluated in seconds, fails if the range is of an unknown time unit.
+pub fn compare_controllers<P: TransferTimeDomain<f64> + Clone>(
+    plant: &P,
+    setpoint: &dyn TimeSignal<f64>,
+    range: TimeRange,
+    candidates: &[(&str, BoxedTransferTimeDomain<f64>)],
+    criteria: &[Criterion],
+) -> Result<ComparisonTable, TimeUnitError> {
+    let simulation = Simulation::new(range);
+    let mut rows: Vec<ComparisonRow> = candidates
+        .iter()
+        .map(|(name, controller)| {
+            let mut controller = controller.clone();
+            let result = simulation.run_closed_loop(setpoint, &mut *controller, &mut plant.clone());
+            Ok(ComparisonRow {
+                rank: 0,
+                candidate: String::from(*name),
+                values: criteria
+                    .iter()
+                    .map(|c| c.metric.evaluate(&result, &c.trace))
+                    .collect::<Result<_, _>>()?,
+            })
+        })
+        .collect::<Result<_, TimeUnitError>>()?;
+    rows.sort_by(|a, b| {
+        a.values
+            .iter()
+            .zip(b.values.iter())
+            .map(|(x, y)| x.total_cmp(y))
+            .find(|o| o.is_ne())
+            .unwrap_or(core::cmp::Ordering::Equal)
+    });
+    for (i, row) in rows.iter_mut().enumerate() {
+        row.rank = i + 1;
+    }
+    Ok(ComparisonTable {
+        criteria: criteria.iter().map(|c| c.name.clone()).collect(),
+        rows,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::plant::pt0::PT0;
+    use crate::plant::pt1::PT1;
+    use crate::signal::StepFunction;
+    use std::boxed::Box;
+    use std::vec;
+
+    #[test]
+    fn test_compare_controllers_tie_break() {
+        let plant = PT1::<f64>::default().set_t1_time_or_default(2.0);
+        let candidates: Vec<(&str, BoxedTransferTimeDomain<f64>)> = vec![
+            ("a", Box::new(PT0::<f64>::default().set_kp(1.0))),
+            ("b", Box::new(PT0::<f64>::default().set_kp(1.0))),
+            ("c", Box::new(PT0::<f64>::default().set_kp(3.0))),
+        ];
+        let table = compare_controllers(
+            &plant,
+            &StepFunction::default(),
+            TimeRange::default().set_end(30.0),
+            &candidates,
+            &[
+                Criterion::new("final", "setpoint", Metric::FinalValue),
+                Criterion::new("max control", "control", Metric::Max),
+            ],
+        )
+        .unwrap();
+        let order: Vec<&str> = table.rows.iter().map(|r| r.candidate.as_str()).collect();
+        assert_eq!(order, vec!["a", "b", "c"]);
+        assert_eq!(table.rows[2].rank, 3);
+        assert_eq!(table.rows[2].values, vec![1.0, 3.0]);
+    }
+}
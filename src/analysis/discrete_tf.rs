@@ -0,0 +1,347 @@
+//! # Discrete-time transfer functions
+//!
+//! $ G(z) = \frac{b_{0} + b_{1} z^{-1} + ... + b_{m} z^{-m}}{a_{0} + a_{1} z^{-1} + ... + a_{n} z^{-n}} $
+//!
+//! `DiscreteTF` is derived exactly from the recurrences of the linear plant
+//! elements (PT0, PT1, PT2). A [`LinearDiagram`] of such blocks, gains, sums,
+//! delays and feedback loops is reduced to one overall `DiscreteTF`, so the
+//! analysis tooling can operate on compositions and not just single elements.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use cb_simulation_util::analysis::discrete_tf::{LinearBlock, LinearDiagram};
+//! use cb_simulation_util::plant::pt1::PT1;
+//!
+//! fn main() {
+//!     let plant = PT1::<f64>::default().set_t1_time_or_default(2.0).set_kp(3.0);
+//!     let diagram = LinearDiagram::Feedback {
+//!         forward: Box::new(LinearDiagram::Series(vec![
+//!             LinearDiagram::Gain(2.0),
+//!             LinearDiagram::Block(plant.discrete_tf()),
+//!         ])),
+//!         feedback: Box::new(LinearDiagram::Delay(1)),
+//!     };
+//!     let tf = diagram.discrete_tf();
+//!     assert!((tf.dc_gain() - 6.0 / 7.0).abs() < 1e-12);
+//! }
+//! ```
+
+use core::fmt::{self, Display};
+use std::boxed::Box;
+use std::vec;
+use std::vec::Vec;
+
+use super::poly;
+use crate::plant::dead_time::DeadTime;
+use crate::plant::pt0::PT0;
+use crate::plant::pt1::PT1;
+use crate::plant::pt2::PT2;
+use crate::plant::ptn::PTn;
+use crate::plant::solver::Solver;
+
+/// Transfer function in powers of $z^{-1}$, `den[0]` is normalized to 1
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiscreteTF {
+    pub num: Vec<f64>,
+    pub den: Vec<f64>,
+}
+
+impl DiscreteTF {
+    pub fn new(num: Vec<f64>, den: Vec<f64>) -> Self {
+        let a0 = den.first().copied().unwrap_or(1.0);
+        DiscreteTF {
+            num: poly::trim(poly::scale(&num, 1.0 / a0)),
+            den: poly::trim(poly::scale(&den, 1.0 / a0)),
+        }
+    }
+
+    pub fn gain(k: f64) -> Self {
+        DiscreteTF::new(vec![k], vec![1.0])
+    }
+
+    /// Pure delay of `samples` samples
+    pub fn delay(samples: usize) -> Self {
+        let mut num = vec![0.0; samples + 1];
+        num[samples] = 1.0;
+        DiscreteTF::new(num, vec![1.0])
+    }
+
+    pub fn series(&self, other: &DiscreteTF) -> Self {
+        DiscreteTF::new(
+            poly::mul(&self.num, &other.num),
+            poly::mul(&self.den, &other.den),
+        )
+    }
+
+    pub fn parallel(&self, other: &DiscreteTF) -> Self {
+        DiscreteTF::new(
+            poly::add(
+                &poly::mul(&self.num, &other.den),
+                &poly::mul(&other.num, &self.den),
+            ),
+            poly::mul(&self.den, &other.den),
+        )
+    }
+
+    /// Negative feedback of `feedback` around `self`: $ \frac{G}{1 + G H} $
+    pub fn feedback(&self, feedback: &DiscreteTF) -> Self {
+        DiscreteTF::new(
+            poly::mul(&self.num, &feedback.den),
+            poly::add(
+                &poly::mul(&self.den, &feedback.den),
+                &poly::mul(&self.num, &feedback.num),
+            ),
+        )
+    }
+
+    /// Steady-state gain $G(z = 1)$
+    pub fn dc_gain(&self) -> f64 {
+        self.num.iter().sum::<f64>() / self.den.iter().sum::<f64>()
+    }
+
+    /// Magnitude and phase (radians) at `frequency` in cycles per time unit
+    ///
+    /// Evaluates $G(e^{j \omega T_{s}})$ for the given sample time $T_{s}$.
+    pub fn frequency_response(&self, frequency: f64, sample_time: f64) -> (f64, f64) {
+        let theta = 2.0 * core::f64::consts::PI * frequency * sample_time;
+        let eval = |coefficients: &[f64]| {
+            coefficients
+                .iter()
+                .enumerate()
+                .fold((0.0, 0.0), |(re, im), (k, c)| {
+                    let phi = -theta * k as f64;
+                    (re + c * phi.cos(), im + c * phi.sin())
+                })
+        };
+        let (nr, ni) = eval(&self.num);
+        let (dr, di) = eval(&self.den);
+        let magnitude = (nr * nr + ni * ni).sqrt() / (dr * dr + di * di).sqrt();
+        (magnitude, ni.atan2(nr) - di.atan2(dr))
+    }
+
+    /// Response of the difference equation to `input`, starting at rest
+    pub fn simulate(&self, input: &[f64]) -> Vec<f64> {
+        let mut output: Vec<f64> = Vec::with_capacity(input.len());
+        for k in 0..input.len() {
+            let mut y = 0.0;
+            for (i, b) in self.num.iter().enumerate() {
+                if k >= i {
+                    y += b * input[k - i];
+                }
+            }
+            for (i, a) in self.den.iter().enumerate().skip(1) {
+                if k >= i {
+                    y -= a * output[k - i];
+                }
+            }
+            output.push(y);
+        }
+        output
+    }
+}
+
+impl Display for DiscreteTF {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "DiscreteTF(num: {:?}, den: {:?})", self.num, self.den)
+    }
+}
+
+/// Elements with an exact linear discrete-time representation
+pub trait LinearBlock {
+    fn discrete_tf(&self) -> DiscreteTF;
+}
+
+impl LinearBlock for PT0<f64> {
+    fn discrete_tf(&self) -> DiscreteTF {
+        let samples = (self.t0_time / self.sample_time) as usize;
+        DiscreteTF::delay(samples).series(&DiscreteTF::gain(self.kp))
+    }
+}
+
+impl LinearBlock for DeadTime<f64> {
+    fn discrete_tf(&self) -> DiscreteTF {
+        DiscreteTF::delay(self.delay_samples()).series(&DiscreteTF::gain(self.kp))
+    }
+}
+
+impl LinearBlock for PT1<f64> {
+    // y[k] = y[k-1] + alpha * (kp * u[k] - y[k-1]), alpha of the solver,
+    // Trapezoidal with the mean of u[k] and u[k-1]
+    fn discrete_tf(&self) -> DiscreteTF {
+        let alpha = self.alpha();
+        let num = match self.solver {
+            Solver::Trapezoidal => vec![0.5 * alpha * self.kp, 0.5 * alpha * self.kp],
+            _ => vec![alpha * self.kp],
+        };
+        DiscreteTF::new(num, vec![1.0, alpha - 1.0])
+    }
+}
+
+impl LinearBlock for PT2<f64> {
+    // y = [1 0] x with x[k] = Ad x[k-1] + Bd kp u[k] of the solver:
+    // Y/U = kp (b1 + (a12 b2 - a22 b1) z^-1) / det(I - Ad z^-1),
+    // Trapezoidal with the mean of u[k] and u[k-1]. For Euler forward the
+    // denominator is (1 - z^-1) (1 - c z^-1) + h^2 omega^3 z^-2 with
+    // c = 1 - 2 D omega h.
+    fn discrete_tf(&self) -> DiscreteTF {
+        let (ad, bd) = self.discretized();
+        let num = vec![
+            self.kp * bd[0],
+            self.kp * (ad[[0, 1]] * bd[1] - ad[[1, 1]] * bd[0]),
+        ];
+        let num = match self.solver {
+            Solver::Trapezoidal => poly::mul(&num, &[0.5, 0.5]),
+            _ => num,
+        };
+        let den = vec![
+            1.0,
+            -(ad[[0, 0]] + ad[[1, 1]]),
+            ad[[0, 0]] * ad[[1, 1]] - ad[[0, 1]] * ad[[1, 0]],
+        ];
+        DiscreteTF::new(num, den)
+    }
+}
+
+impl LinearBlock for PTn<f64> {
+    // series of identical PT1 stages, the gain in the first stage
+    fn discrete_tf(&self) -> DiscreteTF {
+        let alpha = self.sample_time / self.tn_time;
+        let stage = DiscreteTF::new(vec![alpha], vec![1.0, alpha - 1.0]);
+        (1..self.order()).fold(stage.series(&DiscreteTF::gain(self.kp)), |tf, _| {
+            tf.series(&stage)
+        })
+    }
+}
+
+/// A block diagram made of linear parts only
+#[derive(Debug, Clone, PartialEq)]
+pub enum LinearDiagram {
+    Block(DiscreteTF),
+    Gain(f64),
+    /// Delay by a number of samples
+    Delay(usize),
+    /// Blocks connected one after another
+    Series(Vec<LinearDiagram>),
+    /// Sum of the outputs of blocks sharing the same input
+    Sum(Vec<LinearDiagram>),
+    /// Negative feedback loop
+    Feedback {
+        forward: Box<LinearDiagram>,
+        feedback: Box<LinearDiagram>,
+    },
+}
+
+impl LinearDiagram {
+    /// Reduce the diagram to one overall transfer function
+    pub fn discrete_tf(&self) -> DiscreteTF {
+        match self {
+            LinearDiagram::Block(tf) => tf.clone(),
+            LinearDiagram::Gain(k) => DiscreteTF::gain(*k),
+            LinearDiagram::Delay(n) => DiscreteTF::delay(*n),
+            LinearDiagram::Series(parts) => parts
+                .iter()
+                .fold(DiscreteTF::gain(1.0), |tf, p| tf.series(&p.discrete_tf())),
+            LinearDiagram::Sum(parts) => parts
+                .iter()
+                .fold(DiscreteTF::gain(0.0), |tf, p| tf.parallel(&p.discrete_tf())),
+            LinearDiagram::Feedback { forward, feedback } => {
+                forward.discrete_tf().feedback(&feedback.discrete_tf())
+            }
+        }
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::plant::TransferTimeDomain;
+
+    fn step_input() -> Vec<f64> {
+        (0..50).map(|k| if k < 3 { 0.0 } else { 1.0 }).collect()
+    }
+
+    fn assert_same(tf: &DiscreteTF, element: &mut dyn TransferTimeDomain<f64>) {
+        let input = step_input();
+        let expected: Vec<f64> = input.iter().map(|u| element.transfer_td(*u)).collect();
+        for (a, b) in tf.simulate(&input).iter().zip(expected.iter()) {
+            assert!((a - b).abs() < 1e-9, "{} != {}", a, b);
+        }
+    }
+
+    #[test]
+    fn test_DiscreteTF_matches_elements() {
+        let mut pt0 = PT0::<f64>::default()
+            .set_t0_time_or_default(4.0)
+            .set_kp(2.0);
+        assert_same(&pt0.discrete_tf(), &mut pt0);
+        let mut pt1 = PT1::<f64>::default()
+            .set_t1_time_or_default(3.0)
+            .set_kp(0.5);
+        assert_same(&pt1.discrete_tf(), &mut pt1);
+        let mut pt2 = PT2::<f64>::default()
+            .set_sample_time_or_default(0.1)
+            .set_omega_or_default(2.0)
+            .set_damping_or_default(0.3)
+            .set_kp(1.5);
+        assert_same(&pt2.discrete_tf(), &mut pt2);
+        let mut ptn = PTn::<f64>::default()
+            .set_order_or_default(3)
+            .set_tn_time_or_default(2.5)
+            .set_kp(2.0);
+        assert_same(&ptn.discrete_tf(), &mut ptn);
+    }
+
+    #[test]
+    fn test_DiscreteTF_matches_solvers() {
+        for solver in [
+            Solver::EulerForward,
+            Solver::EulerBackward,
+            Solver::Trapezoidal,
+            Solver::RungeKutta4,
+        ] {
+            let mut pt1 = PT1::<f64>::default()
+                .set_sample_time_or_default(0.5)
+                .set_solver(solver)
+                .set_t1_time_or_default(2.0)
+                .set_kp(3.0);
+            assert_same(&pt1.discrete_tf(), &mut pt1);
+            let mut pt2 = PT2::<f64>::default()
+                .set_sample_time_or_default(0.2)
+                .set_solver(solver)
+                .set_omega_or_default(1.5)
+                .set_damping_or_default(0.4)
+                .set_kp(2.0);
+            assert_same(&pt2.discrete_tf(), &mut pt2);
+        }
+    }
+
+    #[test]
+    fn test_LinearDiagram_series_matches_chain() {
+        let pt1 = PT1::<f64>::default().set_t1_time_or_default(3.0);
+        let pt0 = PT0::<f64>::default().set_t0_time_or_default(2.0);
+        let tf = LinearDiagram::Series(vec![
+            LinearDiagram::Block(pt1.discrete_tf()),
+            LinearDiagram::Block(pt0.discrete_tf()),
+        ])
+        .discrete_tf();
+        let (mut pt1, mut pt0) = (pt1, pt0);
+        let input = step_input();
+        let expected: Vec<f64> = input
+            .iter()
+            .map(|u| pt0.transfer_td(pt1.transfer_td(*u)))
+            .collect();
+        for (a, b) in tf.simulate(&input).iter().zip(expected.iter()) {
+            assert!((a - b).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_LinearDiagram_sum() {
+        let tf = LinearDiagram::Sum(vec![LinearDiagram::Gain(2.0), LinearDiagram::Delay(1)])
+            .discrete_tf();
+        assert_eq!(tf.simulate(&[1.0, 0.0, 0.0]), vec![2.0, 1.0, 0.0]);
+    }
+}
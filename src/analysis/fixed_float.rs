@@ -0,0 +1,230 @@
+//! # Fixed point versus floating point
+//!
+//! Runs the `i32` and the `f64` version of the same configured element, or
+//! of the same controller in a loop, side by side on identical stimuli. The
+//! fixed point element sees its input converted with a `FixedPoint` scaling
+//! and its output converted back, so both runs produce real valued traces.
+//! The quantization error over time and its statistics in multiples of the
+//! least significant bit help picking word lengths before deployment.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use cb_simulation_util::analysis::fixed_float::compare_element;
+//! use cb_simulation_util::plant::iir_biquad::IIRBiquad;
+//! use cb_simulation_util::signal::{FixedPoint, StepFunction, TimeRange};
+//! use cb_simulation_util::sim::Simulation;
+//!
+//! fn main() {
+//!     let sim = Simulation::new(TimeRange::default().set_end(100.0));
+//!     let step = StepFunction::default().step(1.0);
+//!     let mut float = IIRBiquad::<f64>::butterworth_low_pass(0.05, 1.0).unwrap();
+//!     let mut fixed = IIRBiquad::<i32>::butterworth_low_pass(0.05, 1.0).unwrap();
+//!     let report = compare_element(&sim, &step, &mut float, &mut fixed, FixedPoint::new(10));
+//!     println!("{}", report);
+//!     assert!(report.max_error_lsb("output").unwrap() < 8.0);
+//! }
+//! ```
+
+use core::fmt::{self, Display};
+
+use crate::plant::{SampleTime, TransferTimeDomain, TypeIdentifier};
+use crate::signal::{FixedPoint, TimeSignal};
+use crate::sim::{SimDiff, SimResult, Simulation};
+
+/// Real valued view of a fixed point element
+///
+/// Converts the input to fixed point with `scaling`, runs the element and
+/// converts its output back.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FixedPointView<E> {
+    pub element: E,
+    pub scaling: FixedPoint,
+}
+
+impl<E> FixedPointView<E> {
+    pub fn new(element: E, scaling: FixedPoint) -> Self {
+        FixedPointView { element, scaling }
+    }
+}
+
+impl<E: TypeIdentifier> TypeIdentifier for FixedPointView<E> {
+    fn short_type_name(&self) -> &'static str {
+        self.element.short_type_name()
+    }
+}
+
+impl<E: SampleTime> SampleTime for FixedPointView<E> {
+    fn sample_time(&self) -> Option<f64> {
+        self.element.sample_time()
+    }
+}
+
+impl<E: TransferTimeDomain<i32>> TransferTimeDomain<f64> for FixedPointView<E> {
+    fn transfer_td(&mut self, input: f64) -> f64 {
+        let output = self
+            .element
+            .transfer_td(self.scaling.to_fixed::<i32>(input));
+        self.scaling.to_real(output)
+    }
+
+    fn output_unit(&self, input_unit: &'static str) -> &'static str {
+        self.element.output_unit(input_unit)
+    }
+
+    fn check_units(
+        &self,
+        input_unit: &'static str,
+    ) -> Result<&'static str, crate::plant::unit_gain::UnitCheckError> {
+        self.element.check_units(input_unit)
+    }
+}
+
+/// Both runs and their difference `fixed - float`
+#[derive(Debug, Clone, PartialEq)]
+pub struct FixedFloatComparison {
+    pub float: SimResult,
+    pub fixed: SimResult,
+    pub diff: SimDiff,
+    /// Value of the least significant bit of the fixed point scaling
+    pub lsb: f64,
+}
+
+impl FixedFloatComparison {
+    fn new(float: SimResult, fixed: SimResult, scaling: FixedPoint) -> Self {
+        let diff = float.diff(&fixed);
+        FixedFloatComparison {
+            float,
+            fixed,
+            diff,
+            lsb: scaling.resolution(),
+        }
+    }
+
+    /// Largest absolute error of `trace` in LSB
+    pub fn max_error_lsb(&self, trace: &str) -> Option<f64> {
+        self.diff.deviation(trace).map(|d| d.max_abs / self.lsb)
+    }
+
+    /// RMS error of `trace` in LSB
+    pub fn rms_error_lsb(&self, trace: &str) -> Option<f64> {
+        self.diff.deviation(trace).map(|d| d.rms / self.lsb)
+    }
+
+    /// Mean error of `trace` in LSB, a systematic offset e.g. from truncation
+    pub fn bias_lsb(&self, trace: &str) -> Option<f64> {
+        let errors = self.diff.errors.trace(trace)?;
+        errors.values.mean().map(|m| m / self.lsb)
+    }
+}
+
+impl Display for FixedFloatComparison {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Fixed vs float (lsb: {})", self.lsb)?;
+        for d in &self.diff.deviations {
+            writeln!(
+                f,
+                "  {}: max {:.2} lsb, rms {:.2} lsb, bias {:.2} lsb",
+                d.name,
+                d.max_abs / self.lsb,
+                d.rms / self.lsb,
+                self.bias_lsb(&d.name).unwrap_or(f64::NAN)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Run `signal` through the float and the fixed point version of an element
+pub fn compare_element<F, I>(
+    sim: &Simulation,
+    signal: &dyn TimeSignal<f64>,
+    float: &mut F,
+    fixed: &mut I,
+    scaling: FixedPoint,
+) -> FixedFloatComparison
+where
+    F: TransferTimeDomain<f64> + ?Sized,
+    I: TransferTimeDomain<i32> + Clone,
+{
+    let float_result = sim.run(signal, float);
+    let mut view = FixedPointView::new(fixed.clone(), scaling);
+    let fixed_result = sim.run(signal, &mut view);
+    *fixed = view.element;
+    FixedFloatComparison::new(float_result, fixed_result, scaling)
+}
+
+/// Run the float and the fixed point version of a controller in a unity
+/// feedback loop around identical copies of `plant`
+///
+/// Only the controller is quantized, the plant stays real valued like the
+/// physical process the firmware controls.
+pub fn compare_loop<F, I, P>(
+    sim: &Simulation,
+    setpoint: &dyn TimeSignal<f64>,
+    float: &mut F,
+    fixed: &mut I,
+    plant: &P,
+    scaling: FixedPoint,
+) -> FixedFloatComparison
+where
+    F: TransferTimeDomain<f64> + ?Sized,
+    I: TransferTimeDomain<i32> + Clone,
+    P: TransferTimeDomain<f64> + Clone,
+{
+    let float_result = sim.run_closed_loop(setpoint, float, &mut plant.clone());
+    let mut view = FixedPointView::new(fixed.clone(), scaling);
+    let fixed_result = sim.run_closed_loop(setpoint, &mut view, &mut plant.clone());
+    *fixed = view.element;
+    FixedFloatComparison::new(float_result, fixed_result, scaling)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::controller::pi::{AntiWindup, PI};
+    use crate::plant::pt1::PT1;
+    use crate::signal::{StepFunction, TimeRange};
+
+    #[test]
+    fn test_compare_loop_word_length() {
+        let sim = Simulation::new(TimeRange::default().set_end(100.0));
+        let setpoint = StepFunction::default().step(2.0);
+        let plant = PT1::<f64>::default().set_t1_time_or_default(10.0);
+        let error = |frac_bits: u8| {
+            let mut float = PI::<f64>::default()
+                .set_kp(2.0)
+                .set_ti_time_or_default(8.0)
+                .set_anti_windup(AntiWindup::None);
+            let mut fixed = PI::<i32>::default()
+                .set_kp(2)
+                .set_ti_time_or_default(8.0)
+                .set_anti_windup(AntiWindup::None);
+            let report = compare_loop(
+                &sim,
+                &setpoint,
+                &mut float,
+                &mut fixed,
+                &plant,
+                FixedPoint::new(frac_bits),
+            );
+            assert_eq!(report.diff.unmatched.len(), 0);
+            report.diff.deviation("output").unwrap().max_abs
+        };
+        // more bits after the comma, smaller error in real units
+        assert!(error(10) < error(2));
+        assert!(error(10) < 0.01);
+    }
+
+    #[test]
+    fn test_compare_element_identical_for_integers() {
+        let sim = Simulation::new(TimeRange::default().set_end(10.0));
+        let step = StepFunction::default().step(3.0);
+        let mut float = crate::plant::pt0::PT0::<f64>::default().set_kp(2.0);
+        let mut fixed = crate::plant::pt0::PT0::<i32>::default().set_kp(2);
+        let report = compare_element(&sim, &step, &mut float, &mut fixed, FixedPoint::new(0));
+        assert!(report.diff.is_identical());
+        assert_eq!(report.bias_lsb("output"), Some(0.0));
+    }
+}
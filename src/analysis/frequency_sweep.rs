@@ -0,0 +1,300 @@
+//! # Frequency sweep experiment
+//!
+//! Determines a Bode dataset by simulation: for each frequency of a grid a
+//! fresh copy of the element is excited with a sinusoid, the transient is
+//! left to decay, and amplitude and phase of the fundamental are extracted
+//! by correlation with sine and cosine over whole periods.
+//!
+//! Works for nonlinear and composite elements, for which no analytic
+//! frequency response exists (the describing function is measured then).
+//!
+//! The sample time and the settle time are in the `time_unit` of the
+//! element parameters, s by default, the frequencies are in Hz.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use cb_simulation_util::analysis::frequency_sweep::FrequencySweep;
+//! use cb_simulation_util::plant::pt0::PT0;
+//!
+//! fn main() {
+//!     let delay = PT0::<f64>::default()
+//!         .set_sample_time_or_default(0.01)
+//!         .set_t0_time_or_default(0.25)
+//!         .set_kp(2.0);
+//!     let bode = FrequencySweep::default().set_sample_time(0.01).run(&delay, &[1.0]).unwrap();
+//!     assert!((bode[0].magnitude - 2.0).abs() < 1e-9);
+//!     assert!((bode[0].phase + 90.0).abs() < 1e-6);
+//! }
+//! ```
+
+use core::f64::consts::PI;
+use core::fmt::{self, Display};
+use std::vec::Vec;
+
+use crate::plant::TransferTimeDomain;
+use crate::signal::{TimeUnitError, convert_time};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SweepError {
+    /// The time unit of the sweep is unknown
+    TimeUnit(TimeUnitError),
+    /// The frequency is not finite and > 0
+    Frequency(f64),
+    /// The sample time is not finite and > 0
+    SampleTime(f64),
+}
+
+impl Display for SweepError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SweepError::TimeUnit(error) => write!(f, "{}", error),
+            SweepError::Frequency(frequency) => {
+                write!(f, "Invalid frequency {}: Must be finite and > 0", frequency)
+            }
+            SweepError::SampleTime(sample_time) => {
+                write!(
+                    f,
+                    "Invalid sample time {}: Must be finite and > 0",
+                    sample_time
+                )
+            }
+        }
+    }
+}
+
+impl From<TimeUnitError> for SweepError {
+    fn from(error: TimeUnitError) -> Self {
+        SweepError::TimeUnit(error)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BodePoint {
+    /// Frequency in Hz
+    pub frequency: f64,
+    /// Ratio of output to input amplitude of the fundamental
+    pub magnitude: f64,
+    /// Phase of the output relative to the input in degrees, unwrapped along the grid
+    pub phase: f64,
+}
+
+impl BodePoint {
+    pub fn magnitude_db(&self) -> f64 {
+        20.0 * self.magnitude.log10()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FrequencySweep {
+    pub sample_time: f64,
+    /// Time unit of `sample_time` and `settle_time`
+    pub time_unit: &'static str,
+    pub amplitude: f64,
+    pub offset: f64,
+    /// Periods simulated before measuring
+    pub settle_periods: usize,
+    /// Minimum time simulated before measuring, for slow elements at high frequencies
+    pub settle_time: f64,
+    /// Periods used for the correlation
+    pub measure_periods: usize,
+}
+
+impl Default for FrequencySweep {
+    fn default() -> Self {
+        FrequencySweep {
+            sample_time: 1.0,
+            time_unit: "s",
+            amplitude: 1.0,
+            offset: 0.0,
+            settle_periods: 10,
+            settle_time: 0.0,
+            measure_periods: 4,
+        }
+    }
+}
+
+impl FrequencySweep {
+    pub fn set_sample_time(self, sample_time: f64) -> Self {
+        FrequencySweep {
+            sample_time,
+            ..self
+        }
+    }
+
+    /// Time unit of the sample time, fails if it is not a known time unit
+    pub fn set_time_unit(self, time_unit: &'static str) -> Result<Self, TimeUnitError> {
+        convert_time(1.0, time_unit, "s")?;
+        Ok(FrequencySweep { time_unit, ..self })
+    }
+
+    pub fn set_amplitude(self, amplitude: f64) -> Self {
+        FrequencySweep { amplitude, ..self }
+    }
+
+    pub fn set_offset(self, offset: f64) -> Self {
+        FrequencySweep { offset, ..self }
+    }
+
+    pub fn set_settle_periods(self, settle_periods: usize) -> Self {
+        FrequencySweep {
+            settle_periods,
+            ..self
+        }
+    }
+
+    pub fn set_settle_time(self, settle_time: f64) -> Self {
+        FrequencySweep {
+            settle_time,
+            ..self
+        }
+    }
+
+    pub fn set_measure_periods(self, measure_periods: usize) -> Self {
+        FrequencySweep {
+            measure_periods: measure_periods.max(1),
+            ..self
+        }
+    }
+
+    /// Measure one point of the frequency response, `plant` is used as is
+    ///
+    /// `frequency` is in Hz, fails if it or the sample time is not finite and > 0.
+    pub fn measure<E: TransferTimeDomain<f64> + ?Sized>(
+        &self,
+        plant: &mut E,
+        frequency: f64,
+    ) -> Result<BodePoint, SweepError> {
+        if !(frequency.is_finite() && frequency > 0.0) {
+            return Err(SweepError::Frequency(frequency));
+        }
+        if !(self.sample_time.is_finite() && self.sample_time > 0.0) {
+            return Err(SweepError::SampleTime(self.sample_time));
+        }
+        // cycles per time unit of the sample time
+        let cycles = convert_time(frequency, self.time_unit, "s")?;
+        let omega = 2.0 * PI * cycles;
+        let settle_samples = ((self.settle_periods as f64 / cycles).max(self.settle_time)
+            / self.sample_time)
+            .ceil() as usize;
+        let measure_samples =
+            ((self.measure_periods as f64 / cycles) / self.sample_time).round() as usize;
+        let mut k = 0usize;
+        let mut step = |plant: &mut E| {
+            let t = k as f64 * self.sample_time;
+            k += 1;
+            let y = plant.transfer_td(self.offset + self.amplitude * (omega * t).sin());
+            (t, y)
+        };
+        for _ in 0..settle_samples {
+            step(plant);
+        }
+        let (mut in_phase, mut quadrature) = (0.0, 0.0);
+        for _ in 0..measure_samples.max(1) {
+            let (t, y) = step(plant);
+            in_phase += y * (omega * t).sin();
+            quadrature += y * (omega * t).cos();
+        }
+        let n = measure_samples.max(1) as f64;
+        let (in_phase, quadrature) = (2.0 * in_phase / n, 2.0 * quadrature / n);
+        Ok(BodePoint {
+            frequency,
+            magnitude: (in_phase * in_phase + quadrature * quadrature).sqrt() / self.amplitude,
+            phase: quadrature.atan2(in_phase).to_degrees(),
+        })
+    }
+
+    /// Measure the frequency response for every frequency of the grid
+    ///
+    /// Each frequency starts with a fresh clone of `plant`.
+    pub fn run<E: TransferTimeDomain<f64> + Clone>(
+        &self,
+        plant: &E,
+        frequencies: &[f64],
+    ) -> Result<Vec<BodePoint>, SweepError> {
+        let mut points: Vec<BodePoint> = Vec::with_capacity(frequencies.len());
+        for f in frequencies {
+            let mut point = self.measure(&mut plant.clone(), *f)?;
+            if let Some(previous) = points.last() {
+                while point.phase - previous.phase > 180.0 {
+                    point.phase -= 360.0;
+                }
+                while point.phase - previous.phase < -180.0 {
+                    point.phase += 360.0;
+                }
+            }
+            points.push(point);
+        }
+        Ok(points)
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::analysis::discrete_tf::LinearBlock;
+    use crate::plant::pt1::PT1;
+
+    #[test]
+    fn test_FrequencySweep_matches_discrete_tf() {
+        let plant = PT1::<f64>::default()
+            .set_sample_time_or_default(0.01)
+            .set_t1_time_or_default(0.5)
+            .set_kp(3.0);
+        let frequencies = [0.1, 0.5, 1.0, 5.0];
+        let bode = FrequencySweep::default()
+            .set_sample_time(0.01)
+            .set_settle_time(5.0)
+            .run(&plant, &frequencies)
+            .unwrap();
+        let tf = plant.discrete_tf();
+        for point in bode {
+            let (magnitude, phase) = tf.frequency_response(point.frequency, 0.01);
+            assert!((point.magnitude - magnitude).abs() < 1e-3 * magnitude);
+            assert!((point.phase - phase.to_degrees()).abs() < 0.1);
+        }
+    }
+
+    #[test]
+    fn test_FrequencySweep_time_unit() {
+        // the same element with its sample time in ms
+        let plant = PT1::<f64>::default()
+            .set_sample_time_or_default(10.0)
+            .set_t1_time_or_default(500.0);
+        let sweep = FrequencySweep::default()
+            .set_sample_time(10.0)
+            .set_settle_time(5000.0)
+            .set_time_unit("ms")
+            .unwrap();
+        let point = sweep.measure(&mut plant.clone(), 0.5).unwrap();
+        let (magnitude, _) = plant.discrete_tf().frequency_response(0.5, 0.01);
+        assert_eq!(point.frequency, 0.5);
+        assert!((point.magnitude - magnitude).abs() < 1e-3 * magnitude);
+        assert_eq!(
+            FrequencySweep::default().set_time_unit("fortnight"),
+            Err(TimeUnitError::Unknown("fortnight"))
+        );
+    }
+
+    #[test]
+    fn test_FrequencySweep_rejects_invalid_frequency() {
+        let plant = PT1::<f64>::default();
+        let sweep = FrequencySweep::default();
+        for frequency in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+            assert!(matches!(
+                sweep.measure(&mut plant.clone(), frequency),
+                Err(SweepError::Frequency(_))
+            ));
+        }
+        assert!(matches!(
+            sweep.run(&plant, &[0.1, 0.0]),
+            Err(SweepError::Frequency(0.0))
+        ));
+        assert_eq!(
+            sweep.set_sample_time(0.0).run(&plant, &[0.1]),
+            Err(SweepError::SampleTime(0.0))
+        );
+    }
+}
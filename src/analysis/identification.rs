@@ -0,0 +1,654 @@
+//! # Identification
+//!
+//! Estimates models from measured input / output data by least squares.
+//!
+//! * `arx`: linear ARX model
+//!   $ y[k] + a_{1} y[k-1] + \dots + a_{n_a} y[k-n_a] = b_{1} u[k-n_k] + \dots + b_{n_b} u[k-n_k-n_b+1] $
+//! * `hammerstein`: polynomial nonlinearity → ARX model. The products of
+//!   linear and polynomial coefficients are estimated linearly
+//!   (over-parameterization) and split again by a rank-1 factorization.
+//!   The polynomial is normalized to a linear coefficient of 1.
+//! * `wiener`: ARX model → polynomial nonlinearity, estimated in two steps:
+//!   the linear model first, then the polynomial mapping its output to the
+//!   measurement. Suited for mild nonlinearities.
+//!
+//! An `ArxModel` carries the covariance of its parameters and diagnostics of
+//! the residuals, to judge whether an estimate is trustworthy: the standard
+//! errors give confidence intervals, e.g. $ \pm 1.96 \sigma $ for 95 %,
+//! correlated residuals hint at a too low model order or colored noise.
+//!
+//! To detect overfitting, `split_data` separates estimation and validation
+//! data and `validation_fit` rates a model by simulating it on data it was
+//! not fitted to; `cross_validate_arx` does both for candidate orders.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use cb_simulation_util::analysis::identification::{ArxOrders, arx};
+//! use cb_simulation_util::plant::TransferTimeDomain;
+//! use cb_simulation_util::plant::pt1::PT1;
+//!
+//! fn main() {
+//!     let mut plant = PT1::<f64>::default().set_t1_time_or_default(4.0).set_kp(2.0);
+//!     let input: Vec<f64> = (0..100).map(|k| if (k / 10) % 2 == 0 { 1.0 } else { -1.0 }).collect();
+//!     let output: Vec<f64> = input.iter().map(|u| plant.transfer_td(*u)).collect();
+//!     let model = arx(&input, &output, ArxOrders { na: 1, nb: 1, nk: 0 }).unwrap();
+//!     assert!((model.a[1] + 0.75).abs() < 1e-9);
+//!     assert!((model.b[0] - 0.5).abs() < 1e-9);
+//!     // noise free data: the gain of 2 is known exactly
+//!     assert!((model.static_gain() - 2.0).abs() < 1e-9);
+//!     assert!(model.static_gain_std() < 1e-6);
+//! }
+//! ```
+
+use core::fmt::{self, Display};
+use ndarray::{Array1, Array2};
+use std::vec;
+use std::vec::Vec;
+
+use super::discrete_tf::DiscreteTF;
+use crate::linalg;
+use crate::plant::TransferTimeDomain;
+use crate::plant::block_oriented::{Hammerstein, Wiener};
+use crate::plant::discrete_transfer::DiscreteTransfer;
+use crate::plant::polynomial::Polynomial;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IdentificationError {
+    /// Input and output have different length
+    LengthMismatch,
+    /// Fewer samples than parameters to estimate
+    TooFewSamples,
+    /// The data does not excite all parameters, e.g. a constant input
+    Singular,
+    /// The estimation fraction of a split must be within 0..1
+    InvalidSplit,
+}
+
+impl Display for IdentificationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IdentificationError::LengthMismatch => write!(f, "Input and output differ in length"),
+            IdentificationError::TooFewSamples => write!(f, "Too few samples for the model order"),
+            IdentificationError::Singular => {
+                write!(f, "Data not informative enough, regression is singular")
+            }
+            IdentificationError::InvalidSplit => {
+                write!(f, "Estimation fraction must be within 0..1")
+            }
+        }
+    }
+}
+
+/// Orders of an ARX model
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ArxOrders {
+    /// Number of output coefficients $a_1 \dots a_{n_a}$
+    pub na: usize,
+    /// Number of input coefficients
+    pub nb: usize,
+    /// Input delay in samples
+    pub nk: usize,
+}
+
+impl ArxOrders {
+    fn parameters(&self) -> usize {
+        self.na + self.nb
+    }
+
+    /// First sample index with a complete regressor
+    fn start(&self) -> usize {
+        self.na.max(self.nk + self.nb.saturating_sub(1))
+    }
+
+    /// Regressor at sample `k`, for inputs transformed by `input`
+    fn regressor(&self, k: usize, output: &[f64], input: impl Fn(usize) -> Vec<f64>) -> Vec<f64> {
+        let mut phi: Vec<f64> = (1..=self.na).map(|i| -output[k - i]).collect();
+        for j in 0..self.nb {
+            phi.extend(input(k - self.nk - j));
+        }
+        phi
+    }
+}
+
+/// Normalized fit $ 100 (1 - \lVert y - \hat{y} \rVert / \lVert y - \bar{y} \rVert) $ in percent
+///
+/// 100 for a perfect model, 0 for a model as good as the mean of the
+/// measurement, negative for worse ones.
+pub fn fit_percent(measured: &[f64], predicted: &[f64]) -> f64 {
+    let n = measured.len().min(predicted.len());
+    if n == 0 {
+        return 0.0;
+    }
+    let mean = measured[..n].iter().sum::<f64>() / n as f64;
+    let spread = measured[..n]
+        .iter()
+        .map(|y| (y - mean).powi(2))
+        .sum::<f64>();
+    let error = measured[..n]
+        .iter()
+        .zip(predicted)
+        .map(|(y, p)| (y - p).powi(2))
+        .sum::<f64>();
+    if spread > 0.0 {
+        100.0 * (1.0 - (error / spread).sqrt())
+    } else {
+        0.0
+    }
+}
+
+/// Number of lags of the residual autocorrelation
+const RESIDUAL_LAGS: usize = 10;
+
+/// Diagnostics of the one step ahead prediction errors of a fit
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResidualDiagnostics {
+    pub mean: f64,
+    /// Variance, corrected by the degrees of freedom
+    pub variance: f64,
+    /// One step ahead prediction fit in percent, see `fit_percent`
+    pub fit_percent: f64,
+    /// Normalized autocorrelation for the lags 1, 2, ...
+    pub autocorrelation: Vec<f64>,
+    /// 95 % bound of the autocorrelation of white residuals, $ 1.96 / \sqrt{N} $
+    pub whiteness_bound: f64,
+}
+
+impl ResidualDiagnostics {
+    fn new(residual: &Array1<f64>, y: &Array1<f64>, parameters: usize) -> Self {
+        let n = residual.len();
+        let mean = residual.sum() / n as f64;
+        let energy = residual.dot(residual);
+        let dof = (n as f64 - parameters as f64).max(1.0);
+        let predicted = y - residual;
+        let fit_percent = fit_percent(
+            y.as_slice().unwrap_or_default(),
+            predicted.as_slice().unwrap_or_default(),
+        );
+        let centered = residual - mean;
+        let r0 = centered.dot(&centered);
+        let autocorrelation = (1..=RESIDUAL_LAGS.min(n.saturating_sub(1)))
+            .map(|lag| {
+                let r = centered
+                    .slice(ndarray::s![lag..])
+                    .dot(&centered.slice(ndarray::s![..n - lag]));
+                if r0 > 0.0 { r / r0 } else { 0.0 }
+            })
+            .collect();
+        ResidualDiagnostics {
+            mean,
+            variance: energy / dof,
+            fit_percent,
+            autocorrelation,
+            whiteness_bound: 1.96 / (n as f64).sqrt(),
+        }
+    }
+
+    /// Whether the autocorrelation stays within the whiteness bound at all lags
+    pub fn is_white(&self) -> bool {
+        self.autocorrelation
+            .iter()
+            .all(|r| r.abs() <= self.whiteness_bound)
+    }
+}
+
+/// Estimated ARX model, coefficients in powers of $z^{-1}$
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArxModel {
+    /// Denominator `[1, a_1, ..., a_na]`
+    pub a: Vec<f64>,
+    /// Numerator with `nk` leading zeros
+    pub b: Vec<f64>,
+    /// Variance of the one step ahead prediction error
+    pub residual_variance: f64,
+    /// Covariance of the parameters `[a_1, ..., a_na, b_nk, ...]`
+    pub covariance: Array2<f64>,
+    pub diagnostics: ResidualDiagnostics,
+}
+
+impl ArxModel {
+    /// Standard errors of the parameters `[a_1, ..., a_na, b_nk, ...]`
+    pub fn standard_errors(&self) -> Vec<f64> {
+        self.covariance
+            .diag()
+            .iter()
+            .map(|v| v.max(0.0).sqrt())
+            .collect()
+    }
+
+    /// Confidence intervals of the parameters for the coverage factor `z`,
+    /// e.g. 1.96 for 95 %
+    pub fn confidence_intervals(&self, z: f64) -> Vec<(f64, f64)> {
+        let nk = self.b.len() - (self.covariance.nrows() - (self.a.len() - 1));
+        self.a[1..]
+            .iter()
+            .chain(&self.b[nk..])
+            .zip(self.standard_errors())
+            .map(|(p, sigma)| (p - z * sigma, p + z * sigma))
+            .collect()
+    }
+
+    /// Steady state gain $ \sum b / \sum a $
+    pub fn static_gain(&self) -> f64 {
+        self.b.iter().sum::<f64>() / self.a.iter().sum::<f64>()
+    }
+
+    /// Standard error of `static_gain`, linearized around the estimate
+    pub fn static_gain_std(&self) -> f64 {
+        let (sum_a, sum_b) = (self.a.iter().sum::<f64>(), self.b.iter().sum::<f64>());
+        let na = self.a.len() - 1;
+        let gradient = Array1::from_shape_fn(self.covariance.nrows(), |i| {
+            if i < na {
+                -sum_b / (sum_a * sum_a)
+            } else {
+                1.0 / sum_a
+            }
+        });
+        gradient
+            .dot(&self.covariance.dot(&gradient))
+            .max(0.0)
+            .sqrt()
+    }
+
+    pub fn discrete_tf(&self) -> DiscreteTF {
+        DiscreteTF::new(self.b.clone(), self.a.clone())
+    }
+
+    pub fn element(&self) -> DiscreteTransfer<f64> {
+        DiscreteTransfer::<f64>::new(self.b.clone(), self.a.clone()).unwrap_or_default()
+    }
+}
+
+fn check(
+    input: &[f64],
+    output: &[f64],
+    orders: &ArxOrders,
+    parameters: usize,
+) -> Result<(), IdentificationError> {
+    if input.len() != output.len() {
+        return Err(IdentificationError::LengthMismatch);
+    }
+    if output.len() < orders.start() + parameters {
+        return Err(IdentificationError::TooFewSamples);
+    }
+    Ok(())
+}
+
+/// Least squares fit of `output[k]` on the regressors
+///
+/// Returns the parameters, their covariance and the residual diagnostics.
+fn fit(
+    orders: &ArxOrders,
+    input: &[f64],
+    output: &[f64],
+    features: impl Fn(f64) -> Vec<f64>,
+) -> Result<(Array1<f64>, Array2<f64>, ResidualDiagnostics), IdentificationError> {
+    let rows: Vec<Vec<f64>> = (orders.start()..output.len())
+        .map(|k| orders.regressor(k, output, |i| features(input[i])))
+        .collect();
+    let columns = rows
+        .first()
+        .map(Vec::len)
+        .filter(|columns| *columns > 0)
+        .ok_or(IdentificationError::TooFewSamples)?;
+    let phi = Array2::from_shape_vec((rows.len(), columns), rows.into_iter().flatten().collect())
+        .map_err(|_| IdentificationError::Singular)?;
+    let y = Array1::from_iter(output[orders.start()..].iter().copied());
+    let theta = linalg::least_squares(&phi, &y).ok_or(IdentificationError::Singular)?;
+    let residual = &y - &phi.dot(&theta);
+    let diagnostics = ResidualDiagnostics::new(&residual, &y, columns);
+    let information = linalg::inverse(&phi.t().dot(&phi)).ok_or(IdentificationError::Singular)?;
+    Ok((theta, information * diagnostics.variance, diagnostics))
+}
+
+fn model(
+    orders: &ArxOrders,
+    a: &[f64],
+    b: &[f64],
+    covariance: Array2<f64>,
+    diagnostics: ResidualDiagnostics,
+) -> ArxModel {
+    let mut den = vec![1.0];
+    den.extend_from_slice(a);
+    let mut num = vec![0.0; orders.nk];
+    num.extend_from_slice(b);
+    ArxModel {
+        a: den,
+        b: num,
+        residual_variance: diagnostics.variance,
+        covariance,
+        diagnostics,
+    }
+}
+
+/// Linear ARX model of the given orders
+pub fn arx(
+    input: &[f64],
+    output: &[f64],
+    orders: ArxOrders,
+) -> Result<ArxModel, IdentificationError> {
+    check(input, output, &orders, orders.parameters())?;
+    let (theta, covariance, diagnostics) = fit(&orders, input, output, |u| vec![u])?;
+    let theta = theta.to_vec();
+    Ok(model(
+        &orders,
+        &theta[..orders.na],
+        &theta[orders.na..],
+        covariance,
+        diagnostics,
+    ))
+}
+
+/// Hammerstein model with a polynomial nonlinearity of `degree` (without constant term)
+pub fn hammerstein(
+    input: &[f64],
+    output: &[f64],
+    orders: ArxOrders,
+    degree: usize,
+) -> Result<Hammerstein<Polynomial, DiscreteTransfer<f64>>, IdentificationError> {
+    let degree = degree.max(1);
+    check(input, output, &orders, orders.na + orders.nb * degree)?;
+    let (theta, _, diagnostics) = fit(&orders, input, output, |u| {
+        (1..=degree as i32).map(|p| u.powi(p)).collect()
+    })?;
+    // theta[na + j * degree + p] = b_j * g_p, split by the leading singular vectors
+    let products = Array2::from_shape_fn((orders.nb, degree), |(j, p)| {
+        theta[orders.na + j * degree + p]
+    });
+    let mut g = Array1::from_elem(degree, 1.0);
+    for _ in 0..100 {
+        let mut b = products.dot(&g);
+        let norm = b.dot(&b).sqrt();
+        if norm == 0.0 {
+            return Err(IdentificationError::Singular);
+        }
+        b /= norm;
+        g = products.t().dot(&b);
+    }
+    let b = products.dot(&g) / g.dot(&g);
+    // normalize the linear coefficient of the polynomial to 1
+    let scale = if g[0].abs() > 1e-12 { g[0] } else { 1.0 };
+    let b = b * scale;
+    let mut coefficients = vec![0.0];
+    coefficients.extend(g.iter().map(|c| c / scale));
+    // the covariance of the split coefficients is not known
+    let covariance = Array2::zeros((orders.parameters(), orders.parameters()));
+    let linear = model(
+        &orders,
+        &theta.to_vec()[..orders.na],
+        &b.to_vec(),
+        covariance,
+        diagnostics,
+    );
+    Ok(Hammerstein::new(
+        Polynomial::new(coefficients),
+        linear.element(),
+    ))
+}
+
+/// Wiener model with a polynomial nonlinearity of `degree` (with constant term)
+pub fn wiener(
+    input: &[f64],
+    output: &[f64],
+    orders: ArxOrders,
+    degree: usize,
+) -> Result<Wiener<DiscreteTransfer<f64>, Polynomial>, IdentificationError> {
+    let linear = arx(input, output, orders)?;
+    let tf = linear.discrete_tf();
+    let intermediate = tf.simulate(input);
+    let phi = Array2::from_shape_fn((input.len(), degree + 1), |(k, p)| {
+        intermediate[k].powi(p as i32)
+    });
+    let coefficients = linalg::least_squares(&phi, &Array1::from_iter(output.iter().copied()))
+        .ok_or(IdentificationError::Singular)?;
+    Ok(Wiener::new(
+        linear.element(),
+        Polynomial::new(coefficients.to_vec()),
+    ))
+}
+
+/// A segment of input / output data
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DataSegment<'a> {
+    pub input: &'a [f64],
+    pub output: &'a [f64],
+}
+
+/// Split the data into a leading estimation and a trailing validation segment
+///
+/// `estimation_fraction` is the share of the samples used for estimation.
+pub fn split_data<'a>(
+    input: &'a [f64],
+    output: &'a [f64],
+    estimation_fraction: f64,
+) -> Result<(DataSegment<'a>, DataSegment<'a>), IdentificationError> {
+    if input.len() != output.len() {
+        return Err(IdentificationError::LengthMismatch);
+    }
+    if !(estimation_fraction > 0.0 && estimation_fraction < 1.0) {
+        return Err(IdentificationError::InvalidSplit);
+    }
+    let at = (input.len() as f64 * estimation_fraction).round() as usize;
+    if at == 0 || at == input.len() {
+        return Err(IdentificationError::TooFewSamples);
+    }
+    Ok((
+        DataSegment {
+            input: &input[..at],
+            output: &output[..at],
+        },
+        DataSegment {
+            input: &input[at..],
+            output: &output[at..],
+        },
+    ))
+}
+
+/// Fit of the simulated `model` output to the measured `segment`, see `fit_percent`
+///
+/// The model is simulated from its current state, e.g. the zero state, so
+/// the initial transient lowers the fit of slow models on short segments.
+pub fn validation_fit<E: TransferTimeDomain<f64> + ?Sized>(
+    model: &mut E,
+    segment: &DataSegment,
+) -> f64 {
+    let simulated: Vec<f64> = segment
+        .input
+        .iter()
+        .map(|u| model.transfer_td(*u))
+        .collect();
+    fit_percent(segment.output, &simulated)
+}
+
+/// Estimate ARX models of the candidate `orders` and rate them on validation data
+///
+/// Returns the validation fit of every candidate in the order given. A
+/// higher order with a lower validation fit than a lower one is overfitted.
+pub fn cross_validate_arx(
+    input: &[f64],
+    output: &[f64],
+    orders: &[ArxOrders],
+    estimation_fraction: f64,
+) -> Result<Vec<(ArxOrders, f64)>, IdentificationError> {
+    let (estimation, validation) = split_data(input, output, estimation_fraction)?;
+    orders
+        .iter()
+        .map(|candidate| {
+            let model = arx(estimation.input, estimation.output, *candidate)?;
+            let simulated = model.discrete_tf().simulate(validation.input);
+            Ok((*candidate, fit_percent(validation.output, &simulated)))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn excitation(samples: usize) -> Vec<f64> {
+        (0..samples)
+            .map(|k| 2.0 * crate::rng::unit(crate::rng::mix(k as u64)) - 1.0)
+            .collect()
+    }
+
+    #[test]
+    fn test_hammerstein_recovers_polynomial() {
+        let input = excitation(300);
+        let mut plant = Hammerstein::new(
+            Polynomial::new(vec![0.0, 1.0, 0.5]),
+            DiscreteTransfer::<f64>::new(vec![0.0, 0.3], vec![1.0, -0.7]).unwrap(),
+        );
+        let output: Vec<f64> = input.iter().map(|u| plant.transfer_td(*u)).collect();
+        let mut sut = hammerstein(
+            &input,
+            &output,
+            ArxOrders {
+                na: 1,
+                nb: 1,
+                nk: 1,
+            },
+            2,
+        )
+        .unwrap();
+        assert!((sut.nonlinearity.coefficients[2] - 0.5).abs() < 1e-6);
+        assert!((sut.linear.numerator()[1] - 0.3).abs() < 1e-6);
+        for (u, y) in input.iter().zip(output.iter()) {
+            assert!((sut.transfer_td(*u) - y).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_wiener_two_step() {
+        let input = excitation(400);
+        let mut plant = Wiener::new(
+            DiscreteTransfer::<f64>::new(vec![0.0, 0.2], vec![1.0, -0.8]).unwrap(),
+            Polynomial::new(vec![0.1, 1.0, 0.1]),
+        );
+        let output: Vec<f64> = input.iter().map(|u| plant.transfer_td(*u)).collect();
+        let mut sut = wiener(
+            &input,
+            &output,
+            ArxOrders {
+                na: 1,
+                nb: 1,
+                nk: 1,
+            },
+            2,
+        )
+        .unwrap();
+        let error: f64 = input
+            .iter()
+            .zip(output.iter())
+            .map(|(u, y)| (sut.transfer_td(*u) - y).powi(2))
+            .sum::<f64>()
+            / input.len() as f64;
+        assert!(error < 1e-3, "{}", error);
+    }
+
+    #[test]
+    fn test_arx_confidence_and_residuals() {
+        let input = excitation(2000);
+        let noise = |k: usize| 0.1 * (crate::rng::unit(crate::rng::mix(k as u64 + 7919)) - 0.5);
+        let orders = ArxOrders {
+            na: 1,
+            nb: 1,
+            nk: 1,
+        };
+        // white equation error: the intervals cover the true parameters
+        let mut output = vec![0.0; input.len()];
+        for k in 1..input.len() {
+            output[k] = 0.75 * output[k - 1] + 0.5 * input[k - 1] + noise(k);
+        }
+        let sut = arx(&input, &output, orders).unwrap();
+        let intervals = sut.confidence_intervals(3.0);
+        assert!(intervals[0].0 < -0.75 && -0.75 < intervals[0].1);
+        assert!(intervals[1].0 < 0.5 && 0.5 < intervals[1].1);
+        let sigma = sut.standard_errors();
+        assert!(sigma[0] > 0.0 && sigma[0] < 0.01, "{:?}", sigma);
+        assert!((sut.static_gain() - 2.0).abs() < 3.0 * sut.static_gain_std());
+        assert!(sut.diagnostics.fit_percent > 90.0);
+        assert!(sut.diagnostics.autocorrelation[0].abs() < sut.diagnostics.whiteness_bound);
+        // noise on the measurement passes the model dynamics: colored residuals
+        let mut plant = DiscreteTransfer::<f64>::new(vec![0.0, 0.5], vec![1.0, -0.75]).unwrap();
+        let measured: Vec<f64> = input
+            .iter()
+            .enumerate()
+            .map(|(k, u)| plant.transfer_td(*u) + noise(k))
+            .collect();
+        let sut = arx(&input, &measured, orders).unwrap();
+        assert!(!sut.diagnostics.is_white());
+        assert_eq!(sut.diagnostics.autocorrelation.len(), RESIDUAL_LAGS);
+    }
+
+    #[test]
+    fn test_cross_validate_arx() {
+        let input = excitation(600);
+        let noise = |k: usize| 0.2 * (crate::rng::unit(crate::rng::mix(k as u64 + 31)) - 0.5);
+        let mut output = vec![0.0; input.len()];
+        for k in 2..input.len() {
+            output[k] = 1.2 * output[k - 1] - 0.5 * output[k - 2] + 0.4 * input[k - 1] + noise(k);
+        }
+        let candidates: Vec<ArxOrders> = (1..=3)
+            .map(|n| ArxOrders {
+                na: n,
+                nb: n,
+                nk: 1,
+            })
+            .collect();
+        let fits = cross_validate_arx(&input, &output, &candidates, 0.7).unwrap();
+        assert_eq!(fits.len(), 3);
+        // the true order fits clearly better than the too low one
+        assert!(fits[1].1 > fits[0].1 + 5.0, "{:?}", fits);
+        // the noise filtered by the plant is not simulated, a higher order does not help
+        assert!(fits[1].1 > 60.0, "{:?}", fits);
+        assert!(fits[2].1 < fits[1].1 + 1.0, "{:?}", fits);
+        let (estimation, validation) = split_data(&input, &output, 0.7).unwrap();
+        assert_eq!(
+            (estimation.input.len(), validation.output.len()),
+            (420, 180)
+        );
+        let mut model = arx(estimation.input, estimation.output, candidates[1])
+            .unwrap()
+            .element();
+        assert!((validation_fit(&mut model, &validation) - fits[1].1).abs() < 1e-9);
+        assert_eq!(
+            split_data(&input, &output, 1.0),
+            Err(IdentificationError::InvalidSplit)
+        );
+        assert_eq!(fit_percent(&[1.0, 2.0], &[1.0, 2.0]), 100.0);
+        assert_eq!(fit_percent(&[1.0, 3.0], &[2.0, 2.0]), 0.0);
+    }
+
+    #[test]
+    fn test_arx_errors() {
+        let orders = ArxOrders {
+            na: 2,
+            nb: 2,
+            nk: 1,
+        };
+        assert_eq!(
+            arx(&[1.0; 3], &[1.0; 4], orders),
+            Err(IdentificationError::LengthMismatch)
+        );
+        assert_eq!(
+            arx(&[1.0; 4], &[1.0; 4], orders),
+            Err(IdentificationError::TooFewSamples)
+        );
+        assert_eq!(
+            arx(&[1.0; 50], &[1.0; 50], orders),
+            Err(IdentificationError::Singular)
+        );
+        // an empty regression
+        let none = ArxOrders {
+            na: 0,
+            nb: 0,
+            nk: 0,
+        };
+        assert_eq!(arx(&[], &[], none), Err(IdentificationError::TooFewSamples));
+        assert_eq!(
+            arx(&[1.0; 5], &[1.0; 5], none),
+            Err(IdentificationError::TooFewSamples)
+        );
+    }
+}
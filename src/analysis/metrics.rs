@@ -0,0 +1,88 @@
+//! # Performance metrics
+//!
+//! Integral error criteria of a control error trace.
+//! All integrals use the rectangle rule with the sample interval taken from the time axis.
+//! They are in its time unit, convert it with `SimResult::time_in`, as
+//! `requirements::Metric` does to evaluate them in seconds.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use ndarray::array;
+//! use cb_simulation_util::analysis::TimeWindow;
+//! use cb_simulation_util::analysis::metrics::ise;
+//!
+//! fn main() {
+//!     let time = array![0.0, 1.0, 2.0, 3.0];
+//!     let error = array![10.0, 1.0, 1.0, 1.0];
+//!     assert_eq!(ise(&time, &error, TimeWindow::All), 103.0);
+//!     assert_eq!(ise(&time, &error, TimeWindow::From(1.0)), 3.0);
+//! }
+//! ```
+
+use super::TimeWindow;
+use ndarray::Array1;
+
+fn integrate(
+    time: &Array1<f64>,
+    values: &Array1<f64>,
+    window: TimeWindow,
+    f: impl Fn(f64, f64) -> f64,
+) -> f64 {
+    let range = window.indices(time, values);
+    let mut sum = 0.0;
+    for i in range {
+        let dt = if i + 1 < time.len() {
+            time[i + 1] - time[i]
+        } else if i > 0 {
+            time[i] - time[i - 1]
+        } else {
+            1.0
+        };
+        sum += f(time[i], values[i]) * dt;
+    }
+    sum
+}
+
+/// Integral of squared error
+pub fn ise(time: &Array1<f64>, error: &Array1<f64>, window: TimeWindow) -> f64 {
+    integrate(time, error, window, |_, e| e * e)
+}
+
+/// Integral of absolute error
+pub fn iae(time: &Array1<f64>, error: &Array1<f64>, window: TimeWindow) -> f64 {
+    integrate(time, error, window, |_, e| e.abs())
+}
+
+/// Integral of time weighted absolute error
+pub fn itae(time: &Array1<f64>, error: &Array1<f64>, window: TimeWindow) -> f64 {
+    integrate(time, error, window, |t, e| t * e.abs())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use ndarray::array;
+    use std::vec;
+
+    #[test]
+    fn test_iae_itae() {
+        let time = array![0.0, 2.0, 4.0];
+        let error = array![-1.0, 1.0, -2.0];
+        assert_eq!(iae(&time, &error, TimeWindow::All), 8.0);
+        assert_eq!(itae(&time, &error, TimeWindow::All), 20.0);
+        assert_eq!(iae(&time, &error, TimeWindow::Between(1.0, 3.0)), 2.0);
+    }
+
+    #[test]
+    fn test_ise_steady_state() {
+        let time = array![0.0, 1.0, 2.0, 3.0, 4.0];
+        let error = array![4.0, 2.0, 0.0, 0.0, 0.0];
+        assert_eq!(ise(&time, &error, TimeWindow::All), 20.0);
+        assert_eq!(
+            ise(&time, &error, TimeWindow::SteadyState { tolerance: 0.01 }),
+            0.0
+        );
+    }
+}
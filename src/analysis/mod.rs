@@ -0,0 +1,114 @@
+//! # Analysis
+//!
+//! Evaluation of simulation results: canonical responses, performance metrics
+//! and spectra, and transfer functions of linear elements.
+
+pub mod characteristic;
+pub mod comparison;
+pub mod discrete_tf;
+pub mod fixed_float;
+pub mod frequency_sweep;
+pub mod identification;
+pub mod metrics;
+pub mod notch_placement;
+pub(crate) mod poly;
+pub mod requirements;
+pub mod response;
+pub mod rga;
+pub mod rls;
+pub mod spectrum;
+pub mod word_length;
+
+pub use response::*;
+
+use core::ops::Range;
+use ndarray::Array1;
+
+/// Part of a trace taken into account by metrics and spectra
+///
+/// Used to exclude initial transients without slicing arrays manually.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum TimeWindow {
+    /// The whole trace
+    #[default]
+    All,
+    /// All samples with `time >= start`
+    From(f64),
+    /// All samples with `start <= time <= end`
+    Between(f64, f64),
+    /// All samples after the detected steady-state onset
+    ///
+    /// `tolerance` is relative to the span (max - min) of the trace,
+    /// see [`steady_state_onset`].
+    SteadyState { tolerance: f64 },
+}
+
+impl TimeWindow {
+    /// Index range of the samples within the window
+    pub fn indices(&self, time: &Array1<f64>, values: &Array1<f64>) -> Range<usize> {
+        let len = time.len().min(values.len());
+        match *self {
+            TimeWindow::All => 0..len,
+            TimeWindow::From(start) => first_index_at(time, start)..len,
+            TimeWindow::Between(start, end) => {
+                let first = first_index_at(time, start);
+                let last = time.iter().take(len).rposition(|t| *t <= end);
+                match last {
+                    Some(last) if last >= first => first..last + 1,
+                    _ => first..first,
+                }
+            }
+            TimeWindow::SteadyState { tolerance } => steady_state_onset(values, tolerance)..len,
+        }
+    }
+}
+
+fn first_index_at(time: &Array1<f64>, start: f64) -> usize {
+    time.iter().position(|t| *t >= start).unwrap_or(time.len())
+}
+
+/// Index from which on the trace stays within a band around its final value
+///
+/// The band half width is `tolerance * (max - min)` of the whole trace,
+/// e.g. `0.02` for the common 2% settling band.
+pub fn steady_state_onset(values: &Array1<f64>, tolerance: f64) -> usize {
+    let Some(last) = values.last() else {
+        return 0;
+    };
+    let max = values.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+    let min = values.iter().cloned().fold(f64::INFINITY, f64::min);
+    let band = tolerance * (max - min);
+    match values.iter().rposition(|v| (v - last).abs() > band) {
+        Some(i) => i + 1,
+        None => 0,
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use ndarray::array;
+    use std::vec;
+
+    #[test]
+    fn test_TimeWindow_indices() {
+        let time = array![0.0, 1.0, 2.0, 3.0, 4.0];
+        let values = array![0.0, 5.0, 1.1, 0.9, 1.0];
+        assert_eq!(TimeWindow::All.indices(&time, &values), 0..5);
+        assert_eq!(TimeWindow::From(1.5).indices(&time, &values), 2..5);
+        assert_eq!(TimeWindow::Between(1.0, 3.0).indices(&time, &values), 1..4);
+        assert_eq!(TimeWindow::Between(3.5, 1.0).indices(&time, &values), 4..4);
+        assert_eq!(
+            TimeWindow::SteadyState { tolerance: 0.05 }.indices(&time, &values),
+            2..5
+        );
+    }
+
+    #[test]
+    fn test_steady_state_onset_constant() {
+        assert_eq!(steady_state_onset(&array![1.0, 1.0, 1.0], 0.02), 0);
+        assert_eq!(steady_state_onset(&Array1::zeros(0), 0.02), 0);
+    }
+}
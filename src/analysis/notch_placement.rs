@@ -0,0 +1,144 @@
+//! # Notch placement
+//!
+//! Detects the dominant resonance peak in a spectrum, see `spectrum::psd`
+//! and `spectrum::psd_hz` for a time axis not in seconds, and places a
+//! `Notch` filter at its frequency with the given depth and width, e.g. to
+//! suppress a mechanical resonance seen in a simulated or measured trace.
+//!
+//! The peak is searched within a frequency band, so low frequency content
+//! like the reference tracking of the loop is not taken for the resonance.
+//! Its frequency is refined between the DFT bins by a parabola through the
+//! largest bin and its two neighbours.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use ndarray::Array1;
+//! use cb_simulation_util::analysis::TimeWindow;
+//! use cb_simulation_util::analysis::notch_placement::place_notch;
+//! use cb_simulation_util::analysis::spectrum::psd;
+//! use cb_simulation_util::plant::TransferTimeDomain;
+//! use cb_simulation_util::plant::notch::Notch;
+//!
+//! fn main() {
+//!     // 1 Hz motion with a superimposed vibration at 12 Hz, sampled at 1 ms
+//!     let ts = 0.001;
+//!     let time: Array1<f64> = (0..1000).map(|k| k as f64 * ts).collect();
+//!     let vibration = |t: f64| 0.2 * (2.0 * core::f64::consts::PI * 12.0 * t).sin();
+//!     let motion = |t: f64| (2.0 * core::f64::consts::PI * t).sin();
+//!     let values = time.mapv(|t| motion(t) + vibration(t));
+//!     let spectrum = psd(&time, &values, TimeWindow::All);
+//!     let mut notch: Notch<f64> = place_notch(&spectrum, 5.0..=100.0, ts, 0.0, 0.3).unwrap();
+//!     assert!((notch.omega / (2.0 * core::f64::consts::PI) - 12.0).abs() < 0.1);
+//!     let out: Vec<f64> = (0..4000)
+//!         .map(|k| notch.transfer_td(vibration(k as f64 * ts)))
+//!         .collect();
+//!     let residual = out[3000..].iter().fold(0.0_f64, |m, y| m.max(y.abs()));
+//!     assert!(residual < 0.01);
+//! }
+//! ```
+
+use core::f64::consts::PI;
+use core::ops::RangeInclusive;
+
+use super::spectrum::Spectrum;
+use crate::plant::notch::Notch;
+
+/// Frequency of the largest power within `band`, both in Hz
+///
+/// Returns `None` if no bin of the spectrum lies within the band.
+pub fn resonance_peak(spectrum: &Spectrum, band: RangeInclusive<f64>) -> Option<f64> {
+    let power = &spectrum.power;
+    let (peak, _) = spectrum
+        .frequency
+        .iter()
+        .enumerate()
+        .filter(|(_, f)| band.contains(f))
+        .max_by(|a, b| power[a.0].total_cmp(&power[b.0]))?;
+    let frequency = spectrum.frequency[peak];
+    if peak == 0 || peak + 1 >= power.len() {
+        return Some(frequency);
+    }
+    // vertex of the parabola through the peak bin and its neighbours
+    let (left, center, right) = (power[peak - 1], power[peak], power[peak + 1]);
+    let curvature = left - 2.0 * center + right;
+    if curvature >= 0.0 {
+        return Some(frequency);
+    }
+    let offset = (0.5 * (left - right) / curvature).clamp(-0.5, 0.5);
+    let bin_width = spectrum.frequency[peak + 1] - frequency;
+    Some(frequency + offset * bin_width)
+}
+
+/// Notch at the resonance peak within `band`, see [`resonance_peak`]
+///
+/// `depth` and `width` are those of `Notch`. Fails if there is no peak in the
+/// band or the peak is not below the Nyquist frequency of `sample_time`.
+pub fn place_notch<N>(
+    spectrum: &Spectrum,
+    band: RangeInclusive<f64>,
+    sample_time: f64,
+    depth: f64,
+    width: f64,
+) -> Result<Notch<N>, &'static str>
+where
+    Notch<N>: Default,
+{
+    if sample_time <= 0.0 {
+        return Err("Invalid sample_time: Must be > 0.0");
+    }
+    if !(0.0..=1.0).contains(&depth) || width <= 0.0 {
+        return Err("Invalid notch: depth must be within 0..=1 and width > 0.0");
+    }
+    let frequency = resonance_peak(spectrum, band).ok_or("No spectrum bin within the band")?;
+    let omega = 2.0 * PI * frequency;
+    if frequency <= 0.0 || omega * sample_time >= PI {
+        return Err("Invalid peak: Must be > 0.0 and below the Nyquist frequency");
+    }
+    Ok(Notch::<N>::default()
+        .set_sample_time_or_default(sample_time)
+        .set_omega_or_default(omega)
+        .set_depth_or_default(depth)
+        .set_width_or_default(width))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::analysis::TimeWindow;
+    use crate::analysis::spectrum::psd;
+    use ndarray::Array1;
+
+    fn spectrum_of(frequencies: &[(f64, f64)], n: usize, dt: f64) -> Spectrum {
+        let time: Array1<f64> = (0..n).map(|k| k as f64 * dt).collect();
+        let values = time.mapv(|t| {
+            frequencies
+                .iter()
+                .map(|(f, a)| a * (2.0 * PI * f * t).sin())
+                .sum()
+        });
+        psd(&time, &values, TimeWindow::All)
+    }
+
+    #[test]
+    fn test_resonance_peak_band_and_interpolation() {
+        // a strong slow component outside the band, the resonance between two bins
+        let spectrum = spectrum_of(&[(0.5, 10.0), (7.25, 1.0)], 200, 0.01);
+        assert_eq!(spectrum.peak_frequency(), Some(0.5));
+        let peak = resonance_peak(&spectrum, 2.0..=50.0).unwrap();
+        assert!((peak - 7.25).abs() < 0.2);
+        assert!((peak - 7.25).abs() < (spectrum.frequency[15] - 7.25).abs());
+        assert_eq!(resonance_peak(&spectrum, 60.0..=70.0), None);
+    }
+
+    #[test]
+    fn test_place_notch_errors() {
+        let spectrum = spectrum_of(&[(40.0, 1.0)], 100, 0.01);
+        assert!(place_notch::<f64>(&spectrum, 1.0..=50.0, 0.01, 0.0, 0.5).is_ok());
+        // resonance above the Nyquist frequency of the controller
+        assert!(place_notch::<f64>(&spectrum, 1.0..=50.0, 0.02, 0.0, 0.5).is_err());
+        assert!(place_notch::<i32>(&spectrum, 1.0..=50.0, 0.01, 2.0, 0.5).is_err());
+        assert!(place_notch::<i32>(&spectrum, 1.0..=50.0, 0.01, 0.1, 0.0).is_err());
+    }
+}
@@ -0,0 +1,40 @@
+//! Polynomial helpers, coefficients in ascending powers
+
+use std::vec;
+use std::vec::Vec;
+
+pub(crate) fn mul(a: &[f64], b: &[f64]) -> Vec<f64> {
+    if a.is_empty() || b.is_empty() {
+        return Vec::new();
+    }
+    let mut c = vec![0.0; a.len() + b.len() - 1];
+    for (i, x) in a.iter().enumerate() {
+        for (j, y) in b.iter().enumerate() {
+            c[i + j] += x * y;
+        }
+    }
+    c
+}
+
+pub(crate) fn add(a: &[f64], b: &[f64]) -> Vec<f64> {
+    let mut c = vec![0.0; a.len().max(b.len())];
+    for (i, x) in a.iter().enumerate() {
+        c[i] += x;
+    }
+    for (i, y) in b.iter().enumerate() {
+        c[i] += y;
+    }
+    c
+}
+
+pub(crate) fn scale(a: &[f64], k: f64) -> Vec<f64> {
+    a.iter().map(|x| x * k).collect()
+}
+
+/// Remove trailing (highest power) zero coefficients, keeps at least one coefficient
+pub(crate) fn trim(mut a: Vec<f64>) -> Vec<f64> {
+    while a.len() > 1 && a[a.len() - 1] == 0.0 {
+        a.pop();
+    }
+    a
+}
@@ -0,0 +1,308 @@
+//! # Requirements evaluation
+//!
+//! Evaluates named requirements - a metric of a trace compared against a
+//! threshold - against one or many `SimResult`s. The report is available as
+//! machine-readable JSON and as human-readable text, for automated design
+//! verification campaigns.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use cb_simulation_util::analysis::requirements::{evaluate, Bound, Metric, Requirement};
+//! use cb_simulation_util::analysis::step_response;
+//! use cb_simulation_util::plant::pt2::PT2;
+//! use cb_simulation_util::signal::TimeRange;
+//!
+//! fn main() {
+//!     let mut plant = PT2::<f64>::default().set_sample_time_or_default(0.1).set_damping_or_default(0.5);
+//!     let result = step_response(&mut plant, TimeRange::default().set_sampling_interval(0.1)).unwrap();
+//!     let requirements = [
+//!         Requirement::new("overshoot below 10%", "output", Metric::Overshoot, Bound::AtMost(10.0)),
+//!         Requirement::new("final value", "output", Metric::FinalValue, Bound::AtLeast(0.99)),
+//!     ];
+//!     let report = evaluate(&requirements, &[("nominal", &result)]).unwrap();
+//!     assert!(!report.passed());
+//!     assert!(report.to_json().contains("\"requirement\":\"overshoot below 10%\""));
+//! }
+//! ```
+
+use core::fmt::{self, Display};
+use std::string::String;
+use std::vec::Vec;
+
+use super::TimeWindow;
+use super::metrics;
+use crate::json;
+use crate::signal::TimeUnitError;
+use crate::sim::SimResult;
+
+/// A scalar figure of merit of a trace
+///
+/// Times, integrals and their windows are in seconds, whatever the time
+/// unit of the result.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Metric {
+    Max,
+    Min,
+    FinalValue,
+    /// Overshoot beyond the final value in percent of the total change
+    Overshoot,
+    /// Time from the start until the trace stays within `tolerance` (relative to its span)
+    SettlingTime {
+        tolerance: f64,
+    },
+    /// Integral of squared values, the trace is taken as error signal
+    Ise(TimeWindow),
+    /// Integral of absolute values, the trace is taken as error signal
+    Iae(TimeWindow),
+}
+
+impl Metric {
+    /// Value of the metric for a trace, `NaN` for empty traces
+    ///
+    /// Fails if a time based metric is evaluated on a result of an unknown
+    /// time unit.
+    pub fn evaluate(&self, result: &SimResult, trace: &str) -> Result<f64, TimeUnitError> {
+        let Some(trace) = result.trace(trace) else {
+            return Ok(f64::NAN);
+        };
+        let values = &trace.values;
+        let (Some(first), Some(last)) = (values.first(), values.last()) else {
+            return Ok(f64::NAN);
+        };
+        Ok(match *self {
+            Metric::Max => values.iter().cloned().fold(f64::NEG_INFINITY, f64::max),
+            Metric::Min => values.iter().cloned().fold(f64::INFINITY, f64::min),
+            Metric::FinalValue => *last,
+            Metric::Overshoot => {
+                let change = last - first;
+                if change == 0.0 {
+                    return Ok(0.0);
+                }
+                let peak = values
+                    .iter()
+                    .map(|v| (v - last) * change.signum())
+                    .fold(0.0, f64::max);
+                100.0 * peak / change.abs()
+            }
+            Metric::SettlingTime { tolerance } => {
+                let time = result.time_in("s")?;
+                let onset = super::steady_state_onset(values, tolerance);
+                time.get(onset).map_or(f64::NAN, |t| t - time[0])
+            }
+            Metric::Ise(window) => metrics::ise(&result.time_in("s")?, values, window),
+            Metric::Iae(window) => metrics::iae(&result.time_in("s")?, values, window),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Bound {
+    AtMost(f64),
+    AtLeast(f64),
+}
+
+impl Bound {
+    pub fn holds(&self, value: f64) -> bool {
+        match *self {
+            Bound::AtMost(limit) => value <= limit,
+            Bound::AtLeast(limit) => value >= limit,
+        }
+    }
+}
+
+impl Display for Bound {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Bound::AtMost(limit) => write!(f, "<= {}", limit),
+            Bound::AtLeast(limit) => write!(f, ">= {}", limit),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Requirement {
+    pub name: String,
+    pub trace: String,
+    pub metric: Metric,
+    pub bound: Bound,
+}
+
+impl Requirement {
+    pub fn new(name: &str, trace: &str, metric: Metric, bound: Bound) -> Self {
+        Requirement {
+            name: String::from(name),
+            trace: String::from(trace),
+            metric,
+            bound,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RequirementResult {
+    pub requirement: String,
+    pub run: String,
+    pub value: f64,
+    pub bound: Bound,
+    pub passed: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct RequirementsReport {
+    pub results: Vec<RequirementResult>,
+}
+
+impl RequirementsReport {
+    pub fn passed(&self) -> bool {
+        self.results.iter().all(|r| r.passed)
+    }
+
+    pub fn to_json(&self) -> String {
+        let mut out = String::from("{\"passed\":");
+        out.push_str(if self.passed() { "true" } else { "false" });
+        out.push_str(",\"results\":[");
+        for (i, r) in self.results.iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            let (kind, limit) = match r.bound {
+                Bound::AtMost(limit) => ("at_most", limit),
+                Bound::AtLeast(limit) => ("at_least", limit),
+            };
+            out.push_str(&std::format!(
+                "{{\"requirement\":{},\"run\":{},\"value\":{},\"{}\":{},\"passed\":{}}}",
+                json::string(&r.requirement),
+                json::string(&r.run),
+                json::number(r.value),
+                kind,
+                json::number(limit),
+                r.passed
+            ));
+        }
+        out.push_str("]}");
+        out
+    }
+}
+
+impl Display for RequirementsReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for r in &self.results {
+            writeln!(
+                f,
+                "[{}] {} ({}): {} {}",
+                if r.passed { "PASS" } else { "FAIL" },
+                r.requirement,
+                r.run,
+                r.value,
+                r.bound
+            )?;
+        }
+        let failed = self.results.iter().filter(|r| !r.passed).count();
+        write!(f, "{} of {} checks failed", failed, self.results.len())
+    }
+}
+
+/// Evaluate every requirement against every named run
+///
+/// Fails if a run of an unknown time unit is checked for a time based metric.
+pub fn evaluate(
+    requirements: &[Requirement],
+    runs: &[(&str, &SimResult)],
+) -> Result<RequirementsReport, TimeUnitError> {
+    let mut results = Vec::new();
+    for (run, result) in runs {
+        for requirement in requirements {
+            let value = requirement.metric.evaluate(result, &requirement.trace)?;
+            results.push(RequirementResult {
+                requirement: requirement.name.clone(),
+                run: String::from(*run),
+                value,
+                bound: requirement.bound,
+                passed: requirement.bound.holds(value),
+            });
+        }
+    }
+    Ok(RequirementsReport { results })
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::sim::{Trace, TraceMetadata};
+    use ndarray::array;
+    use std::vec;
+
+    fn result(values: ndarray::Array1<f64>) -> SimResult {
+        SimResult {
+            time: array![0.0, 1.0, 2.0, 3.0, 4.0],
+            time_unit: "s",
+            traces: vec![Trace {
+                name: String::from("y"),
+                meta: TraceMetadata {
+                    unit: "1",
+                    source: "Test",
+                    sample_interval: 1.0,
+                },
+                values,
+            }],
+        }
+    }
+
+    #[test]
+    fn test_Metric_evaluate() {
+        let r = result(array![0.0, 1.5, 0.8, 1.0, 1.0]);
+        assert_eq!(Metric::Overshoot.evaluate(&r, "y"), Ok(50.0));
+        assert_eq!(Metric::Max.evaluate(&r, "y"), Ok(1.5));
+        assert_eq!(
+            Metric::SettlingTime { tolerance: 0.1 }.evaluate(&r, "y"),
+            Ok(3.0)
+        );
+        assert!(Metric::FinalValue.evaluate(&r, "missing").unwrap().is_nan());
+    }
+
+    #[test]
+    fn test_Metric_evaluate_in_seconds() {
+        let mut r = result(array![0.0, 1.5, 0.8, 1.0, 1.0]);
+        r.time_unit = "ms";
+        assert_eq!(
+            Metric::SettlingTime { tolerance: 0.1 }.evaluate(&r, "y"),
+            Ok(0.003)
+        );
+        let ise = Metric::Ise(TimeWindow::From(0.002))
+            .evaluate(&r, "y")
+            .unwrap();
+        assert!((ise - 0.001 * (0.64 + 1.0 + 1.0)).abs() < 1e-15, "{}", ise);
+        r.time_unit = "fortnight";
+        assert_eq!(Metric::Max.evaluate(&r, "y"), Ok(1.5));
+        assert_eq!(
+            Metric::Iae(TimeWindow::All).evaluate(&r, "y"),
+            Err(TimeUnitError::Unknown("fortnight"))
+        );
+    }
+
+    #[test]
+    fn test_RequirementsReport_json_and_text() {
+        let good = result(array![0.0, 1.0, 1.0, 1.0, 1.0]);
+        let bad = result(array![0.0, 2.0, 1.0, 1.0, 1.0]);
+        let report = evaluate(
+            &[Requirement::new(
+                "overshoot",
+                "y",
+                Metric::Overshoot,
+                Bound::AtMost(10.0),
+            )],
+            &[("good", &good), ("bad", &bad)],
+        )
+        .unwrap();
+        assert_eq!(
+            report.to_json(),
+            "{\"passed\":false,\"results\":[\
+             {\"requirement\":\"overshoot\",\"run\":\"good\",\"value\":0,\"at_most\":10,\"passed\":true},\
+             {\"requirement\":\"overshoot\",\"run\":\"bad\",\"value\":100,\"at_most\":10,\"passed\":false}]}"
+        );
+        assert!(std::format!("{}", report).ends_with("1 of 2 checks failed"));
+    }
+}
@@ -0,0 +1,121 @@
+//! # Canonical responses
+//!
+//! Step, impulse and ramp response of an element over a `TimeRange`,
+//! each one call returning a `SimResult`. A `Simulation` can be passed
+//! instead of the range, e.g. with the time unit of the element parameters,
+//! see `Simulation::set_element_time_unit`; an unknown unit or an element
+//! sampled with another step than the range is an error.
+//!
+//! The stimulus starts at `range.start`, so the first sample already sees it.
+//! Times, the impulse area and the ramp slope are in the time unit of the
+//! range.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use cb_simulation_util::analysis::{impulse_response, step_response};
+//! use cb_simulation_util::plant::pt1::PT1;
+//! use cb_simulation_util::signal::TimeRange;
+//!
+//! fn main() {
+//!     let mut plant = PT1::<f64>::default().set_t1_time_or_default(4.0);
+//!     let step = step_response(&mut plant, TimeRange::default()).unwrap();
+//!     assert!(step.trace("output").unwrap().values[99] > 0.99);
+//!
+//!     let mut plant = PT1::<f64>::default().set_t1_time_or_default(4.0);
+//!     let impulse = impulse_response(&mut plant, TimeRange::default()).unwrap();
+//!     assert_eq!(impulse.trace("output").unwrap().values[0], 0.25);
+//! }
+//! ```
+
+use crate::plant::{SampleTime, TransferTimeDomain};
+use crate::signal::{ImpulseFunction, RampFunction, StepFunction};
+use crate::sim::{SimResult, Simulation, SimulationError};
+
+/// Response to a unit step
+pub fn step_response<E: TransferTimeDomain<f64> + SampleTime + ?Sized>(
+    plant: &mut E,
+    simulation: impl Into<Simulation>,
+) -> Result<SimResult, SimulationError> {
+    let simulation: Simulation = simulation.into();
+    let step = StepFunction::default().step(simulation.range.start);
+    simulation.run_checked(&step, plant)
+}
+
+/// Response to a pulse of one sample with area 1 (amplitude `1 / sampling_interval`)
+///
+/// The discrete approximation of a Dirac impulse, so the result approximates
+/// the impulse response of the continuous element.
+pub fn impulse_response<E: TransferTimeDomain<f64> + SampleTime + ?Sized>(
+    plant: &mut E,
+    simulation: impl Into<Simulation>,
+) -> Result<SimResult, SimulationError> {
+    let simulation: Simulation = simulation.into();
+    let range = simulation.range;
+    let dt = range.sampling_interval;
+    let impulse = ImpulseFunction::default()
+        .amplitude(1.0 / dt)
+        .start(range.start + dt)
+        .duration(dt / 2.0);
+    simulation.run_checked(&impulse, plant)
+}
+
+/// Response to a ramp with slope 1 per time unit
+pub fn ramp_response<E: TransferTimeDomain<f64> + SampleTime + ?Sized>(
+    plant: &mut E,
+    simulation: impl Into<Simulation>,
+) -> Result<SimResult, SimulationError> {
+    let simulation: Simulation = simulation.into();
+    let ramp = RampFunction::default().start(simulation.range.start);
+    simulation.run_checked(&ramp, plant)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::plant::pt0::PT0;
+    use crate::signal::{TimeRange, TimeUnitError};
+
+    #[test]
+    fn test_impulse_response_area() {
+        let mut plant = PT0::<f64>::default().set_sample_time_or_default(0.5);
+        let range = TimeRange::default().set_sampling_interval(0.5);
+        let result = impulse_response(&mut plant, range).unwrap();
+        let output = &result.trace("output").unwrap().values;
+        assert_eq!(output.sum() * 0.5, 1.0);
+        assert_eq!(output[0], 2.0);
+    }
+
+    #[test]
+    fn test_ramp_response_gain() {
+        let mut plant = PT0::<f64>::default().set_kp(2.0);
+        let result = ramp_response(&mut plant, TimeRange::default()).unwrap();
+        let output = &result.trace("output").unwrap().values;
+        assert_eq!(output[0], 2.0);
+        assert_eq!(output[9], 20.0);
+        assert_eq!(result.trace("input").unwrap().meta.source, "Ramp");
+    }
+
+    #[test]
+    fn test_step_response_time_unit() {
+        // the range is sampled every ms
+        let mut plant = PT0::<f64>::default().set_sample_time_or_default(0.001);
+        let simulation = Simulation::new(TimeRange::default())
+            .set_element_time_unit("s")
+            .unwrap();
+        assert!(step_response(&mut plant, simulation).is_ok());
+        assert!(matches!(
+            step_response(&mut PT0::<f64>::default(), simulation),
+            Err(SimulationError::SampleTime(_))
+        ));
+        let mut simulation = simulation;
+        simulation.range = simulation.range.set_unit_of_measurement("fortnight");
+        assert_eq!(
+            step_response(&mut plant, simulation),
+            Err(SimulationError::TimeUnit(TimeUnitError::Unknown(
+                "fortnight"
+            )))
+        );
+    }
+}
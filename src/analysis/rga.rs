@@ -0,0 +1,129 @@
+//! # Relative gain array
+//!
+//! Interaction measure of a square MIMO steady state gain matrix $K$:
+//!
+//! $ \Lambda = K \circ (K^{-1})^{T} $
+//!
+//! $\lambda_{ij}$ is the gain from input $j$ to output $i$ with all other
+//! loops open, relative to the gain with all other loops closed. Rows and
+//! columns each sum up to 1. Pair input $j$ with output $i$ where
+//! $\lambda_{ij}$ is close to 1; avoid pairings on negative elements.
+//!
+//! The gain matrix comes from a model (`StateSpace::steady_state_gain`) or
+//! from step experiments (`gain_from_steps`).
+//!
+//! ## Example
+//!
+//! ```rust
+//! use ndarray::array;
+//! use cb_simulation_util::analysis::rga::{pairing, rga};
+//!
+//! fn main() {
+//!     let gain = array![[1.0, 2.0], [3.0, 1.0]];
+//!     let lambda = rga(&gain).unwrap();
+//!     assert!((lambda[[0, 0]] + 0.2).abs() < 1e-12);
+//!     // output 0 is paired with input 1, output 1 with input 0
+//!     assert_eq!(pairing(&lambda), vec![1, 0]);
+//! }
+//! ```
+
+use ndarray::Array2;
+use std::vec::Vec;
+
+use crate::linalg;
+
+/// Relative gain array of the square gain matrix, fails if it is singular
+pub fn rga(gain: &Array2<f64>) -> Result<Array2<f64>, &'static str> {
+    if !gain.is_square() {
+        return Err("Invalid gain matrix: Must be square");
+    }
+    let inverse = linalg::inverse(gain).ok_or("Invalid gain matrix: Singular")?;
+    Ok(gain * &inverse.t())
+}
+
+/// Steady state gain matrix from step experiments
+///
+/// Column $e$ of `input_steps` and `output_steps` holds the input and
+/// resulting steady state output change of experiment $e$. With as many
+/// linear independent experiments as inputs $ K = \Delta Y \Delta U^{-1} $.
+pub fn gain_from_steps(
+    input_steps: &Array2<f64>,
+    output_steps: &Array2<f64>,
+) -> Result<Array2<f64>, &'static str> {
+    if !input_steps.is_square() || output_steps.ncols() != input_steps.ncols() {
+        return Err("Invalid steps: One experiment per input needed");
+    }
+    let inverse = linalg::inverse(input_steps).ok_or("Invalid steps: Not linear independent")?;
+    Ok(output_steps.dot(&inverse))
+}
+
+/// Suggested pairing, element `i` is the input controlling output `i`
+///
+/// Chooses the permutation with all relative gains positive and the sum of
+/// $ |\lambda - 1| $ minimal. Falls back to the diagonal if there is none.
+pub fn pairing(rga: &Array2<f64>) -> Vec<usize> {
+    let n = rga.nrows();
+    let mut permutation: Vec<usize> = (0..n).collect();
+    let mut best = permutation.clone();
+    let mut best_cost = f64::INFINITY;
+    loop {
+        let pairs = permutation.iter().enumerate().map(|(i, j)| rga[[i, *j]]);
+        if pairs.clone().all(|lambda| lambda > 0.0) {
+            let cost: f64 = pairs.map(|lambda| (lambda - 1.0).abs()).sum();
+            if cost < best_cost {
+                best_cost = cost;
+                best = permutation.clone();
+            }
+        }
+        if !next_permutation(&mut permutation) {
+            return best;
+        }
+    }
+}
+
+/// Lexicographic next permutation, false after the last one
+fn next_permutation(p: &mut [usize]) -> bool {
+    let Some(i) = (1..p.len()).rev().find(|i| p[i - 1] < p[*i]) else {
+        return false;
+    };
+    let j = (i..p.len()).rev().find(|j| p[*j] > p[i - 1]).unwrap_or(i);
+    p.swap(i - 1, j);
+    p[i..].reverse();
+    true
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::plant::thermal_zones::TwoZoneThermal;
+    use ndarray::array;
+    use std::vec;
+
+    #[test]
+    fn test_rga_rows_sum_to_one() {
+        let gain = array![[2.0, 0.5, 0.1], [0.3, 1.0, 0.2], [0.1, 0.4, 3.0]];
+        let lambda = rga(&gain).unwrap();
+        for row in lambda.outer_iter() {
+            assert!((row.sum() - 1.0).abs() < 1e-12);
+        }
+        assert_eq!(pairing(&lambda), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn test_rga_thermal_zones() {
+        let lambda = rga(&TwoZoneThermal::default().gain_matrix()).unwrap();
+        // 70^2 / (70^2 - 20^2)
+        assert!((lambda[[0, 0]] - 4900.0 / 4500.0).abs() < 1e-12);
+        assert!(rga(&array![[1.0, 1.0], [1.0, 1.0]]).is_err());
+    }
+
+    #[test]
+    fn test_gain_from_steps() {
+        let gain = array![[1.0, 2.0], [3.0, 1.0]];
+        let steps = array![[1.0, 1.0], [0.0, 2.0]];
+        let responses = gain.dot(&steps);
+        let estimate = gain_from_steps(&steps, &responses).unwrap();
+        assert!((&estimate - &gain).iter().all(|e| e.abs() < 1e-12));
+    }
+}
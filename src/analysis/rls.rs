@@ -0,0 +1,271 @@
+//! # Recursive least squares
+//!
+//! `RecursiveLeastSquares` estimates the coefficients of an ARX model (see
+//! `identification`) sample by sample while a simulation runs, so the model
+//! of a plant can be learned inside the loop, e.g. for adaptive control.
+//!
+//! With the regressor $ \varphi[k] = [-y[k-1] \dots -y[k-n_a], u[k-n_k] \dots u[k-n_k-n_b+1]] $
+//! each sample updates
+//!
+//! $ e[k] = y[k] - \varphi^{T}[k] \hat{\theta}[k-1] $
+//!
+//! $ K[k] = \frac{P[k-1] \varphi[k]}{\lambda + \varphi^{T}[k] P[k-1] \varphi[k]} $
+//!
+//! $ \hat{\theta}[k] = \hat{\theta}[k-1] + K[k] e[k] $, $ P[k] = (P[k-1] - K[k] \varphi^{T}[k] P[k-1]) / \lambda $
+//!
+//! The forgetting factor $ \lambda \le 1 $ weights a sample $j$ steps back
+//! by $ \lambda^{j} $: 1 averages over all data, smaller values track slowly
+//! changing plants with a memory of about $ 1 / (1 - \lambda) $ samples.
+//! The estimator starts at rest with zero parameters and a large covariance.
+//!
+//! As `MimoTransferTimeDomain` the inputs are `[u, y]` of the plant and the
+//! outputs the current estimate `[a_1, ..., a_na, b_1, ..., b_nb]`.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use cb_simulation_util::analysis::identification::ArxOrders;
+//! use cb_simulation_util::analysis::rls::RecursiveLeastSquares;
+//! use cb_simulation_util::plant::TransferTimeDomain;
+//! use cb_simulation_util::plant::pt1::PT1;
+//!
+//! fn main() {
+//!     let mut plant = PT1::<f64>::default().set_t1_time_or_default(4.0).set_kp(2.0);
+//!     let mut rls = RecursiveLeastSquares::new(ArxOrders { na: 1, nb: 1, nk: 0 }, 0.98).unwrap();
+//!     for k in 0..200 {
+//!         let u = if (k / 10) % 2 == 0 { 1.0 } else { -1.0 };
+//!         let y = plant.transfer_td(u);
+//!         rls.update(u, y);
+//!     }
+//!     assert!((rls.discrete_tf().dc_gain() - 2.0).abs() < 1e-6);
+//! }
+//! ```
+
+use core::fmt::{self, Display};
+use ndarray::{Array1, Array2, ArrayView1};
+use std::vec;
+use std::vec::Vec;
+
+use super::discrete_tf::DiscreteTF;
+use super::identification::ArxOrders;
+use crate::plant::{MimoTransferTimeDomain, TypeIdentifier};
+
+/// Initial covariance of the parameters, large for a fast start
+const INITIAL_COVARIANCE: f64 = 1.0e4;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecursiveLeastSquares {
+    pub orders: ArxOrders,
+    /// Forgetting factor $ \lambda $ in (0, 1]
+    pub forgetting: f64,
+    /// Estimate `[a_1, ..., a_na, b_1, ..., b_nb]`
+    theta: Array1<f64>,
+    covariance: Array2<f64>,
+    /// `u[k], u[k-1], ...`, the latest first
+    inputs: Vec<f64>,
+    /// `y[k-1], y[k-2], ...`, the latest first
+    outputs: Vec<f64>,
+}
+
+impl RecursiveLeastSquares {
+    pub fn new(orders: ArxOrders, forgetting: f64) -> Result<Self, &'static str> {
+        if !(forgetting > 0.0 && forgetting <= 1.0) {
+            return Err("Forgetting factor must be within (0, 1]");
+        }
+        if orders.na + orders.nb == 0 {
+            return Err("At least one parameter to estimate is required");
+        }
+        let parameters = orders.na + orders.nb;
+        Ok(RecursiveLeastSquares {
+            orders,
+            forgetting,
+            theta: Array1::zeros(parameters),
+            covariance: Array2::eye(parameters) * INITIAL_COVARIANCE,
+            inputs: vec![0.0; orders.nk + orders.nb],
+            outputs: vec![0.0; orders.na],
+        })
+    }
+
+    /// Start from the estimate `theta` with the diagonal covariance `variance`
+    ///
+    /// A small variance trusts the prior estimate, a large one forgets it
+    /// quickly. Ignored if the length does not fit or `variance` is not
+    /// positive.
+    pub fn set_initial_estimate(self, theta: &[f64], variance: f64) -> Self {
+        if theta.len() != self.theta.len() || variance <= 0.0 {
+            return self;
+        }
+        let parameters = theta.len();
+        RecursiveLeastSquares {
+            theta: Array1::from_vec(theta.to_vec()),
+            covariance: Array2::eye(parameters) * variance,
+            ..self
+        }
+    }
+
+    /// Process one sample of the plant input and output
+    ///
+    /// Returns the a priori prediction error $ e[k] $.
+    pub fn update(&mut self, input: f64, output: f64) -> f64 {
+        self.inputs.rotate_right(1);
+        if let Some(latest) = self.inputs.first_mut() {
+            *latest = input;
+        }
+        let phi = self.regressor();
+        let error = output - phi.dot(&self.theta);
+        let p_phi = self.covariance.dot(&phi);
+        let gain = &p_phi / (self.forgetting + phi.dot(&p_phi));
+        self.theta = &self.theta + &(&gain * error);
+        let correction = gain
+            .view()
+            .insert_axis(ndarray::Axis(1))
+            .dot(&p_phi.view().insert_axis(ndarray::Axis(0)));
+        self.covariance = (&self.covariance - &correction) / self.forgetting;
+        self.outputs.rotate_right(1);
+        if let Some(latest) = self.outputs.first_mut() {
+            *latest = output;
+        }
+        error
+    }
+
+    fn regressor(&self) -> Array1<f64> {
+        self.outputs
+            .iter()
+            .map(|y| -y)
+            .chain(self.inputs[self.orders.nk..].iter().copied())
+            .collect()
+    }
+
+    /// Current estimate `[a_1, ..., a_na, b_1, ..., b_nb]`
+    pub fn parameters(&self) -> &Array1<f64> {
+        &self.theta
+    }
+
+    /// Covariance of the estimate, up to the noise variance
+    pub fn covariance(&self) -> &Array2<f64> {
+        &self.covariance
+    }
+
+    /// Transfer function of the current estimate
+    pub fn discrete_tf(&self) -> DiscreteTF {
+        let na = self.orders.na;
+        let mut den = vec![1.0];
+        den.extend(self.theta.iter().take(na));
+        let mut num = vec![0.0; self.orders.nk];
+        num.extend(self.theta.iter().skip(na));
+        DiscreteTF::new(num, den)
+    }
+
+    /// Forget the estimate and the data, back to the initial state
+    pub fn reset(&mut self) {
+        if let Ok(fresh) = RecursiveLeastSquares::new(self.orders, self.forgetting) {
+            *self = fresh;
+        }
+    }
+}
+
+impl TypeIdentifier for RecursiveLeastSquares {
+    fn short_type_name(&self) -> &'static str {
+        "RecursiveLeastSquares"
+    }
+}
+
+impl Display for RecursiveLeastSquares {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "RecursiveLeastSquares(na: {}, nb: {}, nk: {}, forgetting: {})",
+            self.orders.na, self.orders.nb, self.orders.nk, self.forgetting
+        )
+    }
+}
+
+impl MimoTransferTimeDomain for RecursiveLeastSquares {
+    fn input_count(&self) -> usize {
+        2
+    }
+
+    fn output_count(&self) -> usize {
+        self.theta.len()
+    }
+
+    fn transfer_td(&mut self, u: ArrayView1<f64>) -> Array1<f64> {
+        self.update(u[0], u[1]);
+        self.theta.clone()
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::plant::TransferTimeDomain;
+    use crate::plant::pt1::PT1;
+    use ndarray::array;
+
+    fn square(k: usize) -> f64 {
+        if (k / 7).is_multiple_of(2) { 1.0 } else { -1.0 }
+    }
+
+    #[test]
+    fn test_RecursiveLeastSquares_converges_to_arx() {
+        let mut plant = PT1::<f64>::default()
+            .set_t1_time_or_default(4.0)
+            .set_kp(2.0);
+        let mut sut = RecursiveLeastSquares::new(
+            ArxOrders {
+                na: 1,
+                nb: 1,
+                nk: 0,
+            },
+            1.0,
+        )
+        .unwrap();
+        for k in 0..100 {
+            let u = square(k);
+            let y = plant.transfer_td(u);
+            sut.update(u, y);
+        }
+        let theta = sut.parameters();
+        assert!((theta[0] + 0.75).abs() < 1e-6, "{}", theta);
+        assert!((theta[1] - 0.5).abs() < 1e-6, "{}", theta);
+        assert!((sut.discrete_tf().dc_gain() - 2.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_RecursiveLeastSquares_tracks_gain_change() {
+        let mut plant = PT1::<f64>::default().set_t1_time_or_default(2.0);
+        let mut sut = RecursiveLeastSquares::new(
+            ArxOrders {
+                na: 1,
+                nb: 1,
+                nk: 0,
+            },
+            0.9,
+        )
+        .unwrap();
+        for k in 0..300 {
+            if k == 150 {
+                plant.kp = 3.0;
+            }
+            let u = square(k);
+            let y = plant.transfer_td(u);
+            MimoTransferTimeDomain::transfer_td(&mut sut, array![u, y].view());
+        }
+        assert!((sut.discrete_tf().dc_gain() - 3.0).abs() < 1e-6);
+        sut.reset();
+        assert_eq!(sut.parameters(), array![0.0, 0.0]);
+        assert!(
+            RecursiveLeastSquares::new(
+                ArxOrders {
+                    na: 1,
+                    nb: 1,
+                    nk: 0
+                },
+                1.5
+            )
+            .is_err()
+        );
+    }
+}
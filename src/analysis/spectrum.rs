@@ -0,0 +1,149 @@
+//! # Spectrum
+//!
+//! One-sided power spectral density estimate (periodogram) of a trace,
+//! computed by a direct DFT of the samples within a `TimeWindow`.
+//!
+//! `psd` takes the time axis as is, the frequencies are in cycles per its
+//! time unit. `psd_hz` converts from the time unit of a `TimeRange` or
+//! `SimResult`, e.g. ms, so the frequencies are in Hz.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use ndarray::Array1;
+//! use cb_simulation_util::analysis::TimeWindow;
+//! use cb_simulation_util::analysis::spectrum::psd;
+//!
+//! fn main() {
+//!     let time: Array1<f64> = (0..64).map(|k| k as f64 * 0.1).collect();
+//!     let values = time.mapv(|t| (2.0 * core::f64::consts::PI * 1.25 * t).sin());
+//!     let spectrum = psd(&time, &values, TimeWindow::All);
+//!     let peak = spectrum.peak_frequency().unwrap();
+//!     assert!((peak - 1.25).abs() < 1e-9);
+//! }
+//! ```
+
+use super::TimeWindow;
+use crate::signal::{TimeUnitError, convert_time};
+use core::f64::consts::PI;
+use ndarray::Array1;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Spectrum {
+    /// Frequencies in cycles per time unit of the time axis
+    pub frequency: Array1<f64>,
+    pub power: Array1<f64>,
+}
+
+impl Spectrum {
+    /// Frequency of the largest power, the DC component excluded
+    pub fn peak_frequency(&self) -> Option<f64> {
+        self.power
+            .iter()
+            .enumerate()
+            .skip(1)
+            .max_by(|a, b| a.1.total_cmp(b.1))
+            .map(|(i, _)| self.frequency[i])
+    }
+}
+
+/// Power spectral density of the samples within `window`
+///
+/// The sample interval is taken from the first two time stamps of the window.
+pub fn psd(time: &Array1<f64>, values: &Array1<f64>, window: TimeWindow) -> Spectrum {
+    let range = window.indices(time, values);
+    let n = range.len();
+    if n < 2 {
+        return Spectrum {
+            frequency: Array1::zeros(0),
+            power: Array1::zeros(0),
+        };
+    }
+    let dt = time[range.start + 1] - time[range.start];
+    let samples = values.slice(ndarray::s![range]);
+    let bins = n / 2 + 1;
+    let mut frequency = Array1::zeros(bins);
+    let mut power = Array1::zeros(bins);
+    for k in 0..bins {
+        let (mut re, mut im) = (0.0, 0.0);
+        for (i, x) in samples.iter().enumerate() {
+            let phi = -2.0 * PI * (k * i) as f64 / n as f64;
+            re += x * phi.cos();
+            im += x * phi.sin();
+        }
+        let mut p = (re * re + im * im) * dt / n as f64;
+        if k != 0 && !(n.is_multiple_of(2) && k == n / 2) {
+            p *= 2.0;
+        }
+        frequency[k] = k as f64 / (n as f64 * dt);
+        power[k] = p;
+    }
+    Spectrum { frequency, power }
+}
+
+/// Power spectral density with the frequencies in Hz and the power per Hz
+///
+/// `time` and the window are in `time_unit`, fails if it is not a known
+/// time unit.
+pub fn psd_hz(
+    time: &Array1<f64>,
+    time_unit: &'static str,
+    values: &Array1<f64>,
+    window: TimeWindow,
+) -> Result<Spectrum, TimeUnitError> {
+    let seconds = convert_time(1.0, time_unit, "s")?;
+    let spectrum = psd(time, values, window);
+    Ok(Spectrum {
+        frequency: spectrum.frequency / seconds,
+        power: spectrum.power * seconds,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_psd_parseval() {
+        let time: Array1<f64> = (0..16).map(|k| k as f64).collect();
+        let values = time.mapv(|t| {
+            if (t as usize).is_multiple_of(3) {
+                1.0
+            } else {
+                -0.5
+            }
+        });
+        let spectrum = psd(&time, &values, TimeWindow::All);
+        let energy: f64 = values.iter().map(|v| v * v).sum();
+        let df = spectrum.frequency[1];
+        let total: f64 = spectrum.power.sum() * df * 16.0;
+        assert!((total - energy).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_psd_hz_converts_time_unit() {
+        // 4 ms sampling interval, 50 Hz
+        let time: Array1<f64> = (0..100).map(|k| k as f64 * 4.0).collect();
+        let values = time.mapv(|t| (2.0 * PI * 0.05 * t).sin());
+        let per_ms = psd(&time, &values, TimeWindow::All);
+        let spectrum = psd_hz(&time, "ms", &values, TimeWindow::All).unwrap();
+        assert!((per_ms.peak_frequency().unwrap() - 0.05).abs() < 1e-12);
+        assert!((spectrum.peak_frequency().unwrap() - 50.0).abs() < 1e-9);
+        // the total power does not depend on the unit
+        let total = |s: &Spectrum| s.power.sum() * s.frequency[1];
+        assert!((total(&spectrum) - total(&per_ms)).abs() < 1e-12);
+        assert_eq!(
+            psd_hz(&time, "fortnight", &values, TimeWindow::All),
+            Err(TimeUnitError::Unknown("fortnight"))
+        );
+    }
+
+    #[test]
+    fn test_psd_window_excludes_transient() {
+        let time: Array1<f64> = (0..40).map(|k| k as f64).collect();
+        let values = time.mapv(|t| if t < 20.0 { 100.0 } else { 0.0 });
+        let spectrum = psd(&time, &values, TimeWindow::From(20.0));
+        assert_eq!(spectrum.power.sum(), 0.0);
+    }
+}
@@ -0,0 +1,232 @@
+//! # Word length advisor
+//!
+//! Sweeps the number of fraction bits of the fixed point controller in a
+//! loop, see `fixed_float::compare_loop`, and reports the smallest format
+//! which keeps the deviation of the loop output from the floating point
+//! reference within a bound. The integer bits are derived from the largest
+//! magnitude of the controller input and output in the reference run.
+//!
+//! More fraction bits reduce the error only until the values or the
+//! intermediate products of the controller overflow or saturate, then the
+//! error grows again. So the advised fraction bits are the smallest count of
+//! the sweep within the bound. Counts which do not fit into an `i32` together
+//! with the integer bits are infeasible and never advised.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use cb_simulation_util::analysis::word_length::advise_word_length;
+//! use cb_simulation_util::controller::pi::{AntiWindup, PI};
+//! use cb_simulation_util::plant::pt1::PT1;
+//! use cb_simulation_util::signal::{StepFunction, TimeRange};
+//! use cb_simulation_util::sim::Simulation;
+//!
+//! fn main() {
+//!     let sim = Simulation::new(TimeRange::default().set_end(100.0));
+//!     let float = PI::<f64>::default().set_kp(2.0).set_ti_time_or_default(8.0)
+//!         .set_anti_windup(AntiWindup::None);
+//!     let fixed = PI::<i32>::default().set_kp(2).set_ti_time_or_default(8.0)
+//!         .set_anti_windup(AntiWindup::None);
+//!     let plant = PT1::<f64>::default().set_t1_time_or_default(10.0);
+//!     let advice = advise_word_length(
+//!         &sim, &StepFunction::default().step(2.0), &float, &fixed, &plant, 0..=16, 0.01,
+//!     );
+//!     println!("{}", advice);
+//!     assert_eq!(advice.integer_bits, 3);
+//!     assert_eq!(advice.frac_bits, Some(6));
+//! }
+//! ```
+
+use core::fmt::{self, Display};
+use core::ops::RangeInclusive;
+use std::vec::Vec;
+
+use super::fixed_float::compare_loop;
+use crate::plant::TransferTimeDomain;
+use crate::signal::{FixedPoint, TimeSignal};
+use crate::sim::Simulation;
+
+/// Deviation of the loop output for one number of fraction bits
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SweepPoint {
+    pub frac_bits: u8,
+    /// Largest absolute deviation of the loop output from the float reference
+    pub max_error: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct WordLengthAdvice {
+    /// Error bound of the loop output
+    pub bound: f64,
+    /// Bits before the binary point including the sign bit
+    pub integer_bits: u8,
+    /// Smallest fraction bits within the bound, `None` if no count of the sweep is
+    pub frac_bits: Option<u8>,
+    pub sweep: Vec<SweepPoint>,
+}
+
+impl WordLengthAdvice {
+    /// Whether `frac_bits` fit into an `i32` together with the integer bits
+    pub fn is_feasible(&self, frac_bits: u8) -> bool {
+        self.integer_bits as u32 + frac_bits as u32 <= 32
+    }
+
+    /// Total word length of the advised format
+    pub fn word_length(&self) -> Option<u8> {
+        self.frac_bits.map(|f| self.integer_bits + f)
+    }
+}
+
+impl Display for WordLengthAdvice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Word length sweep (bound: {})", self.bound)?;
+        for p in &self.sweep {
+            writeln!(
+                f,
+                "  Q{}.{}: max error {}{}",
+                self.integer_bits,
+                p.frac_bits,
+                p.max_error,
+                if Some(p.frac_bits) == self.frac_bits {
+                    " <- advised"
+                } else if !self.is_feasible(p.frac_bits) {
+                    " (overflows)"
+                } else {
+                    ""
+                }
+            )?;
+        }
+        match self.word_length() {
+            Some(bits) => writeln!(f, "advised word length: {} bits", bits),
+            None => writeln!(f, "no format of the sweep is within the bound"),
+        }
+    }
+}
+
+/// Bits needed for the integer part of `magnitude`, including the sign bit
+fn integer_bits(magnitude: f64) -> u8 {
+    let mut bits = 1;
+    while bits < 32 && magnitude >= (1u64 << (bits - 1)) as f64 {
+        bits += 1;
+    }
+    bits
+}
+
+/// Sweep `frac_bits` for the fixed point version of a loop controller
+///
+/// `bound` limits the largest deviation of the loop output from the run with
+/// the float controller, in the unit of the output.
+pub fn advise_word_length<F, I, P>(
+    sim: &Simulation,
+    setpoint: &dyn TimeSignal<f64>,
+    float: &F,
+    fixed: &I,
+    plant: &P,
+    frac_bits: RangeInclusive<u8>,
+    bound: f64,
+) -> WordLengthAdvice
+where
+    F: TransferTimeDomain<f64> + Clone,
+    I: TransferTimeDomain<i32> + Clone,
+    P: TransferTimeDomain<f64> + Clone,
+{
+    let mut magnitude: f64 = 0.0;
+    let sweep: Vec<SweepPoint> = frac_bits
+        .map(|bits| {
+            let report = compare_loop(
+                sim,
+                setpoint,
+                &mut float.clone(),
+                &mut fixed.clone(),
+                plant,
+                FixedPoint::new(bits),
+            );
+            for name in ["error", "control"] {
+                if let Some(trace) = report.float.trace(name) {
+                    magnitude = trace.values.iter().fold(magnitude, |m, v| m.max(v.abs()));
+                }
+            }
+            SweepPoint {
+                frac_bits: bits,
+                max_error: report
+                    .diff
+                    .deviation("output")
+                    .map_or(f64::NAN, |d| d.max_abs),
+            }
+        })
+        .collect();
+    let mut advice = WordLengthAdvice {
+        bound,
+        integer_bits: integer_bits(magnitude),
+        frac_bits: None,
+        sweep,
+    };
+    advice.frac_bits = advice
+        .sweep
+        .iter()
+        .filter(|p| advice.is_feasible(p.frac_bits) && p.max_error <= bound)
+        .map(|p| p.frac_bits)
+        .min();
+    advice
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::controller::pi::{AntiWindup, PI};
+    use crate::plant::pt1::PT1;
+    use crate::signal::{StepFunction, TimeRange};
+
+    #[test]
+    fn test_integer_bits() {
+        assert_eq!(integer_bits(0.0), 1);
+        assert_eq!(integer_bits(0.99), 1);
+        assert_eq!(integer_bits(1.0), 2);
+        assert_eq!(integer_bits(4.5), 4);
+        assert_eq!(integer_bits(1.0e12), 32);
+    }
+
+    #[test]
+    fn test_advise_word_length_unreachable_bound() {
+        let sim = Simulation::new(TimeRange::default().set_end(50.0));
+        let float = PI::<f64>::default()
+            .set_kp(2.0)
+            .set_anti_windup(AntiWindup::None);
+        let fixed = PI::<i32>::default()
+            .set_kp(2)
+            .set_anti_windup(AntiWindup::None);
+        let plant = PT1::<f64>::default().set_t1_time_or_default(10.0);
+        let setpoint = StepFunction::default().step(1.0);
+        let advice = advise_word_length(&sim, &setpoint, &float, &fixed, &plant, 0..=4, 1e-12);
+        assert_eq!(advice.sweep.len(), 5);
+        assert_eq!(advice.frac_bits, None);
+        assert_eq!(advice.word_length(), None);
+        let advice = advise_word_length(&sim, &setpoint, &float, &fixed, &plant, 0..=4, 10.0);
+        assert_eq!(advice.frac_bits, Some(0));
+    }
+
+    #[test]
+    fn test_advise_word_length_skips_overflowing_formats() {
+        let sim = Simulation::new(TimeRange::default().set_end(100.0));
+        let float = PI::<f64>::default()
+            .set_kp(2.0)
+            .set_ti_time_or_default(8.0)
+            .set_anti_windup(AntiWindup::None);
+        let fixed = PI::<i32>::default()
+            .set_kp(2)
+            .set_ti_time_or_default(8.0)
+            .set_anti_windup(AntiWindup::None);
+        let plant = PT1::<f64>::default().set_t1_time_or_default(10.0);
+        let setpoint = StepFunction::default().post(100.0);
+        let advice = advise_word_length(&sim, &setpoint, &float, &fixed, &plant, 0..=30, 0.5);
+        assert_eq!(advice.integer_bits, 9);
+        // the controller saturates from 15 fraction bits on, counts beyond 23
+        // do not fit into an i32 at all
+        assert!(advice.sweep[0].max_error <= 0.5);
+        assert!(advice.sweep[15].max_error > 0.5);
+        assert!(advice.is_feasible(23) && !advice.is_feasible(24));
+        assert_eq!(advice.frac_bits, Some(0));
+        assert_eq!(advice.word_length(), Some(9));
+    }
+}
@@ -0,0 +1,515 @@
+//! # cb-sim
+//!
+//! Runs a scenario described in a TOML or JSON file, writes the traces as CSV
+//! or Parquet and prints the evaluated metrics. The exit code is 0 if all metrics pass,
+//! 1 if any fails and 2 for usage or configuration errors, so it can gate CI
+//! pipelines.
+//!
+//! ```text
+//! cb-sim <run.toml | run.json> [--csv <file>] [--parquet <file>] [--watch]
+//! cb-sim --list
+//! ```
+//!
+//! The Parquet file holds one uncompressed row group with a `DOUBLE` column
+//! per trace, the columns are labeled like the CSV header, e.g.
+//! `position [rad]`.
+//!
+//! Next to each output a reproducibility manifest is written,
+//! `<name>.manifest.json` for `<name>.csv` or `<name>.parquet`, see
+//! `manifest::RunManifest`. It records the crate
+//! version, the hash of the run file, the time range, seeds and block
+//! parameters and the hash of the traces.
+//!
+//! With `--watch` the run file is polled for changes and the simulation is
+//! rerun, and the outputs rewritten, every time it is saved. Configuration errors
+//! are reported without leaving the watch loop, so a tuning session survives
+//! a typo. Stop it with Ctrl-C.
+//!
+//! A run file names a built-in scenario and optionally overrides the time
+//! range, adds requirements and sets the CSV or Parquet output:
+//!
+//! ```toml
+//! scenario = "servo"
+//!
+//! [time]
+//! end = 0.5
+//!
+//! [output]
+//! csv = "servo.csv"
+//! parquet = "servo.parquet"
+//!
+//! [[requirements]]
+//! name = "settled in time"
+//! trace = "position"
+//! metric = "settling_time"
+//! tolerance = 0.02
+//! at_most = 0.2
+//! ```
+//!
+//! Instead of a scenario, a `[diagram]` table defines the experiment itself,
+//! see `config::DiagramConfig`, e.g.
+//!
+//! ```toml
+//! [diagram.time]
+//! unit = "s"
+//! end = 30.0
+//! sampling_interval = 0.5
+//!
+//! [diagram.signals]
+//! input = "Step(step_time=1, pre=0, post=2)"
+//!
+//! [[diagram.blocks]]
+//! name = "lag"
+//! type = "PT1"
+//! parameters = { sample_time = 0.5, t1_time = 2.0 }
+//!
+//! [[diagram.connections]]
+//! from = "input"
+//! to = "lag"
+//! ```
+
+use std::process::ExitCode;
+use std::time::{Duration, SystemTime};
+
+use cb_simulation_util::analysis::requirements::{self, Bound, Metric, Requirement};
+use cb_simulation_util::config::DiagramConfig;
+use cb_simulation_util::manifest::RunManifest;
+use cb_simulation_util::scenario::{Diagram, ScenarioRegistry};
+use cb_simulation_util::signal::TimeRange;
+use cb_simulation_util::sim::SimResult;
+use parquet::basic::{Repetition, Type as PhysicalType};
+use parquet::data_type::DoubleType;
+use parquet::errors::ParquetError;
+use parquet::file::writer::SerializedFileWriter;
+use parquet::schema::types::Type;
+use serde::Deserialize;
+use std::sync::Arc;
+
+#[derive(Debug, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+struct RunConfig {
+    scenario: Option<String>,
+    diagram: Option<DiagramConfig>,
+    #[serde(default)]
+    time: TimeConfig,
+    #[serde(default)]
+    output: OutputConfig,
+    #[serde(default)]
+    requirements: Vec<RequirementConfig>,
+}
+
+#[derive(Debug, Default, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+struct TimeConfig {
+    start: Option<f64>,
+    end: Option<f64>,
+    sampling_interval: Option<f64>,
+}
+
+#[derive(Debug, Default, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+struct OutputConfig {
+    csv: Option<String>,
+    parquet: Option<String>,
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+struct RequirementConfig {
+    name: String,
+    trace: String,
+    metric: String,
+    tolerance: Option<f64>,
+    at_most: Option<f64>,
+    at_least: Option<f64>,
+}
+
+impl RequirementConfig {
+    fn requirement(&self) -> Result<Requirement, String> {
+        let metric = match self.metric.as_str() {
+            "max" => Metric::Max,
+            "min" => Metric::Min,
+            "final_value" => Metric::FinalValue,
+            "overshoot" => Metric::Overshoot,
+            "settling_time" => Metric::SettlingTime {
+                tolerance: self.tolerance.unwrap_or(0.02),
+            },
+            other => {
+                return Err(format!(
+                    "requirement '{}': unknown metric '{}'",
+                    self.name, other
+                ));
+            }
+        };
+        let bound = match (self.at_most, self.at_least) {
+            (Some(limit), None) => Bound::AtMost(limit),
+            (None, Some(limit)) => Bound::AtLeast(limit),
+            _ => {
+                return Err(format!(
+                    "requirement '{}': exactly one of at_most or at_least needed",
+                    self.name
+                ));
+            }
+        };
+        Ok(Requirement::new(&self.name, &self.trace, metric, bound))
+    }
+}
+
+fn parse(path: &str, text: &str) -> Result<RunConfig, String> {
+    if path.ends_with(".json") {
+        serde_json::from_str(text).map_err(|e| format!("{}: {}", path, e))
+    } else {
+        toml::from_str(text).map_err(|e| format!("{}: {}", path, e))
+    }
+}
+
+/// `servo.csv` -> `servo.manifest.json`
+fn manifest_path(output: &str) -> String {
+    std::path::Path::new(output)
+        .with_extension("manifest.json")
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// Columns labeled like the CSV header, all in one row group
+fn write_parquet(result: &SimResult, file: &str) -> Result<(), ParquetError> {
+    let columns: Vec<(String, Vec<f64>)> =
+        std::iter::once((format!("time [{}]", result.time_unit), result.time.to_vec()))
+            .chain(
+                result
+                    .traces
+                    .iter()
+                    .map(|t| (format!("{} [{}]", t.name, t.meta.unit), t.values.to_vec())),
+            )
+            .collect();
+    let fields = columns
+        .iter()
+        .map(|(label, _)| {
+            Type::primitive_type_builder(label, PhysicalType::DOUBLE)
+                .with_repetition(Repetition::REQUIRED)
+                .build()
+                .map(Arc::new)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let schema = Type::group_type_builder("traces")
+        .with_fields(fields)
+        .build()?;
+    let file = std::fs::File::create(file).map_err(|e| ParquetError::External(Box::new(e)))?;
+    let mut writer = SerializedFileWriter::new(file, Arc::new(schema), Default::default())?;
+    let mut row_group = writer.next_row_group()?;
+    for (_, values) in &columns {
+        let Some(mut column) = row_group.next_column()? else {
+            break;
+        };
+        column
+            .typed::<DoubleType>()
+            .write_batch(values, None, None)?;
+        column.close()?;
+    }
+    row_group.close()?;
+    writer.close()?;
+    Ok(())
+}
+
+/// Write the traces and the manifest next to them
+fn write_outputs(
+    outputs: &[(&str, &str)],
+    result: &SimResult,
+    manifest: &RunManifest,
+) -> Result<(), String> {
+    for (format, file) in outputs {
+        match *format {
+            "csv" => std::fs::write(file, result.to_csv()).map_err(|e| e.to_string()),
+            _ => write_parquet(result, file).map_err(|e| e.to_string()),
+        }
+        .map_err(|e| format!("{}: {}", file, e))?;
+        println!("traces written to {}", file);
+        let manifest_file = manifest_path(file);
+        std::fs::write(&manifest_file, manifest.to_json())
+            .map_err(|e| format!("{}: {}", manifest_file, e))?;
+        println!("manifest written to {}", manifest_file);
+    }
+    Ok(())
+}
+
+fn run(path: &str, csv: Option<String>, parquet: Option<String>) -> Result<bool, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+    let config = parse(path, &text)?;
+    let registry = ScenarioRegistry::builtin();
+    let (name, mut diagram, mut range, mut checks): (_, Box<dyn Diagram>, TimeRange, _) =
+        match (&config.scenario, &config.diagram) {
+            (Some(name), None) => {
+                let scenario = registry.get(name).ok_or_else(|| {
+                    format!(
+                        "unknown scenario '{}', available: {}",
+                        name,
+                        registry.names().join(", ")
+                    )
+                })?;
+                (
+                    scenario.name(),
+                    scenario.build(),
+                    scenario.time_range(),
+                    scenario.expected_metrics(),
+                )
+            }
+            (None, Some(diagram)) => {
+                let error = |e| format!("{}: {}", path, e);
+                (
+                    "diagram",
+                    Box::new(diagram.build_builtin().map_err(error)?),
+                    diagram.time_range().map_err(error)?,
+                    Vec::new(),
+                )
+            }
+            _ => {
+                return Err(format!(
+                    "{}: exactly one of scenario or diagram needed",
+                    path
+                ));
+            }
+        };
+    if let Some(start) = config.time.start {
+        range = range.set_start(start);
+    }
+    if let Some(end) = config.time.end {
+        range = range.set_end(end);
+    }
+    if let Some(interval) = config.time.sampling_interval {
+        range = range.set_sampling_interval(interval);
+    }
+    for requirement in &config.requirements {
+        checks.push(requirement.requirement()?);
+    }
+
+    let result = diagram.run(range);
+    let csv = csv.or(config.output.csv);
+    let parquet = parquet.or(config.output.parquet);
+    let outputs: Vec<(&str, &str)> = [("csv", &csv), ("parquet", &parquet)]
+        .into_iter()
+        .filter_map(|(format, file)| Some((format, file.as_deref()?)))
+        .collect();
+    if !outputs.is_empty() {
+        let manifest = RunManifest::new(name, &range)
+            .set_config(&text)
+            .add_diagram(&*diagram)
+            .set_result(&result);
+        write_outputs(&outputs, &result, &manifest)?;
+    }
+    let report = requirements::evaluate(&checks, &[(name, &result)]).map_err(|e| e.to_string())?;
+    println!("{}", report);
+    Ok(report.passed())
+}
+
+/// Interval at which the run file is checked for changes in watch mode
+const WATCH_INTERVAL: Duration = Duration::from_millis(250);
+
+const USAGE: &str = "usage: cb-sim <run.toml | run.json> [--csv <file>] [--parquet <file>] [--watch]\n       cb-sim --list";
+
+#[derive(Debug, PartialEq)]
+enum Command {
+    List,
+    Run {
+        path: String,
+        csv: Option<String>,
+        parquet: Option<String>,
+        watch: bool,
+    },
+}
+
+fn parse_args(args: &[String]) -> Option<Command> {
+    if let [flag] = args
+        && flag == "--list"
+    {
+        return Some(Command::List);
+    }
+    let (mut path, mut csv, mut parquet, mut watch) = (None, None, None, false);
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--csv" if csv.is_none() => csv = Some(args.next()?.clone()),
+            "--parquet" if parquet.is_none() => parquet = Some(args.next()?.clone()),
+            "--watch" if !watch => watch = true,
+            flag if flag.starts_with("--") => return None,
+            _ if path.is_none() => path = Some(arg.clone()),
+            _ => return None,
+        }
+    }
+    Some(Command::Run {
+        path: path?,
+        csv,
+        parquet,
+        watch,
+    })
+}
+
+fn modified(path: &str) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// Rerun whenever the modification time of the run file changes, never returns
+fn watch(path: &str, csv: Option<String>, parquet: Option<String>) -> ! {
+    let mut last = modified(path);
+    loop {
+        match run(path, csv.clone(), parquet.clone()) {
+            Ok(true) => println!("PASSED"),
+            Ok(false) => println!("FAILED"),
+            Err(message) => eprintln!("error: {}", message),
+        }
+        println!("watching {} for changes ...", path);
+        loop {
+            std::thread::sleep(WATCH_INTERVAL);
+            let current = modified(path);
+            if current.is_some() && current != last {
+                last = current;
+                break;
+            }
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let (path, csv, parquet) = match parse_args(&args) {
+        Some(Command::List) => {
+            for scenario in ScenarioRegistry::builtin().iter() {
+                println!("{:<20} {}", scenario.name(), scenario.description());
+            }
+            return ExitCode::SUCCESS;
+        }
+        Some(Command::Run {
+            path,
+            csv,
+            parquet,
+            watch: true,
+        }) => watch(&path, csv, parquet),
+        Some(Command::Run {
+            path, csv, parquet, ..
+        }) => (path, csv, parquet),
+        None => {
+            eprintln!("{}", USAGE);
+            return ExitCode::from(2);
+        }
+    };
+    match run(&path, csv, parquet) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(message) => {
+            eprintln!("error: {}", message);
+            ExitCode::from(2)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_parse_toml_and_json_alike() {
+        let toml = "scenario = \"servo\"\n[time]\nend = 0.1\n[[requirements]]\nname = \"peak\"\ntrace = \"current\"\nmetric = \"max\"\nat_most = 10.0\n";
+        let json = r#"{"scenario": "servo", "time": {"end": 0.1},
+            "requirements": [{"name": "peak", "trace": "current", "metric": "max", "at_most": 10.0}]}"#;
+        let from_toml = parse("run.toml", toml).unwrap();
+        assert_eq!(from_toml, parse("run.json", json).unwrap());
+        assert_eq!(from_toml.scenario.as_deref(), Some("servo"));
+        assert_eq!(from_toml.time.end, Some(0.1));
+        assert_eq!(
+            from_toml.requirements[0].requirement().unwrap().bound,
+            Bound::AtMost(10.0)
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse("run.toml", "scenario = \"servo\"\nunknown = 1\n").is_err());
+        let config = parse(
+            "run.toml",
+            "scenario = \"servo\"\n[[requirements]]\nname = \"x\"\ntrace = \"position\"\nmetric = \"median\"\nat_most = 1.0\n",
+        )
+        .unwrap();
+        assert!(config.requirements[0].requirement().is_err());
+    }
+
+    #[test]
+    fn test_run_diagram() {
+        let path = std::env::temp_dir().join("cb_sim_test_run_diagram.toml");
+        let toml = "[diagram.time]\nunit = \"s\"\nend = 30.0\nsampling_interval = 0.5\n\
+            [diagram.signals]\ninput = \"Step(step_time=1, pre=0, post=2)\"\n\
+            [[diagram.blocks]]\nname = \"lag\"\ntype = \"PT1\"\nparameters = { sample_time = 0.5, t1_time = 2.0 }\n\
+            [[diagram.connections]]\nfrom = \"input\"\nto = \"lag\"\n\
+            [[requirements]]\nname = \"settled\"\ntrace = \"lag\"\nmetric = \"final_value\"\nat_least = 1.99\n";
+        std::fs::write(&path, toml).unwrap();
+        let path = path.to_string_lossy().into_owned();
+        assert_eq!(run(&path, None, None), Ok(true));
+        std::fs::write(&path, format!("scenario = \"servo\"\n{}", toml)).unwrap();
+        assert!(run(&path, None, None).is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let args = |list: &[&str]| -> Vec<String> { list.iter().map(|a| a.to_string()).collect() };
+        assert_eq!(parse_args(&args(&["--list"])), Some(Command::List));
+        assert_eq!(
+            parse_args(&args(&["--watch", "run.toml", "--csv", "out.csv"])),
+            Some(Command::Run {
+                path: String::from("run.toml"),
+                csv: Some(String::from("out.csv")),
+                parquet: None,
+                watch: true
+            })
+        );
+        assert_eq!(
+            parse_args(&args(&["run.toml", "--parquet", "out.parquet"])),
+            Some(Command::Run {
+                path: String::from("run.toml"),
+                csv: None,
+                parquet: Some(String::from("out.parquet")),
+                watch: false
+            })
+        );
+        assert_eq!(parse_args(&args(&[])), None);
+        assert_eq!(parse_args(&args(&["run.toml", "--csv"])), None);
+        assert_eq!(parse_args(&args(&["run.toml", "--plot"])), None);
+        assert_eq!(parse_args(&args(&["a.toml", "b.toml"])), None);
+    }
+
+    #[test]
+    fn test_write_parquet() {
+        use parquet::file::reader::{FileReader, SerializedFileReader};
+        use parquet::record::RowAccessor;
+        let range = TimeRange::default().set_end(10.0);
+        let registry = ScenarioRegistry::builtin();
+        let scenario = registry.get("servo").unwrap();
+        let result = scenario.build().run(range);
+        let path = std::env::temp_dir().join("cb_sim_test_write_parquet.parquet");
+        let file = path.to_string_lossy().into_owned();
+        write_parquet(&result, &file).unwrap();
+        let reader = SerializedFileReader::new(std::fs::File::open(&path).unwrap()).unwrap();
+        let metadata = reader.metadata();
+        assert_eq!(
+            metadata.file_metadata().num_rows(),
+            result.time.len() as i64
+        );
+        let schema = metadata.file_metadata().schema_descr();
+        assert_eq!(schema.num_columns(), result.traces.len() + 1);
+        assert_eq!(
+            schema.column(1).name(),
+            format!("{} [{}]", result.traces[0].name, result.traces[0].meta.unit)
+        );
+        let last = reader.get_row_iter(None).unwrap().last().unwrap().unwrap();
+        assert_eq!(
+            last.get_double(1).unwrap(),
+            *result.traces[0].values.last().unwrap()
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_manifest_path() {
+        assert_eq!(manifest_path("out/servo.csv"), "out/servo.manifest.json");
+        assert_eq!(manifest_path("servo"), "servo.manifest.json");
+    }
+}
@@ -0,0 +1,7 @@
+//! # Code generation
+//!
+//! Emits source code equivalent to configured elements, so parameters validated
+//! in simulation can be transferred verbatim into target projects.
+
+pub mod rust;
+pub mod structured_text;
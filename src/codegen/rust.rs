@@ -0,0 +1,386 @@
+//! # Dependency-free `no_std` Rust export
+//!
+//! Generates a standalone Rust module implementing a chain of fixed-point
+//! (`i32`) elements with baked-in coefficients. The generated code uses
+//! `core` only and reproduces the `transfer_td` sequence of the configured
+//! elements and their `Solver`, so firmware does not need this crate as a
+//! dependency.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use cb_simulation_util::codegen::rust::RustChain;
+//! use cb_simulation_util::plant::pt0::PT0;
+//! use cb_simulation_util::plant::pt1::PT1;
+//!
+//! fn main() {
+//!     let code = RustChain::new("Filter")
+//!         .push(&PT1::<i32>::default().set_t1_time_or_default(4.0))
+//!         .push(&PT0::<i32>::default().set_t0_time_or_default(2.0))
+//!         .generate();
+//!     assert!(code.contains("pub struct Filter {"));
+//!     assert!(code.contains("pub fn step(&mut self, u: i32) -> i32 {"));
+//! }
+//! ```
+
+use core::fmt::Write;
+use std::format;
+use std::string::String;
+use std::vec::Vec;
+
+use crate::plant::TypeIdentifier;
+use crate::plant::pt0::PT0;
+use crate::plant::pt1::PT1;
+use crate::plant::pt2::PT2;
+use crate::plant::solver::Solver;
+
+const FIX_KOMMA_SHIFT_BITS: u8 = 10;
+const FIX_KOMMA_SHIFT: i64 = 1 << FIX_KOMMA_SHIFT_BITS;
+
+/// Generated code fragments of one element in the chain
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct RustStage {
+    /// Short type name of the element, used as comment
+    pub name: &'static str,
+    /// Struct fields holding the state, one per line
+    pub fields: Vec<(String, String)>,
+    /// Statements transforming `x` into the new `x`
+    pub step: String,
+}
+
+pub trait ToRustStage {
+    /// Code fragments for stage number `index`, field names must be prefixed with `s{index}_`
+    fn to_rust_stage(&self, index: usize) -> RustStage;
+}
+
+/// A chain of elements exported as one struct with a `step` function
+#[derive(Debug, Clone, PartialEq)]
+pub struct RustChain {
+    pub name: String,
+    pub stages: Vec<RustStage>,
+}
+
+impl RustChain {
+    pub fn new(name: &str) -> Self {
+        RustChain {
+            name: String::from(name),
+            stages: Vec::new(),
+        }
+    }
+
+    pub fn push<E: ToRustStage>(mut self, element: &E) -> Self {
+        let index = self.stages.len();
+        self.stages.push(element.to_rust_stage(index));
+        self
+    }
+
+    pub fn generate(&self) -> String {
+        let mut code = String::new();
+        let _ = writeln!(
+            code,
+            "// Generated by {} {} - do not edit.\n// Depends on `core` only, usable in `no_std` firmware.\n",
+            env!("CARGO_PKG_NAME"),
+            env!("CARGO_PKG_VERSION")
+        );
+        let _ = writeln!(code, "#[derive(Debug, Clone, Copy, PartialEq)]");
+        let _ = writeln!(code, "pub struct {} {{", self.name);
+        for stage in &self.stages {
+            for (field, (ty, _)) in stage.fields.iter().map(|f| (&f.0, split_type(&f.1))) {
+                let _ = writeln!(code, "    {}: {},", field, ty);
+            }
+        }
+        let _ = writeln!(code, "}}\n");
+        let _ = writeln!(code, "impl {} {{", self.name);
+        let _ = writeln!(code, "    pub const fn new() -> Self {{");
+        let _ = writeln!(code, "        {} {{", self.name);
+        for stage in &self.stages {
+            for (field, (_, init)) in stage.fields.iter().map(|f| (&f.0, split_type(&f.1))) {
+                let _ = writeln!(code, "            {}: {},", field, init);
+            }
+        }
+        let _ = writeln!(code, "        }}\n    }}\n");
+        let _ = writeln!(code, "    pub fn step(&mut self, u: i32) -> i32 {{");
+        let _ = writeln!(code, "        let x = u;");
+        for (i, stage) in self.stages.iter().enumerate() {
+            let _ = writeln!(code, "        // stage {}: {}", i, stage.name);
+            for line in stage.step.lines() {
+                let _ = writeln!(code, "        {}", line);
+            }
+        }
+        let _ = writeln!(code, "        x\n    }}\n}}");
+        code
+    }
+}
+
+/// Field declarations are given as `type = initializer`
+fn split_type(decl: &str) -> (&str, &str) {
+    match decl.split_once(" = ") {
+        Some((ty, init)) => (ty, init),
+        None => (decl, "0"),
+    }
+}
+
+impl ToRustStage for PT0<i32> {
+    fn to_rust_stage(&self, index: usize) -> RustStage {
+        let length = (self.t0_time / self.sample_time) as usize;
+        RustStage {
+            name: self.short_type_name(),
+            fields: std::vec![(
+                format!("s{}_buffer", index),
+                format!("[i32; {}] = [0; {}]", length + 1, length + 1)
+            )],
+            step: format!(
+                "self.s{i}_buffer.copy_within(1.., 0);\n\
+                 self.s{i}_buffer[{len}] = x * {kp};\n\
+                 let x = self.s{i}_buffer[0] >> {bits};\n",
+                i = index,
+                len = length,
+                kp = self.kp,
+                bits = FIX_KOMMA_SHIFT_BITS
+            ),
+        }
+    }
+}
+
+impl ToRustStage for PT1<i32> {
+    fn to_rust_stage(&self, index: usize) -> RustStage {
+        if self.solver == Solver::Trapezoidal {
+            return RustStage {
+                name: self.short_type_name(),
+                fields: std::vec![
+                    (format!("s{}_previous_output", index), String::from("i32")),
+                    (format!("s{}_previous_input", index), String::from("i32")),
+                ],
+                step: format!(
+                    "let target = (x as i64 + self.s{i}_previous_input as i64) * {kp} / 2;\n\
+                     self.s{i}_previous_input = x;\n\
+                     let out = self.s{i}_previous_output as i64\n    \
+                     + (({alpha} * (target - self.s{i}_previous_output as i64)) >> {bits});\n\
+                     self.s{i}_previous_output = out as i32;\n\
+                     let x = self.s{i}_previous_output >> {bits};\n",
+                    i = index,
+                    alpha = self.alpha(),
+                    kp = self.kp,
+                    bits = FIX_KOMMA_SHIFT_BITS
+                ),
+            };
+        }
+        RustStage {
+            name: self.short_type_name(),
+            fields: std::vec![(format!("s{}_previous_output", index), String::from("i32"))],
+            step: format!(
+                "let out = (self.s{i}_previous_output + {alpha} * (x * {kp} - self.s{i}_previous_output)) >> {bits};\n\
+                 self.s{i}_previous_output = out;\n\
+                 let x = out >> {bits};\n",
+                i = index,
+                alpha = self.alpha(),
+                kp = self.kp,
+                bits = FIX_KOMMA_SHIFT_BITS
+            ),
+        }
+    }
+}
+
+impl ToRustStage for PT2<i32> {
+    fn to_rust_stage(&self, index: usize) -> RustStage {
+        let mut fields = std::vec![
+            (format!("s{}_previous_output", index), String::from("i32")),
+            (
+                format!("s{}_previous_diff_output", index),
+                String::from("i32")
+            ),
+        ];
+        if self.solver != Solver::EulerForward {
+            let [[a11, a12], [a21, a22]] = self.coefficients().fixed_ad;
+            let [b1, b2] = self.coefficients().fixed_bd;
+            let input = if self.solver == Solver::Trapezoidal {
+                fields.push((format!("s{}_previous_input", index), String::from("i32")));
+                format!(
+                    "let u: i64 = {kp} * (x as i64 + self.s{i}_previous_input as i64) / 2;\n\
+                     self.s{i}_previous_input = x;\n",
+                    i = index,
+                    kp = self.kp
+                )
+            } else {
+                format!("let u: i64 = {} * x as i64;\n", self.kp)
+            };
+            return RustStage {
+                name: self.short_type_name(),
+                fields,
+                step: format!(
+                    "{input}let x0 = self.s{i}_previous_output as i64;\n\
+                     let x1 = self.s{i}_previous_diff_output as i64;\n\
+                     self.s{i}_previous_output = (({a11} * x0 + {a12} * x1 + {b1} * u) >> {bits})\n    \
+                     .clamp(i32::MIN as i64, i32::MAX as i64) as i32;\n\
+                     self.s{i}_previous_diff_output = (({a21} * x0 + {a22} * x1 + {b2} * u) >> {bits})\n    \
+                     .clamp(i32::MIN as i64, i32::MAX as i64) as i32;\n\
+                     let x = self.s{i}_previous_output >> {bits};\n",
+                    input = input,
+                    i = index,
+                    a11 = a11,
+                    a12 = a12,
+                    a21 = a21,
+                    a22 = a22,
+                    b1 = b1,
+                    b2 = b2,
+                    bits = FIX_KOMMA_SHIFT_BITS
+                ),
+            };
+        }
+        let omega: i64 = (self.omega * (FIX_KOMMA_SHIFT as f64)) as i64;
+        let omega_squared = omega * omega / FIX_KOMMA_SHIFT;
+        let damping: i64 = (self.damping * (FIX_KOMMA_SHIFT as f64)) as i64;
+        RustStage {
+            name: self.short_type_name(),
+            fields,
+            step: format!(
+                "let diff_output: i64 = self.s{i}_previous_diff_output as i64\n    \
+                 + {ts} * (-2 * {d} * {w} / {f} * self.s{i}_previous_diff_output as i64 / {f}\n        \
+                 - {w2} * self.s{i}_previous_output as i64\n        \
+                 + {kp} * x as i64 * {w2} / {f});\n\
+                 let output: i64 = self.s{i}_previous_output as i64 + {ts} * {w} * self.s{i}_previous_diff_output as i64;\n\
+                 self.s{i}_previous_diff_output = diff_output as i32;\n\
+                 self.s{i}_previous_output = output as i32;\n\
+                 let x = self.s{i}_previous_output >> {bits};\n",
+                i = index,
+                ts = self.sample_time as i64,
+                d = damping,
+                w = omega,
+                w2 = omega_squared,
+                kp = self.kp as i64,
+                f = FIX_KOMMA_SHIFT,
+                bits = FIX_KOMMA_SHIFT_BITS
+            ),
+        }
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_RustChain_fields_and_init() {
+        let code = RustChain::new("Chain")
+            .push(&PT0::<i32>::default().set_t0_time_or_default(2.0))
+            .push(&PT2::<i32>::default())
+            .generate();
+        assert!(code.contains("    s0_buffer: [i32; 3],\n"));
+        assert!(code.contains("            s0_buffer: [0; 3],\n"));
+        assert!(code.contains("    s1_previous_diff_output: i32,\n"));
+        assert!(code.contains("            s1_previous_output: 0,\n"));
+        assert!(code.contains("        // stage 1: PT2\n"));
+    }
+
+    #[test]
+    fn test_RustChain_PT1_coefficients() {
+        let code = RustChain::new("Chain")
+            .push(&PT1::<i32>::default().set_t1_time_or_default(4.0).set_kp(2))
+            .generate();
+        assert!(code.contains("256 * (x * 2048 - self.s0_previous_output)"));
+    }
+
+    #[test]
+    fn test_RustChain_coefficients_of_solver() {
+        let pt1 = PT1::<i32>::default()
+            .set_sample_time_or_default(2.0)
+            .set_solver(Solver::EulerBackward)
+            .set_t1_time_or_default(1.0);
+        let code = RustChain::new("Chain").push(&pt1).generate();
+        // alpha = 2 / 3 in fixed point
+        assert!(code.contains("682 * (x * 1024 - self.s0_previous_output)"));
+        let code = RustChain::new("Chain")
+            .push(&pt1.set_solver(Solver::Trapezoidal))
+            .generate();
+        assert!(code.contains("    s0_previous_input: i32,\n"));
+        let pt2 = PT2::<i32>::default()
+            .set_sample_time_or_default(0.5)
+            .set_solver(Solver::RungeKutta4);
+        let code = RustChain::new("Chain").push(&pt2).generate();
+        let a12 = pt2.coefficients().fixed_ad[0][1];
+        assert!(code.contains(&format!("* x0 + {} * x1", a12)));
+        assert!(!code.contains("diff_output: i64"));
+    }
+
+    /// The chain compiled into the tests, see `generated`
+    fn generated_chain() -> RustChain {
+        RustChain::new("Chain")
+            .push(&pt1_euler())
+            .push(&pt1_trapezoidal())
+            .push(&pt2_runge_kutta())
+            .push(&pt2_trapezoidal())
+            .push(&PT0::<i32>::default().set_t0_time_or_default(2.0))
+    }
+
+    fn pt1_euler() -> PT1<i32> {
+        PT1::<i32>::default().set_t1_time_or_default(4.0).set_kp(2)
+    }
+
+    fn pt1_trapezoidal() -> PT1<i32> {
+        PT1::<i32>::default()
+            .set_solver(Solver::Trapezoidal)
+            .set_t1_time_or_default(3.0)
+    }
+
+    fn pt2_runge_kutta() -> PT2<i32> {
+        PT2::<i32>::default()
+            .set_sample_time_or_default(0.5)
+            .set_solver(Solver::RungeKutta4)
+            .set_damping_or_default(0.5)
+    }
+
+    fn pt2_trapezoidal() -> PT2<i32> {
+        PT2::<i32>::default()
+            .set_solver(Solver::Trapezoidal)
+            .set_omega_or_default(0.5)
+            .set_damping_or_default(0.7)
+    }
+
+    /// Output of `generated_chain`, compiled as part of the tests
+    #[allow(clippy::all)]
+    mod generated {
+        include!("testdata/chain.rs");
+    }
+
+    #[test]
+    fn test_RustChain_generated_code_is_up_to_date() {
+        let code = generated_chain().generate();
+        // skip the header with the crate version
+        let (_, code) = code.split_once("\n\n").unwrap();
+        let (_, compiled) = include_str!("testdata/chain.rs")
+            .split_once("\n\n")
+            .unwrap();
+        assert_eq!(
+            code, compiled,
+            "replace src/codegen/testdata/chain.rs with the generated code"
+        );
+    }
+
+    #[test]
+    fn test_RustChain_generated_code_matches_elements() {
+        use crate::plant::TransferTimeDomain;
+        let mut elements: [&mut dyn TransferTimeDomain<i32>; 5] = [
+            &mut pt1_euler(),
+            &mut pt1_trapezoidal(),
+            &mut pt2_runge_kutta(),
+            &mut pt2_trapezoidal(),
+            &mut PT0::<i32>::default().set_t0_time_or_default(2.0),
+        ];
+        let mut sut = generated::Chain::new();
+        for k in 0..200 {
+            let u = match k {
+                0..50 => 1000,
+                50..100 => -250,
+                _ => k * 7 - 1000,
+            };
+            let expected = elements.iter_mut().fold(u, |x, e| e.transfer_td(x));
+            assert_eq!(sut.step(u), expected, "sample {}", k);
+            if k == 49 {
+                // the step has passed all stages
+                assert!(expected > 0);
+            }
+        }
+    }
+}
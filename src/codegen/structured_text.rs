@@ -0,0 +1,216 @@
+//! # IEC 61131-3 Structured Text export
+//!
+//! Generates a `FUNCTION_BLOCK` with input `u`, output `y` and the element's
+//! parameters as constants. The recurrence is the same as in `transfer_td`
+//! of the configured `Solver`, so PLC and simulation produce identical
+//! sample sequences.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use cb_simulation_util::codegen::structured_text::StructuredText;
+//! use cb_simulation_util::plant::pt1::PT1;
+//!
+//! fn main() {
+//!     let pt1 = PT1::<f64>::default().set_t1_time_or_default(4.0).set_kp(2.0);
+//!     let st = pt1.to_structured_text("FB_Filter");
+//!     assert!(st.starts_with("FUNCTION_BLOCK FB_Filter\n"));
+//!     assert!(st.contains("ALPHA : REAL := 0.25;"));
+//! }
+//! ```
+
+use core::fmt::Write;
+use std::format;
+use std::string::String;
+
+use crate::plant::pt0::PT0;
+use crate::plant::pt1::PT1;
+use crate::plant::pt2::PT2;
+use crate::plant::solver::Solver;
+
+pub trait StructuredText {
+    /// Function block declaration and body named `name`
+    fn to_structured_text(&self, name: &str) -> String;
+}
+
+/// A REAL literal, IEC 61131-3 requires a decimal point also with exponent
+pub fn real_literal(value: f64) -> String {
+    let s = format!("{:?}", value);
+    match s.find('e') {
+        Some(i) if !s[..i].contains('.') => format!("{}.0E{}", &s[..i], &s[i + 1..]),
+        Some(i) => format!("{}E{}", &s[..i], &s[i + 1..]),
+        None => s,
+    }
+}
+
+/// Common frame of a function block with a single REAL input and output
+pub(crate) fn function_block(
+    name: &str,
+    constants: &[(&str, f64)],
+    vars: &str,
+    body: &str,
+) -> String {
+    let mut st = String::new();
+    let _ = writeln!(st, "FUNCTION_BLOCK {}", name);
+    st.push_str("VAR_INPUT\n    u : REAL;\nEND_VAR\n");
+    st.push_str("VAR_OUTPUT\n    y : REAL;\nEND_VAR\n");
+    st.push_str("VAR CONSTANT\n");
+    for (c, v) in constants {
+        let _ = writeln!(st, "    {} : REAL := {};", c, real_literal(*v));
+    }
+    st.push_str("END_VAR\n");
+    let _ = write!(st, "VAR\n{}END_VAR\n", vars);
+    st.push_str(body);
+    st.push_str("END_FUNCTION_BLOCK\n");
+    st
+}
+
+impl StructuredText for PT0<f64> {
+    fn to_structured_text(&self, name: &str) -> String {
+        let length = (self.t0_time / self.sample_time) as usize;
+        function_block(
+            name,
+            &[("KP", self.kp)],
+            &format!(
+                "    buffered_output : ARRAY[0..{}] OF REAL;\n    i : INT;\n",
+                length
+            ),
+            &format!(
+                "FOR i := 0 TO {} DO\n    buffered_output[i] := buffered_output[i + 1];\nEND_FOR;\n\
+                 buffered_output[{}] := u * KP;\ny := buffered_output[0];\n",
+                length as i64 - 1,
+                length
+            ),
+        )
+    }
+}
+
+impl StructuredText for PT1<f64> {
+    fn to_structured_text(&self, name: &str) -> String {
+        let constants = [("KP", self.kp), ("ALPHA", self.alpha())];
+        if self.solver == Solver::Trapezoidal {
+            return function_block(
+                name,
+                &constants,
+                "    previous_output : REAL := 0.0;\n    previous_input : REAL := 0.0;\n",
+                "y := previous_output + ALPHA * (KP * 0.5 * (u + previous_input) - previous_output);\n\
+                 previous_input := u;\n\
+                 previous_output := y;\n",
+            );
+        }
+        function_block(
+            name,
+            &constants,
+            "    previous_output : REAL := 0.0;\n",
+            "y := previous_output + ALPHA * (KP * u - previous_output);\n\
+             previous_output := y;\n",
+        )
+    }
+}
+
+impl StructuredText for PT2<f64> {
+    fn to_structured_text(&self, name: &str) -> String {
+        if self.solver != Solver::EulerForward {
+            let [[a11, a12], [a21, a22]] = self.coefficients().ad;
+            let [b1, b2] = self.coefficients().bd;
+            let (vars, input) = if self.solver == Solver::Trapezoidal {
+                (
+                    "    previous_input : REAL := 0.0;\n",
+                    "v := KP * 0.5 * (u + previous_input);\nprevious_input := u;\n",
+                )
+            } else {
+                ("", "v := KP * u;\n")
+            };
+            return function_block(
+                name,
+                &[
+                    ("KP", self.kp),
+                    ("A11", a11),
+                    ("A12", a12),
+                    ("A21", a21),
+                    ("A22", a22),
+                    ("B1", b1),
+                    ("B2", b2),
+                ],
+                &format!(
+                    "    previous_output : REAL := 0.0;\n    previous_diff_output : REAL := 0.0;\n{}    v : REAL;\n",
+                    vars
+                ),
+                &format!(
+                    "{}y := A11 * previous_output + A12 * previous_diff_output + B1 * v;\n\
+                     previous_diff_output := A21 * previous_output + A22 * previous_diff_output + B2 * v;\n\
+                     previous_output := y;\n",
+                    input
+                ),
+            );
+        }
+        function_block(
+            name,
+            &[
+                ("KP", self.kp),
+                ("TS", self.sample_time),
+                ("OMEGA", self.omega),
+                ("DAMPING", self.damping),
+            ],
+            "    previous_output : REAL := 0.0;\n    previous_diff_output : REAL := 0.0;\n    diff_output : REAL;\n",
+            "diff_output := previous_diff_output + TS * (-2.0 * DAMPING * OMEGA * previous_diff_output\n    \
+             - OMEGA * OMEGA * previous_output + KP * OMEGA * OMEGA * u);\n\
+             y := previous_output + TS * OMEGA * previous_diff_output;\n\
+             previous_diff_output := diff_output;\n\
+             previous_output := y;\n",
+        )
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_real_literal() {
+        assert_eq!(real_literal(1.0), "1.0");
+        assert_eq!(real_literal(-0.25), "-0.25");
+        assert_eq!(real_literal(1e-7), "1.0E-7");
+        assert_eq!(real_literal(1.5e20), "1.5E20");
+    }
+
+    #[test]
+    fn test_PT0_structured_text() {
+        let st = PT0::<f64>::default()
+            .set_t0_time_or_default(3.0)
+            .to_structured_text("FB_Delay");
+        assert!(st.contains("buffered_output : ARRAY[0..3] OF REAL;"));
+        assert!(st.contains("FOR i := 0 TO 2 DO"));
+        assert!(st.ends_with("y := buffered_output[0];\nEND_FUNCTION_BLOCK\n"));
+    }
+
+    #[test]
+    fn test_PT2_structured_text() {
+        let st = PT2::<f64>::default().to_structured_text("FB_PT2");
+        assert!(st.contains("DAMPING : REAL := 1.0;"));
+        assert!(st.contains("previous_diff_output := diff_output;"));
+    }
+
+    #[test]
+    fn test_PT1_PT2_structured_text_of_solver() {
+        let st = PT1::<f64>::default()
+            .set_solver(Solver::EulerBackward)
+            .to_structured_text("FB_PT1");
+        assert!(st.contains("ALPHA : REAL := 0.5;"));
+        let st = PT1::<f64>::default()
+            .set_solver(Solver::Trapezoidal)
+            .to_structured_text("FB_PT1");
+        assert!(st.contains("ALPHA : REAL := 0.6666666666666666;"));
+        assert!(st.contains("KP * 0.5 * (u + previous_input)"));
+        let pt2 = PT2::<f64>::default()
+            .set_sample_time_or_default(0.5)
+            .set_solver(Solver::Trapezoidal);
+        let st = pt2.to_structured_text("FB_PT2");
+        let a12 = real_literal(pt2.coefficients().ad[0][1]);
+        assert!(st.contains(&format!("A12 : REAL := {};", a12)));
+        assert!(st.contains("previous_input := u;"));
+        assert!(!st.contains("OMEGA"));
+    }
+}
@@ -0,0 +1,70 @@
+// Generated by cb-simulation-util 0.4.0 - do not edit.
+// Depends on `core` only, usable in `no_std` firmware.
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Chain {
+    s0_previous_output: i32,
+    s1_previous_output: i32,
+    s1_previous_input: i32,
+    s2_previous_output: i32,
+    s2_previous_diff_output: i32,
+    s3_previous_output: i32,
+    s3_previous_diff_output: i32,
+    s3_previous_input: i32,
+    s4_buffer: [i32; 3],
+}
+
+impl Chain {
+    pub const fn new() -> Self {
+        Chain {
+            s0_previous_output: 0,
+            s1_previous_output: 0,
+            s1_previous_input: 0,
+            s2_previous_output: 0,
+            s2_previous_diff_output: 0,
+            s3_previous_output: 0,
+            s3_previous_diff_output: 0,
+            s3_previous_input: 0,
+            s4_buffer: [0; 3],
+        }
+    }
+
+    pub fn step(&mut self, u: i32) -> i32 {
+        let x = u;
+        // stage 0: PT1
+        let out = (self.s0_previous_output + 256 * (x * 2048 - self.s0_previous_output)) >> 10;
+        self.s0_previous_output = out;
+        let x = out >> 10;
+        // stage 1: PT1
+        let target = (x as i64 + self.s1_previous_input as i64) * 1024 / 2;
+        self.s1_previous_input = x;
+        let out = self.s1_previous_output as i64
+            + ((292 * (target - self.s1_previous_output as i64)) >> 10);
+        self.s1_previous_output = out as i32;
+        let x = self.s1_previous_output >> 10;
+        // stage 2: PT2
+        let u: i64 = 1024 * x as i64;
+        let x0 = self.s2_previous_output as i64;
+        let x1 = self.s2_previous_diff_output as i64;
+        self.s2_previous_output = ((917 * x0 + 387 * x1 + 107 * u) >> 10)
+            .clamp(i32::MIN as i64, i32::MAX as i64) as i32;
+        self.s2_previous_diff_output = ((-387 * x0 + 531 * x1 + 387 * u) >> 10)
+            .clamp(i32::MIN as i64, i32::MAX as i64) as i32;
+        let x = self.s2_previous_output >> 10;
+        // stage 3: PT2
+        let u: i64 = 1024 * (x as i64 + self.s3_previous_input as i64) / 2;
+        self.s3_previous_input = x;
+        let x0 = self.s3_previous_output as i64;
+        let x1 = self.s3_previous_diff_output as i64;
+        self.s3_previous_output = ((978 * x0 + 371 * x1 + 46 * u) >> 10)
+            .clamp(i32::MIN as i64, i32::MAX as i64) as i32;
+        self.s3_previous_diff_output = ((-185 * x0 + 459 * x1 + 185 * u) >> 10)
+            .clamp(i32::MIN as i64, i32::MAX as i64) as i32;
+        let x = self.s3_previous_output >> 10;
+        // stage 4: PT0
+        self.s4_buffer.copy_within(1.., 0);
+        self.s4_buffer[2] = x * 1024;
+        let x = self.s4_buffer[0] >> 10;
+        x
+    }
+}
@@ -0,0 +1,551 @@
+//! # Block diagrams from configuration files
+//!
+//! A `DiagramConfig` describes an experiment declaratively, in TOML or JSON:
+//! named time signals in the format of their `Display`, see
+//! `signal::factory`, named plant blocks with their parameters, see
+//! `plant::registry`, weighted connections and the time range.
+//!
+//! ```toml
+//! [time]
+//! unit = "s"
+//! end = 20.0
+//! sampling_interval = 0.1
+//!
+//! [signals]
+//! setpoint = "Step(step_time=1, pre=0, post=1)"
+//!
+//! [[blocks]]
+//! name = "controller"
+//! type = "Integrator"
+//! parameters = { sample_time = 0.1, kp = 0.5 }
+//!
+//! [[blocks]]
+//! name = "plant"
+//! type = "PT1"
+//! parameters = { sample_time = 0.1, t1_time = 2.0 }
+//!
+//! [[connections]]
+//! from = "setpoint"
+//! to = "controller"
+//!
+//! [[connections]]
+//! from = "plant"
+//! to = "controller"
+//! gain = -1.0
+//!
+//! [[connections]]
+//! from = "controller"
+//! to = "plant"
+//! ```
+//!
+//! The input of a block is the weighted sum of its connections. The blocks
+//! are evaluated in the listed order: a connection from a block listed
+//! before reads its output of the same sample, one from the block itself or
+//! a block listed after reads the output of the previous sample, so
+//! feedback loops need no special treatment. Every signal and block output
+//! is recorded as a trace of its name.
+//!
+//! A block with `enabled = false` is bypassed, it passes its input through.
+//! `Diagram::set_block_enabled` toggles a block of the built diagram, so a
+//! variant with and without e.g. a filter needs no second configuration.
+//!
+//! `DiagramConfig::from_json` is always available, `from_toml` with the
+//! `toml` feature.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use cb_simulation_util::config::DiagramConfig;
+//!
+//! fn main() {
+//!     let config = DiagramConfig::from_json(r#"{
+//!         "time": {"unit": "s", "end": 30.0, "sampling_interval": 0.5},
+//!         "signals": {"input": "Step(step_time=1, pre=0, post=2)"},
+//!         "blocks": [{"name": "lag", "type": "PT1", "parameters": {"sample_time": 0.5, "t1_time": 2.0}}],
+//!         "connections": [{"from": "input", "to": "lag"}]
+//!     }"#).unwrap();
+//!     let mut diagram = config.build_builtin().unwrap();
+//!     let result = diagram.run();
+//!     let lag = result.trace("lag").unwrap();
+//!     assert!((lag.values[lag.values.len() - 1] - 2.0).abs() < 1e-3);
+//! }
+//! ```
+
+use core::fmt::{self, Display};
+use ndarray::Array1;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::format;
+use std::string::{String, ToString};
+use std::vec;
+use std::vec::Vec;
+
+use crate::plant::BoxedTransferTimeDomain;
+use crate::plant::registry::{BuildError, PlantRegistry};
+use crate::scenario::Diagram;
+use crate::signal::factory::{SignalRegistry, SpecError};
+use crate::signal::{BoxedTimeSignal, TimeRange};
+use crate::sim::{SimResult, Trace, TraceMetadata};
+
+/// Time units of a `TimeRange`, see `signal::seconds_per_unit`
+const TIME_UNITS: [&str; 7] = ["us", "µs", "ms", "s", "sec", "min", "h"];
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigError {
+    /// The text is no valid TOML or JSON of a `DiagramConfig`
+    Format(String),
+    /// A signal specification could not be parsed
+    Signal { name: String, error: SpecError },
+    /// A block could not be built from its type and parameters
+    Block { name: String, error: BuildError },
+    /// Two signals or blocks share the name
+    DuplicateName(String),
+    /// A connection refers to no signal or block of the name
+    UnknownName(String),
+    /// A connection ends at a signal instead of a block
+    NotABlock(String),
+    /// The time range is inconsistent
+    TimeRange(&'static str),
+}
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Format(reason) => write!(f, "Invalid configuration: {}", reason),
+            ConfigError::Signal { name, error } => write!(f, "Signal {}: {}", name, error),
+            ConfigError::Block { name, error } => write!(f, "Block {}: {}", name, error),
+            ConfigError::DuplicateName(name) => write!(f, "Name {} used twice", name),
+            ConfigError::UnknownName(name) => write!(f, "No signal or block named {}", name),
+            ConfigError::NotABlock(name) => write!(f, "Connection to signal {}", name),
+            ConfigError::TimeRange(reason) => write!(f, "Invalid time range: {}", reason),
+        }
+    }
+}
+
+/// Time range, missing values are those of `TimeRange::default`
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TimeConfig {
+    pub unit: Option<String>,
+    pub start: Option<f64>,
+    pub end: Option<f64>,
+    pub sampling_interval: Option<f64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BlockConfig {
+    pub name: String,
+    /// Name of the element in the `PlantRegistry`
+    #[serde(rename = "type")]
+    pub element: String,
+    #[serde(default)]
+    pub parameters: BTreeMap<String, f64>,
+    /// A disabled block passes its input through
+    #[serde(default = "enabled")]
+    pub enabled: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ConnectionConfig {
+    /// Name of a signal or block
+    pub from: String,
+    /// Name of a block
+    pub to: String,
+    #[serde(default = "unit_gain")]
+    pub gain: f64,
+}
+
+fn unit_gain() -> f64 {
+    1.0
+}
+
+fn enabled() -> bool {
+    true
+}
+
+/// A block diagram experiment, see the module documentation
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DiagramConfig {
+    #[serde(default)]
+    pub time: TimeConfig,
+    /// Specifications of the time signals by name
+    #[serde(default)]
+    pub signals: BTreeMap<String, String>,
+    #[serde(default)]
+    pub blocks: Vec<BlockConfig>,
+    #[serde(default)]
+    pub connections: Vec<ConnectionConfig>,
+}
+
+impl DiagramConfig {
+    pub fn from_json(text: &str) -> Result<Self, ConfigError> {
+        serde_json::from_str(text).map_err(|e| ConfigError::Format(e.to_string()))
+    }
+
+    #[cfg(feature = "toml")]
+    pub fn from_toml(text: &str) -> Result<Self, ConfigError> {
+        toml::from_str(text).map_err(|e| ConfigError::Format(e.to_string()))
+    }
+
+    /// The configured time range
+    pub fn time_range(&self) -> Result<TimeRange, ConfigError> {
+        let default = TimeRange::default();
+        let unit = match &self.time.unit {
+            Some(unit) => *TIME_UNITS
+                .iter()
+                .find(|u| **u == unit.as_str())
+                .ok_or(ConfigError::TimeRange("unknown time unit"))?,
+            None => default.unit_of_measurement,
+        };
+        let start = self.time.start.unwrap_or(default.start);
+        let end = self.time.end.unwrap_or(default.end);
+        let interval = self
+            .time
+            .sampling_interval
+            .unwrap_or(default.sampling_interval);
+        if !(start.is_finite() && end.is_finite() && start < end) {
+            return Err(ConfigError::TimeRange("start must be less than end"));
+        }
+        if !(interval > 0.0 && interval <= end - start) {
+            return Err(ConfigError::TimeRange(
+                "sampling interval must be > 0 and fit into the range",
+            ));
+        }
+        let range = default.set_unit_of_measurement(unit);
+        // the setters check start <= end on each call
+        let range = if start > range.end {
+            range.set_end(end).set_start(start)
+        } else {
+            range.set_start(start).set_end(end)
+        };
+        Ok(range.set_sampling_interval(interval))
+    }
+
+    /// Build the diagram with the elements of `plants` and the signals of `signals`
+    pub fn build(
+        &self,
+        plants: &PlantRegistry,
+        signals: &SignalRegistry,
+    ) -> Result<ConfiguredDiagram, ConfigError> {
+        let range = self.time_range()?;
+        let mut names: Vec<&str> = self.signals.keys().map(String::as_str).collect();
+        for block in &self.blocks {
+            if names.contains(&block.name.as_str()) {
+                return Err(ConfigError::DuplicateName(block.name.clone()));
+            }
+            names.push(&block.name);
+        }
+        let mut built_signals = Vec::with_capacity(self.signals.len());
+        for (name, spec) in &self.signals {
+            let signal = signals.parse(spec).map_err(|error| ConfigError::Signal {
+                name: name.clone(),
+                error,
+            })?;
+            built_signals.push((name.clone(), signal));
+        }
+        let mut blocks = Vec::with_capacity(self.blocks.len());
+        for block in &self.blocks {
+            let parameters: Vec<(&str, f64)> = block
+                .parameters
+                .iter()
+                .map(|(key, value)| (key.as_str(), *value))
+                .collect();
+            let element =
+                plants
+                    .build(&block.element, &parameters)
+                    .map_err(|error| ConfigError::Block {
+                        name: block.name.clone(),
+                        error,
+                    })?;
+            blocks.push((block.name.clone(), element));
+        }
+        let mut inputs = vec![Vec::new(); blocks.len()];
+        for connection in &self.connections {
+            let source = match built_signals
+                .iter()
+                .position(|(n, _)| *n == connection.from)
+            {
+                Some(i) => Source::Signal(i),
+                None => blocks
+                    .iter()
+                    .position(|(n, _)| *n == connection.from)
+                    .map(Source::Block)
+                    .ok_or_else(|| ConfigError::UnknownName(connection.from.clone()))?,
+            };
+            let target = blocks
+                .iter()
+                .position(|(n, _)| *n == connection.to)
+                .ok_or_else(|| {
+                    if built_signals.iter().any(|(n, _)| *n == connection.to) {
+                        ConfigError::NotABlock(connection.to.clone())
+                    } else {
+                        ConfigError::UnknownName(connection.to.clone())
+                    }
+                })?;
+            inputs[target].push((source, connection.gain));
+        }
+        Ok(ConfiguredDiagram {
+            range,
+            signals: built_signals,
+            blocks,
+            inputs,
+            enabled: self.blocks.iter().map(|block| block.enabled).collect(),
+        })
+    }
+
+    /// Build the diagram with the builtin elements and signals
+    pub fn build_builtin(&self) -> Result<ConfiguredDiagram, ConfigError> {
+        self.build(&PlantRegistry::builtin(), &SignalRegistry::builtin())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Source {
+    Signal(usize),
+    Block(usize),
+}
+
+/// Runnable diagram of a `DiagramConfig`
+#[derive(Clone)]
+pub struct ConfiguredDiagram {
+    pub range: TimeRange,
+    signals: Vec<(String, BoxedTimeSignal<f64>)>,
+    blocks: Vec<(String, BoxedTransferTimeDomain<f64>)>,
+    /// Weighted sources of each block input
+    inputs: Vec<Vec<(Source, f64)>>,
+    /// Per block, a disabled block passes its input through
+    enabled: Vec<bool>,
+}
+
+impl ConfiguredDiagram {
+    /// Run over `range`, recording each signal and block output
+    pub fn run(&mut self) -> SimResult {
+        let time: Array1<f64> = self.range.collect();
+        let n = time.len();
+        let mut signal_values = vec![Array1::zeros(n); self.signals.len()];
+        let mut block_values = vec![Array1::zeros(n); self.blocks.len()];
+        // latest output of each block
+        let mut outputs = vec![0.0; self.blocks.len()];
+        for (k, t) in time.iter().enumerate() {
+            for ((_, signal), values) in self.signals.iter().zip(signal_values.iter_mut()) {
+                values[k] = signal.time_to_signal(*t);
+            }
+            for (b, (_, block)) in self.blocks.iter_mut().enumerate() {
+                let input: f64 = self.inputs[b]
+                    .iter()
+                    .map(|(source, gain)| {
+                        gain * match *source {
+                            Source::Signal(i) => signal_values[i][k],
+                            Source::Block(j) => outputs[j],
+                        }
+                    })
+                    .sum();
+                outputs[b] = if self.enabled[b] {
+                    block.transfer_td(input)
+                } else {
+                    input
+                };
+                block_values[b][k] = outputs[b];
+            }
+        }
+        let meta = |unit, source| TraceMetadata {
+            unit,
+            source,
+            sample_interval: self.range.sampling_interval,
+        };
+        let mut traces = Vec::with_capacity(self.signals.len() + self.blocks.len());
+        for ((name, signal), values) in self.signals.iter().zip(signal_values) {
+            traces.push(Trace {
+                name: name.clone(),
+                meta: meta("1", signal.short_type_name()),
+                values,
+            });
+        }
+        for (((name, block), values), enabled) in
+            self.blocks.iter().zip(block_values).zip(&self.enabled)
+        {
+            let unit = if *enabled {
+                block.output_unit("1")
+            } else {
+                "1"
+            };
+            traces.push(Trace {
+                name: name.clone(),
+                meta: meta(unit, block.short_type_name()),
+                values,
+            });
+        }
+        SimResult {
+            time,
+            time_unit: self.range.unit_of_measurement,
+            traces,
+        }
+    }
+}
+
+impl Diagram for ConfiguredDiagram {
+    fn run(&mut self, range: TimeRange) -> SimResult {
+        self.range = range;
+        ConfiguredDiagram::run(self)
+    }
+
+    fn parameters(&self) -> Vec<(String, String)> {
+        let signals = self
+            .signals
+            .iter()
+            .map(|(name, signal)| (name.clone(), format!("{}", signal)));
+        let blocks = self
+            .blocks
+            .iter()
+            .zip(&self.enabled)
+            .map(|((name, block), enabled)| {
+                let bypassed = if *enabled { "" } else { " [bypassed]" };
+                (name.clone(), format!("{}{}", block, bypassed))
+            });
+        signals.chain(blocks).collect()
+    }
+
+    fn set_block_enabled(&mut self, name: &str, enabled: bool) -> Result<(), &'static str> {
+        let index = self
+            .blocks
+            .iter()
+            .position(|(n, _)| n == name)
+            .ok_or("Unknown block")?;
+        self.enabled[index] = enabled;
+        Ok(())
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    const LOOP: &str = r#"{
+        "time": {"unit": "s", "end": 60.0, "sampling_interval": 0.1},
+        "signals": {"setpoint": "Step(step_time=1, pre=0, post=1)"},
+        "blocks": [
+            {"name": "controller", "type": "Integrator", "parameters": {"sample_time": 0.1, "kp": 0.5}},
+            {"name": "plant", "type": "PT1", "parameters": {"sample_time": 0.1, "t1_time": 2.0}}
+        ],
+        "connections": [
+            {"from": "setpoint", "to": "controller"},
+            {"from": "plant", "to": "controller", "gain": -1.0},
+            {"from": "controller", "to": "plant"}
+        ]
+    }"#;
+
+    #[test]
+    fn test_DiagramConfig_closed_loop() {
+        let config = DiagramConfig::from_json(LOOP).unwrap();
+        let mut sut = config.build_builtin().unwrap();
+        let result = sut.run();
+        let names: Vec<&str> = result.traces.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, vec!["setpoint", "controller", "plant"]);
+        let plant = &result.trace("plant").unwrap().values;
+        // integral control: no steady state error
+        assert!((plant[plant.len() - 1] - 1.0).abs() < 1e-3);
+        assert_eq!(result.time_unit, "s");
+        assert_eq!(sut.parameters()[2].0, "plant");
+        let parameters = Diagram::parameters(&sut);
+        assert_eq!(parameters[0].1, "Step(step_time=1, pre=0, post=1)");
+    }
+
+    #[test]
+    fn test_ConfiguredDiagram_bypass() {
+        let config = DiagramConfig::from_json(
+            r#"{
+            "time": {"unit": "s", "end": 1.0, "sampling_interval": 0.1},
+            "signals": {"input": "Step(step_time=0, pre=0, post=2)"},
+            "blocks": [
+                {"name": "lag", "type": "PT1", "parameters": {"sample_time": 0.1, "t1_time": 2.0}},
+                {"name": "gain", "type": "PT0", "parameters": {"sample_time": 0.1, "kp": 3.0}, "enabled": false}
+            ],
+            "connections": [{"from": "input", "to": "lag"}, {"from": "lag", "to": "gain"}]
+        }"#,
+        )
+        .unwrap();
+        let mut sut = config.build_builtin().unwrap();
+        let result = sut.run();
+        assert_eq!(
+            result.trace("gain").unwrap().values,
+            result.trace("lag").unwrap().values
+        );
+        assert!(Diagram::parameters(&sut)[2].1.ends_with("[bypassed]"));
+        sut.set_block_enabled("gain", true).unwrap();
+        sut.set_block_enabled("lag", false).unwrap();
+        let result = sut.run();
+        let gain = &result.trace("gain").unwrap().values;
+        assert_eq!(gain[gain.len() - 1], 6.0);
+        assert_eq!(sut.set_block_enabled("input", false), Err("Unknown block"));
+    }
+
+    #[test]
+    fn test_DiagramConfig_errors() {
+        let build = |text: &str| DiagramConfig::from_json(text).and_then(|c| c.build_builtin());
+        assert!(matches!(
+            build(r#"{"blocks": [{"name": "a", "type": "PT1", "gain": 1}]}"#),
+            Err(ConfigError::Format(_))
+        ));
+        assert!(matches!(
+            build(r#"{"signals": {"u": "Step(post=x)"}}"#),
+            Err(ConfigError::Signal { .. })
+        ));
+        assert!(matches!(
+            build(r#"{"blocks": [{"name": "a", "type": "PT1", "parameters": {"t2_time": 1}}]}"#),
+            Err(ConfigError::Block { .. })
+        ));
+        assert_eq!(
+            build(r#"{"signals": {"a": "Step()"}, "blocks": [{"name": "a", "type": "PT0"}]}"#)
+                .err(),
+            Some(ConfigError::DuplicateName(String::from("a")))
+        );
+        assert_eq!(
+            build(r#"{"blocks": [{"name": "a", "type": "PT0"}], "connections": [{"from": "b", "to": "a"}]}"#)
+                .err(),
+            Some(ConfigError::UnknownName(String::from("b")))
+        );
+        assert_eq!(
+            build(r#"{"signals": {"u": "Step()"}, "connections": [{"from": "u", "to": "u"}]}"#)
+                .err(),
+            Some(ConfigError::NotABlock(String::from("u")))
+        );
+        assert!(matches!(
+            build(r#"{"time": {"start": 5, "end": 1}}"#),
+            Err(ConfigError::TimeRange(_))
+        ));
+        assert!(matches!(
+            build(r#"{"time": {"unit": "days"}}"#),
+            Err(ConfigError::TimeRange(_))
+        ));
+        // a range beyond the default end
+        let range = DiagramConfig::from_json(r#"{"time": {"start": 200, "end": 300}}"#)
+            .unwrap()
+            .time_range()
+            .unwrap();
+        assert_eq!(
+            (range.start, range.end, range.unit_of_measurement),
+            (200.0, 300.0, "ms")
+        );
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn test_DiagramConfig_toml_matches_json() {
+        let toml = "[time]\nunit = \"s\"\nend = 60.0\nsampling_interval = 0.1\n\n\
+            [signals]\nsetpoint = \"Step(step_time=1, pre=0, post=1)\"\n\n\
+            [[blocks]]\nname = \"controller\"\ntype = \"Integrator\"\nparameters = { sample_time = 0.1, kp = 0.5 }\n\n\
+            [[blocks]]\nname = \"plant\"\ntype = \"PT1\"\nparameters = { sample_time = 0.1, t1_time = 2.0 }\n\n\
+            [[connections]]\nfrom = \"setpoint\"\nto = \"controller\"\n\n\
+            [[connections]]\nfrom = \"plant\"\nto = \"controller\"\ngain = -1.0\n\n\
+            [[connections]]\nfrom = \"controller\"\nto = \"plant\"\n";
+        assert_eq!(
+            DiagramConfig::from_toml(toml).unwrap(),
+            DiagramConfig::from_json(LOOP).unwrap()
+        );
+    }
+}
@@ -0,0 +1,14 @@
+//! # Controllers
+//!
+//! Controller elements for closed-loop simulations. Controllers are transfer
+//! elements like the plants: their input is the control error, their output
+//! the manipulated variable. They can be used with `Simulation::run_closed_loop`
+//! or in the forward path of a `Feedback` block.
+
+pub mod mrac;
+pub mod pi;
+pub mod pole_placement;
+pub mod relay;
+pub mod three_point;
+
+pub use crate::plant::{SampleTime, TransferTimeDomain, TypeIdentifier};
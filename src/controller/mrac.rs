@@ -0,0 +1,330 @@
+//! A model-reference adaptive controller (MRAC) with the MIT rule
+//!
+//! The controller $ u = \theta_{1} r - \theta_{2} y $ adapts its gains so
+//! that the plant output $y$ follows the output $y_{m}$ of a reference model
+//! driven by the reference $r$. The MIT rule descends the gradient of
+//! $ e^{2} / 2 $ with the model error $ e = y - y_{m} $:
+//!
+//! $ \dot{\theta}_{1} = -\gamma e \, \frac{G_{m}}{k_{m}} r $, $ \dot{\theta}_{2} = \gamma e \, \frac{G_{m}}{k_{m}} y $
+//!
+//! where the sensitivities are approximated by filtering $r$ and $y$ with
+//! the reference model $G_{m}$ normalized to unity gain. The plant gain must
+//! be positive. A large adaptation gain $ \gamma $ adapts faster but may
+//! destabilize the loop, the rule has no stability guarantee.
+//!
+//! The reference model is a `PT1` or `PT2`, its sample time is the sample
+//! time of the controller. As `MimoTransferTimeDomain` the inputs are
+//! `[r, y]` and the output `[u]`.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use cb_simulation_util::controller::mrac::{Mrac, ReferenceModel};
+//! use cb_simulation_util::plant::TransferTimeDomain;
+//! use cb_simulation_util::plant::pt1::PT1;
+//!
+//! fn main() {
+//!     let model = PT1::<f64>::default()
+//!         .set_sample_time_or_default(0.1)
+//!         .set_t1_time_or_default(1.0);
+//!     let mut mrac = Mrac::new(ReferenceModel::PT1(model)).set_gamma_or_default(2.0);
+//!     // unknown plant: slower and with twice the gain
+//!     let mut plant = PT1::<f64>::default()
+//!         .set_sample_time_or_default(0.1)
+//!         .set_t1_time_or_default(2.0)
+//!         .set_kp(2.0);
+//!     let mut y = 0.0;
+//!     for k in 0..6000 {
+//!         let r = if (k / 100) % 2 == 0 { 1.0 } else { -1.0 };
+//!         y = plant.transfer_td(mrac.control(r, y));
+//!     }
+//!     assert!((y - mrac.model_output()).abs() < 0.01);
+//! }
+//! ```
+
+use core::fmt::{self, Display};
+use ndarray::{Array1, ArrayView1};
+
+use super::*;
+use crate::plant::MimoTransferTimeDomain;
+use crate::plant::pt1::PT1;
+use crate::plant::pt2::PT2;
+use crate::plant::snapshot::StateAccess;
+
+/// Desired closed loop behavior
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReferenceModel {
+    PT1(PT1<f64>),
+    PT2(PT2<f64>),
+}
+
+impl ReferenceModel {
+    fn sample_time(&self) -> f64 {
+        match self {
+            ReferenceModel::PT1(model) => model.sample_time,
+            ReferenceModel::PT2(model) => model.sample_time,
+        }
+    }
+
+    /// The same dynamics with unity gain, starting at rest
+    fn sensitivity_filter(&self) -> Self {
+        match self {
+            ReferenceModel::PT1(model) => ReferenceModel::PT1(
+                PT1::<f64>::default()
+                    .set_sample_time_or_default(model.sample_time)
+                    .set_solver(model.solver)
+                    .set_t1_time_or_default(model.t1_time),
+            ),
+            ReferenceModel::PT2(model) => ReferenceModel::PT2(
+                PT2::<f64>::default()
+                    .set_sample_time_or_default(model.sample_time)
+                    .set_solver(model.solver)
+                    .set_omega_or_default(model.omega)
+                    .set_damping_or_default(model.damping),
+            ),
+        }
+    }
+
+    fn transfer_td(&mut self, input: f64) -> f64 {
+        match self {
+            ReferenceModel::PT1(model) => model.transfer_td(input),
+            ReferenceModel::PT2(model) => model.transfer_td(input),
+        }
+    }
+}
+
+/// State of a `ReferenceModel`, see the `StateAccess` of its element
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReferenceModelState {
+    PT1(<PT1<f64> as StateAccess>::State),
+    PT2(<PT2<f64> as StateAccess>::State),
+}
+
+impl StateAccess for ReferenceModel {
+    type State = ReferenceModelState;
+
+    fn save_state(&self) -> Self::State {
+        match self {
+            ReferenceModel::PT1(model) => ReferenceModelState::PT1(model.save_state()),
+            ReferenceModel::PT2(model) => ReferenceModelState::PT2(model.save_state()),
+        }
+    }
+
+    /// Ignored for the state of the other element
+    fn restore_state(&mut self, state: &Self::State) {
+        match (self, state) {
+            (ReferenceModel::PT1(model), ReferenceModelState::PT1(state)) => {
+                model.restore_state(state)
+            }
+            (ReferenceModel::PT2(model), ReferenceModelState::PT2(state)) => {
+                model.restore_state(state)
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Display for ReferenceModel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReferenceModel::PT1(model) => write!(f, "{}", model),
+            ReferenceModel::PT2(model) => write!(f, "{}", model),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Mrac {
+    /// Adaptation gain $ \gamma $
+    pub gamma: f64,
+    model: ReferenceModel,
+    reference_filter: ReferenceModel,
+    output_filter: ReferenceModel,
+    /// Filtered reference and output of the previous sample
+    sensitivities: (f64, f64),
+    theta: (f64, f64),
+    model_output: f64,
+}
+
+impl Mrac {
+    /// Controller starting with $ \theta_{1} = 1 $, $ \theta_{2} = 0 $ and `gamma` 1
+    pub fn new(model: ReferenceModel) -> Self {
+        Mrac {
+            gamma: 1.0,
+            reference_filter: model.sensitivity_filter(),
+            output_filter: model.sensitivity_filter(),
+            model,
+            sensitivities: (0.0, 0.0),
+            theta: (1.0, 0.0),
+            model_output: 0.0,
+        }
+    }
+
+    /// Adaptation gain, non-positive values are replaced by 1
+    pub fn set_gamma_or_default(self, gamma: f64) -> Self {
+        let gamma = if gamma > 0.0 { gamma } else { 1.0 };
+        Mrac { gamma, ..self }
+    }
+
+    /// Start the adaptation from the feedforward gain `theta1` and the
+    /// feedback gain `theta2`
+    pub fn set_gains(self, theta1: f64, theta2: f64) -> Self {
+        Mrac {
+            theta: (theta1, theta2),
+            ..self
+        }
+    }
+
+    /// Current gains $ (\theta_{1}, \theta_{2}) $
+    pub fn gains(&self) -> (f64, f64) {
+        self.theta
+    }
+
+    pub fn model(&self) -> &ReferenceModel {
+        &self.model
+    }
+
+    /// Reference model output of the latest sample
+    pub fn model_output(&self) -> f64 {
+        self.model_output
+    }
+
+    /// Manipulated variable for the `reference` and the latest plant `output`
+    ///
+    /// The gains are adapted with the error of `output` against the model
+    /// output of the previous sample, then the model is advanced.
+    pub fn control(&mut self, reference: f64, output: f64) -> f64 {
+        let error = output - self.model_output;
+        let step = self.gamma * error * self.model.sample_time();
+        let (r_filtered, y_filtered) = self.sensitivities;
+        self.theta.0 -= step * r_filtered;
+        self.theta.1 += step * y_filtered;
+        self.sensitivities = (
+            self.reference_filter.transfer_td(reference),
+            self.output_filter.transfer_td(output),
+        );
+        self.model_output = self.model.transfer_td(reference);
+        self.theta.0 * reference - self.theta.1 * output
+    }
+}
+
+impl StateAccess for Mrac {
+    /// Reference model, reference and output filter, their outputs of the
+    /// previous sample, the gains and the model output
+    type State = (
+        ReferenceModelState,
+        ReferenceModelState,
+        ReferenceModelState,
+        (f64, f64),
+        (f64, f64),
+        f64,
+    );
+
+    fn save_state(&self) -> Self::State {
+        (
+            self.model.save_state(),
+            self.reference_filter.save_state(),
+            self.output_filter.save_state(),
+            self.sensitivities,
+            self.theta,
+            self.model_output,
+        )
+    }
+
+    fn restore_state(&mut self, state: &Self::State) {
+        self.model.restore_state(&state.0);
+        self.reference_filter.restore_state(&state.1);
+        self.output_filter.restore_state(&state.2);
+        (self.sensitivities, self.theta, self.model_output) = (state.3, state.4, state.5);
+    }
+}
+
+impl TypeIdentifier for Mrac {
+    fn short_type_name(&self) -> &'static str {
+        "Mrac"
+    }
+}
+
+impl SampleTime for Mrac {
+    fn sample_time(&self) -> Option<f64> {
+        Some(self.model.sample_time())
+    }
+}
+
+impl Display for Mrac {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Mrac(gamma: {}, model: {})", self.gamma, self.model)
+    }
+}
+
+impl MimoTransferTimeDomain for Mrac {
+    fn input_count(&self) -> usize {
+        2
+    }
+
+    fn output_count(&self) -> usize {
+        1
+    }
+
+    fn transfer_td(&mut self, u: ArrayView1<f64>) -> Array1<f64> {
+        Array1::from_elem(1, self.control(u[0], u[1]))
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use ndarray::array;
+    use std::vec;
+
+    fn square(k: usize) -> f64 {
+        if (k / 100).is_multiple_of(2) {
+            1.0
+        } else {
+            -1.0
+        }
+    }
+
+    #[test]
+    fn test_Mrac_adapts_to_matching_gains() {
+        // plant: a_p = 0.05, gain 2; model: a_m = 0.1, gain 1
+        let model = PT1::<f64>::default()
+            .set_sample_time_or_default(0.1)
+            .set_t1_time_or_default(1.0);
+        let mut sut = Mrac::new(ReferenceModel::PT1(model)).set_gamma_or_default(2.0);
+        let mut plant = PT1::<f64>::default()
+            .set_sample_time_or_default(0.1)
+            .set_t1_time_or_default(2.0)
+            .set_kp(2.0);
+        let mut y = 0.0;
+        for k in 0..10000 {
+            y = plant.transfer_td(sut.control(square(k), y));
+        }
+        // perfect model following: a_p k theta1 = a_m, a_p (1 + k theta2) = a_m
+        let (theta1, theta2) = sut.gains();
+        assert!((theta1 - 1.0).abs() < 0.01, "{}", theta1);
+        assert!((theta2 - 0.5).abs() < 0.01, "{}", theta2);
+    }
+
+    #[test]
+    fn test_Mrac_PT2_model() {
+        let model = PT2::<f64>::default()
+            .set_sample_time_or_default(0.05)
+            .set_omega_or_default(2.0)
+            .set_damping_or_default(1.0);
+        let mut sut = Mrac::new(ReferenceModel::PT2(model)).set_gamma_or_default(1.0);
+        // same dynamics, half the gain: only the feedforward gain must adapt
+        let mut plant = model.set_kp(0.5);
+        let mut y = 0.0;
+        for k in 0..20000 {
+            let u = MimoTransferTimeDomain::transfer_td(&mut sut, array![square(k), y].view());
+            y = plant.transfer_td(u[0]);
+        }
+        let (theta1, theta2) = sut.gains();
+        assert!((theta1 - 2.0).abs() < 0.01, "{}", theta1);
+        assert!(theta2.abs() < 0.01, "{}", theta2);
+        assert_eq!(sut.sample_time(), Some(0.05));
+    }
+}
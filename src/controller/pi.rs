@@ -0,0 +1,349 @@
+//! A PI controller with output saturation and anti-windup
+//!
+//! $ v[k] = K_P e[k] + i[k] $, $ u[k] = sat(v[k]) $
+//!
+//! $ i[k] = i[k-1] + K_P \frac{T_s}{T_I} e[k] $
+//!
+//! where $T_I$ is the integral time and $T_s$ the sample time.
+//!
+//! While the output is saturated the integral would keep growing (windup)
+//! and the loop overshoots once the actuator leaves saturation. The
+//! anti-windup strategy limits this:
+//! * `Clamping`: the integral is frozen while the output is saturated and the
+//!   error drives further into saturation (conditional integration)
+//! * `BackCalculation`: the difference between saturated and unsaturated
+//!   output is fed back to the integral, $ i[k] \mathrel{+}= \frac{T_s}{T_t} (u[k] - v[k]) $
+//!   with the tracking time $T_t$
+//!
+//! ## Example
+//!
+//! ```rust
+//! use cb_simulation_util::controller::pi::{AntiWindup, PI};
+//! use cb_simulation_util::plant::TransferTimeDomain;
+//!
+//! fn main() {
+//!     let mut sut = PI::<f64>::default()
+//!         .set_kp(2.0)
+//!         .set_ti_time_or_default(4.0)
+//!         .set_output_limits(-1.0, 1.0)
+//!         .set_anti_windup(AntiWindup::Clamping);
+//!     assert_eq!(sut.transfer_td(0.25), 0.625);
+//!     for _ in 0..100 {
+//!         assert_eq!(sut.transfer_td(10.0), 1.0);
+//!     }
+//!     // the integral did not wind up, the output leaves saturation at once
+//!     assert!(sut.transfer_td(-1.0) < 0.0);
+//! }
+//! ```
+
+use super::*;
+use crate::plant::snapshot::StateAccess;
+use crate::plant::steady_state::SteadyState;
+use core::fmt::{self, Display};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AntiWindup {
+    /// Integrate regardless of saturation
+    None,
+    /// Conditional integration
+    Clamping,
+    /// Feed back the saturation excess with tracking time `tracking_time`
+    BackCalculation { tracking_time: f64 },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PI<N> {
+    pub ti_time: f64,
+    pub sample_time: f64,
+    pub kp: N,
+    pub output_min: N,
+    pub output_max: N,
+    pub anti_windup: AntiWindup,
+    integral: N,
+}
+
+impl<N: Copy + PartialOrd> PI<N> {
+    pub fn set_sample_time_or_default(self, sample_time: f64) -> Self {
+        if sample_time > 0.0 {
+            PI::<N> {
+                sample_time,
+                ..self
+            }
+        } else {
+            PI::<N> {
+                sample_time: 1.0,
+                ..self
+            }
+        }
+    }
+
+    pub fn set_ti_time_or_default(self, ti_time: f64) -> Self {
+        if ti_time >= self.sample_time {
+            PI::<N> { ti_time, ..self }
+        } else {
+            PI::<N> {
+                ti_time: self.sample_time,
+                ..self
+            }
+        }
+    }
+
+    /// Output saturation, the limits are swapped if `min > max`
+    pub fn set_output_limits(self, min: N, max: N) -> Self {
+        let (output_min, output_max) = if min > max { (max, min) } else { (min, max) };
+        PI::<N> {
+            output_min,
+            output_max,
+            ..self
+        }
+    }
+
+    pub fn set_anti_windup(self, anti_windup: AntiWindup) -> Self {
+        PI::<N> {
+            anti_windup,
+            ..self
+        }
+    }
+
+    /// The internal state: the integral part
+    pub fn state(&self) -> N {
+        self.integral
+    }
+
+    fn saturate(&self, v: N) -> N {
+        if v < self.output_min {
+            self.output_min
+        } else if v > self.output_max {
+            self.output_max
+        } else {
+            v
+        }
+    }
+}
+
+const FIX_KOMMA_SHIFT_BITS: u8 = 10;
+const FIX_KOMMA_SHIFT: i32 = 1 << FIX_KOMMA_SHIFT_BITS;
+
+impl PI<i32> {
+    pub fn set_kp(self, kp: i32) -> Self {
+        PI::<i32> {
+            kp: kp * FIX_KOMMA_SHIFT,
+            ..self
+        }
+    }
+
+    // coefficients are fixed point with 10 bits after the comma
+    fn fixed(value: f64) -> i64 {
+        (value * FIX_KOMMA_SHIFT as f64) as i64
+    }
+}
+
+impl Default for PI<i32> {
+    fn default() -> Self {
+        PI::<i32> {
+            ti_time: 1.0,
+            sample_time: 1.0,
+            kp: FIX_KOMMA_SHIFT,
+            output_min: i32::MIN,
+            output_max: i32::MAX,
+            anti_windup: AntiWindup::Clamping,
+            integral: 0,
+        }
+    }
+}
+
+impl TransferTimeDomain<i32> for PI<i32> {
+    fn transfer_td(&mut self, error: i32) -> i32 {
+        // proportional part and integral are scaled by FIX_KOMMA_SHIFT
+        let proportional = error as i64 * self.kp as i64;
+        let candidate = self.integral as i64
+            + ((Self::fixed(self.sample_time / self.ti_time) * proportional)
+                >> FIX_KOMMA_SHIFT_BITS);
+        let v = proportional + candidate;
+        let u = (v >> FIX_KOMMA_SHIFT_BITS).clamp(self.output_min as i64, self.output_max as i64);
+        let excess = (u << FIX_KOMMA_SHIFT_BITS) - v;
+        let integral = match self.anti_windup {
+            AntiWindup::None => candidate,
+            AntiWindup::Clamping if excess != 0 && (excess < 0) == (error > 0) => {
+                self.integral as i64
+            }
+            AntiWindup::Clamping => candidate,
+            AntiWindup::BackCalculation { tracking_time } => {
+                candidate
+                    + ((Self::fixed(self.sample_time / tracking_time) * excess)
+                        >> FIX_KOMMA_SHIFT_BITS)
+            }
+        };
+        self.integral = integral.clamp(i32::MIN as i64, i32::MAX as i64) as i32;
+        u as i32
+    }
+}
+
+impl PI<f64> {
+    pub fn set_kp(self, kp: f64) -> Self {
+        PI::<f64> { kp, ..self }
+    }
+}
+
+impl Default for PI<f64> {
+    fn default() -> Self {
+        PI::<f64> {
+            ti_time: 1.0,
+            sample_time: 1.0,
+            kp: 1.0,
+            output_min: f64::NEG_INFINITY,
+            output_max: f64::INFINITY,
+            anti_windup: AntiWindup::Clamping,
+            integral: 0.0,
+        }
+    }
+}
+
+impl TransferTimeDomain<f64> for PI<f64> {
+    fn transfer_td(&mut self, error: f64) -> f64 {
+        let proportional = self.kp * error;
+        let candidate = self.integral + proportional * self.sample_time / self.ti_time;
+        let v = proportional + candidate;
+        let u = self.saturate(v);
+        self.integral = match self.anti_windup {
+            AntiWindup::None => candidate,
+            // saturated and the error drives further into saturation
+            AntiWindup::Clamping if u != v && (u < v) == (error > 0.0) => self.integral,
+            AntiWindup::Clamping => candidate,
+            AntiWindup::BackCalculation { tracking_time } => {
+                candidate + self.sample_time / tracking_time * (u - v)
+            }
+        };
+        u
+    }
+}
+
+impl SteadyState for PI<f64> {
+    /// Zero with integral action, `None` beyond the output limits
+    fn steady_input(&self, output: f64) -> Option<f64> {
+        if output < self.output_min || output > self.output_max {
+            None
+        } else if self.ti_time.is_finite() {
+            Some(0.0)
+        } else if self.kp != 0.0 {
+            Some((output - self.integral) / self.kp)
+        } else {
+            None
+        }
+    }
+
+    /// Keeps the integral, with integral action a non-zero input has no
+    /// equilibrium and is run once
+    fn settle(&mut self, input: f64) -> f64 {
+        if self.ti_time.is_finite() && input != 0.0 {
+            return self.transfer_td(input);
+        }
+        self.saturate(self.kp * input + self.integral)
+    }
+
+    /// With integral action the integral holds the whole output
+    fn settle_output(&mut self, output: f64) -> Result<f64, &'static str> {
+        let input = self.steady_input(output).ok_or(self.short_type_name())?;
+        if self.ti_time.is_finite() {
+            self.integral = output;
+        }
+        Ok(input)
+    }
+}
+
+impl<N: Copy + fmt::Debug + PartialEq> StateAccess for PI<N> {
+    /// The integral part
+    type State = N;
+
+    fn save_state(&self) -> Self::State {
+        self.integral
+    }
+
+    fn restore_state(&mut self, state: &Self::State) {
+        self.integral = *state;
+    }
+}
+
+impl<N> TypeIdentifier for PI<N> {
+    fn short_type_name(&self) -> &'static str {
+        "PI"
+    }
+}
+
+impl<N> SampleTime for PI<N> {
+    fn sample_time(&self) -> Option<f64> {
+        Some(self.sample_time)
+    }
+}
+
+impl<N: Display> Display for PI<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "PI(sample_time: {}, ti_time {}, kp: {}, output: [{}, {}], anti_windup: {:?})",
+            self.sample_time,
+            self.ti_time,
+            self.kp,
+            self.output_min,
+            self.output_max,
+            self.anti_windup
+        )
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::plant::pt1::PT1;
+
+    fn overshoot(anti_windup: AntiWindup) -> f64 {
+        let mut controller = PI::<f64>::default()
+            .set_kp(2.0)
+            .set_ti_time_or_default(5.0)
+            .set_output_limits(0.0, 1.5)
+            .set_anti_windup(anti_windup);
+        let mut plant = PT1::<f64>::default().set_t1_time_or_default(10.0);
+        let mut y = 0.0;
+        let mut max: f64 = 0.0;
+        for _ in 0..300 {
+            y = plant.transfer_td(controller.transfer_td(1.0 - y));
+            max = max.max(y);
+        }
+        assert!((y - 1.0).abs() < 1e-3);
+        max - 1.0
+    }
+
+    #[test]
+    fn test_PI_anti_windup_reduces_overshoot() {
+        let none = overshoot(AntiWindup::None);
+        let clamping = overshoot(AntiWindup::Clamping);
+        let back_calculation = overshoot(AntiWindup::BackCalculation { tracking_time: 2.0 });
+        assert!(clamping < none);
+        assert!(back_calculation < none);
+    }
+
+    #[test]
+    fn test_PI_f64_integrates() {
+        let mut sut = PI::<f64>::default().set_ti_time_or_default(2.0);
+        assert_eq!(sut.transfer_td(1.0), 1.5);
+        assert_eq!(sut.transfer_td(1.0), 2.0);
+        assert_eq!(sut.state(), 1.0);
+    }
+
+    #[test]
+    fn test_PI_i32_saturation() {
+        let mut sut = PI::<i32>::default()
+            .set_kp(2)
+            .set_ti_time_or_default(2.0)
+            .set_output_limits(-100, 100);
+        assert_eq!(sut.transfer_td(10), 30);
+        assert_eq!(sut.transfer_td(10), 40);
+        for _ in 0..20 {
+            assert_eq!(sut.transfer_td(100), 100);
+        }
+        assert_eq!(sut.state(), 20 << 10);
+        assert_eq!(sut.transfer_td(-20), -40);
+    }
+}
@@ -0,0 +1,240 @@
+//! # Pole placement
+//!
+//! State feedback $ u[k] = V r[k] - K x[k] $ for a single input `StateSpace`
+//! plant, with the gain $K$ computed by Ackermann's formula so that the
+//! closed loop $ A - B K $ has the given discrete poles:
+//!
+//! $ K = [0 \dots 0\ 1] \, W_c^{-1} \, \varphi(A) $
+//!
+//! where $ W_c = [B\ AB \dots A^{n-1}B] $ is the controllability matrix and
+//! $\varphi$ the desired characteristic polynomial. The prefilter $V$ makes
+//! the first output follow a constant reference without steady state error.
+//!
+//! Poles are given in the z-plane, inside the unit circle for a stable loop.
+//! All poles at 0 give a deadbeat controller which settles in $n$ steps.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use ndarray::array;
+//! use cb_simulation_util::controller::pole_placement::{Pole, StateFeedback};
+//! use cb_simulation_util::plant::MimoTransferTimeDomain;
+//! use cb_simulation_util::plant::state_space::StateSpace;
+//!
+//! fn main() {
+//!     // double integrator, sample time 0.1
+//!     let plant = StateSpace::new(
+//!         array![[1.0, 0.1], [0.0, 1.0]],
+//!         array![[0.005], [0.1]],
+//!         array![[1.0, 0.0]],
+//!         array![[0.0]],
+//!     )
+//!     .unwrap();
+//!     let poles = [Pole::Complex { re: 0.8, im: 0.1 }];
+//!     let feedback = StateFeedback::place(&plant, &poles).unwrap();
+//!     let mut closed_loop = feedback.closed_loop(&plant).unwrap();
+//!     let mut y = 0.0;
+//!     for _ in 0..200 {
+//!         y = closed_loop.transfer_td(array![1.0].view())[0];
+//!     }
+//!     assert!((y - 1.0).abs() < 1e-9);
+//! }
+//! ```
+
+use core::fmt::{self, Display};
+use ndarray::{Array1, Array2, s};
+use std::vec;
+use std::vec::Vec;
+
+use crate::analysis::poly;
+use crate::linalg;
+use crate::plant::state_space::StateSpace;
+
+/// Desired closed loop pole in the z-plane
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Pole {
+    Real(f64),
+    /// A complex pole, its conjugate is placed as well
+    Complex {
+        re: f64,
+        im: f64,
+    },
+}
+
+impl Pole {
+    /// Factor of the characteristic polynomial, ascending powers
+    fn factor(&self) -> Vec<f64> {
+        match *self {
+            Pole::Real(p) => vec![-p, 1.0],
+            Pole::Complex { re, im } => vec![re * re + im * im, -2.0 * re, 1.0],
+        }
+    }
+
+    fn order(&self) -> usize {
+        match self {
+            Pole::Real(_) => 1,
+            Pole::Complex { .. } => 2,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PolePlacementError {
+    /// Ackermann's formula needs a plant with exactly one input
+    NotSingleInput,
+    /// The number of poles, conjugates included, differs from the number of states
+    OrderMismatch { states: usize, poles: usize },
+    /// The controllability matrix is singular
+    Uncontrollable,
+}
+
+impl Display for PolePlacementError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PolePlacementError::NotSingleInput => write!(f, "Plant must have exactly one input"),
+            PolePlacementError::OrderMismatch { states, poles } => {
+                write!(f, "{} poles given for {} states", poles, states)
+            }
+            PolePlacementError::Uncontrollable => write!(f, "Plant is not controllable"),
+        }
+    }
+}
+
+/// State feedback gain and reference prefilter
+#[derive(Debug, Clone, PartialEq)]
+pub struct StateFeedback {
+    /// Feedback gain $K$, one entry per state
+    pub gain: Array1<f64>,
+    /// Reference prefilter $V$, `NaN` if the closed loop has no finite steady state gain
+    pub prefilter: f64,
+}
+
+impl StateFeedback {
+    /// Place the closed loop poles of `plant` at `poles`
+    pub fn place(plant: &StateSpace, poles: &[Pole]) -> Result<Self, PolePlacementError> {
+        if plant.b.ncols() != 1 {
+            return Err(PolePlacementError::NotSingleInput);
+        }
+        let n = plant.states();
+        let order = poles.iter().map(Pole::order).sum();
+        if order != n {
+            return Err(PolePlacementError::OrderMismatch {
+                states: n,
+                poles: order,
+            });
+        }
+        // controllability matrix [B, AB, ..., A^(n-1) B]
+        let mut controllability = Array2::zeros((n, n));
+        let mut column = plant.b.column(0).to_owned();
+        for i in 0..n {
+            controllability.column_mut(i).assign(&column);
+            column = plant.a.dot(&column);
+        }
+        let inverse =
+            linalg::inverse(&controllability).ok_or(PolePlacementError::Uncontrollable)?;
+        // phi(A) with the coefficients of the desired characteristic polynomial
+        let coefficients = poles
+            .iter()
+            .fold(vec![1.0], |acc, p| poly::mul(&acc, &p.factor()));
+        let mut phi = Array2::zeros((n, n));
+        let mut power = Array2::eye(n);
+        for c in &coefficients {
+            phi = phi + &power * *c;
+            power = power.dot(&plant.a);
+        }
+        let gain = inverse.slice(s![n - 1, ..]).dot(&phi);
+        let mut feedback = StateFeedback {
+            gain,
+            prefilter: 1.0,
+        };
+        feedback.prefilter = feedback
+            .closed_loop(plant)
+            .ok()
+            .and_then(|closed| closed.steady_state_gain())
+            .map_or(f64::NAN, |g| 1.0 / g[[0, 0]]);
+        Ok(feedback)
+    }
+
+    /// Manipulated variable $ u = V r - K x $
+    pub fn control(&self, reference: f64, state: &Array1<f64>) -> f64 {
+        self.prefilter * reference - self.gain.dot(state)
+    }
+
+    /// The closed loop as plant with the reference as input
+    ///
+    /// $ x[k+1] = (A - B K) x[k] + B V r[k] $, $ y[k] = (C - D K) x[k] + D V r[k] $
+    pub fn closed_loop(&self, plant: &StateSpace) -> Result<StateSpace, &'static str> {
+        let k = self.gain.view().insert_axis(ndarray::Axis(0));
+        let closed = StateSpace::new(
+            &plant.a - &plant.b.dot(&k),
+            &plant.b * self.prefilter,
+            &plant.c - &plant.d.dot(&k),
+            &plant.d * self.prefilter,
+        )?;
+        Ok(closed.set_sample_time_or_default(plant.sample_time))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::plant::MimoTransferTimeDomain;
+    use ndarray::array;
+
+    fn double_integrator() -> StateSpace {
+        StateSpace::new(
+            array![[1.0, 1.0], [0.0, 1.0]],
+            array![[0.5], [1.0]],
+            array![[1.0, 0.0]],
+            array![[0.0]],
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_place_characteristic_polynomial() {
+        let plant = double_integrator();
+        let sut = StateFeedback::place(&plant, &[Pole::Real(0.5), Pole::Real(0.2)]).unwrap();
+        let a = sut.closed_loop(&plant).unwrap().a;
+        let trace = a[[0, 0]] + a[[1, 1]];
+        let det = a[[0, 0]] * a[[1, 1]] - a[[0, 1]] * a[[1, 0]];
+        assert!((trace - 0.7).abs() < 1e-12);
+        assert!((det - 0.1).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_place_deadbeat_settles_in_n_steps() {
+        let plant = double_integrator();
+        let sut = StateFeedback::place(&plant, &[Pole::Real(0.0), Pole::Real(0.0)]).unwrap();
+        let mut closed = sut.closed_loop(&plant).unwrap();
+        let outputs: Vec<f64> = (0..5)
+            .map(|_| closed.transfer_td(array![2.0].view())[0])
+            .collect();
+        // y[k] = C x[k], the state reaches the reference after two steps
+        assert!(outputs[2..].iter().all(|y| (y - 2.0).abs() < 1e-12));
+    }
+
+    #[test]
+    fn test_place_errors() {
+        let plant = double_integrator();
+        assert_eq!(
+            StateFeedback::place(&plant, &[Pole::Real(0.5)]),
+            Err(PolePlacementError::OrderMismatch {
+                states: 2,
+                poles: 1
+            })
+        );
+        let uncontrollable = StateSpace::new(
+            array![[0.5, 0.0], [0.0, 0.7]],
+            array![[1.0], [0.0]],
+            array![[1.0, 1.0]],
+            array![[0.0]],
+        )
+        .unwrap();
+        assert_eq!(
+            StateFeedback::place(&uncontrollable, &[Pole::Complex { re: 0.1, im: 0.1 }]),
+            Err(PolePlacementError::Uncontrollable)
+        );
+    }
+}
@@ -0,0 +1,193 @@
+//! A relay aka two-point controller
+//!
+//! Switches the output to `on_value` when the input rises above the
+//! switch-on threshold and back to `off_value` when it falls below the
+//! switch-off threshold. In between the previous output is kept. The
+//! switching state is a `Hysteresis` with two constant branches.
+//!
+//! With the control error as input ($ e = r - y $) it acts like a
+//! thermostat: heating on when the temperature is too low.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use cb_simulation_util::controller::relay::Relay;
+//! use cb_simulation_util::plant::pt1::PT1;
+//! use cb_simulation_util::signal::{StepFunction, TimeRange};
+//! use cb_simulation_util::sim::Simulation;
+//!
+//! fn main() {
+//!     let mut thermostat = Relay::<f64>::default()
+//!         .set_thresholds(0.5, -0.5)
+//!         .unwrap()
+//!         .set_output(0.0, 40.0);
+//!     let mut room = PT1::<f64>::default().set_t1_time_or_default(20.0);
+//!     let result = Simulation::new(TimeRange::default().set_end(500.0))
+//!         .run_closed_loop(&StepFunction::default().post(20.0), &mut thermostat, &mut room);
+//!     let output = &result.trace("output").unwrap().values;
+//!     assert!(output.iter().skip(200).all(|y| (y - 20.0).abs() < 1.5));
+//! }
+//! ```
+
+use core::fmt::{self, Display};
+use num_traits::{Num, Zero};
+
+use super::*;
+use crate::TransferFunction;
+use crate::hysteresis::{Hysteresis, HysteresisBuilder, LinearFn};
+use crate::plant::snapshot::StateAccess;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Relay<N> {
+    pub on_threshold: N,
+    pub off_threshold: N,
+    pub on_value: N,
+    pub off_value: N,
+    hysteresis: Hysteresis<N>,
+}
+
+impl<N: Default + Num + Copy + PartialOrd> Relay<N> {
+    pub(crate) fn new(on_threshold: N, off_threshold: N, on_value: N, off_value: N) -> Self {
+        let hysteresis = HysteresisBuilder::new(
+            LinearFn {
+                m: N::zero(),
+                n: off_value,
+            },
+            LinearFn {
+                m: N::zero(),
+                n: on_value,
+            },
+        )
+        .lower_x(off_threshold)
+        .upper_x(on_threshold)
+        .build();
+        Relay {
+            on_threshold,
+            off_threshold,
+            on_value,
+            off_value,
+            hysteresis,
+        }
+    }
+
+    /// Switch on above `on_threshold`, off below `off_threshold`, the relay starts off
+    pub fn set_thresholds(self, on_threshold: N, off_threshold: N) -> Result<Self, &'static str> {
+        if off_threshold <= on_threshold {
+            Ok(Relay::new(
+                on_threshold,
+                off_threshold,
+                self.on_value,
+                self.off_value,
+            ))
+        } else {
+            Err("Invalid thresholds: off_threshold must be <= on_threshold")
+        }
+    }
+
+    /// Output values of both states, the relay starts off
+    pub fn set_output(self, off_value: N, on_value: N) -> Self {
+        Relay::new(self.on_threshold, self.off_threshold, on_value, off_value)
+    }
+
+    pub fn is_on(&self) -> bool {
+        self.hysteresis.is_upper()
+    }
+}
+
+impl Default for Relay<f64> {
+    /// Switches between 0 and 1 at 0
+    fn default() -> Self {
+        Relay::new(0.0, 0.0, 1.0, 0.0)
+    }
+}
+
+impl Default for Relay<i32> {
+    /// Switches between 0 and 1 at 0
+    fn default() -> Self {
+        Relay::new(0, 0, 1, 0)
+    }
+}
+
+impl<N> StateAccess for Relay<N> {
+    /// Whether the relay is on
+    type State = bool;
+
+    fn save_state(&self) -> Self::State {
+        self.hysteresis.save_state()
+    }
+
+    fn restore_state(&mut self, state: &Self::State) {
+        self.hysteresis.restore_state(state);
+    }
+}
+
+impl<N> TypeIdentifier for Relay<N> {
+    fn short_type_name(&self) -> &'static str {
+        "Relay"
+    }
+}
+
+impl<N> SampleTime for Relay<N> {
+    fn sample_time(&self) -> Option<f64> {
+        None
+    }
+}
+
+impl<N: Display> Display for Relay<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Relay(on_threshold: {}, off_threshold: {}, on_value: {}, off_value: {})",
+            self.on_threshold, self.off_threshold, self.on_value, self.off_value
+        )
+    }
+}
+
+impl<N: Num + Zero + Copy + PartialOrd> TransferTimeDomain<N> for Relay<N> {
+    fn transfer_td(&mut self, input: N) -> N {
+        // both branches are constant, the hysteresis is defined for every input
+        self.hysteresis.transfer(input).unwrap_or(self.off_value)
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use std::format;
+
+    #[test]
+    fn test_Relay_i32_switching() {
+        let mut sut = Relay::<i32>::default()
+            .set_thresholds(10, -10)
+            .unwrap()
+            .set_output(-100, 100);
+        assert_eq!(sut.transfer_td(5), -100);
+        assert_eq!(sut.transfer_td(11), 100);
+        assert!(sut.is_on());
+        assert_eq!(sut.transfer_td(-10), 100);
+        assert_eq!(sut.transfer_td(-11), -100);
+        assert!(!sut.is_on());
+    }
+
+    #[test]
+    fn test_Relay_restore_state() {
+        let mut sut = Relay::<f64>::default().set_thresholds(1.0, -1.0).unwrap();
+        sut.transfer_td(2.0);
+        let on = sut.save_state();
+        sut.transfer_td(-2.0);
+        assert!(!sut.is_on());
+        sut.restore_state(&on);
+        assert_eq!(sut.transfer_td(0.0), 1.0);
+    }
+
+    #[test]
+    fn test_Relay_invalid_thresholds() {
+        assert!(Relay::<f64>::default().set_thresholds(-1.0, 1.0).is_err());
+        assert_eq!(
+            format!("{}", Relay::<f64>::default()),
+            "Relay(on_threshold: 0, off_threshold: 0, on_value: 1, off_value: 0)"
+        );
+    }
+}
@@ -0,0 +1,172 @@
+//! A three-point controller, e.g. for motorized valves
+//!
+//! Drives the actuator with `-output`, `0` or `+output` ("lower", "stop",
+//! "raise") depending on the control error:
+//!
+//! * raise when the error exceeds `dead_band`, stop again when it falls
+//!   below `dead_band - hysteresis`
+//! * lower when the error falls below `-dead_band`, stop again when it
+//!   rises above `-dead_band + hysteresis`
+//!
+//! Both bands are `Relay`s on the error and its negation.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use cb_simulation_util::controller::three_point::ThreePoint;
+//! use cb_simulation_util::plant::TransferTimeDomain;
+//!
+//! fn main() {
+//!     let mut sut = ThreePoint::<f64>::default()
+//!         .set_dead_band(1.0, 0.5)
+//!         .unwrap()
+//!         .set_output(24.0);
+//!     assert_eq!(sut.transfer_td(0.8), 0.0);
+//!     assert_eq!(sut.transfer_td(1.2), 24.0);
+//!     assert_eq!(sut.transfer_td(0.6), 24.0); // within the hysteresis
+//!     assert_eq!(sut.transfer_td(0.4), 0.0);
+//!     assert_eq!(sut.transfer_td(-1.5), -24.0);
+//! }
+//! ```
+
+use core::fmt::{self, Display};
+use core::ops::Neg;
+use num_traits::Num;
+
+use super::relay::Relay;
+use super::*;
+use crate::plant::snapshot::StateAccess;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ThreePoint<N> {
+    pub dead_band: N,
+    pub hysteresis: N,
+    pub output: N,
+    raise: Relay<N>,
+    lower: Relay<N>,
+}
+
+impl<N: Default + Num + Copy + PartialOrd + Neg<Output = N>> ThreePoint<N> {
+    fn new(dead_band: N, hysteresis: N, output: N) -> Self {
+        let relay = Relay::new(dead_band, dead_band - hysteresis, output, N::zero());
+        ThreePoint {
+            dead_band,
+            hysteresis,
+            output,
+            raise: relay,
+            lower: relay,
+        }
+    }
+
+    /// Half width of the zero output band and the switching hysteresis, both restart stopped
+    ///
+    /// Requires `0 <= hysteresis <= dead_band`, otherwise raise and lower would overlap.
+    pub fn set_dead_band(self, dead_band: N, hysteresis: N) -> Result<Self, &'static str> {
+        if N::zero() <= hysteresis && hysteresis <= dead_band {
+            Ok(ThreePoint::new(dead_band, hysteresis, self.output))
+        } else {
+            Err("Invalid dead band: Must be 0 <= hysteresis <= dead_band")
+        }
+    }
+
+    /// Magnitude of the raise and lower output, restarts stopped
+    pub fn set_output(self, output: N) -> Self {
+        ThreePoint::new(self.dead_band, self.hysteresis, output)
+    }
+
+    pub fn is_raising(&self) -> bool {
+        self.raise.is_on()
+    }
+
+    pub fn is_lowering(&self) -> bool {
+        self.lower.is_on()
+    }
+}
+
+impl Default for ThreePoint<f64> {
+    /// Dead band 1, hysteresis 0.5, output 1
+    fn default() -> Self {
+        ThreePoint::new(1.0, 0.5, 1.0)
+    }
+}
+
+impl Default for ThreePoint<i32> {
+    /// Dead band 2, hysteresis 1, output 1
+    fn default() -> Self {
+        ThreePoint::new(2, 1, 1)
+    }
+}
+
+impl<N> StateAccess for ThreePoint<N> {
+    /// Whether the raise and the lower relay are on
+    type State = (bool, bool);
+
+    fn save_state(&self) -> Self::State {
+        (self.raise.save_state(), self.lower.save_state())
+    }
+
+    fn restore_state(&mut self, state: &Self::State) {
+        self.raise.restore_state(&state.0);
+        self.lower.restore_state(&state.1);
+    }
+}
+
+impl<N> TypeIdentifier for ThreePoint<N> {
+    fn short_type_name(&self) -> &'static str {
+        "ThreePoint"
+    }
+}
+
+impl<N> SampleTime for ThreePoint<N> {
+    fn sample_time(&self) -> Option<f64> {
+        None
+    }
+}
+
+impl<N: Display> Display for ThreePoint<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "ThreePoint(dead_band: {}, hysteresis: {}, output: {})",
+            self.dead_band, self.hysteresis, self.output
+        )
+    }
+}
+
+impl<N: Num + Copy + PartialOrd + Neg<Output = N>> TransferTimeDomain<N> for ThreePoint<N> {
+    fn transfer_td(&mut self, error: N) -> N {
+        self.raise.transfer_td(error) - self.lower.transfer_td(-error)
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_ThreePoint_i32_lower_band() {
+        let mut sut = ThreePoint::<i32>::default().set_output(100);
+        assert_eq!(sut.transfer_td(-2), 0);
+        assert_eq!(sut.transfer_td(-3), -100);
+        assert!(sut.is_lowering());
+        assert_eq!(sut.transfer_td(-1), -100);
+        assert_eq!(sut.transfer_td(0), 0);
+        assert!(!sut.is_lowering() && !sut.is_raising());
+    }
+
+    #[test]
+    fn test_ThreePoint_invalid_band() {
+        assert!(
+            ThreePoint::<f64>::default()
+                .set_dead_band(1.0, 2.0)
+                .is_err()
+        );
+        assert!(
+            ThreePoint::<f64>::default()
+                .set_dead_band(1.0, -0.1)
+                .is_err()
+        );
+    }
+}
@@ -1,21 +1,40 @@
 use core::cmp::PartialOrd;
+use core::fmt::{self, Display};
+use core::str::FromStr;
 use num_traits::Num;
 
+use crate::parse::{Fields, ParseError};
 use crate::{NotDefinedError, TransferFunction};
 
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 enum Direction {
     FromUpper,
     FromLower,
 }
 
+impl FromStr for Direction {
+    type Err = ParseError;
+
+    /// The variant name as printed by `Debug`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "FromUpper" => Ok(Direction::FromUpper),
+            "FromLower" => Ok(Direction::FromLower),
+            _ => Err(ParseError::Value("direction")),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct LinearFn<N> {
     pub m: N,
     pub n: N,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Hysteresis<N> {
     upper_fn: LinearFn<N>,
     lower_fn: LinearFn<N>,
@@ -24,6 +43,13 @@ pub struct Hysteresis<N> {
     direction: Direction,
 }
 
+impl<N> Hysteresis<N> {
+    /// Whether the upper function is active, i.e. the input last left the band upwards
+    pub fn is_upper(&self) -> bool {
+        self.direction == Direction::FromUpper
+    }
+}
+
 impl<N: Num + Copy + Clone + PartialOrd> TransferFunction<N> for Hysteresis<N> {
     fn transfer(&mut self, u: N) -> Result<N, NotDefinedError> {
         if self.lower > u {
@@ -41,6 +67,99 @@ impl<N: Num + Copy + Clone + PartialOrd> TransferFunction<N> for Hysteresis<N> {
     }
 }
 
+impl<N: Display> Display for Hysteresis<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Hysteresis(lower: {}, upper: {}, lower_m: {}, lower_n: {}, upper_m: {}, upper_n: {}",
+            self.lower,
+            self.upper,
+            self.lower_fn.m,
+            self.lower_fn.n,
+            self.upper_fn.m,
+            self.upper_fn.n
+        )?;
+        if self.direction != Direction::FromLower {
+            write!(f, ", direction: {:?}", self.direction)?;
+        }
+        write!(f, ")")
+    }
+}
+
+impl<N: FromStr> FromStr for Hysteresis<N> {
+    type Err = ParseError;
+
+    /// Parse the `Display` output
+    ///
+    /// The active function is printed only if it is the upper one, like the
+    /// initial direction of `HysteresisBuilder::upper_direction`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut fields = Fields::new(s, "Hysteresis")?;
+        let lower = fields.next("lower", ": ")?;
+        let upper = fields.next("upper", ": ")?;
+        let lower_fn = LinearFn {
+            m: fields.next("lower_m", ": ")?,
+            n: fields.next("lower_n", ": ")?,
+        };
+        let upper_fn = LinearFn {
+            m: fields.next("upper_m", ": ")?,
+            n: fields.next("upper_n", ": ")?,
+        };
+        let direction = fields
+            .optional("direction", ": ")?
+            .unwrap_or(Direction::FromLower);
+        fields.end()?;
+        Ok(Hysteresis {
+            upper_fn,
+            lower_fn,
+            upper,
+            lower,
+            direction,
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+impl<N> crate::plant::snapshot::StateAccess for Hysteresis<N> {
+    /// Whether the upper function is active
+    type State = bool;
+
+    fn save_state(&self) -> Self::State {
+        self.is_upper()
+    }
+
+    fn restore_state(&mut self, state: &Self::State) {
+        self.direction = if *state {
+            Direction::FromUpper
+        } else {
+            Direction::FromLower
+        };
+    }
+}
+
+#[cfg(feature = "std")]
+impl crate::plant::TypeIdentifier for Hysteresis<f64> {
+    fn short_type_name(&self) -> &'static str {
+        "Hysteresis"
+    }
+}
+
+#[cfg(feature = "std")]
+impl<N> crate::plant::SampleTime for Hysteresis<N> {
+    /// Switches on the input only, fits any sample time
+    fn sample_time(&self) -> Option<f64> {
+        None
+    }
+}
+
+#[cfg(feature = "std")]
+impl crate::plant::TransferTimeDomain<f64> for Hysteresis<f64> {
+    fn transfer_td(&mut self, u: f64) -> f64 {
+        // the hysteresis is defined for every input
+        self.transfer(u).unwrap_or(u)
+    }
+}
+
 /// Build a hysteresis with
 ///
 /// # Examples
@@ -319,4 +438,50 @@ mod tests {
                 .build();
         assert_eq!(expected, sut)
     }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_Hysteresis_serde_round_trip() {
+        let mut sut =
+            HysteresisBuilder::<f64>::new(LinearFn { m: 1.0, n: 0.0 }, LinearFn { m: 1.0, n: 1.0 })
+                .lower_x(0.5)
+                .upper_x(1.0)
+                .build();
+        let _ = sut.transfer(2.0);
+        let json = serde_json::to_string(&sut).unwrap();
+        let restored: Hysteresis<f64> = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored, sut);
+        assert!(restored.is_upper());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_Hysteresis_from_str_round_trip() {
+        use std::string::ToString;
+
+        let sut = HysteresisBuilder::<f64>::new(
+            LinearFn { m: 0.5, n: -1.0 },
+            LinearFn { m: 1.0, n: 1.0 },
+        )
+        .spread_x(0.25)
+        .lower_x(-3.5)
+        .build();
+        let text = "Hysteresis(lower: -3.5, upper: -3.25, lower_m: 0.5, lower_n: -1, upper_m: 1, upper_n: 1)";
+        assert_eq!(sut.to_string(), text);
+        assert_eq!(text.parse::<Hysteresis<f64>>(), Ok(sut));
+        let sut = HysteresisBuilder::<i32>::new(LinearFn { m: 1, n: 0 }, LinearFn { m: 1, n: 2 })
+            .lower_x(1)
+            .upper_x(4)
+            .upper_direction()
+            .build();
+        assert!(sut.to_string().ends_with(", direction: FromUpper)"));
+        let parsed = sut.to_string().parse::<Hysteresis<i32>>();
+        assert_eq!(parsed, Ok(sut));
+        assert!(parsed.unwrap().is_upper());
+        assert_eq!(
+            "Hysteresis(lower: 1, upper: x, lower_m: 1, lower_n: 0, upper_m: 1, upper_n: 1)"
+                .parse::<Hysteresis<f64>>(),
+            Err(ParseError::Value("upper"))
+        );
+    }
 }
@@ -3,13 +3,37 @@
 #[cfg(feature = "std")]
 extern crate std;
 
+#[cfg(feature = "std")]
+pub mod analysis;
+#[cfg(feature = "std")]
+pub mod codegen;
+#[cfg(all(feature = "std", feature = "serde"))]
+pub mod config;
+#[cfg(feature = "std")]
+pub mod controller;
 pub mod hysteresis;
 #[cfg(feature = "std")]
+mod json;
+#[cfg(feature = "std")]
+mod linalg;
+#[cfg(feature = "std")]
+pub mod logic;
+#[cfg(feature = "std")]
+pub mod manifest;
+pub mod parse;
+#[cfg(feature = "std")]
 pub mod plant;
+#[cfg(feature = "std")]
+mod rng;
 
 #[cfg(feature = "std")]
+pub mod scenario;
+
 pub mod signal;
 
+#[cfg(feature = "std")]
+pub mod sim;
+
 use core::fmt;
 
 #[derive(Debug, Clone)]
@@ -0,0 +1,127 @@
+//! Small dense linear algebra helpers on `ndarray` matrices
+//!
+//! Sized for the low order systems of this crate, not for large matrices.
+
+use ndarray::{Array1, Array2};
+use std::vec::Vec;
+
+/// Matrix exponential $e^{A}$, scaling and squaring with a Taylor series
+pub(crate) fn expm(a: &Array2<f64>) -> Array2<f64> {
+    let n = a.nrows();
+    let norm = a.iter().map(|x| x.abs()).fold(0.0, f64::max) * n as f64;
+    let squarings = if norm > 0.5 {
+        (norm / 0.5).log2().ceil() as i32
+    } else {
+        0
+    };
+    let scaled = a / 2f64.powi(squarings);
+    let mut result = Array2::eye(n);
+    let mut term = Array2::eye(n);
+    for k in 1..=20 {
+        term = term.dot(&scaled) / k as f64;
+        result += &term;
+    }
+    for _ in 0..squarings {
+        result = result.dot(&result);
+    }
+    result
+}
+
+/// Faddeev–LeVerrier algorithm
+///
+/// Returns the characteristic polynomial $ det(zI - A) = z^n + c_1 z^{n-1} + \dots + c_n $
+/// as `[1, c_1, ..., c_n]` and the matrices $M_0 \dots M_{n-1}$ with
+/// $ adj(zI - A) = \sum_k M_k z^{n-1-k} $.
+pub(crate) fn faddeev_leverrier(a: &Array2<f64>) -> (Vec<f64>, Vec<Array2<f64>>) {
+    let n = a.nrows();
+    let mut coefficients = Vec::with_capacity(n + 1);
+    let mut adjugates = Vec::with_capacity(n);
+    coefficients.push(1.0);
+    let mut m = Array2::eye(n);
+    for k in 1..=n {
+        adjugates.push(m.clone());
+        let am = a.dot(&m);
+        let c = -am.diag().sum() / k as f64;
+        coefficients.push(c);
+        m = am + Array2::<f64>::eye(n) * c;
+    }
+    (coefficients, adjugates)
+}
+
+/// Inverse by Gauss-Jordan elimination with partial pivoting, `None` if singular
+pub(crate) fn inverse(a: &Array2<f64>) -> Option<Array2<f64>> {
+    let n = a.nrows();
+    let mut m = a.clone();
+    let mut inv = Array2::eye(n);
+    for col in 0..n {
+        let pivot = (col..n).max_by(|i, j| m[[*i, col]].abs().total_cmp(&m[[*j, col]].abs()))?;
+        if m[[pivot, col]].abs() < 1e-12 {
+            return None;
+        }
+        for j in 0..n {
+            m.swap([col, j], [pivot, j]);
+            inv.swap([col, j], [pivot, j]);
+        }
+        let p = m[[col, col]];
+        for j in 0..n {
+            m[[col, j]] /= p;
+            inv[[col, j]] /= p;
+        }
+        for i in 0..n {
+            if i != col {
+                let factor = m[[i, col]];
+                for j in 0..n {
+                    m[[i, j]] -= factor * m[[col, j]];
+                    inv[[i, j]] -= factor * inv[[col, j]];
+                }
+            }
+        }
+    }
+    Some(inv)
+}
+
+/// Least squares solution of $ \Phi \theta = y $ via the normal equations, `None` if rank deficient
+pub(crate) fn least_squares(phi: &Array2<f64>, y: &Array1<f64>) -> Option<Array1<f64>> {
+    let normal = phi.t().dot(phi);
+    Some(inverse(&normal)?.dot(&phi.t().dot(y)))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use ndarray::array;
+    use std::vec;
+
+    #[test]
+    fn test_expm_rotation() {
+        let a = array![[0.0, -1.0], [1.0, 0.0]];
+        let e = expm(&a);
+        assert!((e[[0, 0]] - 1f64.cos()).abs() < 1e-12);
+        assert!((e[[1, 0]] - 1f64.sin()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_faddeev_leverrier() {
+        let a = array![[0.0, 1.0], [-2.0, -3.0]];
+        let (c, m) = faddeev_leverrier(&a);
+        assert_eq!(c, vec![1.0, 3.0, 2.0]);
+        assert_eq!(m[0], Array2::<f64>::eye(2));
+    }
+
+    #[test]
+    fn test_inverse_and_least_squares() {
+        let a = array![[0.0, 1.0], [-2.0, -3.0]];
+        let product = a.dot(&inverse(&a).unwrap());
+        assert!(
+            (product - Array2::<f64>::eye(2))
+                .iter()
+                .all(|x| x.abs() < 1e-12)
+        );
+        assert!(inverse(&array![[1.0, 2.0], [2.0, 4.0]]).is_none());
+        // line through (0, 1), (1, 3), (2, 5)
+        let phi = array![[1.0, 0.0], [1.0, 1.0], [1.0, 2.0]];
+        let theta = least_squares(&phi, &array![1.0, 3.0, 5.0]).unwrap();
+        assert!((theta[0] - 1.0).abs() < 1e-12 && (theta[1] - 2.0).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,338 @@
+//! Counting and totalizing blocks
+//!
+//! * `SwitchCounter` counts switch-on events (off → on transitions) of a
+//!   relay or compressor signal, optionally only those within a sliding time
+//!   window - e.g. compressor starts per hour
+//! * `Totalizer` accumulates a rate over time, e.g. flow to volume or power
+//!   to energy, optionally ignoring negative values
+//!
+//! The output is the count or total, so running a block over a trace with
+//! `SimResult::derive` yields wear and consumption metrics as traces. The
+//! block's sample time must be the sample interval of the trace, else the
+//! window and the totals would be scaled wrongly.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use cb_simulation_util::logic::counter::SwitchCounter;
+//! use cb_simulation_util::plant::TransferTimeDomain;
+//!
+//! fn main() {
+//!     let mut starts_per_minute = SwitchCounter::default()
+//!         .set_sample_time_or_default(10.0)
+//!         .set_window(Some(60.0));
+//!     let mut count = 0.0;
+//!     for k in 0..12 {
+//!         // relay toggling every 20 s during the first minute
+//!         let relay = if k < 6 && (k / 2) % 2 == 0 { 1.0 } else { 0.0 };
+//!         count = starts_per_minute.transfer_td(relay);
+//!     }
+//!     assert_eq!(starts_per_minute.total(), 2);
+//!     assert_eq!(count, 0.0);
+//! }
+//! ```
+
+use super::*;
+use crate::plant::snapshot::StateAccess;
+use core::fmt::{self, Display};
+use std::collections::VecDeque;
+
+/// Counts off → on transitions, an input above `threshold` is on
+#[derive(Debug, Clone, PartialEq)]
+pub struct SwitchCounter {
+    pub threshold: f64,
+    pub sample_time: f64,
+    /// Count only events within this time span back from now
+    pub window: Option<f64>,
+    previous_on: bool,
+    elapsed: f64,
+    total: u64,
+    events: VecDeque<f64>,
+}
+
+impl SwitchCounter {
+    pub fn set_sample_time_or_default(self, sample_time: f64) -> Self {
+        if sample_time > 0.0 {
+            SwitchCounter {
+                sample_time,
+                ..self
+            }
+        } else {
+            SwitchCounter {
+                sample_time: 1.0,
+                ..self
+            }
+        }
+    }
+
+    pub fn set_threshold(self, threshold: f64) -> Self {
+        SwitchCounter { threshold, ..self }
+    }
+
+    pub fn set_window(self, window: Option<f64>) -> Self {
+        SwitchCounter { window, ..self }
+    }
+
+    /// All switch-on events since start, regardless of the window
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    fn count(&mut self, on: bool) -> f64 {
+        self.elapsed += self.sample_time;
+        if on && !self.previous_on {
+            self.total += 1;
+            self.events.push_back(self.elapsed);
+        }
+        self.previous_on = on;
+        match self.window {
+            Some(window) => {
+                while self
+                    .events
+                    .front()
+                    .is_some_and(|t| *t <= self.elapsed - window)
+                {
+                    self.events.pop_front();
+                }
+                self.events.len() as f64
+            }
+            None => self.total as f64,
+        }
+    }
+}
+
+impl Default for SwitchCounter {
+    fn default() -> Self {
+        SwitchCounter {
+            threshold: 0.5,
+            sample_time: 1.0,
+            window: None,
+            previous_on: false,
+            elapsed: 0.0,
+            total: 0,
+            events: VecDeque::new(),
+        }
+    }
+}
+
+impl TransferTimeDomain<f64> for SwitchCounter {
+    fn transfer_td(&mut self, input: f64) -> f64 {
+        self.count(input > self.threshold)
+    }
+
+    fn output_unit(&self, _input_unit: &'static str) -> &'static str {
+        "1"
+    }
+}
+
+impl StateAccess for SwitchCounter {
+    /// Previous input state, elapsed time, total count and the event times
+    /// within the window
+    type State = (bool, f64, u64, VecDeque<f64>);
+
+    fn save_state(&self) -> Self::State {
+        (
+            self.previous_on,
+            self.elapsed,
+            self.total,
+            self.events.clone(),
+        )
+    }
+
+    fn restore_state(&mut self, state: &Self::State) {
+        (self.previous_on, self.elapsed, self.total) = (state.0, state.1, state.2);
+        self.events.clone_from(&state.3);
+    }
+}
+
+impl TypeIdentifier for SwitchCounter {
+    fn short_type_name(&self) -> &'static str {
+        "SwitchCounter"
+    }
+}
+
+impl SampleTime for SwitchCounter {
+    fn sample_time(&self) -> Option<f64> {
+        Some(self.sample_time)
+    }
+}
+
+impl Display for SwitchCounter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "SwitchCounter(sample_time: {}, threshold: {}, window: {:?})",
+            self.sample_time, self.threshold, self.window
+        )
+    }
+}
+
+/// Accumulates `kp * input * sample_time`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Totalizer {
+    pub sample_time: f64,
+    pub kp: f64,
+    /// Ignore negative inputs, e.g. backflow through a flow meter
+    pub positive_only: bool,
+    /// Unit of the total, the input unit if `None`
+    pub unit: Option<&'static str>,
+    total: f64,
+}
+
+impl Totalizer {
+    pub fn set_sample_time_or_default(self, sample_time: f64) -> Self {
+        if sample_time > 0.0 {
+            Totalizer {
+                sample_time,
+                ..self
+            }
+        } else {
+            Totalizer {
+                sample_time: 1.0,
+                ..self
+            }
+        }
+    }
+
+    pub fn set_kp(self, kp: f64) -> Self {
+        Totalizer { kp, ..self }
+    }
+
+    pub fn set_positive_only(self, positive_only: bool) -> Self {
+        Totalizer {
+            positive_only,
+            ..self
+        }
+    }
+
+    pub fn set_unit(self, unit: &'static str) -> Self {
+        Totalizer {
+            unit: Some(unit),
+            ..self
+        }
+    }
+
+    pub fn total(&self) -> f64 {
+        self.total
+    }
+
+    pub fn reset(&mut self) {
+        self.total = 0.0;
+    }
+}
+
+impl Default for Totalizer {
+    fn default() -> Self {
+        Totalizer {
+            sample_time: 1.0,
+            kp: 1.0,
+            positive_only: false,
+            unit: None,
+            total: 0.0,
+        }
+    }
+}
+
+impl TransferTimeDomain<f64> for Totalizer {
+    fn transfer_td(&mut self, input: f64) -> f64 {
+        let input = if self.positive_only {
+            input.max(0.0)
+        } else {
+            input
+        };
+        self.total += self.kp * input * self.sample_time;
+        self.total
+    }
+
+    fn output_unit(&self, input_unit: &'static str) -> &'static str {
+        self.unit.unwrap_or(input_unit)
+    }
+}
+
+impl StateAccess for Totalizer {
+    /// Accumulated total
+    type State = f64;
+
+    fn save_state(&self) -> Self::State {
+        self.total
+    }
+
+    fn restore_state(&mut self, state: &Self::State) {
+        self.total = *state;
+    }
+}
+
+impl TypeIdentifier for Totalizer {
+    fn short_type_name(&self) -> &'static str {
+        "Totalizer"
+    }
+}
+
+impl SampleTime for Totalizer {
+    fn sample_time(&self) -> Option<f64> {
+        Some(self.sample_time)
+    }
+}
+
+impl Display for Totalizer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Totalizer(sample_time: {}, kp: {}, positive_only: {})",
+            self.sample_time, self.kp, self.positive_only
+        )
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::signal::{SquareWave, TimeRange};
+    use crate::sim::{Simulation, SimulationError};
+    use std::string::String;
+
+    #[test]
+    fn test_SwitchCounter_derived_trace() {
+        let mut relay = crate::plant::unit_gain::UnitGain::default();
+        let mut result = Simulation::new(TimeRange::default().set_end(100.0))
+            .run(&SquareWave::default().period(10.0), &mut relay);
+        let starts = result
+            .derive(
+                "starts",
+                "output",
+                &mut SwitchCounter::default().set_window(Some(20.0)),
+            )
+            .unwrap();
+        assert_eq!(starts.meta.source, "SwitchCounter");
+        assert_eq!(starts.values[50], 2.0);
+        assert_eq!(
+            result.derive("x", "missing", &mut Totalizer::default()),
+            Err(SimulationError::UnknownTrace(String::from("missing")))
+        );
+        // a counter sampled at 0.5 would count over a 10 instead of a 20 wide window
+        let mut counter = SwitchCounter::default()
+            .set_sample_time_or_default(0.5)
+            .set_window(Some(20.0));
+        assert!(matches!(
+            result.derive("starts", "output", &mut counter),
+            Err(SimulationError::SampleTime(_))
+        ));
+        assert_eq!(result.traces.len(), 3);
+    }
+
+    #[test]
+    fn test_Totalizer_positive_only() {
+        let mut sut = Totalizer::default()
+            .set_sample_time_or_default(0.5)
+            .set_kp(2.0)
+            .set_positive_only(true)
+            .set_unit("m³");
+        assert_eq!(sut.transfer_td(3.0), 3.0);
+        assert_eq!(sut.transfer_td(-3.0), 3.0);
+        assert_eq!(sut.output_unit("m³/s"), "m³");
+        sut.reset();
+        assert_eq!(sut.total(), 0.0);
+    }
+}
@@ -0,0 +1,129 @@
+//! Edge detectors, like the PLC function blocks `R_TRIG` and `F_TRIG`
+//!
+//! The output is true for exactly one sample after the input changed.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use cb_simulation_util::logic::edge::RisingEdge;
+//! use cb_simulation_util::plant::TransferTimeDomain;
+//!
+//! fn main() {
+//!     let mut sut = RisingEdge::default();
+//!     let output: Vec<bool> = [false, true, true, false, true]
+//!         .iter()
+//!         .map(|u| sut.transfer_td(*u))
+//!         .collect();
+//!     assert_eq!(output, [false, true, false, false, true]);
+//! }
+//! ```
+
+use super::*;
+use crate::plant::snapshot::StateAccess;
+use core::fmt::{self, Display};
+
+/// True for one sample on a false → true transition
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct RisingEdge {
+    previous_input: bool,
+}
+
+/// True for one sample on a true → false transition
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct FallingEdge {
+    previous_input: bool,
+}
+
+impl TransferTimeDomain<bool> for RisingEdge {
+    fn transfer_td(&mut self, input: bool) -> bool {
+        let edge = input && !self.previous_input;
+        self.previous_input = input;
+        edge
+    }
+}
+
+impl TransferTimeDomain<bool> for FallingEdge {
+    fn transfer_td(&mut self, input: bool) -> bool {
+        let edge = !input && self.previous_input;
+        self.previous_input = input;
+        edge
+    }
+}
+
+impl StateAccess for RisingEdge {
+    /// Previous input
+    type State = bool;
+
+    fn save_state(&self) -> Self::State {
+        self.previous_input
+    }
+
+    fn restore_state(&mut self, state: &Self::State) {
+        self.previous_input = *state;
+    }
+}
+
+impl TypeIdentifier for RisingEdge {
+    fn short_type_name(&self) -> &'static str {
+        "RisingEdge"
+    }
+}
+
+impl StateAccess for FallingEdge {
+    /// Previous input
+    type State = bool;
+
+    fn save_state(&self) -> Self::State {
+        self.previous_input
+    }
+
+    fn restore_state(&mut self, state: &Self::State) {
+        self.previous_input = *state;
+    }
+}
+
+impl TypeIdentifier for FallingEdge {
+    fn short_type_name(&self) -> &'static str {
+        "FallingEdge"
+    }
+}
+
+impl SampleTime for RisingEdge {
+    fn sample_time(&self) -> Option<f64> {
+        None
+    }
+}
+
+impl SampleTime for FallingEdge {
+    fn sample_time(&self) -> Option<f64> {
+        None
+    }
+}
+
+impl Display for RisingEdge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "RisingEdge()")
+    }
+}
+
+impl Display for FallingEdge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "FallingEdge()")
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_FallingEdge() {
+        let mut sut = FallingEdge::default();
+        assert!(!sut.transfer_td(false));
+        assert!(!sut.transfer_td(true));
+        assert!(sut.transfer_td(false));
+        assert!(!sut.transfer_td(false));
+    }
+}
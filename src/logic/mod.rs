@@ -0,0 +1,12 @@
+//! # Logic blocks
+//!
+//! Stateful blocks on boolean signals - edge detectors and the standard PLC
+//! timers - for simulating supervisory logic, interlocks and sequences next
+//! to the continuous loop. Like the plant elements, they are transfer
+//! elements sampled with a fixed sample time.
+
+pub mod counter;
+pub mod edge;
+pub mod timer;
+
+pub use crate::plant::{SampleTime, TransferTimeDomain, TypeIdentifier};
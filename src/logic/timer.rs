@@ -0,0 +1,244 @@
+//! PLC timers on boolean signals
+//!
+//! * `OnDelay` (`TON`): the output follows a rising input after `delay`,
+//!   input pulses shorter than `delay` are suppressed
+//! * `OffDelay` (`TOF`): the output follows a falling input after `delay`,
+//!   short drop-outs of the input are bridged
+//! * `Pulse` (`TP`): a rising input starts an output pulse of length
+//!   `duration`, independent of the input length - a pulse stretcher.
+//!   Rising edges during a running pulse are ignored.
+//!
+//! Time advances by `sample_time` per call of `transfer_td`. A delay counts
+//! from the sample of the input edge, so the output of `OnDelay` and
+//! `OffDelay` changes at the first sample at least `delay` after it.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use cb_simulation_util::logic::timer::OnDelay;
+//! use cb_simulation_util::plant::TransferTimeDomain;
+//!
+//! fn main() {
+//!     // compressor may only start after the request is stable for 3 s
+//!     let mut sut = OnDelay::default().set_delay(3.0);
+//!     let output: Vec<bool> = [true, false, true, true, true, true, true, false]
+//!         .iter()
+//!         .map(|u| sut.transfer_td(*u))
+//!         .collect();
+//!     // rising at 2 s, switched on at 5 s
+//!     assert_eq!(output, [false, false, false, false, false, true, true, false]);
+//! }
+//! ```
+
+use super::*;
+use crate::plant::snapshot::StateAccess;
+use core::fmt::{self, Display};
+
+macro_rules! timer {
+    ($timer:ident, $time:ident, $doc:literal) => {
+        #[doc = $doc]
+        #[derive(Debug, Clone, Copy, PartialEq)]
+        pub struct $timer {
+            pub $time: f64,
+            pub sample_time: f64,
+            elapsed: f64,
+            output: bool,
+        }
+
+        impl $timer {
+            pub fn set_sample_time_or_default(self, sample_time: f64) -> Self {
+                if sample_time > 0.0 {
+                    $timer {
+                        sample_time,
+                        ..self
+                    }
+                } else {
+                    $timer {
+                        sample_time: 1.0,
+                        ..self
+                    }
+                }
+            }
+
+            /// Time elapsed since the timer started until the end of the
+            /// current sample, 0 if not running
+            pub fn elapsed(&self) -> f64 {
+                self.elapsed
+            }
+        }
+
+        impl Default for $timer {
+            fn default() -> Self {
+                $timer {
+                    $time: 1.0,
+                    sample_time: 1.0,
+                    elapsed: 0.0,
+                    output: false,
+                }
+            }
+        }
+
+        impl StateAccess for $timer {
+            /// Elapsed time and output
+            type State = (f64, bool);
+
+            fn save_state(&self) -> Self::State {
+                (self.elapsed, self.output)
+            }
+
+            fn restore_state(&mut self, state: &Self::State) {
+                (self.elapsed, self.output) = *state;
+            }
+        }
+
+        impl TypeIdentifier for $timer {
+            fn short_type_name(&self) -> &'static str {
+                stringify!($timer)
+            }
+        }
+
+        impl SampleTime for $timer {
+            fn sample_time(&self) -> Option<f64> {
+                Some(self.sample_time)
+            }
+        }
+
+        impl Display for $timer {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(
+                    f,
+                    "{}(sample_time: {}, {}: {})",
+                    self.short_type_name(),
+                    self.sample_time,
+                    stringify!($time),
+                    self.$time
+                )
+            }
+        }
+    };
+}
+
+timer!(OnDelay, delay, "Switch-on delay, `TON`");
+timer!(OffDelay, delay, "Switch-off delay, `TOF`");
+timer!(
+    Pulse,
+    duration,
+    "Pulse of fixed length on a rising edge, `TP`"
+);
+
+// small tolerance, so accumulated sample times reach the configured time
+const TIME_EPSILON: f64 = 1e-9;
+
+impl OnDelay {
+    pub fn set_delay(self, delay: f64) -> Self {
+        OnDelay { delay, ..self }
+    }
+}
+
+impl TransferTimeDomain<bool> for OnDelay {
+    fn transfer_td(&mut self, input: bool) -> bool {
+        if input {
+            if !self.output {
+                self.output = self.elapsed >= self.delay - TIME_EPSILON;
+                self.elapsed += self.sample_time;
+            }
+        } else {
+            self.elapsed = 0.0;
+            self.output = false;
+        }
+        self.output
+    }
+}
+
+impl OffDelay {
+    pub fn set_delay(self, delay: f64) -> Self {
+        OffDelay { delay, ..self }
+    }
+}
+
+impl TransferTimeDomain<bool> for OffDelay {
+    fn transfer_td(&mut self, input: bool) -> bool {
+        if input {
+            self.elapsed = 0.0;
+            self.output = true;
+        } else if self.output {
+            self.output = self.elapsed < self.delay - TIME_EPSILON;
+            self.elapsed += self.sample_time;
+            if !self.output {
+                self.elapsed = 0.0;
+            }
+        }
+        self.output
+    }
+}
+
+impl Pulse {
+    pub fn set_duration(self, duration: f64) -> Self {
+        Pulse { duration, ..self }
+    }
+}
+
+impl TransferTimeDomain<bool> for Pulse {
+    fn transfer_td(&mut self, input: bool) -> bool {
+        if self.output {
+            self.elapsed += self.sample_time;
+            if self.elapsed >= self.duration - TIME_EPSILON {
+                self.output = false;
+            }
+        } else if input && self.elapsed == 0.0 {
+            self.output = true;
+        }
+        // a new pulse requires the input to be released first
+        if !self.output && !input {
+            self.elapsed = 0.0;
+        }
+        self.output
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use std::vec::Vec;
+
+    fn run<T: TransferTimeDomain<bool>>(sut: &mut T, input: &[u8]) -> Vec<u8> {
+        input
+            .iter()
+            .map(|u| sut.transfer_td(*u == 1) as u8)
+            .collect()
+    }
+
+    #[test]
+    fn test_OnDelay_suppresses_short_pulses() {
+        let mut sut = OnDelay::default()
+            .set_delay(1.0)
+            .set_sample_time_or_default(0.5);
+        // rising at 1.0 s, switched on at 2.0 s
+        assert_eq!(run(&mut sut, &[1, 0, 1, 1, 1, 1, 0]), [0, 0, 0, 0, 1, 1, 0]);
+        let mut sut = OnDelay::default().set_delay(0.0);
+        assert_eq!(run(&mut sut, &[0, 1, 1, 0]), [0, 1, 1, 0]);
+    }
+
+    #[test]
+    fn test_OffDelay_bridges_dropouts() {
+        let mut sut = OffDelay::default()
+            .set_delay(1.0)
+            .set_sample_time_or_default(0.5);
+        // falling at 1.5 s, switched off at 2.5 s
+        assert_eq!(run(&mut sut, &[1, 0, 1, 0, 0, 0, 0]), [1, 1, 1, 1, 1, 0, 0]);
+        let mut sut = OffDelay::default().set_delay(0.0);
+        assert_eq!(run(&mut sut, &[1, 1, 0, 1]), [1, 1, 0, 1]);
+    }
+
+    #[test]
+    fn test_Pulse_stretches_and_ignores_retrigger() {
+        let mut sut = Pulse::default().set_duration(3.0);
+        assert_eq!(
+            run(&mut sut, &[1, 0, 1, 0, 0, 1, 1, 1, 1, 1, 0, 1]),
+            [1, 1, 1, 0, 0, 1, 1, 1, 0, 0, 0, 1]
+        );
+        assert_eq!(sut.short_type_name(), "Pulse");
+    }
+}
@@ -0,0 +1,222 @@
+//! # Reproducibility manifest
+//!
+//! A `RunManifest` records everything needed to repeat a simulation run
+//! exactly: the crate version, a hash of the run configuration, the time
+//! range and solver, the seeds of all random sources and the parameters of
+//! every block. Written as JSON next to the results, an archived result can
+//! be reproduced later and checked against the hash of its traces.
+//!
+//! The hashes are 64 bit FNV-1a, enough to detect a changed configuration or
+//! result, but not meant to protect against deliberate tampering.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use cb_simulation_util::manifest::RunManifest;
+//! use cb_simulation_util::plant::pt1::PT1;
+//! use cb_simulation_util::signal::{StepFunction, TimeRange};
+//! use cb_simulation_util::sim::Simulation;
+//!
+//! fn main() {
+//!     let range = TimeRange::default().set_end(10.0);
+//!     let mut plant = PT1::<f64>::default().set_t1_time_or_default(2.0);
+//!     let manifest = RunManifest::new("pt1-step", &range).add_block("plant", &plant);
+//!     let result = Simulation::new(range).run(&StepFunction::default(), &mut plant);
+//!     let manifest = manifest.set_result(&result);
+//!     let json = manifest.to_json();
+//!     assert!(json.contains("\"scenario\": \"pt1-step\""));
+//!     assert!(json.contains("\"plant\": \"PT1("));
+//! }
+//! ```
+
+use core::fmt::Write;
+use std::format;
+use std::string::String;
+use std::vec::Vec;
+
+use crate::json;
+use crate::scenario::Diagram;
+use crate::signal::TimeRange;
+use crate::sim::SimResult;
+
+/// 64 bit FNV-1a hash of `bytes`
+pub fn content_hash(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, b| {
+        (hash ^ *b as u64).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunManifest {
+    pub crate_version: &'static str,
+    /// Name of the scenario or run
+    pub scenario: String,
+    /// Hash of the run configuration, e.g. the text of a run file
+    pub config_hash: Option<u64>,
+    pub time_range: TimeRange,
+    /// Integration method and step size control
+    pub solver: String,
+    /// Seeds of the random sources by name
+    pub seeds: Vec<(String, u64)>,
+    /// Parameters of the blocks by name, as written by their `Display`
+    pub parameters: Vec<(String, String)>,
+    /// Hash of the CSV export of the result
+    pub result_hash: Option<u64>,
+}
+
+impl RunManifest {
+    /// Manifest of a fixed step run over `time_range`
+    pub fn new(scenario: &str, time_range: &TimeRange) -> Self {
+        RunManifest {
+            crate_version: env!("CARGO_PKG_VERSION"),
+            scenario: String::from(scenario),
+            config_hash: None,
+            time_range: *time_range,
+            solver: format!("fixed step {}", time_range.sampling_interval),
+            seeds: Vec::new(),
+            parameters: Vec::new(),
+            result_hash: None,
+        }
+    }
+
+    /// Record the hash of the configuration `text`
+    pub fn set_config(self, text: &str) -> Self {
+        RunManifest {
+            config_hash: Some(content_hash(text.as_bytes())),
+            ..self
+        }
+    }
+
+    pub fn set_solver(self, solver: &str) -> Self {
+        RunManifest {
+            solver: String::from(solver),
+            ..self
+        }
+    }
+
+    pub fn add_seed(mut self, name: &str, seed: u64) -> Self {
+        self.seeds.push((String::from(name), seed));
+        self
+    }
+
+    /// Record the parameters of `block`
+    pub fn add_block(mut self, name: &str, block: &dyn core::fmt::Display) -> Self {
+        self.parameters
+            .push((String::from(name), format!("{}", block)));
+        self
+    }
+
+    /// Record the parameters and seeds of `diagram`
+    pub fn add_diagram(mut self, diagram: &dyn Diagram) -> Self {
+        self.parameters.extend(diagram.parameters());
+        self.seeds.extend(diagram.seeds());
+        self
+    }
+
+    /// Record the hash of `result`, see `matches_result`
+    pub fn set_result(self, result: &SimResult) -> Self {
+        RunManifest {
+            result_hash: Some(content_hash(result.to_csv().as_bytes())),
+            ..self
+        }
+    }
+
+    /// Whether `result` is identical to the recorded one
+    pub fn matches_result(&self, result: &SimResult) -> bool {
+        self.result_hash == Some(content_hash(result.to_csv().as_bytes()))
+    }
+
+    pub fn to_json(&self) -> String {
+        let hash = |h: Option<u64>| h.map_or(String::from("null"), |h| format!("\"{:016x}\"", h));
+        let mut out = String::from("{\n");
+        let _ = writeln!(
+            out,
+            "  \"crate\": {},",
+            json::string(env!("CARGO_PKG_NAME"))
+        );
+        let _ = writeln!(
+            out,
+            "  \"crate_version\": {},",
+            json::string(self.crate_version)
+        );
+        let _ = writeln!(out, "  \"scenario\": {},", json::string(&self.scenario));
+        let _ = writeln!(out, "  \"config_hash\": {},", hash(self.config_hash));
+        let range = &self.time_range;
+        let _ = writeln!(
+            out,
+            "  \"time\": {{\"start\": {}, \"end\": {}, \"sampling_interval\": {}, \"unit\": {}}},",
+            json::number(range.start),
+            json::number(range.end),
+            json::number(range.sampling_interval),
+            json::string(range.unit_of_measurement)
+        );
+        let _ = writeln!(out, "  \"solver\": {},", json::string(&self.solver));
+        let seeds: Vec<String> = self
+            .seeds
+            .iter()
+            .map(|(name, seed)| format!("{}: {}", json::string(name), seed))
+            .collect();
+        let _ = writeln!(out, "  \"seeds\": {{{}}},", seeds.join(", "));
+        out.push_str("  \"parameters\": {");
+        for (i, (name, value)) in self.parameters.iter().enumerate() {
+            let _ = write!(
+                out,
+                "{}\n    {}: {}",
+                if i > 0 { "," } else { "" },
+                json::string(name),
+                json::string(value)
+            );
+        }
+        out.push_str(if self.parameters.is_empty() {
+            "},\n"
+        } else {
+            "\n  },\n"
+        });
+        let _ = writeln!(out, "  \"result_hash\": {}", hash(self.result_hash));
+        out.push_str("}\n");
+        out
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::plant::pt1::PT1;
+    use crate::signal::StepFunction;
+    use crate::sim::Simulation;
+
+    #[test]
+    fn test_content_hash() {
+        // reference values of FNV-1a 64
+        assert_eq!(content_hash(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(content_hash(b"a"), 0xaf63_dc4c_8601_ec8c);
+    }
+
+    #[test]
+    fn test_RunManifest_json_and_result_check() {
+        let range = TimeRange::default().set_end(5.0);
+        let mut plant = PT1::<f64>::default();
+        let run =
+            |plant: &mut PT1<f64>| Simulation::new(range).run(&StepFunction::default(), plant);
+        let manifest = RunManifest::new("step \"1\"", &range)
+            .set_config("scenario = 1")
+            .add_seed("noise", 42)
+            .add_block("plant", &plant)
+            .set_result(&run(&mut plant.clone()));
+        assert!(manifest.matches_result(&run(&mut plant)));
+        assert!(
+            !manifest.matches_result(&run(&mut PT1::<f64>::default().set_t1_time_or_default(3.0)))
+        );
+        let json = manifest.to_json();
+        assert!(json.contains("\"scenario\": \"step \\\"1\\\"\","));
+        assert!(json.contains("\"seeds\": {\"noise\": 42},"));
+        assert!(json.contains(&format!(
+            "\"config_hash\": \"{:016x}\"",
+            content_hash(b"scenario = 1")
+        )));
+        let empty = RunManifest::new("empty", &range).to_json();
+        assert!(empty.contains("\"parameters\": {},\n  \"result_hash\": null\n}"));
+    }
+}
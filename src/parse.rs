@@ -0,0 +1,110 @@
+//! # Parsing of `Display` output
+//!
+//! Elements and signals which implement `FromStr` read back exactly what
+//! their `Display` impl prints, e.g. `PT1(sample_time: 1, t1_time 5, kp: 2)`,
+//! so an element description logged in one run reconstructs the element in
+//! another. Only the parameters are printed, a parsed element starts in its
+//! initial state.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use cb_simulation_util::hysteresis::Hysteresis;
+//! use cb_simulation_util::parse::ParseError;
+//!
+//! fn main() {
+//!     let text = "Hysteresis(lower: 0.5, upper: 1, lower_m: 1, lower_n: 0, upper_m: 1, upper_n: 1)";
+//!     let hysteresis: Hysteresis<f64> = text.parse().unwrap();
+//!     assert_eq!(hysteresis.to_string(), text);
+//!     assert_eq!(
+//!         "Hysteresis(lower: 0.5)".parse::<Hysteresis<f64>>(),
+//!         Err(ParseError::Field("upper"))
+//!     );
+//! }
+//! ```
+
+use core::fmt;
+use core::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseError {
+    /// The text is not printed as `Name(...)` of the expected name
+    Type(&'static str),
+    /// The field is missing or not in the printed order
+    Field(&'static str),
+    /// The value of the field is malformed or out of range
+    Value(&'static str),
+    /// There is text left after the last field
+    Trailing,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::Type(name) => write!(f, "Expected {}(...)", name),
+            ParseError::Field(key) => write!(f, "Missing field {}", key),
+            ParseError::Value(key) => write!(f, "Invalid value of {}", key),
+            ParseError::Trailing => write!(f, "Unexpected text after the last field"),
+        }
+    }
+}
+
+/// The fields of `Name(key: value, ...)`, read in the printed order
+pub(crate) struct Fields<'a> {
+    rest: &'a str,
+    first: bool,
+}
+
+impl<'a> Fields<'a> {
+    /// Fields of `text` printed as `name(...)`
+    pub(crate) fn new(text: &'a str, name: &'static str) -> Result<Self, ParseError> {
+        text.strip_prefix(name)
+            .and_then(|text| text.strip_prefix('('))
+            .and_then(|text| text.strip_suffix(')'))
+            .map(|rest| Fields { rest, first: true })
+            .ok_or(ParseError::Type(name))
+    }
+
+    /// Value of the next field, printed as `key`, `separator` and the value
+    pub(crate) fn next<T: FromStr>(
+        &mut self,
+        key: &'static str,
+        separator: &str,
+    ) -> Result<T, ParseError> {
+        self.optional(key, separator)?.ok_or(ParseError::Field(key))
+    }
+
+    /// Like `next` for a field which is only printed if not default,
+    /// `None` if the next field is another one or there is none
+    pub(crate) fn optional<T: FromStr>(
+        &mut self,
+        key: &'static str,
+        separator: &str,
+    ) -> Result<Option<T>, ParseError> {
+        let field = if self.first {
+            Some(self.rest)
+        } else {
+            self.rest.strip_prefix(", ")
+        };
+        let Some(value) = field
+            .and_then(|field| field.strip_prefix(key))
+            .and_then(|field| field.strip_prefix(separator))
+        else {
+            return Ok(None);
+        };
+        let end = value.find(", ").unwrap_or(value.len());
+        let parsed = value[..end].parse().map_err(|_| ParseError::Value(key))?;
+        self.rest = &value[end..];
+        self.first = false;
+        Ok(Some(parsed))
+    }
+
+    /// Fails if there are fields left
+    pub(crate) fn end(self) -> Result<(), ParseError> {
+        if self.rest.is_empty() {
+            Ok(())
+        } else {
+            Err(ParseError::Trailing)
+        }
+    }
+}
@@ -0,0 +1,309 @@
+//! An actuator model combining dead time, saturation and rate limiting
+//!
+//! The command passes the blocks in the order of a real actuator:
+//!
+//! 1. dead time $T_{0}$ of the command transmission, see `DeadTime`
+//! 2. saturation to the travel range $ [u_{min}, u_{max}] $, see `Saturation`
+//! 3. rate limit of the drive, see `RateLimiter`
+//!
+//! As the rate limiter follows the saturated command, the actuator position
+//! never leaves the travel range and leaves a limit as soon as the command
+//! returns into the range.
+//!
+//! The model is configured with `ActuatorModelBuilder`, every part left out
+//! has no effect.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use cb_simulation_util::plant::TransferTimeDomain;
+//! use cb_simulation_util::plant::actuator_model::ActuatorModelBuilder;
+//!
+//! fn main() {
+//!     // valve 0..100 %, 20 %/s drive, 1 s dead time, sampled every 0.5 s
+//!     let mut valve = ActuatorModelBuilder::<f64>::new()
+//!         .sample_time(0.5)
+//!         .limits(0.0, 100.0)
+//!         .rates(20.0, 20.0)
+//!         .dead_time(1.0)
+//!         .build()
+//!         .unwrap();
+//!     let out: Vec<f64> = (0..6).map(|_| valve.transfer_td(150.0)).collect();
+//!     assert_eq!(out, [0.0, 0.0, 10.0, 20.0, 30.0, 40.0]);
+//!     assert!(valve.is_saturated());
+//! }
+//! ```
+
+use std::collections::VecDeque;
+
+use super::dead_time::DeadTime;
+use super::rate_limiter::RateLimiter;
+use super::saturation::Saturation;
+use super::snapshot::StateAccess;
+use super::*;
+use core::fmt::{self, Display};
+use num_traits::Zero;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ActuatorModel<N> {
+    pub sample_time: f64,
+    /// Dead time of the command
+    delay: DeadTime<N>,
+    saturation: Saturation<N>,
+    rate_limiter: RateLimiter<N>,
+}
+
+impl<N: Copy + PartialOrd> ActuatorModel<N> {
+    pub fn saturation(&self) -> &Saturation<N> {
+        &self.saturation
+    }
+
+    pub fn rate_limiter(&self) -> &RateLimiter<N> {
+        &self.rate_limiter
+    }
+
+    /// Whether the last delayed command was outside the travel range
+    pub fn is_saturated(&self) -> bool {
+        self.saturation.is_saturated()
+    }
+
+    /// Dead time of the command in time units
+    pub fn dead_time(&self) -> f64 {
+        self.delay.t0_time
+    }
+
+    /// Number of samples a command is delayed
+    pub fn delay_samples(&self) -> usize {
+        self.delay.delay_samples()
+    }
+
+    /// The actuator position
+    pub fn state(&self) -> N {
+        self.rate_limiter.state()
+    }
+}
+
+/// Builder of an `ActuatorModel`, all parts are optional
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ActuatorModelBuilder<N> {
+    sample_time: f64,
+    limits: Option<(N, N)>,
+    rates: Option<(f64, f64)>,
+    dead_time: f64,
+    initial_position: Option<N>,
+}
+
+impl<N> ActuatorModelBuilder<N>
+where
+    N: Copy + Zero + PartialOrd,
+    Saturation<N>: Default,
+    RateLimiter<N>: Default,
+    DeadTime<N>: Default,
+{
+    pub fn new() -> Self {
+        ActuatorModelBuilder {
+            sample_time: 1.0,
+            limits: None,
+            rates: None,
+            dead_time: 0.0,
+            initial_position: None,
+        }
+    }
+
+    pub fn sample_time(mut self, sample_time: f64) -> Self {
+        self.sample_time = sample_time;
+        self
+    }
+
+    /// Travel range of the actuator
+    pub fn limits(mut self, min: N, max: N) -> Self {
+        self.limits = Some((min, max));
+        self
+    }
+
+    /// Rising and falling rate of the drive per time unit
+    pub fn rates(mut self, rising_rate: f64, falling_rate: f64) -> Self {
+        self.rates = Some((rising_rate, falling_rate));
+        self
+    }
+
+    pub fn dead_time(mut self, dead_time: f64) -> Self {
+        self.dead_time = dead_time;
+        self
+    }
+
+    /// Actuator position at simulation start, 0 by default
+    pub fn initial_position(mut self, position: N) -> Self {
+        self.initial_position = Some(position);
+        self
+    }
+
+    /// Fails for a sample time <= 0, a negative or non-finite dead time,
+    /// limits with `min > max`, rates <= 0 or an initial position outside the
+    /// limits
+    pub fn build(&self) -> Result<ActuatorModel<N>, &'static str> {
+        if !(self.sample_time > 0.0 && self.sample_time.is_finite()) {
+            return Err("Invalid sample_time: Must be > 0.0");
+        }
+        let mut delay = DeadTime::<N>::default()
+            .set_sample_time_or_default(self.sample_time)
+            .set_t0_time_or_default(self.dead_time);
+        if delay.t0_time != self.dead_time {
+            return Err("Invalid dead_time: Must be >= 0.0 and finite");
+        }
+        let mut saturation = Saturation::<N>::default();
+        if let Some((min, max)) = self.limits {
+            saturation = saturation.set_limits(min, max)?;
+        }
+        let mut rate_limiter =
+            RateLimiter::<N>::default().set_sample_time_or_default(self.sample_time);
+        if let Some((rising_rate, falling_rate)) = self.rates {
+            rate_limiter = rate_limiter.set_rates(rising_rate, falling_rate)?;
+        }
+        let position = self.initial_position.unwrap_or_else(N::zero);
+        if position < saturation.min || position > saturation.max {
+            return Err("Invalid initial_position: Must be within the limits");
+        }
+        delay.fill(position);
+        Ok(ActuatorModel {
+            sample_time: self.sample_time,
+            delay,
+            saturation,
+            rate_limiter: rate_limiter.reset(position),
+        })
+    }
+}
+
+impl<N> Default for ActuatorModelBuilder<N>
+where
+    N: Copy + Zero + PartialOrd,
+    Saturation<N>: Default,
+    RateLimiter<N>: Default,
+    DeadTime<N>: Default,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<N: Copy + fmt::Debug + PartialEq> StateAccess for ActuatorModel<N> {
+    /// Saturation flag, position of the rate limiter and the delay line
+    type State = (bool, N, VecDeque<N>);
+
+    fn save_state(&self) -> Self::State {
+        (
+            self.saturation.save_state(),
+            self.rate_limiter.save_state(),
+            self.delay.save_state(),
+        )
+    }
+
+    fn restore_state(&mut self, state: &Self::State) {
+        if state.2.len() <= self.delay.delay_samples() {
+            self.saturation.restore_state(&state.0);
+            self.rate_limiter.restore_state(&state.1);
+            self.delay.restore_state(&state.2);
+        }
+    }
+}
+
+impl<N> TypeIdentifier for ActuatorModel<N> {
+    fn short_type_name(&self) -> &'static str {
+        "ActuatorModel"
+    }
+}
+
+impl<N> SampleTime for ActuatorModel<N> {
+    fn sample_time(&self) -> Option<f64> {
+        Some(self.sample_time)
+    }
+}
+
+impl<N: Display> Display for ActuatorModel<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "ActuatorModel(sample_time: {}, dead_time: {}, min: {}, max: {}, rising_rate: {}, falling_rate: {})",
+            self.sample_time,
+            self.delay.t0_time,
+            self.saturation.min,
+            self.saturation.max,
+            self.rate_limiter.rising_rate,
+            self.rate_limiter.falling_rate
+        )
+    }
+}
+
+impl<N: Copy + PartialOrd + Zero> TransferTimeDomain<N> for ActuatorModel<N>
+where
+    RateLimiter<N>: TransferTimeDomain<N>,
+{
+    fn transfer_td(&mut self, input: N) -> N {
+        let command = self.delay.delay(input);
+        let limited = self.saturation.transfer_td(command);
+        self.rate_limiter.transfer_td(limited)
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use std::vec::Vec;
+
+    #[test]
+    fn test_ActuatorModel_default_passes_through() {
+        let mut sut = ActuatorModelBuilder::<f64>::default().build().unwrap();
+        assert_eq!(sut.transfer_td(-1.0e9), -1.0e9);
+        assert_eq!(sut.delay_samples(), 0);
+        assert!(!sut.is_saturated());
+    }
+
+    #[test]
+    fn test_ActuatorModel_dead_time_starts_at_initial_position() {
+        let mut sut = ActuatorModelBuilder::<f64>::new()
+            .dead_time(2.0)
+            .initial_position(3.0)
+            .build()
+            .unwrap();
+        assert_eq!((sut.dead_time(), sut.delay_samples()), (2.0, 2));
+        let out: Vec<f64> = (0..3).map(|_| sut.transfer_td(5.0)).collect();
+        assert_eq!(out, [3.0, 3.0, 5.0]);
+    }
+
+    #[test]
+    fn test_ActuatorModel_i32_leaves_limit_at_once() {
+        let mut sut = ActuatorModelBuilder::<i32>::new()
+            .limits(-50, 50)
+            .rates(40.0, 40.0)
+            .initial_position(50)
+            .build()
+            .unwrap();
+        let out: Vec<i32> = [1000, 1000, 0, 0, -1000]
+            .iter()
+            .map(|u| sut.transfer_td(*u))
+            .collect();
+        assert_eq!(out, [50, 50, 10, 0, -40]);
+        assert_eq!(sut.state(), -40);
+    }
+
+    #[test]
+    fn test_ActuatorModelBuilder_invalid() {
+        let builder = ActuatorModelBuilder::<f64>::new();
+        assert!(builder.sample_time(0.0).build().is_err());
+        assert!(builder.dead_time(-1.0).build().is_err());
+        assert!(builder.dead_time(f64::INFINITY).build().is_err());
+        assert!(builder.sample_time(f64::NAN).build().is_err());
+        assert!(builder.limits(1.0, -1.0).build().is_err());
+        assert!(builder.rates(0.0, 1.0).build().is_err());
+        assert!(builder.limits(1.0, 2.0).build().is_err());
+        assert!(
+            builder
+                .limits(1.0, 2.0)
+                .initial_position(1.5)
+                .build()
+                .is_ok()
+        );
+    }
+}
@@ -0,0 +1,387 @@
+//! # Assertion blocks
+//!
+//! Pass-through elements checking the signal flowing through them.
+//! Violations do not stop the simulation, they are recorded and reported
+//! afterwards, so sweep and CI runs can verify requirements like
+//! "overshoot < 10%" automatically.
+//!
+//! - `RangeAssert` - value within `[min, max]`
+//! - `RateAssert` - absolute rate of change below a limit
+//! - `SettleAssert` - within a tolerance band around a target after a settle time
+//!
+//! ## Example
+//!
+//! ```rust
+//! use cb_simulation_util::plant::TransferTimeDomain;
+//! use cb_simulation_util::plant::assertion::{check_all, RangeAssert, SettleAssert};
+//! use cb_simulation_util::plant::pt2::PT2;
+//!
+//! fn main() {
+//!     let mut plant = PT2::<f64>::default()
+//!         .set_sample_time_or_default(0.1)
+//!         .set_damping_or_default(0.3);
+//!     let mut overshoot = RangeAssert::new("overshoot < 10%", f64::MIN, 1.1);
+//!     let mut settle = SettleAssert::new("settled after 20", 1.0, 0.02, 20.0).set_sample_time(0.1);
+//!     for _ in 0..400 {
+//!         let y = plant.transfer_td(1.0);
+//!         settle.transfer_td(overshoot.transfer_td(y));
+//!     }
+//!     let report = check_all(&[&overshoot, &settle]).unwrap_err();
+//!     assert_eq!(report.violations.len(), 1);
+//!     assert_eq!(report.violations[0].assertion, "overshoot < 10%");
+//! }
+//! ```
+
+use super::*;
+use core::fmt::{self, Display};
+use std::vec::Vec;
+
+/// Summary of all samples violating one assertion
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Violation {
+    pub assertion: &'static str,
+    /// Time of the first violating sample
+    pub first_time: f64,
+    /// Time of the last violating sample
+    pub last_time: f64,
+    pub samples: usize,
+    /// Value with the largest distance to the allowed region
+    pub worst_value: f64,
+    worst_excess: f64,
+}
+
+impl Violation {
+    fn new(assertion: &'static str) -> Self {
+        Violation {
+            assertion,
+            first_time: 0.0,
+            last_time: 0.0,
+            samples: 0,
+            worst_value: 0.0,
+            worst_excess: 0.0,
+        }
+    }
+
+    fn record(&mut self, time: f64, value: f64, excess: f64) {
+        if self.samples == 0 {
+            self.first_time = time;
+            #[cfg(feature = "tracing")]
+            tracing::warn!(
+                assertion = self.assertion,
+                time,
+                value,
+                "assertion violated"
+            );
+        }
+        if self.samples == 0 || excess > self.worst_excess {
+            self.worst_value = value;
+            self.worst_excess = excess;
+        }
+        self.last_time = time;
+        self.samples += 1;
+    }
+}
+
+impl Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} violated {} times between t={} and t={}, worst value {}",
+            self.assertion, self.samples, self.first_time, self.last_time, self.worst_value
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct AssertionReport {
+    pub violations: Vec<Violation>,
+}
+
+impl AssertionReport {
+    pub fn passed(&self) -> bool {
+        self.violations.is_empty()
+    }
+}
+
+impl Display for AssertionReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.passed() {
+            return write!(f, "all assertions passed");
+        }
+        for v in &self.violations {
+            writeln!(f, "{}", v)?;
+        }
+        Ok(())
+    }
+}
+
+pub trait Assertion {
+    /// The recorded violation, `None` if the assertion held for every sample
+    fn violation(&self) -> Option<Violation>;
+}
+
+/// Collect the violations of all assertions, `Err` if at least one was violated
+pub fn check_all(assertions: &[&dyn Assertion]) -> Result<(), AssertionReport> {
+    let report = AssertionReport {
+        violations: assertions.iter().filter_map(|a| a.violation()).collect(),
+    };
+    if report.passed() { Ok(()) } else { Err(report) }
+}
+
+fn violation_if_any(violation: &Violation) -> Option<Violation> {
+    if violation.samples > 0 {
+        Some(*violation)
+    } else {
+        None
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RangeAssert {
+    pub min: f64,
+    pub max: f64,
+    pub sample_time: f64,
+    samples: usize,
+    violation: Violation,
+}
+
+impl RangeAssert {
+    pub fn new(name: &'static str, min: f64, max: f64) -> Self {
+        RangeAssert {
+            min,
+            max,
+            sample_time: 1.0,
+            samples: 0,
+            violation: Violation::new(name),
+        }
+    }
+
+    pub fn set_sample_time(self, sample_time: f64) -> Self {
+        RangeAssert {
+            sample_time,
+            ..self
+        }
+    }
+}
+
+impl TypeIdentifier for RangeAssert {
+    fn short_type_name(&self) -> &'static str {
+        "RangeAssert"
+    }
+}
+
+impl SampleTime for RangeAssert {
+    fn sample_time(&self) -> Option<f64> {
+        Some(self.sample_time)
+    }
+}
+
+impl Display for RangeAssert {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "RangeAssert(name: {}, min: {}, max: {})",
+            self.violation.assertion, self.min, self.max
+        )
+    }
+}
+
+impl TransferTimeDomain<f64> for RangeAssert {
+    fn transfer_td(&mut self, u: f64) -> f64 {
+        let time = self.samples as f64 * self.sample_time;
+        self.samples += 1;
+        let excess = (self.min - u).max(u - self.max);
+        if excess > 0.0 {
+            self.violation.record(time, u, excess);
+        }
+        u
+    }
+}
+
+impl Assertion for RangeAssert {
+    fn violation(&self) -> Option<Violation> {
+        violation_if_any(&self.violation)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateAssert {
+    /// Maximum absolute change per time unit
+    pub max_rate: f64,
+    pub sample_time: f64,
+    samples: usize,
+    previous: f64,
+    violation: Violation,
+}
+
+impl RateAssert {
+    pub fn new(name: &'static str, max_rate: f64) -> Self {
+        RateAssert {
+            max_rate,
+            sample_time: 1.0,
+            samples: 0,
+            previous: 0.0,
+            violation: Violation::new(name),
+        }
+    }
+
+    pub fn set_sample_time(self, sample_time: f64) -> Self {
+        RateAssert {
+            sample_time,
+            ..self
+        }
+    }
+}
+
+impl TypeIdentifier for RateAssert {
+    fn short_type_name(&self) -> &'static str {
+        "RateAssert"
+    }
+}
+
+impl SampleTime for RateAssert {
+    fn sample_time(&self) -> Option<f64> {
+        Some(self.sample_time)
+    }
+}
+
+impl Display for RateAssert {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "RateAssert(name: {}, max_rate: {})",
+            self.violation.assertion, self.max_rate
+        )
+    }
+}
+
+impl TransferTimeDomain<f64> for RateAssert {
+    fn transfer_td(&mut self, u: f64) -> f64 {
+        let time = self.samples as f64 * self.sample_time;
+        if self.samples > 0 {
+            let rate = (u - self.previous).abs() / self.sample_time;
+            if rate > self.max_rate {
+                self.violation.record(time, u, rate - self.max_rate);
+            }
+        }
+        self.samples += 1;
+        self.previous = u;
+        u
+    }
+}
+
+impl Assertion for RateAssert {
+    fn violation(&self) -> Option<Violation> {
+        violation_if_any(&self.violation)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SettleAssert {
+    pub target: f64,
+    /// Allowed deviation relative to the target, e.g. `0.02` for a 2% band
+    pub tolerance: f64,
+    pub settle_time: f64,
+    pub sample_time: f64,
+    samples: usize,
+    violation: Violation,
+}
+
+impl SettleAssert {
+    pub fn new(name: &'static str, target: f64, tolerance: f64, settle_time: f64) -> Self {
+        SettleAssert {
+            target,
+            tolerance,
+            settle_time,
+            sample_time: 1.0,
+            samples: 0,
+            violation: Violation::new(name),
+        }
+    }
+
+    pub fn set_sample_time(self, sample_time: f64) -> Self {
+        SettleAssert {
+            sample_time,
+            ..self
+        }
+    }
+}
+
+impl TypeIdentifier for SettleAssert {
+    fn short_type_name(&self) -> &'static str {
+        "SettleAssert"
+    }
+}
+
+impl SampleTime for SettleAssert {
+    fn sample_time(&self) -> Option<f64> {
+        Some(self.sample_time)
+    }
+}
+
+impl Display for SettleAssert {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "SettleAssert(name: {}, target: {}, tolerance: {}, settle_time: {})",
+            self.violation.assertion, self.target, self.tolerance, self.settle_time
+        )
+    }
+}
+
+impl TransferTimeDomain<f64> for SettleAssert {
+    fn transfer_td(&mut self, u: f64) -> f64 {
+        let time = self.samples as f64 * self.sample_time;
+        self.samples += 1;
+        let excess = (u - self.target).abs() - self.tolerance * self.target.abs();
+        if time >= self.settle_time && excess > 0.0 {
+            self.violation.record(time, u, excess);
+        }
+        u
+    }
+}
+
+impl Assertion for SettleAssert {
+    fn violation(&self) -> Option<Violation> {
+        violation_if_any(&self.violation)
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_RangeAssert_records_worst() {
+        let mut sut = RangeAssert::new("range", -1.0, 1.0);
+        for u in [0.0, 2.0, 3.0, 0.0, -1.5] {
+            assert_eq!(u, sut.transfer_td(u));
+        }
+        let v = sut.violation().unwrap();
+        assert_eq!(v.samples, 3);
+        assert_eq!(v.first_time, 1.0);
+        assert_eq!(v.last_time, 4.0);
+        assert_eq!(v.worst_value, 3.0);
+    }
+
+    #[test]
+    fn test_RateAssert() {
+        let mut sut = RateAssert::new("rate", 1.0).set_sample_time(0.5);
+        for u in [5.0, 5.25, 5.5, 6.5] {
+            sut.transfer_td(u);
+        }
+        let v = sut.violation().unwrap();
+        assert_eq!(v.samples, 1);
+        assert_eq!(v.first_time, 1.5);
+    }
+
+    #[test]
+    fn test_check_all_passed() {
+        let mut sut = SettleAssert::new("settle", 2.0, 0.1, 1.0);
+        sut.transfer_td(0.0);
+        sut.transfer_td(1.9);
+        assert_eq!(check_all(&[&sut]), Ok(()));
+    }
+}
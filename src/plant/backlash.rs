@@ -0,0 +1,124 @@
+//! A backlash aka gear play
+//!
+//! The output follows the input only while the gap of width $w$ is closed:
+//!
+//! $ out[k] = \begin{cases} in[k] - w/2 & in[k] - out[k-1] > w/2 \\ in[k] + w/2 & in[k] - out[k-1] < -w/2 \\ out[k-1] & otherwise \end{cases} $
+//!
+//! Models the play of gearboxes and linkages on a position signal. On each
+//! reversal the output stands still until the input crossed the gap.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use cb_simulation_util::plant::TransferTimeDomain;
+//! use cb_simulation_util::plant::backlash::Backlash;
+//!
+//! fn main() {
+//!     let mut gear = Backlash::default().set_width_or_default(1.0);
+//!     assert_eq!(gear.transfer_td(2.0), 1.5);
+//!     assert_eq!(gear.transfer_td(1.5), 1.5); // reversal, within the gap
+//!     assert_eq!(gear.transfer_td(0.0), 0.5);
+//! }
+//! ```
+
+use super::snapshot::StateAccess;
+use super::*;
+use core::fmt::{self, Display};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Backlash {
+    pub width: f64,
+    previous_output: f64,
+}
+
+impl Backlash {
+    /// Total play, a negative width is replaced by zero
+    pub fn set_width_or_default(self, width: f64) -> Self {
+        Backlash {
+            width: width.max(0.0),
+            ..self
+        }
+    }
+
+    /// Start from `output`
+    pub fn reset(self, output: f64) -> Self {
+        Backlash {
+            previous_output: output,
+            ..self
+        }
+    }
+
+    pub fn state(&self) -> f64 {
+        self.previous_output
+    }
+}
+
+impl Default for Backlash {
+    /// No play
+    fn default() -> Self {
+        Backlash {
+            width: 0.0,
+            previous_output: 0.0,
+        }
+    }
+}
+
+impl StateAccess for Backlash {
+    /// The previous output
+    type State = f64;
+
+    fn save_state(&self) -> Self::State {
+        self.previous_output
+    }
+
+    fn restore_state(&mut self, state: &Self::State) {
+        self.previous_output = *state;
+    }
+}
+
+impl TypeIdentifier for Backlash {
+    fn short_type_name(&self) -> &'static str {
+        "Backlash"
+    }
+}
+
+impl SampleTime for Backlash {
+    fn sample_time(&self) -> Option<f64> {
+        None
+    }
+}
+
+impl Display for Backlash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Backlash(width: {})", self.width)
+    }
+}
+
+impl TransferTimeDomain<f64> for Backlash {
+    fn transfer_td(&mut self, input: f64) -> f64 {
+        let half = 0.5 * self.width;
+        if input - self.previous_output > half {
+            self.previous_output = input - half;
+        } else if input - self.previous_output < -half {
+            self.previous_output = input + half;
+        }
+        self.previous_output
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_Backlash_zero_width_passes_through() {
+        let mut sut = Backlash::default();
+        assert_eq!(sut.transfer_td(0.3), 0.3);
+        assert_eq!(sut.transfer_td(-0.3), -0.3);
+        let mut sut = sut.set_width_or_default(-1.0);
+        assert_eq!(sut.width, 0.0);
+        assert_eq!(sut.transfer_td(1.0), 1.0);
+    }
+}
@@ -0,0 +1,274 @@
+//! # Battery equivalent circuit
+//!
+//! Thevenin model of a battery cell or pack with current input (positive
+//! when discharging) and terminal voltage output:
+//!
+//! $ v[k] = OCV(SoC[k]) - R_{0} i[k] - \sum_{j} v_{j}[k] $
+//!
+//! $ v_{j}[k+1] = e^{-T_s / (R_{j} C_{j})} v_{j}[k] + R_{j} (1 - e^{-T_s / (R_{j} C_{j})}) i[k] $
+//!
+//! $ SoC[k+1] = SoC[k] - \frac{T_s i[k]}{3600 Q} $
+//!
+//! where $OCV$ is the open circuit voltage lookup over the state of charge
+//! (0..1), $R_{0}$ the series resistance, $R_{j} C_{j}$ one or two RC pairs
+//! for the polarization and $Q$ the capacity in Ah. The RC pairs are
+//! discretized exactly for a constant current within a sample.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use cb_simulation_util::plant::TransferTimeDomain;
+//! use cb_simulation_util::plant::battery::Battery;
+//!
+//! fn main() {
+//!     let mut cell = Battery::default().set_soc(1.0);
+//!     let rest = cell.transfer_td(0.0);
+//!     let loaded = cell.transfer_td(10.0);
+//!     // the series resistance drops the voltage immediately
+//!     assert!((rest - loaded - 10.0 * cell.series_resistance).abs() < 1e-9);
+//!     let charged = cell.soc();
+//!     for _ in 0..3600 {
+//!         cell.transfer_td(1.0);
+//!     }
+//!     // 1 A for 1 h discharges the 2.5 Ah cell by 40 %
+//!     assert!((charged - cell.soc() - 0.4).abs() < 1e-9);
+//! }
+//! ```
+
+use std::vec;
+use std::vec::Vec;
+
+use super::map::Map1D;
+use super::snapshot::StateAccess;
+use super::*;
+use core::fmt::{self, Display};
+
+/// Resistance in Ohm and capacitance in F of a polarization RC pair
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RcPair {
+    pub resistance: f64,
+    pub capacitance: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Battery {
+    pub sample_time: f64,
+    /// Capacity in Ah
+    pub capacity: f64,
+    /// Series resistance in Ohm
+    pub series_resistance: f64,
+    /// Open circuit voltage in V over the state of charge 0..1
+    pub ocv: Map1D,
+    rc_pairs: Vec<RcPair>,
+    soc: f64,
+    rc_voltages: Vec<f64>,
+}
+
+impl Battery {
+    pub fn set_sample_time_or_default(self, sample_time: f64) -> Self {
+        if sample_time > 0.0 {
+            Battery {
+                sample_time,
+                ..self
+            }
+        } else {
+            Battery {
+                sample_time: 1.0,
+                ..self
+            }
+        }
+    }
+
+    pub fn set_capacity(self, capacity: f64) -> Result<Self, &'static str> {
+        if capacity > 0.0 {
+            Ok(Battery { capacity, ..self })
+        } else {
+            Err("Invalid capacity: Must be > 0.0")
+        }
+    }
+
+    pub fn set_series_resistance(self, series_resistance: f64) -> Result<Self, &'static str> {
+        if series_resistance >= 0.0 {
+            Ok(Battery {
+                series_resistance,
+                ..self
+            })
+        } else {
+            Err("Invalid series_resistance: Must be >= 0.0")
+        }
+    }
+
+    pub fn set_ocv(self, ocv: Map1D) -> Self {
+        Battery { ocv, ..self }
+    }
+
+    /// One or two RC pairs, the polarization voltages restart at zero
+    pub fn set_rc_pairs(self, rc_pairs: Vec<RcPair>) -> Result<Self, &'static str> {
+        if rc_pairs.is_empty() || rc_pairs.len() > 2 {
+            return Err("Invalid rc_pairs: One or two pairs supported");
+        }
+        if rc_pairs
+            .iter()
+            .any(|p| p.resistance <= 0.0 || p.capacitance <= 0.0)
+        {
+            return Err("Invalid rc_pairs: Resistance and capacitance must be > 0.0");
+        }
+        Ok(Battery {
+            rc_voltages: vec![0.0; rc_pairs.len()],
+            rc_pairs,
+            ..self
+        })
+    }
+
+    /// State of charge, clamped to 0..1
+    pub fn set_soc(self, soc: f64) -> Self {
+        Battery {
+            soc: soc.clamp(0.0, 1.0),
+            ..self
+        }
+    }
+
+    pub fn rc_pairs(&self) -> &[RcPair] {
+        &self.rc_pairs
+    }
+
+    pub fn soc(&self) -> f64 {
+        self.soc
+    }
+
+    /// Voltages across the RC pairs
+    pub fn rc_voltages(&self) -> &[f64] {
+        &self.rc_voltages
+    }
+}
+
+impl Default for Battery {
+    /// A 2.5 Ah NMC cell with two RC pairs, charged 50 %
+    fn default() -> Self {
+        let ocv = Map1D::new(
+            vec![0.0, 0.1, 0.2, 0.4, 0.6, 0.8, 0.9, 1.0],
+            vec![3.0, 3.45, 3.55, 3.65, 3.8, 3.95, 4.05, 4.2],
+        )
+        .expect("breakpoints are ascending");
+        Battery {
+            sample_time: 1.0,
+            capacity: 2.5,
+            series_resistance: 0.02,
+            ocv,
+            rc_pairs: vec![
+                RcPair {
+                    resistance: 0.015,
+                    capacitance: 2000.0,
+                },
+                RcPair {
+                    resistance: 0.02,
+                    capacitance: 40000.0,
+                },
+            ],
+            soc: 0.5,
+            rc_voltages: vec![0.0; 2],
+        }
+    }
+}
+
+impl StateAccess for Battery {
+    /// State of charge and the voltages of the RC pairs
+    type State = (f64, Vec<f64>);
+
+    fn save_state(&self) -> Self::State {
+        (self.soc, self.rc_voltages.clone())
+    }
+
+    /// Ignored for a different number of RC pairs
+    fn restore_state(&mut self, state: &Self::State) {
+        if state.1.len() == self.rc_voltages.len() {
+            self.soc = state.0;
+            self.rc_voltages.clone_from(&state.1);
+        }
+    }
+}
+
+impl TypeIdentifier for Battery {
+    fn short_type_name(&self) -> &'static str {
+        "Battery"
+    }
+}
+
+impl SampleTime for Battery {
+    fn sample_time(&self) -> Option<f64> {
+        Some(self.sample_time)
+    }
+}
+
+impl Display for Battery {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Battery(sample_time: {}, capacity: {}, series_resistance: {}, rc_pairs: {}, soc: {})",
+            self.sample_time,
+            self.capacity,
+            self.series_resistance,
+            self.rc_pairs.len(),
+            self.soc
+        )
+    }
+}
+
+impl TransferTimeDomain<f64> for Battery {
+    fn transfer_td(&mut self, current: f64) -> f64 {
+        let voltage = self.ocv.lookup(self.soc)
+            - self.series_resistance * current
+            - self.rc_voltages.iter().sum::<f64>();
+        for (pair, v) in self.rc_pairs.iter().zip(self.rc_voltages.iter_mut()) {
+            let decay = (-self.sample_time / (pair.resistance * pair.capacitance)).exp();
+            *v = decay * *v + pair.resistance * (1.0 - decay) * current;
+        }
+        self.soc =
+            (self.soc - self.sample_time * current / (3600.0 * self.capacity)).clamp(0.0, 1.0);
+        voltage
+    }
+
+    fn output_unit(&self, _input_unit: &'static str) -> &'static str {
+        "V"
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_Battery_relaxation_after_pulse() {
+        let mut sut = Battery::default()
+            .set_rc_pairs(vec![RcPair {
+                resistance: 0.01,
+                capacitance: 1000.0,
+            }])
+            .unwrap();
+        for _ in 0..100 {
+            sut.transfer_td(5.0);
+        }
+        // tau = 10 s, polarization settled to R * I
+        assert!((sut.rc_voltages()[0] - 0.05).abs() < 1e-5);
+        let ocv = sut.ocv.lookup(sut.soc());
+        let mut relaxed = 0.0;
+        for _ in 0..100 {
+            relaxed = sut.transfer_td(0.0);
+        }
+        assert!((relaxed - ocv).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_Battery_soc_limits_and_validation() {
+        let mut sut = Battery::default().set_soc(0.001);
+        for _ in 0..100 {
+            sut.transfer_td(10.0);
+        }
+        assert_eq!(sut.soc(), 0.0);
+        assert!(Battery::default().set_rc_pairs(vec![]).is_err());
+        assert!(Battery::default().set_capacity(0.0).is_err());
+        assert_eq!(sut.output_unit("A"), "V");
+    }
+}
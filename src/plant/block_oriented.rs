@@ -0,0 +1,224 @@
+//! # Block oriented nonlinear models
+//!
+//! Mildly nonlinear plants are commonly modelled by a static nonlinearity
+//! and a linear dynamic block in series:
+//!
+//! * `Hammerstein`: nonlinearity → linear block, e.g. a valve characteristic
+//!   in front of a thermal process
+//! * `Wiener`: linear block → nonlinearity, e.g. a process followed by a
+//!   nonlinear sensor
+//!
+//! Both are elements themselves. `analysis::identification` estimates them
+//! from measured data.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use cb_simulation_util::plant::TransferTimeDomain;
+//! use cb_simulation_util::plant::block_oriented::{Hammerstein, Wiener};
+//! use cb_simulation_util::plant::polynomial::Polynomial;
+//! use cb_simulation_util::plant::pt1::PT1;
+//!
+//! fn main() {
+//!     let square = Polynomial::new(vec![0.0, 0.0, 1.0]);
+//!     let lag = PT1::<f64>::default().set_t1_time_or_default(2.0);
+//!     let mut hammerstein = Hammerstein::new(square.clone(), lag);
+//!     let mut wiener = Wiener::new(lag, square);
+//!     assert_eq!(hammerstein.transfer_td(2.0), 2.0);
+//!     assert_eq!(wiener.transfer_td(2.0), 1.0);
+//! }
+//! ```
+
+use super::*;
+use core::fmt::{self, Display};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Hammerstein<F, L> {
+    pub nonlinearity: F,
+    pub linear: L,
+}
+
+impl<F, L> Hammerstein<F, L> {
+    pub fn new(nonlinearity: F, linear: L) -> Self {
+        Hammerstein {
+            nonlinearity,
+            linear,
+        }
+    }
+}
+
+impl<F, L> TypeIdentifier for Hammerstein<F, L> {
+    fn short_type_name(&self) -> &'static str {
+        "Hammerstein"
+    }
+}
+
+impl<F: SampleTime, L: SampleTime> SampleTime for Hammerstein<F, L> {
+    fn sample_time(&self) -> Option<f64> {
+        common_sample_time([self.nonlinearity.sample_time(), self.linear.sample_time()])
+    }
+}
+
+impl<F: Display, L: Display> Display for Hammerstein<F, L> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Hammerstein(nonlinearity: {}, linear: {})",
+            self.nonlinearity, self.linear
+        )
+    }
+}
+
+impl<N, F: TransferTimeDomain<N>, L: TransferTimeDomain<N>> TransferTimeDomain<N>
+    for Hammerstein<F, L>
+{
+    fn transfer_td(&mut self, u: N) -> N {
+        let v = self.nonlinearity.transfer_td(u);
+        self.linear.transfer_td(v)
+    }
+
+    fn output_unit(&self, input_unit: &'static str) -> &'static str {
+        self.linear
+            .output_unit(self.nonlinearity.output_unit(input_unit))
+    }
+
+    fn check_units(
+        &self,
+        input_unit: &'static str,
+    ) -> Result<&'static str, unit_gain::UnitCheckError> {
+        self.linear
+            .check_units(self.nonlinearity.check_units(input_unit)?)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Wiener<L, F> {
+    pub linear: L,
+    pub nonlinearity: F,
+}
+
+impl<L, F> Wiener<L, F> {
+    pub fn new(linear: L, nonlinearity: F) -> Self {
+        Wiener {
+            linear,
+            nonlinearity,
+        }
+    }
+}
+
+impl<L, F> TypeIdentifier for Wiener<L, F> {
+    fn short_type_name(&self) -> &'static str {
+        "Wiener"
+    }
+}
+
+impl<L: SampleTime, F: SampleTime> SampleTime for Wiener<L, F> {
+    fn sample_time(&self) -> Option<f64> {
+        common_sample_time([self.linear.sample_time(), self.nonlinearity.sample_time()])
+    }
+}
+
+impl<L: Display, F: Display> Display for Wiener<L, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Wiener(linear: {}, nonlinearity: {})",
+            self.linear, self.nonlinearity
+        )
+    }
+}
+
+impl<N, L: TransferTimeDomain<N>, F: TransferTimeDomain<N>> TransferTimeDomain<N> for Wiener<L, F> {
+    fn transfer_td(&mut self, u: N) -> N {
+        let v = self.linear.transfer_td(u);
+        self.nonlinearity.transfer_td(v)
+    }
+
+    fn output_unit(&self, input_unit: &'static str) -> &'static str {
+        self.nonlinearity
+            .output_unit(self.linear.output_unit(input_unit))
+    }
+
+    fn check_units(
+        &self,
+        input_unit: &'static str,
+    ) -> Result<&'static str, unit_gain::UnitCheckError> {
+        self.nonlinearity
+            .check_units(self.linear.check_units(input_unit)?)
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::plant::polynomial::Polynomial;
+    use crate::plant::pt1::PT1;
+    use crate::plant::pt2::PT2;
+    use crate::plant::unit_gain::{Quantity, Unit, UnitGain};
+    use std::string::ToString;
+    use std::vec;
+
+    fn square() -> Polynomial {
+        Polynomial::new(vec![0.0, 0.0, 1.0])
+    }
+
+    fn lag() -> PT1<f64> {
+        PT1::<f64>::default().set_t1_time_or_default(3.0)
+    }
+
+    #[test]
+    fn test_Hammerstein_nonlinearity_first() {
+        let mut sut = Hammerstein::new(square(), lag());
+        let mut reference = lag();
+        for u in [1.0, 2.0, -2.0, 0.5] {
+            assert_eq!(sut.transfer_td(u), reference.transfer_td(u * u));
+        }
+    }
+
+    #[test]
+    fn test_Wiener_linear_first() {
+        let mut sut = Wiener::new(lag(), square());
+        let mut reference = lag();
+        for u in [1.0, 2.0, -2.0, 0.5] {
+            let v = reference.transfer_td(u);
+            assert_eq!(sut.transfer_td(u), v * v);
+        }
+    }
+
+    #[test]
+    fn test_Hammerstein_sample_time() {
+        // the static polynomial fits any sample time
+        assert_eq!(Hammerstein::new(square(), lag()).sample_time(), Some(1.0));
+        let mismatch =
+            Hammerstein::new(lag(), PT2::<f64>::default().set_sample_time_or_default(0.5));
+        assert!(mismatch.sample_time().unwrap().is_nan());
+        assert_eq!(Wiener::new(square(), square()).sample_time(), None);
+    }
+
+    #[test]
+    fn test_Wiener_output_unit() {
+        let transmitter = UnitGain::span(
+            Unit::new("%", Quantity::Ratio, 0.01, 0.0),
+            (0.0, 100.0),
+            Unit::new("mA", Quantity::Current, 1.0e-3, 0.0),
+            (4.0, 20.0),
+        )
+        .unwrap();
+        let sut = Wiener::new(lag(), transmitter);
+        assert_eq!(sut.output_unit("%"), "mA");
+        assert_eq!(sut.check_units("%"), Ok("mA"));
+        let sut = Hammerstein::new(transmitter, lag());
+        assert_eq!(sut.output_unit("%"), "mA");
+        assert!(sut.check_units("mA").is_err());
+        assert_eq!(
+            sut.to_string(),
+            std::format!(
+                "Hammerstein(nonlinearity: {}, linear: {})",
+                sut.nonlinearity,
+                sut.linear
+            )
+        );
+    }
+}
@@ -0,0 +1,322 @@
+//! # Centrifugal compressor with surge and recycle valve
+//!
+//! The Greitzer model of a compressor feeding a plenum volume, which
+//! discharges through the process valve and an anti-surge recycle valve
+//! back to the suction side, in relative units (1 = design point):
+//!
+//! * duct: $ \tau_{d} \dot{q} = \psi(q) - p $
+//! * plenum: $ \tau_{p} \dot{p} = q - (k_{v} d + k_{r} r) \sqrt{p} $
+//!
+//! with the compressor flow $q$, the pressure rise $p$, the speed line
+//! $\psi$ as `Map1D`, the process valve opening $d$ and the recycle valve
+//! opening $r$ (0..1). The state equations are integrated with
+//! `OdeSolver::RungeKutta4`.
+//!
+//! Left of the peak of the speed line, the surge line, the slope is
+//! positive. An operating point there is unstable for a large plenum:
+//! the flow collapses and reverses periodically, the compressor surges.
+//! Opening the recycle valve moves the compressor flow back right of the
+//! surge line while the process flow stays low.
+//!
+//! Inputs are `[recycle opening, process valve opening]`, outputs are
+//! `[compressor flow, pressure rise, surge margin]`, the surge margin is
+//! $ (q - q_{surge}) / q_{surge} $, negative beyond the surge line.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use ndarray::array;
+//! use cb_simulation_util::plant::MimoTransferTimeDomain;
+//! use cb_simulation_util::plant::compressor::Compressor;
+//!
+//! fn main() {
+//!     let mut sut = Compressor::default();
+//!     assert_eq!(sut.surge_flow(), 0.8);
+//!     // half the process demand: the recycle valve keeps the compressor out of surge
+//!     let mut y = array![0.0, 0.0, 0.0];
+//!     for _ in 0..2000 {
+//!         y = sut.transfer_td(array![0.6, 0.5].view());
+//!     }
+//!     assert!(y[2] > 0.1, "{}", y);
+//! }
+//! ```
+
+use ndarray::{Array1, ArrayView1};
+use std::vec;
+
+use super::map::{Extrapolation, Map1D};
+use super::ode::{OdeRhs, OdeSolver};
+use super::snapshot::StateAccess;
+use super::*;
+use core::fmt::{self, Display};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Compressor {
+    pub sample_time: f64,
+    /// Pressure rise over the flow at constant speed
+    pub speed_line: Map1D,
+    /// Time constant of the flow in the duct $\tau_{d}$
+    pub duct_time: f64,
+    /// Time constant of the pressure in the plenum $\tau_{p}$
+    pub plenum_time: f64,
+    /// Flow of the process valve fully open at pressure rise 1
+    pub process_kv: f64,
+    /// Flow of the recycle valve fully open at pressure rise 1
+    pub recycle_kv: f64,
+    /// `[flow, pressure]`
+    state: [f64; 2],
+}
+
+/// State equations for the held valve openings
+struct Dynamics<'a> {
+    compressor: &'a Compressor,
+    recycle: f64,
+    process: f64,
+}
+
+impl OdeRhs for Dynamics<'_> {
+    fn states(&self) -> usize {
+        2
+    }
+
+    fn derivative(&self, state: &[f64], _input: f64, derivative: &mut [f64]) {
+        let c = self.compressor;
+        let (flow, pressure) = (state[0], state[1]);
+        derivative[0] = (c.speed_line.lookup(flow) - pressure) / c.duct_time;
+        derivative[1] = (flow - c.outflow(self.recycle, self.process, pressure)) / c.plenum_time;
+    }
+}
+
+impl Compressor {
+    pub fn set_sample_time_or_default(self, sample_time: f64) -> Self {
+        if sample_time > 0.0 {
+            Compressor {
+                sample_time,
+                ..self
+            }
+        } else {
+            Compressor {
+                sample_time: 1.0,
+                ..self
+            }
+        }
+    }
+
+    /// Speed line, extrapolated linearly beyond its breakpoints
+    pub fn set_speed_line(self, speed_line: Map1D) -> Self {
+        Compressor {
+            speed_line: speed_line.set_extrapolation(Extrapolation::Linear),
+            ..self
+        }
+    }
+
+    pub fn set_time_constants(
+        self,
+        duct_time: f64,
+        plenum_time: f64,
+    ) -> Result<Self, &'static str> {
+        if duct_time > 0.0 && plenum_time > 0.0 {
+            Ok(Compressor {
+                duct_time,
+                plenum_time,
+                ..self
+            })
+        } else {
+            Err("Invalid time constants: Must be > 0.0")
+        }
+    }
+
+    pub fn set_valves(self, process_kv: f64, recycle_kv: f64) -> Result<Self, &'static str> {
+        if process_kv > 0.0 && recycle_kv > 0.0 {
+            Ok(Compressor {
+                process_kv,
+                recycle_kv,
+                ..self
+            })
+        } else {
+            Err("Invalid kv: Must be > 0.0")
+        }
+    }
+
+    /// Start at the flow `flow` and the pressure rise `pressure`
+    pub fn reset(self, flow: f64, pressure: f64) -> Self {
+        Compressor {
+            state: [flow, pressure],
+            ..self
+        }
+    }
+
+    /// Flow through both valves at the pressure rise `pressure`
+    fn outflow(&self, recycle: f64, process: f64, pressure: f64) -> f64 {
+        let kv =
+            self.process_kv * process.clamp(0.0, 1.0) + self.recycle_kv * recycle.clamp(0.0, 1.0);
+        kv * pressure.max(0.0).sqrt()
+    }
+
+    /// Flow at the peak of the speed line
+    pub fn surge_flow(&self) -> f64 {
+        let (flows, pressures) = (self.speed_line.breakpoints(), self.speed_line.values());
+        let peak = (0..pressures.len()).fold(0, |peak, i| {
+            if pressures[i] > pressures[peak] {
+                i
+            } else {
+                peak
+            }
+        });
+        flows[peak]
+    }
+
+    /// Relative distance of `flow` from the surge line
+    pub fn surge_margin(&self, flow: f64) -> f64 {
+        let surge_flow = self.surge_flow();
+        (flow - surge_flow) / surge_flow
+    }
+
+    /// `[flow, pressure]`
+    pub fn state(&self) -> [f64; 2] {
+        self.state
+    }
+}
+
+impl Default for Compressor {
+    /// Speed line of the Moore-Greitzer cubic with its peak at 0.8 flow,
+    /// the design point at flow 1, a plenum 10 times slower than the duct
+    ///
+    /// Surges with the recycle valve closed below about 80 % process valve
+    /// opening, where the valve line crosses the speed line left of its peak.
+    fn default() -> Self {
+        let speed_line = Map1D::new(
+            vec![
+                -0.3, -0.2, -0.1, 0.0, 0.1, 0.2, 0.3, 0.4, 0.5, 0.6, 0.7, 0.8, 0.9, 1.0, 1.1, 1.2,
+                1.3, 1.4,
+            ],
+            vec![
+                0.816, 0.631, 0.53, 0.5, 0.526, 0.594, 0.69, 0.8, 0.91, 1.006, 1.074, 1.1, 1.07,
+                0.969, 0.784, 0.5, 0.104, -0.419,
+            ],
+        )
+        .expect("valid speed line")
+        .set_extrapolation(Extrapolation::Linear);
+        Compressor {
+            sample_time: 0.05,
+            speed_line,
+            duct_time: 0.1,
+            plenum_time: 1.0,
+            process_kv: 1.0,
+            recycle_kv: 0.8,
+            state: [1.0, 0.97],
+        }
+    }
+}
+
+impl StateAccess for Compressor {
+    /// `[flow, pressure]`
+    type State = [f64; 2];
+
+    fn save_state(&self) -> Self::State {
+        self.state
+    }
+
+    fn restore_state(&mut self, state: &Self::State) {
+        self.state = *state;
+    }
+}
+
+impl TypeIdentifier for Compressor {
+    fn short_type_name(&self) -> &'static str {
+        "Compressor"
+    }
+}
+
+impl SampleTime for Compressor {
+    fn sample_time(&self) -> Option<f64> {
+        Some(self.sample_time)
+    }
+}
+
+impl Display for Compressor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Compressor(sample_time: {}, duct_time: {}, plenum_time: {}, process_kv: {}, recycle_kv: {}, surge_flow: {})",
+            self.sample_time,
+            self.duct_time,
+            self.plenum_time,
+            self.process_kv,
+            self.recycle_kv,
+            self.surge_flow()
+        )
+    }
+}
+
+impl MimoTransferTimeDomain for Compressor {
+    fn input_count(&self) -> usize {
+        2
+    }
+
+    fn output_count(&self) -> usize {
+        3
+    }
+
+    fn transfer_td(&mut self, u: ArrayView1<f64>) -> Array1<f64> {
+        // several steps per sample, the duct is fast
+        let substeps = (self.sample_time / (0.1 * self.duct_time)).ceil().max(1.0);
+        let h = self.sample_time / substeps;
+        let mut state = self.state;
+        let dynamics = Dynamics {
+            compressor: self,
+            recycle: u[0],
+            process: u[1],
+        };
+        for _ in 0..substeps as usize {
+            OdeSolver::RungeKutta4.step(&dynamics, &mut state, 0.0, h);
+        }
+        self.state = state;
+        let [flow, pressure] = state;
+        Array1::from_vec(vec![flow, pressure, self.surge_margin(flow)])
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use ndarray::array;
+
+    /// Flow range over the second half of 60 s
+    fn flow_range(sut: &mut Compressor, recycle: f64, process: f64) -> (f64, f64) {
+        let samples = (60.0 / sut.sample_time) as usize;
+        let mut range = (f64::INFINITY, f64::NEG_INFINITY);
+        for k in 0..samples {
+            let flow = sut.transfer_td(array![recycle, process].view())[0];
+            if k >= samples / 2 {
+                range = (range.0.min(flow), range.1.max(flow));
+            }
+        }
+        range
+    }
+
+    #[test]
+    fn test_Compressor_design_point_is_stable() {
+        let mut sut = Compressor::default();
+        let (min, max) = flow_range(&mut sut, 0.0, 1.0);
+        // q = sqrt(psi(q)) near the design point
+        assert!(max - min < 1e-6, "{} {}", min, max);
+        assert!((min - 0.99).abs() < 0.01, "{}", min);
+        let [flow, pressure] = sut.state();
+        assert!((flow - pressure.sqrt()).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_Compressor_surges_without_recycle() {
+        let mut sut = Compressor::default();
+        let (min, max) = flow_range(&mut sut, 0.0, 0.5);
+        // deep surge: the flow collapses, even reverses
+        assert!(min < 0.1 && max > 0.8, "{} {}", min, max);
+        // the recycle valve restores a stable operating point
+        let (min, max) = flow_range(&mut sut, 0.6, 0.5);
+        assert!(max - min < 1e-6, "{} {}", min, max);
+        assert!(sut.surge_margin(min) > 0.1);
+    }
+}
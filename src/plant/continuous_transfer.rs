@@ -0,0 +1,382 @@
+//! A continuous transfer function and its discretization
+//!
+//! $ G(s) = \frac{b_{0} s^{m} + \dots + b_{m}}{a_{0} s^{n} + \dots + a_{n}} $
+//!
+//! with coefficients in descending powers of $s$, as written on paper.
+//! `discretize` turns it into a `DiscreteTransfer` for a given sample time,
+//! instead of deriving a difference equation by hand:
+//!
+//! * `Tustin`: bilinear transform $ s = \frac{2}{T_{s}} \frac{1 - z^{-1}}{1 + z^{-1}} $,
+//!   keeps stability and the DC gain, warps high frequencies
+//! * `TustinPrewarped(omega)`: Tustin with $ \frac{2}{T_{s}} $ replaced by
+//!   $ \frac{\omega}{\tan(\omega T_{s} / 2)} $, so the frequency response at the
+//!   critical angular frequency $\omega$ is kept exactly, e.g. the center of
+//!   a notch or a resonant compensator at a low sample rate
+//! * `ZeroOrderHold`: exact for inputs held constant over a sample,
+//!   i.e. the step response matches at the sample instants
+//! * `BackwardEuler`: $ s = \frac{1 - z^{-1}}{T_{s}} $, simple and always stable
+//! * `RampInvariant`: exact for inputs changing linearly between samples
+//!   (first order hold), i.e. the ramp response matches at the sample instants
+//! * `ImpulseInvariant`: $ h_{d}[k] = T_{s} h(k T_{s}) $, the sampled impulse
+//!   response, e.g. to compare with a measured impulse response; the DC gain
+//!   is not kept and a direct feedthrough is passed through unchanged
+//!
+//! Which method fits best depends on the class of signals driving the block,
+//! so the methods can be compared on the same transfer function.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use cb_simulation_util::plant::TransferTimeDomain;
+//! use cb_simulation_util::plant::continuous_transfer::{ContinuousTransfer, Discretization};
+//!
+//! fn main() {
+//!     // 2 / (5 s + 1)
+//!     let lag = ContinuousTransfer::new(vec![2.0], vec![5.0, 1.0]).unwrap();
+//!     let mut sut = lag.discretize(Discretization::ZeroOrderHold, 0.5).unwrap();
+//!     let mut y = 0.0;
+//!     for _ in 0..10 {
+//!         y = sut.transfer_td(1.0);
+//!     }
+//!     // exact step response at t = 5: 2 (1 - e^-1), the first sample is delayed
+//!     let expected = 2.0 * (1.0 - (-4.5f64 / 5.0).exp());
+//!     assert!((y - expected).abs() < 1e-9);
+//! }
+//! ```
+
+use ndarray::{Array1, Array2};
+use std::vec;
+use std::vec::Vec;
+
+use super::discrete_transfer::DiscreteTransfer;
+use super::*;
+use crate::analysis::poly;
+use crate::linalg;
+use core::fmt::{self, Display};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Discretization {
+    Tustin,
+    /// Tustin prewarped at the angular frequency in rad per time unit
+    ///
+    /// Falls back to `Tustin` unless $ 0 < \omega T_{s} < \pi $.
+    TustinPrewarped(f64),
+    ZeroOrderHold,
+    BackwardEuler,
+    RampInvariant,
+    ImpulseInvariant,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContinuousTransfer {
+    /// Numerator coefficients, descending powers of $s$
+    num: Vec<f64>,
+    /// Denominator coefficients, descending powers of $s$, `den[0] != 0`
+    den: Vec<f64>,
+}
+
+impl ContinuousTransfer {
+    /// Transfer function from coefficients in descending powers of $s$
+    ///
+    /// Fails if the denominator is zero or the transfer function is improper
+    /// (numerator degree greater than denominator degree).
+    pub fn new(num: Vec<f64>, den: Vec<f64>) -> Result<Self, &'static str> {
+        let strip = |c: Vec<f64>| -> Vec<f64> { c.into_iter().skip_while(|x| *x == 0.0).collect() };
+        let (num, den) = (strip(num), strip(den));
+        if den.is_empty() {
+            return Err("Invalid denominator: all coefficients zero");
+        }
+        if num.len() > den.len() {
+            return Err("Improper transfer function: numerator degree exceeds denominator degree");
+        }
+        let num = if num.is_empty() { vec![0.0] } else { num };
+        Ok(ContinuousTransfer { num, den })
+    }
+
+    pub fn numerator(&self) -> &[f64] {
+        &self.num
+    }
+
+    pub fn denominator(&self) -> &[f64] {
+        &self.den
+    }
+
+    /// Order of the denominator
+    pub fn order(&self) -> usize {
+        self.den.len() - 1
+    }
+
+    /// Steady-state gain $G(s = 0)$
+    pub fn dc_gain(&self) -> f64 {
+        self.num[self.num.len() - 1] / self.den[self.den.len() - 1]
+    }
+
+    /// Numerator padded to the denominator length, both normalized with `den[0]`
+    fn normalized(&self) -> (Vec<f64>, Vec<f64>) {
+        let a0 = self.den[0];
+        let mut num = vec![0.0; self.den.len() - self.num.len()];
+        num.extend(self.num.iter().map(|c| c / a0));
+        (num, self.den.iter().map(|c| c / a0).collect())
+    }
+
+    /// Controllable canonical state space form `(A, B, C, D)`
+    pub(crate) fn state_space(&self) -> (Array2<f64>, Array1<f64>, Array1<f64>, f64) {
+        let (b, a) = self.normalized();
+        let n = self.order();
+        let mut a_matrix = Array2::zeros((n, n));
+        for j in 0..n {
+            a_matrix[[0, j]] = -a[j + 1];
+        }
+        for i in 1..n {
+            a_matrix[[i, i - 1]] = 1.0;
+        }
+        let mut b_vector = Array1::zeros(n);
+        if n > 0 {
+            b_vector[0] = 1.0;
+        }
+        let c_vector = (1..=n).map(|i| b[i] - b[0] * a[i]).collect();
+        (a_matrix, b_vector, c_vector, b[0])
+    }
+
+    /// Substitute $s$ by a first order rational function of $z^{-1}$
+    ///
+    /// $ s = \frac{k \cdot p(z^{-1})}{q(z^{-1})} $, coefficients ascending in $z^{-1}$
+    fn substitute(&self, k: f64, p: &[f64], q: &[f64]) -> (Vec<f64>, Vec<f64>) {
+        let (b, a) = self.normalized();
+        let n = self.order();
+        let transform = |coefficients: &[f64]| {
+            coefficients
+                .iter()
+                .enumerate()
+                .fold(vec![0.0], |sum, (i, c)| {
+                    // c * s^(n - i) * q^n
+                    let power = n - i;
+                    let mut term = vec![c * k.powi(power as i32)];
+                    for _ in 0..power {
+                        term = poly::mul(&term, p);
+                    }
+                    for _ in power..n {
+                        term = poly::mul(&term, q);
+                    }
+                    poly::add(&sum, &term)
+                })
+        };
+        (transform(&b), transform(&a))
+    }
+
+    /// $ A_{d} = e^{A T_{s}} $ and the input integrals of a zero and first order hold
+    ///
+    /// $ \Gamma_{1} = \int_{0}^{T_{s}} e^{A \tau} d\tau \, B $ and
+    /// $ \Gamma_{2} = \frac{1}{T_{s}} \int_{0}^{T_{s}} e^{A \tau} (T_{s} - \tau) d\tau \, B $
+    fn hold_integrals(&self, sample_time: f64) -> (Array2<f64>, Array1<f64>, Array1<f64>) {
+        let (a, b, _, _) = self.state_space();
+        let n = self.order();
+        // exp([[A, B, 0], [0, 0, 1 / Ts], [0, 0, 0]] Ts) = [[Ad, G1, G2], [0, 1, 1], [0, 0, 1]]
+        let mut augmented = Array2::zeros((n + 2, n + 2));
+        augmented
+            .slice_mut(ndarray::s![..n, ..n])
+            .assign(&(&a * sample_time));
+        augmented
+            .slice_mut(ndarray::s![..n, n])
+            .assign(&(&b * sample_time));
+        augmented[[n, n + 1]] = 1.0;
+        let phi = linalg::expm(&augmented);
+        (
+            phi.slice(ndarray::s![..n, ..n]).to_owned(),
+            phi.slice(ndarray::s![..n, n]).to_owned(),
+            phi.slice(ndarray::s![..n, n + 1]).to_owned(),
+        )
+    }
+
+    /// Transfer function of $ x[k+1] = A_d x[k] + B_d u[k] $, $ y[k] = C x[k] + D u[k] $
+    fn discrete_transfer(
+        ad: &Array2<f64>,
+        bd: &Array1<f64>,
+        c: &Array1<f64>,
+        d: f64,
+    ) -> (Vec<f64>, Vec<f64>) {
+        let (den, adjugates) = linalg::faddeev_leverrier(ad);
+        let mut num: Vec<f64> = den.iter().map(|coefficient| d * coefficient).collect();
+        for (k, m) in adjugates.iter().enumerate() {
+            num[k + 1] += c.dot(&m.dot(bd));
+        }
+        (num, den)
+    }
+
+    fn zero_order_hold(&self, sample_time: f64) -> (Vec<f64>, Vec<f64>) {
+        let (_, _, c, d) = self.state_space();
+        let (ad, bd, _) = self.hold_integrals(sample_time);
+        Self::discrete_transfer(&ad, &bd, &c, d)
+    }
+
+    fn ramp_invariant(&self, sample_time: f64) -> (Vec<f64>, Vec<f64>) {
+        let (_, _, c, d) = self.state_space();
+        let (ad, g1, g2) = self.hold_integrals(sample_time);
+        // x[k+1] = Ad x[k] + G1 u[k] + G2 (u[k+1] - u[k]), made causal with xi = x - G2 u
+        let bd = &g1 + &ad.dot(&g2) - &g2;
+        Self::discrete_transfer(&ad, &bd, &c, d + c.dot(&g2))
+    }
+
+    fn impulse_invariant(&self, sample_time: f64) -> (Vec<f64>, Vec<f64>) {
+        let (_, b, c, d) = self.state_space();
+        let (ad, _, _) = self.hold_integrals(sample_time);
+        // Ts C (I - Ad z^-1)^-1 B = Ts C B + C (zI - Ad)^-1 Ts Ad B
+        let bd = ad.dot(&b) * sample_time;
+        Self::discrete_transfer(&ad, &bd, &c, d + sample_time * c.dot(&b))
+    }
+
+    /// Discrete equivalent for `sample_time`, fails if it is not finite and > 0.0
+    pub fn discretize(
+        &self,
+        method: Discretization,
+        sample_time: f64,
+    ) -> Result<DiscreteTransfer<f64>, &'static str> {
+        if !(sample_time > 0.0 && sample_time.is_finite()) {
+            return Err("Invalid sample_time: Must be finite and > 0.0");
+        }
+        let (b, a) = match method {
+            Discretization::Tustin => self.substitute(2.0 / sample_time, &[1.0, -1.0], &[1.0, 1.0]),
+            Discretization::TustinPrewarped(omega) => {
+                let k = if omega > 0.0 && omega * sample_time < core::f64::consts::PI {
+                    omega / (omega * sample_time / 2.0).tan()
+                } else {
+                    2.0 / sample_time
+                };
+                self.substitute(k, &[1.0, -1.0], &[1.0, 1.0])
+            }
+            Discretization::BackwardEuler => {
+                self.substitute(1.0 / sample_time, &[1.0, -1.0], &[1.0])
+            }
+            Discretization::ZeroOrderHold => self.zero_order_hold(sample_time),
+            Discretization::RampInvariant => self.ramp_invariant(sample_time),
+            Discretization::ImpulseInvariant => self.impulse_invariant(sample_time),
+        };
+        Ok(DiscreteTransfer::<f64>::new(poly::trim(b), poly::trim(a))?
+            .set_sample_time_or_default(sample_time))
+    }
+}
+
+impl Display for ContinuousTransfer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "ContinuousTransfer(num: {:?}, den: {:?})",
+            self.num, self.den
+        )
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::analysis::discrete_tf::DiscreteTF;
+
+    fn step(sut: &mut DiscreteTransfer<f64>, samples: usize) -> f64 {
+        (0..samples).fold(0.0, |_, _| sut.transfer_td(1.0))
+    }
+
+    #[test]
+    fn test_ContinuousTransfer_first_order_methods() {
+        let lag = ContinuousTransfer::new(vec![1.0], vec![2.0, 1.0]).unwrap();
+        let h = 0.5;
+        let e = (-h / 2.0f64).exp();
+        let zoh = lag.discretize(Discretization::ZeroOrderHold, h).unwrap();
+        assert!((zoh.numerator()[1] - (1.0 - e)).abs() < 1e-12);
+        assert!((zoh.denominator()[1] + e).abs() < 1e-12);
+        let euler = lag.discretize(Discretization::BackwardEuler, h).unwrap();
+        assert!((euler.numerator()[0] - h / (2.0 + h)).abs() < 1e-12);
+        assert!((euler.denominator()[1] + 2.0 / (2.0 + h)).abs() < 1e-12);
+        let mut tustin = lag.discretize(Discretization::Tustin, h).unwrap();
+        assert!((step(&mut tustin, 400) - 1.0).abs() < 1e-9);
+        for sample_time in [0.0, -h, f64::NAN, f64::INFINITY] {
+            assert!(lag.discretize(Discretization::Tustin, sample_time).is_err());
+            assert!(
+                lag.discretize(Discretization::ZeroOrderHold, sample_time)
+                    .is_err()
+            );
+        }
+    }
+
+    #[test]
+    fn test_ContinuousTransfer_second_order_zoh_step() {
+        // 4 / (s^2 + 2 s + 4): omega 2, damping 0.5
+        let sut = ContinuousTransfer::new(vec![4.0], vec![1.0, 2.0, 4.0]).unwrap();
+        assert_eq!(sut.dc_gain(), 1.0);
+        let mut zoh = sut.discretize(Discretization::ZeroOrderHold, 0.1).unwrap();
+        let y = step(&mut zoh, 11);
+        // analytic step response at t = 1.0
+        let wd = 3f64.sqrt();
+        let expected = 1.0 - (-1.0f64).exp() * (wd.cos() + wd.sin() / wd);
+        assert!((y - expected).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_ContinuousTransfer_biproper_and_invalid() {
+        // (s + 2) / (s + 1): direct feedthrough 1
+        let sut = ContinuousTransfer::new(vec![1.0, 2.0], vec![1.0, 1.0]).unwrap();
+        for method in [
+            Discretization::Tustin,
+            Discretization::ZeroOrderHold,
+            Discretization::BackwardEuler,
+            Discretization::RampInvariant,
+        ] {
+            let mut d = sut.discretize(method, 0.01).unwrap();
+            assert!((step(&mut d, 3000) - 2.0).abs() < 1e-6);
+        }
+        assert!(ContinuousTransfer::new(vec![1.0, 0.0], vec![1.0]).is_err());
+        assert!(ContinuousTransfer::new(vec![1.0], vec![0.0]).is_err());
+    }
+
+    #[test]
+    fn test_ContinuousTransfer_ramp_invariant_matches_ramp_response() {
+        // 1 / (2 s + 1): ramp response t - 2 (1 - e^(-t/2))
+        let lag = ContinuousTransfer::new(vec![1.0], vec![2.0, 1.0]).unwrap();
+        let h = 0.5;
+        let mut sut = lag.discretize(Discretization::RampInvariant, h).unwrap();
+        for k in 0..20 {
+            let t = k as f64 * h;
+            let expected = t - 2.0 * (1.0 - (-t / 2.0).exp());
+            assert!((sut.transfer_td(t) - expected).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_ContinuousTransfer_impulse_invariant_samples_impulse_response() {
+        // 4 / (s^2 + 2 s + 4): impulse response 4 / wd e^-t sin(wd t)
+        let sut = ContinuousTransfer::new(vec![4.0], vec![1.0, 2.0, 4.0]).unwrap();
+        let h = 0.1;
+        let mut d = sut.discretize(Discretization::ImpulseInvariant, h).unwrap();
+        let wd = 3f64.sqrt();
+        for k in 0..30 {
+            let t = k as f64 * h;
+            let expected = h * 4.0 / wd * (-t).exp() * (wd * t).sin();
+            let y = d.transfer_td(if k == 0 { 1.0 } else { 0.0 });
+            assert!((y - expected).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_ContinuousTransfer_prewarped_tustin_keeps_notch_frequency() {
+        // (s^2 + 400) / (s^2 + 20 s + 400): notch at 20 rad/s, near the Nyquist frequency of 31.4 rad/s
+        let notch = ContinuousTransfer::new(vec![1.0, 0.0, 400.0], vec![1.0, 20.0, 400.0]).unwrap();
+        let h = 0.1;
+        let center = 20.0 / (2.0 * core::f64::consts::PI);
+        let gain_at_center = |method| {
+            let d = notch.discretize(method, h).unwrap();
+            DiscreteTF::new(d.numerator().to_vec(), d.denominator().to_vec())
+                .frequency_response(center, h)
+                .0
+        };
+        assert!(gain_at_center(Discretization::TustinPrewarped(20.0)) < 1e-9);
+        assert!(gain_at_center(Discretization::Tustin) > 0.3);
+        // out of range falls back to plain Tustin
+        assert_eq!(
+            notch
+                .discretize(Discretization::TustinPrewarped(40.0), h)
+                .unwrap(),
+            notch.discretize(Discretization::Tustin, h).unwrap()
+        );
+    }
+}
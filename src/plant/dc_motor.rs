@@ -0,0 +1,216 @@
+//! A permanent magnet DC motor
+//!
+//! $ L \dot{i} = v - R i - K_{e} \omega $
+//!
+//! $ J \dot{\omega} = K_{t} i - b \omega - \tau_{load} $
+//!
+//! $ \dot{\theta} = \omega $
+//!
+//! with armature resistance $R$ and inductance $L$, back EMF constant
+//! $K_{e}$, torque constant $K_{t}$, rotor inertia $J$ and viscous friction
+//! $b$. Inputs are `[voltage, load_torque]`, outputs `[current, speed, angle]`
+//! in SI units. The linear model is discretized with a zero-order hold.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use ndarray::array;
+//! use cb_simulation_util::plant::MimoTransferTimeDomain;
+//! use cb_simulation_util::plant::dc_motor::DcMotor;
+//!
+//! fn main() {
+//!     let mut sut = DcMotor::default().set_sample_time_or_default(0.001);
+//!     let mut y = array![0.0, 0.0, 0.0];
+//!     for _ in 0..2000 {
+//!         y = sut.transfer_td(array![12.0, 0.0].view());
+//!     }
+//!     // no load speed: v = R i + Ke w and Kt i = b w
+//!     assert!((y[1] - sut.no_load_speed(12.0)).abs() < 1e-3);
+//! }
+//! ```
+
+use ndarray::{Array1, Array2, ArrayView1, array};
+use std::vec;
+
+use super::snapshot::StateAccess;
+use super::state_space::StateSpace;
+use super::*;
+use core::fmt::{self, Display};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DcMotor {
+    /// Armature resistance in Ohm
+    pub resistance: f64,
+    /// Armature inductance in H
+    pub inductance: f64,
+    /// Torque constant in Nm/A, equal to the back EMF constant in Vs/rad
+    pub motor_constant: f64,
+    /// Rotor inertia in kg m²
+    pub inertia: f64,
+    /// Viscous friction in Nm s/rad
+    pub friction: f64,
+    model: StateSpace,
+}
+
+impl DcMotor {
+    fn rebuild(self, sample_time: f64) -> Self {
+        let (r, l, k, j, b) = (
+            self.resistance,
+            self.inductance,
+            self.motor_constant,
+            self.inertia,
+            self.friction,
+        );
+        let state = self.model.state().clone();
+        let mut model = StateSpace::from_continuous(
+            array![[-r / l, -k / l, 0.0], [k / j, -b / j, 0.0], [0.0, 1.0, 0.0]],
+            array![[1.0 / l, 0.0], [0.0, -1.0 / j], [0.0, 0.0]],
+            Array2::eye(3),
+            Array2::zeros((3, 2)),
+            sample_time,
+        )
+        .expect("3 states, 2 inputs always fit");
+        model.set_state(state);
+        DcMotor { model, ..self }
+    }
+
+    pub fn set_sample_time_or_default(self, sample_time: f64) -> Self {
+        let sample_time = if sample_time > 0.0 { sample_time } else { 1.0 };
+        self.rebuild(sample_time)
+    }
+
+    /// Armature resistance and inductance, non-positive values are ignored
+    pub fn set_armature(self, resistance: f64, inductance: f64) -> Self {
+        if resistance <= 0.0 || inductance <= 0.0 {
+            return self;
+        }
+        let sample_time = self.model.sample_time;
+        DcMotor {
+            resistance,
+            inductance,
+            ..self
+        }
+        .rebuild(sample_time)
+    }
+
+    /// Motor constant, inertia and friction, invalid values are ignored
+    pub fn set_mechanics(self, motor_constant: f64, inertia: f64, friction: f64) -> Self {
+        if motor_constant <= 0.0 || inertia <= 0.0 || friction < 0.0 {
+            return self;
+        }
+        let sample_time = self.model.sample_time;
+        DcMotor {
+            motor_constant,
+            inertia,
+            friction,
+            ..self
+        }
+        .rebuild(sample_time)
+    }
+
+    /// Steady state speed in rad/s at `voltage` without load
+    pub fn no_load_speed(&self, voltage: f64) -> f64 {
+        let k = self.motor_constant;
+        voltage * k / (self.resistance * self.friction + k * k)
+    }
+
+    /// The state `[current, speed, angle]`
+    pub fn state(&self) -> &Array1<f64> {
+        self.model.state()
+    }
+}
+
+impl Default for DcMotor {
+    /// Small 24 V servo motor
+    fn default() -> Self {
+        DcMotor {
+            resistance: 1.0,
+            inductance: 1.0e-3,
+            motor_constant: 0.05,
+            inertia: 1.0e-5,
+            friction: 1.0e-5,
+            model: StateSpace::new(
+                Array2::eye(3),
+                Array2::zeros((3, 2)),
+                Array2::eye(3),
+                Array2::zeros((3, 2)),
+            )
+            .expect("3 states, 2 inputs always fit"),
+        }
+        .rebuild(1.0)
+    }
+}
+
+impl StateAccess for DcMotor {
+    /// `[current, speed, angle]`
+    type State = Array1<f64>;
+
+    fn save_state(&self) -> Self::State {
+        self.model.save_state()
+    }
+
+    fn restore_state(&mut self, state: &Self::State) {
+        self.model.restore_state(state);
+    }
+}
+
+impl TypeIdentifier for DcMotor {
+    fn short_type_name(&self) -> &'static str {
+        "DcMotor"
+    }
+}
+
+impl SampleTime for DcMotor {
+    fn sample_time(&self) -> Option<f64> {
+        Some(self.model.sample_time)
+    }
+}
+
+impl Display for DcMotor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "DcMotor(sample_time: {}, resistance: {}, inductance: {}, motor_constant: {}, inertia: {}, friction: {})",
+            self.model.sample_time,
+            self.resistance,
+            self.inductance,
+            self.motor_constant,
+            self.inertia,
+            self.friction
+        )
+    }
+}
+
+impl MimoTransferTimeDomain for DcMotor {
+    fn input_count(&self) -> usize {
+        2
+    }
+
+    fn output_count(&self) -> usize {
+        3
+    }
+
+    fn transfer_td(&mut self, u: ArrayView1<f64>) -> Array1<f64> {
+        self.model.transfer_td(u)
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_DcMotor_stall_current_and_load() {
+        let mut sut = DcMotor::default().set_sample_time_or_default(1.0e-4);
+        // blocked by a load torque equal to the stall torque
+        let stall_torque = 0.05 * 12.0 / 1.0;
+        let mut y = array![0.0, 0.0, 0.0];
+        for _ in 0..1000 {
+            y = sut.transfer_td(array![12.0, stall_torque].view());
+        }
+        assert!((y[0] - 12.0).abs() < 1e-6);
+        assert!(y[1].abs() < 1e-6);
+    }
+}
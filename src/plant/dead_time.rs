@@ -0,0 +1,274 @@
+//! A dead time element without buffer size limit
+//!
+//! $ out[k] = P \cdot in[k - d] $ with $ d = \lfloor T_{0} / T_{s} \rfloor $
+//!
+//! where $T_{s}$ is the sample time, $T_{0}$ the dead time
+//! and $P$ the amplification.
+//!
+//! Same behaviour as `PT0`, but the delay line is a `VecDeque` of
+//! $ d = T_{0} / T_{s} $ samples, computed whenever one of both is set. It
+//! has no size limit and never panics, at the price of not being `Copy`.
+//! The line grows with the first $d$ samples instead of being allocated
+//! when configured, so a long dead time costs memory only once it is
+//! simulated. A negative or non-finite time is invalid and the setters fall
+//! back to the default.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use cb_simulation_util::plant::TransferTimeDomain;
+//! use cb_simulation_util::plant::dead_time::DeadTime;
+//!
+//! fn main() {
+//!     // 2 hours of transport delay sampled every 100 ms
+//!     let mut sut = DeadTime::<f64>::default()
+//!         .set_sample_time_or_default(0.1)
+//!         .set_t0_time_or_default(7200.0);
+//!     assert_eq!(sut.delay_samples(), 72000);
+//!     assert_eq!(sut.transfer_td(1.0), 0.0);
+//! }
+//! ```
+
+use std::collections::VecDeque;
+use std::vec::Vec;
+
+use super::snapshot::StateAccess;
+use super::*;
+use core::fmt::{self, Display};
+use num_traits::Zero;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeadTime<N> {
+    pub t0_time: f64,
+    pub sample_time: f64,
+    pub kp: N,
+    /// $ \lfloor T_{0} / T_{s} \rfloor $, saturating for huge ratios
+    delay_samples: usize,
+    /// The delayed values, oldest first, at most `delay_samples`
+    buffered_output: VecDeque<N>,
+}
+
+impl<N: Zero + Clone> DeadTime<N> {
+    pub fn set_sample_time_or_default(self, sample_time: f64) -> Self {
+        let valid = sample_time > 0.0 && sample_time.is_finite();
+        let sample_time = if valid { sample_time } else { 1.0 };
+        DeadTime::<N> {
+            sample_time,
+            ..self
+        }
+        .resized()
+    }
+
+    pub fn set_t0_time_or_default(self, t0_time: f64) -> Self {
+        let valid = t0_time >= 0.0 && t0_time.is_finite();
+        let t0_time = if valid { t0_time } else { 0.0 };
+        DeadTime::<N> { t0_time, ..self }.resized()
+    }
+
+    /// Empty delay line for the current configuration, the output is 0
+    /// until the line has filled
+    fn resized(mut self) -> Self {
+        self.delay_samples = (self.t0_time / self.sample_time) as usize;
+        self.buffered_output.clear();
+        self
+    }
+
+    /// Delay `input` by the dead time, without the amplification
+    ///
+    /// For models which delay a signal as one of their parts, e.g. the
+    /// latency of a `SensorModel`.
+    pub fn delay(&mut self, input: N) -> N {
+        self.buffered_output.push_back(input);
+        if self.buffered_output.len() > self.delay_samples {
+            self.buffered_output.pop_front().unwrap_or_else(N::zero)
+        } else {
+            N::zero()
+        }
+    }
+
+    /// Set all delayed values to `value`, e.g. to start at an operating point
+    ///
+    /// Allocates the whole delay line.
+    pub fn fill(&mut self, value: N) {
+        self.buffered_output.clear();
+        self.buffered_output.resize(self.delay_samples, value);
+    }
+}
+
+impl<N: Clone> DeadTime<N> {
+    /// Number of samples an input is delayed
+    pub fn delay_samples(&self) -> usize {
+        self.delay_samples
+    }
+
+    /// The delayed values, oldest value first
+    ///
+    /// Shorter than `delay_samples` until the delay line has filled, the
+    /// missing older values are 0.
+    pub fn buffer_state(&self) -> Vec<N> {
+        self.buffered_output.iter().cloned().collect()
+    }
+}
+
+impl<N: Clone + fmt::Debug + PartialEq> StateAccess for DeadTime<N> {
+    /// The delayed values, see `buffer_state`
+    type State = VecDeque<N>;
+
+    fn save_state(&self) -> Self::State {
+        self.buffered_output.clone()
+    }
+
+    /// Ignored if the state holds more than `delay_samples` values
+    fn restore_state(&mut self, state: &Self::State) {
+        if state.len() <= self.delay_samples {
+            self.buffered_output.clone_from(state);
+        }
+    }
+}
+
+impl<N> TypeIdentifier for DeadTime<N> {
+    fn short_type_name(&self) -> &'static str {
+        "DeadTime"
+    }
+}
+
+impl<N> SampleTime for DeadTime<N> {
+    fn sample_time(&self) -> Option<f64> {
+        Some(self.sample_time)
+    }
+}
+
+impl<N: Display> Display for DeadTime<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "DeadTime(sample_time: {}, t0_time {}, kp: {})",
+            self.sample_time, self.t0_time, self.kp
+        )
+    }
+}
+
+impl DeadTime<f64> {
+    pub fn set_kp(self, kp: f64) -> Self {
+        DeadTime::<f64> { kp, ..self }
+    }
+}
+
+impl Default for DeadTime<f64> {
+    fn default() -> Self {
+        DeadTime::<f64> {
+            t0_time: 0.0,
+            sample_time: 1.0,
+            kp: 1.0,
+            delay_samples: 0,
+            buffered_output: VecDeque::new(),
+        }
+    }
+}
+
+impl TransferTimeDomain<f64> for DeadTime<f64> {
+    fn transfer_td(&mut self, input: f64) -> f64 {
+        self.delay(input * self.kp)
+    }
+}
+
+const FIX_KOMMA_SHIFT_BITS: u8 = 10;
+const FIX_KOMMA_SHIFT: i32 = 1 << FIX_KOMMA_SHIFT_BITS;
+
+impl DeadTime<i32> {
+    pub fn set_kp(self, kp: i32) -> Self {
+        DeadTime::<i32> {
+            kp: kp * FIX_KOMMA_SHIFT,
+            ..self
+        }
+    }
+}
+
+impl Default for DeadTime<i32> {
+    fn default() -> Self {
+        DeadTime::<i32> {
+            t0_time: 0.0,
+            sample_time: 1.0,
+            kp: FIX_KOMMA_SHIFT,
+            delay_samples: 0,
+            buffered_output: VecDeque::new(),
+        }
+    }
+}
+
+impl TransferTimeDomain<i32> for DeadTime<i32> {
+    fn transfer_td(&mut self, input: i32) -> i32 {
+        self.delay(input * self.kp) >> FIX_KOMMA_SHIFT_BITS
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::plant::pt0::PT0;
+
+    #[test]
+    fn test_DeadTime_matches_PT0() {
+        let mut sut = DeadTime::<i32>::default()
+            .set_t0_time_or_default(1.5)
+            .set_sample_time_or_default(0.5)
+            .set_kp(2);
+        let mut pt0 = PT0::<i32>::default()
+            .set_sample_time_or_default(0.5)
+            .set_t0_time_or_default(1.5)
+            .set_kp(2);
+        for k in 0..10 {
+            assert_eq!(sut.transfer_td(k * 10), pt0.transfer_td(k * 10));
+        }
+        assert_eq!(sut.delay_samples(), 3);
+    }
+
+    #[test]
+    fn test_DeadTime_beyond_PT0_limit() {
+        let mut sut = DeadTime::<f64>::default().set_t0_time_or_default(5000.0);
+        for k in 0..5000 {
+            assert_eq!(sut.transfer_td(k as f64 + 1.0), 0.0);
+        }
+        assert_eq!(sut.transfer_td(0.0), 1.0);
+        assert_eq!(sut.buffer_state()[0], 2.0);
+    }
+
+    #[test]
+    fn test_DeadTime_long_delay_grows_on_demand() {
+        // 10^10 samples are configured without allocating them
+        let mut sut = DeadTime::<f64>::default()
+            .set_sample_time_or_default(1e-6)
+            .set_t0_time_or_default(1e4);
+        assert_eq!((sut.t0_time, sut.delay_samples()), (1e4, 10_000_000_000));
+        assert_eq!(sut.transfer_td(1.0), 0.0);
+        assert_eq!(sut.buffer_state(), [1.0]);
+        // an overflowing ratio saturates instead of failing
+        let sut = DeadTime::<f64>::default()
+            .set_sample_time_or_default(1e-300)
+            .set_t0_time_or_default(1e300);
+        assert_eq!((sut.t0_time, sut.delay_samples()), (1e300, usize::MAX));
+        // invalid times fall back to the defaults
+        let sut = DeadTime::<f64>::default().set_t0_time_or_default(f64::INFINITY);
+        assert_eq!((sut.t0_time, sut.delay_samples()), (0.0, 0));
+        let sut = DeadTime::<f64>::default().set_t0_time_or_default(f64::NAN);
+        assert_eq!(sut.t0_time, 0.0);
+        let sut = DeadTime::<i32>::default()
+            .set_t0_time_or_default(10.0)
+            .set_sample_time_or_default(f64::NAN);
+        assert_eq!((sut.sample_time, sut.delay_samples()), (1.0, 10));
+    }
+
+    #[test]
+    fn test_DeadTime_delay_without_kp() {
+        let mut sut = DeadTime::<i32>::default()
+            .set_t0_time_or_default(2.0)
+            .set_kp(2);
+        sut.fill(7);
+        assert_eq!(sut.buffer_state(), [7, 7]);
+        assert_eq!(sut.delay(i32::MAX), 7);
+        assert_eq!(sut.delay(0), 7);
+        assert_eq!(sut.delay(0), i32::MAX);
+    }
+}
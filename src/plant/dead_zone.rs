@@ -0,0 +1,134 @@
+//! A dead zone nonlinearity
+//!
+//! $ out[k] = \begin{cases} in[k] - u_{upper} & in[k] > u_{upper} \\ 0 & u_{lower} \le in[k] \le u_{upper} \\ in[k] - u_{lower} & in[k] < u_{lower} \end{cases} $
+//!
+//! Models a band around zero without effect, e.g. the dead band of a
+//! joystick or the breakaway of a sticking valve. Outside the band the input
+//! passes shifted by the band edge, so the output is continuous.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use cb_simulation_util::plant::TransferTimeDomain;
+//! use cb_simulation_util::plant::dead_zone::DeadZone;
+//!
+//! fn main() {
+//!     let mut joystick = DeadZone::<f64>::default().set_width_or_default(0.1);
+//!     assert_eq!(joystick.transfer_td(0.05), 0.0);
+//!     assert_eq!(joystick.transfer_td(0.6), 0.5);
+//!     assert_eq!(joystick.transfer_td(-0.6), -0.5);
+//! }
+//! ```
+
+use super::*;
+use core::fmt::{self, Display};
+use core::ops::{Neg, Sub};
+
+use num_traits::Zero;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DeadZone<N> {
+    pub lower: N,
+    pub upper: N,
+}
+
+impl<N: PartialOrd + Copy + Zero + Neg<Output = N>> DeadZone<N> {
+    /// Band `[lower, upper]`, it must contain zero
+    pub fn set_band(self, lower: N, upper: N) -> Result<Self, &'static str> {
+        if lower <= N::zero() && N::zero() <= upper {
+            Ok(DeadZone { lower, upper })
+        } else {
+            Err("Invalid band: Must be lower <= 0 <= upper")
+        }
+    }
+
+    /// Symmetric band `[-width, width]`, a negative width is replaced by zero
+    pub fn set_width_or_default(self, width: N) -> Self {
+        if width >= N::zero() {
+            DeadZone {
+                lower: -width,
+                upper: width,
+            }
+        } else {
+            DeadZone {
+                lower: N::zero(),
+                upper: N::zero(),
+            }
+        }
+    }
+}
+
+impl Default for DeadZone<f64> {
+    /// No dead zone
+    fn default() -> Self {
+        DeadZone {
+            lower: 0.0,
+            upper: 0.0,
+        }
+    }
+}
+
+impl Default for DeadZone<i32> {
+    /// No dead zone
+    fn default() -> Self {
+        DeadZone { lower: 0, upper: 0 }
+    }
+}
+
+impl<N> TypeIdentifier for DeadZone<N> {
+    fn short_type_name(&self) -> &'static str {
+        "DeadZone"
+    }
+}
+
+impl<N> SampleTime for DeadZone<N> {
+    fn sample_time(&self) -> Option<f64> {
+        None
+    }
+}
+
+impl<N: Display> Display for DeadZone<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "DeadZone(lower: {}, upper: {})", self.lower, self.upper)
+    }
+}
+
+impl<N: PartialOrd + Copy + Zero + Sub<Output = N>> TransferTimeDomain<N> for DeadZone<N> {
+    fn transfer_td(&mut self, input: N) -> N {
+        if input > self.upper {
+            input - self.upper
+        } else if input < self.lower {
+            input - self.lower
+        } else {
+            N::zero()
+        }
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use std::format;
+
+    #[test]
+    fn test_DeadZone_i32_asymmetric_band() {
+        let mut sut = DeadZone::<i32>::default().set_band(-5, 10).unwrap();
+        assert_eq!(sut.transfer_td(10), 0);
+        assert_eq!(sut.transfer_td(-5), 0);
+        assert_eq!(sut.transfer_td(15), 5);
+        assert_eq!(sut.transfer_td(-15), -10);
+        assert!(DeadZone::<i32>::default().set_band(1, 10).is_err());
+    }
+
+    #[test]
+    fn test_DeadZone_f64_default_passes_through() {
+        let mut sut = DeadZone::<f64>::default();
+        assert_eq!(sut.transfer_td(-0.25), -0.25);
+        assert_eq!(
+            format!("{}", sut.set_width_or_default(-1.0)),
+            "DeadZone(lower: 0, upper: 0)"
+        );
+    }
+}
@@ -0,0 +1,217 @@
+//! # Decoupling network for MIMO loops
+//!
+//! Sits between a set of SISO controllers and a MIMO plant and compensates
+//! the interaction between the loops:
+//!
+//! $ out_{i}[k] = \sum_{j} D_{ij}(in_{j})[k] $
+//!
+//! Each path $D_{ij}$ is a boxed element (a gain or any dynamic element) or
+//! absent. `static_inverse` computes the steady state decoupler
+//! $ D = K^{-1} \mathrm{diag}(K) $ from the plant gain matrix $K$, so the
+//! decoupled plant $K D$ keeps the diagonal gains the SISO controllers were
+//! tuned for, while the cross couplings vanish in steady state.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use ndarray::array;
+//! use cb_simulation_util::plant::MimoTransferTimeDomain;
+//! use cb_simulation_util::plant::decoupler::Decoupler;
+//!
+//! fn main() {
+//!     let plant_gain = array![[2.0, 1.0], [1.0, 2.0]];
+//!     let mut sut = Decoupler::static_inverse(&plant_gain).unwrap();
+//!     let u = sut.transfer_td(array![1.0, 0.0].view());
+//!     let y = plant_gain.dot(&u);
+//!     assert!((y[0] - 2.0).abs() < 1e-12);
+//!     assert!(y[1].abs() < 1e-12);
+//! }
+//! ```
+
+use ndarray::{Array1, Array2, ArrayView1};
+
+use super::pt0::PT0;
+use super::*;
+use crate::linalg;
+use core::fmt::{self, Display};
+use std::vec::Vec;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Decoupler {
+    /// `paths[i][j]` acts from input `j` to output `i`
+    paths: Vec<Vec<Option<BoxedTransferTimeDomain<f64>>>>,
+}
+
+impl Decoupler {
+    /// Dynamic decoupler, fails if the rows differ in length
+    pub fn new(
+        paths: Vec<Vec<Option<BoxedTransferTimeDomain<f64>>>>,
+    ) -> Result<Self, &'static str> {
+        let inputs = paths.first().map_or(0, |row| row.len());
+        if paths.iter().any(|row| row.len() != inputs) {
+            return Err("Invalid decoupler: All rows need the same number of paths");
+        }
+        Ok(Decoupler { paths })
+    }
+
+    /// Static decoupler with a gain per path, zero gains leave the path open
+    pub fn from_gains(gains: &Array2<f64>) -> Self {
+        let paths = gains
+            .outer_iter()
+            .map(|row| {
+                row.iter()
+                    .map(|g| {
+                        (*g != 0.0).then(|| {
+                            Box::new(PT0::<f64>::default().set_kp(*g))
+                                as BoxedTransferTimeDomain<f64>
+                        })
+                    })
+                    .collect()
+            })
+            .collect();
+        Decoupler { paths }
+    }
+
+    /// Steady state decoupler $ K^{-1} \mathrm{diag}(K) $ for the plant gain matrix $K$
+    ///
+    /// Fails if $K$ is not square or singular.
+    pub fn static_inverse(plant_gain: &Array2<f64>) -> Result<Self, &'static str> {
+        if !plant_gain.is_square() {
+            return Err("Invalid plant gain: Must be square");
+        }
+        let inverse = linalg::inverse(plant_gain).ok_or("Invalid plant gain: Singular")?;
+        let diagonal = Array2::from_diag(&plant_gain.diag());
+        Ok(Decoupler::from_gains(&inverse.dot(&diagonal)))
+    }
+
+    /// The element acting from input `input` to output `output`, if any
+    pub fn path(&self, output: usize, input: usize) -> Option<&BoxedTransferTimeDomain<f64>> {
+        self.paths.get(output)?.get(input)?.as_ref()
+    }
+
+    /// Replace the element acting from input `input` to output `output`
+    ///
+    /// # Panics
+    /// If `output` or `input` is out of bounds
+    pub fn set_path(
+        &mut self,
+        output: usize,
+        input: usize,
+        path: Option<BoxedTransferTimeDomain<f64>>,
+    ) {
+        self.paths[output][input] = path;
+    }
+}
+
+impl TypeIdentifier for Decoupler {
+    fn short_type_name(&self) -> &'static str {
+        "Decoupler"
+    }
+}
+
+impl SampleTime for Decoupler {
+    /// Common sample time of the paths
+    fn sample_time(&self) -> Option<f64> {
+        common_sample_time(
+            self.paths
+                .iter()
+                .flatten()
+                .flatten()
+                .map(|p| p.sample_time()),
+        )
+    }
+}
+
+impl Display for Decoupler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Decoupler(")?;
+        for (i, row) in self.paths.iter().enumerate() {
+            if i > 0 {
+                write!(f, "; ")?;
+            }
+            for (j, path) in row.iter().enumerate() {
+                if j > 0 {
+                    write!(f, ", ")?;
+                }
+                match path {
+                    Some(element) => write!(f, "{}", element)?,
+                    None => write!(f, "-")?,
+                }
+            }
+        }
+        write!(f, ")")
+    }
+}
+
+impl MimoTransferTimeDomain for Decoupler {
+    fn input_count(&self) -> usize {
+        self.paths.first().map_or(0, |row| row.len())
+    }
+
+    fn output_count(&self) -> usize {
+        self.paths.len()
+    }
+
+    fn transfer_td(&mut self, u: ArrayView1<f64>) -> Array1<f64> {
+        self.paths
+            .iter_mut()
+            .map(|row| {
+                row.iter_mut()
+                    .zip(u.iter())
+                    .filter_map(|(path, u)| path.as_mut().map(|p| p.transfer_td(*u)))
+                    .sum()
+            })
+            .collect()
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::plant::MimoTransferTimeDomain;
+    use crate::plant::pt1::PT1;
+    use crate::plant::thermal_zones::TwoZoneThermal;
+    use ndarray::array;
+    use std::format;
+    use std::vec;
+
+    #[test]
+    fn test_Decoupler_thermal_zones_steady_state() {
+        let mut plant = TwoZoneThermal::default().set_sample_time_or_default(600.0);
+        let mut sut = Decoupler::static_inverse(&plant.gain_matrix()).unwrap();
+        let mut y = array![0.0, 0.0];
+        for _ in 0..2000 {
+            let u = sut.transfer_td(array![100.0, 0.0].view());
+            y = plant.transfer_td(u.view());
+        }
+        assert!((y[0] - 100.0 * plant.gain_matrix()[[0, 0]]).abs() < 1e-6);
+        assert!(y[1].abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_Decoupler_dynamic_paths() {
+        let mut sut = Decoupler::new(vec![
+            vec![
+                Some(Box::new(PT1::<f64>::default().set_t1_time_or_default(2.0))
+                    as BoxedTransferTimeDomain<f64>),
+                None,
+            ],
+            vec![None, Some(Box::new(PT0::<f64>::default().set_kp(2.0)))],
+        ])
+        .unwrap();
+        assert_eq!(sut.transfer_td(array![1.0, 1.0].view()), array![0.5, 2.0]);
+        assert!(sut.path(0, 1).is_none());
+        assert_eq!(
+            format!("{}", sut),
+            "Decoupler(PT1(sample_time: 1, t1_time 2, kp: 1), -; -, PT0(sample_time: 1, t0_time 0, kp: 2))"
+        );
+    }
+
+    #[test]
+    fn test_Decoupler_invalid() {
+        assert!(Decoupler::new(vec![vec![None, None], vec![None]]).is_err());
+        assert!(Decoupler::static_inverse(&array![[1.0, 2.0], [2.0, 4.0]]).is_err());
+    }
+}
@@ -0,0 +1,272 @@
+//! A generic discrete transfer function element
+//!
+//! $ G(z) = \frac{b_{0} + b_{1} z^{-1} + \dots + b_{m} z^{-m}}{a_{0} + a_{1} z^{-1} + \dots + a_{n} z^{-n}} $
+//!
+//! evaluated as difference equation (direct form I)
+//!
+//! $ out[k] = \frac{1}{a_{0}} ( \sum_{i} b_{i} \cdot in[k-i] - \sum_{i \ge 1} a_{i} \cdot out[k-i] ) $
+//!
+//! For plants which do not fit PT0/PT1/PT2, e.g. identified models or
+//! transfer functions discretized from the s-domain. The coefficients belong
+//! to the sample time the element is simulated with.
+//!
+//! For `i32` the coefficients are converted to fixed point with 10 bits
+//! after the comma.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use cb_simulation_util::plant::TransferTimeDomain;
+//! use cb_simulation_util::plant::discrete_transfer::DiscreteTransfer;
+//!
+//! fn main() {
+//!     // moving average of two samples
+//!     let mut sut = DiscreteTransfer::<f64>::new(vec![0.5, 0.5], vec![1.0]).unwrap();
+//!     assert_eq!(sut.transfer_td(2.0), 1.0);
+//!     assert_eq!(sut.transfer_td(4.0), 3.0);
+//! }
+//! ```
+
+use std::vec;
+use std::vec::Vec;
+
+use super::snapshot::StateAccess;
+use super::*;
+use core::fmt::{self, Display};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiscreteTransfer<N> {
+    pub sample_time: f64,
+    /// Numerator coefficients, normalized with $a_0$
+    b: Vec<N>,
+    /// Denominator coefficients, normalized with $a_0$
+    a: Vec<N>,
+    /// Previous inputs, the most recent first
+    inputs: Vec<N>,
+    /// Previous outputs, the most recent first
+    outputs: Vec<N>,
+}
+
+fn normalize(b: Vec<f64>, a: Vec<f64>) -> Result<(Vec<f64>, Vec<f64>), &'static str> {
+    if b.is_empty() {
+        return Err("Invalid numerator: Must have at least one coefficient");
+    }
+    match a.first() {
+        Some(a0) if *a0 != 0.0 => {
+            let a0 = *a0;
+            Ok((
+                b.iter().map(|c| c / a0).collect(),
+                a.iter().map(|c| c / a0).collect(),
+            ))
+        }
+        _ => Err("Invalid denominator: a[0] must not be zero"),
+    }
+}
+
+impl<N> DiscreteTransfer<N> {
+    pub fn set_sample_time_or_default(self, sample_time: f64) -> Self {
+        if sample_time > 0.0 {
+            DiscreteTransfer::<N> {
+                sample_time,
+                ..self
+            }
+        } else {
+            DiscreteTransfer::<N> {
+                sample_time: 1.0,
+                ..self
+            }
+        }
+    }
+
+    /// Order of the difference equation
+    pub fn order(&self) -> usize {
+        self.a.len().max(self.b.len()).saturating_sub(1)
+    }
+}
+
+const FIX_KOMMA_SHIFT_BITS: u8 = 10;
+const FIX_KOMMA_SHIFT: i32 = 1 << FIX_KOMMA_SHIFT_BITS;
+
+impl DiscreteTransfer<i32> {
+    /// Element from real coefficients, fails if `a` is empty or `a[0] == 0`
+    pub fn new(b: Vec<f64>, a: Vec<f64>) -> Result<Self, &'static str> {
+        let (b, a) = normalize(b, a)?;
+        let fixed = |c: &f64| (c * FIX_KOMMA_SHIFT as f64).round() as i32;
+        Ok(DiscreteTransfer::<i32> {
+            sample_time: 1.0,
+            inputs: vec![0; b.len()],
+            outputs: vec![0; a.len()],
+            b: b.iter().map(fixed).collect(),
+            a: a.iter().map(fixed).collect(),
+        })
+    }
+}
+
+impl Default for DiscreteTransfer<i32> {
+    fn default() -> Self {
+        DiscreteTransfer::<i32>::new(vec![1.0], vec![1.0]).unwrap()
+    }
+}
+
+impl TransferTimeDomain<i32> for DiscreteTransfer<i32> {
+    fn transfer_td(&mut self, input: i32) -> i32 {
+        // outputs are kept with 10 bits after the comma
+        self.inputs.rotate_right(1);
+        self.inputs[0] = input;
+        let forward: i64 = self
+            .b
+            .iter()
+            .zip(self.inputs.iter())
+            .map(|(b, x)| *b as i64 * *x as i64)
+            .sum();
+        // outputs[0] is out[k-1]
+        let backward: i64 = self
+            .a
+            .iter()
+            .skip(1)
+            .zip(self.outputs.iter())
+            .map(|(a, y)| (*a as i64 * *y as i64) >> FIX_KOMMA_SHIFT_BITS)
+            .sum();
+        let output = (forward - backward).clamp(i32::MIN as i64, i32::MAX as i64) as i32;
+        self.outputs.rotate_right(1);
+        self.outputs[0] = output;
+        output >> FIX_KOMMA_SHIFT_BITS
+    }
+}
+
+impl DiscreteTransfer<f64> {
+    /// Element from coefficients, fails if `a` is empty or `a[0] == 0`
+    pub fn new(b: Vec<f64>, a: Vec<f64>) -> Result<Self, &'static str> {
+        let (b, a) = normalize(b, a)?;
+        Ok(DiscreteTransfer::<f64> {
+            sample_time: 1.0,
+            inputs: vec![0.0; b.len()],
+            outputs: vec![0.0; a.len()],
+            b,
+            a,
+        })
+    }
+
+    pub fn numerator(&self) -> &[f64] {
+        &self.b
+    }
+
+    pub fn denominator(&self) -> &[f64] {
+        &self.a
+    }
+}
+
+impl Default for DiscreteTransfer<f64> {
+    fn default() -> Self {
+        DiscreteTransfer::<f64>::new(vec![1.0], vec![1.0]).unwrap()
+    }
+}
+
+impl TransferTimeDomain<f64> for DiscreteTransfer<f64> {
+    fn transfer_td(&mut self, input: f64) -> f64 {
+        self.inputs.rotate_right(1);
+        self.inputs[0] = input;
+        let forward: f64 = self
+            .b
+            .iter()
+            .zip(self.inputs.iter())
+            .map(|(b, x)| b * x)
+            .sum();
+        // outputs[0] is out[k-1]
+        let backward: f64 = self
+            .a
+            .iter()
+            .skip(1)
+            .zip(self.outputs.iter())
+            .map(|(a, y)| a * y)
+            .sum();
+        let output = forward - backward;
+        self.outputs.rotate_right(1);
+        self.outputs[0] = output;
+        output
+    }
+}
+
+impl<N: Clone + Debug + PartialEq> StateAccess for DiscreteTransfer<N> {
+    /// Previous inputs and outputs, the most recent first
+    type State = (Vec<N>, Vec<N>);
+
+    fn save_state(&self) -> Self::State {
+        (self.inputs.clone(), self.outputs.clone())
+    }
+
+    fn restore_state(&mut self, state: &Self::State) {
+        if state.0.len() == self.inputs.len() && state.1.len() == self.outputs.len() {
+            self.inputs.clone_from(&state.0);
+            self.outputs.clone_from(&state.1);
+        }
+    }
+}
+
+impl<N> TypeIdentifier for DiscreteTransfer<N> {
+    fn short_type_name(&self) -> &'static str {
+        "DiscreteTransfer"
+    }
+}
+
+impl<N> SampleTime for DiscreteTransfer<N> {
+    fn sample_time(&self) -> Option<f64> {
+        Some(self.sample_time)
+    }
+}
+
+impl<N: Debug> Display for DiscreteTransfer<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "DiscreteTransfer(sample_time: {}, b: {:?}, a: {:?})",
+            self.sample_time, self.b, self.a
+        )
+    }
+}
+
+impl From<crate::analysis::discrete_tf::DiscreteTF> for DiscreteTransfer<f64> {
+    fn from(tf: crate::analysis::discrete_tf::DiscreteTF) -> Self {
+        // DiscreteTF keeps den[0] == 1
+        DiscreteTransfer::<f64>::new(tf.num, tf.den).unwrap_or_default()
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::analysis::discrete_tf::LinearBlock;
+    use crate::plant::pt2::PT2;
+
+    #[test]
+    fn test_DiscreteTransfer_matches_PT2() {
+        let mut pt2 = PT2::<f64>::default()
+            .set_sample_time_or_default(0.1)
+            .set_omega_or_default(2.0)
+            .set_damping_or_default(0.4);
+        let mut sut = DiscreteTransfer::from(pt2.discrete_tf()).set_sample_time_or_default(0.1);
+        assert_eq!(sut.order(), 2);
+        for _ in 0..100 {
+            assert!((sut.transfer_td(1.0) - pt2.transfer_td(1.0)).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_DiscreteTransfer_i32_first_order() {
+        // y[k] = 0.5 y[k-1] + 0.5 u[k], normalized from a0 = 2
+        let mut sut = DiscreteTransfer::<i32>::new(vec![1.0], vec![2.0, -1.0]).unwrap();
+        assert_eq!(sut.transfer_td(1000), 500);
+        assert_eq!(sut.transfer_td(1000), 750);
+        assert_eq!(sut.transfer_td(1000), 875);
+    }
+
+    #[test]
+    fn test_DiscreteTransfer_invalid() {
+        assert!(DiscreteTransfer::<f64>::new(vec![1.0], vec![]).is_err());
+        assert!(DiscreteTransfer::<f64>::new(vec![], vec![1.0]).is_err());
+        assert!(DiscreteTransfer::<i32>::new(vec![], vec![1.0, -0.5]).is_err());
+        assert!(DiscreteTransfer::<i32>::new(vec![1.0], vec![0.0, 1.0]).is_err());
+    }
+}
@@ -0,0 +1,368 @@
+//! # Feedback loop
+//!
+//! Closes a loop around a forward path $G$ with a feedback path $H$:
+//!
+//! $ e[k] = r[k] - H(y[k-1]) $, $ y[k] = G(e[k]) $
+//!
+//! The feedback path sees the output of the previous sample, which avoids
+//! an algebraic loop. Combined with a controller in the forward path (e.g.
+//! a `Series` of controller and plant) this simulates a control loop with
+//! the existing PT1/PT2 plants.
+//!
+//! Either path can be bypassed with `set_enabled`, it then passes its input
+//! through: a bypassed feedback path is an ideal sensor, a bypassed forward
+//! path a unit gain. A bypassed path keeps its state.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use cb_simulation_util::plant::TransferTimeDomain;
+//! use cb_simulation_util::plant::feedback::Feedback;
+//! use cb_simulation_util::plant::pt1::PT1;
+//!
+//! fn main() {
+//!     let plant = PT1::<f64>::default().set_t1_time_or_default(5.0);
+//!     let mut sut = Feedback::unity(Box::new(plant));
+//!     let mut y = 0.0;
+//!     for _ in 0..200 {
+//!         y = sut.transfer_td(1.0);
+//!     }
+//!     // proportional loop with gain 1: steady state output 1 / (1 + 1)
+//!     assert!((y - 0.5).abs() < 1e-6);
+//! }
+//! ```
+
+use super::series::Series;
+use super::snapshot::{BoxedState, StateAccess, restore_child_state, save_child_state};
+use super::steady_state::{SteadyState, as_steady_state, as_steady_state_mut};
+use super::*;
+use core::fmt::{self, Display};
+
+/// The paths of a `Feedback` loop
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Path {
+    Forward,
+    Feedback,
+}
+
+#[derive(Debug, Clone)]
+pub struct Feedback {
+    pub forward: BoxedTransferTimeDomain<f64>,
+    pub feedback: BoxedTransferTimeDomain<f64>,
+    previous_output: f64,
+    previous_error: f64,
+    /// `[forward, feedback]`, a disabled path passes its input through
+    enabled: [bool; 2],
+}
+
+impl Feedback {
+    pub fn new(
+        forward: BoxedTransferTimeDomain<f64>,
+        feedback: BoxedTransferTimeDomain<f64>,
+    ) -> Self {
+        Feedback {
+            forward,
+            feedback,
+            previous_output: 0.0,
+            previous_error: 0.0,
+            enabled: [true; 2],
+        }
+    }
+
+    /// Feedback loop with an ideal sensor, $ H = 1 $
+    ///
+    /// The feedback path is an empty `Series`, it passes signal and unit through.
+    pub fn unity(forward: BoxedTransferTimeDomain<f64>) -> Self {
+        Feedback::new(forward, Box::new(Series::<f64>::default()))
+    }
+
+    /// Control error $ e[k] $ of the last sample
+    pub fn error(&self) -> f64 {
+        self.previous_error
+    }
+
+    /// Enable the path, or bypass it with `false`
+    pub fn set_enabled(&mut self, path: Path, enabled: bool) {
+        self.enabled[path as usize] = enabled;
+    }
+
+    pub fn is_enabled(&self, path: Path) -> bool {
+        self.enabled[path as usize]
+    }
+}
+
+impl PartialEq for Feedback {
+    fn eq(&self, other: &Self) -> bool {
+        self.forward.eq(&other.forward)
+            && self.feedback.eq(&other.feedback)
+            && self.previous_output == other.previous_output
+            && self.previous_error == other.previous_error
+            && self.enabled == other.enabled
+    }
+}
+
+impl StateAccess for Feedback {
+    /// The states of the forward and the feedback path, the previous output
+    /// and error
+    type State = (Option<BoxedState>, Option<BoxedState>, f64, f64);
+
+    fn save_state(&self) -> Self::State {
+        (
+            save_child_state(&*self.forward),
+            save_child_state(&*self.feedback),
+            self.previous_output,
+            self.previous_error,
+        )
+    }
+
+    fn restore_state(&mut self, state: &Self::State) {
+        restore_child_state(&mut *self.forward, &state.0);
+        restore_child_state(&mut *self.feedback, &state.1);
+        (self.previous_output, self.previous_error) = (state.2, state.3);
+    }
+}
+
+/// Secant iterations solving the loop for its steady output
+const MAX_STEADY_ITERATIONS: usize = 50;
+
+impl Feedback {
+    /// Steady output of the feedback path for the loop output `output`
+    fn steady_measurement(&self, output: f64) -> Option<f64> {
+        if !self.is_enabled(Path::Feedback) {
+            return Some(output);
+        }
+        let mut feedback = self.feedback.clone();
+        Some(as_steady_state_mut(&mut *feedback)?.settle(output))
+    }
+
+    /// Loop output holding the reference `r`, `None` if not found
+    fn steady_output(&self, r: f64) -> Option<f64> {
+        let residual = |y: f64| Some(self.steady_input(y)? - r);
+        let (mut y0, mut y1) = (r, r + 1.0);
+        let (mut f0, mut f1) = (residual(y0)?, residual(y1)?);
+        for _ in 0..MAX_STEADY_ITERATIONS {
+            if f1.abs() <= 1e-12 * r.abs().max(1.0) {
+                return Some(y1);
+            }
+            if f1 == f0 {
+                return None;
+            }
+            let y2 = y1 - f1 * (y1 - y0) / (f1 - f0);
+            (y0, f0) = (y1, f1);
+            y1 = y2;
+            f1 = residual(y1)?;
+        }
+        None
+    }
+}
+
+impl SteadyState for Feedback {
+    /// The control error holding the output plus the steady measurement of it
+    fn steady_input(&self, output: f64) -> Option<f64> {
+        let error = if self.is_enabled(Path::Forward) {
+            as_steady_state(&*self.forward)?.steady_input(output)?
+        } else {
+            output
+        };
+        Some(error + self.steady_measurement(output)?)
+    }
+
+    /// Solves the loop for its output with the secant method on
+    /// `steady_input`, exact after one step for linear paths. A loop without
+    /// a steady state at `r` is run once with it.
+    fn settle(&mut self, r: f64) -> f64 {
+        match self.steady_output(r) {
+            Some(output) if self.settle_output(output).is_ok() => output,
+            _ => self.transfer_td(r),
+        }
+    }
+
+    /// Settles the forward path at `output` and the feedback path with it
+    fn settle_output(&mut self, output: f64) -> Result<f64, &'static str> {
+        let error = if self.is_enabled(Path::Forward) {
+            let name = self.forward.short_type_name();
+            as_steady_state_mut(&mut *self.forward)
+                .ok_or(name)?
+                .settle_output(output)?
+        } else {
+            output
+        };
+        let measured = if self.is_enabled(Path::Feedback) {
+            let name = self.feedback.short_type_name();
+            as_steady_state_mut(&mut *self.feedback)
+                .ok_or(name)?
+                .settle(output)
+        } else {
+            output
+        };
+        self.previous_output = output;
+        self.previous_error = error;
+        Ok(error + measured)
+    }
+}
+
+impl TypeIdentifier for Feedback {
+    fn short_type_name(&self) -> &'static str {
+        "Feedback"
+    }
+}
+
+impl SampleTime for Feedback {
+    /// Common sample time of the enabled paths
+    fn sample_time(&self) -> Option<f64> {
+        common_sample_time(
+            [self.forward.sample_time(), self.feedback.sample_time()]
+                .into_iter()
+                .zip(self.enabled)
+                .filter(|(_, enabled)| *enabled)
+                .map(|(sample_time, _)| sample_time),
+        )
+    }
+}
+
+impl Display for Feedback {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let bypassed = |path| {
+            if self.is_enabled(path) {
+                ""
+            } else {
+                " [bypassed]"
+            }
+        };
+        write!(
+            f,
+            "Feedback(forward: {}{}, feedback: {}{})",
+            self.forward,
+            bypassed(Path::Forward),
+            self.feedback,
+            bypassed(Path::Feedback)
+        )
+    }
+}
+
+impl TransferTimeDomain<f64> for Feedback {
+    fn transfer_td(&mut self, r: f64) -> f64 {
+        let measured = if self.is_enabled(Path::Feedback) {
+            self.feedback.transfer_td(self.previous_output)
+        } else {
+            self.previous_output
+        };
+        self.previous_error = r - measured;
+        self.previous_output = if self.is_enabled(Path::Forward) {
+            self.forward.transfer_td(self.previous_error)
+        } else {
+            self.previous_error
+        };
+        self.previous_output
+    }
+
+    fn output_unit(&self, input_unit: &'static str) -> &'static str {
+        if self.is_enabled(Path::Forward) {
+            self.forward.output_unit(input_unit)
+        } else {
+            input_unit
+        }
+    }
+
+    /// Checks both paths, the feedback path must return the unit of the reference
+    fn check_units(
+        &self,
+        input_unit: &'static str,
+    ) -> Result<&'static str, unit_gain::UnitCheckError> {
+        let output_unit = if self.is_enabled(Path::Forward) {
+            self.forward.check_units(input_unit)?
+        } else {
+            input_unit
+        };
+        let measured_unit = if self.is_enabled(Path::Feedback) {
+            self.feedback.check_units(output_unit)?
+        } else {
+            output_unit
+        };
+        if measured_unit != input_unit {
+            return Err(unit_gain::UnitCheckError {
+                element: self.short_type_name(),
+                expected: input_unit,
+                found: measured_unit,
+            });
+        }
+        Ok(output_unit)
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::plant::pt0::PT0;
+    use crate::plant::pt1::PT1;
+    use crate::plant::series::Series;
+    use std::format;
+
+    #[test]
+    fn test_Feedback_uses_previous_output() {
+        let mut sut = Feedback::unity(Box::new(unit_gain::UnitGain::default()));
+        assert_eq!(sut.transfer_td(1.0), 1.0);
+        assert_eq!(sut.error(), 1.0);
+        assert_eq!(sut.transfer_td(1.0), 0.0);
+        assert_eq!(sut.transfer_td(1.0), 1.0);
+    }
+
+    #[test]
+    fn test_Feedback_sensor_delay_in_feedback_path() {
+        let mut forward = Series::<f64>::default();
+        forward.push(Box::new(PT1::<f64>::default().set_t1_time_or_default(4.0)));
+        let sensor = PT0::<f64>::default().set_t0_time_or_default(2.0);
+        let mut sut = Feedback::new(Box::new(forward), Box::new(sensor));
+        let mut y = 0.0;
+        for _ in 0..500 {
+            y = sut.transfer_td(2.0);
+        }
+        assert!((y - 1.0).abs() < 1e-6);
+        assert!(format!("{}", sut).starts_with("Feedback(forward: Series(PT1("));
+    }
+
+    #[test]
+    fn test_Feedback_check_units() {
+        use unit_gain::{Quantity, Unit, UnitCheckError, UnitGain};
+        let percent = Unit::new("%", Quantity::Ratio, 0.01, 0.0);
+        let milli_ampere = Unit::new("mA", Quantity::Current, 1.0e-3, 0.0);
+        let transmitter = UnitGain::span(percent, (0.0, 100.0), milli_ampere, (4.0, 20.0)).unwrap();
+        let sut = Feedback::unity(Box::new(PT1::<f64>::default()));
+        assert_eq!(sut.check_units("%"), Ok("%"));
+        // the measurement in mA is subtracted from a reference in %
+        let mut sut = Feedback::new(Box::new(PT1::<f64>::default()), Box::new(transmitter));
+        assert_eq!(
+            sut.check_units("%"),
+            Err(UnitCheckError {
+                element: "Feedback",
+                expected: "%",
+                found: "mA"
+            })
+        );
+        assert!(sut.check_units("mA").is_err());
+        sut.set_enabled(Path::Feedback, false);
+        assert_eq!(sut.check_units("%"), Ok("%"));
+    }
+
+    #[test]
+    fn test_Feedback_bypass() {
+        let sensor = PT0::<f64>::default().set_t0_time_or_default(2.0);
+        let mut sut = Feedback::new(Box::new(PT1::<f64>::default()), Box::new(sensor));
+        sut.set_enabled(Path::Feedback, false);
+        sut.set_enabled(Path::Forward, false);
+        assert!(!sut.is_enabled(Path::Forward));
+        // unit gain with unity feedback: the output alternates
+        assert_eq!(sut.transfer_td(1.0), 1.0);
+        assert_eq!(sut.transfer_td(1.0), 0.0);
+        assert_eq!(sut.output_unit("m"), "m");
+        assert!(format!("{}", sut).ends_with("[bypassed])"));
+        sut.set_enabled(Path::Forward, true);
+        assert_eq!(sut.sample_time(), Some(1.0));
+        assert_ne!(
+            sut.clone(),
+            Feedback::new(sut.forward.clone(), sut.feedback.clone())
+        );
+    }
+}
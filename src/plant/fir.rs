@@ -0,0 +1,209 @@
+//! A finite impulse response (FIR) filter element
+//!
+//! $ out[k] = \sum_{i=0}^{n-1} h_{i} \cdot in[k-i] $
+//!
+//! where $h_{i}$ are the $n$ coefficients, i.e. the response to a unit pulse.
+//!
+//! Any coefficient slice can be used, e.g. a measured impulse response sampled
+//! with the simulation sample time or a filter designed elsewhere. The element
+//! keeps the last $n$ inputs in its own delay line.
+//!
+//! For `i32` the coefficients are converted to fixed point with 10 bits
+//! after the comma.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use cb_simulation_util::plant::TransferTimeDomain;
+//! use cb_simulation_util::plant::fir::FIR;
+//!
+//! fn main() {
+//!     // measured impulse response of a sensor
+//!     let mut sensor = FIR::<f64>::new(&[0.5, 0.25, 0.125, 0.125]).unwrap();
+//!     assert_eq!(sensor.transfer_td(8.0), 4.0);
+//!     assert_eq!(sensor.transfer_td(8.0), 6.0);
+//!     assert_eq!(sensor.transfer_td(8.0), 7.0);
+//!     assert_eq!(sensor.transfer_td(8.0), 8.0);
+//!     assert_eq!(sensor.transfer_td(8.0), 8.0);
+//! }
+//! ```
+
+use std::vec;
+use std::vec::Vec;
+
+use super::snapshot::StateAccess;
+use super::*;
+use core::fmt::{self, Display};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FIR<N> {
+    pub sample_time: f64,
+    coefficients: Vec<f64>,
+    /// Previous inputs as ring buffer, `write_index` points to the oldest
+    delay_line: Vec<N>,
+    write_index: usize,
+}
+
+impl<N: Copy + Default> FIR<N> {
+    /// Filter from coefficients, fails for an empty slice
+    pub fn new(coefficients: &[f64]) -> Result<Self, &'static str> {
+        if coefficients.is_empty() {
+            return Err("Invalid coefficients: Must not be empty");
+        }
+        Ok(FIR::<N> {
+            sample_time: 1.0,
+            coefficients: coefficients.to_vec(),
+            delay_line: vec![N::default(); coefficients.len()],
+            write_index: 0,
+        })
+    }
+
+    pub fn set_sample_time_or_default(self, sample_time: f64) -> Self {
+        if sample_time > 0.0 {
+            FIR::<N> {
+                sample_time,
+                ..self
+            }
+        } else {
+            FIR::<N> {
+                sample_time: 1.0,
+                ..self
+            }
+        }
+    }
+
+    pub fn coefficients(&self) -> &[f64] {
+        &self.coefficients
+    }
+
+    /// Number of coefficients
+    pub fn len(&self) -> usize {
+        self.coefficients.len()
+    }
+
+    /// Always false, a filter has at least one coefficient
+    pub fn is_empty(&self) -> bool {
+        self.coefficients.is_empty()
+    }
+
+    /// The internal state: the previous inputs, the most recent first
+    pub fn state(&self) -> Vec<N> {
+        let n = self.delay_line.len();
+        (1..=n)
+            .map(|i| self.delay_line[(self.write_index + n - i) % n])
+            .collect()
+    }
+
+    /// Store `input` and return pairs of coefficient and delayed input
+    fn push(&mut self, input: N) -> impl Iterator<Item = (f64, N)> + '_ {
+        let n = self.delay_line.len();
+        self.delay_line[self.write_index] = input;
+        let newest = self.write_index;
+        self.write_index = (self.write_index + 1) % n;
+        let delay_line = &self.delay_line;
+        self.coefficients
+            .iter()
+            .enumerate()
+            .map(move |(i, h)| (*h, delay_line[(newest + n - i) % n]))
+    }
+}
+
+const FIX_KOMMA_SHIFT_BITS: u8 = 10;
+const FIX_KOMMA_SHIFT: i64 = 1 << FIX_KOMMA_SHIFT_BITS;
+
+impl Default for FIR<i32> {
+    /// Passes the input through
+    fn default() -> Self {
+        FIR::<i32>::new(&[1.0]).unwrap()
+    }
+}
+
+impl Default for FIR<f64> {
+    /// Passes the input through
+    fn default() -> Self {
+        FIR::<f64>::new(&[1.0]).unwrap()
+    }
+}
+
+impl<N: Clone + fmt::Debug + PartialEq> StateAccess for FIR<N> {
+    /// Delay line and its write index
+    type State = (Vec<N>, usize);
+
+    fn save_state(&self) -> Self::State {
+        (self.delay_line.clone(), self.write_index)
+    }
+
+    fn restore_state(&mut self, state: &Self::State) {
+        if state.0.len() == self.delay_line.len() {
+            self.delay_line.clone_from(&state.0);
+            self.write_index = state.1;
+        }
+    }
+}
+
+impl<N> TypeIdentifier for FIR<N> {
+    fn short_type_name(&self) -> &'static str {
+        "FIR"
+    }
+}
+
+impl<N> SampleTime for FIR<N> {
+    fn sample_time(&self) -> Option<f64> {
+        Some(self.sample_time)
+    }
+}
+
+impl<N> Display for FIR<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "FIR(sample_time: {}, coefficients: {:?})",
+            self.sample_time, self.coefficients
+        )
+    }
+}
+
+impl TransferTimeDomain<i32> for FIR<i32> {
+    fn transfer_td(&mut self, input: i32) -> i32 {
+        let sum: i64 = self
+            .push(input)
+            .map(|(h, x)| (h * FIX_KOMMA_SHIFT as f64).round() as i64 * x as i64)
+            .sum();
+        (sum >> FIX_KOMMA_SHIFT_BITS).clamp(i32::MIN as i64, i32::MAX as i64) as i32
+    }
+}
+
+impl TransferTimeDomain<f64> for FIR<f64> {
+    fn transfer_td(&mut self, input: f64) -> f64 {
+        self.push(input).map(|(h, x)| h * x).sum()
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_FIR_f64_impulse_response() {
+        let h = [0.1, -0.4, 0.7, 0.2, 0.05];
+        let mut sut = FIR::<f64>::new(&h).unwrap();
+        let response: Vec<f64> = (0..7)
+            .map(|k| sut.transfer_td(if k == 0 { 1.0 } else { 0.0 }))
+            .collect();
+        assert_eq!(&response[..5], &h);
+        assert_eq!(&response[5..], &[0.0, 0.0]);
+    }
+
+    #[test]
+    fn test_FIR_i32_state_and_saturation() {
+        let mut sut = FIR::<i32>::new(&[2.0, 2.0]).unwrap();
+        assert_eq!(sut.transfer_td(100), 200);
+        assert_eq!(sut.transfer_td(-50), 100);
+        assert_eq!(sut.state(), vec![-50, 100]);
+        sut.transfer_td(i32::MAX);
+        assert_eq!(sut.transfer_td(i32::MAX), i32::MAX);
+        assert!(FIR::<i32>::new(&[]).is_err());
+    }
+}
@@ -0,0 +1,291 @@
+//! A second order IIR filter section aka biquad
+//!
+//! $ out[k] = b_{0} in[k] + b_{1} in[k-1] + b_{2} in[k-2] - a_{1} out[k-1] - a_{2} out[k-2] $
+//!
+//! with the coefficients normalized to $a_{0} = 1$.
+//!
+//! Besides arbitrary coefficients, second order Butterworth low-pass,
+//! high-pass and band-pass designs are provided. They are derived with the
+//! Tustin method prewarped at the cutoff frequencies, so the gain at the
+//! cutoff is exactly $1/\sqrt{2}$ for any sample time below the Nyquist
+//! limit. Cutoff frequencies are in Hz, i.e. cycles per time unit of the
+//! sample time.
+//!
+//! For `i32` the coefficients are converted to fixed point with 10 bits
+//! after the comma.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use cb_simulation_util::plant::TransferTimeDomain;
+//! use cb_simulation_util::plant::iir_biquad::IIRBiquad;
+//!
+//! fn main() {
+//!     let mut low_pass = IIRBiquad::<f64>::butterworth_low_pass(5.0, 0.001).unwrap();
+//!     let mut output = 0.0;
+//!     for _ in 0..1000 {
+//!         output = low_pass.transfer_td(1.0);
+//!     }
+//!     assert!((output - 1.0).abs() < 1e-9);
+//!     assert!(IIRBiquad::<f64>::butterworth_low_pass(600.0, 0.001).is_err());
+//! }
+//! ```
+
+use super::snapshot::StateAccess;
+use super::*;
+use core::f64::consts::{PI, SQRT_2};
+use core::fmt::{self, Display};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IIRBiquad<N> {
+    pub sample_time: f64,
+    /// Numerator coefficients, normalized with $a_0$
+    b: [f64; 3],
+    /// Denominator coefficients, normalized with $a_0$
+    a: [f64; 3],
+    /// in[k-1], in[k-2]
+    inputs: [N; 2],
+    /// out[k-1], out[k-2]
+    outputs: [N; 2],
+}
+
+/// `tan(pi f T)`, the prewarped frequency of the Tustin method
+fn prewarp(frequency: f64, sample_time: f64) -> Result<f64, &'static str> {
+    if sample_time <= 0.0 {
+        return Err("Invalid sample_time: Must be > 0.0");
+    }
+    if frequency <= 0.0 || 2.0 * frequency * sample_time >= 1.0 {
+        return Err("Invalid cutoff: Must be > 0.0 and below the Nyquist frequency");
+    }
+    Ok((PI * frequency * sample_time).tan())
+}
+
+impl<N: Copy + Default> IIRBiquad<N> {
+    /// Section from coefficients, fails if `a[0] == 0`
+    pub fn new(b: [f64; 3], a: [f64; 3]) -> Result<Self, &'static str> {
+        if a[0] == 0.0 {
+            return Err("Invalid denominator: a[0] must not be zero");
+        }
+        Ok(IIRBiquad::<N> {
+            sample_time: 1.0,
+            b: b.map(|c| c / a[0]),
+            a: a.map(|c| c / a[0]),
+            inputs: [N::default(); 2],
+            outputs: [N::default(); 2],
+        })
+    }
+
+    /// Butterworth low-pass with the -3 dB `cutoff` in Hz
+    pub fn butterworth_low_pass(cutoff: f64, sample_time: f64) -> Result<Self, &'static str> {
+        let k = prewarp(cutoff, sample_time)?;
+        let kk = k * k;
+        Ok(Self::new(
+            [kk, 2.0 * kk, kk],
+            [
+                1.0 + SQRT_2 * k + kk,
+                2.0 * (kk - 1.0),
+                1.0 - SQRT_2 * k + kk,
+            ],
+        )?
+        .set_sample_time_or_default(sample_time))
+    }
+
+    /// Butterworth high-pass with the -3 dB `cutoff` in Hz
+    pub fn butterworth_high_pass(cutoff: f64, sample_time: f64) -> Result<Self, &'static str> {
+        let k = prewarp(cutoff, sample_time)?;
+        let kk = k * k;
+        Ok(Self::new(
+            [1.0, -2.0, 1.0],
+            [
+                1.0 + SQRT_2 * k + kk,
+                2.0 * (kk - 1.0),
+                1.0 - SQRT_2 * k + kk,
+            ],
+        )?
+        .set_sample_time_or_default(sample_time))
+    }
+
+    /// Band-pass between the -3 dB frequencies `low` and `high` in Hz, unit gain at the center
+    pub fn butterworth_band_pass(
+        low: f64,
+        high: f64,
+        sample_time: f64,
+    ) -> Result<Self, &'static str> {
+        if low >= high {
+            return Err("Invalid band: low must be < high");
+        }
+        let (kl, kh) = (prewarp(low, sample_time)?, prewarp(high, sample_time)?);
+        let (bandwidth, center) = (kh - kl, kl * kh);
+        Ok(Self::new(
+            [bandwidth, 0.0, -bandwidth],
+            [
+                1.0 + bandwidth + center,
+                2.0 * (center - 1.0),
+                1.0 - bandwidth + center,
+            ],
+        )?
+        .set_sample_time_or_default(sample_time))
+    }
+}
+
+impl<N: Copy> IIRBiquad<N> {
+    pub fn set_sample_time_or_default(self, sample_time: f64) -> Self {
+        if sample_time > 0.0 {
+            IIRBiquad::<N> {
+                sample_time,
+                ..self
+            }
+        } else {
+            IIRBiquad::<N> {
+                sample_time: 1.0,
+                ..self
+            }
+        }
+    }
+
+    pub fn numerator(&self) -> &[f64; 3] {
+        &self.b
+    }
+
+    pub fn denominator(&self) -> &[f64; 3] {
+        &self.a
+    }
+
+    /// The internal state: (previous inputs, previous outputs), the most recent first
+    pub fn state(&self) -> ([N; 2], [N; 2]) {
+        (self.inputs, self.outputs)
+    }
+}
+
+const FIX_KOMMA_SHIFT_BITS: u8 = 10;
+const FIX_KOMMA_SHIFT: i64 = 1 << FIX_KOMMA_SHIFT_BITS;
+
+impl Default for IIRBiquad<i32> {
+    /// Passes the input through
+    fn default() -> Self {
+        IIRBiquad::<i32>::new([1.0, 0.0, 0.0], [1.0, 0.0, 0.0]).unwrap()
+    }
+}
+
+impl Default for IIRBiquad<f64> {
+    /// Passes the input through
+    fn default() -> Self {
+        IIRBiquad::<f64>::new([1.0, 0.0, 0.0], [1.0, 0.0, 0.0]).unwrap()
+    }
+}
+
+impl<N: Copy + fmt::Debug + PartialEq> StateAccess for IIRBiquad<N> {
+    /// in[k-1], in[k-2] and out[k-1], out[k-2]
+    type State = ([N; 2], [N; 2]);
+
+    fn save_state(&self) -> Self::State {
+        (self.inputs, self.outputs)
+    }
+
+    fn restore_state(&mut self, state: &Self::State) {
+        (self.inputs, self.outputs) = *state;
+    }
+}
+
+impl<N> TypeIdentifier for IIRBiquad<N> {
+    fn short_type_name(&self) -> &'static str {
+        "IIRBiquad"
+    }
+}
+
+impl<N> SampleTime for IIRBiquad<N> {
+    fn sample_time(&self) -> Option<f64> {
+        Some(self.sample_time)
+    }
+}
+
+impl<N> Display for IIRBiquad<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "IIRBiquad(sample_time: {}, b: {:?}, a: {:?})",
+            self.sample_time, self.b, self.a
+        )
+    }
+}
+
+impl TransferTimeDomain<i32> for IIRBiquad<i32> {
+    fn transfer_td(&mut self, input: i32) -> i32 {
+        // outputs are kept with 10 bits after the comma
+        let fixed = |c: f64| (c * FIX_KOMMA_SHIFT as f64).round() as i64;
+        let forward = fixed(self.b[0]) * input as i64
+            + fixed(self.b[1]) * self.inputs[0] as i64
+            + fixed(self.b[2]) * self.inputs[1] as i64;
+        let backward = (fixed(self.a[1]) * self.outputs[0] as i64
+            + fixed(self.a[2]) * self.outputs[1] as i64)
+            >> FIX_KOMMA_SHIFT_BITS;
+        let output = (forward - backward).clamp(i32::MIN as i64, i32::MAX as i64) as i32;
+        self.inputs = [input, self.inputs[0]];
+        self.outputs = [output, self.outputs[0]];
+        output >> FIX_KOMMA_SHIFT_BITS
+    }
+}
+
+impl TransferTimeDomain<f64> for IIRBiquad<f64> {
+    fn transfer_td(&mut self, input: f64) -> f64 {
+        let output = self.b[0] * input + self.b[1] * self.inputs[0] + self.b[2] * self.inputs[1]
+            - self.a[1] * self.outputs[0]
+            - self.a[2] * self.outputs[1];
+        self.inputs = [input, self.inputs[0]];
+        self.outputs = [output, self.outputs[0]];
+        output
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    /// Steady state amplitude of the response to a sine of `frequency` in Hz
+    fn amplitude(mut filter: IIRBiquad<f64>, frequency: f64) -> f64 {
+        let ts = filter.sample_time;
+        (0..20000)
+            .map(|k| filter.transfer_td((2.0 * PI * frequency * k as f64 * ts).sin()))
+            .skip(15000)
+            .fold(0.0, |m: f64, y| m.max(y.abs()))
+    }
+
+    #[test]
+    fn test_IIRBiquad_butterworth_cutoff_gain() {
+        let ts = 0.001;
+        let low_pass = IIRBiquad::<f64>::butterworth_low_pass(20.0, ts).unwrap();
+        let high_pass = IIRBiquad::<f64>::butterworth_high_pass(20.0, ts).unwrap();
+        assert!((amplitude(low_pass, 20.0) - 0.5_f64.sqrt()).abs() < 1e-3);
+        assert!((amplitude(high_pass, 20.0) - 0.5_f64.sqrt()).abs() < 1e-3);
+        assert!(amplitude(low_pass, 200.0) < 0.02);
+        assert!(amplitude(high_pass, 2.0) < 0.02);
+    }
+
+    #[test]
+    fn test_IIRBiquad_butterworth_band_pass() {
+        let ts = 0.001;
+        let sut = IIRBiquad::<f64>::butterworth_band_pass(10.0, 40.0, ts).unwrap();
+        // geometric mean of the prewarped band edges
+        let center = ((PI * 10.0 * ts).tan() * (PI * 40.0 * ts).tan())
+            .sqrt()
+            .atan()
+            / (PI * ts);
+        assert!((amplitude(sut, center) - 1.0).abs() < 1e-3);
+        assert!((amplitude(sut, 10.0) - 0.5_f64.sqrt()).abs() < 1e-3);
+        assert!((amplitude(sut, 40.0) - 0.5_f64.sqrt()).abs() < 1e-3);
+        assert!(IIRBiquad::<f64>::butterworth_band_pass(40.0, 10.0, ts).is_err());
+    }
+
+    #[test]
+    fn test_IIRBiquad_i32_low_pass() {
+        let mut sut = IIRBiquad::<i32>::butterworth_low_pass(1.0, 0.05).unwrap();
+        let mut output = 0;
+        for _ in 0..200 {
+            output = sut.transfer_td(1000);
+        }
+        assert!((output - 1000).abs() <= 5);
+        assert!(IIRBiquad::<i32>::new([1.0, 0.0, 0.0], [0.0, 1.0, 0.0]).is_err());
+    }
+}
@@ -0,0 +1,182 @@
+//! # Instrumented element
+//!
+//! Wraps an element and calls a user closure after each `transfer_td` with the
+//! sample time, input, output and the wrapped element (its state). Enables
+//! custom logging, tracing or live assertions without modifying element code.
+//!
+//! The time passed to the closure advances by the sample time of the element,
+//! elements without a sample time count in samples.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use cb_simulation_util::plant::TransferTimeDomain;
+//! use cb_simulation_util::plant::instrumented::Instrumented;
+//! use cb_simulation_util::plant::pt1::PT1;
+//!
+//! fn main() {
+//!     let mut log = Vec::new();
+//!     let pt1 = PT1::<f64>::default()
+//!         .set_sample_time_or_default(0.5)
+//!         .set_t1_time_or_default(1.0);
+//!     let mut sut = Instrumented::new(pt1, |time, u, y, element: &PT1<f64>| {
+//!         log.push((time, u, y, element.state()));
+//!     });
+//!     sut.transfer_td(1.0);
+//!     sut.transfer_td(1.0);
+//!     drop(sut);
+//!     assert_eq!(log[1], (0.5, 1.0, 0.75, 0.75));
+//! }
+//! ```
+
+use super::*;
+use core::fmt::{self, Display};
+use core::marker::PhantomData;
+
+pub struct Instrumented<N, T, F>
+where
+    F: FnMut(f64, N, N, &T),
+{
+    pub element: T,
+    pub sample_time: f64,
+    hook: F,
+    samples: usize,
+    _signal: PhantomData<N>,
+}
+
+impl<N, T: SampleTime, F> Instrumented<N, T, F>
+where
+    F: FnMut(f64, N, N, &T),
+{
+    /// Wrap `element`, `hook` is called with `(time, input, output, element)`
+    ///
+    /// The time advances by the sample time of the element, or 1.0 without one.
+    pub fn new(element: T, hook: F) -> Self {
+        Instrumented {
+            sample_time: element.sample_time().unwrap_or(1.0),
+            element,
+            hook,
+            samples: 0,
+            _signal: PhantomData,
+        }
+    }
+}
+
+impl<N, T, F> Instrumented<N, T, F>
+where
+    F: FnMut(f64, N, N, &T),
+{
+    /// Override the sample time used to compute the time passed to the hook
+    pub fn set_sample_time(self, sample_time: f64) -> Self {
+        Instrumented {
+            sample_time,
+            ..self
+        }
+    }
+
+    /// Unwrap the element
+    pub fn into_inner(self) -> T {
+        self.element
+    }
+}
+
+impl<N, T: TypeIdentifier, F> TypeIdentifier for Instrumented<N, T, F>
+where
+    F: FnMut(f64, N, N, &T),
+{
+    fn short_type_name(&self) -> &'static str {
+        self.element.short_type_name()
+    }
+}
+
+impl<N, T: SampleTime, F> SampleTime for Instrumented<N, T, F>
+where
+    F: FnMut(f64, N, N, &T),
+{
+    fn sample_time(&self) -> Option<f64> {
+        self.element.sample_time()
+    }
+}
+
+impl<N, T: Display, F> Display for Instrumented<N, T, F>
+where
+    F: FnMut(f64, N, N, &T),
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Instrumented({})", self.element)
+    }
+}
+
+impl<N, T: Debug, F> Debug for Instrumented<N, T, F>
+where
+    F: FnMut(f64, N, N, &T),
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Instrumented")
+            .field("element", &self.element)
+            .field("sample_time", &self.sample_time)
+            .field("samples", &self.samples)
+            .finish()
+    }
+}
+
+impl<N: Copy, T: TransferTimeDomain<N>, F> TransferTimeDomain<N> for Instrumented<N, T, F>
+where
+    F: FnMut(f64, N, N, &T),
+{
+    fn transfer_td(&mut self, u: N) -> N {
+        let time = self.samples as f64 * self.sample_time;
+        self.samples += 1;
+        let y = self.element.transfer_td(u);
+        (self.hook)(time, u, y, &self.element);
+        y
+    }
+
+    fn output_unit(&self, input_unit: &'static str) -> &'static str {
+        self.element.output_unit(input_unit)
+    }
+
+    fn check_units(
+        &self,
+        input_unit: &'static str,
+    ) -> Result<&'static str, unit_gain::UnitCheckError> {
+        self.element.check_units(input_unit)
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::plant::pt0::PT0;
+
+    #[test]
+    fn test_Instrumented_passes_through() {
+        let mut calls = 0;
+        let mut sut = Instrumented::new(
+            PT0::<i32>::default().set_t0_time_or_default(1.0),
+            |_, _, _, _: &PT0<i32>| calls += 1,
+        );
+        assert_eq!(sut.short_type_name(), "PT0");
+        assert_eq!(0, sut.transfer_td(7));
+        assert_eq!(7, sut.transfer_td(8));
+        assert_eq!(sut.into_inner().buffer_state(), &[7 << 10, 8 << 10]);
+        assert_eq!(calls, 2);
+    }
+
+    #[test]
+    fn test_Instrumented_time_of_element_sample_time() {
+        let mut times = std::vec::Vec::new();
+        let pt0 = PT0::<f64>::default().set_sample_time_or_default(0.25);
+        {
+            let mut sut = Instrumented::new(pt0, |time, _, _, _: &PT0<f64>| times.push(time));
+            assert_eq!(sut.sample_time, 0.25);
+            sut.transfer_td(1.0);
+            sut.transfer_td(1.0);
+            let mut sut = sut.set_sample_time(2.0);
+            sut.transfer_td(1.0);
+        }
+        assert_eq!(times, [0.0, 0.25, 4.0]);
+    }
+}
@@ -0,0 +1,241 @@
+//! An integrator element aka I element
+//!
+//! $ out[k] = out[k-1] + T_s P \cdot in[k] $
+//!
+//! where $T_{s}$ is the sample time and $P$ the amplification,
+//! Euler forward method.
+//!
+//! Optional lower and upper limits clamp the output, like the end stops of
+//! a valve or a tank which cannot be emptied below zero. `reset` sets the
+//! output back to zero.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use cb_simulation_util::plant::TransferTimeDomain;
+//! use cb_simulation_util::plant::integrator::Integrator;
+//!
+//! fn main() {
+//!     let mut tank = Integrator::<f64>::default()
+//!         .set_sample_time_or_default(0.5)
+//!         .set_limits(Some(0.0), Some(2.0));
+//!     assert_eq!(tank.transfer_td(1.0), 0.5);
+//!     assert_eq!(tank.transfer_td(-2.0), 0.0);
+//!     for _ in 0..10 {
+//!         tank.transfer_td(1.0);
+//!     }
+//!     assert_eq!(tank.transfer_td(1.0), 2.0);
+//!     tank.reset();
+//!     assert_eq!(tank.state(), 0.0);
+//! }
+//! ```
+
+use super::snapshot::StateAccess;
+use super::*;
+use core::fmt::{self, Display};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Integrator<N> {
+    pub sample_time: f64,
+    pub kp: N,
+    pub lower_limit: Option<N>,
+    pub upper_limit: Option<N>,
+    previous_output: N,
+}
+
+impl<N: Copy + PartialOrd> Integrator<N> {
+    pub fn set_sample_time_or_default(self, sample_time: f64) -> Self {
+        if sample_time > 0.0 {
+            Integrator::<N> {
+                sample_time,
+                ..self
+            }
+        } else {
+            Integrator::<N> {
+                sample_time: 1.0,
+                ..self
+            }
+        }
+    }
+
+    /// Output limits, `None` for an unlimited side; swapped if `lower > upper`
+    pub fn set_limits(self, lower_limit: Option<N>, upper_limit: Option<N>) -> Self {
+        match (lower_limit, upper_limit) {
+            (Some(lower), Some(upper)) if lower > upper => Integrator::<N> {
+                lower_limit: upper_limit,
+                upper_limit: lower_limit,
+                ..self
+            },
+            _ => Integrator::<N> {
+                lower_limit,
+                upper_limit,
+                ..self
+            },
+        }
+    }
+
+    fn clamp(&self, value: N) -> N {
+        match (self.lower_limit, self.upper_limit) {
+            (Some(lower), _) if value < lower => lower,
+            (_, Some(upper)) if value > upper => upper,
+            _ => value,
+        }
+    }
+}
+
+const FIX_KOMMA_SHIFT_BITS: u8 = 10;
+const FIX_KOMMA_SHIFT: i32 = 1 << FIX_KOMMA_SHIFT_BITS;
+
+impl Integrator<i32> {
+    pub fn set_kp(self, kp: i32) -> Self {
+        Integrator::<i32> {
+            kp: kp * FIX_KOMMA_SHIFT,
+            ..self
+        }
+    }
+
+    /// Set the output back to zero
+    pub fn reset(&mut self) {
+        self.previous_output = 0;
+    }
+
+    /// The internal state: the output
+    pub fn state(&self) -> i32 {
+        self.previous_output >> FIX_KOMMA_SHIFT_BITS
+    }
+}
+
+impl Default for Integrator<i32> {
+    fn default() -> Self {
+        Integrator::<i32> {
+            sample_time: 1.0,
+            kp: FIX_KOMMA_SHIFT,
+            lower_limit: None,
+            upper_limit: None,
+            previous_output: 0,
+        }
+    }
+}
+
+impl TransferTimeDomain<i32> for Integrator<i32> {
+    fn transfer_td(&mut self, input: i32) -> i32 {
+        // sample time is fixed point with 10 bits after the comma,
+        // the output is kept with 10 bits after the comma
+        let ts = (self.sample_time * FIX_KOMMA_SHIFT as f64) as i64;
+        let out = self.previous_output as i64
+            + ((ts * input as i64 * self.kp as i64) >> FIX_KOMMA_SHIFT_BITS);
+        let out = match (self.lower_limit, self.upper_limit) {
+            (Some(lower), _) if out < (lower as i64) << FIX_KOMMA_SHIFT_BITS => {
+                (lower as i64) << FIX_KOMMA_SHIFT_BITS
+            }
+            (_, Some(upper)) if out > (upper as i64) << FIX_KOMMA_SHIFT_BITS => {
+                (upper as i64) << FIX_KOMMA_SHIFT_BITS
+            }
+            _ => out,
+        };
+        self.previous_output = out.clamp(i32::MIN as i64, i32::MAX as i64) as i32;
+        self.previous_output >> FIX_KOMMA_SHIFT_BITS
+    }
+}
+
+impl Integrator<f64> {
+    pub fn set_kp(self, kp: f64) -> Self {
+        Integrator::<f64> { kp, ..self }
+    }
+
+    /// Set the output back to zero
+    pub fn reset(&mut self) {
+        self.previous_output = 0.0;
+    }
+
+    /// The internal state: the output
+    pub fn state(&self) -> f64 {
+        self.previous_output
+    }
+}
+
+impl Default for Integrator<f64> {
+    fn default() -> Self {
+        Integrator::<f64> {
+            sample_time: 1.0,
+            kp: 1.0,
+            lower_limit: None,
+            upper_limit: None,
+            previous_output: 0.0,
+        }
+    }
+}
+
+impl TransferTimeDomain<f64> for Integrator<f64> {
+    fn transfer_td(&mut self, input: f64) -> f64 {
+        let out = self.clamp(self.previous_output + self.sample_time * self.kp * input);
+        self.previous_output = out;
+        out
+    }
+}
+
+impl<N: Copy + Debug + PartialEq> StateAccess for Integrator<N> {
+    /// Previous output, for `i32` in fixed point
+    type State = N;
+
+    fn save_state(&self) -> Self::State {
+        self.previous_output
+    }
+
+    fn restore_state(&mut self, state: &Self::State) {
+        self.previous_output = *state;
+    }
+}
+
+impl<N> TypeIdentifier for Integrator<N> {
+    fn short_type_name(&self) -> &'static str {
+        "Integrator"
+    }
+}
+
+impl<N> SampleTime for Integrator<N> {
+    fn sample_time(&self) -> Option<f64> {
+        Some(self.sample_time)
+    }
+}
+
+impl<N: Display + Debug> Display for Integrator<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Integrator(sample_time: {}, kp: {}, lower_limit: {:?}, upper_limit: {:?})",
+            self.sample_time, self.kp, self.lower_limit, self.upper_limit
+        )
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_Integrator_i32_limits() {
+        let mut sut = Integrator::<i32>::default()
+            .set_kp(2)
+            .set_sample_time_or_default(0.5)
+            .set_limits(Some(100), Some(-100));
+        assert_eq!(sut.lower_limit, Some(-100));
+        assert_eq!(sut.transfer_td(30), 30);
+        assert_eq!(sut.transfer_td(30), 60);
+        assert_eq!(sut.transfer_td(100), 100);
+        assert_eq!(sut.transfer_td(-15), 85);
+        sut.reset();
+        assert_eq!(sut.transfer_td(-500), -100);
+    }
+
+    #[test]
+    fn test_Integrator_f64_unlimited() {
+        let mut sut = Integrator::<f64>::default().set_kp(0.5);
+        for _ in 0..4 {
+            sut.transfer_td(-1.0);
+        }
+        assert_eq!(sut.state(), -2.0);
+    }
+}
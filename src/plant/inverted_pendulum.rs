@@ -0,0 +1,264 @@
+//! An inverted pendulum on a cart
+//!
+//! $ \ddot{x} = \frac{F - b \dot{x} + m l \dot{\theta}^{2} \sin\theta - m g \sin\theta \cos\theta}{M + m \sin^{2}\theta} $
+//!
+//! $ \ddot{\theta} = \frac{g \sin\theta - \ddot{x} \cos\theta}{l} $
+//!
+//! with cart mass $M$, pendulum mass $m$ concentrated at the distance $l$
+//! from the pivot, cart friction $b$ and the gravity $g$. The angle $\theta$
+//! is 0 upright and positive when the pendulum leans in the positive $x$
+//! direction, the input is the force $F$ on the cart in N.
+//!
+//! The full nonlinear dynamics are integrated with the classic Runge-Kutta
+//! method, see `OdeSolver`, each sample is split into steps of at most 1 ms.
+//!
+//! As `TransferTimeDomain` the output is the angle in rad, as
+//! `MimoTransferTimeDomain` the outputs are the whole state
+//! `[position, velocity, angle, angular_velocity]`.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use cb_simulation_util::plant::TransferTimeDomain;
+//! use cb_simulation_util::plant::inverted_pendulum::InvertedPendulum;
+//!
+//! fn main() {
+//!     let mut sut = InvertedPendulum::default().set_sample_time_or_default(0.01);
+//!     sut.set_state([0.0, 0.0, 0.1, 0.0]);
+//!     // a PD controller pushes the cart below the falling pendulum, it keeps
+//!     // the pendulum upright while the cart drifts away slowly
+//!     let mut angle = 0.1;
+//!     for _ in 0..500 {
+//!         let force = 40.0 * angle + 10.0 * sut.state()[3];
+//!         angle = sut.transfer_td(force);
+//!     }
+//!     assert!(angle.abs() < 0.01);
+//!     // without control it falls over
+//!     sut.set_state([0.0, 0.0, 0.1, 0.0]);
+//!     for _ in 0..100 {
+//!         angle = sut.transfer_td(0.0);
+//!     }
+//!     assert!(angle > 1.0);
+//! }
+//! ```
+
+use ndarray::{Array1, ArrayView1};
+
+use super::ode::{OdeRhs, OdeSolver};
+use super::snapshot::StateAccess;
+use super::*;
+use core::fmt::{self, Display};
+
+/// Largest integration step in s
+const MAX_STEP: f64 = 1.0e-3;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct InvertedPendulum {
+    pub sample_time: f64,
+    /// Cart mass in kg
+    pub cart_mass: f64,
+    /// Pendulum mass in kg
+    pub pendulum_mass: f64,
+    /// Distance of the pendulum mass from the pivot in m
+    pub length: f64,
+    /// Viscous friction of the cart in Ns/m
+    pub friction: f64,
+    /// Gravity in m/s²
+    pub gravity: f64,
+    state: [f64; 4],
+}
+
+impl InvertedPendulum {
+    pub fn set_sample_time_or_default(self, sample_time: f64) -> Self {
+        let sample_time = if sample_time > 0.0 { sample_time } else { 1.0 };
+        InvertedPendulum {
+            sample_time,
+            ..self
+        }
+    }
+
+    /// Cart mass, pendulum mass and length, non-positive values are ignored
+    pub fn set_geometry(self, cart_mass: f64, pendulum_mass: f64, length: f64) -> Self {
+        if cart_mass <= 0.0 || pendulum_mass <= 0.0 || length <= 0.0 {
+            return self;
+        }
+        InvertedPendulum {
+            cart_mass,
+            pendulum_mass,
+            length,
+            ..self
+        }
+    }
+
+    /// Cart friction, negative values are ignored
+    pub fn set_friction(self, friction: f64) -> Self {
+        if friction < 0.0 {
+            return self;
+        }
+        InvertedPendulum { friction, ..self }
+    }
+
+    /// The state `[position, velocity, angle, angular_velocity]`
+    pub fn state(&self) -> [f64; 4] {
+        self.state
+    }
+
+    /// Start from `[position, velocity, angle, angular_velocity]`
+    pub fn set_state(&mut self, state: [f64; 4]) {
+        self.state = state;
+    }
+
+    /// Growth rate of a small deviation from upright in 1/s, friction neglected
+    ///
+    /// $ \lambda = \sqrt{g (M + m) / (M l)} $, the unstable pole of the
+    /// linearized plant.
+    pub fn unstable_pole(&self) -> f64 {
+        let total = self.cart_mass + self.pendulum_mass;
+        (self.gravity * total / (self.cart_mass * self.length)).sqrt()
+    }
+
+    /// Integrate over one sample with the force held constant
+    fn advance(&mut self, force: f64) {
+        let steps = (self.sample_time / MAX_STEP).ceil().max(1.0);
+        let h = self.sample_time / steps;
+        let mut state = self.state;
+        for _ in 0..steps as usize {
+            OdeSolver::RungeKutta4.step(self, &mut state, force, h);
+        }
+        self.state = state;
+    }
+}
+
+impl OdeRhs for InvertedPendulum {
+    fn states(&self) -> usize {
+        4
+    }
+
+    fn derivative(&self, state: &[f64], force: f64, derivative: &mut [f64]) {
+        let (v, theta, omega) = (state[1], state[2], state[3]);
+        let (m, l, g) = (self.pendulum_mass, self.length, self.gravity);
+        let (sin, cos) = (theta.sin(), theta.cos());
+        let acceleration = (force - self.friction * v + m * l * omega * omega * sin
+            - m * g * sin * cos)
+            / (self.cart_mass + m * sin * sin);
+        derivative[0] = v;
+        derivative[1] = acceleration;
+        derivative[2] = omega;
+        derivative[3] = (g * sin - acceleration * cos) / l;
+    }
+
+    fn output(&self, state: &[f64], _force: f64) -> f64 {
+        state[2]
+    }
+}
+
+impl Default for InvertedPendulum {
+    /// 1 kg cart, 0.1 kg at 0.5 m, 0.1 Ns/m friction, upright at rest
+    fn default() -> Self {
+        InvertedPendulum {
+            sample_time: 1.0,
+            cart_mass: 1.0,
+            pendulum_mass: 0.1,
+            length: 0.5,
+            friction: 0.1,
+            gravity: 9.81,
+            state: [0.0; 4],
+        }
+    }
+}
+
+impl StateAccess for InvertedPendulum {
+    /// `[position, velocity, angle, angular velocity]`
+    type State = [f64; 4];
+
+    fn save_state(&self) -> Self::State {
+        self.state
+    }
+
+    fn restore_state(&mut self, state: &Self::State) {
+        self.state = *state;
+    }
+}
+
+impl TypeIdentifier for InvertedPendulum {
+    fn short_type_name(&self) -> &'static str {
+        "InvertedPendulum"
+    }
+}
+
+impl SampleTime for InvertedPendulum {
+    fn sample_time(&self) -> Option<f64> {
+        Some(self.sample_time)
+    }
+}
+
+impl Display for InvertedPendulum {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "InvertedPendulum(sample_time: {}, cart_mass: {}, pendulum_mass: {}, length: {}, friction: {})",
+            self.sample_time, self.cart_mass, self.pendulum_mass, self.length, self.friction
+        )
+    }
+}
+
+impl TransferTimeDomain<f64> for InvertedPendulum {
+    fn transfer_td(&mut self, force: f64) -> f64 {
+        self.advance(force);
+        self.state[2]
+    }
+}
+
+impl MimoTransferTimeDomain for InvertedPendulum {
+    fn input_count(&self) -> usize {
+        1
+    }
+
+    fn output_count(&self) -> usize {
+        4
+    }
+
+    fn transfer_td(&mut self, u: ArrayView1<f64>) -> Array1<f64> {
+        self.advance(u[0]);
+        Array1::from_vec(self.state.to_vec())
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use ndarray::array;
+    use std::vec;
+
+    #[test]
+    fn test_InvertedPendulum_small_deviation_grows_with_unstable_pole() {
+        let mut sut = InvertedPendulum::default()
+            .set_friction(0.0)
+            .set_sample_time_or_default(0.01);
+        sut.set_state([0.0, 0.0, 1.0e-6, 0.0]);
+        for _ in 0..50 {
+            TransferTimeDomain::transfer_td(&mut sut, 0.0);
+        }
+        let expected = 1.0e-6 * (sut.unstable_pole() * 0.5).cosh();
+        assert!((sut.state()[2] / expected - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_InvertedPendulum_hanging_keeps_momentum() {
+        // hanging down without friction: the cart and pendulum swing, the
+        // total momentum in x stays zero
+        let mut sut = InvertedPendulum::default()
+            .set_friction(0.0)
+            .set_sample_time_or_default(0.05);
+        sut.set_state([0.0, 0.0, core::f64::consts::PI - 0.3, 0.0]);
+        for _ in 0..40 {
+            let y = MimoTransferTimeDomain::transfer_td(&mut sut, array![0.0].view());
+            let momentum = (sut.cart_mass + sut.pendulum_mass) * y[1]
+                + sut.pendulum_mass * sut.length * y[3] * y[2].cos();
+            assert!(momentum.abs() < 1e-9);
+        }
+        assert_eq!(sut.set_geometry(0.0, 1.0, 1.0).cart_mass, 1.0);
+    }
+}
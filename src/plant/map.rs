@@ -0,0 +1,287 @@
+//! Lookup table static nonlinearities
+//!
+//! * `Map1D`: $ out[k] = f(in[k]) $, e.g. valve characteristics or pump curves
+//! * `Map2D`: $ out[k] = f(in[k], v(t)) $, e.g. engine maps over speed and load,
+//!   the second input $v$ is a time signal
+//!
+//! Between breakpoints the table is interpolated linearly (bilinearly for
+//! `Map2D`). Outside the breakpoints the boundary value is held, or for
+//! `Map1D` optionally extrapolated linearly.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use cb_simulation_util::plant::TransferTimeDomain;
+//! use cb_simulation_util::plant::map::{Extrapolation, Map1D};
+//!
+//! fn main() {
+//!     // equal percentage valve: opening in % → relative flow
+//!     let mut valve = Map1D::new(
+//!         vec![0.0, 25.0, 50.0, 75.0, 100.0],
+//!         vec![0.0, 0.06, 0.18, 0.42, 1.0],
+//!     )
+//!     .unwrap();
+//!     assert!((valve.transfer_td(62.5) - 0.30).abs() < 1e-12);
+//!     assert_eq!(valve.transfer_td(120.0), 1.0);
+//!     let mut valve = valve.set_extrapolation(Extrapolation::Linear);
+//!     assert!((valve.transfer_td(-25.0) + 0.06).abs() < 1e-12);
+//! }
+//! ```
+
+use ndarray::Array2;
+use std::vec::Vec;
+
+use super::*;
+use crate::signal::BoxedTimeSignal;
+use core::fmt::{self, Display};
+
+/// Behaviour outside the breakpoints
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Extrapolation {
+    /// Hold the boundary value
+    #[default]
+    Clamp,
+    /// Continue the first and last segment
+    Linear,
+}
+
+fn check_breakpoints(breakpoints: &[f64], values: usize) -> Result<(), &'static str> {
+    if breakpoints.len() < 2 {
+        return Err("Invalid map: at least two breakpoints required");
+    }
+    if breakpoints.windows(2).any(|w| w[1] <= w[0]) {
+        return Err("Invalid map: breakpoints must strictly increase");
+    }
+    if breakpoints.len() != values {
+        return Err("Invalid map: number of breakpoints and values differ");
+    }
+    Ok(())
+}
+
+/// Segment index `i` and position `t` with `x = x[i] + t (x[i+1] - x[i])`
+fn locate(breakpoints: &[f64], x: f64, extrapolation: Extrapolation) -> (usize, f64) {
+    let i = breakpoints
+        .partition_point(|b| *b <= x)
+        .clamp(1, breakpoints.len() - 1)
+        - 1;
+    let t = (x - breakpoints[i]) / (breakpoints[i + 1] - breakpoints[i]);
+    match extrapolation {
+        Extrapolation::Clamp => (i, t.clamp(0.0, 1.0)),
+        Extrapolation::Linear => (i, t),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Map1D {
+    breakpoints: Vec<f64>,
+    values: Vec<f64>,
+    pub extrapolation: Extrapolation,
+}
+
+impl Map1D {
+    /// Table of `values` at strictly increasing `breakpoints`
+    pub fn new(breakpoints: Vec<f64>, values: Vec<f64>) -> Result<Self, &'static str> {
+        check_breakpoints(&breakpoints, values.len())?;
+        Ok(Map1D {
+            breakpoints,
+            values,
+            extrapolation: Extrapolation::Clamp,
+        })
+    }
+
+    pub fn set_extrapolation(self, extrapolation: Extrapolation) -> Self {
+        Map1D {
+            extrapolation,
+            ..self
+        }
+    }
+
+    pub fn breakpoints(&self) -> &[f64] {
+        &self.breakpoints
+    }
+
+    pub fn values(&self) -> &[f64] {
+        &self.values
+    }
+
+    pub fn lookup(&self, x: f64) -> f64 {
+        let (i, t) = locate(&self.breakpoints, x, self.extrapolation);
+        self.values[i] + t * (self.values[i + 1] - self.values[i])
+    }
+}
+
+impl TypeIdentifier for Map1D {
+    fn short_type_name(&self) -> &'static str {
+        "Map1D"
+    }
+}
+
+impl SampleTime for Map1D {
+    fn sample_time(&self) -> Option<f64> {
+        None
+    }
+}
+
+impl Display for Map1D {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Map1D(breakpoints: {:?}, values: {:?}, extrapolation: {:?})",
+            self.breakpoints, self.values, self.extrapolation
+        )
+    }
+}
+
+impl TransferTimeDomain<f64> for Map1D {
+    fn transfer_td(&mut self, input: f64) -> f64 {
+        self.lookup(input)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Map2D {
+    x_breakpoints: Vec<f64>,
+    y_breakpoints: Vec<f64>,
+    /// `table[[i, j]]` is the value at `(x_breakpoints[i], y_breakpoints[j])`
+    table: Array2<f64>,
+    /// Second input over time, 0 if not set
+    pub second_input: Option<BoxedTimeSignal<f64>>,
+    pub sample_time: f64,
+    time: f64,
+}
+
+impl Map2D {
+    /// Table with one row per x breakpoint and one column per y breakpoint
+    pub fn new(
+        x_breakpoints: Vec<f64>,
+        y_breakpoints: Vec<f64>,
+        table: Array2<f64>,
+    ) -> Result<Self, &'static str> {
+        check_breakpoints(&x_breakpoints, table.nrows())?;
+        check_breakpoints(&y_breakpoints, table.ncols())?;
+        Ok(Map2D {
+            x_breakpoints,
+            y_breakpoints,
+            table,
+            second_input: None,
+            sample_time: 1.0,
+            time: 0.0,
+        })
+    }
+
+    pub fn set_second_input(self, second_input: BoxedTimeSignal<f64>) -> Self {
+        Map2D {
+            second_input: Some(second_input),
+            ..self
+        }
+    }
+
+    pub fn set_sample_time_or_default(self, sample_time: f64) -> Self {
+        if sample_time > 0.0 {
+            Map2D {
+                sample_time,
+                ..self
+            }
+        } else {
+            Map2D {
+                sample_time: 1.0,
+                ..self
+            }
+        }
+    }
+
+    /// Bilinear interpolation, clamped at the table boundaries
+    pub fn lookup(&self, x: f64, y: f64) -> f64 {
+        let (i, s) = locate(&self.x_breakpoints, x, Extrapolation::Clamp);
+        let (j, t) = locate(&self.y_breakpoints, y, Extrapolation::Clamp);
+        let v = &self.table;
+        (1.0 - s) * ((1.0 - t) * v[[i, j]] + t * v[[i, j + 1]])
+            + s * ((1.0 - t) * v[[i + 1, j]] + t * v[[i + 1, j + 1]])
+    }
+}
+
+impl PartialEq for Map2D {
+    fn eq(&self, other: &Self) -> bool {
+        let inputs_equal = match (&self.second_input, &other.second_input) {
+            (Some(a), Some(b)) => a.eq(b),
+            (None, None) => true,
+            _ => false,
+        };
+        inputs_equal
+            && self.x_breakpoints == other.x_breakpoints
+            && self.y_breakpoints == other.y_breakpoints
+            && self.table == other.table
+            && self.sample_time == other.sample_time
+            && self.time == other.time
+    }
+}
+
+impl TypeIdentifier for Map2D {
+    fn short_type_name(&self) -> &'static str {
+        "Map2D"
+    }
+}
+
+impl SampleTime for Map2D {
+    fn sample_time(&self) -> Option<f64> {
+        Some(self.sample_time)
+    }
+}
+
+impl Display for Map2D {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Map2D(x_breakpoints: {:?}, y_breakpoints: {:?}",
+            self.x_breakpoints, self.y_breakpoints
+        )?;
+        if let Some(second_input) = &self.second_input {
+            write!(f, ", second_input: {}", second_input)?;
+        }
+        write!(f, ")")
+    }
+}
+
+impl TransferTimeDomain<f64> for Map2D {
+    fn transfer_td(&mut self, input: f64) -> f64 {
+        let y = self
+            .second_input
+            .as_ref()
+            .map_or(0.0, |s| s.time_to_signal(self.time));
+        self.time += self.sample_time;
+        self.lookup(input, y)
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::signal::StepFunction;
+    use ndarray::array;
+    use std::vec;
+
+    #[test]
+    fn test_Map1D_invalid() {
+        assert!(Map1D::new(vec![0.0], vec![1.0]).is_err());
+        assert!(Map1D::new(vec![0.0, 0.0], vec![1.0, 2.0]).is_err());
+        assert!(Map1D::new(vec![0.0, 1.0], vec![1.0]).is_err());
+    }
+
+    #[test]
+    fn test_Map2D_bilinear_with_second_input() {
+        // x: speed, y: load
+        let table = array![[0.0, 10.0], [20.0, 40.0]];
+        let mut sut = Map2D::new(vec![0.0, 100.0], vec![0.0, 1.0], table)
+            .unwrap()
+            .set_second_input(Box::new(
+                StepFunction::default().pre(0.5).post(1.0).step(0.5),
+            ));
+        assert_eq!(sut.lookup(50.0, 0.5), 17.5);
+        assert_eq!(sut.lookup(200.0, -1.0), 20.0);
+        assert_eq!(sut.transfer_td(50.0), 17.5);
+        assert_eq!(sut.transfer_td(50.0), 25.0);
+        assert!(Map2D::new(vec![0.0, 1.0], vec![0.0, 1.0, 2.0], Array2::zeros((2, 2))).is_err());
+    }
+}
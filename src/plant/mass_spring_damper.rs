@@ -0,0 +1,241 @@
+//! A mass-spring-damper, e.g. a vibration mount or a flexible drive
+//!
+//! $ m \ddot{x} + c \dot{x} + k x = F $
+//!
+//! with mass $m$, damping coefficient $c$ and stiffness $k$. The input is the
+//! force $F$, the output the position $x$ or, selected with `set_output`,
+//! the velocity $\dot{x}$; both are available from the state. The linear
+//! model is discretized with a zero-order hold, i.e. exact for a force held
+//! constant over a sample.
+//!
+//! It is a `PT2` with physical parameters: $ K = 1 / k $,
+//! $ \omega_{0} = \sqrt{k / m} $ and $ D = c / (2 \sqrt{k m}) $.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use cb_simulation_util::plant::TransferTimeDomain;
+//! use cb_simulation_util::plant::mass_spring_damper::MassSpringDamper;
+//!
+//! fn main() {
+//!     // 2 kg on a 800 N/m spring, 8 Ns/m damping: 20 rad/s, D = 0.1
+//!     let mut sut = MassSpringDamper::default()
+//!         .set_parameters(2.0, 800.0, 8.0)
+//!         .set_sample_time_or_default(0.001);
+//!     assert!((sut.damping_ratio() - 0.1).abs() < 1e-12);
+//!     let mut x = 0.0;
+//!     for _ in 0..10000 {
+//!         x = sut.transfer_td(40.0);
+//!     }
+//!     // static deflection F / k
+//!     assert!((x - 0.05).abs() < 1e-6);
+//! }
+//! ```
+
+use ndarray::{Array1, Array2, array};
+use std::vec;
+
+use super::snapshot::StateAccess;
+use super::state_space::StateSpace;
+use super::*;
+use core::fmt::{self, Display};
+
+/// The output of a `MassSpringDamper`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MassSpringDamperOutput {
+    #[default]
+    Position,
+    Velocity,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MassSpringDamper {
+    /// Mass in kg
+    pub mass: f64,
+    /// Spring stiffness in N/m
+    pub stiffness: f64,
+    /// Damping coefficient in Ns/m
+    pub damping: f64,
+    pub output: MassSpringDamperOutput,
+    model: StateSpace,
+}
+
+impl MassSpringDamper {
+    fn rebuild(self, sample_time: f64) -> Self {
+        let (m, k, c) = (self.mass, self.stiffness, self.damping);
+        let state = self.model.state().clone();
+        let mut model = StateSpace::from_continuous(
+            array![[0.0, 1.0], [-k / m, -c / m]],
+            array![[0.0], [1.0 / m]],
+            Array2::eye(2),
+            Array2::zeros((2, 1)),
+            sample_time,
+        )
+        .expect("2 states, 1 input always fit");
+        model.set_state(state);
+        MassSpringDamper { model, ..self }
+    }
+
+    pub fn set_sample_time_or_default(self, sample_time: f64) -> Self {
+        let sample_time = if sample_time > 0.0 { sample_time } else { 1.0 };
+        self.rebuild(sample_time)
+    }
+
+    /// Mass, stiffness and damping, invalid values are ignored
+    ///
+    /// Mass and stiffness must be > 0, damping >= 0.
+    pub fn set_parameters(self, mass: f64, stiffness: f64, damping: f64) -> Self {
+        if mass <= 0.0 || stiffness <= 0.0 || damping < 0.0 {
+            return self;
+        }
+        let sample_time = self.model.sample_time;
+        MassSpringDamper {
+            mass,
+            stiffness,
+            damping,
+            ..self
+        }
+        .rebuild(sample_time)
+    }
+
+    pub fn set_output(self, output: MassSpringDamperOutput) -> Self {
+        MassSpringDamper { output, ..self }
+    }
+
+    /// Undamped natural angular frequency $ \omega_{0} = \sqrt{k / m} $
+    pub fn natural_frequency(&self) -> f64 {
+        (self.stiffness / self.mass).sqrt()
+    }
+
+    /// Damping ratio $ D = c / (2 \sqrt{k m}) $
+    pub fn damping_ratio(&self) -> f64 {
+        self.damping / (2.0 * (self.stiffness * self.mass).sqrt())
+    }
+
+    pub fn position(&self) -> f64 {
+        self.model.state()[0]
+    }
+
+    pub fn velocity(&self) -> f64 {
+        self.model.state()[1]
+    }
+
+    /// The state `[position, velocity]`
+    pub fn state(&self) -> &Array1<f64> {
+        self.model.state()
+    }
+
+    /// Start from `position` and `velocity`
+    pub fn set_state(&mut self, position: f64, velocity: f64) {
+        self.model.set_state(array![position, velocity]);
+    }
+}
+
+impl Default for MassSpringDamper {
+    /// 1 kg, 1 N/m, 1 Ns/m: $ \omega_{0} = 1 $, $ D = 0.5 $
+    fn default() -> Self {
+        MassSpringDamper {
+            mass: 1.0,
+            stiffness: 1.0,
+            damping: 1.0,
+            output: MassSpringDamperOutput::Position,
+            model: StateSpace::new(
+                Array2::eye(2),
+                Array2::zeros((2, 1)),
+                Array2::eye(2),
+                Array2::zeros((2, 1)),
+            )
+            .expect("2 states, 1 input always fit"),
+        }
+        .rebuild(1.0)
+    }
+}
+
+impl StateAccess for MassSpringDamper {
+    type State = Array1<f64>;
+
+    fn save_state(&self) -> Self::State {
+        self.model.save_state()
+    }
+
+    fn restore_state(&mut self, state: &Self::State) {
+        self.model.restore_state(state);
+    }
+}
+
+impl TypeIdentifier for MassSpringDamper {
+    fn short_type_name(&self) -> &'static str {
+        "MassSpringDamper"
+    }
+}
+
+impl SampleTime for MassSpringDamper {
+    fn sample_time(&self) -> Option<f64> {
+        Some(self.model.sample_time)
+    }
+}
+
+impl Display for MassSpringDamper {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "MassSpringDamper(sample_time: {}, mass: {}, stiffness: {}, damping: {}, output: {:?})",
+            self.model.sample_time, self.mass, self.stiffness, self.damping, self.output
+        )
+    }
+}
+
+impl TransferTimeDomain<f64> for MassSpringDamper {
+    fn transfer_td(&mut self, input: f64) -> f64 {
+        let y = MimoTransferTimeDomain::transfer_td(&mut self.model, array![input].view());
+        match self.output {
+            MassSpringDamperOutput::Position => y[0],
+            MassSpringDamperOutput::Velocity => y[1],
+        }
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::plant::pt2::PT2;
+
+    #[test]
+    fn test_MassSpringDamper_free_oscillation() {
+        // undamped: x(t) = x0 cos(w0 t), v(t) = -x0 w0 sin(w0 t)
+        let mut sut = MassSpringDamper::default()
+            .set_parameters(0.5, 2.0, 0.0)
+            .set_sample_time_or_default(0.01)
+            .set_output(MassSpringDamperOutput::Velocity);
+        sut.set_state(0.1, 0.0);
+        let mut v = 0.0;
+        for _ in 0..100 {
+            v = sut.transfer_td(0.0);
+        }
+        // the output is the state before the step, t = 0.99
+        assert!((v + 0.1 * 2.0 * (2.0f64 * 0.99).sin()).abs() < 1e-9);
+        assert!((sut.position() - 0.1 * 2.0f64.cos()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_MassSpringDamper_matches_PT2_steady_state() {
+        let mut sut = MassSpringDamper::default()
+            .set_parameters(1.0, 4.0, 2.0)
+            .set_sample_time_or_default(0.1);
+        let mut pt2 = PT2::<f64>::default()
+            .set_sample_time_or_default(0.1)
+            .set_kp(0.25)
+            .set_omega_or_default(sut.natural_frequency())
+            .set_damping_or_default(sut.damping_ratio());
+        let (mut x, mut y) = (0.0, 0.0);
+        for _ in 0..500 {
+            x = sut.transfer_td(2.0);
+            y = pt2.transfer_td(2.0);
+        }
+        assert!((x - 0.5).abs() < 1e-9);
+        assert!((x - y).abs() < 1e-6);
+        assert_eq!(sut.set_parameters(0.0, 1.0, 1.0).mass, 1.0);
+    }
+}
@@ -0,0 +1,232 @@
+//! # Measurement chain preset
+//!
+//! A complete digital measurement chain as one block, built from a few
+//! datasheet values of the sensor and its ADC:
+//!
+//! 1. anti-aliasing filter: 2nd order Butterworth low pass with the cutoff
+//!    at 40 % of the sensor sample rate, discretized with Tustin at the
+//!    simulation sample time
+//! 2. sampler: takes a sample at the sensor rate and holds it (zero-order
+//!    hold) until the next one, the sensor rate is independent of the
+//!    simulation sample time
+//! 3. noise: white gaussian noise with the rms value of the datasheet,
+//!    reproducible from the seed
+//! 4. quantizer: clamps to the measurement range and rounds to the ADC
+//!    resolution
+//!
+//! ## Example
+//!
+//! ```rust
+//! use cb_simulation_util::plant::TransferTimeDomain;
+//! use cb_simulation_util::plant::measurement_chain::{MeasurementChain, SensorDatasheet};
+//!
+//! fn main() {
+//!     // 0..10 bar transmitter, 12 bit ADC sampled at 100 Hz, simulated at 1 ms
+//!     let datasheet = SensorDatasheet {
+//!         range: (0.0, 10.0),
+//!         bits: 12,
+//!         sample_rate: 100.0,
+//!         noise_rms: 0.0,
+//!     };
+//!     let mut sut = MeasurementChain::from_datasheet(datasheet, 0.001).unwrap();
+//!     let mut y = 0.0;
+//!     for _ in 0..1000 {
+//!         y = sut.transfer_td(4.2);
+//!     }
+//!     assert!((y - 4.2).abs() <= sut.resolution());
+//!     assert_eq!(sut.transfer_td(20.0), y); // held until the next sensor sample
+//! }
+//! ```
+
+use std::vec;
+
+use super::continuous_transfer::{ContinuousTransfer, Discretization};
+use super::discrete_transfer::DiscreteTransfer;
+use super::*;
+use core::f64::consts::{PI, SQRT_2};
+use core::fmt::{self, Display};
+
+/// Datasheet values of a sensor with its ADC
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SensorDatasheet {
+    /// Measurement range `(min, max)` in the unit of the measured value
+    pub range: (f64, f64),
+    /// ADC resolution in bits
+    pub bits: u32,
+    /// Sensor sample rate in Hz
+    pub sample_rate: f64,
+    /// Rms value of the sensor noise in the unit of the measured value
+    pub noise_rms: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MeasurementChain {
+    pub datasheet: SensorDatasheet,
+    pub sample_time: f64,
+    pub seed: u64,
+    filter: DiscreteTransfer<f64>,
+    time: f64,
+    next_sample_time: f64,
+    samples: u64,
+    held: f64,
+}
+
+impl MeasurementChain {
+    /// Chain for simulation steps of `sample_time`
+    ///
+    /// Fails if the sensor samples faster than the simulation, or for an
+    /// empty range or a resolution outside 1..=32 bits.
+    pub fn from_datasheet(
+        datasheet: SensorDatasheet,
+        sample_time: f64,
+    ) -> Result<Self, &'static str> {
+        if sample_time <= 0.0 {
+            return Err("Invalid sample_time: Must be > 0.0");
+        }
+        if datasheet.sample_rate <= 0.0 || datasheet.sample_rate * sample_time > 1.0 + 1e-9 {
+            return Err("Invalid sample_rate: Must be > 0.0 and not faster than the simulation");
+        }
+        if datasheet.range.0 >= datasheet.range.1 {
+            return Err("Invalid range: min must be < max");
+        }
+        if !(1..=32).contains(&datasheet.bits) {
+            return Err("Invalid bits: Must be within 1..=32");
+        }
+        let wc = 2.0 * PI * 0.4 * datasheet.sample_rate;
+        let filter = ContinuousTransfer::new(vec![wc * wc], vec![1.0, SQRT_2 * wc, wc * wc])?
+            .discretize(Discretization::Tustin, sample_time)?;
+        Ok(MeasurementChain {
+            datasheet,
+            sample_time,
+            seed: 0,
+            filter,
+            time: 0.0,
+            next_sample_time: 0.0,
+            samples: 0,
+            held: datasheet.range.0,
+        })
+    }
+
+    pub fn set_seed(self, seed: u64) -> Self {
+        MeasurementChain { seed, ..self }
+    }
+
+    /// Cutoff frequency of the anti-aliasing filter in Hz
+    pub fn cutoff_frequency(&self) -> f64 {
+        0.4 * self.datasheet.sample_rate
+    }
+
+    /// Value of the least significant bit
+    pub fn resolution(&self) -> f64 {
+        let (min, max) = self.datasheet.range;
+        (max - min) / ((1u64 << self.datasheet.bits) - 1) as f64
+    }
+
+    fn quantize(&self, value: f64) -> f64 {
+        let (min, max) = self.datasheet.range;
+        let lsb = self.resolution();
+        let clamped = value.clamp(min, max);
+        min + ((clamped - min) / lsb).round() * lsb
+    }
+}
+
+impl TypeIdentifier for MeasurementChain {
+    fn short_type_name(&self) -> &'static str {
+        "MeasurementChain"
+    }
+}
+
+impl SampleTime for MeasurementChain {
+    fn sample_time(&self) -> Option<f64> {
+        Some(self.sample_time)
+    }
+}
+
+impl Display for MeasurementChain {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "MeasurementChain(sample_time: {}, range: [{}, {}], bits: {}, sample_rate: {}, noise_rms: {})",
+            self.sample_time,
+            self.datasheet.range.0,
+            self.datasheet.range.1,
+            self.datasheet.bits,
+            self.datasheet.sample_rate,
+            self.datasheet.noise_rms
+        )
+    }
+}
+
+impl TransferTimeDomain<f64> for MeasurementChain {
+    fn transfer_td(&mut self, input: f64) -> f64 {
+        let filtered = self.filter.transfer_td(input);
+        self.time += self.sample_time;
+        if self.time >= self.next_sample_time - 1e-9 * self.sample_time {
+            let noise = if self.datasheet.noise_rms > 0.0 {
+                let bits = crate::rng::mix(self.seed ^ crate::rng::mix(self.samples));
+                self.datasheet.noise_rms * crate::rng::normal(bits, crate::rng::mix(bits))
+            } else {
+                0.0
+            };
+            self.held = self.quantize(filtered + noise);
+            self.samples += 1;
+            self.next_sample_time += 1.0 / self.datasheet.sample_rate;
+        }
+        self.held
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use std::vec::Vec;
+
+    fn datasheet() -> SensorDatasheet {
+        SensorDatasheet {
+            range: (-1.0, 1.0),
+            bits: 16,
+            sample_rate: 50.0,
+            noise_rms: 0.0,
+        }
+    }
+
+    #[test]
+    fn test_MeasurementChain_attenuates_aliasing_frequency() {
+        // 45 Hz would alias to 5 Hz at 50 Hz sampling
+        let ts = 0.0005;
+        let mut sut = MeasurementChain::from_datasheet(datasheet(), ts).unwrap();
+        let out: Vec<f64> = (0..4000)
+            .map(|k| sut.transfer_td(0.9 * (2.0 * PI * 45.0 * k as f64 * ts).sin()))
+            .collect();
+        let peak = out[2000..].iter().fold(0.0f64, |m, y| m.max(y.abs()));
+        assert!(peak < 0.45, "{}", peak);
+    }
+
+    #[test]
+    fn test_MeasurementChain_noise_rms_and_seed() {
+        let noisy = SensorDatasheet {
+            noise_rms: 0.01,
+            ..datasheet()
+        };
+        let mut sut = MeasurementChain::from_datasheet(noisy, 0.02)
+            .unwrap()
+            .set_seed(7);
+        let mut reference = sut.clone();
+        let out: Vec<f64> = (0..5000).map(|_| sut.transfer_td(0.0)).collect();
+        let rms = (out.iter().map(|y| y * y).sum::<f64>() / out.len() as f64).sqrt();
+        assert!((rms - 0.01).abs() < 0.001, "{}", rms);
+        assert_eq!(reference.transfer_td(0.0), out[0]);
+    }
+
+    #[test]
+    fn test_MeasurementChain_invalid_datasheet() {
+        assert!(MeasurementChain::from_datasheet(datasheet(), 0.1).is_err());
+        let reversed = SensorDatasheet {
+            range: (1.0, -1.0),
+            ..datasheet()
+        };
+        assert!(MeasurementChain::from_datasheet(reversed, 0.001).is_err());
+    }
+}
@@ -0,0 +1,228 @@
+//! A median filter, e.g. the spike rejection of a noisy sensor signal
+//!
+//! $ out[k] = median(in[k-n+1], \dots, in[k]) $
+//!
+//! where $n$ is the window length in samples. Single spikes shorter than
+//! half the window are removed completely, while steps pass with a delay of
+//! half the window and without being smeared like by a moving average.
+//!
+//! For an even window the mean of the two middle values is used. Until the
+//! window is filled the median is taken over the samples seen so far.
+//!
+//! `NaN` samples, e.g. dropped sensor readings, are ignored like spikes: the
+//! median is taken over the other samples of the window. A window of `NaN`
+//! samples only gives `NaN`.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use cb_simulation_util::plant::TransferTimeDomain;
+//! use cb_simulation_util::plant::median_filter::MedianFilter;
+//!
+//! fn main() {
+//!     let mut despike = MedianFilter::<f64>::default().set_window(3).unwrap();
+//!     let out: Vec<f64> = [1.0, 1.0, 50.0, 1.0, 2.0, 2.0, 2.0]
+//!         .iter()
+//!         .map(|u| despike.transfer_td(*u))
+//!         .collect();
+//!     assert_eq!(out, [1.0, 1.0, 1.0, 1.0, 2.0, 2.0, 2.0]);
+//! }
+//! ```
+
+use std::vec;
+use std::vec::Vec;
+
+use super::snapshot::StateAccess;
+use super::*;
+use core::fmt::{self, Display};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MedianFilter<N> {
+    pub sample_time: f64,
+    /// Samples of the window, as ring buffer
+    buffer: Vec<N>,
+    write_index: usize,
+    /// Number of valid samples in the buffer
+    filled: usize,
+}
+
+impl<N: Copy + Default + PartialOrd> MedianFilter<N> {
+    pub fn set_sample_time_or_default(self, sample_time: f64) -> Self {
+        if sample_time > 0.0 {
+            MedianFilter::<N> {
+                sample_time,
+                ..self
+            }
+        } else {
+            MedianFilter::<N> {
+                sample_time: 1.0,
+                ..self
+            }
+        }
+    }
+
+    /// Set the window length in samples, clears the buffer
+    pub fn set_window(self, samples: usize) -> Result<Self, &'static str> {
+        if samples == 0 {
+            return Err("Invalid window: Must be at least one sample");
+        }
+        Ok(MedianFilter::<N> {
+            buffer: vec![N::default(); samples],
+            write_index: 0,
+            filled: 0,
+            ..self
+        })
+    }
+
+    /// Window length in samples
+    pub fn window(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// Store `input` and return the valid samples of the window, sorted
+    ///
+    /// Samples not comparable to themselves (`NaN`) are left out.
+    fn push(&mut self, input: N) -> Vec<N> {
+        self.buffer[self.write_index] = input;
+        self.write_index = (self.write_index + 1) % self.buffer.len();
+        self.filled = (self.filled + 1).min(self.buffer.len());
+        // the buffer fills up from index 0
+        let mut sorted: Vec<N> = self.buffer[..self.filled]
+            .iter()
+            .copied()
+            .filter(|v| v.partial_cmp(v).is_some())
+            .collect();
+        // a total order without NaN
+        sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(core::cmp::Ordering::Equal));
+        sorted
+    }
+}
+
+impl Default for MedianFilter<i32> {
+    /// Window of one sample, i.e. passes the input through
+    fn default() -> Self {
+        MedianFilter::<i32> {
+            sample_time: 1.0,
+            buffer: vec![0],
+            write_index: 0,
+            filled: 0,
+        }
+    }
+}
+
+impl Default for MedianFilter<f64> {
+    /// Window of one sample, i.e. passes the input through
+    fn default() -> Self {
+        MedianFilter::<f64> {
+            sample_time: 1.0,
+            buffer: vec![0.0],
+            write_index: 0,
+            filled: 0,
+        }
+    }
+}
+
+impl<N: Clone + fmt::Debug + PartialEq> StateAccess for MedianFilter<N> {
+    /// Window buffer, its write index and the number of valid samples
+    type State = (Vec<N>, usize, usize);
+
+    fn save_state(&self) -> Self::State {
+        (self.buffer.clone(), self.write_index, self.filled)
+    }
+
+    fn restore_state(&mut self, state: &Self::State) {
+        if state.0.len() == self.buffer.len() {
+            self.buffer.clone_from(&state.0);
+            (self.write_index, self.filled) = (state.1, state.2);
+        }
+    }
+}
+
+impl<N> TypeIdentifier for MedianFilter<N> {
+    fn short_type_name(&self) -> &'static str {
+        "MedianFilter"
+    }
+}
+
+impl<N> SampleTime for MedianFilter<N> {
+    fn sample_time(&self) -> Option<f64> {
+        Some(self.sample_time)
+    }
+}
+
+impl<N> Display for MedianFilter<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "MedianFilter(sample_time: {}, window: {})",
+            self.sample_time,
+            self.buffer.len()
+        )
+    }
+}
+
+impl TransferTimeDomain<i32> for MedianFilter<i32> {
+    fn transfer_td(&mut self, input: i32) -> i32 {
+        let sorted = self.push(input);
+        let n = sorted.len();
+        if n % 2 == 1 {
+            sorted[n / 2]
+        } else {
+            ((sorted[n / 2 - 1] as i64 + sorted[n / 2] as i64) / 2) as i32
+        }
+    }
+}
+
+impl TransferTimeDomain<f64> for MedianFilter<f64> {
+    fn transfer_td(&mut self, input: f64) -> f64 {
+        let sorted = self.push(input);
+        let n = sorted.len();
+        if n == 0 {
+            f64::NAN
+        } else if n % 2 == 1 {
+            sorted[n / 2]
+        } else {
+            (sorted[n / 2 - 1] + sorted[n / 2]) / 2.0
+        }
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_MedianFilter_i32_even_window() {
+        let mut sut = MedianFilter::<i32>::default().set_window(4).unwrap();
+        assert_eq!(sut.transfer_td(10), 10);
+        assert_eq!(sut.transfer_td(20), 15);
+        assert_eq!(sut.transfer_td(i32::MAX), 20);
+        assert_eq!(sut.transfer_td(i32::MAX), i32::MAX / 2 + 10);
+        assert_eq!(sut.transfer_td(-5), 20 / 2 + i32::MAX / 2);
+        assert!(sut.set_window(0).is_err());
+    }
+
+    #[test]
+    fn test_MedianFilter_f64_burst_shorter_than_half_window() {
+        let mut sut = MedianFilter::<f64>::default().set_window(5).unwrap();
+        let input = [0.0, 0.0, 0.0, 9.0, -9.0, 0.0, 0.0, 0.0];
+        for u in input {
+            assert_eq!(sut.transfer_td(u), 0.0);
+        }
+    }
+
+    #[test]
+    fn test_MedianFilter_f64_ignores_nan() {
+        let nan = f64::NAN;
+        let mut sut = MedianFilter::<f64>::default().set_window(3).unwrap();
+        assert!(sut.transfer_td(nan).is_nan());
+        let out: Vec<f64> = [3.0, 1.0, nan, 2.0, nan, 5.0]
+            .iter()
+            .map(|u| sut.transfer_td(*u))
+            .collect();
+        // the windows without NaN: [3], [3, 1], [3, 1], [1, 2], [2], [2, 5]
+        assert_eq!(out, [3.0, 2.0, 2.0, 1.5, 2.0, 3.5]);
+    }
+}
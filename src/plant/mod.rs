@@ -4,11 +4,61 @@ use core::fmt::Debug;
 use core::fmt::Display;
 
 use dyn_clone::DynClone; // DynClone is a trait with clones a Box
+use ndarray::{Array1, ArrayView1};
 use std::boxed::Box;
 
+pub mod actuator_model;
+pub mod assertion;
+pub mod backlash;
+pub mod battery;
+pub mod block_oriented;
+pub mod compressor;
+pub mod continuous_transfer;
+pub mod dc_motor;
+pub mod dead_time;
+pub mod dead_zone;
+pub mod decoupler;
+pub mod discrete_transfer;
+pub mod feedback;
+pub mod fir;
+pub mod hydraulic;
+pub mod iir_biquad;
+pub mod instrumented;
+pub mod integrator;
+pub mod inverted_pendulum;
+pub mod map;
+pub mod mass_spring_damper;
+pub mod measurement_chain;
+pub mod median_filter;
+pub mod moving_average;
+pub mod noise_source;
+pub mod notch;
+pub mod ode;
+pub mod ph_neutralization;
+pub mod polynomial;
 pub mod pt0;
 pub mod pt1;
 pub mod pt2;
+pub mod ptn;
+pub mod quantizer;
+pub mod rate_limiter;
+pub mod registry;
+pub mod resampler;
+pub mod saturation;
+pub mod sensor_model;
+pub mod series;
+pub mod snapshot;
+pub mod solver;
+pub mod state_space;
+pub mod steady_state;
+pub mod stiction;
+pub mod switch;
+#[cfg(feature = "serde")]
+pub mod tagged;
+pub mod thermal_rc;
+pub mod thermal_zones;
+pub mod unit_gain;
+pub mod zero_order_hold;
 
 pub trait TypeIdentifier {
     /// Treated as a "dynamic type identifier"
@@ -34,12 +84,75 @@ pub trait TransferTimeDomain<N>: TypeIdentifier {
     /// This is not a requirement of the transfer function.
     /// It is just to focus on the function itself and not on value ranges and units of measurement.
     fn transfer_td(&mut self, u: N) -> N;
+
+    /// Unit of measurement of the output signal given the unit of the input signal
+    ///
+    /// Most elements keep the unit, unit converting elements override this.
+    fn output_unit(&self, input_unit: &'static str) -> &'static str {
+        input_unit
+    }
+
+    /// Unit of the output signal, fails if an element declares another input unit
+    ///
+    /// Unit declaring elements like `UnitGain` check their input, composites
+    /// check their elements along the signal path.
+    fn check_units(
+        &self,
+        input_unit: &'static str,
+    ) -> Result<&'static str, unit_gain::UnitCheckError> {
+        Ok(self.output_unit(input_unit))
+    }
+}
+
+/// Transfer function of an element with several inputs and outputs
+pub trait MimoTransferTimeDomain: TypeIdentifier {
+    fn input_count(&self) -> usize;
+    fn output_count(&self) -> usize;
+
+    /// Transfer function for time domain
+    ///
+    /// # Arguments
+    /// * `u` - input vector of `input_count` elements
+    /// # Returns
+    /// * output vector of `output_count` elements
+    /// # Panics
+    /// Implementations may panic if `u` has not `input_count` elements,
+    /// see `try_transfer_td`
+    fn transfer_td(&mut self, u: ArrayView1<f64>) -> Array1<f64>;
+
+    /// Like `transfer_td`, fails if `u` has not `input_count` elements
+    fn try_transfer_td(&mut self, u: ArrayView1<f64>) -> Result<Array1<f64>, &'static str> {
+        if u.len() == self.input_count() {
+            Ok(self.transfer_td(u))
+        } else {
+            Err("Invalid input: Must have input_count elements")
+        }
+    }
+}
+
+pub trait SampleTime {
+    /// Sample time the element's coefficients are computed for
+    ///
+    /// Static elements without internal state return `None`, they fit any sample time.
+    fn sample_time(&self) -> Option<f64>;
+}
+
+/// Common sample time of the parts of a composite element
+///
+/// `None` if no part has a sample time, `NaN` if the parts disagree, so the
+/// composite fails any sample time check.
+pub fn common_sample_time(parts: impl IntoIterator<Item = Option<f64>>) -> Option<f64> {
+    parts
+        .into_iter()
+        .flatten()
+        .reduce(|common, ts| if ts == common { common } else { f64::NAN })
 }
 
 pub trait DynTransferTimeDomain<S: Debug + Display + Clone + Copy + Sized + Send + Sync>:
-    TransferTimeDomain<S> + Debug + Display + DynClone + 'static + Send + Sync
+    TransferTimeDomain<S> + SampleTime + Debug + Display + DynClone + 'static + Send + Sync
 {
     fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
     fn as_dyn_element(&self) -> &dyn DynTransferTimeDomain<S>;
     fn dyn_eq(&self, other: &dyn DynTransferTimeDomain<S>) -> bool;
 }
@@ -47,10 +160,10 @@ pub trait DynTransferTimeDomain<S: Debug + Display + Clone + Copy + Sized + Send
 impl<T, S> DynTransferTimeDomain<S> for T
 where
     T: TransferTimeDomain<S>
+        + SampleTime
         + Debug
         + Display
         + DynClone
-        + Copy
         + 'static
         + PartialEq
         + Send
@@ -61,6 +174,10 @@ where
         self
     }
 
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
     fn as_dyn_element(&self) -> &dyn DynTransferTimeDomain<S> {
         self
     }
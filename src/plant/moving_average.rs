@@ -0,0 +1,204 @@
+//! A moving average filter, e.g. the smoothing of a sensor transmitter
+//!
+//! $ out[k] = \frac{1}{n} \sum_{i=0}^{n-1} in[k-i] $
+//!
+//! where $n$ is the window length in samples. The window can also be given
+//! in time units, it is then rounded to whole samples of the sample time.
+//!
+//! Until the window is filled the average is taken over the samples seen so
+//! far, so the output starts at the first input instead of ramping up from 0.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use cb_simulation_util::plant::TransferTimeDomain;
+//! use cb_simulation_util::plant::moving_average::MovingAverage;
+//!
+//! fn main() {
+//!     let mut smoothing = MovingAverage::<f64>::default()
+//!         .set_sample_time_or_default(0.5)
+//!         .set_window_time(1.5)
+//!         .unwrap();
+//!     assert_eq!(smoothing.window(), 3);
+//!     assert_eq!(smoothing.transfer_td(3.0), 3.0);
+//!     assert_eq!(smoothing.transfer_td(6.0), 4.5);
+//!     assert_eq!(smoothing.transfer_td(9.0), 6.0);
+//!     assert_eq!(smoothing.transfer_td(9.0), 8.0);
+//! }
+//! ```
+
+use std::vec;
+use std::vec::Vec;
+
+use super::snapshot::StateAccess;
+use super::*;
+use core::fmt::{self, Display};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MovingAverage<N> {
+    pub sample_time: f64,
+    /// Samples of the window, as ring buffer
+    buffer: Vec<N>,
+    write_index: usize,
+    /// Number of valid samples in the buffer
+    filled: usize,
+}
+
+impl<N: Copy + Default> MovingAverage<N> {
+    pub fn set_sample_time_or_default(self, sample_time: f64) -> Self {
+        if sample_time > 0.0 {
+            MovingAverage::<N> {
+                sample_time,
+                ..self
+            }
+        } else {
+            MovingAverage::<N> {
+                sample_time: 1.0,
+                ..self
+            }
+        }
+    }
+
+    /// Set the window length in samples, clears the buffer
+    pub fn set_window(self, samples: usize) -> Result<Self, &'static str> {
+        if samples == 0 {
+            return Err("Invalid window: Must be at least one sample");
+        }
+        Ok(MovingAverage::<N> {
+            buffer: vec![N::default(); samples],
+            write_index: 0,
+            filled: 0,
+            ..self
+        })
+    }
+
+    /// Set the window length in time units, rounded to whole samples
+    ///
+    /// Uses the current sample time, so set the sample time first.
+    pub fn set_window_time(self, window_time: f64) -> Result<Self, &'static str> {
+        let samples = (window_time / self.sample_time).round();
+        if samples >= 1.0 {
+            self.set_window(samples as usize)
+        } else {
+            Err("Invalid window_time: Must be at least one sample time")
+        }
+    }
+
+    /// Window length in samples
+    pub fn window(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// Store `input` and return the valid samples of the window
+    fn push(&mut self, input: N) -> &[N] {
+        self.buffer[self.write_index] = input;
+        self.write_index = (self.write_index + 1) % self.buffer.len();
+        self.filled = (self.filled + 1).min(self.buffer.len());
+        // the buffer fills up from index 0
+        &self.buffer[..self.filled]
+    }
+}
+
+impl Default for MovingAverage<i32> {
+    /// Window of one sample, i.e. passes the input through
+    fn default() -> Self {
+        MovingAverage::<i32> {
+            sample_time: 1.0,
+            buffer: vec![0],
+            write_index: 0,
+            filled: 0,
+        }
+    }
+}
+
+impl Default for MovingAverage<f64> {
+    /// Window of one sample, i.e. passes the input through
+    fn default() -> Self {
+        MovingAverage::<f64> {
+            sample_time: 1.0,
+            buffer: vec![0.0],
+            write_index: 0,
+            filled: 0,
+        }
+    }
+}
+
+impl<N: Clone + fmt::Debug + PartialEq> StateAccess for MovingAverage<N> {
+    /// Window buffer, its write index and the number of valid samples
+    type State = (Vec<N>, usize, usize);
+
+    fn save_state(&self) -> Self::State {
+        (self.buffer.clone(), self.write_index, self.filled)
+    }
+
+    fn restore_state(&mut self, state: &Self::State) {
+        if state.0.len() == self.buffer.len() {
+            self.buffer.clone_from(&state.0);
+            (self.write_index, self.filled) = (state.1, state.2);
+        }
+    }
+}
+
+impl<N> TypeIdentifier for MovingAverage<N> {
+    fn short_type_name(&self) -> &'static str {
+        "MovingAverage"
+    }
+}
+
+impl<N> SampleTime for MovingAverage<N> {
+    fn sample_time(&self) -> Option<f64> {
+        Some(self.sample_time)
+    }
+}
+
+impl<N> Display for MovingAverage<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "MovingAverage(sample_time: {}, window: {})",
+            self.sample_time,
+            self.buffer.len()
+        )
+    }
+}
+
+impl TransferTimeDomain<i32> for MovingAverage<i32> {
+    fn transfer_td(&mut self, input: i32) -> i32 {
+        let window = self.push(input);
+        let sum: i64 = window.iter().map(|v| *v as i64).sum();
+        (sum / window.len() as i64) as i32
+    }
+}
+
+impl TransferTimeDomain<f64> for MovingAverage<f64> {
+    fn transfer_td(&mut self, input: f64) -> f64 {
+        let window = self.push(input);
+        window.iter().sum::<f64>() / window.len() as f64
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_MovingAverage_i32_no_overflow() {
+        let mut sut = MovingAverage::<i32>::default().set_window(4).unwrap();
+        assert_eq!(sut.transfer_td(i32::MAX), i32::MAX);
+        assert_eq!(sut.transfer_td(i32::MAX), i32::MAX);
+        for _ in 0..4 {
+            sut.transfer_td(-8);
+        }
+        assert_eq!(sut.transfer_td(0), -6);
+    }
+
+    #[test]
+    fn test_MovingAverage_invalid_window() {
+        let sut = MovingAverage::<f64>::default().set_sample_time_or_default(0.1);
+        assert!(sut.clone().set_window(0).is_err());
+        assert!(sut.clone().set_window_time(0.04).is_err());
+        assert_eq!(sut.set_window_time(0.26).unwrap().window(), 3);
+    }
+}
@@ -0,0 +1,246 @@
+//! A noise source, adds white noise to its input, e.g. a noisy measurement
+//!
+//! $ out[k] = in[k] + n[k] $
+//!
+//! where $n[k]$ are independent samples with mean 0 and the configured
+//! variance $\sigma^2$, gaussian or uniformly distributed in
+//! $ [-\sqrt{3}\sigma, \sqrt{3}\sigma] $.
+//!
+//! The noise sequence is reproducible from the seed: two sources with the same
+//! seed produce the same noise, independent of the input. With the feature
+//! `rand` the seed can also be drawn from any `rand::RngCore`, e.g. to seed the
+//! sources of a Monte Carlo run from one generator.
+//!
+//! For `i32` the noise is rounded to whole counts.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use cb_simulation_util::plant::TransferTimeDomain;
+//! use cb_simulation_util::plant::noise_source::NoiseSource;
+//!
+//! fn main() {
+//!     let mut sensor = NoiseSource::<f64>::default().set_variance(0.01).unwrap().set_seed(42);
+//!     let mut replay = sensor.clone();
+//!     let y = sensor.transfer_td(20.0);
+//!     assert_ne!(y, 20.0);
+//!     assert_eq!(replay.transfer_td(20.0), y);
+//! }
+//! ```
+
+use super::snapshot::StateAccess;
+use super::*;
+use crate::rng;
+use core::fmt::{self, Display};
+
+/// Distribution of the noise samples
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NoiseDistribution {
+    #[default]
+    Gaussian,
+    Uniform,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct NoiseSource<N> {
+    pub sample_time: f64,
+    pub variance: f64,
+    pub distribution: NoiseDistribution,
+    pub seed: u64,
+    /// Number of noise samples drawn so far
+    samples: u64,
+    _marker: core::marker::PhantomData<N>,
+}
+
+impl<N> NoiseSource<N> {
+    pub fn set_sample_time_or_default(self, sample_time: f64) -> Self {
+        if sample_time > 0.0 {
+            NoiseSource::<N> {
+                sample_time,
+                ..self
+            }
+        } else {
+            NoiseSource::<N> {
+                sample_time: 1.0,
+                ..self
+            }
+        }
+    }
+
+    /// Set the variance $\sigma^2$ of the noise in the unit of the signal squared
+    pub fn set_variance(self, variance: f64) -> Result<Self, &'static str> {
+        if variance.is_finite() && variance >= 0.0 {
+            Ok(NoiseSource::<N> { variance, ..self })
+        } else {
+            Err("Invalid variance: Must be finite and not negative")
+        }
+    }
+
+    pub fn set_distribution(self, distribution: NoiseDistribution) -> Self {
+        NoiseSource::<N> {
+            distribution,
+            ..self
+        }
+    }
+
+    /// Set the seed and restart the noise sequence
+    pub fn set_seed(self, seed: u64) -> Self {
+        NoiseSource::<N> {
+            seed,
+            samples: 0,
+            ..self
+        }
+    }
+
+    /// Draw the seed from `rng` and restart the noise sequence
+    #[cfg(feature = "rand")]
+    pub fn set_seed_from_rng<R: rand::RngCore + ?Sized>(self, rng: &mut R) -> Self {
+        self.set_seed(rng.next_u64())
+    }
+
+    /// Standard deviation $\sigma$ of the noise
+    pub fn standard_deviation(&self) -> f64 {
+        self.variance.sqrt()
+    }
+
+    /// Next sample of the noise sequence
+    fn next_noise(&mut self) -> f64 {
+        let bits = rng::mix(self.seed ^ rng::mix(self.samples));
+        self.samples += 1;
+        let sigma = self.standard_deviation();
+        match self.distribution {
+            NoiseDistribution::Gaussian => sigma * rng::normal(bits, rng::mix(bits)),
+            NoiseDistribution::Uniform => sigma * 3f64.sqrt() * (2.0 * rng::unit(bits) - 1.0),
+        }
+    }
+}
+
+impl<N> Default for NoiseSource<N> {
+    /// Variance 0, i.e. passes the input through
+    fn default() -> Self {
+        NoiseSource::<N> {
+            sample_time: 1.0,
+            variance: 0.0,
+            distribution: NoiseDistribution::Gaussian,
+            seed: 0,
+            samples: 0,
+            _marker: core::marker::PhantomData,
+        }
+    }
+}
+
+impl<N> StateAccess for NoiseSource<N> {
+    /// Number of noise samples drawn, the position in the random sequence
+    type State = u64;
+
+    fn save_state(&self) -> Self::State {
+        self.samples
+    }
+
+    fn restore_state(&mut self, state: &Self::State) {
+        self.samples = *state;
+    }
+}
+
+impl<N> TypeIdentifier for NoiseSource<N> {
+    fn short_type_name(&self) -> &'static str {
+        "NoiseSource"
+    }
+}
+
+impl<N> SampleTime for NoiseSource<N> {
+    fn sample_time(&self) -> Option<f64> {
+        Some(self.sample_time)
+    }
+}
+
+impl<N> Display for NoiseSource<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "NoiseSource(sample_time: {}, variance: {}, distribution: {:?}, seed: {})",
+            self.sample_time, self.variance, self.distribution, self.seed
+        )
+    }
+}
+
+impl TransferTimeDomain<i32> for NoiseSource<i32> {
+    fn transfer_td(&mut self, input: i32) -> i32 {
+        let noise = self.next_noise().round() as i64;
+        (input as i64 + noise).clamp(i32::MIN as i64, i32::MAX as i64) as i32
+    }
+}
+
+impl TransferTimeDomain<f64> for NoiseSource<f64> {
+    fn transfer_td(&mut self, input: f64) -> f64 {
+        input + self.next_noise()
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use std::vec::Vec;
+
+    fn moments(sut: &mut NoiseSource<f64>, n: usize) -> (f64, f64) {
+        let samples: Vec<f64> = (0..n).map(|_| sut.transfer_td(5.0) - 5.0).collect();
+        let mean = samples.iter().sum::<f64>() / n as f64;
+        let variance = samples.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / n as f64;
+        (mean, variance)
+    }
+
+    #[test]
+    fn test_NoiseSource_f64_variance() {
+        for distribution in [NoiseDistribution::Gaussian, NoiseDistribution::Uniform] {
+            let mut sut = NoiseSource::<f64>::default()
+                .set_variance(4.0)
+                .unwrap()
+                .set_distribution(distribution)
+                .set_seed(3);
+            let (mean, variance) = moments(&mut sut, 20000);
+            assert!(mean.abs() < 0.06);
+            assert!((variance - 4.0).abs() < 0.2);
+        }
+        let mut uniform = NoiseSource::<f64>::default()
+            .set_variance(1.0 / 3.0)
+            .unwrap()
+            .set_distribution(NoiseDistribution::Uniform);
+        assert!((0..1000).all(|_| uniform.transfer_td(0.0).abs() <= 1.0));
+        assert!(NoiseSource::<f64>::default().set_variance(-1.0).is_err());
+    }
+
+    #[test]
+    fn test_NoiseSource_seed_reproducible() {
+        let noisy = NoiseSource::<f64>::default().set_variance(1.0).unwrap();
+        let run = |mut sut: NoiseSource<f64>| -> Vec<f64> {
+            (0..10).map(|k| sut.transfer_td(k as f64)).collect()
+        };
+        assert_eq!(
+            run(noisy.clone().set_seed(1)),
+            run(noisy.clone().set_seed(1))
+        );
+        assert_ne!(run(noisy.clone().set_seed(1)), run(noisy.set_seed(2)));
+    }
+
+    #[test]
+    fn test_NoiseSource_i32_saturates() {
+        let mut sut = NoiseSource::<i32>::default();
+        assert_eq!(sut.transfer_td(i32::MAX), i32::MAX);
+        let mut sut = sut.set_variance(1.0e6).unwrap();
+        let out: Vec<i32> = (0..100).map(|_| sut.transfer_td(i32::MAX)).collect();
+        assert!(out.iter().all(|y| *y > 0));
+        assert!(out.iter().any(|y| *y < i32::MAX));
+    }
+
+    #[cfg(feature = "rand")]
+    #[test]
+    fn test_NoiseSource_seed_from_rng() {
+        use rand::SeedableRng;
+        let mut master = rand::rngs::SmallRng::seed_from_u64(7);
+        let a = NoiseSource::<f64>::default().set_seed_from_rng(&mut master);
+        let b = NoiseSource::<f64>::default().set_seed_from_rng(&mut master);
+        assert_ne!(a.seed, b.seed);
+    }
+}
@@ -0,0 +1,267 @@
+//! A notch filter, e.g. to suppress the resonance of a PT2 plant
+//!
+//! $ G(s) = \frac{s^{2} + 2 d D \omega s + \omega^{2}}{s^{2} + 2 D \omega s + \omega^{2}} $
+//!
+//! where $\omega$ is the center angular frequency
+//! and $d$ is the depth, the gain at the center frequency (0 removes it completely)
+//! and $D$ is the width, the damping of the poles (a larger value widens the notch)
+//!
+//! The filter is discretized with the Tustin method prewarped at $\omega$,
+//! so the notch lies exactly at the center frequency for any sample time
+//! below the Nyquist limit. Far away from $\omega$ the gain is 1.
+//!
+//! For `i32` the coefficients are converted to fixed point with 10 bits
+//! after the comma.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use cb_simulation_util::plant::TransferTimeDomain;
+//! use cb_simulation_util::plant::notch::Notch;
+//!
+//! fn main() {
+//!     let ts = 0.01;
+//!     let mut notch = Notch::<f64>::default()
+//!         .set_sample_time_or_default(ts)
+//!         .set_omega_or_default(20.0)
+//!         .set_depth_or_default(0.0);
+//!     let out: Vec<f64> = (0..2000)
+//!         .map(|k| notch.transfer_td((20.0 * k as f64 * ts).sin()))
+//!         .collect();
+//!     let residual = out[1000..].iter().fold(0.0_f64, |m, y| m.max(y.abs()));
+//!     assert!(residual < 1e-3);
+//! }
+//! ```
+
+use super::snapshot::StateAccess;
+use super::*;
+use core::f64::consts::PI;
+use core::fmt::{self, Display};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Notch<N> {
+    pub sample_time: f64,
+    /// Center angular frequency in rad per time unit
+    pub omega: f64,
+    /// Gain at the center frequency, 0..=1
+    pub depth: f64,
+    /// Damping of the poles, > 0
+    pub width: f64,
+    /// in[k-1], in[k-2]
+    inputs: [N; 2],
+    /// out[k-1], out[k-2]
+    outputs: [N; 2],
+}
+
+impl<N> Notch<N> {
+    pub fn set_sample_time_or_default(self, sample_time: f64) -> Self {
+        if sample_time > 0.0 {
+            Notch::<N> {
+                sample_time,
+                ..self
+            }
+        } else {
+            Notch::<N> {
+                sample_time: 1.0,
+                ..self
+            }
+        }
+    }
+
+    /// Set the center frequency, it must be below the Nyquist frequency $\pi / T_s$
+    pub fn set_omega_or_default(self, omega: f64) -> Self {
+        if omega > 0.0 && omega * self.sample_time < PI {
+            Notch::<N> { omega, ..self }
+        } else {
+            Notch::<N> {
+                omega: 0.5 / self.sample_time,
+                ..self
+            }
+        }
+    }
+
+    pub fn set_depth_or_default(self, depth: f64) -> Self {
+        if (0.0..=1.0).contains(&depth) {
+            Notch::<N> { depth, ..self }
+        } else {
+            Notch::<N> { depth: 0.0, ..self }
+        }
+    }
+
+    pub fn set_width_or_default(self, width: f64) -> Self {
+        if width > 0.0 {
+            Notch::<N> { width, ..self }
+        } else {
+            Notch::<N> { width: 0.5, ..self }
+        }
+    }
+
+    /// Discrete coefficients `(b, a)` with `a[0] == 1`
+    pub fn coefficients(&self) -> ([f64; 3], [f64; 3]) {
+        let k = self.omega / (self.omega * self.sample_time / 2.0).tan();
+        let (kk, ww) = (k * k, self.omega * self.omega);
+        let zero = 2.0 * self.depth * self.width * self.omega * k;
+        let pole = 2.0 * self.width * self.omega * k;
+        let a0 = kk + pole + ww;
+        (
+            [
+                (kk + zero + ww) / a0,
+                2.0 * (ww - kk) / a0,
+                (kk - zero + ww) / a0,
+            ],
+            [1.0, 2.0 * (ww - kk) / a0, (kk - pole + ww) / a0],
+        )
+    }
+}
+
+impl<N: Copy> Notch<N> {
+    /// The internal state: (previous inputs, previous outputs), the most recent first
+    pub fn state(&self) -> ([N; 2], [N; 2]) {
+        (self.inputs, self.outputs)
+    }
+}
+
+const FIX_KOMMA_SHIFT_BITS: u8 = 10;
+const FIX_KOMMA_SHIFT: i64 = 1 << FIX_KOMMA_SHIFT_BITS;
+
+impl Default for Notch<i32> {
+    fn default() -> Self {
+        Notch::<i32> {
+            sample_time: 1.0,
+            omega: 0.5,
+            depth: 0.0,
+            width: 0.5,
+            inputs: [0; 2],
+            outputs: [0; 2],
+        }
+    }
+}
+
+impl Default for Notch<f64> {
+    fn default() -> Self {
+        Notch::<f64> {
+            sample_time: 1.0,
+            omega: 0.5,
+            depth: 0.0,
+            width: 0.5,
+            inputs: [0.0; 2],
+            outputs: [0.0; 2],
+        }
+    }
+}
+
+impl<N: Copy + fmt::Debug + PartialEq> StateAccess for Notch<N> {
+    /// in[k-1], in[k-2] and out[k-1], out[k-2]
+    type State = ([N; 2], [N; 2]);
+
+    fn save_state(&self) -> Self::State {
+        (self.inputs, self.outputs)
+    }
+
+    fn restore_state(&mut self, state: &Self::State) {
+        (self.inputs, self.outputs) = *state;
+    }
+}
+
+impl<N> TypeIdentifier for Notch<N> {
+    fn short_type_name(&self) -> &'static str {
+        "Notch"
+    }
+}
+
+impl<N> SampleTime for Notch<N> {
+    fn sample_time(&self) -> Option<f64> {
+        Some(self.sample_time)
+    }
+}
+
+impl<N> Display for Notch<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Notch(sample_time: {}, omega: {}, depth: {}, width: {})",
+            self.sample_time, self.omega, self.depth, self.width
+        )
+    }
+}
+
+impl TransferTimeDomain<i32> for Notch<i32> {
+    fn transfer_td(&mut self, input: i32) -> i32 {
+        // outputs are kept with 10 bits after the comma
+        let (b, a) = self.coefficients();
+        let fixed = |c: f64| (c * FIX_KOMMA_SHIFT as f64).round() as i64;
+        let forward = fixed(b[0]) * input as i64
+            + fixed(b[1]) * self.inputs[0] as i64
+            + fixed(b[2]) * self.inputs[1] as i64;
+        let backward = (fixed(a[1]) * self.outputs[0] as i64
+            + fixed(a[2]) * self.outputs[1] as i64)
+            >> FIX_KOMMA_SHIFT_BITS;
+        let output = (forward - backward).clamp(i32::MIN as i64, i32::MAX as i64) as i32;
+        self.inputs = [input, self.inputs[0]];
+        self.outputs = [output, self.outputs[0]];
+        output >> FIX_KOMMA_SHIFT_BITS
+    }
+}
+
+impl TransferTimeDomain<f64> for Notch<f64> {
+    fn transfer_td(&mut self, input: f64) -> f64 {
+        let (b, a) = self.coefficients();
+        let output = b[0] * input + b[1] * self.inputs[0] + b[2] * self.inputs[1]
+            - a[1] * self.outputs[0]
+            - a[2] * self.outputs[1];
+        self.inputs = [input, self.inputs[0]];
+        self.outputs = [output, self.outputs[0]];
+        output
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn amplitude(notch: &mut Notch<f64>, omega: f64) -> f64 {
+        let ts = notch.sample_time;
+        (0..4000)
+            .map(|k| notch.transfer_td((omega * k as f64 * ts).sin()))
+            .skip(3000)
+            .fold(0.0, |m: f64, y| m.max(y.abs()))
+    }
+
+    #[test]
+    fn test_Notch_f64_depth_and_passband() {
+        let sut = Notch::<f64>::default()
+            .set_sample_time_or_default(0.01)
+            .set_omega_or_default(10.0)
+            .set_width_or_default(0.3)
+            .set_depth_or_default(0.25);
+        assert!((amplitude(&mut sut.clone(), 10.0) - 0.25).abs() < 0.01);
+        assert!((amplitude(&mut sut.clone(), 0.5) - 1.0).abs() < 0.01);
+        assert!((amplitude(&mut sut.clone(), 200.0) - 1.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_Notch_i32_dc_gain() {
+        let mut sut = Notch::<i32>::default()
+            .set_sample_time_or_default(0.1)
+            .set_omega_or_default(5.0);
+        let mut output = 0;
+        for _ in 0..200 {
+            output = sut.transfer_td(1000);
+        }
+        assert!((output - 1000).abs() <= 2);
+    }
+
+    #[test]
+    fn test_Notch_invalid_parameters() {
+        let sut = Notch::<f64>::default()
+            .set_sample_time_or_default(0.1)
+            .set_omega_or_default(100.0)
+            .set_depth_or_default(2.0)
+            .set_width_or_default(-1.0);
+        assert_eq!(sut.omega, 5.0);
+        assert_eq!(sut.depth, 0.0);
+        assert_eq!(sut.width, 0.5);
+    }
+}
@@ -0,0 +1,267 @@
+//! # Plants from ordinary differential equations
+//!
+//! A nonlinear plant $ \dot{x} = f(x, u) $, $ y = g(x, u) $ is described by
+//! implementing `OdeRhs`. `OdePlant` turns it into a `TransferTimeDomain`
+//! element: the input is held over the sample and the state integrated with
+//! the selected `OdeSolver` in `substeps` steps per sample.
+//!
+//! * `EulerForward`: first order, one evaluation of $f$ per step
+//! * `Heun`: second order, two evaluations
+//! * `RungeKutta4`: the classic fourth order method, four evaluations
+//!
+//! ## Example
+//!
+//! ```rust
+//! use cb_simulation_util::plant::TransferTimeDomain;
+//! use cb_simulation_util::plant::ode::{OdePlant, OdeRhs, OdeSolver};
+//!
+//! /// Tank with free outflow: A h' = q - k sqrt(h)
+//! #[derive(Debug, Clone, PartialEq)]
+//! struct Tank;
+//!
+//! impl OdeRhs for Tank {
+//!     fn states(&self) -> usize {
+//!         1
+//!     }
+//!
+//!     fn derivative(&self, state: &[f64], input: f64, derivative: &mut [f64]) {
+//!         derivative[0] = (input - 0.5 * state[0].max(0.0).sqrt()) / 2.0;
+//!     }
+//! }
+//!
+//! fn main() {
+//!     let mut tank = OdePlant::new(Tank)
+//!         .set_sample_time_or_default(1.0)
+//!         .set_solver(OdeSolver::RungeKutta4);
+//!     let mut level = 0.0;
+//!     for _ in 0..500 {
+//!         level = tank.transfer_td(1.0);
+//!     }
+//!     // inflow equals outflow at h = (q / k)^2
+//!     assert!((level - 4.0).abs() < 1e-6);
+//! }
+//! ```
+
+use std::vec;
+use std::vec::Vec;
+
+use super::snapshot::StateAccess;
+use super::*;
+use core::fmt::{self, Display};
+
+/// Right hand side of the state equations of a plant
+pub trait OdeRhs {
+    /// Number of states
+    fn states(&self) -> usize;
+
+    /// Write $ \dot{x} = f(x, u) $ into `derivative`, of `states` elements
+    fn derivative(&self, state: &[f64], input: f64, derivative: &mut [f64]);
+
+    /// Output $ y = g(x, u) $, the first state by default
+    fn output(&self, state: &[f64], _input: f64) -> f64 {
+        state[0]
+    }
+}
+
+/// Integration method of an `OdePlant`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OdeSolver {
+    EulerForward,
+    Heun,
+    #[default]
+    RungeKutta4,
+}
+
+impl OdeSolver {
+    /// Advance `state` by one step of `h` seconds with the input held at `input`
+    pub fn step<R: OdeRhs + ?Sized>(&self, rhs: &R, state: &mut [f64], input: f64, h: f64) {
+        let n = state.len();
+        let mut k1 = vec![0.0; n];
+        rhs.derivative(state, input, &mut k1);
+        let shifted = |k: &[f64], factor: f64| -> Vec<f64> {
+            state.iter().zip(k).map(|(x, d)| x + factor * d).collect()
+        };
+        match self {
+            OdeSolver::EulerForward => {
+                state.iter_mut().zip(&k1).for_each(|(x, d)| *x += h * d);
+            }
+            OdeSolver::Heun => {
+                let mut k2 = vec![0.0; n];
+                rhs.derivative(&shifted(&k1, h), input, &mut k2);
+                for i in 0..n {
+                    state[i] += 0.5 * h * (k1[i] + k2[i]);
+                }
+            }
+            OdeSolver::RungeKutta4 => {
+                let (mut k2, mut k3, mut k4) = (vec![0.0; n], vec![0.0; n], vec![0.0; n]);
+                rhs.derivative(&shifted(&k1, 0.5 * h), input, &mut k2);
+                rhs.derivative(&shifted(&k2, 0.5 * h), input, &mut k3);
+                rhs.derivative(&shifted(&k3, h), input, &mut k4);
+                for i in 0..n {
+                    state[i] += h / 6.0 * (k1[i] + 2.0 * k2[i] + 2.0 * k3[i] + k4[i]);
+                }
+            }
+        }
+    }
+}
+
+/// A plant given by its state equations, see `OdeRhs`
+#[derive(Debug, Clone, PartialEq)]
+pub struct OdePlant<R> {
+    pub sample_time: f64,
+    pub solver: OdeSolver,
+    /// Integration steps per sample
+    pub substeps: usize,
+    rhs: R,
+    state: Vec<f64>,
+}
+
+impl<R: OdeRhs> OdePlant<R> {
+    /// Plant starting at the zero state, integrated with `RungeKutta4`
+    pub fn new(rhs: R) -> Self {
+        OdePlant {
+            sample_time: 1.0,
+            solver: OdeSolver::default(),
+            substeps: 1,
+            state: vec![0.0; rhs.states()],
+            rhs,
+        }
+    }
+
+    pub fn set_sample_time_or_default(self, sample_time: f64) -> Self {
+        let sample_time = if sample_time > 0.0 { sample_time } else { 1.0 };
+        OdePlant {
+            sample_time,
+            ..self
+        }
+    }
+
+    pub fn set_solver(self, solver: OdeSolver) -> Self {
+        OdePlant { solver, ..self }
+    }
+
+    /// Integration steps per sample, at least 1
+    pub fn set_substeps_or_default(self, substeps: usize) -> Self {
+        OdePlant {
+            substeps: substeps.max(1),
+            ..self
+        }
+    }
+
+    pub fn rhs(&self) -> &R {
+        &self.rhs
+    }
+
+    pub fn state(&self) -> &[f64] {
+        &self.state
+    }
+
+    /// Set the state, ignored if the length does not fit
+    pub fn set_state(&mut self, state: &[f64]) {
+        if state.len() == self.state.len() {
+            self.state.copy_from_slice(state);
+        }
+    }
+}
+
+impl<R> StateAccess for OdePlant<R> {
+    type State = Vec<f64>;
+
+    fn save_state(&self) -> Self::State {
+        self.state.clone()
+    }
+
+    /// Ignored if the length does not fit, see `set_state`
+    fn restore_state(&mut self, state: &Self::State) {
+        if state.len() == self.state.len() {
+            self.state.clone_from(state);
+        }
+    }
+}
+
+impl<R> TypeIdentifier for OdePlant<R> {
+    fn short_type_name(&self) -> &'static str {
+        "OdePlant"
+    }
+}
+
+impl<R> SampleTime for OdePlant<R> {
+    fn sample_time(&self) -> Option<f64> {
+        Some(self.sample_time)
+    }
+}
+
+impl<R: fmt::Debug> Display for OdePlant<R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "OdePlant(sample_time: {}, solver: {:?}, substeps: {}, rhs: {:?})",
+            self.sample_time, self.solver, self.substeps, self.rhs
+        )
+    }
+}
+
+impl<R: OdeRhs> TransferTimeDomain<f64> for OdePlant<R> {
+    fn transfer_td(&mut self, input: f64) -> f64 {
+        let h = self.sample_time / self.substeps as f64;
+        for _ in 0..self.substeps {
+            self.solver.step(&self.rhs, &mut self.state, input, h);
+        }
+        self.rhs.output(&self.state, input)
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    /// $ \dot{x} = -x + u $
+    #[derive(Debug, Clone, PartialEq)]
+    struct Lag;
+
+    impl OdeRhs for Lag {
+        fn states(&self) -> usize {
+            1
+        }
+
+        fn derivative(&self, state: &[f64], input: f64, derivative: &mut [f64]) {
+            derivative[0] = input - state[0];
+        }
+    }
+
+    #[test]
+    fn test_OdeSolver_order() {
+        // error after 1 s of a unit step, halving the step size
+        let error = |solver: OdeSolver, substeps: usize| {
+            let mut sut = OdePlant::new(Lag)
+                .set_solver(solver)
+                .set_substeps_or_default(substeps);
+            (sut.transfer_td(1.0) - (1.0 - (-1.0f64).exp())).abs()
+        };
+        for (solver, order) in [
+            (OdeSolver::EulerForward, 1),
+            (OdeSolver::Heun, 2),
+            (OdeSolver::RungeKutta4, 4),
+        ] {
+            let ratio = error(solver, 20) / error(solver, 40);
+            assert!(
+                (ratio.log2() - order as f64).abs() < 0.1,
+                "{:?} {}",
+                solver,
+                ratio
+            );
+        }
+    }
+
+    #[test]
+    fn test_OdePlant_state_and_output() {
+        let mut sut = OdePlant::new(Lag).set_solver(OdeSolver::EulerForward);
+        sut.set_state(&[2.0]);
+        assert_eq!(sut.transfer_td(0.0), 0.0);
+        sut.set_state(&[1.0, 2.0]);
+        assert_eq!(sut.state(), [0.0]);
+        assert_eq!(sut.set_substeps_or_default(0).substeps, 1);
+    }
+}
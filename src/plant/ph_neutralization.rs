@@ -0,0 +1,285 @@
+//! # pH neutralization in a stirred tank
+//!
+//! A continuously stirred tank reactor (CSTR) of the volume $V$ is fed with
+//! a strong acid of the concentration $c_{a}$ at the constant flow $F_{a}$
+//! and neutralized by a strong base of the concentration $c_{b}$ at the
+//! manipulated flow $F_{b}$. With the reaction invariants, the anion
+//! concentration $x_{a}$ of the acid and the cation concentration $x_{b}$ of
+//! the base in the tank, the mixing is a first order lag with the residence
+//! time $ V / (F_{a} + F_{b}) $:
+//!
+//! * $ V \dot{x}_{a} = F_{a} c_{a} - (F_{a} + F_{b}) x_{a} $
+//! * $ V \dot{x}_{b} = F_{b} c_{b} - (F_{a} + F_{b}) x_{b} $
+//!
+//! The pH follows from the charge balance
+//! $ [H^{+}] + x_{b} = [OH^{-}] + x_{a} $ with $ [H^{+}][OH^{-}] = K_{w} $:
+//!
+//! $ [H^{+}] = (d + \sqrt{d^{2} + 4 K_{w}}) / 2 $ with $ d = x_{a} - x_{b} $
+//!
+//! The titration curve is extremely steep around pH 7 and flat far from
+//! it, the static gain changes by several orders of magnitude over the
+//! operating range, the classic benchmark for gain scheduling and adaptive
+//! control. Flows are in L/s, the volume in L, concentrations in mol/L. The
+//! base flow is held over a sample, the mixing is integrated exactly.
+//!
+//! Input is the base flow, output the pH. The acid flow is a disturbance
+//! which can be changed between samples.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use cb_simulation_util::plant::TransferTimeDomain;
+//! use cb_simulation_util::plant::ph_neutralization::PhNeutralization;
+//!
+//! fn main() {
+//!     // 0.1 L/s of 0.01 mol/L acid, neutralized by 0.1 L/s of the base
+//!     let mut tank = PhNeutralization::default();
+//!     assert!((tank.titration_ph(0.1) - 7.0).abs() < 1e-9);
+//!     // 10 % less or more base: more than 3 pH off
+//!     assert!(tank.titration_ph(0.09) < 3.3 && tank.titration_ph(0.11) > 10.6);
+//!     let mut ph = 0.0;
+//!     for _ in 0..1000 {
+//!         ph = tank.transfer_td(0.09);
+//!     }
+//!     assert!((ph - tank.titration_ph(0.09)).abs() < 1e-6);
+//! }
+//! ```
+
+use super::snapshot::StateAccess;
+use super::steady_state::SteadyState;
+use super::*;
+use core::f64::consts::LN_10;
+use core::fmt::{self, Display};
+
+/// Ion product of water at 25 °C in (mol/L)²
+pub const WATER_ION_PRODUCT: f64 = 1e-14;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PhNeutralization {
+    pub sample_time: f64,
+    /// Tank volume $V$ in L
+    pub volume: f64,
+    /// Acid feed $F_{a}$ in L/s
+    pub acid_flow: f64,
+    /// Acid concentration $c_{a}$ in mol/L
+    pub acid_concentration: f64,
+    /// Base concentration $c_{b}$ in mol/L
+    pub base_concentration: f64,
+    /// `[x_a, x_b]` in mol/L
+    state: [f64; 2],
+}
+
+/// pH of the difference `d` of the acid anions and the base cations
+fn ph_of(d: f64) -> f64 {
+    // the larger of [H+] and [OH-] without cancellation
+    let excess = 0.5 * (d.abs() + (d * d + 4.0 * WATER_ION_PRODUCT).sqrt());
+    if d >= 0.0 {
+        -excess.log10()
+    } else {
+        excess.log10() - WATER_ION_PRODUCT.log10()
+    }
+}
+
+impl PhNeutralization {
+    pub fn set_sample_time_or_default(self, sample_time: f64) -> Self {
+        if sample_time > 0.0 {
+            PhNeutralization {
+                sample_time,
+                ..self
+            }
+        } else {
+            PhNeutralization {
+                sample_time: 1.0,
+                ..self
+            }
+        }
+    }
+
+    pub fn set_volume(self, volume: f64) -> Result<Self, &'static str> {
+        if volume > 0.0 {
+            Ok(PhNeutralization { volume, ..self })
+        } else {
+            Err("Invalid volume: Must be > 0.0")
+        }
+    }
+
+    /// Acid feed flow and concentration
+    pub fn set_acid(self, flow: f64, concentration: f64) -> Result<Self, &'static str> {
+        if flow > 0.0 && concentration > 0.0 {
+            Ok(PhNeutralization {
+                acid_flow: flow,
+                acid_concentration: concentration,
+                ..self
+            })
+        } else {
+            Err("Invalid acid: flow and concentration must be > 0.0")
+        }
+    }
+
+    pub fn set_base_concentration(self, concentration: f64) -> Result<Self, &'static str> {
+        if concentration > 0.0 {
+            Ok(PhNeutralization {
+                base_concentration: concentration,
+                ..self
+            })
+        } else {
+            Err("Invalid base concentration: Must be > 0.0")
+        }
+    }
+
+    /// Equilibrium `[x_a, x_b]` of the constant base flow `base_flow`
+    fn equilibrium(&self, base_flow: f64) -> [f64; 2] {
+        let flow = self.acid_flow + base_flow;
+        [
+            self.acid_flow * self.acid_concentration / flow,
+            base_flow * self.base_concentration / flow,
+        ]
+    }
+
+    /// pH in the tank
+    pub fn ph(&self) -> f64 {
+        ph_of(self.state[0] - self.state[1])
+    }
+
+    /// Steady state pH of the constant base flow `base_flow`, the titration curve
+    pub fn titration_ph(&self, base_flow: f64) -> f64 {
+        let [acid, base] = self.equilibrium(base_flow.max(0.0));
+        ph_of(acid - base)
+    }
+
+    /// Slope of the titration curve, pH per L/s of base at `base_flow`
+    pub fn process_gain(&self, base_flow: f64) -> f64 {
+        let base_flow = base_flow.max(0.0);
+        let [acid, base] = self.equilibrium(base_flow);
+        let d = acid - base;
+        let flow = self.acid_flow + base_flow;
+        (self.base_concentration + d) / (flow * LN_10 * (d * d + 4.0 * WATER_ION_PRODUCT).sqrt())
+    }
+}
+
+impl Default for PhNeutralization {
+    /// 10 L tank, 0.1 L/s of 0.01 mol/L acid and 0.01 mol/L base, filled
+    /// with the acid feed
+    fn default() -> Self {
+        PhNeutralization {
+            sample_time: 1.0,
+            volume: 10.0,
+            acid_flow: 0.1,
+            acid_concentration: 0.01,
+            base_concentration: 0.01,
+            state: [0.01, 0.0],
+        }
+    }
+}
+
+impl StateAccess for PhNeutralization {
+    /// `[x_a, x_b]` in mol/L
+    type State = [f64; 2];
+
+    fn save_state(&self) -> Self::State {
+        self.state
+    }
+
+    fn restore_state(&mut self, state: &Self::State) {
+        self.state = *state;
+    }
+}
+
+impl TypeIdentifier for PhNeutralization {
+    fn short_type_name(&self) -> &'static str {
+        "PhNeutralization"
+    }
+}
+
+impl SampleTime for PhNeutralization {
+    fn sample_time(&self) -> Option<f64> {
+        Some(self.sample_time)
+    }
+}
+
+impl Display for PhNeutralization {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "PhNeutralization(sample_time: {}, volume: {}, acid_flow: {}, acid_concentration: {}, base_concentration: {})",
+            self.sample_time,
+            self.volume,
+            self.acid_flow,
+            self.acid_concentration,
+            self.base_concentration
+        )
+    }
+}
+
+impl TransferTimeDomain<f64> for PhNeutralization {
+    fn transfer_td(&mut self, input: f64) -> f64 {
+        let base_flow = input.max(0.0);
+        let equilibrium = self.equilibrium(base_flow);
+        let decay = (-(self.acid_flow + base_flow) * self.sample_time / self.volume).exp();
+        for (x, x_eq) in self.state.iter_mut().zip(equilibrium) {
+            *x = x_eq + (*x - x_eq) * decay;
+        }
+        self.ph()
+    }
+}
+
+impl SteadyState for PhNeutralization {
+    fn steady_input(&self, output: f64) -> Option<f64> {
+        // F_a c_a - F_b c_b = d (F_a + F_b)
+        let hydrogen = 10f64.powf(-output);
+        let d = hydrogen - WATER_ION_PRODUCT / hydrogen;
+        let base_flow =
+            self.acid_flow * (self.acid_concentration - d) / (self.base_concentration + d);
+        (self.base_concentration + d > 0.0 && base_flow >= 0.0).then_some(base_flow)
+    }
+
+    fn settle(&mut self, input: f64) -> f64 {
+        self.state = self.equilibrium(input.max(0.0));
+        self.ph()
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_PhNeutralization_titration_curve() {
+        let mut sut = PhNeutralization::default();
+        assert!((sut.ph() - 2.0).abs() < 1e-9);
+        // symmetric about the neutral point for equal concentrations
+        let (acid, base) = (sut.titration_ph(0.08), sut.titration_ph(0.125));
+        assert!((acid + base - 14.0).abs() < 1e-6, "{} {}", acid, base);
+        for ph in [2.5, 4.0, 7.0, 9.0, 11.5] {
+            let base_flow = sut.steady_input(ph).unwrap();
+            assert!((sut.titration_ph(base_flow) - ph).abs() < 1e-9);
+            assert!((sut.settle(base_flow) - ph).abs() < 1e-9);
+        }
+        // more acidic than the feed or more basic than the base is unreachable
+        assert_eq!(sut.steady_input(1.5), None);
+        assert_eq!(sut.steady_input(12.5), None);
+        // the gain near neutral is orders of magnitude above the one far off
+        let ratio = sut.process_gain(0.1) / sut.process_gain(0.05);
+        assert!(ratio > 5e3, "{}", ratio);
+        let h = 1e-9;
+        let slope = (sut.titration_ph(0.06 + h) - sut.titration_ph(0.06 - h)) / (2.0 * h);
+        assert!((slope / sut.process_gain(0.06) - 1.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_PhNeutralization_mixing_lag() {
+        let mut sut = PhNeutralization::default().set_sample_time_or_default(0.5);
+        sut.settle(0.05);
+        // one residence time V / (F_a + F_b) = 50 s after a step to 0.1 L/s
+        let mut ph = 0.0;
+        for _ in 0..100 {
+            ph = sut.transfer_td(0.1);
+        }
+        let d = 0.01 / 3.0 * (-1.0f64).exp();
+        assert!((sut.ph() - ph_of(d)).abs() < 1e-9, "{}", ph);
+        assert!(PhNeutralization::default().set_acid(0.0, 0.01).is_err());
+        assert!(PhNeutralization::default().set_volume(-1.0).is_err());
+    }
+}
@@ -0,0 +1,158 @@
+//! A polynomial static nonlinearity
+//!
+//! $ out[k] = c_{0} + c_{1} in[k] + c_{2} in[k]^{2} + \dots + c_{n} in[k]^{n} $
+//!
+//! evaluated with the Horner scheme. Typical uses are sensor linearization
+//! curves (e.g. thermocouple or RTD polynomials) and simple nonlinear plant
+//! approximations. As fitted polynomials diverge quickly outside their fit
+//! range, the input can be limited to a valid range.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use cb_simulation_util::plant::TransferTimeDomain;
+//! use cb_simulation_util::plant::polynomial::Polynomial;
+//!
+//! fn main() {
+//!     // Pt100 (Callendar-Van Dusen, T >= 0 °C): R = R0 (1 + A T + B T^2)
+//!     let mut pt100 = Polynomial::new(vec![100.0, 100.0 * 3.9083e-3, 100.0 * -5.775e-7])
+//!         .set_input_range(0.0, 850.0)
+//!         .unwrap();
+//!     assert!((pt100.transfer_td(100.0) - 138.5055).abs() < 1e-9);
+//!     assert_eq!(pt100.transfer_td(-10.0), 100.0);
+//! }
+//! ```
+
+use std::vec;
+use std::vec::Vec;
+
+use super::*;
+use core::fmt::{self, Display};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Polynomial {
+    /// Coefficients in ascending powers, `coefficients[i]` belongs to $x^i$
+    pub coefficients: Vec<f64>,
+    /// The input is clamped to this range before evaluation
+    pub input_range: Option<(f64, f64)>,
+}
+
+impl Polynomial {
+    pub fn new(coefficients: Vec<f64>) -> Self {
+        Polynomial {
+            coefficients,
+            input_range: None,
+        }
+    }
+
+    /// Limit the input to `[min, max]`, the limits are swapped if `min > max`
+    pub fn set_input_range(self, min: f64, max: f64) -> Result<Self, &'static str> {
+        if min.is_nan() || max.is_nan() {
+            return Err("Invalid input range: min and max must not be NaN");
+        }
+        Ok(Polynomial {
+            input_range: Some((min.min(max), min.max(max))),
+            ..self
+        })
+    }
+
+    pub fn degree(&self) -> usize {
+        self.coefficients.len().saturating_sub(1)
+    }
+
+    pub fn evaluate(&self, x: f64) -> f64 {
+        let x = match self.input_range {
+            Some((min, max)) => x.clamp(min, max),
+            None => x,
+        };
+        self.coefficients.iter().rev().fold(0.0, |y, c| y * x + c)
+    }
+
+    /// The derivative polynomial, e.g. the local sensitivity of a sensor curve
+    pub fn derivative(&self) -> Polynomial {
+        Polynomial {
+            coefficients: self
+                .coefficients
+                .iter()
+                .enumerate()
+                .skip(1)
+                .map(|(i, c)| i as f64 * c)
+                .collect(),
+            input_range: self.input_range,
+        }
+    }
+}
+
+impl Default for Polynomial {
+    /// The identity $ out[k] = in[k] $
+    fn default() -> Self {
+        Polynomial::new(vec![0.0, 1.0])
+    }
+}
+
+impl TypeIdentifier for Polynomial {
+    fn short_type_name(&self) -> &'static str {
+        "Polynomial"
+    }
+}
+
+impl SampleTime for Polynomial {
+    fn sample_time(&self) -> Option<f64> {
+        None
+    }
+}
+
+impl Display for Polynomial {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Polynomial(coefficients: {:?}", self.coefficients)?;
+        if let Some((min, max)) = self.input_range {
+            write!(f, ", input_range: [{}, {}]", min, max)?;
+        }
+        write!(f, ")")
+    }
+}
+
+impl TransferTimeDomain<f64> for Polynomial {
+    fn transfer_td(&mut self, input: f64) -> f64 {
+        self.evaluate(input)
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use std::format;
+
+    #[test]
+    fn test_Polynomial_horner_and_derivative() {
+        let sut = Polynomial::new(vec![1.0, -2.0, 0.0, 0.5]);
+        assert_eq!(sut.degree(), 3);
+        assert_eq!(sut.evaluate(2.0), 1.0);
+        assert_eq!(sut.derivative().evaluate(2.0), 4.0);
+        assert_eq!(Polynomial::new(vec![]).evaluate(3.0), 0.0);
+        assert_eq!(
+            format!(
+                "{}",
+                Polynomial::default().set_input_range(1.0, -1.0).unwrap()
+            ),
+            "Polynomial(coefficients: [0.0, 1.0], input_range: [-1, 1])"
+        );
+        assert!(
+            Polynomial::default()
+                .set_input_range(f64::NAN, 1.0)
+                .is_err()
+        );
+        assert!(
+            Polynomial::default()
+                .set_input_range(0.0, f64::NAN)
+                .is_err()
+        );
+        // an infinite bound limits one side only
+        let sut = Polynomial::default()
+            .set_input_range(0.0, f64::INFINITY)
+            .unwrap();
+        assert_eq!((sut.evaluate(-1.0), sut.evaluate(1e300)), (0.0, 1e300));
+    }
+}
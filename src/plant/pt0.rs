@@ -8,21 +8,41 @@
 //!
 //! For t_0 = 0 it is equivalent to a simple gain element.
 //!
+//! The delay line is a ring buffer with a circular write index, so each step
+//! is O(1) independent of the delay length.
+//!
 
+use super::snapshot::StateAccess;
 use super::*;
+use crate::parse::{Fields, ParseError};
 use core::fmt::{self, Display};
 use core::panic;
+use core::str::FromStr;
+use std::vec::Vec;
 
 use num_traits::{Num, Zero};
 
 const MAX_BUFFER_SIZE: usize = 1000;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(
+        into = "serialized::PT0Data<N>",
+        try_from = "serialized::PT0Data<N>",
+        bound(
+            serialize = "N: Copy + serde::Serialize",
+            deserialize = "N: Copy + Zero + serde::Deserialize<'de>"
+        )
+    )
+)]
 pub struct PT0<N> {
     pub t0_time: f64,
     pub sample_time: f64,
     pub kp: N,
     buffered_output: [N; MAX_BUFFER_SIZE], // a fixed array meets the Copy trait requirements
+    write_index: usize,
 }
 
 impl<N: PartialOrd + Zero + Clone + Num> PT0<N> {
@@ -63,12 +83,133 @@ impl<N: PartialOrd + Zero + Clone + Num> PT0<N> {
     }
 }
 
+impl<N> PT0<N> {
+    /// Number of buffered samples for the times, fails with the name of the invalid one
+    ///
+    /// The single check of parsed and deserialized elements: the sample time
+    /// must be > 0.0 and finite, the dead time >= 0.0 and shorter than
+    /// `MAX_BUFFER_SIZE` samples.
+    fn checked_buffer_length(sample_time: f64, t0_time: f64) -> Result<usize, &'static str> {
+        if !(sample_time > 0.0 && sample_time.is_finite()) {
+            return Err("sample_time");
+        }
+        let delay = t0_time / sample_time;
+        if !(t0_time >= 0.0 && delay < MAX_BUFFER_SIZE as f64) {
+            return Err("t0_time");
+        }
+        Ok(delay as usize + 1)
+    }
+}
+
+impl<N: Copy> PT0<N> {
+    /// Number of buffered samples, the delay in samples plus the current one
+    fn buffer_length(&self) -> usize {
+        let length = (self.t0_time / self.sample_time) as usize;
+        if length >= MAX_BUFFER_SIZE {
+            panic!(
+                "Panic: Buffer size exceeded at PT0 element with t0_time: {}",
+                self.t0_time
+            );
+        }
+        length + 1
+    }
+
+    /// The active part of the delay buffer, oldest value first
+    pub fn buffer_state(&self) -> Vec<N> {
+        let length = ((self.t0_time / self.sample_time) as usize).min(MAX_BUFFER_SIZE - 1) + 1;
+        let oldest = self.write_index % length;
+        (0..length)
+            .map(|i| self.buffered_output[(oldest + i) % length])
+            .collect()
+    }
+
+    /// Store `value` and return the one stored `buffer_length() - 1` steps ago
+    fn push(&mut self, value: N) -> N {
+        let length = self.buffer_length();
+        let index = self.write_index % length;
+        self.buffered_output[index] = value;
+        self.write_index = (index + 1) % length;
+        self.buffered_output[self.write_index]
+    }
+}
+
+impl<N: Copy + fmt::Debug + PartialEq> StateAccess for PT0<N> {
+    type State = ([N; MAX_BUFFER_SIZE], usize);
+
+    fn save_state(&self) -> Self::State {
+        (self.buffered_output, self.write_index)
+    }
+
+    fn restore_state(&mut self, state: &Self::State) {
+        (self.buffered_output, self.write_index) = *state;
+    }
+}
+
+/// The active part of the delay buffer only, oldest value first
+#[cfg(feature = "serde")]
+mod serialized {
+    use super::*;
+
+    #[derive(serde::Serialize, serde::Deserialize)]
+    #[serde(rename = "PT0")]
+    pub struct PT0Data<N> {
+        t0_time: f64,
+        sample_time: f64,
+        kp: N,
+        buffered_output: Vec<N>,
+    }
+
+    impl<N: Copy> From<PT0<N>> for PT0Data<N> {
+        fn from(pt0: PT0<N>) -> Self {
+            PT0Data {
+                t0_time: pt0.t0_time,
+                sample_time: pt0.sample_time,
+                kp: pt0.kp,
+                buffered_output: pt0.buffer_state(),
+            }
+        }
+    }
+
+    impl<N: Copy + Zero> TryFrom<PT0Data<N>> for PT0<N> {
+        type Error = &'static str;
+
+        fn try_from(data: PT0Data<N>) -> Result<Self, Self::Error> {
+            let length = PT0::<N>::checked_buffer_length(data.sample_time, data.t0_time).map_err(
+                |field| match field {
+                    "sample_time" => "Invalid PT0: sample_time must be > 0.0 and finite",
+                    _ => "Invalid PT0: t0_time must be >= 0.0 and shorter than 1000 samples",
+                },
+            )?;
+            if data.buffered_output.len() != length {
+                return Err(
+                    "Invalid PT0: buffered_output must hold t0_time / sample_time + 1 values",
+                );
+            }
+            let mut buffered_output = [N::zero(); MAX_BUFFER_SIZE];
+            buffered_output[..length].copy_from_slice(&data.buffered_output);
+            Ok(PT0 {
+                t0_time: data.t0_time,
+                sample_time: data.sample_time,
+                kp: data.kp,
+                buffered_output,
+                write_index: 0,
+            })
+        }
+    }
+}
+
 impl<N> TypeIdentifier for PT0<N> {
     fn short_type_name(&self) -> &'static str {
         "PT0"
     }
 }
 
+impl<N> SampleTime for PT0<N> {
+    fn sample_time(&self) -> Option<f64> {
+        Some(self.sample_time)
+    }
+}
+
 impl<N: Display> Display for PT0<N> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
@@ -79,6 +220,27 @@ impl<N: Display> Display for PT0<N> {
     }
 }
 
+impl<N: FromStr + Copy + Zero> FromStr for PT0<N> {
+    type Err = ParseError;
+
+    /// Parse the `Display` output, with an empty delay buffer
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut fields = Fields::new(s, "PT0")?;
+        let sample_time: f64 = fields.next("sample_time", ": ")?;
+        let t0_time: f64 = fields.next("t0_time", " ")?;
+        let kp = fields.next("kp", ": ")?;
+        fields.end()?;
+        PT0::<N>::checked_buffer_length(sample_time, t0_time).map_err(ParseError::Value)?;
+        Ok(PT0 {
+            t0_time,
+            sample_time,
+            kp,
+            buffered_output: [N::zero(); MAX_BUFFER_SIZE],
+            write_index: 0,
+        })
+    }
+}
+
 impl PT0<f64> {
     pub fn set_kp(self, kp: f64) -> Self {
         PT0::<f64> { kp, ..self }
@@ -92,28 +254,14 @@ impl Default for PT0<f64> {
             sample_time: 1.0,
             kp: 1.0,
             buffered_output: [0.0; MAX_BUFFER_SIZE],
+            write_index: 0,
         }
     }
 }
 
 impl TransferTimeDomain<f64> for PT0<f64> {
     fn transfer_td(&mut self, input: f64) -> f64 {
-        let length = (self.t0_time / self.sample_time) as usize;
-        if length > MAX_BUFFER_SIZE {
-            panic!(
-                "Panic: Buffer size exceeded at PT0 element with t0_time: {}",
-                self.t0_time
-            );
-        }
-
-        for i in 0..length {
-            // Shift the buffer to the left
-            self.buffered_output[i] = self.buffered_output[i + 1];
-        }
-        // Add the new input to the end of the buffer
-        self.buffered_output[length] = input * self.kp;
-        // The output is the first element of the buffer
-        self.buffered_output[0]
+        self.push(input * self.kp)
     }
 }
 
@@ -136,28 +284,14 @@ impl Default for PT0<i32> {
             t0_time: 0.0,
             kp: FIX_KOMMA_SHIFT,
             buffered_output: [0; MAX_BUFFER_SIZE],
+            write_index: 0,
         }
     }
 }
 
 impl TransferTimeDomain<i32> for PT0<i32> {
     fn transfer_td(&mut self, input: i32) -> i32 {
-        let length = (self.t0_time / self.sample_time) as usize;
-        if length > MAX_BUFFER_SIZE {
-            panic!(
-                "Panic: Buffer size exceeded at PT0 element with t0_time: {}",
-                self.t0_time
-            );
-        }
-
-        for i in 0..length {
-            // Shift the buffer to the left
-            self.buffered_output[i] = self.buffered_output[i + 1];
-        }
-        // Add the new input to the end of the buffer
-        self.buffered_output[length] = input * self.kp;
-        // The output is the first element of the buffer
-        self.buffered_output[0] >> FIX_KOMMA_SHIFT_BITS
+        self.push(input * self.kp) >> FIX_KOMMA_SHIFT_BITS
     }
 }
 
@@ -166,6 +300,7 @@ impl TransferTimeDomain<i32> for PT0<i32> {
 mod tests {
 
     use super::*;
+    use std::string::ToString;
 
     #[allow(dead_code)]
     #[test]
@@ -176,6 +311,7 @@ mod tests {
                 t0_time: 0.0,
                 sample_time: 1.0,
                 buffered_output: [0.0; MAX_BUFFER_SIZE],
+                write_index: 0,
             },
             PT0::<f64>::default().set_kp(2.0)
         );
@@ -205,6 +341,22 @@ mod tests {
         assert_eq!(2000, sut.transfer_td(2000));
     }
 
+    #[test]
+    fn test_PT0_f64_ring_buffer_wraps() {
+        let mut sut = PT0::<f64>::default()
+            .set_sample_time_or_default(0.01)
+            .set_t0_time_or_default(5.0);
+        let delay = 500;
+        for k in 0..3 * delay {
+            let expected = if k < delay { 0.0 } else { (k - delay) as f64 };
+            assert_eq!(expected, sut.transfer_td(k as f64));
+        }
+        let state = sut.buffer_state();
+        assert_eq!(state.len(), delay + 1);
+        assert_eq!(state[0], (2 * delay - 1) as f64);
+        assert_eq!(state[delay], (3 * delay - 1) as f64);
+    }
+
     #[test]
     fn test_PT0_f64_default() {
         assert_eq!(
@@ -213,8 +365,62 @@ mod tests {
                 t0_time: 0.0,
                 sample_time: 1.0,
                 buffered_output: [0.0; MAX_BUFFER_SIZE],
+                write_index: 0,
             },
             PT0::<f64>::default()
         );
     }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_PT0_serde_round_trip() {
+        let mut sut = PT0::<f64>::default().set_t0_time_or_default(3.0);
+        for k in 0..5 {
+            sut.transfer_td(k as f64);
+        }
+        let json = serde_json::to_string(&sut).unwrap();
+        let mut restored: PT0<f64> = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.buffer_state(), sut.buffer_state());
+        for k in 5..10 {
+            assert_eq!(restored.transfer_td(k as f64), sut.transfer_td(k as f64));
+        }
+        let truncated = json.replace("[1.0,2.0,3.0,4.0]", "[1.0]");
+        assert!(serde_json::from_str::<PT0<f64>>(&truncated).is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_PT0_from_str_and_serde_accept_the_same_dead_time() {
+        let longest = PT0::<f64>::default().set_t0_time_or_default((MAX_BUFFER_SIZE - 1) as f64);
+        assert_eq!(longest.to_string().parse::<PT0<f64>>(), Ok(longest));
+        let json = serde_json::to_string(&longest).unwrap();
+        assert_eq!(serde_json::from_str::<PT0<f64>>(&json).unwrap(), longest);
+        let too_long = longest.set_t0_time_or_default(MAX_BUFFER_SIZE as f64);
+        assert_eq!(
+            too_long.to_string().parse::<PT0<f64>>(),
+            Err(ParseError::Value("t0_time"))
+        );
+        let json = json.replace("\"t0_time\":999.0", "\"t0_time\":1000.0");
+        assert!(serde_json::from_str::<PT0<f64>>(&json).is_err());
+    }
+
+    #[test]
+    fn test_PT0_from_str_round_trip() {
+        let sut = PT0::<f64>::default()
+            .set_sample_time_or_default(0.5)
+            .set_t0_time_or_default(2.0)
+            .set_kp(-1.5);
+        let parsed: PT0<f64> = sut.to_string().parse().unwrap();
+        assert_eq!(parsed, sut);
+        let sut = PT0::<i32>::default().set_kp(3);
+        assert_eq!(sut.to_string().parse::<PT0<i32>>(), Ok(sut));
+        assert_eq!(
+            "PT0(sample_time: 1, t0_time -1, kp: 1)".parse::<PT0<f64>>(),
+            Err(ParseError::Value("t0_time"))
+        );
+        assert_eq!(
+            "PT0(sample_time: 1, t0_time: 1, kp: 1)".parse::<PT0<f64>>(),
+            Err(ParseError::Field("t0_time"))
+        );
+    }
 }
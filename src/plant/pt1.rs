@@ -7,18 +7,29 @@
 //! and $P$ is the amplification
 //! Euler forward method
 //!
+//! Other integration methods are selected with `set_solver`, see `Solver`.
+//! They replace $\alpha$; `Trapezoidal` applies it to the mean of $in[k]$
+//! and $in[k-1]$.
+//!
 
 use num_traits::Zero;
 
+use super::snapshot::StateAccess;
+use super::solver::{Solver, StabilityError};
 use super::*;
+use crate::parse::{Fields, ParseError};
 use core::fmt::{self, Display};
+use core::str::FromStr;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PT1<N> {
     pub t1_time: f64,
     pub sample_time: f64,
     pub kp: N,
+    pub solver: Solver,
     previous_output: N,
+    previous_input: N,
 }
 
 impl<N: PartialOrd + Zero> PT1<N> {
@@ -36,8 +47,14 @@ impl<N: PartialOrd + Zero> PT1<N> {
         }
     }
 
+    /// Set the time constant
+    ///
+    /// With `Solver::EulerForward` it must be greater than or equal to the
+    /// sample time, with the other solvers greater than 0.0.
     pub fn set_t1_time_or_default(self, t1_time: f64) -> Self {
-        if t1_time >= self.sample_time {
+        let valid =
+            t1_time >= self.sample_time || (self.solver != Solver::EulerForward && t1_time > 0.0);
+        if valid {
             PT1::<N> { t1_time, ..self }
         } else {
             PT1::<N> {
@@ -48,14 +65,63 @@ impl<N: PartialOrd + Zero> PT1<N> {
     }
 }
 
+impl<N> PT1<N> {
+    /// Set the integration method, select it before the time constant
+    pub fn set_solver(self, solver: Solver) -> Self {
+        PT1::<N> { solver, ..self }
+    }
+
+    /// Whether the recurrence of the solver follows the continuous lag
+    ///
+    /// With Euler forward it diverges for `sample_time >= 2 t1_time` and
+    /// alternates in sign for `sample_time > t1_time`.
+    pub fn check_stability(&self) -> Result<(), StabilityError> {
+        if self.t1_time <= 0.0 {
+            return Err(StabilityError::Unstable {
+                spectral_radius: f64::INFINITY,
+            });
+        }
+        let alpha = self
+            .solver
+            .first_order_alpha(self.sample_time / self.t1_time);
+        super::solver::check_poles(&ndarray::Array2::from_elem((1, 1), 1.0 - alpha))
+    }
+
+    /// Switch to `Solver::EulerBackward` if `check_stability` fails
+    ///
+    /// Keeps the time constant and the sample time, unlike limiting the
+    /// time constant to the sample time.
+    pub fn stabilize(self) -> Self {
+        match self.check_stability() {
+            Ok(()) => self,
+            Err(_) => self.set_solver(Solver::EulerBackward),
+        }
+    }
+}
+
+impl<N: Copy> PT1<N> {
+    /// The internal state: previous output
+    pub fn state(&self) -> N {
+        self.previous_output
+    }
+}
+
 const FIX_KOMMA_SHIFT_BITS: u8 = 10;
 const FIX_KOMMA_SHIFT: i32 = 1 << FIX_KOMMA_SHIFT_BITS;
 
 impl PT1<i32> {
     // alpha is fixed point with 10 bits after the comma
     // alpha is used to overcome sampling rate / t1 time dependency
-    fn alpha(&self) -> i32 {
-        (self.sample_time * FIX_KOMMA_SHIFT as f64 / self.t1_time) as i32
+    pub(crate) fn alpha(&self) -> i32 {
+        match self.solver {
+            Solver::EulerForward => {
+                (self.sample_time * FIX_KOMMA_SHIFT as f64 / self.t1_time) as i32
+            }
+            solver => {
+                let alpha = solver.first_order_alpha(self.sample_time / self.t1_time);
+                (alpha * FIX_KOMMA_SHIFT as f64) as i32
+            }
+        }
     }
 
     pub fn set_kp(self, kp: i32) -> Self {
@@ -72,29 +138,97 @@ impl Default for PT1<i32> {
             sample_time: 1.0,
             t1_time: 1.0,
             kp: FIX_KOMMA_SHIFT,
+            solver: Solver::EulerForward,
             previous_output: 0,
+            previous_input: 0,
         }
     }
 }
 
+impl<N: Copy + fmt::Debug + PartialEq> StateAccess for PT1<N> {
+    /// Previous output and previous input
+    type State = (N, N);
+
+    fn save_state(&self) -> Self::State {
+        (self.previous_output, self.previous_input)
+    }
+
+    fn restore_state(&mut self, state: &Self::State) {
+        (self.previous_output, self.previous_input) = *state;
+    }
+}
+
 impl<N> TypeIdentifier for PT1<N> {
     fn short_type_name(&self) -> &'static str {
         "PT1"
     }
 }
 
+impl<N> SampleTime for PT1<N> {
+    fn sample_time(&self) -> Option<f64> {
+        Some(self.sample_time)
+    }
+}
+
 impl<N: Display> Display for PT1<N> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
             f,
-            "PT1(sample_time: {}, t1_time {}, kp: {})",
+            "PT1(sample_time: {}, t1_time {}, kp: {}",
             self.sample_time, self.t1_time, self.kp
-        )
+        )?;
+        if self.solver != Solver::EulerForward {
+            write!(f, ", solver: {:?}", self.solver)?;
+        }
+        write!(f, ")")
+    }
+}
+
+impl<N: FromStr + PartialOrd + Zero> FromStr for PT1<N> {
+    type Err = ParseError;
+
+    /// Parse the `Display` output, at rest
+    ///
+    /// Fails for a sample time or time constant the setters do not accept.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut fields = Fields::new(s, "PT1")?;
+        let sample_time: f64 = fields.next("sample_time", ": ")?;
+        let t1_time: f64 = fields.next("t1_time", " ")?;
+        let kp = fields.next("kp", ": ")?;
+        let solver = fields.optional("solver", ": ")?.unwrap_or_default();
+        fields.end()?;
+        let pt1 = PT1 {
+            sample_time: 1.0,
+            t1_time: 1.0,
+            kp,
+            solver,
+            previous_output: N::zero(),
+            previous_input: N::zero(),
+        }
+        .set_sample_time_or_default(sample_time);
+        if pt1.sample_time != sample_time {
+            return Err(ParseError::Value("sample_time"));
+        }
+        let pt1 = pt1.set_t1_time_or_default(t1_time);
+        if pt1.t1_time != t1_time {
+            return Err(ParseError::Value("t1_time"));
+        }
+        Ok(pt1)
     }
 }
 
 impl TransferTimeDomain<i32> for PT1<i32> {
     fn transfer_td(&mut self, input: i32) -> i32 {
+        if self.solver == Solver::Trapezoidal {
+            // amplify before halving, the mean of odd sums keeps its half
+            let target = (input as i64 + self.previous_input as i64) * self.kp as i64 / 2;
+            self.previous_input = input;
+            let out = self.previous_output as i64
+                + ((self.alpha() as i64 * (target - self.previous_output as i64))
+                    >> FIX_KOMMA_SHIFT_BITS);
+            self.previous_output = out as i32;
+            return self.previous_output >> FIX_KOMMA_SHIFT_BITS;
+        }
         let out = self.previous_output + (self.alpha() * (input * self.kp - self.previous_output))
             >> FIX_KOMMA_SHIFT_BITS;
         self.previous_output = out;
@@ -104,8 +238,11 @@ impl TransferTimeDomain<i32> for PT1<i32> {
 
 impl PT1<f64> {
     // alpha is used to overcome sampling rate / t1 time dependency
-    fn alpha(&self) -> f64 {
-        self.sample_time / self.t1_time
+    pub(crate) fn alpha(&self) -> f64 {
+        match self.solver {
+            Solver::EulerForward => self.sample_time / self.t1_time,
+            solver => solver.first_order_alpha(self.sample_time / self.t1_time),
+        }
     }
 
     pub fn set_kp(self, kp: f64) -> Self {
@@ -119,13 +256,22 @@ impl Default for PT1<f64> {
             t1_time: 1.0,
             sample_time: 1.0,
             kp: 1.0,
+            solver: Solver::EulerForward,
             previous_output: 0.0,
+            previous_input: 0.0,
         }
     }
 }
 
 impl TransferTimeDomain<f64> for PT1<f64> {
     fn transfer_td(&mut self, input: f64) -> f64 {
+        let input = if self.solver == Solver::Trapezoidal {
+            let mean = 0.5 * (input + self.previous_input);
+            self.previous_input = input;
+            mean
+        } else {
+            input
+        };
         let out = self.previous_output + (self.alpha() * (input * self.kp - self.previous_output));
         self.previous_output = out;
         out
@@ -137,6 +283,7 @@ impl TransferTimeDomain<f64> for PT1<f64> {
 mod tests {
 
     use super::*;
+    use std::string::ToString;
 
     #[allow(dead_code)]
     #[test]
@@ -147,7 +294,9 @@ mod tests {
                 kp: 2048,
                 t1_time: 1.0,
                 sample_time: 1.0,
+                solver: Solver::EulerForward,
                 previous_output: 0,
+                previous_input: 0,
             },
             PT1::<i32>::default().set_kp(2)
         );
@@ -159,6 +308,69 @@ mod tests {
         assert_eq!(1000, sut.transfer_td(1000));
     }
 
+    #[test]
+    fn test_PT1_solvers_stable_for_large_sample_time() {
+        // sample time 3 times the time constant, exact step response 1 - e^(-3 k)
+        for solver in [Solver::EulerBackward, Solver::Trapezoidal] {
+            let mut sut = PT1::<f64>::default()
+                .set_sample_time_or_default(3.0)
+                .set_solver(solver)
+                .set_t1_time_or_default(1.0);
+            let out: std::vec::Vec<f64> = (0..20).map(|_| sut.transfer_td(1.0)).collect();
+            // Trapezoidal rings with 1 - alpha = -0.2 but stays bounded
+            assert!(out.iter().all(|y| *y <= 1.1), "{:?}", solver);
+            assert!((out[19] - 1.0).abs() < 1e-3, "{:?}", solver);
+        }
+        let mut sut = PT1::<f64>::default()
+            .set_sample_time_or_default(0.5)
+            .set_solver(Solver::RungeKutta4);
+        let exact = 1.0 - (-0.5f64).exp();
+        assert!((sut.transfer_td(1.0) - exact).abs() < 1e-3);
+        // Euler forward keeps limiting the time constant to the sample time
+        let sut = PT1::<f64>::default()
+            .set_sample_time_or_default(3.0)
+            .set_t1_time_or_default(1.0);
+        assert_eq!(sut.t1_time, 3.0);
+    }
+
+    #[test]
+    fn test_PT1_i32_trapezoidal() {
+        let mut sut = PT1::<i32>::default()
+            .set_sample_time_or_default(2.0)
+            .set_solver(Solver::Trapezoidal)
+            .set_t1_time_or_default(1.0);
+        // alpha = 1: the output follows the mean of the last two inputs
+        assert_eq!(sut.transfer_td(1000), 500);
+        assert_eq!(sut.transfer_td(1000), 1000);
+        // mean 1.5 of the inputs 1 and 2 amplified by 3
+        let mut sut = sut.set_kp(3);
+        sut.restore_state(&(0, 1));
+        assert_eq!(sut.transfer_td(2), 4);
+    }
+
+    #[test]
+    fn test_PT1_check_stability() {
+        let mut sut = PT1::<f64>::default();
+        assert_eq!(sut.check_stability(), Ok(()));
+        // public fields bypass the limit of the setter
+        sut.sample_time = 2.5;
+        assert_eq!(
+            sut.check_stability(),
+            Err(StabilityError::Unstable {
+                spectral_radius: 1.5
+            })
+        );
+        sut.sample_time = 1.5;
+        assert_eq!(
+            sut.check_stability(),
+            Err(StabilityError::Ringing { pole: -0.5 })
+        );
+        let mut sut = sut.stabilize();
+        assert_eq!(sut.solver, Solver::EulerBackward);
+        assert_eq!(sut.check_stability(), Ok(()));
+        assert!((sut.transfer_td(1.0) - 0.6).abs() < 1e-12);
+    }
+
     #[test]
     fn test_PT1_f64_default() {
         assert_eq!(
@@ -166,9 +378,54 @@ mod tests {
                 kp: 1.0,
                 t1_time: 1.0,
                 sample_time: 1.0,
+                solver: Solver::EulerForward,
                 previous_output: 0.0,
+                previous_input: 0.0,
             },
             PT1::<f64>::default()
         );
     }
+
+    #[test]
+    fn test_PT1_from_str_round_trip() {
+        let sut = PT1::<f64>::default()
+            .set_sample_time_or_default(0.1)
+            .set_t1_time_or_default(2.5)
+            .set_kp(0.25)
+            .set_solver(Solver::Trapezoidal);
+        let parsed: PT1<f64> = sut.to_string().parse().unwrap();
+        assert_eq!(parsed, sut);
+        assert_eq!(parsed.to_string(), sut.to_string());
+        let sut = PT1::<i32>::default().set_kp(-2);
+        assert_eq!(sut.to_string().parse::<PT1<i32>>(), Ok(sut));
+        assert_eq!(
+            "PT1(sample_time: 1, t1_time 2, kp: 1, solver: Euler)".parse::<PT1<f64>>(),
+            Err(ParseError::Value("solver"))
+        );
+        assert_eq!(
+            "PT1(sample_time: 1, t1_time 2, kp: 1) ".parse::<PT1<f64>>(),
+            Err(ParseError::Type("PT1"))
+        );
+    }
+
+    #[test]
+    fn test_PT1_from_str_rejects_invalid_times() {
+        assert_eq!(
+            "PT1(sample_time: 0, t1_time 2, kp: 1)".parse::<PT1<f64>>(),
+            Err(ParseError::Value("sample_time"))
+        );
+        assert_eq!(
+            "PT1(sample_time: 1, t1_time 0, kp: 1)".parse::<PT1<f64>>(),
+            Err(ParseError::Value("t1_time"))
+        );
+        assert_eq!(
+            "PT1(sample_time: 1, t1_time 0.5, kp: 1)".parse::<PT1<f64>>(),
+            Err(ParseError::Value("t1_time"))
+        );
+        assert!(
+            "PT1(sample_time: 1, t1_time 0.5, kp: 1, solver: Trapezoidal)"
+                .parse::<PT1<f64>>()
+                .is_ok()
+        );
+    }
 }
@@ -15,6 +15,12 @@
 //! and $P$ is the amplification
 //! (Euler Forward method)
 //!
+//! Other integration methods of the same state equations are selected with
+//! `set_solver`, see `Solver`. Unlike Euler forward, `EulerBackward` and
+//! `Trapezoidal` remain stable for any sample time. Their coefficients are
+//! computed by the setters and recomputed on the next sample after a public
+//! field changed directly.
+//!
 //! PT2 == PS2 element iff damping factor $D = 0.0 $
 //!
 //! $D  =  \frac{T_{1} + T_{2}}{2 \cdot T_{1} \cdot T_{2}} $
@@ -27,40 +33,183 @@
 use num_traits::Zero;
 use std::*;
 
+use ndarray::{Array1, Array2, array};
+
+use super::snapshot::StateAccess;
+use super::solver::{Solver, StabilityError};
 use super::*;
+use crate::parse::{Fields, ParseError};
 use core::fmt::{self, Display};
+use core::str::FromStr;
+
+/// Omega, damping, sample time and solver, the parameters of the coefficients
+type Parameters = (f64, f64, f64, Solver);
+
+/// Discretized state equations $ x[k] = A_{d} x[k-1] + B_{d} K u[k] $ of the solver
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub(crate) struct Coefficients {
+    /// The parameters the coefficients were computed for
+    parameters: Option<Parameters>,
+    pub(crate) ad: [[f64; 2]; 2],
+    pub(crate) bd: [f64; 2],
+    /// `ad` and `bd` in fixed point with 10 bits after the comma
+    pub(crate) fixed_ad: [[i64; 2]; 2],
+    pub(crate) fixed_bd: [i64; 2],
+}
+
+impl Coefficients {
+    fn new(parameters: Parameters, ad: &Array2<f64>, bd: &Array1<f64>) -> Self {
+        let fixed = |c: f64| (c * FIX_KOMMA_SHIFT as f64).round() as i64;
+        Coefficients {
+            parameters: Some(parameters),
+            ad: [[ad[[0, 0]], ad[[0, 1]]], [ad[[1, 0]], ad[[1, 1]]]],
+            bd: [bd[0], bd[1]],
+            fixed_ad: [
+                [fixed(ad[[0, 0]]), fixed(ad[[0, 1]])],
+                [fixed(ad[[1, 0]]), fixed(ad[[1, 1]])],
+            ],
+            fixed_bd: [fixed(bd[0]), fixed(bd[1])],
+        }
+    }
+}
 
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(from = "SerdePT2<N>"))]
 pub struct PT2<N> {
     pub omega: f64,
     pub damping: f64,
     pub sample_time: f64,
     pub kp: N,
+    pub solver: Solver,
+    #[cfg_attr(feature = "serde", serde(skip_serializing))]
+    coefficients: Coefficients,
     previous_output: N,
     previous_diff_output: N,
+    previous_input: N,
+}
+
+/// The serialized fields of `PT2`, the coefficients are recomputed
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct SerdePT2<N> {
+    omega: f64,
+    damping: f64,
+    sample_time: f64,
+    kp: N,
+    solver: Solver,
+    previous_output: N,
+    previous_diff_output: N,
+    previous_input: N,
+}
+
+#[cfg(feature = "serde")]
+impl<N> From<SerdePT2<N>> for PT2<N> {
+    fn from(fields: SerdePT2<N>) -> Self {
+        PT2 {
+            omega: fields.omega,
+            damping: fields.damping,
+            sample_time: fields.sample_time,
+            kp: fields.kp,
+            solver: fields.solver,
+            coefficients: Coefficients::default(),
+            previous_output: fields.previous_output,
+            previous_diff_output: fields.previous_diff_output,
+            previous_input: fields.previous_input,
+        }
+        .update()
+    }
+}
+
+impl<N> PT2<N> {
+    /// Set the integration method, select it before the time constants
+    pub fn set_solver(self, solver: Solver) -> Self {
+        PT2::<N> { solver, ..self }.update()
+    }
+
+    fn parameters(&self) -> Parameters {
+        (self.omega, self.damping, self.sample_time, self.solver)
+    }
+
+    /// Recompute the coefficients after a parameter changed
+    fn update(self) -> Self {
+        let (ad, bd) = self.discretized();
+        PT2::<N> {
+            coefficients: Coefficients::new(self.parameters(), &ad, &bd),
+            ..self
+        }
+    }
+
+    /// The coefficients of the current parameters, recomputed if a public
+    /// field changed since the last setter
+    pub(crate) fn coefficients(&self) -> Coefficients {
+        if self.coefficients.parameters == Some(self.parameters()) {
+            self.coefficients
+        } else {
+            let (ad, bd) = self.discretized();
+            Coefficients::new(self.parameters(), &ad, &bd)
+        }
+    }
+
+    /// `coefficients`, kept for the next samples
+    fn updated_coefficients(&mut self) -> Coefficients {
+        self.coefficients = self.coefficients();
+        self.coefficients
+    }
+
+    /// Whether `time` is a valid time constant for the solver
+    ///
+    /// Euler forward needs time constants of at least the sample time.
+    fn is_valid_time(&self, time: f64) -> bool {
+        time.is_finite()
+            && (time >= self.sample_time || (self.solver != Solver::EulerForward && time > 0.0))
+    }
+
+    /// Whether the recurrence of the solver follows the continuous element
+    ///
+    /// Fails if the discrete poles leave the unit circle or a real pole is
+    /// negative, e.g. with Euler forward for $ \omega T_{s} $ close to 1 or
+    /// larger.
+    pub fn check_stability(&self) -> Result<(), StabilityError> {
+        super::solver::check_poles(&self.discretized().0)
+    }
+
+    /// Switch to `Solver::EulerBackward` if `check_stability` fails
+    pub fn stabilize(self) -> Self {
+        match self.check_stability() {
+            Ok(()) => self,
+            Err(_) => self.set_solver(Solver::EulerBackward),
+        }
+    }
+
+    /// Discretized state equations of the solver
+    ///
+    /// The input of `Bd` is the amplified input.
+    pub(crate) fn discretized(&self) -> (Array2<f64>, Array1<f64>) {
+        let (omega, damping) = (self.omega, self.damping);
+        let a = array![[0.0, omega], [-omega * omega, -2.0 * damping * omega]];
+        let b = array![0.0, omega * omega];
+        self.solver.discretize(&a, &b, self.sample_time)
+    }
 }
 
 impl<N: PartialOrd + Zero> PT2<N> {
     pub fn set_sample_time_or_default(self, sample_time: f64) -> Self {
-        if sample_time > 0.0 {
-            PT2::<N> {
-                sample_time,
-                ..self
-            }
-        } else {
-            PT2::<N> {
-                sample_time: 1.0,
-                ..self
-            }
+        let sample_time = if sample_time > 0.0 { sample_time } else { 1.0 };
+        PT2::<N> {
+            sample_time,
+            ..self
         }
+        .update()
     }
 
     pub fn set_omega_or_default(self, omega: f64) -> Self {
-        if 1.0 / omega >= self.sample_time {
-            PT2::<N> { omega, ..self }
+        let omega = if self.is_valid_time(1.0 / omega) {
+            omega
         } else {
-            PT2::<N> { omega: 1.0, ..self }
-        }
+            1.0
+        };
+        PT2::<N> { omega, ..self }.update()
     }
 
     /// Set the damping factor
@@ -70,37 +219,29 @@ impl<N: PartialOrd + Zero> PT2<N> {
     /// $D = 1.0 $  *critically damped oscillation* - no over oscillation, fastest possible response
     /// $D > 1.0 $  *overdamped oscillation* - no over oscillation
     pub fn set_damping_or_default(self, damping: f64) -> Self {
-        if damping >= 0.0 {
-            PT2::<N> { damping, ..self }
-        } else {
-            PT2::<N> {
-                damping: 1.0,
-                ..self
-            }
-        }
+        let damping = if damping >= 0.0 { damping } else { 1.0 };
+        PT2::<N> { damping, ..self }.update()
     }
 
     /// Set the time constant of the first order lag
     ///
-    /// - it must be greater than or equal to the sample time
+    /// - it must be greater than or equal to the sample time with Euler forward
     /// - is equivalent to set the period of angular frequency
     pub fn set_t1_time_or_default(self, t1_time: f64) -> Self {
-        if t1_time >= self.sample_time {
-            PT2::<N> {
-                omega: 1.0 / t1_time,
-                ..self
-            }
+        let omega = if self.is_valid_time(t1_time) {
+            1.0 / t1_time
         } else {
-            PT2::<N> { omega: 1.0, ..self }
-        }
+            1.0
+        };
+        PT2::<N> { omega, ..self }.update()
     }
 
     /// Set the time constant of the second order lag
-    /// - it must be greater than or equal to the sample time
+    /// - it must be greater than or equal to the sample time with Euler forward
     /// - modifies the angular frequency and damping factor
     /// - leads to a damping >= 1.0
     pub fn set_t2_time_or_default(self, t2_time: f64) -> Self {
-        if t2_time >= self.sample_time {
+        let pt2 = if self.is_valid_time(t2_time) {
             let omega = (1.0 / t2_time * self.omega).sqrt();
             PT2::<N> {
                 omega,
@@ -112,7 +253,15 @@ impl<N: PartialOrd + Zero> PT2<N> {
                 damping: 1.0, // t1 == t2 equivalent to critically damped oscillation
                 ..self
             }
-        }
+        };
+        pt2.update()
+    }
+}
+
+impl<N: Copy> PT2<N> {
+    /// The internal state: (previous output, previous diff output)
+    pub fn state(&self) -> (N, N) {
+        (self.previous_output, self.previous_diff_output)
     }
 }
 
@@ -135,9 +284,34 @@ impl Default for PT2<i32> {
             omega: 1.0,
             damping: 0.0,
             kp: FIX_KOMMA_SHIFT as i32,
+            solver: Solver::EulerForward,
+            coefficients: Coefficients::default(),
             previous_output: 0,
             previous_diff_output: 0,
+            previous_input: 0,
         }
+        .update()
+    }
+}
+
+impl<N: Copy + fmt::Debug + PartialEq> StateAccess for PT2<N> {
+    /// Previous output, its derivative and the previous input
+    type State = (N, N, N);
+
+    fn save_state(&self) -> Self::State {
+        (
+            self.previous_output,
+            self.previous_diff_output,
+            self.previous_input,
+        )
+    }
+
+    fn restore_state(&mut self, state: &Self::State) {
+        (
+            self.previous_output,
+            self.previous_diff_output,
+            self.previous_input,
+        ) = *state;
     }
 }
 
@@ -147,18 +321,96 @@ impl<N> TypeIdentifier for PT2<N> {
     }
 }
 
+impl<N> SampleTime for PT2<N> {
+    fn sample_time(&self) -> Option<f64> {
+        Some(self.sample_time)
+    }
+}
+
 impl<N: Display> Display for PT2<N> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
             f,
-            "PT2(sample_time: {}, omega {}, damping {}, kp: {})",
+            "PT2(sample_time: {}, omega {}, damping {}, kp: {}",
             self.sample_time, self.omega, self.damping, self.kp
-        )
+        )?;
+        if self.solver != Solver::EulerForward {
+            write!(f, ", solver: {:?}", self.solver)?;
+        }
+        write!(f, ")")
+    }
+}
+
+impl<N: FromStr + PartialOrd + Zero> FromStr for PT2<N> {
+    type Err = ParseError;
+
+    /// Parse the `Display` output, at rest
+    ///
+    /// Fails for a sample time, angular frequency or damping the setters do
+    /// not accept.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut fields = Fields::new(s, "PT2")?;
+        let sample_time: f64 = fields.next("sample_time", ": ")?;
+        let omega: f64 = fields.next("omega", " ")?;
+        let damping: f64 = fields.next("damping", " ")?;
+        let kp = fields.next("kp", ": ")?;
+        let solver = fields.optional("solver", ": ")?.unwrap_or_default();
+        fields.end()?;
+        let pt2 = PT2 {
+            sample_time: 1.0,
+            omega: 1.0,
+            damping: 1.0,
+            kp,
+            solver,
+            coefficients: Coefficients::default(),
+            previous_output: N::zero(),
+            previous_diff_output: N::zero(),
+            previous_input: N::zero(),
+        }
+        .set_sample_time_or_default(sample_time);
+        if pt2.sample_time != sample_time {
+            return Err(ParseError::Value("sample_time"));
+        }
+        let pt2 = pt2.set_omega_or_default(omega);
+        if pt2.omega != omega {
+            return Err(ParseError::Value("omega"));
+        }
+        let pt2 = pt2.set_damping_or_default(damping);
+        if pt2.damping != damping {
+            return Err(ParseError::Value("damping"));
+        }
+        Ok(pt2)
     }
 }
 
 impl TransferTimeDomain<i32> for PT2<i32> {
     fn transfer_td(&mut self, input: i32) -> i32 {
+        if self.solver != Solver::EulerForward {
+            // both states and the coefficients with 10 bits after the comma
+            let u = if self.solver == Solver::Trapezoidal {
+                // amplify before halving, the mean of odd sums keeps its half
+                let sum = input as i64 + self.previous_input as i64;
+                self.previous_input = input;
+                self.kp as i64 * sum / 2
+            } else {
+                self.kp as i64 * input as i64
+            };
+            let Coefficients {
+                fixed_ad: ad,
+                fixed_bd: bd,
+                ..
+            } = self.updated_coefficients();
+            let x = [
+                self.previous_output as i64,
+                self.previous_diff_output as i64,
+            ];
+            let next: [i64; 2] = core::array::from_fn(|i| {
+                (ad[i][0] * x[0] + ad[i][1] * x[1] + bd[i] * u) >> FIX_KOMMA_SHIFT_BITS
+            });
+            self.previous_output = next[0].clamp(i32::MIN as i64, i32::MAX as i64) as i32;
+            self.previous_diff_output = next[1].clamp(i32::MIN as i64, i32::MAX as i64) as i32;
+            return self.previous_output >> FIX_KOMMA_SHIFT_BITS;
+        }
         let omega: i64 = (self.omega * (FIX_KOMMA_SHIFT as f64)) as i64;
         let omega_squared = omega * omega / FIX_KOMMA_SHIFT;
         let damping: i64 = (self.damping * (FIX_KOMMA_SHIFT as f64)) as i64;
@@ -193,14 +445,35 @@ impl Default for PT2<f64> {
             damping: 1.0,
             sample_time: 1.0,
             kp: 1.0,
+            solver: Solver::EulerForward,
+            coefficients: Coefficients::default(),
             previous_output: 0.0,
             previous_diff_output: 0.0,
+            previous_input: 0.0,
         }
+        .update()
     }
 }
 
 impl TransferTimeDomain<f64> for PT2<f64> {
     fn transfer_td(&mut self, input: f64) -> f64 {
+        if self.solver != Solver::EulerForward {
+            let input = if self.solver == Solver::Trapezoidal {
+                let mean = 0.5 * (input + self.previous_input);
+                self.previous_input = input;
+                mean
+            } else {
+                input
+            };
+            let Coefficients { ad, bd, .. } = self.updated_coefficients();
+            let x = [self.previous_output, self.previous_diff_output];
+            let u = self.kp * input;
+            let next: [f64; 2] =
+                core::array::from_fn(|i| ad[i][0] * x[0] + ad[i][1] * x[1] + bd[i] * u);
+            self.previous_output = next[0];
+            self.previous_diff_output = next[1];
+            return next[0];
+        }
         let omega_squared = self.omega * self.omega;
 
         // $ x2[k] = x2​[k−1] + h(−2D omega ​x2​[k−1]) − \omega^{2} ​x1​[k−1] + K \omega^{2} ​u[k]) $
@@ -223,6 +496,7 @@ impl TransferTimeDomain<f64> for PT2<f64> {
 mod tests {
 
     use super::*;
+    use std::string::ToString;
 
     #[test]
     fn test_PT2_new() {
@@ -233,9 +507,13 @@ mod tests {
                 omega: 1.0,
                 damping: 0.0,
                 sample_time: 1.0,
+                solver: Solver::EulerForward,
+                coefficients: Coefficients::default(),
                 previous_output: 0,
-                previous_diff_output: 0
-            },
+                previous_diff_output: 0,
+                previous_input: 0,
+            }
+            .update(),
             PT2::<i32>::default().set_kp(2)
         );
     }
@@ -246,6 +524,104 @@ mod tests {
         assert_eq!(0, sut.transfer_td(1000));
     }
 
+    #[test]
+    fn test_PT2_solvers_for_large_sample_time() {
+        // sample time twice the period of the natural frequency
+        for solver in [Solver::EulerBackward, Solver::Trapezoidal] {
+            let mut sut = PT2::<f64>::default()
+                .set_sample_time_or_default(2.0)
+                .set_solver(solver)
+                .set_omega_or_default(1.0)
+                .set_damping_or_default(0.5)
+                .set_kp(2.0);
+            assert_eq!(sut.omega, 1.0);
+            let mut y = 0.0;
+            for _ in 0..100 {
+                y = sut.transfer_td(1.0);
+                assert!(y.abs() < 10.0, "{:?}", solver);
+            }
+            assert!((y - 2.0).abs() < 1e-6, "{:?}", solver);
+        }
+        let mut euler = PT2::<f64>::default()
+            .set_sample_time_or_default(2.0)
+            .set_damping_or_default(0.5);
+        assert!(
+            (0..100)
+                .map(|_| euler.transfer_td(1.0))
+                .any(|y| y.abs() > 1e6)
+        );
+    }
+
+    #[test]
+    fn test_PT2_check_stability() {
+        let sut = PT2::<f64>::default()
+            .set_sample_time_or_default(0.1)
+            .set_damping_or_default(0.5);
+        assert_eq!(sut.check_stability(), Ok(()));
+        let sut = PT2::<i32>::default().set_sample_time_or_default(2.0);
+        assert!(matches!(
+            sut.check_stability(),
+            Err(StabilityError::Unstable { .. })
+        ));
+        let sut = sut.stabilize();
+        assert_eq!(sut.solver, Solver::EulerBackward);
+        assert_eq!(sut.check_stability(), Ok(()));
+    }
+
+    #[test]
+    fn test_PT2_i32_trapezoidal_steady_state() {
+        let mut sut = PT2::<i32>::default()
+            .set_sample_time_or_default(0.5)
+            .set_solver(Solver::Trapezoidal)
+            .set_damping_or_default(1.0)
+            .set_kp(3);
+        let mut y = 0;
+        for _ in 0..200 {
+            y = sut.transfer_td(100);
+        }
+        assert!((y - 300).abs() <= 1, "{}", y);
+        // the mean 0.5 of alternating inputs is not truncated to 0
+        for k in 0..200 {
+            y = sut.transfer_td(k % 2);
+        }
+        assert_eq!(y, 1);
+    }
+
+    #[test]
+    fn test_PT2_coefficients_follow_setters() {
+        let sut = PT2::<f64>::default()
+            .set_solver(Solver::Trapezoidal)
+            .set_sample_time_or_default(0.5)
+            .set_omega_or_default(2.0)
+            .set_damping_or_default(0.3);
+        let (ad, bd) = Solver::Trapezoidal.discretize(
+            &array![[0.0, 2.0], [-4.0, -1.2]],
+            &array![0.0, 4.0],
+            0.5,
+        );
+        let parameters = (2.0, 0.3, 0.5, Solver::Trapezoidal);
+        assert_eq!(sut.coefficients, Coefficients::new(parameters, &ad, &bd));
+        let parsed: PT2<f64> = sut.to_string().parse().unwrap();
+        assert_eq!(parsed.coefficients, sut.coefficients);
+    }
+
+    #[test]
+    fn test_PT2_coefficients_follow_public_fields() {
+        let configured = PT2::<f64>::default()
+            .set_solver(Solver::Trapezoidal)
+            .set_sample_time_or_default(0.5)
+            .set_omega_or_default(2.0)
+            .set_damping_or_default(0.3);
+        let mut sut = PT2::<f64>::default().set_solver(Solver::EulerBackward);
+        (sut.omega, sut.damping, sut.sample_time) = (2.0, 0.3, 0.5);
+        sut.solver = Solver::Trapezoidal;
+        let mut expected = configured;
+        for _ in 0..20 {
+            assert_eq!(sut.transfer_td(1.0), expected.transfer_td(1.0));
+        }
+        assert_eq!(sut, expected);
+    }
+
     #[test]
     fn test_PT2_f64_default() {
         assert_eq!(
@@ -254,10 +630,65 @@ mod tests {
                 omega: 1.0,
                 sample_time: 1.0,
                 damping: 1.0,
+                solver: Solver::EulerForward,
+                coefficients: Coefficients::default(),
                 previous_diff_output: 0.0,
                 previous_output: 0.0,
-            },
+                previous_input: 0.0,
+            }
+            .update(),
             PT2::<f64>::default()
         );
     }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_PT2_serde_round_trip() {
+        let mut sut = PT2::<f64>::default()
+            .set_sample_time_or_default(0.1)
+            .set_solver(Solver::Trapezoidal);
+        sut.transfer_td(1.0);
+        let json = serde_json::to_string(&sut).unwrap();
+        assert!(json.contains("\"solver\":\"Trapezoidal\""));
+        let restored: PT2<f64> = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored, sut);
+    }
+
+    #[test]
+    fn test_PT2_from_str_round_trip() {
+        let sut = PT2::<f64>::default()
+            .set_solver(Solver::RungeKutta4)
+            .set_sample_time_or_default(0.01)
+            .set_omega_or_default(3.0)
+            .set_damping_or_default(0.4)
+            .set_kp(2.0);
+        let parsed: PT2<f64> = sut.to_string().parse().unwrap();
+        assert_eq!(parsed, sut);
+        let sut = PT2::<i32>::default().set_kp(4);
+        assert_eq!(sut.to_string().parse::<PT2<i32>>(), Ok(sut));
+        assert_eq!(
+            "PT2(sample_time: 1, omega 1, kp: 1)".parse::<PT2<f64>>(),
+            Err(ParseError::Field("damping"))
+        );
+        assert_eq!(
+            "PT2(sample_time: 1, omega 1, damping 1, kp: 1, gain: 2)".parse::<PT2<f64>>(),
+            Err(ParseError::Trailing)
+        );
+    }
+
+    #[test]
+    fn test_PT2_from_str_rejects_invalid_parameters() {
+        assert_eq!(
+            "PT2(sample_time: -1, omega 0.5, damping 1, kp: 1)".parse::<PT2<f64>>(),
+            Err(ParseError::Value("sample_time"))
+        );
+        assert_eq!(
+            "PT2(sample_time: 1, omega 0, damping 1, kp: 1)".parse::<PT2<f64>>(),
+            Err(ParseError::Value("omega"))
+        );
+        assert_eq!(
+            "PT2(sample_time: 1, omega 0.5, damping -0.1, kp: 1)".parse::<PT2<f64>>(),
+            Err(ParseError::Value("damping"))
+        );
+    }
 }
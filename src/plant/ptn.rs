@@ -0,0 +1,251 @@
+//! A PTn element aka n-th order lag element
+//!
+//! A cascade of $n$ identical first order lags with time constant $T_{n}$:
+//!
+//! $ x_{1}[k] = x_{1}[k-1] + \alpha (P \cdot in[k] - x_{1}[k-1]) $
+//!
+//! $ x_{i}[k] = x_{i}[k-1] + \alpha (x_{i-1}[k] - x_{i}[k-1]) $, $ out[k] = x_{n}[k] $
+//!
+//! where $\alpha =\frac{T_{s}}{T_{n}}$
+//! and $T_{s}$ is the sample time constant
+//! and $P$ is the amplification
+//! Euler forward method
+//!
+//! Third and fourth order lags model e.g. thermal processes with several
+//! storage masses without wiring PT1 elements by hand.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use cb_simulation_util::plant::TransferTimeDomain;
+//! use cb_simulation_util::plant::ptn::PTn;
+//!
+//! fn main() {
+//!     let mut sut = PTn::<f64>::default()
+//!         .set_order_or_default(3)
+//!         .set_tn_time_or_default(2.0);
+//!     assert_eq!(sut.transfer_td(1.0), 0.125);
+//!     assert_eq!(sut.state(), &[0.5, 0.25, 0.125]);
+//! }
+//! ```
+
+use num_traits::Zero;
+use std::vec;
+use std::vec::Vec;
+
+use super::snapshot::StateAccess;
+use super::*;
+use core::fmt::{self, Display};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PTn<N> {
+    pub tn_time: f64,
+    pub sample_time: f64,
+    pub kp: N,
+    stages: Vec<N>,
+}
+
+impl<N: PartialOrd + Zero + Clone> PTn<N> {
+    pub fn set_sample_time_or_default(self, sample_time: f64) -> Self {
+        if sample_time > 0.0 {
+            PTn::<N> {
+                sample_time,
+                ..self
+            }
+        } else {
+            PTn::<N> {
+                sample_time: 1.0,
+                ..self
+            }
+        }
+    }
+
+    /// Set the time constant of each stage
+    ///
+    /// - it must be greater than or equal to the sample time
+    pub fn set_tn_time_or_default(self, tn_time: f64) -> Self {
+        if tn_time >= self.sample_time {
+            PTn::<N> { tn_time, ..self }
+        } else {
+            PTn::<N> {
+                tn_time: self.sample_time,
+                ..self
+            }
+        }
+    }
+
+    /// Set the order, i.e. the number of stages; at least 1
+    ///
+    /// Resets the internal state.
+    pub fn set_order_or_default(self, order: usize) -> Self {
+        PTn::<N> {
+            stages: vec![N::zero(); order.max(1)],
+            ..self
+        }
+    }
+}
+
+impl<N> PTn<N> {
+    pub fn order(&self) -> usize {
+        self.stages.len()
+    }
+
+    /// The internal state: the outputs of all stages, the last is the output
+    pub fn state(&self) -> &[N] {
+        &self.stages
+    }
+}
+
+const FIX_KOMMA_SHIFT_BITS: u8 = 10;
+const FIX_KOMMA_SHIFT: i32 = 1 << FIX_KOMMA_SHIFT_BITS;
+
+impl PTn<i32> {
+    // alpha is fixed point with 10 bits after the comma
+    fn alpha(&self) -> i64 {
+        (self.sample_time * FIX_KOMMA_SHIFT as f64 / self.tn_time) as i64
+    }
+
+    pub fn set_kp(self, kp: i32) -> Self {
+        PTn::<i32> {
+            kp: kp * FIX_KOMMA_SHIFT,
+            ..self
+        }
+    }
+}
+
+impl Default for PTn<i32> {
+    fn default() -> Self {
+        PTn::<i32> {
+            tn_time: 1.0,
+            sample_time: 1.0,
+            kp: FIX_KOMMA_SHIFT,
+            stages: vec![0; 2],
+        }
+    }
+}
+
+impl TransferTimeDomain<i32> for PTn<i32> {
+    fn transfer_td(&mut self, input: i32) -> i32 {
+        // stage outputs are kept with 10 bits after the comma
+        let alpha = self.alpha();
+        let mut signal = input as i64 * self.kp as i64;
+        for stage in self.stages.iter_mut() {
+            let previous = *stage as i64;
+            signal = previous + ((alpha * (signal - previous)) >> FIX_KOMMA_SHIFT_BITS);
+            *stage = signal.clamp(i32::MIN as i64, i32::MAX as i64) as i32;
+            signal = *stage as i64;
+        }
+        (signal >> FIX_KOMMA_SHIFT_BITS) as i32
+    }
+}
+
+impl PTn<f64> {
+    fn alpha(&self) -> f64 {
+        self.sample_time / self.tn_time
+    }
+
+    pub fn set_kp(self, kp: f64) -> Self {
+        PTn::<f64> { kp, ..self }
+    }
+}
+
+impl Default for PTn<f64> {
+    fn default() -> Self {
+        PTn::<f64> {
+            tn_time: 1.0,
+            sample_time: 1.0,
+            kp: 1.0,
+            stages: vec![0.0; 2],
+        }
+    }
+}
+
+impl TransferTimeDomain<f64> for PTn<f64> {
+    fn transfer_td(&mut self, input: f64) -> f64 {
+        let alpha = self.alpha();
+        let mut signal = input * self.kp;
+        for stage in self.stages.iter_mut() {
+            *stage += alpha * (signal - *stage);
+            signal = *stage;
+        }
+        signal
+    }
+}
+
+impl<N: Clone + fmt::Debug + PartialEq> StateAccess for PTn<N> {
+    /// Stage outputs
+    type State = Vec<N>;
+
+    fn save_state(&self) -> Self::State {
+        self.stages.clone()
+    }
+
+    fn restore_state(&mut self, state: &Self::State) {
+        if state.len() == self.stages.len() {
+            self.stages.clone_from(state);
+        }
+    }
+}
+
+impl<N> TypeIdentifier for PTn<N> {
+    fn short_type_name(&self) -> &'static str {
+        "PTn"
+    }
+}
+
+impl<N> SampleTime for PTn<N> {
+    fn sample_time(&self) -> Option<f64> {
+        Some(self.sample_time)
+    }
+}
+
+impl<N: Display> Display for PTn<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "PTn(sample_time: {}, tn_time {}, order: {}, kp: {})",
+            self.sample_time,
+            self.tn_time,
+            self.stages.len(),
+            self.kp
+        )
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::plant::pt1::PT1;
+
+    #[test]
+    fn test_PTn_order_one_equals_PT1() {
+        let mut sut = PTn::<f64>::default()
+            .set_order_or_default(0)
+            .set_sample_time_or_default(0.5)
+            .set_tn_time_or_default(3.0)
+            .set_kp(2.0);
+        let mut pt1 = PT1::<f64>::default()
+            .set_sample_time_or_default(0.5)
+            .set_t1_time_or_default(3.0)
+            .set_kp(2.0);
+        assert_eq!(sut.order(), 1);
+        for _ in 0..20 {
+            assert_eq!(sut.transfer_td(1.0), pt1.transfer_td(1.0));
+        }
+    }
+
+    #[test]
+    fn test_PTn_i32_steady_state() {
+        let mut sut = PTn::<i32>::default()
+            .set_order_or_default(4)
+            .set_tn_time_or_default(4.0)
+            .set_kp(3);
+        let mut y = 0;
+        for _ in 0..200 {
+            y = sut.transfer_td(100);
+        }
+        assert!((299..=300).contains(&y));
+    }
+}
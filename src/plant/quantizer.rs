@@ -0,0 +1,353 @@
+//! A quantizer, e.g. an ADC or DAC of finite resolution
+//!
+//! $ out[k] = o + q \cdot round((in[k] - o) / q) $
+//!
+//! with the step size $q$, the offset $o$ of the codes and a selectable
+//! rounding mode. The output is a multiple of $q$ away from $o$, an optional
+//! range clamps it like a converter at the ends of its full scale.
+//!
+//! `from_bits` derives the step from the resolution in bits and the full
+//! scale range, $ q = (max - min) / 2^{bits} $, the output is clamped to the
+//! $2^{bits}$ codes $ min, min + q, \dots, max - q $, so the codes start at
+//! $min$ even if it is no multiple of $q$.
+//!
+//! A coarse quantizer inside a loop causes limit cycles: the error is a
+//! deterministic function of the input. `set_dither` adds pseudo random
+//! noise before the rounding, which turns the error into noise independent
+//! of the input, on average the output follows the input between the codes:
+//!
+//! * `Dither::Uniform`: uniform in $ \pm q/2 $, the mean error vanishes
+//! * `Dither::Triangular`: triangular in $ \pm q $, the error variance is
+//!   independent of the input as well
+//!
+//! ## Example
+//!
+//! ```rust
+//! use cb_simulation_util::plant::TransferTimeDomain;
+//! use cb_simulation_util::plant::quantizer::{Dither, Quantizer, Rounding};
+//!
+//! fn main() {
+//!     // 8 bit ADC over 0..5.12 V: 20 mV per code
+//!     let mut adc = Quantizer::from_bits(8, 0.0, 5.12).unwrap().set_rounding(Rounding::Floor);
+//!     assert!((adc.transfer_td(1.239) - 1.22).abs() < 1e-12);
+//!     assert!((adc.transfer_td(6.0) - 5.10).abs() < 1e-12);
+//!     assert_eq!(adc.transfer_td(-1.0), 0.0);
+//!
+//!     // a DAC of 0.1 resolution, dithered to resolve 0.025 on average
+//!     let mut dac = Quantizer::default().set_step(0.1).unwrap().set_dither(Dither::Triangular, 7);
+//!     let mean = (0..10000).map(|_| dac.transfer_td(0.025)).sum::<f64>() / 10000.0;
+//!     assert!((mean - 0.025).abs() < 0.002);
+//! }
+//! ```
+
+use super::snapshot::StateAccess;
+use super::*;
+use crate::rng;
+use core::fmt::{self, Display};
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Rounding {
+    /// To the nearest step, halfway away from zero
+    #[default]
+    Nearest,
+    /// Down to the next step (truncation of two's complement converters)
+    Floor,
+    /// Toward zero (truncation of sign-magnitude converters)
+    TowardZero,
+}
+
+/// Noise added before the rounding, relative to the step size
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Dither {
+    #[default]
+    Off,
+    /// Uniform in `[-step/2, step/2)`
+    Uniform,
+    /// Triangular in `(-step, step)`, the sum of two uniform values
+    Triangular,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Quantizer<N> {
+    pub step: N,
+    /// Origin of the codes, the output is `offset + code * step`
+    pub offset: N,
+    pub rounding: Rounding,
+    /// Lowest and highest output code
+    pub range: Option<(N, N)>,
+    pub dither: Dither,
+    pub seed: u64,
+    /// Number of dither samples drawn so far
+    samples: u64,
+}
+
+impl<N: Copy + PartialOrd + num_traits::Zero> Quantizer<N> {
+    pub fn set_step(self, step: N) -> Result<Self, &'static str> {
+        if step > N::zero() {
+            Ok(Quantizer { step, ..self })
+        } else {
+            Err("Invalid step: Must be > 0")
+        }
+    }
+
+    pub fn set_offset(self, offset: N) -> Self {
+        Quantizer { offset, ..self }
+    }
+
+    pub fn set_rounding(self, rounding: Rounding) -> Self {
+        Quantizer { rounding, ..self }
+    }
+
+    /// Clamp the output to `[min, max]`, the limits are swapped if `min > max`
+    pub fn set_range(self, min: N, max: N) -> Self {
+        let range = if min > max { (max, min) } else { (min, max) };
+        Quantizer {
+            range: Some(range),
+            ..self
+        }
+    }
+
+    fn clamp(&self, value: N) -> N {
+        match self.range {
+            Some((min, _)) if value < min => min,
+            Some((_, max)) if value > max => max,
+            _ => value,
+        }
+    }
+}
+
+impl Quantizer<f64> {
+    /// Converter with `bits` resolution over the full scale `min..max`
+    pub fn from_bits(bits: u32, min: f64, max: f64) -> Result<Self, &'static str> {
+        if !(1..=52).contains(&bits) {
+            return Err("Invalid bits: Must be within 1..=52");
+        }
+        if min >= max {
+            return Err("Invalid range: min must be < max");
+        }
+        let step = (max - min) / (1u64 << bits) as f64;
+        Ok(Quantizer::default()
+            .set_step(step)?
+            .set_offset(min)
+            .set_range(min, max - step))
+    }
+
+    /// Add `dither` before the rounding, `seed` restarts its sequence
+    pub fn set_dither(self, dither: Dither, seed: u64) -> Self {
+        Quantizer {
+            dither,
+            seed,
+            samples: 0,
+            ..self
+        }
+    }
+
+    /// Next dither sample in units of the step
+    fn next_dither(&mut self) -> f64 {
+        let bits = rng::mix(self.seed ^ rng::mix(self.samples));
+        self.samples += 1;
+        match self.dither {
+            Dither::Off => 0.0,
+            Dither::Uniform => rng::unit(bits) - 0.5,
+            Dither::Triangular => rng::unit(bits) - rng::unit(rng::mix(bits)),
+        }
+    }
+}
+
+impl Default for Quantizer<f64> {
+    /// Step 1, rounding to nearest, unlimited
+    fn default() -> Self {
+        Quantizer {
+            step: 1.0,
+            offset: 0.0,
+            rounding: Rounding::Nearest,
+            range: None,
+            dither: Dither::Off,
+            seed: 0,
+            samples: 0,
+        }
+    }
+}
+
+impl Default for Quantizer<i32> {
+    /// Step 1 (no effect), rounding to nearest, unlimited
+    fn default() -> Self {
+        Quantizer {
+            step: 1,
+            offset: 0,
+            rounding: Rounding::Nearest,
+            range: None,
+            dither: Dither::Off,
+            seed: 0,
+            samples: 0,
+        }
+    }
+}
+
+impl<N> StateAccess for Quantizer<N> {
+    /// Number of dither samples drawn, the position in the random sequence
+    type State = u64;
+
+    fn save_state(&self) -> Self::State {
+        self.samples
+    }
+
+    fn restore_state(&mut self, state: &Self::State) {
+        self.samples = *state;
+    }
+}
+
+impl<N> TypeIdentifier for Quantizer<N> {
+    fn short_type_name(&self) -> &'static str {
+        "Quantizer"
+    }
+}
+
+impl<N> SampleTime for Quantizer<N> {
+    fn sample_time(&self) -> Option<f64> {
+        None
+    }
+}
+
+impl<N: Display + PartialEq + num_traits::Zero> Display for Quantizer<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Quantizer(step: {}, rounding: {:?}",
+            self.step, self.rounding
+        )?;
+        if !self.offset.is_zero() {
+            write!(f, ", offset: {}", self.offset)?;
+        }
+        if let Some((min, max)) = &self.range {
+            write!(f, ", range: [{}, {}]", min, max)?;
+        }
+        if self.dither != Dither::Off {
+            write!(f, ", dither: {:?}, seed: {}", self.dither, self.seed)?;
+        }
+        write!(f, ")")
+    }
+}
+
+impl TransferTimeDomain<f64> for Quantizer<f64> {
+    fn transfer_td(&mut self, input: f64) -> f64 {
+        let codes = (input - self.offset) / self.step + self.next_dither();
+        let code = match self.rounding {
+            Rounding::Nearest => codes.round(),
+            Rounding::Floor => codes.floor(),
+            Rounding::TowardZero => codes.trunc(),
+        };
+        self.clamp(self.offset + code * self.step)
+    }
+}
+
+impl TransferTimeDomain<i32> for Quantizer<i32> {
+    fn transfer_td(&mut self, input: i32) -> i32 {
+        let offset = input as i64 - self.offset as i64;
+        let step = self.step as i64;
+        let code = match self.rounding {
+            Rounding::Nearest => {
+                let half = step / 2;
+                if offset >= 0 {
+                    (offset + half).div_euclid(step)
+                } else {
+                    -((-offset + half).div_euclid(step))
+                }
+            }
+            Rounding::Floor => offset.div_euclid(step),
+            Rounding::TowardZero => offset / step,
+        };
+        let value =
+            (self.offset as i64 + code * step).clamp(i32::MIN as i64, i32::MAX as i64) as i32;
+        self.clamp(value)
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use std::format;
+
+    #[test]
+    fn test_Quantizer_f64_rounding_modes() {
+        let sut = Quantizer::<f64>::default().set_step(0.5).unwrap();
+        let cases = [
+            (Rounding::Nearest, -0.8, -1.0),
+            (Rounding::Floor, -0.2, -0.5),
+            (Rounding::TowardZero, -0.7, -0.5),
+            (Rounding::TowardZero, 0.7, 0.5),
+        ];
+        for (rounding, input, expected) in cases {
+            assert_eq!(sut.set_rounding(rounding).transfer_td(input), expected);
+        }
+        assert!(sut.set_step(0.0).is_err());
+    }
+
+    #[test]
+    fn test_Quantizer_i32_step_and_range() {
+        let mut sut = Quantizer::<i32>::default()
+            .set_step(16)
+            .unwrap()
+            .set_range(-128, 112);
+        assert_eq!(sut.transfer_td(7), 0);
+        assert_eq!(sut.transfer_td(8), 16);
+        assert_eq!(sut.transfer_td(-8), -16);
+        assert_eq!(sut.transfer_td(1000), 112);
+        assert_eq!(sut.set_rounding(Rounding::Floor).transfer_td(-1), -16);
+        assert_eq!(
+            format!("{}", sut),
+            "Quantizer(step: 16, rounding: Nearest, range: [-128, 112])"
+        );
+    }
+
+    #[test]
+    fn test_Quantizer_dither_resolves_below_step() {
+        let mean = |dither: Dither| {
+            let mut sut = Quantizer::<f64>::default().set_dither(dither, 3);
+            (0..20000).map(|_| sut.transfer_td(0.3)).sum::<f64>() / 20000.0
+        };
+        assert_eq!(mean(Dither::Off), 0.0);
+        assert!((mean(Dither::Uniform) - 0.3).abs() < 0.01);
+        assert!((mean(Dither::Triangular) - 0.3).abs() < 0.01);
+        // triangular dither spreads over the neighbouring codes only
+        let mut sut = Quantizer::<f64>::default().set_dither(Dither::Triangular, 3);
+        assert!((0..1000).all(|_| (-1.0..=2.0).contains(&sut.transfer_td(0.5))));
+        let replay = Quantizer::<f64>::default().set_dither(Dither::Triangular, 3);
+        assert_ne!(sut, replay);
+        assert_eq!(
+            format!("{}", replay),
+            "Quantizer(step: 1, rounding: Nearest, dither: Triangular, seed: 3)"
+        );
+    }
+
+    #[test]
+    fn test_Quantizer_from_bits() {
+        let sut = Quantizer::from_bits(12, -10.0, 10.0).unwrap();
+        assert_eq!(sut.step, 20.0 / 4096.0);
+        assert!(Quantizer::from_bits(0, 0.0, 1.0).is_err());
+        assert!(Quantizer::from_bits(8, 1.0, 1.0).is_err());
+    }
+
+    #[test]
+    fn test_Quantizer_from_bits_unaligned_range() {
+        // 0.3..1.3 is no multiple of the step 0.25
+        let mut sut = Quantizer::from_bits(2, 0.3, 1.3).unwrap();
+        let codes = [0.3, 0.55, 0.8, 1.05];
+        for (input, expected) in [(0.3, 0), (0.4, 0), (0.45, 1), (0.9, 2), (1.2, 3), (5.0, 3)] {
+            assert!((sut.transfer_td(input) - codes[expected]).abs() < 1e-12);
+        }
+        assert_eq!(sut.transfer_td(-1.0), 0.3);
+        let mut sut = sut.set_rounding(Rounding::Floor);
+        assert!((sut.transfer_td(0.79) - 0.55).abs() < 1e-12);
+        assert!(format!("{}", sut).contains(", offset: 0.3,"));
+        let mut sut = Quantizer::<i32>::default()
+            .set_step(16)
+            .unwrap()
+            .set_offset(5);
+        assert_eq!(sut.transfer_td(12), 5);
+        assert_eq!(sut.transfer_td(13), 21);
+        assert_eq!(sut.transfer_td(-3), -11);
+    }
+}
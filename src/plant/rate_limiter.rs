@@ -0,0 +1,188 @@
+//! A rate limiter, e.g. the slew constraint of an actuator
+//!
+//! $ out[k] = out[k-1] + \min(\max(in[k] - out[k-1], -r_{fall} T_s), r_{rise} T_s) $
+//!
+//! and $T_{s}$ is the sample time constant
+//! and $r_{rise}$, $r_{fall}$ are the rising and falling rate limits per time unit
+//!
+//! In series with a PT1 or PT2 it represents e.g. a valve drive that cannot
+//! move faster than its motor allows.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use cb_simulation_util::plant::TransferTimeDomain;
+//! use cb_simulation_util::plant::rate_limiter::RateLimiter;
+//!
+//! fn main() {
+//!     let mut drive = RateLimiter::<f64>::default()
+//!         .set_sample_time_or_default(0.5)
+//!         .set_rates(10.0, 20.0)
+//!         .unwrap();
+//!     assert_eq!(drive.transfer_td(100.0), 5.0);
+//!     assert_eq!(drive.transfer_td(100.0), 10.0);
+//!     assert_eq!(drive.transfer_td(0.0), 0.0);
+//! }
+//! ```
+
+use super::snapshot::StateAccess;
+use super::*;
+use core::fmt::{self, Display};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimiter<N> {
+    pub sample_time: f64,
+    /// Maximum increase per time unit, > 0
+    pub rising_rate: f64,
+    /// Maximum decrease per time unit, > 0
+    pub falling_rate: f64,
+    previous_output: N,
+}
+
+impl<N: Copy> RateLimiter<N> {
+    pub fn set_sample_time_or_default(self, sample_time: f64) -> Self {
+        if sample_time > 0.0 {
+            RateLimiter {
+                sample_time,
+                ..self
+            }
+        } else {
+            RateLimiter {
+                sample_time: 1.0,
+                ..self
+            }
+        }
+    }
+
+    pub fn set_rates(self, rising_rate: f64, falling_rate: f64) -> Result<Self, &'static str> {
+        if rising_rate > 0.0 && falling_rate > 0.0 {
+            Ok(RateLimiter {
+                rising_rate,
+                falling_rate,
+                ..self
+            })
+        } else {
+            Err("Invalid rates: Must be > 0.0")
+        }
+    }
+
+    /// Start from `output`, e.g. the actuator position at simulation start
+    pub fn reset(self, output: N) -> Self {
+        RateLimiter {
+            previous_output: output,
+            ..self
+        }
+    }
+
+    pub fn state(&self) -> N {
+        self.previous_output
+    }
+}
+
+impl Default for RateLimiter<f64> {
+    /// Unlimited
+    fn default() -> Self {
+        RateLimiter {
+            sample_time: 1.0,
+            rising_rate: f64::INFINITY,
+            falling_rate: f64::INFINITY,
+            previous_output: 0.0,
+        }
+    }
+}
+
+impl Default for RateLimiter<i32> {
+    /// Unlimited
+    fn default() -> Self {
+        RateLimiter {
+            sample_time: 1.0,
+            rising_rate: f64::INFINITY,
+            falling_rate: f64::INFINITY,
+            previous_output: 0,
+        }
+    }
+}
+
+impl<N: Copy + fmt::Debug + PartialEq> StateAccess for RateLimiter<N> {
+    /// Previous output
+    type State = N;
+
+    fn save_state(&self) -> Self::State {
+        self.previous_output
+    }
+
+    fn restore_state(&mut self, state: &Self::State) {
+        self.previous_output = *state;
+    }
+}
+
+impl<N> TypeIdentifier for RateLimiter<N> {
+    fn short_type_name(&self) -> &'static str {
+        "RateLimiter"
+    }
+}
+
+impl<N> SampleTime for RateLimiter<N> {
+    fn sample_time(&self) -> Option<f64> {
+        Some(self.sample_time)
+    }
+}
+
+impl<N> Display for RateLimiter<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "RateLimiter(sample_time: {}, rising_rate: {}, falling_rate: {})",
+            self.sample_time, self.rising_rate, self.falling_rate
+        )
+    }
+}
+
+impl TransferTimeDomain<f64> for RateLimiter<f64> {
+    fn transfer_td(&mut self, input: f64) -> f64 {
+        let delta = (input - self.previous_output).clamp(
+            -self.falling_rate * self.sample_time,
+            self.rising_rate * self.sample_time,
+        );
+        self.previous_output += delta;
+        self.previous_output
+    }
+}
+
+impl TransferTimeDomain<i32> for RateLimiter<i32> {
+    fn transfer_td(&mut self, input: i32) -> i32 {
+        // at least one LSB per sample, otherwise small rates would freeze the output
+        let rise = (self.rising_rate * self.sample_time).max(1.0);
+        let fall = (self.falling_rate * self.sample_time).max(1.0);
+        let delta = (input as i64 - self.previous_output as i64) as f64;
+        self.previous_output += delta.clamp(-fall, rise) as i32;
+        self.previous_output
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_RateLimiter_i32_ramps() {
+        let mut sut = RateLimiter::<i32>::default()
+            .set_sample_time_or_default(0.1)
+            .set_rates(100.0, 100.0)
+            .unwrap()
+            .reset(50);
+        assert_eq!(sut.transfer_td(100), 60);
+        assert_eq!(sut.transfer_td(100), 70);
+        assert_eq!(sut.transfer_td(65), 65);
+        assert_eq!(sut.transfer_td(i32::MIN), 55);
+    }
+
+    #[test]
+    fn test_RateLimiter_default_unlimited() {
+        let mut sut = RateLimiter::<f64>::default();
+        assert_eq!(sut.transfer_td(1.0e6), 1.0e6);
+        assert!(sut.set_rates(0.0, 1.0).is_err());
+    }
+}
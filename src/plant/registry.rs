@@ -0,0 +1,321 @@
+//! # Plant element registry
+//!
+//! Builds boxed elements from their `short_type_name` and a list of named
+//! parameters, e.g. from a GUI or a configuration file. The parameter names
+//! are the field names of the elements, missing parameters keep the value
+//! of the `Default` element. Unknown parameters are rejected, so a typo
+//! does not silently fall back to a default.
+//!
+//! `PlantRegistry::builtin` knows the common elements of this crate,
+//! `register` adds user defined ones.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use cb_simulation_util::plant::registry::PlantRegistry;
+//!
+//! fn main() {
+//!     let registry = PlantRegistry::builtin();
+//!     let mut lag = registry
+//!         .build("PT1", &[("sample_time", 0.5), ("t1_time", 2.0), ("kp", 3.0)])
+//!         .unwrap();
+//!     assert_eq!(lag.transfer_td(1.0), 0.75);
+//!     assert_eq!(lag.to_string(), "PT1(sample_time: 0.5, t1_time 2, kp: 3)");
+//!     assert!(registry.build("PT1", &[("t1", 2.0)]).is_err());
+//! }
+//! ```
+
+use core::fmt::{self, Display};
+use std::boxed::Box;
+use std::string::String;
+use std::vec;
+use std::vec::Vec;
+
+use super::backlash::Backlash;
+use super::dead_time::DeadTime;
+use super::dead_zone::DeadZone;
+use super::integrator::Integrator;
+use super::pt0::PT0;
+use super::pt1::PT1;
+use super::pt2::PT2;
+use super::ptn::PTn;
+use super::quantizer::Quantizer;
+use super::rate_limiter::RateLimiter;
+use super::saturation::Saturation;
+use super::stiction::Stiction;
+use super::*;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum BuildError {
+    /// No element is registered under the name
+    UnknownType(String),
+    /// The element has no parameter of the name
+    UnknownParameter { element: &'static str, name: String },
+    /// A parameter value was rejected by the element
+    Invalid(&'static str),
+}
+
+impl Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BuildError::UnknownType(name) => write!(f, "Unknown element type {}", name),
+            BuildError::UnknownParameter { element, name } => {
+                write!(f, "Unknown parameter {} of {}", name, element)
+            }
+            BuildError::Invalid(reason) => write!(f, "{}", reason),
+        }
+    }
+}
+
+/// Named parameters passed to a `Constructor`, records which were read
+pub struct Parameters<'a> {
+    values: &'a [(&'a str, f64)],
+    read: Vec<bool>,
+}
+
+impl<'a> Parameters<'a> {
+    /// Call `constructor` of `element` with `values`, fails on unread values
+    pub(crate) fn apply<T>(
+        element: &'static str,
+        values: &'a [(&'a str, f64)],
+        constructor: fn(&mut Parameters) -> Result<T, &'static str>,
+    ) -> Result<T, BuildError> {
+        let mut parameters = Parameters {
+            values,
+            read: vec![false; values.len()],
+        };
+        let built = constructor(&mut parameters).map_err(BuildError::Invalid)?;
+        match parameters.unread() {
+            Some(unknown) => Err(BuildError::UnknownParameter {
+                element,
+                name: String::from(unknown),
+            }),
+            None => Ok(built),
+        }
+    }
+
+    /// Value of the parameter `name`, the last one if given several times
+    pub fn get(&mut self, name: &str) -> Option<f64> {
+        let mut value = None;
+        for (i, (key, v)) in self.values.iter().enumerate() {
+            if *key == name {
+                self.read[i] = true;
+                value = Some(*v);
+            }
+        }
+        value
+    }
+
+    pub fn get_or(&mut self, name: &str, default: f64) -> f64 {
+        self.get(name).unwrap_or(default)
+    }
+
+    /// The first parameter which was not read
+    fn unread(&self) -> Option<&str> {
+        self.values
+            .iter()
+            .zip(&self.read)
+            .find(|(_, read)| !**read)
+            .map(|((key, _), _)| *key)
+    }
+}
+
+/// Builds an element from its parameters
+pub type Constructor = fn(&mut Parameters) -> Result<BoxedTransferTimeDomain<f64>, &'static str>;
+
+#[derive(Default)]
+pub struct PlantRegistry {
+    constructors: Vec<(&'static str, Constructor)>,
+}
+
+impl PlantRegistry {
+    /// Empty registry
+    pub fn new() -> Self {
+        PlantRegistry::default()
+    }
+
+    /// Registry with the common elements of this crate
+    pub fn builtin() -> Self {
+        let builtins: [(&'static str, Constructor); 12] = [
+            ("PT0", |p| {
+                let d = PT0::<f64>::default();
+                Ok(Box::new(
+                    d.set_sample_time_or_default(p.get_or("sample_time", d.sample_time))
+                        .set_t0_time_or_default(p.get_or("t0_time", d.t0_time))
+                        .set_kp(p.get_or("kp", d.kp)),
+                ))
+            }),
+            ("PT1", |p| {
+                let d = PT1::<f64>::default();
+                Ok(Box::new(
+                    d.set_sample_time_or_default(p.get_or("sample_time", d.sample_time))
+                        .set_t1_time_or_default(p.get_or("t1_time", d.t1_time))
+                        .set_kp(p.get_or("kp", d.kp)),
+                ))
+            }),
+            ("PT2", |p| {
+                let d = PT2::<f64>::default();
+                Ok(Box::new(
+                    d.set_sample_time_or_default(p.get_or("sample_time", d.sample_time))
+                        .set_omega_or_default(p.get_or("omega", d.omega))
+                        .set_damping_or_default(p.get_or("damping", d.damping))
+                        .set_kp(p.get_or("kp", d.kp)),
+                ))
+            }),
+            ("PTn", |p| {
+                let d = PTn::<f64>::default();
+                let order = p.get_or("order", d.order() as f64);
+                Ok(Box::new(
+                    d.clone()
+                        .set_sample_time_or_default(p.get_or("sample_time", d.sample_time))
+                        .set_tn_time_or_default(p.get_or("tn_time", d.tn_time))
+                        .set_order_or_default(order.max(0.0) as usize)
+                        .set_kp(p.get_or("kp", d.kp)),
+                ))
+            }),
+            ("DeadTime", |p| {
+                let d = DeadTime::<f64>::default();
+                Ok(Box::new(
+                    d.clone()
+                        .set_sample_time_or_default(p.get_or("sample_time", d.sample_time))
+                        .set_t0_time_or_default(p.get_or("t0_time", d.t0_time))
+                        .set_kp(p.get_or("kp", d.kp)),
+                ))
+            }),
+            ("Integrator", |p| {
+                let d = Integrator::<f64>::default();
+                let limits = (p.get("lower_limit"), p.get("upper_limit"));
+                Ok(Box::new(
+                    d.set_sample_time_or_default(p.get_or("sample_time", d.sample_time))
+                        .set_kp(p.get_or("kp", d.kp))
+                        .set_limits(limits.0, limits.1),
+                ))
+            }),
+            ("Saturation", |p| {
+                let d = Saturation::<f64>::default();
+                let (min, max) = (p.get_or("min", d.min), p.get_or("max", d.max));
+                Ok(Box::new(d.set_limits(min, max)?))
+            }),
+            ("DeadZone", |p| {
+                let d = DeadZone::<f64>::default();
+                let (lower, upper) = (p.get_or("lower", d.lower), p.get_or("upper", d.upper));
+                Ok(Box::new(d.set_band(lower, upper)?))
+            }),
+            ("RateLimiter", |p| {
+                let d = RateLimiter::<f64>::default();
+                let rising = p.get_or("rising_rate", d.rising_rate);
+                let falling = p.get_or("falling_rate", d.falling_rate);
+                Ok(Box::new(
+                    d.set_sample_time_or_default(p.get_or("sample_time", d.sample_time))
+                        .set_rates(rising, falling)?,
+                ))
+            }),
+            ("Backlash", |p| {
+                let d = Backlash::default();
+                Ok(Box::new(d.set_width_or_default(p.get_or("width", d.width))))
+            }),
+            ("Stiction", |p| {
+                let d = Stiction::default();
+                let stickband = p.get_or("stickband", d.stickband);
+                let slip_jump = p.get_or("slip_jump", d.slip_jump);
+                Ok(Box::new(d.set_parameters(stickband, slip_jump)?))
+            }),
+            ("Quantizer", |p| {
+                let d = Quantizer::<f64>::default();
+                Ok(Box::new(d.set_step(p.get_or("step", d.step))?))
+            }),
+        ];
+        PlantRegistry {
+            constructors: builtins.to_vec(),
+        }
+    }
+
+    /// Add an element type, fails if the name is already taken
+    ///
+    /// `name` should be the `short_type_name` of the built elements.
+    pub fn register(
+        &mut self,
+        name: &'static str,
+        constructor: Constructor,
+    ) -> Result<(), &'static str> {
+        if self.constructor(name).is_some() {
+            return Err("Element name already registered");
+        }
+        self.constructors.push((name, constructor));
+        Ok(())
+    }
+
+    fn constructor(&self, name: &str) -> Option<&(&'static str, Constructor)> {
+        self.constructors.iter().find(|(n, _)| *n == name)
+    }
+
+    /// Build the element `name` from `(parameter, value)` pairs
+    pub fn build(
+        &self,
+        name: &str,
+        parameters: &[(&str, f64)],
+    ) -> Result<BoxedTransferTimeDomain<f64>, BuildError> {
+        let (element, constructor) = self
+            .constructor(name)
+            .ok_or_else(|| BuildError::UnknownType(String::from(name)))?;
+        Parameters::apply(element, parameters, *constructor)
+    }
+
+    /// Names in registration order
+    pub fn names(&self) -> Vec<&'static str> {
+        self.constructors.iter().map(|(name, _)| *name).collect()
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_PlantRegistry_builtins_match_short_type_name() {
+        let registry = PlantRegistry::builtin();
+        for name in registry.names() {
+            let element = registry.build(name, &[]).unwrap();
+            assert_eq!(element.short_type_name(), name);
+        }
+    }
+
+    #[test]
+    fn test_PlantRegistry_parameters_and_errors() {
+        let registry = PlantRegistry::builtin();
+        let mut limit = registry
+            .build("Saturation", &[("min", -1.0), ("max", 1.0), ("max", 2.0)])
+            .unwrap();
+        assert_eq!(limit.transfer_td(5.0), 2.0);
+        assert_eq!(
+            registry.build("PT3", &[]).err(),
+            Some(BuildError::UnknownType(String::from("PT3")))
+        );
+        assert_eq!(
+            registry
+                .build("PT2", &[("omega", 2.0), ("zeta", 0.5)])
+                .err(),
+            Some(BuildError::UnknownParameter {
+                element: "PT2",
+                name: String::from("zeta")
+            })
+        );
+        assert!(matches!(
+            registry.build("Quantizer", &[("step", 0.0)]),
+            Err(BuildError::Invalid(_))
+        ));
+    }
+
+    #[test]
+    fn test_PlantRegistry_register() {
+        let mut sut = PlantRegistry::new();
+        let gain: Constructor = |p| Ok(Box::new(PT0::<f64>::default().set_kp(p.get_or("k", 1.0))));
+        sut.register("Gain", gain).unwrap();
+        assert!(sut.register("Gain", gain).is_err());
+        let mut element = sut.build("Gain", &[("k", 4.0)]).unwrap();
+        assert_eq!(element.transfer_td(2.0), 8.0);
+        assert_eq!(sut.names(), vec!["Gain"]);
+    }
+}
@@ -0,0 +1,187 @@
+//! # Resampler
+//!
+//! Runs an element at its own sample time within a simulation of a different
+//! sample time. The element is stepped for every one of its sampling instants
+//! that falls into the current simulation step:
+//!
+//! * an element running slower than the simulation is stepped every few
+//!   simulation steps, its output is held in between
+//! * an element running faster is stepped several times per simulation step
+//!   with the input held constant
+//!
+//! The resampler itself uses the simulation sample time, so it passes
+//! `Simulation::check_sample_time` while the wrapped element keeps its
+//! coefficients, e.g. a 10 ms digital controller within a 1 ms plant model.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use cb_simulation_util::plant::TransferTimeDomain;
+//! use cb_simulation_util::plant::pt1::PT1;
+//! use cb_simulation_util::plant::resampler::Resampler;
+//!
+//! fn main() {
+//!     let slow = PT1::<f64>::default()
+//!         .set_sample_time_or_default(2.0)
+//!         .set_t1_time_or_default(4.0);
+//!     let mut sut = Resampler::new(slow, 1.0).unwrap();
+//!     assert_eq!(sut.transfer_td(1.0), 0.5);
+//!     assert_eq!(sut.transfer_td(1.0), 0.5);
+//!     assert_eq!(sut.transfer_td(1.0), 0.75);
+//! }
+//! ```
+
+use super::snapshot::StateAccess;
+use super::*;
+use core::fmt::{self, Display};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Resampler<E> {
+    pub sample_time: f64,
+    element: E,
+    element_sample_time: f64,
+    time: f64,
+    next_element_time: f64,
+    held: f64,
+}
+
+impl<E: TransferTimeDomain<f64> + SampleTime> Resampler<E> {
+    /// Run `element` within a simulation of `sample_time`
+    ///
+    /// Static elements without a sample time of their own are stepped once per
+    /// simulation step.
+    pub fn new(element: E, sample_time: f64) -> Result<Self, &'static str> {
+        if sample_time <= 0.0 {
+            return Err("Invalid sample_time: Must be > 0.0");
+        }
+        let element_sample_time = match element.sample_time() {
+            Some(ts) if ts > 0.0 => ts,
+            Some(_) => return Err("Invalid element sample_time: Must be > 0.0"),
+            None => sample_time,
+        };
+        Ok(Resampler {
+            sample_time,
+            element,
+            element_sample_time,
+            time: 0.0,
+            next_element_time: 0.0,
+            held: 0.0,
+        })
+    }
+
+    pub fn element(&self) -> &E {
+        &self.element
+    }
+
+    pub fn element_sample_time(&self) -> f64 {
+        self.element_sample_time
+    }
+
+    /// Unwrap the element
+    pub fn into_inner(self) -> E {
+        self.element
+    }
+}
+
+impl<E: StateAccess> StateAccess for Resampler<E> {
+    /// State of the element, the time, the time of its next sample and the
+    /// held output
+    type State = (E::State, f64, f64, f64);
+
+    fn save_state(&self) -> Self::State {
+        (
+            self.element.save_state(),
+            self.time,
+            self.next_element_time,
+            self.held,
+        )
+    }
+
+    fn restore_state(&mut self, state: &Self::State) {
+        self.element.restore_state(&state.0);
+        (self.time, self.next_element_time, self.held) = (state.1, state.2, state.3);
+    }
+}
+
+impl<E> TypeIdentifier for Resampler<E> {
+    fn short_type_name(&self) -> &'static str {
+        "Resampler"
+    }
+}
+
+impl<E> SampleTime for Resampler<E> {
+    fn sample_time(&self) -> Option<f64> {
+        Some(self.sample_time)
+    }
+}
+
+impl<E: Display> Display for Resampler<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Resampler(sample_time: {}, element_sample_time: {}, element: {})",
+            self.sample_time, self.element_sample_time, self.element
+        )
+    }
+}
+
+impl<E: TransferTimeDomain<f64>> TransferTimeDomain<f64> for Resampler<E> {
+    fn transfer_td(&mut self, input: f64) -> f64 {
+        let step_end = self.time + self.sample_time * (1.0 - 1e-9);
+        while self.next_element_time < step_end {
+            self.held = self.element.transfer_td(input);
+            self.next_element_time += self.element_sample_time;
+        }
+        self.time += self.sample_time;
+        self.held
+    }
+
+    fn output_unit(&self, input_unit: &'static str) -> &'static str {
+        self.element.output_unit(input_unit)
+    }
+
+    fn check_units(
+        &self,
+        input_unit: &'static str,
+    ) -> Result<&'static str, unit_gain::UnitCheckError> {
+        self.element.check_units(input_unit)
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::plant::integrator::Integrator;
+    use crate::plant::pt1::PT1;
+    use crate::plant::unit_gain::{Quantity, Unit, UnitGain};
+
+    #[test]
+    fn test_Resampler_fast_element_steps_several_times() {
+        let fast = PT1::<f64>::default()
+            .set_sample_time_or_default(0.25)
+            .set_t1_time_or_default(0.5);
+        let mut reference = fast;
+        let mut sut = Resampler::new(fast, 1.0).unwrap();
+        let mut expected = 0.0;
+        for _ in 0..4 {
+            expected = reference.transfer_td(1.0);
+        }
+        assert_eq!(sut.transfer_td(1.0), expected);
+    }
+
+    #[test]
+    fn test_Resampler_static_element_and_unit() {
+        let gain = UnitGain::convert(
+            Unit::new("bar", Quantity::Pressure, 1.0e5, 0.0),
+            Unit::new("Pa", Quantity::Pressure, 1.0, 0.0),
+        )
+        .unwrap();
+        let mut sut = Resampler::new(gain, 0.1).unwrap();
+        assert_eq!(sut.element_sample_time(), 0.1);
+        assert_eq!(sut.transfer_td(2.0), 200000.0);
+        assert_eq!(sut.output_unit("bar"), "Pa");
+        assert!(Resampler::new(Integrator::<f64>::default(), 0.0).is_err());
+    }
+}
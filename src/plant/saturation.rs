@@ -0,0 +1,156 @@
+//! A saturation element, e.g. the limits of an actuator
+//!
+//! $ out[k] = \min(\max(in[k], u_{min}), u_{max}) $
+//!
+//! A valve cannot open more than 100 %, a heater cannot cool. Inserting the
+//! limits into the plant chain shows windup and slow recovery in the
+//! simulation instead of approximating them outside of it.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use cb_simulation_util::plant::TransferTimeDomain;
+//! use cb_simulation_util::plant::saturation::Saturation;
+//!
+//! fn main() {
+//!     let mut valve = Saturation::<f64>::default().set_limits_or_default(0.0, 100.0);
+//!     assert_eq!(valve.transfer_td(120.0), 100.0);
+//!     assert!(valve.is_saturated());
+//!     assert_eq!(valve.transfer_td(-5.0), 0.0);
+//!     assert_eq!(valve.transfer_td(42.0), 42.0);
+//!     assert!(!valve.is_saturated());
+//! }
+//! ```
+
+use super::snapshot::StateAccess;
+use super::*;
+use core::fmt::{self, Display};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Saturation<N> {
+    pub min: N,
+    pub max: N,
+    saturated: bool,
+}
+
+impl<N: PartialOrd + Copy> Saturation<N> {
+    pub fn set_limits(self, min: N, max: N) -> Result<Self, &'static str> {
+        if min <= max {
+            Ok(Saturation { min, max, ..self })
+        } else {
+            Err("Invalid limits: min must be <= max")
+        }
+    }
+
+    /// Set the limits, they are swapped if `min > max`
+    pub fn set_limits_or_default(self, min: N, max: N) -> Self {
+        if min <= max {
+            Saturation { min, max, ..self }
+        } else {
+            Saturation {
+                min: max,
+                max: min,
+                ..self
+            }
+        }
+    }
+
+    /// Whether the last input was outside the limits
+    pub fn is_saturated(&self) -> bool {
+        self.saturated
+    }
+}
+
+impl Default for Saturation<f64> {
+    /// Unlimited
+    fn default() -> Self {
+        Saturation {
+            min: f64::NEG_INFINITY,
+            max: f64::INFINITY,
+            saturated: false,
+        }
+    }
+}
+
+impl Default for Saturation<i32> {
+    /// Unlimited
+    fn default() -> Self {
+        Saturation {
+            min: i32::MIN,
+            max: i32::MAX,
+            saturated: false,
+        }
+    }
+}
+
+impl<N> StateAccess for Saturation<N> {
+    /// Whether the previous output was limited
+    type State = bool;
+
+    fn save_state(&self) -> Self::State {
+        self.saturated
+    }
+
+    fn restore_state(&mut self, state: &Self::State) {
+        self.saturated = *state;
+    }
+}
+
+impl<N> TypeIdentifier for Saturation<N> {
+    fn short_type_name(&self) -> &'static str {
+        "Saturation"
+    }
+}
+
+impl<N> SampleTime for Saturation<N> {
+    fn sample_time(&self) -> Option<f64> {
+        None
+    }
+}
+
+impl<N: Display> Display for Saturation<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Saturation(min: {}, max: {})", self.min, self.max)
+    }
+}
+
+impl<N: PartialOrd + Copy> TransferTimeDomain<N> for Saturation<N> {
+    fn transfer_td(&mut self, input: N) -> N {
+        self.saturated = true;
+        if input < self.min {
+            self.min
+        } else if input > self.max {
+            self.max
+        } else {
+            self.saturated = false;
+            input
+        }
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use std::format;
+
+    #[test]
+    fn test_Saturation_i32() {
+        let mut sut = Saturation::<i32>::default().set_limits(-10, 10).unwrap();
+        assert_eq!(sut.transfer_td(-20), -10);
+        assert_eq!(sut.transfer_td(5), 5);
+        assert_eq!(sut.transfer_td(i32::MAX), 10);
+        assert!(Saturation::<i32>::default().set_limits(1, 0).is_err());
+    }
+
+    #[test]
+    fn test_Saturation_f64_default_unlimited() {
+        let mut sut = Saturation::<f64>::default();
+        assert_eq!(sut.transfer_td(1.0e300), 1.0e300);
+        assert!(!sut.is_saturated());
+        let sut = sut.set_limits_or_default(1.0, -1.0);
+        assert_eq!(format!("{}", sut), "Saturation(min: -1, max: 1)");
+    }
+}
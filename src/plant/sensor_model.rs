@@ -0,0 +1,295 @@
+//! A sensor model combining the typical errors of a measurement in one block
+//!
+//! $ out[k] = Q(in[j] + b + r \cdot j T_{s} + n[j]) $ with $ j = k - d $
+//!
+//! where $b$ is the bias, $r$ the drift rate of the offset per time unit,
+//! $n$ white gaussian noise, see `NoiseSource`, $Q$ an optional `Quantizer`
+//! and $ d = \lfloor T_{L} / T_{s} \rfloor $ the latency $T_{L}$ in samples,
+//! delayed by a `DeadTime`.
+//!
+//! The errors are applied in the order of a real sensor: offset and noise of
+//! the transducer, quantization of the converter, then the latency of the
+//! transmission. Every error is off by default, so the default model passes
+//! the input through.
+//!
+//! For `i32` the offset and the noise are rounded to whole counts.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use cb_simulation_util::plant::TransferTimeDomain;
+//! use cb_simulation_util::plant::quantizer::Quantizer;
+//! use cb_simulation_util::plant::sensor_model::SensorModel;
+//!
+//! fn main() {
+//!     // temperature sensor, 0.5 K bias drifting 1 K/s, 0.25 K resolution, 0.2 s latency
+//!     let mut sensor = SensorModel::<f64>::default()
+//!         .set_sample_time_or_default(0.1)
+//!         .set_bias(0.5)
+//!         .set_drift_rate(1.0)
+//!         .set_quantizer(Quantizer::default().set_step(0.25).unwrap())
+//!         .set_latency_or_default(0.2);
+//!     let out: Vec<f64> = (0..5).map(|_| sensor.transfer_td(20.0)).collect();
+//!     assert_eq!(out, [0.0, 0.0, 20.5, 20.5, 20.75]);
+//! }
+//! ```
+
+use std::collections::VecDeque;
+
+use super::dead_time::DeadTime;
+use super::noise_source::NoiseSource;
+use super::quantizer::Quantizer;
+use super::snapshot::StateAccess;
+use super::*;
+use core::fmt::{self, Display};
+use num_traits::Zero;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SensorModel<N> {
+    pub sample_time: f64,
+    /// Constant offset of the measurement
+    pub bias: N,
+    /// Change of the offset per time unit
+    pub drift_rate: f64,
+    noise: NoiseSource<N>,
+    quantizer: Option<Quantizer<N>>,
+    /// Time since the start, the drift grows with it
+    time: f64,
+    /// Latency of the measurement
+    delay: DeadTime<N>,
+}
+
+impl<N: Zero + Clone> SensorModel<N> {
+    /// Falls back to the default if invalid, see
+    /// `DeadTime::set_sample_time_or_default`
+    pub fn set_sample_time_or_default(self, sample_time: f64) -> Self {
+        let delay = self.delay.clone().set_sample_time_or_default(sample_time);
+        let sample_time = delay.sample_time;
+        SensorModel::<N> {
+            sample_time,
+            noise: self.noise.clone().set_sample_time_or_default(sample_time),
+            delay,
+            ..self
+        }
+    }
+
+    pub fn set_bias(self, bias: N) -> Self {
+        SensorModel::<N> { bias, ..self }
+    }
+
+    pub fn set_drift_rate(self, drift_rate: f64) -> Self {
+        SensorModel::<N> { drift_rate, ..self }
+    }
+
+    /// Set the variance of the noise, see `NoiseSource::set_variance`
+    pub fn set_noise_variance(self, variance: f64) -> Result<Self, &'static str> {
+        let noise = self.noise.clone().set_variance(variance)?;
+        Ok(SensorModel::<N> { noise, ..self })
+    }
+
+    /// Set the seed of the noise and restart its sequence
+    pub fn set_seed(self, seed: u64) -> Self {
+        SensorModel::<N> {
+            noise: self.noise.clone().set_seed(seed),
+            ..self
+        }
+    }
+
+    pub fn set_quantizer(self, quantizer: Quantizer<N>) -> Self {
+        SensorModel::<N> {
+            quantizer: Some(quantizer),
+            ..self
+        }
+    }
+
+    /// Falls back to no latency if invalid, see `DeadTime::set_t0_time_or_default`
+    pub fn set_latency_or_default(self, latency: f64) -> Self {
+        SensorModel::<N> {
+            delay: self.delay.clone().set_t0_time_or_default(latency),
+            ..self
+        }
+    }
+
+    /// Delay of the measurement in time units
+    pub fn latency(&self) -> f64 {
+        self.delay.t0_time
+    }
+
+    /// Number of samples a measurement is delayed
+    pub fn latency_samples(&self) -> usize {
+        self.delay.delay_samples()
+    }
+
+    /// Variance of the noise
+    pub fn noise_variance(&self) -> f64 {
+        self.noise.variance
+    }
+
+    /// Drift of the offset accumulated so far
+    pub fn drift(&self) -> f64 {
+        self.drift_rate * self.time
+    }
+
+    /// Delay the measured value by the latency
+    fn delayed(&mut self, measured: N) -> N {
+        self.time += self.sample_time;
+        self.delay.delay(measured)
+    }
+}
+
+impl Default for SensorModel<f64> {
+    /// No bias, drift, noise, quantization or latency
+    fn default() -> Self {
+        SensorModel::<f64> {
+            sample_time: 1.0,
+            bias: 0.0,
+            drift_rate: 0.0,
+            noise: NoiseSource::default(),
+            quantizer: None,
+            time: 0.0,
+            delay: DeadTime::default(),
+        }
+    }
+}
+
+impl Default for SensorModel<i32> {
+    /// No bias, drift, noise, quantization or latency
+    fn default() -> Self {
+        SensorModel::<i32> {
+            sample_time: 1.0,
+            bias: 0,
+            drift_rate: 0.0,
+            noise: NoiseSource::default(),
+            quantizer: None,
+            time: 0.0,
+            delay: DeadTime::default(),
+        }
+    }
+}
+
+impl<N: Clone + fmt::Debug + PartialEq> StateAccess for SensorModel<N> {
+    /// Noise and dither positions, the time since the start and the delay line
+    type State = (u64, Option<u64>, f64, VecDeque<N>);
+
+    fn save_state(&self) -> Self::State {
+        (
+            self.noise.save_state(),
+            self.quantizer.as_ref().map(|q| q.save_state()),
+            self.time,
+            self.delay.save_state(),
+        )
+    }
+
+    fn restore_state(&mut self, state: &Self::State) {
+        if state.3.len() <= self.delay.delay_samples() {
+            self.noise.restore_state(&state.0);
+            if let (Some(quantizer), Some(dither)) = (self.quantizer.as_mut(), state.1) {
+                quantizer.restore_state(&dither);
+            }
+            self.time = state.2;
+            self.delay.restore_state(&state.3);
+        }
+    }
+}
+
+impl<N> TypeIdentifier for SensorModel<N> {
+    fn short_type_name(&self) -> &'static str {
+        "SensorModel"
+    }
+}
+
+impl<N> SampleTime for SensorModel<N> {
+    fn sample_time(&self) -> Option<f64> {
+        Some(self.sample_time)
+    }
+}
+
+impl<N: Display + PartialEq + num_traits::Zero> Display for SensorModel<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "SensorModel(sample_time: {}, bias: {}, drift_rate: {}, noise_variance: {}, latency: {}",
+            self.sample_time, self.bias, self.drift_rate, self.noise.variance, self.delay.t0_time
+        )?;
+        if let Some(quantizer) = &self.quantizer {
+            write!(f, ", quantizer: {}", quantizer)?;
+        }
+        write!(f, ")")
+    }
+}
+
+impl TransferTimeDomain<f64> for SensorModel<f64> {
+    fn transfer_td(&mut self, input: f64) -> f64 {
+        let offset = self.bias + self.drift();
+        let mut measured = self.noise.transfer_td(input + offset);
+        if let Some(quantizer) = &mut self.quantizer {
+            measured = quantizer.transfer_td(measured);
+        }
+        self.delayed(measured)
+    }
+}
+
+impl TransferTimeDomain<i32> for SensorModel<i32> {
+    fn transfer_td(&mut self, input: i32) -> i32 {
+        let offset = self.bias as i64 + self.drift().round() as i64;
+        let shifted = (input as i64 + offset).clamp(i32::MIN as i64, i32::MAX as i64) as i32;
+        let mut measured = self.noise.transfer_td(shifted);
+        if let Some(quantizer) = &mut self.quantizer {
+            measured = quantizer.transfer_td(measured);
+        }
+        self.delayed(measured)
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use std::vec::Vec;
+
+    #[test]
+    fn test_SensorModel_default_passes_through() {
+        let mut sut = SensorModel::<f64>::default();
+        assert_eq!(sut.transfer_td(1.25), 1.25);
+        assert_eq!(sut.latency_samples(), 0);
+        let sut = SensorModel::<f64>::default().set_latency_or_default(f64::INFINITY);
+        assert_eq!((sut.latency(), sut.latency_samples()), (0.0, 0));
+    }
+
+    #[test]
+    fn test_SensorModel_i32_drift_and_latency() {
+        let mut sut = SensorModel::<i32>::default()
+            .set_bias(-10)
+            .set_drift_rate(2.5)
+            .set_latency_or_default(2.0)
+            .set_sample_time_or_default(2.0);
+        assert_eq!(sut.latency_samples(), 1);
+        assert_eq!(sut.latency(), 2.0);
+        let out: Vec<i32> = (0..4).map(|_| sut.transfer_td(100)).collect();
+        assert_eq!(out, [0, 90, 95, 100]);
+        assert_eq!(sut.drift(), 20.0);
+        let mut saturated = SensorModel::<i32>::default().set_bias(10);
+        assert_eq!(saturated.transfer_td(i32::MAX), i32::MAX);
+    }
+
+    #[test]
+    fn test_SensorModel_noise_reproducible() {
+        let sut = SensorModel::<f64>::default()
+            .set_noise_variance(0.04)
+            .unwrap()
+            .set_seed(5);
+        let run = |mut sut: SensorModel<f64>| -> Vec<f64> {
+            (0..2000).map(|_| sut.transfer_td(3.0)).collect()
+        };
+        let out = run(sut.clone());
+        assert_eq!(out, run(sut));
+        let variance = out.iter().map(|y| (y - 3.0).powi(2)).sum::<f64>() / out.len() as f64;
+        assert!((variance - 0.04).abs() < 0.004, "{}", variance);
+        assert!(
+            SensorModel::<f64>::default()
+                .set_noise_variance(-1.0)
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,320 @@
+//! # Series connection of elements
+//!
+//! Chains an ordered list of boxed elements, the output of each element is
+//! the input of the next one. The chain is an element itself, so e.g. a
+//! PT1 followed by a PT0 dead time can be simulated as one block.
+//!
+//! An empty series passes the input through, as does a bypassed element:
+//! `set_enabled` switches an element off at runtime, e.g. to compare a loop
+//! with and without its filter. A bypassed element keeps its state.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use cb_simulation_util::plant::TransferTimeDomain;
+//! use cb_simulation_util::plant::pt0::PT0;
+//! use cb_simulation_util::plant::pt1::PT1;
+//! use cb_simulation_util::plant::series::Series;
+//!
+//! fn main() {
+//!     let mut sut = Series::<f64>::default();
+//!     sut.push(Box::new(PT1::<f64>::default().set_t1_time_or_default(2.0)));
+//!     sut.push(Box::new(PT0::<f64>::default().set_t0_time_or_default(1.0)));
+//!     assert_eq!(sut.transfer_td(1.0), 0.0);
+//!     assert_eq!(sut.transfer_td(1.0), 0.5);
+//!     assert_eq!(sut.transfer_td(1.0), 0.75);
+//!     let lag = sut.position("PT1").unwrap();
+//!     sut.set_enabled(lag, false);
+//!     assert_eq!(sut.transfer_td(1.0), 0.875);
+//!     assert_eq!(sut.transfer_td(1.0), 1.0);
+//! }
+//! ```
+
+use super::snapshot::{BoxedState, StateAccess, restore_child_state, save_child_state};
+use super::*;
+use core::fmt::{self, Display};
+use std::vec;
+use std::vec::Vec;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Series<S: Debug + Display + Clone + Copy + Sized + 'static + Send + Sync> {
+    elements: Vec<BoxedTransferTimeDomain<S>>,
+    /// Per element, a disabled element passes its input through
+    enabled: Vec<bool>,
+}
+
+impl<S: Debug + Display + Clone + Copy + Sized + 'static + Send + Sync> Series<S> {
+    pub fn new(elements: Vec<BoxedTransferTimeDomain<S>>) -> Self {
+        let enabled = vec![true; elements.len()];
+        Series { elements, enabled }
+    }
+
+    /// Append an element at the end of the chain
+    pub fn push(&mut self, element: BoxedTransferTimeDomain<S>) {
+        self.elements.push(element);
+        self.enabled.push(true);
+    }
+
+    /// Insert an element at position `index`, shifting all following elements
+    ///
+    /// # Panics
+    /// If `index > len`
+    pub fn insert(&mut self, index: usize, element: BoxedTransferTimeDomain<S>) {
+        self.elements.insert(index, element);
+        self.enabled.insert(index, true);
+    }
+
+    /// Remove and return the element at position `index`
+    ///
+    /// # Panics
+    /// If `index` is out of bounds
+    pub fn remove(&mut self, index: usize) -> BoxedTransferTimeDomain<S> {
+        self.enabled.remove(index);
+        self.elements.remove(index)
+    }
+
+    /// Enable the element at position `index`, or bypass it with `false`
+    ///
+    /// # Panics
+    /// If `index` is out of bounds
+    pub fn set_enabled(&mut self, index: usize, enabled: bool) {
+        self.enabled[index] = enabled;
+    }
+
+    /// Whether the element at position `index` is enabled, `false` if out of bounds
+    pub fn is_enabled(&self, index: usize) -> bool {
+        self.enabled.get(index).copied().unwrap_or(false)
+    }
+
+    /// Position of the first element with the `short_type_name`
+    pub fn position(&self, short_type_name: &str) -> Option<usize> {
+        self.elements
+            .iter()
+            .position(|e| e.short_type_name() == short_type_name)
+    }
+
+    pub fn elements(&self) -> &[BoxedTransferTimeDomain<S>] {
+        &self.elements
+    }
+
+    pub fn elements_mut(&mut self) -> &mut [BoxedTransferTimeDomain<S>] {
+        &mut self.elements
+    }
+
+    pub fn len(&self) -> usize {
+        self.elements.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.elements.is_empty()
+    }
+}
+
+impl<S: Debug + Display + Clone + Copy + Sized + 'static + Send + Sync> Default for Series<S> {
+    fn default() -> Self {
+        Series {
+            elements: Vec::new(),
+            enabled: Vec::new(),
+        }
+    }
+}
+
+impl StateAccess for Series<f64> {
+    /// The states of the elements, `None` for an element without state access
+    type State = Vec<Option<BoxedState>>;
+
+    fn save_state(&self) -> Self::State {
+        self.elements
+            .iter()
+            .map(|e| save_child_state(&**e))
+            .collect()
+    }
+
+    /// Ignored for a different number of elements
+    fn restore_state(&mut self, state: &Self::State) {
+        if state.len() == self.elements.len() {
+            for (element, state) in self.elements.iter_mut().zip(state) {
+                restore_child_state(&mut **element, state);
+            }
+        }
+    }
+}
+
+impl<S: Debug + Display + Clone + Copy + Sized + 'static + Send + Sync> TypeIdentifier
+    for Series<S>
+{
+    fn short_type_name(&self) -> &'static str {
+        "Series"
+    }
+}
+
+impl<S: Debug + Display + Clone + Copy + Sized + 'static + Send + Sync> SampleTime for Series<S> {
+    /// Common sample time of the enabled elements
+    fn sample_time(&self) -> Option<f64> {
+        common_sample_time(
+            self.elements
+                .iter()
+                .zip(&self.enabled)
+                .filter(|(_, enabled)| **enabled)
+                .map(|(element, _)| element.sample_time()),
+        )
+    }
+}
+
+impl<S: Debug + Display + Clone + Copy + Sized + 'static + Send + Sync> Display for Series<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Series(")?;
+        for (i, element) in self.elements.iter().enumerate() {
+            if i > 0 {
+                write!(f, " -> ")?;
+            }
+            write!(f, "{}", element)?;
+            if !self.enabled[i] {
+                write!(f, " [bypassed]")?;
+            }
+        }
+        write!(f, ")")
+    }
+}
+
+impl<S: Debug + Display + Clone + Copy + Sized + 'static + Send + Sync> TransferTimeDomain<S>
+    for Series<S>
+{
+    fn transfer_td(&mut self, u: S) -> S {
+        self.elements
+            .iter_mut()
+            .zip(&self.enabled)
+            .filter(|(_, enabled)| **enabled)
+            .fold(u, |signal, (element, _)| element.transfer_td(signal))
+    }
+
+    fn output_unit(&self, input_unit: &'static str) -> &'static str {
+        self.elements
+            .iter()
+            .zip(&self.enabled)
+            .filter(|(_, enabled)| **enabled)
+            .fold(input_unit, |unit, (element, _)| element.output_unit(unit))
+    }
+
+    fn check_units(
+        &self,
+        input_unit: &'static str,
+    ) -> Result<&'static str, unit_gain::UnitCheckError> {
+        self.elements
+            .iter()
+            .zip(&self.enabled)
+            .filter(|(_, enabled)| **enabled)
+            .try_fold(input_unit, |unit, (element, _)| element.check_units(unit))
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::plant::pt0::PT0;
+    use crate::plant::pt1::PT1;
+    use crate::plant::unit_gain::{Quantity, Unit, UnitGain};
+    use std::format;
+
+    #[test]
+    fn test_Series_empty_passes_through() {
+        let mut sut = Series::<i32>::default();
+        assert_eq!(sut.transfer_td(42), 42);
+        assert_eq!(format!("{}", sut), "Series()");
+    }
+
+    #[test]
+    fn test_Series_insert_remove() {
+        let mut sut = Series::new(vec![
+            Box::new(PT0::<f64>::default().set_t0_time_or_default(1.0))
+                as BoxedTransferTimeDomain<f64>,
+        ]);
+        sut.insert(
+            0,
+            Box::new(
+                UnitGain::convert(
+                    Unit::new("bar", Quantity::Pressure, 1.0e5, 0.0),
+                    Unit::new("Pa", Quantity::Pressure, 1.0, 0.0),
+                )
+                .unwrap(),
+            ),
+        );
+        assert_eq!(sut.len(), 2);
+        assert_eq!(sut.output_unit("bar"), "Pa");
+        assert_eq!(sut.transfer_td(1.0), 0.0);
+        assert_eq!(sut.transfer_td(0.0), 1.0e5);
+        let removed = sut.remove(0);
+        assert_eq!(removed.short_type_name(), "UnitGain");
+        assert_eq!(sut.elements()[0].short_type_name(), "PT0");
+    }
+
+    #[test]
+    fn test_Series_nested_and_display() {
+        let mut inner = Series::<f64>::default();
+        inner.push(Box::new(PT1::<f64>::default()));
+        let mut sut = Series::<f64>::default();
+        sut.push(Box::new(inner));
+        sut.push(Box::new(PT0::<f64>::default()));
+        assert_eq!(
+            format!("{}", sut),
+            "Series(Series(PT1(sample_time: 1, t1_time 1, kp: 1)) -> PT0(sample_time: 1, t0_time 0, kp: 1))"
+        );
+        assert_eq!(sut.clone(), sut);
+    }
+
+    #[test]
+    fn test_Series_bypass() {
+        let mut sut = Series::new(vec![
+            Box::new(
+                UnitGain::convert(
+                    Unit::new("bar", Quantity::Pressure, 1.0e5, 0.0),
+                    Unit::new("Pa", Quantity::Pressure, 1.0, 0.0),
+                )
+                .unwrap(),
+            ) as BoxedTransferTimeDomain<f64>,
+            Box::new(PT0::<f64>::default().set_t0_time_or_default(1.0)),
+        ]);
+        sut.set_enabled(1, false);
+        assert!(sut.is_enabled(0));
+        assert!(!sut.is_enabled(1));
+        assert!(!sut.is_enabled(2));
+        assert_eq!(sut.transfer_td(2.0), 2.0e5);
+        assert!(
+            format!("{}", sut).ends_with("-> PT0(sample_time: 1, t0_time 1, kp: 1) [bypassed])")
+        );
+        sut.set_enabled(0, false);
+        assert_eq!(sut.output_unit("bar"), "bar");
+        // enabled again, the dead time continues from its own state
+        sut.set_enabled(1, true);
+        assert_eq!(sut.transfer_td(3.0), 0.0);
+        sut.insert(0, Box::new(PT1::<f64>::default()));
+        assert!(sut.is_enabled(0) && !sut.is_enabled(1));
+        sut.remove(1);
+        assert_eq!(sut.position("PT0"), Some(1));
+        assert!(sut.is_enabled(1));
+    }
+
+    #[test]
+    fn test_Series_check_units() {
+        let bar = Unit::new("bar", Quantity::Pressure, 1.0e5, 0.0);
+        let pascal = Unit::new("Pa", Quantity::Pressure, 1.0, 0.0);
+        let mut sut = Series::new(vec![
+            Box::new(UnitGain::convert(bar, pascal).unwrap()) as BoxedTransferTimeDomain<f64>,
+            Box::new(PT1::<f64>::default()),
+            Box::new(UnitGain::convert(bar, pascal).unwrap()),
+        ]);
+        assert_eq!(
+            sut.check_units("bar"),
+            Err(unit_gain::UnitCheckError {
+                element: "UnitGain",
+                expected: "bar",
+                found: "Pa"
+            })
+        );
+        sut.set_enabled(0, false);
+        assert_eq!(sut.check_units("bar"), Ok("Pa"));
+        assert!(sut.check_units("Pa").is_err());
+    }
+}
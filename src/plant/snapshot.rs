@@ -0,0 +1,588 @@
+//! # Snapshots of element state
+//!
+//! Captures the internal state (integrators, delay buffers) of plant elements
+//! at a given simulation time. Two snapshots can be compared to find out which
+//! state variables changed and by how much - e.g. to track down why two
+//! nominally identical runs diverge.
+//!
+//! `StateAccess` saves the complete state of an element and restores it
+//! later, to checkpoint a simulation, roll it back or branch what-if runs
+//! from a common history.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use cb_simulation_util::plant::TransferTimeDomain;
+//! use cb_simulation_util::plant::pt1::PT1;
+//! use cb_simulation_util::plant::snapshot::Snapshot;
+//!
+//! fn main() {
+//!     let mut pt1 = PT1::<f64>::default().set_t1_time_or_default(2.0);
+//!     let before = Snapshot::new(0.0).capture("plant", &pt1);
+//!     pt1.transfer_td(1.0);
+//!     let after = Snapshot::new(1.0).capture("plant", &pt1);
+//!     let diff = before.diff(&after);
+//!     assert_eq!(diff.changes.len(), 1);
+//!     assert_eq!(diff.changes[0].delta(), 0.5);
+//! }
+//! ```
+
+use super::*;
+use core::fmt::{self, Display};
+use std::boxed::Box;
+use std::format;
+use std::string::String;
+use std::vec::Vec;
+
+/// Exposes the internal state variables of an element by name
+pub trait StateSnapshot {
+    /// Named state values, e.g. `("previous_output", 0.5)`
+    fn state_values(&self) -> Vec<(String, f64)>;
+}
+
+/// Saves and restores the complete internal state of an element
+///
+/// The state is everything the element changes while running, not its
+/// parameters. A state restored into an element whose parameters change the
+/// state size, e.g. a different delay, is ignored.
+///
+/// Composites of boxed elements - `Series`, `Feedback` and `Switch` - save
+/// the states of their children as `BoxedState`, see `as_state_access`.
+/// Children of other types than the elements of this crate keep their state.
+pub trait StateAccess {
+    type State: Clone + fmt::Debug + PartialEq;
+
+    fn save_state(&self) -> Self::State;
+
+    fn restore_state(&mut self, state: &Self::State);
+}
+
+/// Type erased state of a boxed element, see `DynStateAccess`
+pub struct BoxedState(Box<dyn ErasedState>);
+
+trait ErasedState: DynClone + fmt::Debug + Send + Sync {
+    fn as_any(&self) -> &dyn Any;
+    fn dyn_eq(&self, other: &dyn ErasedState) -> bool;
+}
+
+impl<T: Clone + fmt::Debug + PartialEq + Send + Sync + 'static> ErasedState for T {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn dyn_eq(&self, other: &dyn ErasedState) -> bool {
+        if let Some(other_t) = other.as_any().downcast_ref::<T>() {
+            self == other_t
+        } else {
+            false
+        }
+    }
+}
+
+impl Clone for BoxedState {
+    fn clone(&self) -> Self {
+        BoxedState(dyn_clone::clone_box(&*self.0))
+    }
+}
+
+impl fmt::Debug for BoxedState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl PartialEq for BoxedState {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.dyn_eq(&*other.0)
+    }
+}
+
+/// `StateAccess` with a type erased state, for boxed elements
+pub trait DynStateAccess {
+    fn save_boxed_state(&self) -> BoxedState;
+
+    /// Ignored for the state of another element type
+    fn restore_boxed_state(&mut self, state: &BoxedState);
+}
+
+impl<T: StateAccess> DynStateAccess for T
+where
+    T::State: Send + Sync + 'static,
+{
+    fn save_boxed_state(&self) -> BoxedState {
+        BoxedState(Box::new(self.save_state()))
+    }
+
+    fn restore_boxed_state(&mut self, state: &BoxedState) {
+        if let Some(state) = state.0.as_any().downcast_ref::<T::State>() {
+            self.restore_state(state);
+        }
+    }
+}
+
+/// Downcast `$any` to the first matching element type of this crate
+macro_rules! downcast_state_access {
+    ($any:expr, $downcast:ident, $target:ty) => {{
+        use crate::controller::{pi::PI, relay::Relay, three_point::ThreePoint};
+        use crate::hysteresis::Hysteresis;
+        let any = $any;
+        downcast_state_access!(
+            @types any, $downcast, $target,
+            pt0::PT0<f64>, pt1::PT1<f64>, pt2::PT2<f64>, ptn::PTn<f64>,
+            dead_time::DeadTime<f64>, saturation::Saturation<f64>,
+            integrator::Integrator<f64>, fir::FIR<f64>, iir_biquad::IIRBiquad<f64>,
+            notch::Notch<f64>, discrete_transfer::DiscreteTransfer<f64>,
+            moving_average::MovingAverage<f64>, median_filter::MedianFilter<f64>,
+            rate_limiter::RateLimiter<f64>, quantizer::Quantizer<f64>,
+            noise_source::NoiseSource<f64>, zero_order_hold::ZeroOrderHold<f64>,
+            sensor_model::SensorModel<f64>, actuator_model::ActuatorModel<f64>,
+            thermal_rc::ThermalRC, mass_spring_damper::MassSpringDamper,
+            dc_motor::DcMotor, state_space::StateSpace, backlash::Backlash,
+            battery::Battery, inverted_pendulum::InvertedPendulum, stiction::Stiction,
+            ph_neutralization::PhNeutralization, Hysteresis<f64>, PI<f64>, Relay<f64>,
+            ThreePoint<f64>, series::Series<f64>, feedback::Feedback, switch::Switch
+        )
+    }};
+    (@types $any:ident, $downcast:ident, $target:ty, $($element:ty),+) => {{
+        $(
+            if $any.is::<$element>() {
+                return $any.$downcast::<$element>().map(|e| e as $target);
+            }
+        )+
+        None
+    }};
+}
+
+/// The `DynStateAccess` of a boxed element, for the elements of this crate
+pub fn as_state_access(element: &dyn DynTransferTimeDomain<f64>) -> Option<&dyn DynStateAccess> {
+    downcast_state_access!(element.as_any(), downcast_ref, &dyn DynStateAccess)
+}
+
+/// Mutable `DynStateAccess` of a boxed element, see `as_state_access`
+pub fn as_state_access_mut(
+    element: &mut dyn DynTransferTimeDomain<f64>,
+) -> Option<&mut dyn DynStateAccess> {
+    downcast_state_access!(element.as_any_mut(), downcast_mut, &mut dyn DynStateAccess)
+}
+
+/// The state of a boxed child, `None` if it has no `DynStateAccess`
+pub(crate) fn save_child_state(element: &dyn DynTransferTimeDomain<f64>) -> Option<BoxedState> {
+    as_state_access(element).map(|e| e.save_boxed_state())
+}
+
+/// Restore the state of a boxed child saved by `save_child_state`
+pub(crate) fn restore_child_state(
+    element: &mut dyn DynTransferTimeDomain<f64>,
+    state: &Option<BoxedState>,
+) {
+    if let (Some(element), Some(state)) = (as_state_access_mut(element), state) {
+        element.restore_boxed_state(state);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct StateValue {
+    pub name: String,
+    pub value: f64,
+}
+
+/// State of one or several elements at a given simulation time
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snapshot {
+    pub time: f64,
+    pub values: Vec<StateValue>,
+}
+
+impl Snapshot {
+    pub fn new(time: f64) -> Self {
+        Snapshot {
+            time,
+            values: Vec::new(),
+        }
+    }
+
+    /// Add the state of `element`, each value prefixed with `label`
+    pub fn capture<E: StateSnapshot + TypeIdentifier>(mut self, label: &str, element: &E) -> Self {
+        for (name, value) in element.state_values() {
+            self.values.push(StateValue {
+                name: format!("{}.{}.{}", label, element.short_type_name(), name),
+                value,
+            });
+        }
+        self
+    }
+
+    /// Compare with a later (or parallel) snapshot
+    ///
+    /// Only changed values are reported, a value staying `NaN` is unchanged.
+    /// Values present in just one of both snapshots are reported with `NaN`
+    /// on the missing side.
+    pub fn diff(&self, other: &Snapshot) -> SnapshotDiff {
+        let mut changes = Vec::new();
+        for v in &self.values {
+            let after = other.values.iter().find(|o| o.name == v.name);
+            let unchanged =
+                after.is_some_and(|o| o.value == v.value || (o.value.is_nan() && v.value.is_nan()));
+            if !unchanged {
+                changes.push(StateChange {
+                    name: v.name.clone(),
+                    before: v.value,
+                    after: after.map_or(f64::NAN, |o| o.value),
+                });
+            }
+        }
+        for o in &other.values {
+            if !self.values.iter().any(|v| v.name == o.name) {
+                changes.push(StateChange {
+                    name: o.name.clone(),
+                    before: f64::NAN,
+                    after: o.value,
+                });
+            }
+        }
+        SnapshotDiff {
+            from_time: self.time,
+            to_time: other.time,
+            changes,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct StateChange {
+    pub name: String,
+    pub before: f64,
+    pub after: f64,
+}
+
+impl StateChange {
+    pub fn delta(&self) -> f64 {
+        self.after - self.before
+    }
+}
+
+/// Changed state values between two snapshots
+#[derive(Debug, Clone, PartialEq)]
+pub struct SnapshotDiff {
+    pub from_time: f64,
+    pub to_time: f64,
+    pub changes: Vec<StateChange>,
+}
+
+impl SnapshotDiff {
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+
+impl Display for SnapshotDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Snapshot diff t={} -> t={}", self.from_time, self.to_time)?;
+        for c in &self.changes {
+            writeln!(
+                f,
+                "  {}: {} -> {} (delta {})",
+                c.name,
+                c.before,
+                c.after,
+                c.delta()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl<N: Copy + Into<f64>> StateSnapshot for pt0::PT0<N> {
+    fn state_values(&self) -> Vec<(String, f64)> {
+        self.buffer_state()
+            .iter()
+            .enumerate()
+            .map(|(i, v)| (format!("buffered_output[{}]", i), (*v).into()))
+            .collect()
+    }
+}
+
+impl<N: Copy + Into<f64>> StateSnapshot for dead_time::DeadTime<N> {
+    fn state_values(&self) -> Vec<(String, f64)> {
+        self.buffer_state()
+            .iter()
+            .enumerate()
+            .map(|(i, v)| (format!("buffered_output[{}]", i), (*v).into()))
+            .collect()
+    }
+}
+
+impl<N: Copy + Default + Into<f64>> StateSnapshot for fir::FIR<N> {
+    fn state_values(&self) -> Vec<(String, f64)> {
+        self.state()
+            .iter()
+            .enumerate()
+            .map(|(i, v)| (format!("delayed_input[{}]", i), (*v).into()))
+            .collect()
+    }
+}
+
+impl<N: Copy + Into<f64>> StateSnapshot for pt1::PT1<N> {
+    fn state_values(&self) -> Vec<(String, f64)> {
+        std::vec![(String::from("previous_output"), self.state().into())]
+    }
+}
+
+impl<N: Copy + Into<f64>> StateSnapshot for pt2::PT2<N> {
+    fn state_values(&self) -> Vec<(String, f64)> {
+        let (output, diff_output) = self.state();
+        std::vec![
+            (String::from("previous_output"), output.into()),
+            (String::from("previous_diff_output"), diff_output.into()),
+        ]
+    }
+}
+
+impl<N: Copy + Into<f64>> StateSnapshot for ptn::PTn<N> {
+    fn state_values(&self) -> Vec<(String, f64)> {
+        self.state()
+            .iter()
+            .enumerate()
+            .map(|(i, v)| (format!("stage_output[{}]", i), (*v).into()))
+            .collect()
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::plant::dead_time::DeadTime;
+    use crate::plant::pt0::PT0;
+    use crate::plant::pt2::PT2;
+
+    #[test]
+    fn test_Snapshot_no_change() {
+        let pt2 = PT2::<f64>::default();
+        let a = Snapshot::new(0.0).capture("p", &pt2);
+        let b = Snapshot::new(1.0).capture("p", &pt2);
+        assert!(a.diff(&b).is_empty());
+    }
+
+    #[test]
+    fn test_Snapshot_PT0_buffer_change() {
+        let mut pt0 = PT0::<f64>::default().set_t0_time_or_default(2.0);
+        let a = Snapshot::new(0.0).capture("delay", &pt0);
+        pt0.transfer_td(3.0);
+        let b = Snapshot::new(1.0).capture("delay", &pt0);
+        let diff = a.diff(&b);
+        assert_eq!(diff.changes.len(), 1);
+        assert_eq!(diff.changes[0].name, "delay.PT0.buffered_output[2]");
+        assert_eq!(diff.changes[0].delta(), 3.0);
+    }
+
+    #[test]
+    fn test_StateAccess_branch_runs() {
+        let mut pt2 = PT2::<f64>::default().set_sample_time_or_default(0.1);
+        let mut pt0 = PT0::<f64>::default().set_t0_time_or_default(2.0);
+        for _ in 0..10 {
+            pt0.transfer_td(pt2.transfer_td(1.0));
+        }
+        let checkpoint = (pt2.save_state(), pt0.save_state());
+        let branch = |pt2: &mut PT2<f64>, pt0: &mut PT0<f64>, u: f64| {
+            (0..5).fold(0.0, |_, _| pt0.transfer_td(pt2.transfer_td(u)))
+        };
+        let first = branch(&mut pt2, &mut pt0, 2.0);
+        let other = branch(&mut pt2, &mut pt0, 0.0);
+        pt2.restore_state(&checkpoint.0);
+        pt0.restore_state(&checkpoint.1);
+        assert_eq!(branch(&mut pt2, &mut pt0, 2.0), first);
+        assert_ne!(first, other);
+    }
+
+    /// Outputs of `steps` samples after a restore equal the ones after the save
+    fn replays<E: TransferTimeDomain<f64> + StateAccess>(mut element: E, steps: usize) {
+        let input = |k: usize| (k as f64 * 0.7).sin() + 1.0;
+        for k in 0..steps {
+            element.transfer_td(input(k));
+        }
+        let state = element.save_state();
+        let first: Vec<f64> = (0..steps).map(|k| element.transfer_td(input(k))).collect();
+        element.restore_state(&state);
+        let again: Vec<f64> = (0..steps).map(|k| element.transfer_td(input(k))).collect();
+        assert_eq!(first, again, "{}", element.short_type_name());
+    }
+
+    /// `replays` for MIMO elements, all inputs see the same signal
+    fn replays_mimo<E: MimoTransferTimeDomain + StateAccess>(mut element: E, steps: usize) {
+        let n = element.input_count();
+        let input = |k: usize| ndarray::Array1::from_elem(n, (k as f64 * 0.7).sin() + 1.0);
+        for k in 0..steps {
+            element.transfer_td(input(k).view());
+        }
+        let state = element.save_state();
+        let first: Vec<_> = (0..steps)
+            .map(|k| element.transfer_td(input(k).view()))
+            .collect();
+        element.restore_state(&state);
+        let again: Vec<_> = (0..steps)
+            .map(|k| element.transfer_td(input(k).view()))
+            .collect();
+        assert_eq!(first, again, "{}", element.short_type_name());
+    }
+
+    #[test]
+    fn test_StateAccess_replays_elements() {
+        use crate::controller::mrac::{Mrac, ReferenceModel};
+        use crate::controller::three_point::ThreePoint;
+        use crate::logic::counter::{SwitchCounter, Totalizer};
+        use crate::plant::actuator_model::ActuatorModelBuilder;
+        use crate::plant::backlash::Backlash;
+        use crate::plant::battery::Battery;
+        use crate::plant::compressor::Compressor;
+        use crate::plant::discrete_transfer::DiscreteTransfer;
+        use crate::plant::hydraulic::HydraulicLoop;
+        use crate::plant::iir_biquad::IIRBiquad;
+        use crate::plant::inverted_pendulum::InvertedPendulum;
+        use crate::plant::mass_spring_damper::MassSpringDamper;
+        use crate::plant::median_filter::MedianFilter;
+        use crate::plant::moving_average::MovingAverage;
+        use crate::plant::noise_source::NoiseSource;
+        use crate::plant::notch::Notch;
+        use crate::plant::ode::OdePlant;
+        use crate::plant::ph_neutralization::PhNeutralization;
+        use crate::plant::pt1::PT1;
+        use crate::plant::quantizer::{Dither, Quantizer};
+        use crate::plant::rate_limiter::RateLimiter;
+        use crate::plant::resampler::Resampler;
+        use crate::plant::sensor_model::SensorModel;
+        use crate::plant::stiction::Stiction;
+        use crate::plant::thermal_rc::ThermalRC;
+        use crate::plant::thermal_zones::TwoZoneThermal;
+        use crate::plant::zero_order_hold::ZeroOrderHold;
+
+        replays(
+            DiscreteTransfer::<f64>::new(std::vec![0.5, 0.5], std::vec![1.0, -0.5]).unwrap(),
+            8,
+        );
+        replays(IIRBiquad::<f64>::default(), 8);
+        replays(Notch::<f64>::default(), 8);
+        replays(MovingAverage::<f64>::default(), 8);
+        replays(MedianFilter::<f64>::default(), 8);
+        replays(RateLimiter::<f64>::default(), 8);
+        replays(
+            Quantizer::<f64>::default().set_dither(Dither::Uniform, 7),
+            8,
+        );
+        replays(NoiseSource::<f64>::default(), 8);
+        let sensor = SensorModel::<f64>::default()
+            .set_drift_rate(0.1)
+            .set_noise_variance(0.01)
+            .unwrap()
+            .set_quantizer(Quantizer::default().set_dither(Dither::Uniform, 3))
+            .set_latency_or_default(2.0);
+        replays(sensor, 8);
+        let actuator = ActuatorModelBuilder::<f64>::new()
+            .limits(0.0, 1.5)
+            .rates(0.5, 0.5)
+            .dead_time(2.0)
+            .build()
+            .unwrap();
+        replays(actuator, 8);
+        replays(ZeroOrderHold::<f64>::default(), 8);
+        replays(Resampler::new(PT2::<f64>::default(), 0.25).unwrap(), 8);
+        replays(ThermalRC::default(), 8);
+        replays(MassSpringDamper::default(), 8);
+        replays(SwitchCounter::default(), 8);
+        replays(Totalizer::default(), 8);
+        replays(ThreePoint::<f64>::default(), 8);
+        replays(Backlash::default(), 8);
+        replays(Battery::default(), 8);
+        replays(
+            InvertedPendulum::default().set_sample_time_or_default(0.01),
+            8,
+        );
+        replays(
+            OdePlant::new(InvertedPendulum::default()).set_sample_time_or_default(0.01),
+            8,
+        );
+        replays(Stiction::default(), 8);
+        replays(PhNeutralization::default(), 8);
+        replays_mimo(Mrac::new(ReferenceModel::PT1(PT1::default())), 8);
+        replays_mimo(HydraulicLoop::default(), 8);
+        replays_mimo(TwoZoneThermal::default(), 8);
+        replays_mimo(Compressor::default(), 8);
+
+        let mut timer = crate::logic::timer::OnDelay::default().set_delay(2.0);
+        timer.transfer_td(true);
+        let state = timer.save_state();
+        assert!(!timer.transfer_td(true) && timer.transfer_td(true));
+        timer.restore_state(&state);
+        assert_eq!(timer.elapsed(), 1.0);
+    }
+
+    #[test]
+    fn test_StateAccess_restores_children_of_composites() {
+        use crate::plant::feedback::Feedback;
+        use crate::plant::pt1::PT1;
+        use crate::plant::series::Series;
+        use crate::plant::switch::Switch;
+        use crate::signal::StepFunction;
+        use std::boxed::Box;
+
+        let lag = || Box::new(PT1::<f64>::default().set_t1_time_or_default(3.0));
+        let delay = || Box::new(DeadTime::<f64>::default().set_t0_time_or_default(2.0));
+        let series = Series::new(std::vec![lag(), delay()]);
+        replays(series.clone(), 8);
+        let feedback = Feedback::new(Box::new(series.clone()), lag());
+        replays(feedback.clone(), 8);
+        let selector = Box::new(StepFunction::default().step(10.0));
+        let switch =
+            Switch::new(std::vec![Box::new(feedback), lag()], selector).set_crossfade_time(2.0);
+        replays(switch, 8);
+
+        // a saved child state only fits the same element type
+        let mut sut = series;
+        sut.transfer_td(1.0);
+        let state = sut.save_state();
+        assert!(state.iter().all(|s| s.is_some()));
+        assert_ne!(state[0], state[1]);
+        let mut other = Series::new(std::vec![delay(), lag()]);
+        other.restore_state(&state);
+        assert_eq!(other, Series::new(std::vec![delay(), lag()]));
+    }
+
+    #[test]
+    fn test_StateAccess_ignores_other_size() {
+        let mut short = DeadTime::<f64>::default();
+        let mut long = DeadTime::<f64>::default().set_t0_time_or_default(3.0);
+        long.transfer_td(1.0);
+        let state = long.save_state();
+        short.restore_state(&state);
+        assert_eq!(short.save_state(), DeadTime::<f64>::default().save_state());
+    }
+
+    #[test]
+    fn test_Snapshot_missing_value() {
+        let pt2 = PT2::<f64>::default();
+        let a = Snapshot::new(0.0);
+        let b = Snapshot::new(0.0).capture("p", &pt2);
+        let diff = a.diff(&b);
+        assert_eq!(diff.changes.len(), 2);
+        assert!(diff.changes[0].before.is_nan());
+    }
+
+    #[test]
+    fn test_Snapshot_nan_value() {
+        let value = |value| StateValue {
+            name: String::from("p.PT1.output"),
+            value,
+        };
+        let mut a = Snapshot::new(0.0);
+        a.values.push(value(f64::NAN));
+        let mut b = Snapshot::new(1.0);
+        b.values.push(value(f64::NAN));
+        assert!(a.diff(&b).is_empty());
+        // a NaN value is still reported if the other side misses it
+        let diff = a.diff(&Snapshot::new(1.0));
+        assert_eq!(diff.changes.len(), 1);
+        b.values[0].value = 1.0;
+        assert_eq!(a.diff(&b).changes[0].after, 1.0);
+    }
+}
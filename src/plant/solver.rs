@@ -0,0 +1,222 @@
+//! Integration methods of the linear lag elements `PT1` and `PT2`
+//!
+//! The elements integrate $ \dot{x} = A x + B u $ over one sample time $h$
+//! with the input $u[k]$ of the current sample:
+//!
+//! * `EulerForward`: $ x[k] = (I + hA) x[k-1] + h B u[k] $, the cheapest;
+//!   unstable once $ h $ exceeds twice the smallest time constant
+//! * `EulerBackward`: $ x[k] = (I - hA)^{-1} (x[k-1] + h B u[k]) $, stable
+//!   for any sample time, but damps oscillations too much
+//! * `Trapezoidal`: the Tustin method with the mean of $u[k]$ and $u[k-1]$,
+//!   stable for any sample time and keeps the damping
+//! * `RungeKutta4`: the classic fourth order method with $u[k]$ held over
+//!   the step, very accurate for sample times up to the time constants
+//!
+//! `check_stability` of the elements tells whether the discrete recurrence
+//! of the configured solver and sample time is stable and free of numerical
+//! ringing, `stabilize` switches an offending element to `EulerBackward`.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use cb_simulation_util::plant::TransferTimeDomain;
+//! use cb_simulation_util::plant::pt1::PT1;
+//! use cb_simulation_util::plant::solver::Solver;
+//!
+//! fn main() {
+//!     // sampled 4 times slower than the time constant
+//!     let mut sut = PT1::<f64>::default()
+//!         .set_sample_time_or_default(4.0)
+//!         .set_solver(Solver::EulerBackward)
+//!         .set_t1_time_or_default(1.0);
+//!     assert_eq!(sut.t1_time, 1.0);
+//!     assert!((sut.transfer_td(1.0) - 0.8).abs() < 1e-12);
+//!     assert!((sut.transfer_td(1.0) - 0.96).abs() < 1e-12);
+//! }
+//! ```
+
+use core::fmt::{self, Display};
+use ndarray::{Array1, Array2};
+
+use crate::linalg;
+
+/// Why a discretized element does not follow its continuous model
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StabilityError {
+    /// The recurrence diverges, the largest pole magnitude is >= 1
+    Unstable { spectral_radius: f64 },
+    /// A negative real pole makes the output alternate from sample to sample
+    Ringing { pole: f64 },
+}
+
+impl Display for StabilityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StabilityError::Unstable { spectral_radius } => write!(
+                f,
+                "Unstable discretization: pole magnitude {} >= 1, reduce the sample time",
+                spectral_radius
+            ),
+            StabilityError::Ringing { pole } => write!(
+                f,
+                "Ringing discretization: negative pole {}, reduce the sample time",
+                pole
+            ),
+        }
+    }
+}
+
+/// Check the poles of the discrete system matrix `ad` of 1 or 2 states
+pub(crate) fn check_poles(ad: &Array2<f64>) -> Result<(), StabilityError> {
+    let real_poles = |poles: &[f64]| -> Result<(), StabilityError> {
+        let spectral_radius = poles.iter().fold(0.0_f64, |r, p| r.max(p.abs()));
+        if spectral_radius >= 1.0 {
+            return Err(StabilityError::Unstable { spectral_radius });
+        }
+        match poles.iter().find(|p| **p < 0.0) {
+            Some(pole) => Err(StabilityError::Ringing { pole: *pole }),
+            None => Ok(()),
+        }
+    };
+    if ad.nrows() == 1 {
+        return real_poles(&[ad[[0, 0]]]);
+    }
+    let half_trace = 0.5 * (ad[[0, 0]] + ad[[1, 1]]);
+    let det = ad[[0, 0]] * ad[[1, 1]] - ad[[0, 1]] * ad[[1, 0]];
+    let discriminant = half_trace * half_trace - det;
+    if discriminant >= 0.0 {
+        let root = discriminant.sqrt();
+        real_poles(&[half_trace + root, half_trace - root])
+    } else {
+        // complex pair: the oscillation is part of the continuous model
+        let spectral_radius = det.sqrt();
+        if spectral_radius >= 1.0 {
+            Err(StabilityError::Unstable { spectral_radius })
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Solver {
+    #[default]
+    EulerForward,
+    EulerBackward,
+    Trapezoidal,
+    RungeKutta4,
+}
+
+impl core::str::FromStr for Solver {
+    type Err = crate::parse::ParseError;
+
+    /// The variant name as printed by `Debug`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "EulerForward" => Ok(Solver::EulerForward),
+            "EulerBackward" => Ok(Solver::EulerBackward),
+            "Trapezoidal" => Ok(Solver::Trapezoidal),
+            "RungeKutta4" => Ok(Solver::RungeKutta4),
+            _ => Err(crate::parse::ParseError::Value("solver")),
+        }
+    }
+}
+
+impl Solver {
+    /// Whether the method is stable for any sample time
+    pub fn is_unconditionally_stable(&self) -> bool {
+        matches!(self, Solver::EulerBackward | Solver::Trapezoidal)
+    }
+
+    /// Step factor $\alpha$ of a first order lag $ y[k] = y[k-1] + \alpha (K u - y[k-1]) $
+    ///
+    /// `ratio` is the sample time divided by the time constant.
+    pub fn first_order_alpha(&self, ratio: f64) -> f64 {
+        let x = ratio;
+        match self {
+            Solver::EulerForward => x,
+            Solver::EulerBackward => x / (1.0 + x),
+            Solver::Trapezoidal => x / (1.0 + 0.5 * x),
+            Solver::RungeKutta4 => x - x * x / 2.0 + x.powi(3) / 6.0 - x.powi(4) / 24.0,
+        }
+    }
+
+    /// Discrete `(Ad, Bd)` of $ \dot{x} = A x + B u $ over one step of `h`
+    ///
+    /// With `Trapezoidal` $B_{d}$ applies to the mean of the current and
+    /// the previous input.
+    pub(crate) fn discretize(
+        &self,
+        a: &Array2<f64>,
+        b: &Array1<f64>,
+        h: f64,
+    ) -> (Array2<f64>, Array1<f64>) {
+        let n = a.nrows();
+        let identity = Array2::<f64>::eye(n);
+        let ha = a * h;
+        let inverse = |m: Array2<f64>| linalg::inverse(&m).unwrap_or_else(|| Array2::eye(n));
+        match self {
+            Solver::EulerForward => (&identity + &ha, b * h),
+            Solver::EulerBackward => {
+                let inv = inverse(&identity - &ha);
+                (inv.clone(), inv.dot(b) * h)
+            }
+            Solver::Trapezoidal => {
+                let inv = inverse(&identity - &ha * 0.5);
+                (inv.dot(&(&identity + &ha * 0.5)), inv.dot(b) * h)
+            }
+            Solver::RungeKutta4 => {
+                // truncated series of exp(hA) and of its integral
+                let ha2 = ha.dot(&ha);
+                let ha3 = ha2.dot(&ha);
+                let ha4 = ha3.dot(&ha);
+                let ad = &identity + &ha + &ha2 / 2.0 + &ha3 / 6.0 + &ha4 / 24.0;
+                let integral = &identity + &ha / 2.0 + &ha2 / 6.0 + &ha3 / 24.0;
+                (ad, integral.dot(b) * h)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use ndarray::array;
+    use std::vec;
+
+    #[test]
+    fn test_solver_first_order_alpha_matches_discretize() {
+        for solver in [
+            Solver::EulerForward,
+            Solver::EulerBackward,
+            Solver::Trapezoidal,
+            Solver::RungeKutta4,
+        ] {
+            let (ad, bd) = solver.discretize(&array![[-0.5]], &array![0.5], 0.8);
+            let alpha = solver.first_order_alpha(0.4);
+            assert!((1.0 - ad[[0, 0]] - alpha).abs() < 1e-12, "{:?}", solver);
+            assert!((bd[0] - alpha).abs() < 1e-12, "{:?}", solver);
+        }
+        assert!(Solver::Trapezoidal.is_unconditionally_stable());
+        assert!(!Solver::RungeKutta4.is_unconditionally_stable());
+    }
+
+    #[test]
+    fn test_check_poles() {
+        assert_eq!(check_poles(&array![[0.5]]), Ok(()));
+        assert_eq!(
+            check_poles(&array![[-0.5]]),
+            Err(StabilityError::Ringing { pole: -0.5 })
+        );
+        assert_eq!(
+            check_poles(&array![[1.0, 1.0], [0.0, 0.5]]),
+            Err(StabilityError::Unstable {
+                spectral_radius: 1.0
+            })
+        );
+        // complex pair of magnitude 0.5
+        assert_eq!(check_poles(&array![[0.0, 0.5], [-0.5, 0.0]]), Ok(()));
+    }
+}
@@ -0,0 +1,255 @@
+//! A discrete state space plant with multiple inputs and outputs
+//!
+//! $ x[k+1] = A x[k] + B u[k] $
+//!
+//! $ y[k] = C x[k] + D u[k] $
+//!
+//! with $n$ states, $m$ inputs and $p$ outputs. Implements the vector valued
+//! `MimoTransferTimeDomain`, for coupled plants which the scalar
+//! `TransferTimeDomain` cannot express.
+//!
+//! `from_continuous` discretizes $ \dot{x} = A x + B u $ with a zero-order hold.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use ndarray::array;
+//! use cb_simulation_util::plant::MimoTransferTimeDomain;
+//! use cb_simulation_util::plant::state_space::StateSpace;
+//!
+//! fn main() {
+//!     // two decoupled integrators, the second output is the sum of both states
+//!     let mut sut = StateSpace::new(
+//!         array![[1.0, 0.0], [0.0, 1.0]],
+//!         array![[1.0, 0.0], [0.0, 1.0]],
+//!         array![[1.0, 0.0], [1.0, 1.0]],
+//!         array![[0.0, 0.0], [0.0, 0.0]],
+//!     )
+//!     .unwrap();
+//!     sut.transfer_td(array![1.0, 2.0].view());
+//!     let y = sut.transfer_td(array![1.0, 2.0].view());
+//!     assert_eq!(y, array![1.0, 3.0]);
+//! }
+//! ```
+
+use ndarray::{Array1, Array2, ArrayView1, s};
+
+use super::snapshot::StateAccess;
+use super::*;
+use crate::linalg;
+use core::fmt::{self, Display};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct StateSpace {
+    pub a: Array2<f64>,
+    pub b: Array2<f64>,
+    pub c: Array2<f64>,
+    pub d: Array2<f64>,
+    pub sample_time: f64,
+    state: Array1<f64>,
+}
+
+impl StateSpace {
+    /// Discrete plant, fails if the matrix dimensions do not fit together
+    pub fn new(
+        a: Array2<f64>,
+        b: Array2<f64>,
+        c: Array2<f64>,
+        d: Array2<f64>,
+    ) -> Result<Self, &'static str> {
+        let n = a.nrows();
+        if a.ncols() != n {
+            return Err("Invalid state space: A must be square");
+        }
+        if b.nrows() != n || c.ncols() != n {
+            return Err("Invalid state space: B needs n rows, C needs n columns");
+        }
+        if d.nrows() != c.nrows() || d.ncols() != b.ncols() {
+            return Err("Invalid state space: D needs the rows of C and the columns of B");
+        }
+        Ok(StateSpace {
+            a,
+            b,
+            c,
+            d,
+            sample_time: 1.0,
+            state: Array1::zeros(n),
+        })
+    }
+
+    /// Zero-order hold discretization of the continuous plant $ \dot{x} = A x + B u $
+    pub fn from_continuous(
+        a: Array2<f64>,
+        b: Array2<f64>,
+        c: Array2<f64>,
+        d: Array2<f64>,
+        sample_time: f64,
+    ) -> Result<Self, &'static str> {
+        let continuous = StateSpace::new(a, b, c, d)?;
+        let (n, m) = (continuous.states(), continuous.input_count());
+        // exp([[A, B], [0, 0]] Ts) = [[Ad, Bd], [0, I]]
+        let mut augmented = Array2::zeros((n + m, n + m));
+        augmented.slice_mut(s![..n, ..n]).assign(&continuous.a);
+        augmented.slice_mut(s![..n, n..]).assign(&continuous.b);
+        let phi = linalg::expm(&(augmented * sample_time));
+        Ok(StateSpace {
+            a: phi.slice(s![..n, ..n]).to_owned(),
+            b: phi.slice(s![..n, n..]).to_owned(),
+            sample_time,
+            ..continuous
+        })
+    }
+
+    pub fn set_sample_time_or_default(self, sample_time: f64) -> Self {
+        if sample_time > 0.0 {
+            StateSpace {
+                sample_time,
+                ..self
+            }
+        } else {
+            StateSpace {
+                sample_time: 1.0,
+                ..self
+            }
+        }
+    }
+
+    pub fn states(&self) -> usize {
+        self.a.nrows()
+    }
+
+    /// Steady state gain $ C (I - A)^{-1} B + D $, `None` for plants with integrating behavior
+    pub fn steady_state_gain(&self) -> Option<Array2<f64>> {
+        let i_minus_a = Array2::eye(self.states()) - &self.a;
+        let inverse = linalg::inverse(&i_minus_a)?;
+        Some(self.c.dot(&inverse).dot(&self.b) + &self.d)
+    }
+
+    /// The internal state $x$
+    pub fn state(&self) -> &Array1<f64> {
+        &self.state
+    }
+
+    /// Set the internal state, ignored if the length does not fit
+    pub fn set_state(&mut self, state: Array1<f64>) {
+        if state.len() == self.states() {
+            self.state = state;
+        }
+    }
+}
+
+impl StateAccess for StateSpace {
+    type State = Array1<f64>;
+
+    fn save_state(&self) -> Self::State {
+        self.state.clone()
+    }
+
+    fn restore_state(&mut self, state: &Self::State) {
+        self.set_state(state.clone());
+    }
+}
+
+impl TypeIdentifier for StateSpace {
+    fn short_type_name(&self) -> &'static str {
+        "StateSpace"
+    }
+}
+
+impl SampleTime for StateSpace {
+    fn sample_time(&self) -> Option<f64> {
+        Some(self.sample_time)
+    }
+}
+
+impl Display for StateSpace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "StateSpace(sample_time: {}, states: {}, inputs: {}, outputs: {})",
+            self.sample_time,
+            self.states(),
+            self.input_count(),
+            self.output_count()
+        )
+    }
+}
+
+impl MimoTransferTimeDomain for StateSpace {
+    fn input_count(&self) -> usize {
+        self.b.ncols()
+    }
+
+    fn output_count(&self) -> usize {
+        self.c.nrows()
+    }
+
+    fn transfer_td(&mut self, u: ArrayView1<f64>) -> Array1<f64> {
+        let y = self.c.dot(&self.state) + self.d.dot(&u);
+        self.state = self.a.dot(&self.state) + self.b.dot(&u);
+        y
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::plant::continuous_transfer::{ContinuousTransfer, Discretization};
+    use ndarray::array;
+    use std::vec;
+
+    #[test]
+    fn test_StateSpace_dimension_check() {
+        let sut = StateSpace::new(
+            array![[1.0, 0.0], [0.0, 1.0]],
+            array![[1.0], [0.0]],
+            array![[1.0, 0.0]],
+            array![[0.0, 0.0]],
+        );
+        assert!(sut.is_err());
+    }
+
+    #[test]
+    fn test_StateSpace_rejects_wrong_input_length() {
+        let mut sut = StateSpace::new(
+            array![[1.0]],
+            array![[1.0, 1.0]],
+            array![[1.0]],
+            array![[0.0, 0.0]],
+        )
+        .unwrap();
+        assert!(sut.try_transfer_td(array![1.0].view()).is_err());
+        assert!(sut.try_transfer_td(array![1.0, 2.0, 3.0].view()).is_err());
+        assert_eq!(sut.state(), &array![0.0]);
+        assert_eq!(
+            sut.try_transfer_td(array![1.0, 2.0].view()),
+            Ok(array![0.0])
+        );
+        assert_eq!(sut.state(), &array![3.0]);
+    }
+
+    #[test]
+    fn test_StateSpace_from_continuous_matches_zoh_transfer() {
+        // 4 / (s^2 + 2 s + 4) in controllable canonical form
+        let mut sut = StateSpace::from_continuous(
+            array![[-2.0, -4.0], [1.0, 0.0]],
+            array![[1.0], [0.0]],
+            array![[0.0, 4.0]],
+            array![[0.0]],
+            0.1,
+        )
+        .unwrap();
+        let mut reference = ContinuousTransfer::new(vec![4.0], vec![1.0, 2.0, 4.0])
+            .unwrap()
+            .discretize(Discretization::ZeroOrderHold, 0.1)
+            .unwrap();
+        for _ in 0..50 {
+            let y = sut.transfer_td(array![1.0].view());
+            assert!((y[0] - reference.transfer_td(1.0)).abs() < 1e-9);
+        }
+        assert_eq!(sut.output_count(), 1);
+        assert!((sut.steady_state_gain().unwrap()[[0, 0]] - 1.0).abs() < 1e-9);
+    }
+}
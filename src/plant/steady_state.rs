@@ -0,0 +1,313 @@
+//! # Steady state
+//!
+//! Equilibrium of an element for a constant input. Setting every block of a
+//! diagram to its equilibrium at the operating point starts the simulation
+//! there, without the transient from the zero state.
+//!
+//! `steady_input` inverts the static gain: the input which holds the output
+//! at a given value. `settle` sets the internal state to the equilibrium of
+//! a constant input, e.g. fills a delay buffer with the steady output.
+//! `settle_output` settles at a given output instead, which also covers
+//! integrating elements like a PI controller, whose output is held by a
+//! zero input. Composites settle their elements, a `Feedback` loop the
+//! whole loop, so a diagram built from them is initialized at an operating
+//! point by `Diagram::initialize_at`.
+//! Boxed elements are looked up with `as_steady_state` and
+//! `as_steady_state_mut`.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use cb_simulation_util::plant::TransferTimeDomain;
+//! use cb_simulation_util::plant::pt1::PT1;
+//! use cb_simulation_util::plant::steady_state::SteadyState;
+//!
+//! fn main() {
+//!     let mut plant = PT1::<f64>::default().set_t1_time_or_default(10.0).set_kp(2.0);
+//!     let input = plant.steady_input(50.0).unwrap();
+//!     assert_eq!(input, 25.0);
+//!     plant.settle(input);
+//!     // no transient: the output stays at the operating point
+//!     assert_eq!(plant.transfer_td(input), 50.0);
+//! }
+//! ```
+
+use std::vec;
+
+use super::dead_time::DeadTime;
+use super::feedback::Feedback;
+use super::ph_neutralization::PhNeutralization;
+use super::pt0::PT0;
+use super::pt1::PT1;
+use super::pt2::PT2;
+use super::ptn::PTn;
+use super::saturation::Saturation;
+use super::series::Series;
+use super::snapshot::StateAccess;
+use super::*;
+use crate::controller::pi::PI;
+
+/// Equilibrium of an element for a constant input
+pub trait SteadyState: TypeIdentifier {
+    /// Input holding the output at `output`, `None` if no input does, e.g.
+    /// beyond a limit or for a zero gain
+    fn steady_input(&self, output: f64) -> Option<f64>;
+
+    /// Set the state to the equilibrium of the constant `input`, returns the output
+    fn settle(&mut self, input: f64) -> f64;
+
+    /// Set the state to the equilibrium holding `output`, returns the input
+    ///
+    /// Fails with the short type name of the element no input of which
+    /// holds the output.
+    fn settle_output(&mut self, output: f64) -> Result<f64, &'static str> {
+        let input = self.steady_input(output).ok_or(self.short_type_name())?;
+        self.settle(input);
+        Ok(input)
+    }
+}
+
+/// Input of a static gain `kp` for `output`
+fn inverse_gain(kp: f64, output: f64) -> Option<f64> {
+    if kp != 0.0 { Some(output / kp) } else { None }
+}
+
+impl SteadyState for PT0<f64> {
+    fn steady_input(&self, output: f64) -> Option<f64> {
+        inverse_gain(self.kp, output)
+    }
+
+    fn settle(&mut self, input: f64) -> f64 {
+        let output = self.kp * input;
+        let (mut buffer, _) = self.save_state();
+        buffer.fill(output);
+        self.restore_state(&(buffer, 0));
+        output
+    }
+}
+
+impl SteadyState for DeadTime<f64> {
+    fn steady_input(&self, output: f64) -> Option<f64> {
+        inverse_gain(self.kp, output)
+    }
+
+    fn settle(&mut self, input: f64) -> f64 {
+        let output = self.kp * input;
+        self.fill(output);
+        output
+    }
+}
+
+impl SteadyState for PT1<f64> {
+    fn steady_input(&self, output: f64) -> Option<f64> {
+        inverse_gain(self.kp, output)
+    }
+
+    fn settle(&mut self, input: f64) -> f64 {
+        let output = self.kp * input;
+        self.restore_state(&(output, input));
+        output
+    }
+}
+
+impl SteadyState for PT2<f64> {
+    fn steady_input(&self, output: f64) -> Option<f64> {
+        inverse_gain(self.kp, output)
+    }
+
+    fn settle(&mut self, input: f64) -> f64 {
+        let output = self.kp * input;
+        self.restore_state(&(output, 0.0, input));
+        output
+    }
+}
+
+impl SteadyState for PTn<f64> {
+    fn steady_input(&self, output: f64) -> Option<f64> {
+        inverse_gain(self.kp, output)
+    }
+
+    fn settle(&mut self, input: f64) -> f64 {
+        // the gain is applied in the first stage
+        let output = self.kp * input;
+        let stages = self.save_state().len();
+        self.restore_state(&vec![output; stages]);
+        output
+    }
+}
+
+impl SteadyState for Saturation<f64> {
+    fn steady_input(&self, output: f64) -> Option<f64> {
+        (self.min <= output && output <= self.max).then_some(output)
+    }
+
+    fn settle(&mut self, input: f64) -> f64 {
+        self.transfer_td(input)
+    }
+}
+
+impl SteadyState for Series<f64> {
+    /// Inverts the enabled elements from the last to the first, `None` if
+    /// one of them has no `SteadyState`
+    fn steady_input(&self, output: f64) -> Option<f64> {
+        self.elements()
+            .iter()
+            .enumerate()
+            .rev()
+            .filter(|(i, _)| self.is_enabled(*i))
+            .try_fold(output, |value, (_, e)| {
+                as_steady_state(&**e)?.steady_input(value)
+            })
+    }
+
+    /// Settles the enabled elements from the first to the last, elements
+    /// without a `SteadyState` are run once with their input
+    fn settle(&mut self, input: f64) -> f64 {
+        let mut value = input;
+        for i in 0..self.len() {
+            if !self.is_enabled(i) {
+                continue;
+            }
+            let e = &mut self.elements_mut()[i];
+            value = match as_steady_state_mut(&mut **e) {
+                Some(element) => element.settle(value),
+                None => e.transfer_td(value),
+            };
+        }
+        value
+    }
+
+    /// Settles the enabled elements from the last to the first
+    fn settle_output(&mut self, output: f64) -> Result<f64, &'static str> {
+        let mut value = output;
+        for i in (0..self.len()).rev() {
+            if !self.is_enabled(i) {
+                continue;
+            }
+            let e = &mut self.elements_mut()[i];
+            let name = e.short_type_name();
+            value = as_steady_state_mut(&mut **e)
+                .ok_or(name)?
+                .settle_output(value)?;
+        }
+        Ok(value)
+    }
+}
+
+/// Downcast `$any` to the first matching element type of this module
+macro_rules! downcast_steady_state {
+    ($any:expr, $downcast:ident, $target:ty) => {{
+        let any = $any;
+        downcast_steady_state!(
+            @types any, $downcast, $target,
+            PT0<f64>, DeadTime<f64>, PT1<f64>, PT2<f64>, PTn<f64>, Saturation<f64>, Series<f64>,
+            Feedback, PhNeutralization, PI<f64>
+        )
+    }};
+    (@types $any:ident, $downcast:ident, $target:ty, $($element:ty),+) => {{
+        $(
+            if $any.is::<$element>() {
+                return $any.$downcast::<$element>().map(|e| e as $target);
+            }
+        )+
+        None
+    }};
+}
+
+/// The `SteadyState` of a boxed element, for the elements of this module
+pub fn as_steady_state(element: &dyn DynTransferTimeDomain<f64>) -> Option<&dyn SteadyState> {
+    downcast_steady_state!(element.as_any(), downcast_ref, &dyn SteadyState)
+}
+
+/// Mutable `SteadyState` of a boxed element, see `as_steady_state`
+pub fn as_steady_state_mut(
+    element: &mut dyn DynTransferTimeDomain<f64>,
+) -> Option<&mut dyn SteadyState> {
+    downcast_steady_state!(element.as_any_mut(), downcast_mut, &mut dyn SteadyState)
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use std::boxed::Box;
+
+    #[test]
+    fn test_SteadyState_elements_hold_output() {
+        let mut elements: [BoxedTransferTimeDomain<f64>; 5] = [
+            Box::new(
+                PT0::<f64>::default()
+                    .set_t0_time_or_default(3.0)
+                    .set_kp(2.0),
+            ),
+            Box::new(DeadTime::<f64>::default().set_t0_time_or_default(2.0)),
+            Box::new(PT2::<f64>::default().set_sample_time_or_default(0.2)),
+            Box::new(PTn::<f64>::default().set_kp(0.5)),
+            Box::new(PT1::<f64>::default().set_t1_time_or_default(4.0)),
+        ];
+        for e in elements.iter_mut() {
+            let input = as_steady_state(&**e).unwrap().steady_input(3.0).unwrap();
+            assert_eq!(as_steady_state_mut(&mut **e).unwrap().settle(input), 3.0);
+            for _ in 0..5 {
+                assert!((e.transfer_td(input) - 3.0).abs() < 1e-12, "{}", e);
+            }
+        }
+    }
+
+    #[test]
+    fn test_SteadyState_Series() {
+        let mut sut = Series::new(vec![
+            Box::new(Saturation::<f64>::default().set_limits_or_default(0.0, 10.0))
+                as BoxedTransferTimeDomain<f64>,
+            Box::new(PT1::<f64>::default().set_kp(4.0)),
+        ]);
+        assert_eq!(sut.steady_input(48.0), None);
+        let input = sut.steady_input(20.0).unwrap();
+        assert_eq!(input, 5.0);
+        assert_eq!(sut.settle(input), 20.0);
+        assert_eq!(sut.transfer_td(input), 20.0);
+        assert_eq!(sut.settle_output(48.0), Err("Saturation"));
+        assert_eq!(sut.settle_output(8.0), Ok(2.0));
+        assert_eq!(sut.transfer_td(2.0), 8.0);
+    }
+
+    #[test]
+    fn test_SteadyState_Feedback() {
+        // proportional loop: y = 2 (r - y)
+        let mut sut = Feedback::unity(Box::new(PT1::<f64>::default().set_kp(2.0)));
+        assert_eq!(sut.steady_input(1.0), Some(1.5));
+        assert!((sut.settle(1.5) - 1.0).abs() < 1e-12);
+        for _ in 0..5 {
+            assert!((sut.transfer_td(1.5) - 1.0).abs() < 1e-12);
+        }
+        // integral control with a delayed sensor of gain 0.5: y = r / 0.5
+        let controller = PI::<f64>::default().set_kp(0.5).set_ti_time_or_default(4.0);
+        let forward = Series::new(vec![
+            Box::new(controller) as BoxedTransferTimeDomain<f64>,
+            Box::new(
+                PT1::<f64>::default()
+                    .set_t1_time_or_default(3.0)
+                    .set_kp(4.0),
+            ),
+        ]);
+        let sensor = PT0::<f64>::default()
+            .set_t0_time_or_default(2.0)
+            .set_kp(0.5);
+        let mut sut = Feedback::new(Box::new(forward), Box::new(sensor));
+        assert_eq!(sut.settle_output(3.0), Ok(1.5));
+        for _ in 0..5 {
+            assert!((sut.transfer_td(1.5) - 3.0).abs() < 1e-12);
+        }
+        assert!((sut.settle(2.0) - 4.0).abs() < 1e-9);
+        assert!((sut.transfer_td(2.0) - 4.0).abs() < 1e-9);
+        // the controller cannot deliver the plant input
+        let limited = PI::<f64>::default().set_output_limits(-1.0, 1.0);
+        let forward = Series::new(vec![
+            Box::new(limited) as BoxedTransferTimeDomain<f64>,
+            Box::new(PT1::<f64>::default()),
+        ]);
+        let mut sut = Feedback::unity(Box::new(forward));
+        assert_eq!(sut.settle_output(2.0), Err("PI"));
+    }
+}
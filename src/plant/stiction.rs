@@ -0,0 +1,219 @@
+//! Stiction of a control valve, the two parameter model of Choudhury et al.
+//!
+//! A sticking valve stands still until the static friction is overcome,
+//! then the stem slips and jumps. With the controller output $u$ as input
+//! and the valve position $x$ as output:
+//!
+//! * while moving, the position lags the input: $ x[k] = u[k] - d (S - J)/2 $
+//!   with the direction of motion $d = \pm 1$
+//! * the valve sticks as soon as the input stops or reverses, at the input $u_s$
+//! * it slips once $ |u[k] - u_s| $ exceeds $S$ after a reversal, or $J$
+//!   when continuing in the direction it moved before
+//!
+//! $S$ is the deadband plus stickband, $J$ the slip jump, both in the unit
+//! of the input, e.g. % of the valve span. $J = 0$ is a pure deadband like
+//! a `Backlash`, the slip jump $J > 0$ is what turns a stiction valve in a
+//! loop with integral action into a limit cycle.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use cb_simulation_util::plant::TransferTimeDomain;
+//! use cb_simulation_util::plant::stiction::Stiction;
+//!
+//! fn main() {
+//!     let mut valve = Stiction::default().set_parameters(4.0, 2.0).unwrap();
+//!     assert_eq!(valve.transfer_td(3.0), 0.0); // stuck
+//!     assert_eq!(valve.transfer_td(6.0), 5.0); // slipped
+//!     assert_eq!(valve.transfer_td(8.0), 7.0); // moving
+//!     assert_eq!(valve.transfer_td(5.0), 7.0); // reversed, stuck again
+//! }
+//! ```
+
+use super::snapshot::StateAccess;
+use super::*;
+use core::fmt::{self, Display};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Stiction {
+    /// Deadband plus stickband $S$
+    pub stickband: f64,
+    /// Slip jump $J$
+    pub slip_jump: f64,
+    previous_input: f64,
+    position: f64,
+    /// Input at which the valve stuck, `None` while moving
+    stick_input: Option<f64>,
+    /// Direction of the latest motion, 0 before the first one
+    direction: f64,
+}
+
+impl Stiction {
+    pub fn set_parameters(self, stickband: f64, slip_jump: f64) -> Result<Self, &'static str> {
+        if stickband >= 0.0 && slip_jump >= 0.0 {
+            Ok(Stiction {
+                stickband,
+                slip_jump,
+                ..self
+            })
+        } else {
+            Err("Invalid stiction: stickband and slip_jump must be >= 0.0")
+        }
+    }
+
+    /// Start at rest at `position`, with the input at the same value
+    pub fn reset(self, position: f64) -> Self {
+        Stiction {
+            previous_input: position,
+            position,
+            stick_input: Some(position),
+            direction: 0.0,
+            ..self
+        }
+    }
+
+    /// Valve position
+    pub fn state(&self) -> f64 {
+        self.position
+    }
+
+    pub fn is_stuck(&self) -> bool {
+        self.stick_input.is_some()
+    }
+}
+
+impl Default for Stiction {
+    /// No friction, at rest at 0
+    fn default() -> Self {
+        Stiction {
+            stickband: 0.0,
+            slip_jump: 0.0,
+            previous_input: 0.0,
+            position: 0.0,
+            stick_input: Some(0.0),
+            direction: 0.0,
+        }
+    }
+}
+
+impl StateAccess for Stiction {
+    /// Previous input, position, input at which the valve stuck and the
+    /// direction of the latest motion
+    type State = (f64, f64, Option<f64>, f64);
+
+    fn save_state(&self) -> Self::State {
+        (
+            self.previous_input,
+            self.position,
+            self.stick_input,
+            self.direction,
+        )
+    }
+
+    fn restore_state(&mut self, state: &Self::State) {
+        (
+            self.previous_input,
+            self.position,
+            self.stick_input,
+            self.direction,
+        ) = *state;
+    }
+}
+
+impl TypeIdentifier for Stiction {
+    fn short_type_name(&self) -> &'static str {
+        "Stiction"
+    }
+}
+
+impl SampleTime for Stiction {
+    fn sample_time(&self) -> Option<f64> {
+        None
+    }
+}
+
+impl Display for Stiction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Stiction(stickband: {}, slip_jump: {})",
+            self.stickband, self.slip_jump
+        )
+    }
+}
+
+impl TransferTimeDomain<f64> for Stiction {
+    fn transfer_td(&mut self, input: f64) -> f64 {
+        let velocity = input - self.previous_input;
+        let moving_on = velocity != 0.0 && velocity.signum() == self.direction;
+        if self.stick_input.is_none() && !moving_on {
+            self.stick_input = Some(self.previous_input);
+        }
+        self.previous_input = input;
+        let offset = 0.5 * (self.stickband - self.slip_jump);
+        match self.stick_input {
+            Some(stick_input) => {
+                let travel = input - stick_input;
+                let threshold = if travel.signum() == self.direction {
+                    self.slip_jump
+                } else {
+                    self.stickband
+                };
+                if travel.abs() > threshold {
+                    self.direction = travel.signum();
+                    self.stick_input = None;
+                    self.position = input - self.direction * offset;
+                }
+            }
+            None => self.position = input - self.direction * offset,
+        }
+        self.position
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_Stiction_without_friction_passes_through() {
+        let mut sut = Stiction::default();
+        for input in [0.5, 1.0, 1.0, -2.0, 3.0] {
+            assert_eq!(sut.transfer_td(input), input);
+        }
+        assert!(Stiction::default().set_parameters(1.0, -1.0).is_err());
+    }
+
+    #[test]
+    fn test_Stiction_slip_jump_on_reversal() {
+        let mut sut = Stiction::default()
+            .set_parameters(4.0, 2.0)
+            .unwrap()
+            .reset(50.0);
+        // fine ramp up and down: stick, then slip by J
+        let mut previous = sut.state();
+        let mut jumps = 0;
+        for k in 0..=4000 {
+            let input = 50.0 + 10.0 * (k as f64 * 0.001 * core::f64::consts::TAU).sin();
+            let position = sut.transfer_td(input);
+            if (position - previous).abs() > 1.0 {
+                // from rest the valve jumps to the middle of S and J
+                let jump = if jumps == 0 { 3.0 } else { 2.0 };
+                assert!(((position - previous).abs() - jump).abs() < 0.1, "{}", k);
+                jumps += 1;
+            }
+            previous = position;
+        }
+        // first slip from rest and one per reversal
+        assert_eq!(jumps, 9);
+        // stopped, then moving on in the same direction needs J only
+        let mut sut = Stiction::default().set_parameters(4.0, 2.0).unwrap();
+        assert_eq!(sut.transfer_td(5.0), 4.0);
+        assert_eq!(sut.transfer_td(5.0), 4.0);
+        assert!(sut.is_stuck());
+        assert_eq!(sut.transfer_td(6.5), 4.0);
+        assert_eq!(sut.transfer_td(7.5), 6.5);
+    }
+}
@@ -0,0 +1,259 @@
+//! # Switch between parallel branches
+//!
+//! Feeds the input into several branches and routes the output of one of
+//! them to the output. The branch is chosen by a selector signal over the
+//! simulation time: its value is rounded to the branch index and clamped to
+//! the available branches. Used for mode switching (e.g. manual / automatic),
+//! redundant sensors and override controllers.
+//!
+//! All branches are updated every sample, so a branch switched to continues
+//! from a current state. With a crossfade time the output blends linearly
+//! from the old to the new branch instead of jumping.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use cb_simulation_util::plant::TransferTimeDomain;
+//! use cb_simulation_util::plant::switch::Switch;
+//! use cb_simulation_util::plant::unit_gain::{Unit, UnitGain};
+//! use cb_simulation_util::signal::StepFunction;
+//!
+//! fn main() {
+//!     let normal = UnitGain::default();
+//!     let doubled = UnitGain::span(Unit::ONE, (0.0, 1.0), Unit::ONE, (0.0, 2.0)).unwrap();
+//!     let mut sut = Switch::new(
+//!         vec![Box::new(normal), Box::new(doubled)],
+//!         Box::new(StepFunction::default().step(2.0)),
+//!     )
+//!     .set_crossfade_time(2.0);
+//!     let output: Vec<f64> = (0..6).map(|_| sut.transfer_td(1.0)).collect();
+//!     assert_eq!(output, [1.0, 1.0, 1.0, 1.5, 2.0, 2.0]);
+//! }
+//! ```
+
+use super::snapshot::{BoxedState, StateAccess, restore_child_state, save_child_state};
+use super::*;
+use crate::signal::BoxedTimeSignal;
+use core::fmt::{self, Display};
+use std::vec::Vec;
+
+#[derive(Debug, Clone)]
+pub struct Switch {
+    pub branches: Vec<BoxedTransferTimeDomain<f64>>,
+    /// Branch index over time
+    pub selector: BoxedTimeSignal<f64>,
+    pub sample_time: f64,
+    /// Duration of the linear blend after switching, 0 switches at once
+    pub crossfade_time: f64,
+    time: f64,
+    active: Option<usize>,
+    previous: usize,
+    fade_elapsed: f64,
+}
+
+impl Switch {
+    pub fn new(
+        branches: Vec<BoxedTransferTimeDomain<f64>>,
+        selector: BoxedTimeSignal<f64>,
+    ) -> Self {
+        Switch {
+            branches,
+            selector,
+            sample_time: 1.0,
+            crossfade_time: 0.0,
+            time: 0.0,
+            active: None,
+            previous: 0,
+            fade_elapsed: 0.0,
+        }
+    }
+
+    pub fn set_sample_time_or_default(self, sample_time: f64) -> Self {
+        if sample_time > 0.0 {
+            Switch {
+                sample_time,
+                ..self
+            }
+        } else {
+            Switch {
+                sample_time: 1.0,
+                ..self
+            }
+        }
+    }
+
+    pub fn set_crossfade_time(self, crossfade_time: f64) -> Self {
+        Switch {
+            crossfade_time: crossfade_time.max(0.0),
+            ..self
+        }
+    }
+
+    /// Index of the branch currently selected
+    pub fn active(&self) -> Option<usize> {
+        self.active
+    }
+
+    fn select(&self, time: f64) -> usize {
+        let index = self.selector.time_to_signal(time).round();
+        (index.max(0.0) as usize).min(self.branches.len().saturating_sub(1))
+    }
+}
+
+impl PartialEq for Switch {
+    fn eq(&self, other: &Self) -> bool {
+        self.branches == other.branches
+            && self.selector.eq(&other.selector)
+            && self.sample_time == other.sample_time
+            && self.crossfade_time == other.crossfade_time
+            && self.time == other.time
+            && self.active == other.active
+            && self.previous == other.previous
+            && self.fade_elapsed == other.fade_elapsed
+    }
+}
+
+impl StateAccess for Switch {
+    /// The states of the branches, the time, the active and the previous
+    /// branch and the time since switching
+    type State = (Vec<Option<BoxedState>>, f64, Option<usize>, usize, f64);
+
+    fn save_state(&self) -> Self::State {
+        (
+            self.branches
+                .iter()
+                .map(|b| save_child_state(&**b))
+                .collect(),
+            self.time,
+            self.active,
+            self.previous,
+            self.fade_elapsed,
+        )
+    }
+
+    /// Ignored for a different number of branches
+    fn restore_state(&mut self, state: &Self::State) {
+        if state.0.len() == self.branches.len() {
+            for (branch, state) in self.branches.iter_mut().zip(&state.0) {
+                restore_child_state(&mut **branch, state);
+            }
+            (self.time, self.active, self.previous, self.fade_elapsed) =
+                (state.1, state.2, state.3, state.4);
+        }
+    }
+}
+
+impl TypeIdentifier for Switch {
+    fn short_type_name(&self) -> &'static str {
+        "Switch"
+    }
+}
+
+impl SampleTime for Switch {
+    fn sample_time(&self) -> Option<f64> {
+        Some(self.sample_time)
+    }
+}
+
+impl Display for Switch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Switch(selector: {}, branches: [", self.selector)?;
+        for (i, branch) in self.branches.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}", branch)?;
+        }
+        write!(f, "], crossfade_time: {})", self.crossfade_time)
+    }
+}
+
+impl TransferTimeDomain<f64> for Switch {
+    fn transfer_td(&mut self, input: f64) -> f64 {
+        if self.branches.is_empty() {
+            return input;
+        }
+        let outputs: Vec<f64> = self
+            .branches
+            .iter_mut()
+            .map(|branch| branch.transfer_td(input))
+            .collect();
+        let selected = self.select(self.time);
+        self.time += self.sample_time;
+        match self.active {
+            Some(active) if active != selected => {
+                self.previous = active;
+                self.fade_elapsed = 0.0;
+            }
+            None => {
+                self.previous = selected;
+                self.fade_elapsed = self.crossfade_time;
+            }
+            _ => {}
+        }
+        self.active = Some(selected);
+        self.fade_elapsed += self.sample_time;
+        if self.fade_elapsed < self.crossfade_time {
+            let weight = self.fade_elapsed / self.crossfade_time;
+            weight * outputs[selected] + (1.0 - weight) * outputs[self.previous]
+        } else {
+            outputs[selected]
+        }
+    }
+
+    fn output_unit(&self, input_unit: &'static str) -> &'static str {
+        self.branches
+            .first()
+            .map_or(input_unit, |b| b.output_unit(input_unit))
+    }
+
+    /// Checks all branches, each gets the input of the switch
+    fn check_units(
+        &self,
+        input_unit: &'static str,
+    ) -> Result<&'static str, unit_gain::UnitCheckError> {
+        let mut output_unit = input_unit;
+        for (i, branch) in self.branches.iter().enumerate() {
+            let unit = branch.check_units(input_unit)?;
+            if i == 0 {
+                output_unit = unit;
+            }
+        }
+        Ok(output_unit)
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::plant::pt1::PT1;
+    use crate::signal::StepFunction;
+    use std::vec;
+
+    #[test]
+    fn test_Switch_branches_stay_warm() {
+        let slow = PT1::<f64>::default().set_t1_time_or_default(2.0);
+        let mut sut = Switch::new(
+            vec![Box::new(unit_gain::UnitGain::default()), Box::new(slow)],
+            Box::new(StepFunction::default().step(1.5)),
+        );
+        assert_eq!(sut.transfer_td(1.0), 1.0);
+        assert_eq!(sut.transfer_td(1.0), 1.0);
+        assert_eq!(sut.active(), Some(0));
+        assert_eq!(sut.transfer_td(1.0), 0.875);
+        assert_eq!(sut.active(), Some(1));
+    }
+
+    #[test]
+    fn test_Switch_selector_clamped() {
+        let mut sut = Switch::new(
+            vec![Box::new(unit_gain::UnitGain::default())],
+            Box::new(StepFunction::default().pre(-3.0).post(7.0)),
+        );
+        assert_eq!(sut.transfer_td(2.0), 2.0);
+        assert_eq!(sut.transfer_td(2.0), 2.0);
+        assert_eq!(sut.clone(), sut);
+    }
+}
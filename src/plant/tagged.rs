@@ -0,0 +1,257 @@
+//! # Tagged serialization of boxed elements
+//!
+//! A `BoxedTransferTimeDomain` hides the type of its element, so serde
+//! cannot (de)serialize it directly. An `ElementRegistry` maps the
+//! `short_type_name` of each registered type to its serde implementation.
+//! A boxed element is written with the name as tag:
+//!
+//! `{"type": "PT1", "element": {"sample_time": 1.0, ...}}`
+//!
+//! A `Series` is tagged `"Series"` with the list of its tagged elements,
+//! bypassed ones marked `"enabled": false`, so chains of mixed elements
+//! round-trip. The tagged form is a `serde_json::Value`, any serde format
+//! can write and read it, e.g. JSON or TOML.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use cb_simulation_util::plant::BoxedTransferTimeDomain;
+//! use cb_simulation_util::plant::pt0::PT0;
+//! use cb_simulation_util::plant::pt1::PT1;
+//! use cb_simulation_util::plant::series::Series;
+//! use cb_simulation_util::plant::tagged::ElementRegistry;
+//!
+//! fn main() {
+//!     let chain = Series::new(vec![
+//!         Box::new(PT1::<f64>::default().set_t1_time_or_default(5.0)) as BoxedTransferTimeDomain<f64>,
+//!         Box::new(PT0::<f64>::default().set_t0_time_or_default(2.0)),
+//!     ]);
+//!     let registry = ElementRegistry::builtin();
+//!     let json = serde_json::to_string(&registry.to_value(&chain).unwrap()).unwrap();
+//!     let restored = registry.from_value(&serde_json::from_str(&json).unwrap()).unwrap();
+//!     assert_eq!(restored.as_any().downcast_ref::<Series<f64>>(), Some(&chain));
+//! }
+//! ```
+
+use core::any::Any;
+use core::fmt::{self, Display};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value};
+use std::boxed::Box;
+use std::format;
+use std::string::{String, ToString};
+use std::vec::Vec;
+
+use super::pt0::PT0;
+use super::pt1::PT1;
+use super::pt2::PT2;
+use super::saturation::Saturation;
+use super::series::Series;
+use super::*;
+
+const TYPE_KEY: &str = "type";
+const ELEMENT_KEY: &str = "element";
+const ENABLED_KEY: &str = "enabled";
+const SERIES_TAG: &str = "Series";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TaggedError {
+    /// No type is registered for the tag
+    Unregistered(String),
+    /// The value does not describe an element of the tagged type
+    Malformed(String),
+}
+
+impl Display for TaggedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TaggedError::Unregistered(tag) => write!(f, "No element type registered as {}", tag),
+            TaggedError::Malformed(reason) => write!(f, "Malformed element: {}", reason),
+        }
+    }
+}
+
+type ToValue = fn(&dyn Any) -> Option<Result<Value, serde_json::Error>>;
+type FromValue<S> = fn(Value) -> Result<BoxedTransferTimeDomain<S>, serde_json::Error>;
+
+struct Entry<S> {
+    tag: &'static str,
+    to_value: ToValue,
+    from_value: FromValue<S>,
+}
+
+/// Element types by their `short_type_name`, see the module documentation
+pub struct ElementRegistry<S> {
+    entries: Vec<Entry<S>>,
+}
+
+impl<S: Debug + Display + Clone + Copy + PartialEq + Sized + 'static + Send + Sync>
+    ElementRegistry<S>
+{
+    /// Empty registry, `Series` is always supported
+    pub fn new() -> Self {
+        ElementRegistry {
+            entries: Vec::new(),
+        }
+    }
+
+    /// Register `T` under `tag`, which must be the `short_type_name` of `T`
+    ///
+    /// A type registered before under the same tag is replaced.
+    pub fn register<T>(&mut self, tag: &'static str)
+    where
+        T: DynTransferTimeDomain<S> + Serialize + DeserializeOwned,
+    {
+        self.entries.retain(|entry| entry.tag != tag);
+        self.entries.push(Entry {
+            tag,
+            to_value: |any| any.downcast_ref::<T>().map(serde_json::to_value),
+            from_value: |value| {
+                serde_json::from_value::<T>(value)
+                    .map(|e| Box::new(e) as BoxedTransferTimeDomain<S>)
+            },
+        });
+    }
+
+    /// Registered tags in the order of registration
+    pub fn tags(&self) -> Vec<&'static str> {
+        self.entries.iter().map(|entry| entry.tag).collect()
+    }
+
+    fn entry(&self, tag: &str) -> Result<&Entry<S>, TaggedError> {
+        self.entries
+            .iter()
+            .find(|entry| entry.tag == tag)
+            .ok_or_else(|| TaggedError::Unregistered(String::from(tag)))
+    }
+
+    /// Tagged value of `element`, nested for a `Series`
+    pub fn to_value(&self, element: &dyn DynTransferTimeDomain<S>) -> Result<Value, TaggedError> {
+        let tag = element.short_type_name();
+        let any = element.as_any();
+        let body = match any.downcast_ref::<Series<S>>() {
+            Some(series) => {
+                let mut elements = Vec::with_capacity(series.len());
+                for (i, e) in series.elements().iter().enumerate() {
+                    let mut value = self.to_value(&**e)?;
+                    if !series.is_enabled(i) {
+                        value[ENABLED_KEY] = Value::Bool(false);
+                    }
+                    elements.push(value);
+                }
+                Value::Array(elements)
+            }
+            None => (self.entry(tag)?.to_value)(any)
+                .ok_or_else(|| {
+                    TaggedError::Malformed(format!("{} is not of the registered type", tag))
+                })?
+                .map_err(|e| TaggedError::Malformed(e.to_string()))?,
+        };
+        let mut tagged = Map::new();
+        tagged.insert(String::from(TYPE_KEY), Value::from(tag));
+        tagged.insert(String::from(ELEMENT_KEY), body);
+        Ok(Value::Object(tagged))
+    }
+
+    /// Element of a tagged value, see `to_value`
+    pub fn from_value(&self, value: &Value) -> Result<BoxedTransferTimeDomain<S>, TaggedError> {
+        let tag = value
+            .get(TYPE_KEY)
+            .and_then(Value::as_str)
+            .ok_or_else(|| TaggedError::Malformed(format!("missing \"{}\"", TYPE_KEY)))?;
+        let body = value
+            .get(ELEMENT_KEY)
+            .ok_or_else(|| TaggedError::Malformed(format!("missing \"{}\"", ELEMENT_KEY)))?;
+        if tag == SERIES_TAG {
+            let elements = body.as_array().ok_or_else(|| {
+                TaggedError::Malformed(String::from("Series elements must be a list"))
+            })?;
+            let mut series = Series::<S>::default();
+            for (i, e) in elements.iter().enumerate() {
+                series.push(self.from_value(e)?);
+                if e.get(ENABLED_KEY) == Some(&Value::Bool(false)) {
+                    series.set_enabled(i, false);
+                }
+            }
+            return Ok(Box::new(series));
+        }
+        (self.entry(tag)?.from_value)(body.clone())
+            .map_err(|e| TaggedError::Malformed(format!("{}: {}", tag, e)))
+    }
+}
+
+impl<S: Debug + Display + Clone + Copy + PartialEq + Sized + 'static + Send + Sync> Default
+    for ElementRegistry<S>
+{
+    fn default() -> Self {
+        ElementRegistry::new()
+    }
+}
+
+impl ElementRegistry<f64> {
+    /// Registry of the elements of this crate with serde support
+    pub fn builtin() -> Self {
+        let mut registry = ElementRegistry::new();
+        registry.register::<PT0<f64>>("PT0");
+        registry.register::<PT1<f64>>("PT1");
+        registry.register::<PT2<f64>>("PT2");
+        registry.register::<Saturation<f64>>("Saturation");
+        registry
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use std::vec;
+
+    #[test]
+    fn test_ElementRegistry_round_trips_nested_series() {
+        let mut inner = Series::<f64>::default();
+        inner.push(Box::new(
+            PT2::<f64>::default()
+                .set_sample_time_or_default(0.1)
+                .set_omega_or_default(3.0),
+        ));
+        let mut sut = Series::new(vec![
+            Box::new(Saturation::<f64>::default().set_limits_or_default(-1.0, 1.0))
+                as BoxedTransferTimeDomain<f64>,
+            Box::new(inner),
+            Box::new(PT0::<f64>::default().set_t0_time_or_default(3.0)),
+        ]);
+        sut.set_enabled(2, false);
+        for _ in 0..5 {
+            sut.transfer_td(2.0);
+        }
+        let registry = ElementRegistry::builtin();
+        let json = serde_json::to_string(&registry.to_value(&sut).unwrap()).unwrap();
+        assert!(json.starts_with("{\"element\":[{\"element\":{"), "{}", json);
+        let mut restored = registry
+            .from_value(&serde_json::from_str(&json).unwrap())
+            .unwrap();
+        assert_eq!(restored.as_any().downcast_ref::<Series<f64>>(), Some(&sut));
+        assert_eq!(restored.transfer_td(0.5), sut.transfer_td(0.5));
+    }
+
+    #[test]
+    fn test_ElementRegistry_errors() {
+        let registry = ElementRegistry::<f64>::builtin();
+        let integrator = crate::plant::integrator::Integrator::<f64>::default();
+        assert_eq!(
+            registry.to_value(&integrator),
+            Err(TaggedError::Unregistered(String::from("Integrator")))
+        );
+        let value: Value = serde_json::from_str(r#"{"type": "PT1", "element": 3}"#).unwrap();
+        assert!(matches!(
+            registry.from_value(&value),
+            Err(TaggedError::Malformed(_))
+        ));
+        let mut sut = ElementRegistry::<f64>::new();
+        sut.register::<PT1<f64>>("PT1");
+        sut.register::<PT1<f64>>("PT1");
+        assert_eq!(sut.tags(), vec!["PT1"]);
+    }
+}
@@ -0,0 +1,297 @@
+//! A thermal RC network of one or two nodes, e.g. a heated room
+//!
+//! $ C_{1} \dot{T}_{1} = P - (T_{1} - T_{2}) / R_{1} $
+//!
+//! $ C_{2} \dot{T}_{2} = (T_{1} - T_{2}) / R_{1} - (T_{2} - T_{a}) / R_{2} $
+//!
+//! where $C_{i}$ is the heat capacity of node $i$ [J/K], $R_{i}$ the thermal
+//! resistance to the next node [K/W], $P$ the heating power in W and $T_{a}$
+//! the ambient temperature. The heated node 1 is e.g. the room air, the outer
+//! node 2 the building envelope. Without an outer node the heated node loses
+//! its heat directly: $ C_{1} \dot{T}_{1} = P - (T_{1} - T_{a}) / R_{1} $.
+//!
+//! As `MimoTransferTimeDomain` the inputs are `[power, ambient]` and the
+//! outputs the node temperatures. As `TransferTimeDomain` the input is the
+//! power, the output the temperature of the heated node and the ambient is
+//! the constant `ambient`, so the plant can be driven by the signal
+//! generators directly. The linear model is discretized with a zero-order
+//! hold.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use cb_simulation_util::plant::TransferTimeDomain;
+//! use cb_simulation_util::plant::thermal_rc::ThermalRC;
+//!
+//! fn main() {
+//!     // room air 0.5 MJ/K, envelope 10 MJ/K, 20 °C outside
+//!     let mut room = ThermalRC::default()
+//!         .set_node(0.5e6, 0.002)
+//!         .set_outer_node(10.0e6, 0.008)
+//!         .set_ambient(20.0)
+//!         .set_sample_time_or_default(600.0);
+//!     room.set_temperatures(20.0);
+//!     let mut temperature = 20.0;
+//!     for _ in 0..2000 {
+//!         temperature = room.transfer_td(1000.0);
+//!     }
+//!     // 1 kW through 0.01 K/W
+//!     assert!((temperature - room.steady_state(1000.0)).abs() < 1e-3);
+//!     assert!((temperature - 30.0).abs() < 1e-3);
+//! }
+//! ```
+
+use ndarray::{Array1, Array2, ArrayView1, array};
+use std::vec;
+
+use super::snapshot::StateAccess;
+use super::state_space::StateSpace;
+use super::*;
+use core::fmt::{self, Display};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThermalRC {
+    /// Heat capacity of the heated node in J/K
+    pub capacity: f64,
+    /// Thermal resistance of the heated node to the outer node or, without
+    /// one, to ambient in K/W
+    pub resistance: f64,
+    /// Heat capacity and thermal resistance to ambient of the outer node
+    pub outer_node: Option<(f64, f64)>,
+    /// Ambient temperature of the single input transfer function
+    pub ambient: f64,
+    model: StateSpace,
+}
+
+impl ThermalRC {
+    /// Continuous model, the inputs are `[power, ambient]`
+    fn continuous(&self) -> (Array2<f64>, Array2<f64>) {
+        let (c1, r1) = (self.capacity, self.resistance);
+        match self.outer_node {
+            None => (
+                array![[-1.0 / (r1 * c1)]],
+                array![[1.0 / c1, 1.0 / (r1 * c1)]],
+            ),
+            Some((c2, r2)) => (
+                array![
+                    [-1.0 / (r1 * c1), 1.0 / (r1 * c1)],
+                    [1.0 / (r1 * c2), -(1.0 / r1 + 1.0 / r2) / c2]
+                ],
+                array![[1.0 / c1, 0.0], [0.0, 1.0 / (r2 * c2)]],
+            ),
+        }
+    }
+
+    /// Rediscretize after a parameter change, the node temperatures are kept
+    fn rebuild(self, sample_time: f64) -> Self {
+        let (a, b) = self.continuous();
+        let nodes = a.nrows();
+        // a new outer node starts at the temperature of the heated node
+        let previous = self.model.state();
+        let state =
+            Array1::from_shape_fn(nodes, |i| previous.get(i).copied().unwrap_or(previous[0]));
+        let mut model = StateSpace::from_continuous(
+            a,
+            b,
+            Array2::eye(nodes),
+            Array2::zeros((nodes, 2)),
+            sample_time,
+        )
+        .expect("at most 2 nodes, 2 inputs always fit");
+        model.set_state(state);
+        ThermalRC { model, ..self }
+    }
+
+    pub fn set_sample_time_or_default(self, sample_time: f64) -> Self {
+        let sample_time = if sample_time > 0.0 { sample_time } else { 1.0 };
+        self.rebuild(sample_time)
+    }
+
+    /// Capacity and resistance of the heated node, non-positive values are ignored
+    pub fn set_node(self, capacity: f64, resistance: f64) -> Self {
+        if capacity <= 0.0 || resistance <= 0.0 {
+            return self;
+        }
+        let sample_time = self.model.sample_time;
+        ThermalRC {
+            capacity,
+            resistance,
+            ..self
+        }
+        .rebuild(sample_time)
+    }
+
+    /// Add an outer node between the heated node and ambient, non-positive
+    /// values are ignored
+    pub fn set_outer_node(self, capacity: f64, resistance: f64) -> Self {
+        if capacity <= 0.0 || resistance <= 0.0 {
+            return self;
+        }
+        let sample_time = self.model.sample_time;
+        ThermalRC {
+            outer_node: Some((capacity, resistance)),
+            ..self
+        }
+        .rebuild(sample_time)
+    }
+
+    /// Back to a one-node network
+    pub fn clear_outer_node(self) -> Self {
+        let sample_time = self.model.sample_time;
+        ThermalRC {
+            outer_node: None,
+            ..self
+        }
+        .rebuild(sample_time)
+    }
+
+    pub fn set_ambient(self, ambient: f64) -> Self {
+        ThermalRC { ambient, ..self }
+    }
+
+    /// Number of nodes, 1 or 2
+    pub fn nodes(&self) -> usize {
+        self.model.states()
+    }
+
+    /// Node temperatures, the heated node first
+    pub fn temperatures(&self) -> &Array1<f64> {
+        self.model.state()
+    }
+
+    /// Start with all nodes at `temperature`
+    pub fn set_temperatures(&mut self, temperature: f64) {
+        self.model
+            .set_state(Array1::from_elem(self.nodes(), temperature));
+    }
+
+    /// Thermal resistance of the heated node to ambient in K/W
+    pub fn total_resistance(&self) -> f64 {
+        self.resistance + self.outer_node.map_or(0.0, |(_, r2)| r2)
+    }
+
+    /// Temperature of the heated node reached for constant `power` at `ambient`
+    pub fn steady_state(&self, power: f64) -> f64 {
+        self.ambient + power * self.total_resistance()
+    }
+}
+
+impl Default for ThermalRC {
+    /// One node: a room of 1 MJ/K losing 100 W/K
+    fn default() -> Self {
+        ThermalRC {
+            capacity: 1.0e6,
+            resistance: 0.01,
+            outer_node: None,
+            ambient: 0.0,
+            model: StateSpace::new(
+                Array2::eye(1),
+                Array2::zeros((1, 2)),
+                Array2::eye(1),
+                Array2::zeros((1, 2)),
+            )
+            .expect("at most 2 nodes, 2 inputs always fit"),
+        }
+        .rebuild(1.0)
+    }
+}
+
+impl StateAccess for ThermalRC {
+    type State = Array1<f64>;
+
+    fn save_state(&self) -> Self::State {
+        self.model.save_state()
+    }
+
+    fn restore_state(&mut self, state: &Self::State) {
+        self.model.restore_state(state);
+    }
+}
+
+impl TypeIdentifier for ThermalRC {
+    fn short_type_name(&self) -> &'static str {
+        "ThermalRC"
+    }
+}
+
+impl SampleTime for ThermalRC {
+    fn sample_time(&self) -> Option<f64> {
+        Some(self.model.sample_time)
+    }
+}
+
+impl Display for ThermalRC {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "ThermalRC(sample_time: {}, capacity: {}, resistance: {}",
+            self.model.sample_time, self.capacity, self.resistance
+        )?;
+        if let Some((capacity, resistance)) = self.outer_node {
+            write!(
+                f,
+                ", outer_capacity: {}, outer_resistance: {}",
+                capacity, resistance
+            )?;
+        }
+        write!(f, ", ambient: {})", self.ambient)
+    }
+}
+
+impl MimoTransferTimeDomain for ThermalRC {
+    fn input_count(&self) -> usize {
+        2
+    }
+
+    fn output_count(&self) -> usize {
+        self.nodes()
+    }
+
+    fn transfer_td(&mut self, u: ArrayView1<f64>) -> Array1<f64> {
+        self.model.transfer_td(u)
+    }
+}
+
+impl TransferTimeDomain<f64> for ThermalRC {
+    fn transfer_td(&mut self, input: f64) -> f64 {
+        let u = array![input, self.ambient];
+        MimoTransferTimeDomain::transfer_td(&mut self.model, u.view())[0]
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_ThermalRC_one_node_time_constant() {
+        // tau = R C = 1e4 s
+        let mut sut = ThermalRC::default().set_sample_time_or_default(100.0);
+        for _ in 0..100 {
+            TransferTimeDomain::transfer_td(&mut sut, 1000.0);
+        }
+        let expected = 10.0 * (1.0 - (-1.0f64).exp());
+        assert!((sut.temperatures()[0] - expected).abs() < 1e-9);
+        assert_eq!(sut.nodes(), 1);
+    }
+
+    #[test]
+    fn test_ThermalRC_ambient_input() {
+        let mut sut = ThermalRC::default()
+            .set_outer_node(5.0e6, 0.02)
+            .set_sample_time_or_default(600.0);
+        assert_eq!(sut.output_count(), 2);
+        let mut y = array![0.0, 0.0];
+        for _ in 0..2000 {
+            y = MimoTransferTimeDomain::transfer_td(&mut sut, array![0.0, -5.0].view());
+        }
+        // no heating: both nodes settle at ambient
+        assert!((y[0] + 5.0).abs() < 1e-3 && (y[1] + 5.0).abs() < 1e-3);
+        let sut = sut.clear_outer_node();
+        assert_eq!(sut.temperatures().len(), 1);
+        assert!((sut.temperatures()[0] + 5.0).abs() < 1e-3);
+        assert_eq!(sut.set_node(-1.0, 0.1).capacity, 1.0e6);
+    }
+}
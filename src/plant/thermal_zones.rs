@@ -0,0 +1,253 @@
+//! Two coupled thermal zones, each with its own heater
+//!
+//! $ C_{1} \dot{T}_{1} = P_{1} - G_{1} (T_{1} - T_{a}) - G_{c} (T_{1} - T_{2}) $
+//!
+//! $ C_{2} \dot{T}_{2} = P_{2} - G_{2} (T_{2} - T_{a}) - G_{c} (T_{2} - T_{1}) $
+//!
+//! where $C_{i}$ is the heat capacity of zone $i$ [J/K], $G_{i}$ its loss
+//! conductance to ambient [W/K], $G_{c}$ the conductance between both zones
+//! and $T_{a}$ the ambient temperature.
+//!
+//! Inputs are the heater powers $P_{1}, P_{2}$ in W, outputs the zone
+//! temperatures. Each heater acts on both zones through the coupling, which
+//! makes it a reference plant for decoupling controller designs.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use ndarray::array;
+//! use cb_simulation_util::plant::MimoTransferTimeDomain;
+//! use cb_simulation_util::plant::thermal_zones::TwoZoneThermal;
+//!
+//! fn main() {
+//!     let mut sut = TwoZoneThermal::default()
+//!         .set_ambient(20.0)
+//!         .set_sample_time_or_default(600.0);
+//!     let mut y = array![20.0, 20.0];
+//!     for _ in 0..2000 {
+//!         y = sut.transfer_td(array![100.0, 0.0].view());
+//!     }
+//!     let steady = sut.steady_state(array![100.0, 0.0].view());
+//!     assert!((y[0] - steady[0]).abs() < 1e-6);
+//!     // the unheated zone warms up by the coupling only
+//!     assert!(y[1] > 20.0 && y[1] < y[0]);
+//! }
+//! ```
+
+use ndarray::{Array1, Array2, ArrayView1, array};
+
+use super::snapshot::StateAccess;
+use super::state_space::StateSpace;
+use super::*;
+use crate::linalg;
+use core::fmt::{self, Display};
+use std::vec;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TwoZoneThermal {
+    capacity: [f64; 2],
+    loss_conductance: [f64; 2],
+    coupling_conductance: f64,
+    ambient: f64,
+    model: StateSpace,
+}
+
+impl TwoZoneThermal {
+    fn continuous(&self) -> (Array2<f64>, Array2<f64>) {
+        let [c1, c2] = self.capacity;
+        let [g1, g2] = self.loss_conductance;
+        let gc = self.coupling_conductance;
+        (
+            array![[-(g1 + gc) / c1, gc / c1], [gc / c2, -(g2 + gc) / c2]],
+            array![[1.0 / c1, 0.0], [0.0, 1.0 / c2]],
+        )
+    }
+
+    /// Rediscretize after a parameter change, the zone temperatures are kept
+    fn rebuild(self, sample_time: f64) -> Self {
+        let (a, b) = self.continuous();
+        let state = self.model.state().clone();
+        let mut model =
+            StateSpace::from_continuous(a, b, Array2::eye(2), Array2::zeros((2, 2)), sample_time)
+                .expect("2x2 matrices always fit");
+        model.set_state(state);
+        TwoZoneThermal { model, ..self }
+    }
+
+    pub fn set_sample_time_or_default(self, sample_time: f64) -> Self {
+        let sample_time = if sample_time > 0.0 { sample_time } else { 1.0 };
+        self.rebuild(sample_time)
+    }
+
+    /// Heat capacities of both zones in J/K, non-positive values are ignored
+    pub fn set_capacities(self, zone1: f64, zone2: f64) -> Self {
+        if zone1 <= 0.0 || zone2 <= 0.0 {
+            return self;
+        }
+        let sample_time = self.model.sample_time;
+        TwoZoneThermal {
+            capacity: [zone1, zone2],
+            ..self
+        }
+        .rebuild(sample_time)
+    }
+
+    /// Loss conductances of both zones to ambient in W/K, negative values are ignored
+    pub fn set_loss_conductances(self, zone1: f64, zone2: f64) -> Self {
+        if zone1 < 0.0 || zone2 < 0.0 {
+            return self;
+        }
+        let sample_time = self.model.sample_time;
+        TwoZoneThermal {
+            loss_conductance: [zone1, zone2],
+            ..self
+        }
+        .rebuild(sample_time)
+    }
+
+    /// Conductance between both zones in W/K, negative values are ignored
+    pub fn set_coupling_conductance(self, coupling_conductance: f64) -> Self {
+        if coupling_conductance < 0.0 {
+            return self;
+        }
+        let sample_time = self.model.sample_time;
+        TwoZoneThermal {
+            coupling_conductance,
+            ..self
+        }
+        .rebuild(sample_time)
+    }
+
+    pub fn set_ambient(self, ambient: f64) -> Self {
+        TwoZoneThermal { ambient, ..self }
+    }
+
+    /// Zone temperatures
+    pub fn temperatures(&self) -> Array1<f64> {
+        self.model.state() + self.ambient
+    }
+
+    /// Steady state gain from heater power to temperature rise above ambient in K/W
+    pub fn gain_matrix(&self) -> Array2<f64> {
+        let (a, b) = self.continuous();
+        linalg::inverse(&a).map_or(Array2::from_elem((2, 2), f64::INFINITY), |inv| -inv.dot(&b))
+    }
+
+    /// Zone temperatures reached for constant heater powers
+    pub fn steady_state(&self, power: ArrayView1<f64>) -> Array1<f64> {
+        self.gain_matrix().dot(&power) + self.ambient
+    }
+}
+
+impl Default for TwoZoneThermal {
+    /// Two rooms of 1 MJ/K each, 50 W/K losses and 20 W/K between them
+    fn default() -> Self {
+        TwoZoneThermal {
+            capacity: [1.0e6, 1.0e6],
+            loss_conductance: [50.0, 50.0],
+            coupling_conductance: 20.0,
+            ambient: 0.0,
+            model: StateSpace::new(
+                Array2::eye(2),
+                Array2::zeros((2, 2)),
+                Array2::eye(2),
+                Array2::zeros((2, 2)),
+            )
+            .expect("2x2 matrices always fit"),
+        }
+        .rebuild(1.0)
+    }
+}
+
+impl StateAccess for TwoZoneThermal {
+    /// Both zone temperatures
+    type State = Array1<f64>;
+
+    fn save_state(&self) -> Self::State {
+        self.model.save_state()
+    }
+
+    fn restore_state(&mut self, state: &Self::State) {
+        self.model.restore_state(state);
+    }
+}
+
+impl TypeIdentifier for TwoZoneThermal {
+    fn short_type_name(&self) -> &'static str {
+        "TwoZoneThermal"
+    }
+}
+
+impl SampleTime for TwoZoneThermal {
+    fn sample_time(&self) -> Option<f64> {
+        Some(self.model.sample_time)
+    }
+}
+
+impl Display for TwoZoneThermal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "TwoZoneThermal(sample_time: {}, capacity: {:?}, loss_conductance: {:?}, coupling_conductance: {}, ambient: {})",
+            self.model.sample_time,
+            self.capacity,
+            self.loss_conductance,
+            self.coupling_conductance,
+            self.ambient
+        )
+    }
+}
+
+impl MimoTransferTimeDomain for TwoZoneThermal {
+    fn input_count(&self) -> usize {
+        2
+    }
+
+    fn output_count(&self) -> usize {
+        2
+    }
+
+    fn transfer_td(&mut self, u: ArrayView1<f64>) -> Array1<f64> {
+        self.model.transfer_td(u) + self.ambient
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_TwoZoneThermal_gain_matrix_symmetric() {
+        let sut = TwoZoneThermal::default();
+        let k = sut.gain_matrix();
+        assert!((k[[0, 1]] - k[[1, 0]]).abs() < 1e-12);
+        // (G + Gc) / ((G + Gc)^2 - Gc^2) with G = 50, Gc = 20
+        assert!((k[[0, 0]] - 70.0 / 4500.0).abs() < 1e-12);
+        assert!((k[[0, 1]] - 20.0 / 4500.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_TwoZoneThermal_uncoupled() {
+        let mut sut = TwoZoneThermal::default()
+            .set_coupling_conductance(0.0)
+            .set_sample_time_or_default(60.0);
+        for _ in 0..100 {
+            sut.transfer_td(array![1000.0, 0.0].view());
+        }
+        assert!(sut.temperatures()[0] > 0.0);
+        assert_eq!(sut.temperatures()[1], 0.0);
+    }
+
+    #[test]
+    fn test_TwoZoneThermal_keeps_temperature_on_parameter_change() {
+        let mut sut = TwoZoneThermal::default().set_sample_time_or_default(60.0);
+        for _ in 0..10 {
+            sut.transfer_td(array![1000.0, 500.0].view());
+        }
+        let before = sut.temperatures();
+        let sut = sut.set_capacities(2.0e6, 2.0e6);
+        assert_eq!(sut.temperatures(), before);
+    }
+}
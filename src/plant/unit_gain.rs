@@ -0,0 +1,396 @@
+//! A gain element converting between declared units of measurement
+//!
+//! $ out[k] = m * in[k] + n $
+//!
+//! where $m$ and $n$ are derived from the input and output unit.
+//!
+//! Units of the same quantity (e.g. °C → K) are converted exactly.
+//! Units of different quantities (e.g. % → mA) need an explicit span,
+//! like a 4..20 mA transmitter mapping 0..100 %.
+//!
+//! Units are declared at runtime, a `Unit` holds the symbol and the
+//! conversion into the SI base unit of its quantity. With the `uom` feature
+//! the units of the `uom` crate can be used, e.g.
+//! `Unit::pressure::<uom::si::pressure::bar>()`, other units are declared
+//! with `Unit::new`.
+//!
+//! Composing two gains with `then` fails when the output unit of the first
+//! does not match the input unit of the second, so unit bugs show up while
+//! building the model and not as wrong numbers after the simulation.
+//! Gains within a `Series`, `Feedback` or other composite are checked with
+//! `TransferTimeDomain::check_units`, which compares the unit symbols along
+//! the signal path. Elements which do not declare units pass any unit.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use cb_simulation_util::plant::TransferTimeDomain;
+//! use cb_simulation_util::plant::unit_gain::{Quantity, Unit, UnitGain};
+//!
+//! fn main() {
+//!     let percent = Unit::new("%", Quantity::Ratio, 0.01, 0.0);
+//!     let milli_ampere = Unit::new("mA", Quantity::Current, 1.0e-3, 0.0);
+//!     let mut transmitter =
+//!         UnitGain::span(percent, (0.0, 100.0), milli_ampere, (4.0, 20.0)).unwrap();
+//!     assert_eq!(transmitter.transfer_td(50.0), 12.0);
+//!
+//!     let celsius = Unit::new("°C", Quantity::Temperature, 1.0, 273.15);
+//!     let kelvin = Unit::new("K", Quantity::Temperature, 1.0, 0.0);
+//!     let mut to_kelvin = UnitGain::convert(celsius, kelvin).unwrap();
+//!     assert_eq!(to_kelvin.transfer_td(0.0), 273.15);
+//!
+//!     assert!(transmitter.then(to_kelvin).is_err());
+//!     assert_eq!(transmitter.check_units("%"), Ok("mA"));
+//!     assert!(to_kelvin.check_units("%").is_err());
+//! }
+//! ```
+//!
+//! The same with the `uom` feature:
+//!
+//! ```rust
+//! # #[cfg(feature = "uom")]
+//! # {
+//! use cb_simulation_util::plant::TransferTimeDomain;
+//! use cb_simulation_util::plant::unit_gain::{Unit, UnitGain};
+//! use uom::si::{thermodynamic_temperature as temperature, pressure};
+//!
+//! let mut to_kelvin = UnitGain::convert(
+//!     Unit::temperature::<temperature::degree_celsius>(),
+//!     Unit::temperature::<temperature::kelvin>(),
+//! )
+//! .unwrap();
+//! assert_eq!(to_kelvin.transfer_td(0.0), 273.15);
+//! assert_eq!(Unit::pressure::<pressure::bar>().symbol, "bar");
+//! # }
+//! ```
+
+use super::*;
+use core::fmt::{self, Display};
+
+/// Physical quantity a unit belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Quantity {
+    Ratio,
+    Current,
+    Voltage,
+    Temperature,
+    Pressure,
+    Length,
+    Time,
+}
+
+/// A unit of measurement
+///
+/// `si = value * scale + offset` converts a value into the SI base unit of the quantity
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Unit {
+    pub symbol: &'static str,
+    pub quantity: Quantity,
+    pub scale: f64,
+    pub offset: f64,
+}
+
+impl Unit {
+    /// The dimensionless unit 1
+    pub const ONE: Unit = Unit::new("1", Quantity::Ratio, 1.0, 0.0);
+
+    pub const fn new(symbol: &'static str, quantity: Quantity, scale: f64, offset: f64) -> Self {
+        Unit {
+            symbol,
+            quantity,
+            scale,
+            offset,
+        }
+    }
+
+    /// Unit of the `uom` unit `U`, `uom` converts with `si = (value + constant) * coefficient`
+    #[cfg(feature = "uom")]
+    fn of_uom<U>(quantity: Quantity) -> Self
+    where
+        U: uom::si::Unit + uom::Conversion<f64, T = f64>,
+    {
+        Unit::new(
+            U::abbreviation(),
+            quantity,
+            U::coefficient(),
+            U::constant(uom::ConstantOp::Add) * U::coefficient(),
+        )
+    }
+}
+
+/// Constructors of the units of the `uom` quantities
+#[cfg(feature = "uom")]
+macro_rules! uom_quantities {
+    ($($name:ident: $module:ident => $quantity:ident),* $(,)?) => {
+        impl Unit {
+            $(
+                #[doc = concat!("Unit of the `uom::si::", stringify!($module), "` unit `U`")]
+                pub fn $name<U: uom::si::$module::Conversion<f64>>() -> Self {
+                    Unit::of_uom::<U>(Quantity::$quantity)
+                }
+            )*
+        }
+    };
+}
+
+#[cfg(feature = "uom")]
+uom_quantities!(
+    ratio: ratio => Ratio,
+    current: electric_current => Current,
+    voltage: electric_potential => Voltage,
+    temperature: thermodynamic_temperature => Temperature,
+    pressure: pressure => Pressure,
+    length: length => Length,
+    time: time => Time,
+);
+
+impl Display for Unit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.symbol)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UnitMismatchError {
+    pub expected: Unit,
+    pub found: Unit,
+}
+
+impl Display for UnitMismatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Unit mismatch: expected {} ({:?}), found {} ({:?})",
+            self.expected, self.expected.quantity, self.found, self.found.quantity
+        )
+    }
+}
+
+/// An element got another unit than it declares as input
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UnitCheckError {
+    /// Short type name of the element
+    pub element: &'static str,
+    pub expected: &'static str,
+    pub found: &'static str,
+}
+
+impl Display for UnitCheckError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Unit mismatch at {}: expected {}, found {}",
+            self.element, self.expected, self.found
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UnitGain {
+    pub input: Unit,
+    pub output: Unit,
+    m: f64,
+    n: f64,
+}
+
+impl UnitGain {
+    /// Exact conversion between two units of the same quantity
+    pub fn convert(input: Unit, output: Unit) -> Result<Self, UnitMismatchError> {
+        if input.quantity != output.quantity {
+            return Err(UnitMismatchError {
+                expected: input,
+                found: output,
+            });
+        }
+        Ok(UnitGain {
+            input,
+            output,
+            m: input.scale / output.scale,
+            n: (input.offset - output.offset) / output.scale,
+        })
+    }
+
+    /// Linear mapping of the input span onto the output span, units may differ in quantity
+    ///
+    /// Fails for an empty input span, which has no linear mapping.
+    pub fn span(
+        input: Unit,
+        input_span: (f64, f64),
+        output: Unit,
+        output_span: (f64, f64),
+    ) -> Result<Self, &'static str> {
+        let m = (output_span.1 - output_span.0) / (input_span.1 - input_span.0);
+        let n = output_span.0 - m * input_span.0;
+        if !m.is_finite() || !n.is_finite() {
+            return Err("Invalid span: Input span must not be empty, spans must be finite");
+        }
+        Ok(UnitGain {
+            input,
+            output,
+            m,
+            n,
+        })
+    }
+
+    /// Compose with a following gain, fails if the units do not match
+    pub fn then(self, next: UnitGain) -> Result<Self, UnitMismatchError> {
+        if self.output != next.input {
+            return Err(UnitMismatchError {
+                expected: self.output,
+                found: next.input,
+            });
+        }
+        Ok(UnitGain {
+            input: self.input,
+            output: next.output,
+            m: next.m * self.m,
+            n: next.m * self.n + next.n,
+        })
+    }
+}
+
+impl Default for UnitGain {
+    fn default() -> Self {
+        UnitGain {
+            input: Unit::ONE,
+            output: Unit::ONE,
+            m: 1.0,
+            n: 0.0,
+        }
+    }
+}
+
+impl TypeIdentifier for UnitGain {
+    fn short_type_name(&self) -> &'static str {
+        "UnitGain"
+    }
+}
+
+impl SampleTime for UnitGain {
+    fn sample_time(&self) -> Option<f64> {
+        None
+    }
+}
+
+impl Display for UnitGain {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "UnitGain(input: {}, output: {}, m: {}, n: {})",
+            self.input, self.output, self.m, self.n
+        )
+    }
+}
+
+impl TransferTimeDomain<f64> for UnitGain {
+    fn transfer_td(&mut self, input: f64) -> f64 {
+        self.m * input + self.n
+    }
+
+    fn output_unit(&self, _input_unit: &'static str) -> &'static str {
+        self.output.symbol
+    }
+
+    fn check_units(&self, input_unit: &'static str) -> Result<&'static str, UnitCheckError> {
+        if input_unit != self.input.symbol {
+            return Err(UnitCheckError {
+                element: self.short_type_name(),
+                expected: self.input.symbol,
+                found: input_unit,
+            });
+        }
+        Ok(self.output.symbol)
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    const PERCENT: Unit = Unit::new("%", Quantity::Ratio, 0.01, 0.0);
+    const AMPERE: Unit = Unit::new("A", Quantity::Current, 1.0, 0.0);
+    const MILLI_AMPERE: Unit = Unit::new("mA", Quantity::Current, 1.0e-3, 0.0);
+    const KELVIN: Unit = Unit::new("K", Quantity::Temperature, 1.0, 0.0);
+    const DEGREE_CELSIUS: Unit = Unit::new("°C", Quantity::Temperature, 1.0, 273.15);
+    const DEGREE_FAHRENHEIT: Unit = Unit::new(
+        "°F",
+        Quantity::Temperature,
+        5.0 / 9.0,
+        273.15 - 32.0 * 5.0 / 9.0,
+    );
+    const PASCAL: Unit = Unit::new("Pa", Quantity::Pressure, 1.0, 0.0);
+    const BAR: Unit = Unit::new("bar", Quantity::Pressure, 1.0e5, 0.0);
+
+    #[test]
+    fn test_UnitGain_convert_same_quantity() {
+        let mut sut = UnitGain::convert(BAR, PASCAL).unwrap();
+        assert_eq!(sut.transfer_td(1.5), 150000.0);
+        let mut sut = UnitGain::convert(DEGREE_FAHRENHEIT, DEGREE_CELSIUS).unwrap();
+        assert!((sut.transfer_td(212.0) - 100.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_UnitGain_convert_quantity_mismatch() {
+        assert!(UnitGain::convert(BAR, KELVIN).is_err());
+    }
+
+    #[test]
+    fn test_UnitGain_then() {
+        let transmitter = UnitGain::span(PERCENT, (0.0, 100.0), MILLI_AMPERE, (4.0, 20.0)).unwrap();
+        let to_ampere = UnitGain::convert(MILLI_AMPERE, AMPERE).unwrap();
+        let mut sut = transmitter.then(to_ampere).unwrap();
+        assert_eq!(sut.input, PERCENT);
+        assert_eq!(sut.output, AMPERE);
+        assert!((sut.transfer_td(100.0) - 0.020).abs() < 1e-12);
+        assert_eq!(
+            to_ampere.then(transmitter),
+            Err(UnitMismatchError {
+                expected: AMPERE,
+                found: PERCENT
+            })
+        );
+    }
+
+    #[test]
+    fn test_UnitGain_span_rejects_empty_input_span() {
+        assert!(UnitGain::span(PERCENT, (50.0, 50.0), MILLI_AMPERE, (4.0, 20.0)).is_err());
+        assert!(UnitGain::span(PERCENT, (0.0, f64::NAN), MILLI_AMPERE, (4.0, 20.0)).is_err());
+        // an empty output span is a constant output
+        let mut sut = UnitGain::span(PERCENT, (0.0, 100.0), MILLI_AMPERE, (4.0, 4.0)).unwrap();
+        assert_eq!(sut.transfer_td(50.0), 4.0);
+    }
+
+    #[cfg(feature = "uom")]
+    #[test]
+    fn test_Unit_from_uom() {
+        use uom::si::{
+            electric_current, pressure, ratio, thermodynamic_temperature as temperature,
+        };
+        let close = |a: Unit, b: Unit| {
+            a.symbol == b.symbol
+                && a.quantity == b.quantity
+                && (a.scale - b.scale).abs() <= 1e-12 * b.scale
+                && (a.offset - b.offset).abs() < 1e-9
+        };
+        assert!(close(Unit::ratio::<ratio::percent>(), PERCENT));
+        assert!(close(
+            Unit::current::<electric_current::milliampere>(),
+            MILLI_AMPERE
+        ));
+        assert!(close(Unit::pressure::<pressure::bar>(), BAR));
+        assert!(close(Unit::pressure::<pressure::pascal>(), PASCAL));
+        assert!(close(Unit::temperature::<temperature::kelvin>(), KELVIN));
+        assert!(close(
+            Unit::temperature::<temperature::degree_celsius>(),
+            DEGREE_CELSIUS
+        ));
+        let mut sut = UnitGain::convert(
+            Unit::temperature::<temperature::degree_fahrenheit>(),
+            DEGREE_CELSIUS,
+        )
+        .unwrap();
+        assert!((sut.transfer_td(212.0) - 100.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,181 @@
+//! A zero-order hold, sampling the input at a slower rate than the simulation
+//!
+//! $ out(t) = in(\lfloor t / T_h \rfloor T_h) $
+//!
+//! and $T_{s}$ is the sample time of the simulation
+//! and $T_{h}$ is the hold time, the sampling interval of the held signal
+//!
+//! The input is sampled on the first call and then every $T_{h}$, the output
+//! stays constant in between. E.g. a controller running at 10 ms within a
+//! 1 ms plant simulation only sees the plant output every tenth step.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use cb_simulation_util::plant::TransferTimeDomain;
+//! use cb_simulation_util::plant::zero_order_hold::ZeroOrderHold;
+//!
+//! fn main() {
+//!     let mut zoh = ZeroOrderHold::<f64>::default()
+//!         .set_sample_time_or_default(0.5)
+//!         .set_hold_time(1.0)
+//!         .unwrap();
+//!     assert_eq!(zoh.transfer_td(1.0), 1.0);
+//!     assert_eq!(zoh.transfer_td(2.0), 1.0);
+//!     assert_eq!(zoh.transfer_td(3.0), 3.0);
+//! }
+//! ```
+
+use super::snapshot::StateAccess;
+use super::*;
+use core::fmt::{self, Display};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ZeroOrderHold<N> {
+    pub sample_time: f64,
+    pub hold_time: f64,
+    time: f64,
+    next_sample_time: f64,
+    held: N,
+}
+
+impl<N: Copy> ZeroOrderHold<N> {
+    pub fn set_sample_time_or_default(self, sample_time: f64) -> Self {
+        if sample_time > 0.0 {
+            ZeroOrderHold {
+                sample_time,
+                ..self
+            }
+        } else {
+            ZeroOrderHold {
+                sample_time: 1.0,
+                ..self
+            }
+        }
+    }
+
+    pub fn set_hold_time(self, hold_time: f64) -> Result<Self, &'static str> {
+        if hold_time > 0.0 {
+            Ok(ZeroOrderHold { hold_time, ..self })
+        } else {
+            Err("Invalid hold_time: Must be > 0.0")
+        }
+    }
+
+    /// Currently held value
+    pub fn state(&self) -> N {
+        self.held
+    }
+
+    fn hold(&mut self, input: N) -> N {
+        // sample if the next sampling instant falls into this simulation step
+        if self.next_sample_time < self.time + self.sample_time * (1.0 - 1e-9) {
+            self.held = input;
+            while self.next_sample_time < self.time + self.sample_time * (1.0 - 1e-9) {
+                self.next_sample_time += self.hold_time;
+            }
+        }
+        self.time += self.sample_time;
+        self.held
+    }
+}
+
+impl Default for ZeroOrderHold<f64> {
+    /// Holds for one sample time, i.e. passes the input through
+    fn default() -> Self {
+        ZeroOrderHold {
+            sample_time: 1.0,
+            hold_time: 1.0,
+            time: 0.0,
+            next_sample_time: 0.0,
+            held: 0.0,
+        }
+    }
+}
+
+impl Default for ZeroOrderHold<i32> {
+    /// Holds for one sample time, i.e. passes the input through
+    fn default() -> Self {
+        ZeroOrderHold {
+            sample_time: 1.0,
+            hold_time: 1.0,
+            time: 0.0,
+            next_sample_time: 0.0,
+            held: 0,
+        }
+    }
+}
+
+impl<N: Copy + fmt::Debug + PartialEq> StateAccess for ZeroOrderHold<N> {
+    /// Time, time of the next hold sample and the held value
+    type State = (f64, f64, N);
+
+    fn save_state(&self) -> Self::State {
+        (self.time, self.next_sample_time, self.held)
+    }
+
+    fn restore_state(&mut self, state: &Self::State) {
+        (self.time, self.next_sample_time, self.held) = *state;
+    }
+}
+
+impl<N> TypeIdentifier for ZeroOrderHold<N> {
+    fn short_type_name(&self) -> &'static str {
+        "ZeroOrderHold"
+    }
+}
+
+impl<N> SampleTime for ZeroOrderHold<N> {
+    fn sample_time(&self) -> Option<f64> {
+        Some(self.sample_time)
+    }
+}
+
+impl<N> Display for ZeroOrderHold<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "ZeroOrderHold(sample_time: {}, hold_time: {})",
+            self.sample_time, self.hold_time
+        )
+    }
+}
+
+impl TransferTimeDomain<f64> for ZeroOrderHold<f64> {
+    fn transfer_td(&mut self, input: f64) -> f64 {
+        self.hold(input)
+    }
+}
+
+impl TransferTimeDomain<i32> for ZeroOrderHold<i32> {
+    fn transfer_td(&mut self, input: i32) -> i32 {
+        self.hold(input)
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use std::vec::Vec;
+
+    #[test]
+    fn test_ZeroOrderHold_default_passes_through() {
+        let mut sut = ZeroOrderHold::<i32>::default();
+        assert_eq!(sut.transfer_td(3), 3);
+        assert_eq!(sut.transfer_td(-7), -7);
+        assert!(sut.set_hold_time(0.0).is_err());
+    }
+
+    #[test]
+    fn test_ZeroOrderHold_f64_non_integer_ratio() {
+        // holds 2.5 simulation steps: samples in the steps starting at t = 0, 0.2, 0.5, 0.7
+        let mut sut = ZeroOrderHold::<f64>::default()
+            .set_sample_time_or_default(0.1)
+            .set_hold_time(0.25)
+            .unwrap();
+        let out: Vec<f64> = (0..8).map(|k| sut.transfer_td(k as f64)).collect();
+        assert_eq!(out, [0.0, 0.0, 2.0, 2.0, 2.0, 5.0, 5.0, 7.0]);
+    }
+}
@@ -0,0 +1,55 @@
+//! Small deterministic pseudo random numbers (splitmix64), no dependencies
+//!
+//! Stateless signals need a random value as a pure function of seed and time,
+//! reproducible from the seed.
+
+/// One round of the splitmix64 mixing function
+pub(crate) fn mix(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// Uniform value in `[0, 1)` from 64 random bits
+pub(crate) fn unit(bits: u64) -> f64 {
+    (bits >> 11) as f64 * (1.0 / (1u64 << 53) as f64)
+}
+
+/// Uniform value in `[0, 1)` as pure function of `seed` and `time`
+pub(crate) fn unit_at(seed: u64, time: f64) -> f64 {
+    unit(mix(seed ^ mix(time.to_bits())))
+}
+
+/// Standard normal value from 2 x 64 random bits (Box-Muller)
+pub(crate) fn normal(a: u64, b: u64) -> f64 {
+    let radius = (-2.0 * (1.0 - unit(a)).ln()).sqrt();
+    radius * (2.0 * core::f64::consts::PI * unit(b)).cos()
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_unit_range_and_determinism() {
+        for k in 0..1000 {
+            let x = unit(mix(k));
+            assert!((0.0..1.0).contains(&x));
+        }
+        assert_eq!(unit_at(1, 0.5), unit_at(1, 0.5));
+        assert_ne!(unit_at(1, 0.5), unit_at(2, 0.5));
+    }
+
+    #[test]
+    fn test_normal_moments() {
+        let samples: std::vec::Vec<f64> = (0..20000u64)
+            .map(|k| normal(mix(2 * k), mix(2 * k + 1)))
+            .collect();
+        let mean = samples.iter().sum::<f64>() / samples.len() as f64;
+        let variance = samples.iter().map(|x| x * x).sum::<f64>() / samples.len() as f64;
+        assert!(mean.abs() < 0.03);
+        assert!((variance - 1.0).abs() < 0.05);
+    }
+}
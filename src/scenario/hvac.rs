@@ -0,0 +1,452 @@
+//! # HVAC room scenario
+//!
+//! A room heated by an electric heater, controlled by a PI controller or an
+//! on/off thermostat, against an outdoor temperature profile:
+//!
+//! setpoint → controller → heater (power limit, thermal lag) → room
+//!
+//! The room is a single thermal capacity losing heat to outdoor:
+//!
+//! $ C \dot{T} = P - G (T - T_{out}(t)) $
+//!
+//! Time is in seconds, temperatures in °C, powers in W.
+//!
+//! The PI variant can be warm-started with `Diagram::initialize_at`: the
+//! room at the setpoint, the heater delivering the power to hold it.
+//! Blocks of the plant can be bypassed by their short type name with
+//! `Diagram::set_block_enabled` to compare variants, e.g. an ideal heater
+//! without the `PT1` lag.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use cb_simulation_util::scenario::hvac::HvacScenario;
+//!
+//! fn main() {
+//!     let result = HvacScenario::pi().run();
+//!     let room = result.trace("output").unwrap();
+//!     let last = room.values[room.values.len() - 1];
+//!     assert!((last - 21.0).abs() < 0.5);
+//!     assert!(result.trace("outdoor").unwrap().values.iter().all(|t| *t < 21.0));
+//! }
+//! ```
+
+use core::fmt::{self, Display};
+use ndarray::Array1;
+use std::boxed::Box;
+use std::format;
+use std::string::String;
+use std::vec;
+use std::vec::Vec;
+
+use super::{Diagram, InitializationError};
+use crate::controller::pi::{AntiWindup, PI};
+use crate::controller::relay::Relay;
+use crate::plant::pt1::PT1;
+use crate::plant::saturation::Saturation;
+use crate::plant::series::Series;
+use crate::plant::snapshot::StateAccess;
+use crate::plant::steady_state::{self, SteadyState};
+use crate::plant::{BoxedTransferTimeDomain, SampleTime, TransferTimeDomain, TypeIdentifier};
+use crate::signal::{AmbientProfile, BoxedTimeSignal, StepFunction, TimeRange};
+use crate::sim::{SimResult, Simulation, Trace, TraceMetadata};
+
+/// Single zone room, input heater power in W, output room temperature in °C
+#[derive(Debug, Clone)]
+pub struct Room {
+    /// Heat capacity in J/K
+    pub capacity: f64,
+    /// Heat loss to outdoor in W/K
+    pub loss_conductance: f64,
+    pub outdoor: BoxedTimeSignal<f64>,
+    pub sample_time: f64,
+    time: f64,
+    temperature: f64,
+}
+
+impl Room {
+    pub fn new(
+        capacity: f64,
+        loss_conductance: f64,
+        outdoor: BoxedTimeSignal<f64>,
+    ) -> Result<Self, &'static str> {
+        if capacity <= 0.0 || loss_conductance <= 0.0 {
+            return Err("Invalid room: Capacity and loss conductance must be > 0.0");
+        }
+        Ok(Room {
+            capacity,
+            loss_conductance,
+            temperature: outdoor.time_to_signal(0.0),
+            outdoor,
+            sample_time: 1.0,
+            time: 0.0,
+        })
+    }
+
+    pub fn set_sample_time_or_default(self, sample_time: f64) -> Self {
+        if sample_time > 0.0 {
+            Room {
+                sample_time,
+                ..self
+            }
+        } else {
+            Room {
+                sample_time: 1.0,
+                ..self
+            }
+        }
+    }
+
+    pub fn set_temperature(self, temperature: f64) -> Self {
+        Room {
+            temperature,
+            ..self
+        }
+    }
+
+    pub fn temperature(&self) -> f64 {
+        self.temperature
+    }
+}
+
+impl PartialEq for Room {
+    fn eq(&self, other: &Self) -> bool {
+        self.outdoor.eq(&other.outdoor)
+            && self.capacity == other.capacity
+            && self.loss_conductance == other.loss_conductance
+            && self.sample_time == other.sample_time
+            && self.time == other.time
+            && self.temperature == other.temperature
+    }
+}
+
+impl TypeIdentifier for Room {
+    fn short_type_name(&self) -> &'static str {
+        "Room"
+    }
+}
+
+impl SampleTime for Room {
+    fn sample_time(&self) -> Option<f64> {
+        Some(self.sample_time)
+    }
+}
+
+impl Display for Room {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Room(sample_time: {}, capacity: {}, loss_conductance: {}, outdoor: {})",
+            self.sample_time, self.capacity, self.loss_conductance, self.outdoor
+        )
+    }
+}
+
+impl SteadyState for Room {
+    /// Heating power holding `temperature` at the current outdoor temperature
+    fn steady_input(&self, temperature: f64) -> Option<f64> {
+        Some(self.loss_conductance * (temperature - self.outdoor.time_to_signal(self.time)))
+    }
+
+    fn settle(&mut self, power: f64) -> f64 {
+        self.temperature = self.outdoor.time_to_signal(self.time) + power / self.loss_conductance;
+        self.temperature
+    }
+}
+
+impl TransferTimeDomain<f64> for Room {
+    fn transfer_td(&mut self, power: f64) -> f64 {
+        // exact step response, power and outdoor temperature held over the sample
+        let steady = self.outdoor.time_to_signal(self.time) + power / self.loss_conductance;
+        let decay = (-self.loss_conductance * self.sample_time / self.capacity).exp();
+        self.temperature = steady + (self.temperature - steady) * decay;
+        self.time += self.sample_time;
+        self.temperature
+    }
+
+    fn output_unit(&self, _input_unit: &'static str) -> &'static str {
+        "°C"
+    }
+}
+
+/// Room temperature control against a winter outdoor profile
+#[derive(Debug, Clone)]
+pub struct HvacScenario {
+    pub simulation: Simulation,
+    pub setpoint: BoxedTimeSignal<f64>,
+    pub controller: BoxedTransferTimeDomain<f64>,
+    /// Heater followed by the room
+    pub plant: Series<f64>,
+}
+
+const SAMPLE_TIME: f64 = 60.0;
+const HEATER_POWER: f64 = 3000.0;
+
+impl HvacScenario {
+    fn with_controller(controller: BoxedTransferTimeDomain<f64>) -> Self {
+        // two winter days, early January
+        let outdoor = AmbientProfile::continental().noise(0.0, 6.0, 0);
+        let room = Room::new(5.0e6, 100.0, Box::new(outdoor))
+            .expect("valid room parameters")
+            .set_sample_time_or_default(SAMPLE_TIME)
+            .set_temperature(16.0);
+        let heater_limit = Saturation::<f64>::default().set_limits_or_default(0.0, HEATER_POWER);
+        let heater_lag = PT1::<f64>::default()
+            .set_sample_time_or_default(SAMPLE_TIME)
+            .set_t1_time_or_default(300.0);
+        HvacScenario {
+            simulation: Simulation::new(
+                TimeRange::default()
+                    .set_unit_of_measurement("s")
+                    .set_sampling_interval(SAMPLE_TIME)
+                    .set_end(2.0 * 86400.0),
+            )
+            .set_input_unit("°C"),
+            setpoint: Box::new(StepFunction::default().pre(16.0).post(21.0).step(3600.0)),
+            controller,
+            plant: Series::new(vec![
+                Box::new(heater_limit) as BoxedTransferTimeDomain<f64>,
+                Box::new(heater_lag),
+                Box::new(room),
+            ]),
+        }
+    }
+
+    /// PI controller with clamping anti-windup
+    pub fn pi() -> Self {
+        let controller = PI::<f64>::default()
+            .set_sample_time_or_default(SAMPLE_TIME)
+            .set_kp(1000.0)
+            .set_ti_time_or_default(3600.0)
+            .set_output_limits(0.0, HEATER_POWER)
+            .set_anti_windup(AntiWindup::Clamping);
+        HvacScenario::with_controller(Box::new(controller))
+    }
+
+    /// On/off thermostat, a `Relay` with 0.5 K hysteresis
+    pub fn thermostat() -> Self {
+        let relay = Relay::<f64>::default()
+            .set_thresholds(0.25, -0.25)
+            .expect("ordered thresholds")
+            .set_output(0.0, HEATER_POWER);
+        HvacScenario::with_controller(Box::new(relay))
+    }
+
+    /// Run the closed loop, recording `setpoint`, `error`, `control`, `output` and `outdoor`
+    pub fn run(&mut self) -> SimResult {
+        let mut result = self.simulation.run_closed_loop(
+            &*self.setpoint,
+            &mut *self.controller,
+            &mut self.plant,
+        );
+        for trace in result.traces.iter_mut() {
+            if trace.name == "control" {
+                trace.meta.unit = "W";
+            }
+        }
+        let room = self
+            .plant
+            .elements()
+            .last()
+            .and_then(|e| e.as_any().downcast_ref::<Room>());
+        if let Some(room) = room {
+            let outdoor: Array1<f64> = result
+                .time
+                .iter()
+                .map(|t| room.outdoor.time_to_signal(*t - SAMPLE_TIME))
+                .collect();
+            result.traces.push(Trace {
+                name: String::from("outdoor"),
+                meta: TraceMetadata {
+                    unit: "°C",
+                    source: room.outdoor.short_type_name(),
+                    sample_interval: self.simulation.range.sampling_interval,
+                },
+                values: outdoor,
+            });
+        }
+        result
+    }
+}
+
+impl Diagram for HvacScenario {
+    fn run(&mut self, range: TimeRange) -> SimResult {
+        self.simulation.range = range;
+        HvacScenario::run(self)
+    }
+
+    /// Supported with a PI controller, fails if the heater cannot deliver the power
+    fn initialize_at(&mut self, setpoint: f64) -> Result<(), InitializationError> {
+        if !self.controller.as_any().is::<PI<f64>>() {
+            return Err(InitializationError::Unsupported);
+        }
+        let steady_input = |element: &BoxedTransferTimeDomain<f64>, output: f64| match element
+            .as_any()
+            .downcast_ref::<Room>()
+        {
+            Some(room) => room.steady_input(output),
+            None => steady_state::as_steady_state(&**element)?.steady_input(output),
+        };
+        let mut power = setpoint;
+        let enabled: Vec<bool> = (0..self.plant.len())
+            .map(|i| self.plant.is_enabled(i))
+            .collect();
+        for (element, _) in self
+            .plant
+            .elements()
+            .iter()
+            .zip(&enabled)
+            .rev()
+            .filter(|(_, enabled)| **enabled)
+        {
+            power = steady_input(element, power)
+                .ok_or(InitializationError::Unreachable(element.short_type_name()))?;
+        }
+        let controller = self
+            .controller
+            .as_any_mut()
+            .downcast_mut::<PI<f64>>()
+            .ok_or(InitializationError::Unsupported)?;
+        if power < controller.output_min || power > controller.output_max {
+            return Err(InitializationError::Unreachable(
+                controller.short_type_name(),
+            ));
+        }
+        // zero control error: the integral part delivers the whole output
+        controller.restore_state(&power);
+        let mut value = power;
+        for (element, _) in self
+            .plant
+            .elements_mut()
+            .iter_mut()
+            .zip(&enabled)
+            .filter(|(_, enabled)| **enabled)
+        {
+            value = match element.as_any_mut().downcast_mut::<Room>() {
+                Some(room) => room.settle(value),
+                None => match steady_state::as_steady_state_mut(&mut **element) {
+                    Some(element) => element.settle(value),
+                    None => element.transfer_td(value),
+                },
+            };
+        }
+        Ok(())
+    }
+
+    fn parameters(&self) -> Vec<(String, String)> {
+        vec![
+            (String::from("setpoint"), format!("{}", self.setpoint)),
+            (String::from("controller"), format!("{}", self.controller)),
+            (String::from("plant"), format!("{}", self.plant)),
+        ]
+    }
+
+    fn seeds(&self) -> Vec<(String, u64)> {
+        self.plant
+            .elements()
+            .iter()
+            .filter_map(|e| e.as_any().downcast_ref::<Room>())
+            .filter_map(|room| room.outdoor.as_any().downcast_ref::<AmbientProfile>())
+            .map(|outdoor| (String::from("outdoor"), outdoor.seed))
+            .collect()
+    }
+
+    /// The blocks are the plant elements by their short type name
+    fn set_block_enabled(&mut self, name: &str, enabled: bool) -> Result<(), &'static str> {
+        let index = self.plant.position(name).ok_or("Unknown block")?;
+        self.plant.set_enabled(index, enabled);
+        Ok(())
+    }
+}
+
+impl PartialEq for HvacScenario {
+    fn eq(&self, other: &Self) -> bool {
+        self.setpoint.eq(&other.setpoint)
+            && self.controller.eq(&other.controller)
+            && self.simulation == other.simulation
+            && self.plant == other.plant
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_HvacScenario_thermostat_oscillates_around_setpoint() {
+        let result = HvacScenario::thermostat().run();
+        let room = &result.trace("output").unwrap().values;
+        let settled = room.slice(ndarray::s![room.len() / 2..]);
+        let min = settled.fold(f64::INFINITY, |m, t| m.min(*t));
+        let max = settled.fold(f64::NEG_INFINITY, |m, t| m.max(*t));
+        assert!(min > 20.0 && max < 22.0, "{} {}", min, max);
+        let control = result.trace("control").unwrap();
+        assert_eq!(control.meta.unit, "W");
+        assert!(
+            control
+                .values
+                .iter()
+                .all(|p| *p == 0.0 || *p == HEATER_POWER)
+        );
+    }
+
+    #[test]
+    fn test_HvacScenario_bypass_heater_lag() {
+        let mut sut = HvacScenario::pi();
+        let with_lag = sut.clone().run();
+        sut.set_block_enabled("PT1", false).unwrap();
+        assert!(!sut.plant.is_enabled(1));
+        let result = sut.run();
+        let room = &result.trace("output").unwrap().values;
+        assert!((room[room.len() - 1] - 21.0).abs() < 0.5);
+        assert_ne!(room, &with_lag.trace("output").unwrap().values);
+        assert_eq!(sut.set_block_enabled("PT2", false), Err("Unknown block"));
+        // the ideal heater is settled at the setpoint as well
+        sut.initialize_at(21.0).unwrap();
+        let room = sut.plant.elements()[2].as_any().downcast_ref::<Room>();
+        assert!((room.unwrap().temperature() - 21.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_HvacScenario_initialize_at_starts_without_transient() {
+        let mut sut = HvacScenario::pi();
+        sut.setpoint = Box::new(StepFunction::default().pre(21.0).post(21.0));
+        sut.initialize_at(21.0).unwrap();
+        let result = sut.run();
+        // the outdoor temperature changes, the room drifts only slowly
+        let room = &result.trace("output").unwrap().values;
+        assert!((room[0] - 21.0).abs() < 0.01, "{}", room[0]);
+        assert!(room.iter().all(|t| (t - 21.0).abs() < 0.5));
+        let mut sut = HvacScenario::pi();
+        assert_eq!(
+            sut.initialize_at(60.0),
+            Err(InitializationError::Unreachable("Saturation"))
+        );
+        assert_eq!(
+            HvacScenario::thermostat().initialize_at(21.0),
+            Err(InitializationError::Unsupported)
+        );
+    }
+
+    #[test]
+    fn test_Room_exact_discretization() {
+        let outdoor = StepFunction::default().pre(0.0).post(0.0);
+        let mut sut = Room::new(1000.0, 10.0, Box::new(outdoor))
+            .unwrap()
+            .set_sample_time_or_default(100.0);
+        // tau = 100 s, steady state 10 K above outdoor
+        let t = sut.transfer_td(100.0);
+        assert!((t - 10.0 * (1.0 - (-1.0f64).exp())).abs() < 1e-12);
+        assert_eq!(sut.output_unit("W"), "°C");
+    }
+
+    #[test]
+    fn test_HvacScenario_manifest_entries() {
+        let sut = HvacScenario::pi();
+        assert_eq!(sut.seeds(), vec![(String::from("outdoor"), 0)]);
+        let names: Vec<String> = sut.parameters().into_iter().map(|(n, _)| n).collect();
+        assert_eq!(names, ["setpoint", "controller", "plant"]);
+    }
+}
@@ -0,0 +1,403 @@
+//! # Scenarios
+//!
+//! Ready-to-run closed loop simulations of typical applications: plant,
+//! actuator, controller, setpoint and disturbance profiles with realistic
+//! parameters. Each is a starting point - all parts are public and can be
+//! replaced before running.
+//!
+//! A `Scenario` describes a canned demo: its name, a description, how to
+//! build the `Diagram`, the time range to run it over and the expected
+//! metrics. `Diagram::initialize_at` settles all blocks at an operating
+//! point, so a run starts there without a startup transient. A diagram
+//! exposing its loop as one `SteadyState`, e.g. a `Feedback` of controller
+//! and plant, gets it from `Diagram::steady_state_mut`. The
+//! `ScenarioRegistry` lists the built-in scenarios, so tools can offer
+//! them by name, and accepts user defined ones.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use cb_simulation_util::scenario::ScenarioRegistry;
+//!
+//! fn main() {
+//!     let registry = ScenarioRegistry::builtin();
+//!     assert!(registry.names().contains(&"servo"));
+//!     let (_result, report) = registry.get("hvac-pi").unwrap().verify().unwrap();
+//!     assert!(report.passed(), "{}", report);
+//! }
+//! ```
+
+use core::fmt::{self, Display};
+use std::boxed::Box;
+use std::string::String;
+use std::vec;
+use std::vec::Vec;
+
+use crate::analysis::requirements::{self, Bound, Metric, Requirement, RequirementsReport};
+use crate::plant::steady_state::SteadyState;
+use crate::signal::{TimeRange, TimeUnitError};
+use crate::sim::SimResult;
+
+pub mod hvac;
+pub mod servo;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InitializationError {
+    /// The diagram cannot be settled, e.g. an on/off controller has no steady state
+    Unsupported,
+    /// No steady state of the named block holds the operating point, e.g. an actuator limit
+    Unreachable(&'static str),
+}
+
+impl Display for InitializationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InitializationError::Unsupported => write!(f, "Diagram has no steady state"),
+            InitializationError::Unreachable(block) => {
+                write!(f, "Operating point not reachable by block {}", block)
+            }
+        }
+    }
+}
+
+/// A complete simulation setup, ready to run
+pub trait Diagram {
+    fn run(&mut self, range: TimeRange) -> SimResult;
+
+    /// The diagram from its setpoint to its output as one element, e.g. a `Feedback` loop
+    fn steady_state_mut(&mut self) -> Option<&mut dyn SteadyState> {
+        None
+    }
+
+    /// Settle every block at the steady state of the closed loop at `setpoint`
+    ///
+    /// The plant input holding the output at `setpoint` is propagated back
+    /// from the plant output to the controller, then each block is set to
+    /// its equilibrium, see `SteadyState::settle_output`. A following run
+    /// starts at the operating point if its setpoint profile starts at the
+    /// reference holding `setpoint`, e.g. `setpoint` itself with integral
+    /// control. Unsupported without `steady_state_mut`.
+    fn initialize_at(&mut self, setpoint: f64) -> Result<(), InitializationError> {
+        self.steady_state_mut()
+            .ok_or(InitializationError::Unsupported)?
+            .settle_output(setpoint)
+            .map(|_| ())
+            .map_err(InitializationError::Unreachable)
+    }
+
+    /// Parameters of the blocks by name, e.g. for a `RunManifest`
+    fn parameters(&self) -> Vec<(String, String)> {
+        Vec::new()
+    }
+
+    /// Seeds of the random sources by name
+    fn seeds(&self) -> Vec<(String, u64)> {
+        Vec::new()
+    }
+
+    /// Enable the block `name`, or bypass it with `false`
+    ///
+    /// A bypassed block passes its input through unchanged and keeps its
+    /// state, e.g. to compare a loop with and without its filter. Fails if
+    /// the diagram has no block of the name which can be bypassed.
+    fn set_block_enabled(&mut self, _name: &str, _enabled: bool) -> Result<(), &'static str> {
+        Err("Unknown block")
+    }
+}
+
+/// A canned simulation with the metrics it is expected to meet
+pub trait Scenario: Send + Sync {
+    /// Unique identifier, lower case words separated by `-`
+    fn name(&self) -> &'static str;
+    fn description(&self) -> &'static str;
+    fn build(&self) -> Box<dyn Diagram>;
+    fn time_range(&self) -> TimeRange;
+    fn expected_metrics(&self) -> Vec<Requirement>;
+
+    /// Build and run the diagram, and evaluate the expected metrics
+    ///
+    /// Fails if the time range is of an unknown unit and a metric is time based.
+    fn verify(&self) -> Result<(SimResult, RequirementsReport), TimeUnitError> {
+        let result = self.build().run(self.time_range());
+        let report = requirements::evaluate(&self.expected_metrics(), &[(self.name(), &result)])?;
+        Ok((result, report))
+    }
+}
+
+/// Scenario defined by plain functions, used for the built-in ones
+struct Builtin {
+    name: &'static str,
+    description: &'static str,
+    build: fn() -> Box<dyn Diagram>,
+    time_range: fn() -> TimeRange,
+    expected_metrics: fn() -> Vec<Requirement>,
+}
+
+impl Scenario for Builtin {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn description(&self) -> &'static str {
+        self.description
+    }
+
+    fn build(&self) -> Box<dyn Diagram> {
+        (self.build)()
+    }
+
+    fn time_range(&self) -> TimeRange {
+        (self.time_range)()
+    }
+
+    fn expected_metrics(&self) -> Vec<Requirement> {
+        (self.expected_metrics)()
+    }
+}
+
+#[derive(Default)]
+pub struct ScenarioRegistry {
+    scenarios: Vec<Box<dyn Scenario>>,
+}
+
+impl ScenarioRegistry {
+    /// Empty registry
+    pub fn new() -> Self {
+        ScenarioRegistry::default()
+    }
+
+    /// Registry with all scenarios of this crate
+    pub fn builtin() -> Self {
+        let builtins = [
+            Builtin {
+                name: "hvac-pi",
+                description: "Room heating with PI control against a winter outdoor profile",
+                build: || Box::new(hvac::HvacScenario::pi()),
+                time_range: || hvac::HvacScenario::pi().simulation.range,
+                expected_metrics: || {
+                    vec![
+                        Requirement::new(
+                            "room warm",
+                            "output",
+                            Metric::FinalValue,
+                            Bound::AtLeast(20.5),
+                        ),
+                        Requirement::new(
+                            "no overheating",
+                            "output",
+                            Metric::Max,
+                            Bound::AtMost(22.0),
+                        ),
+                    ]
+                },
+            },
+            Builtin {
+                name: "hvac-thermostat",
+                description: "Room heating with an on/off thermostat against a winter outdoor profile",
+                build: || Box::new(hvac::HvacScenario::thermostat()),
+                time_range: || hvac::HvacScenario::thermostat().simulation.range,
+                expected_metrics: || {
+                    vec![
+                        Requirement::new(
+                            "room warm",
+                            "output",
+                            Metric::FinalValue,
+                            Bound::AtLeast(20.0),
+                        ),
+                        Requirement::new(
+                            "no overheating",
+                            "output",
+                            Metric::Max,
+                            Bound::AtMost(22.0),
+                        ),
+                    ]
+                },
+            },
+            Builtin {
+                name: "servo",
+                description: "Cascaded position control of a DC motor with gearbox backlash and encoder",
+                build: || Box::new(servo::ServoScenario::default()),
+                time_range: || servo::ServoScenario::default().range,
+                expected_metrics: || {
+                    vec![
+                        Requirement::new(
+                            "on target",
+                            "position",
+                            Metric::FinalValue,
+                            Bound::AtLeast(0.98),
+                        ),
+                        Requirement::new(
+                            "overshoot",
+                            "position",
+                            Metric::Overshoot,
+                            Bound::AtMost(10.0),
+                        ),
+                        Requirement::new(
+                            "current limit",
+                            "current",
+                            Metric::Max,
+                            Bound::AtMost(10.5),
+                        ),
+                    ]
+                },
+            },
+        ];
+        let mut registry = ScenarioRegistry::new();
+        for builtin in builtins {
+            registry.scenarios.push(Box::new(builtin));
+        }
+        registry
+    }
+
+    /// Add a scenario, fails if the name is already taken
+    pub fn register(&mut self, scenario: Box<dyn Scenario>) -> Result<(), &'static str> {
+        if self.get(scenario.name()).is_some() {
+            return Err("Scenario name already registered");
+        }
+        self.scenarios.push(scenario);
+        Ok(())
+    }
+
+    pub fn get(&self, name: &str) -> Option<&dyn Scenario> {
+        self.scenarios
+            .iter()
+            .find(|s| s.name() == name)
+            .map(|s| s.as_ref())
+    }
+
+    /// Names in registration order
+    pub fn names(&self) -> Vec<&'static str> {
+        self.scenarios.iter().map(|s| s.name()).collect()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &dyn Scenario> {
+        self.scenarios.iter().map(|s| s.as_ref())
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::controller::pi::PI;
+    use crate::plant::BoxedTransferTimeDomain;
+    use crate::plant::feedback::Feedback;
+    use crate::plant::pt1::PT1;
+    use crate::plant::series::Series;
+    use crate::signal::StepFunction;
+    use crate::sim::Simulation;
+
+    struct Pt1Step;
+
+    struct Pt1Diagram(PT1<f64>);
+
+    impl Diagram for Pt1Diagram {
+        fn run(&mut self, range: TimeRange) -> SimResult {
+            Simulation::new(range).run(&StepFunction::default(), &mut self.0)
+        }
+    }
+
+    impl Scenario for Pt1Step {
+        fn name(&self) -> &'static str {
+            "pt1-step"
+        }
+
+        fn description(&self) -> &'static str {
+            "Step response of a PT1"
+        }
+
+        fn build(&self) -> Box<dyn Diagram> {
+            Box::new(Pt1Diagram(
+                PT1::<f64>::default().set_t1_time_or_default(5.0),
+            ))
+        }
+
+        fn time_range(&self) -> TimeRange {
+            TimeRange::default()
+        }
+
+        fn expected_metrics(&self) -> Vec<Requirement> {
+            vec![Requirement::new(
+                "settled",
+                "output",
+                Metric::FinalValue,
+                Bound::AtLeast(0.99),
+            )]
+        }
+    }
+
+    /// PI control of a PT1, the setpoint as reference of a `Feedback` loop
+    struct LoopDiagram(Feedback);
+
+    impl Diagram for LoopDiagram {
+        fn run(&mut self, range: TimeRange) -> SimResult {
+            let setpoint = StepFunction::default().pre(2.0).post(2.0);
+            Simulation::new(range).run(&setpoint, &mut self.0)
+        }
+
+        fn steady_state_mut(&mut self) -> Option<&mut dyn SteadyState> {
+            Some(&mut self.0)
+        }
+    }
+
+    #[test]
+    fn test_Diagram_initialize_at_from_steady_state() {
+        let controller = PI::<f64>::default()
+            .set_kp(0.5)
+            .set_ti_time_or_default(5.0)
+            .set_output_limits(0.0, 1.0);
+        let forward = Series::new(vec![
+            Box::new(controller) as BoxedTransferTimeDomain<f64>,
+            Box::new(
+                PT1::<f64>::default()
+                    .set_t1_time_or_default(10.0)
+                    .set_kp(4.0),
+            ),
+        ]);
+        let mut sut = LoopDiagram(Feedback::unity(Box::new(forward)));
+        let cold = sut.run(TimeRange::default().set_end(10.0));
+        // from rest: the output starts far below the setpoint
+        assert!(cold.trace("output").unwrap().values[0] < 1.0);
+        sut.initialize_at(2.0).unwrap();
+        let warm = sut.run(TimeRange::default().set_end(10.0));
+        assert!(
+            warm.trace("output")
+                .unwrap()
+                .values
+                .iter()
+                .all(|y| (y - 2.0).abs() < 1e-12)
+        );
+        assert_eq!(
+            sut.initialize_at(8.0),
+            Err(InitializationError::Unreachable("PI"))
+        );
+        assert_eq!(
+            Pt1Diagram(PT1::<f64>::default()).initialize_at(1.0),
+            Err(InitializationError::Unsupported)
+        );
+    }
+
+    #[test]
+    fn test_ScenarioRegistry_builtin_scenarios_pass() {
+        let registry = ScenarioRegistry::builtin();
+        assert_eq!(
+            registry.names(),
+            vec!["hvac-pi", "hvac-thermostat", "servo"]
+        );
+        for scenario in registry.iter() {
+            let (_, report) = scenario.verify().unwrap();
+            assert!(report.passed(), "{}: {}", scenario.name(), report);
+        }
+    }
+
+    #[test]
+    fn test_ScenarioRegistry_register() {
+        let mut registry = ScenarioRegistry::builtin();
+        assert!(registry.register(Box::new(Pt1Step)).is_ok());
+        assert!(registry.register(Box::new(Pt1Step)).is_err());
+        let (result, report) = registry.get("pt1-step").unwrap().verify().unwrap();
+        assert!(report.passed());
+        assert_eq!(result.trace("output").unwrap().meta.source, "PT1");
+    }
+}
@@ -0,0 +1,275 @@
+//! # Servo positioning scenario
+//!
+//! Position control of a load driven by a DC motor through a gearbox, in the
+//! classic cascade structure of servo drives:
+//!
+//! position P → velocity PI → current PI → voltage → DC motor → gearbox with
+//! backlash → load, the load position measured by an incremental encoder
+//!
+//! Each inner loop is tuned faster than the outer one. The encoder
+//! quantizes the position to its counts, the backlash makes the load stand
+//! still on reversals - both show up as small limit cycles around the
+//! target. Time is in seconds, angles in rad.
+//!
+//! `Diagram::initialize_at` places the drive at rest at a position, all
+//! controllers without integral action stored.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use cb_simulation_util::scenario::servo::ServoScenario;
+//!
+//! fn main() {
+//!     let result = ServoScenario::default().run();
+//!     let position = &result.trace("position").unwrap().values;
+//!     assert!((position[position.len() - 1] - 1.0).abs() < 0.02);
+//! }
+//! ```
+
+use core::f64::consts::TAU;
+use core::fmt::Display;
+use ndarray::{Array1, array};
+use std::boxed::Box;
+use std::format;
+use std::string::String;
+use std::vec;
+use std::vec::Vec;
+
+use super::{Diagram, InitializationError};
+use crate::controller::pi::{AntiWindup, PI};
+use crate::plant::backlash::Backlash;
+use crate::plant::dc_motor::DcMotor;
+use crate::plant::quantizer::{Quantizer, Rounding};
+use crate::plant::snapshot::StateAccess;
+use crate::plant::{MimoTransferTimeDomain, TransferTimeDomain, TypeIdentifier};
+use crate::signal::{BoxedTimeSignal, StepFunction, TimeRange};
+use crate::sim::{SimResult, Trace, TraceMetadata};
+
+#[derive(Debug, Clone)]
+pub struct ServoScenario {
+    pub range: TimeRange,
+    /// Load position setpoint in rad
+    pub setpoint: BoxedTimeSignal<f64>,
+    /// Load position error → motor speed setpoint
+    pub position_controller: PI<f64>,
+    /// Motor speed error → current setpoint
+    pub velocity_controller: PI<f64>,
+    /// Current error → motor voltage
+    pub current_controller: PI<f64>,
+    pub motor: DcMotor,
+    /// Motor revolutions per load revolution
+    pub gear_ratio: f64,
+    /// Play on the load side in rad
+    pub gearbox: Backlash,
+    /// Encoder on the load, one step per count
+    pub encoder: Quantizer<f64>,
+}
+
+const SAMPLE_TIME: f64 = 1.0e-4;
+
+impl ServoScenario {
+    /// Run the cascade, recording `setpoint`, `position`, `velocity`, `current` and `voltage`
+    pub fn run(&mut self) -> SimResult {
+        let time: Array1<f64> = self.range.collect();
+        let n = time.len();
+        let mut traces = [
+            Array1::zeros(n),
+            Array1::zeros(n),
+            Array1::zeros(n),
+            Array1::zeros(n),
+            Array1::zeros(n),
+        ];
+        let mut measured = self.motor.state().clone();
+        let mut load_angle = self.gearbox.state();
+        for (k, t) in time.iter().enumerate() {
+            let setpoint = self.setpoint.time_to_signal(*t);
+            let position = self.encoder.transfer_td(load_angle);
+            let speed_setpoint = self.position_controller.transfer_td(setpoint - position);
+            let current_setpoint = self
+                .velocity_controller
+                .transfer_td(speed_setpoint - measured[1]);
+            let voltage = self
+                .current_controller
+                .transfer_td(current_setpoint - measured[0]);
+            measured = self.motor.transfer_td(array![voltage, 0.0].view());
+            load_angle = self.gearbox.transfer_td(measured[2] / self.gear_ratio);
+            for (trace, value) in
+                traces
+                    .iter_mut()
+                    .zip([setpoint, position, measured[1], measured[0], voltage])
+            {
+                trace[k] = value;
+            }
+        }
+        let [setpoint, position, velocity, current, voltage] = traces;
+        let trace = |name: &str, unit, source, values| Trace {
+            name: String::from(name),
+            meta: TraceMetadata {
+                unit,
+                source,
+                sample_interval: self.range.sampling_interval,
+            },
+            values,
+        };
+        SimResult {
+            time,
+            time_unit: self.range.unit_of_measurement,
+            traces: vec![
+                trace("setpoint", "rad", self.setpoint.short_type_name(), setpoint),
+                trace("position", "rad", self.encoder.short_type_name(), position),
+                trace("velocity", "rad/s", self.motor.short_type_name(), velocity),
+                trace("current", "A", self.motor.short_type_name(), current),
+                trace(
+                    "voltage",
+                    "V",
+                    self.current_controller.short_type_name(),
+                    voltage,
+                ),
+            ],
+        }
+    }
+}
+
+impl Default for ServoScenario {
+    /// 24 V motor, 10:1 gearbox with 0.2° play, 4096 counts encoder, 1 rad step after 10 ms
+    fn default() -> Self {
+        // current loop: compensate L/R, crossover 2000 rad/s
+        let current_controller = PI::<f64>::default()
+            .set_sample_time_or_default(SAMPLE_TIME)
+            .set_kp(2.0)
+            .set_ti_time_or_default(1.0e-3)
+            .set_output_limits(-24.0, 24.0)
+            .set_anti_windup(AntiWindup::Clamping);
+        // velocity loop: crossover 200 rad/s on Kt / (J s)
+        let velocity_controller = PI::<f64>::default()
+            .set_sample_time_or_default(SAMPLE_TIME)
+            .set_kp(0.04)
+            .set_ti_time_or_default(0.02)
+            .set_output_limits(-10.0, 10.0)
+            .set_anti_windup(AntiWindup::Clamping);
+        // position loop: proportional only, crossover 30 rad/s on the load
+        let position_controller = PI::<f64>::default()
+            .set_sample_time_or_default(SAMPLE_TIME)
+            .set_kp(300.0)
+            .set_ti_time_or_default(f64::INFINITY)
+            .set_output_limits(-400.0, 400.0);
+        ServoScenario {
+            range: TimeRange::default()
+                .set_unit_of_measurement("s")
+                .set_sampling_interval(SAMPLE_TIME)
+                .set_end(0.3),
+            setpoint: Box::new(StepFunction::default().post(1.0).step(0.01)),
+            position_controller,
+            velocity_controller,
+            current_controller,
+            motor: DcMotor::default().set_sample_time_or_default(SAMPLE_TIME),
+            gear_ratio: 10.0,
+            gearbox: Backlash::default().set_width_or_default(0.2f64.to_radians()),
+            encoder: Quantizer::default()
+                .set_step(TAU / 4096.0)
+                .expect("positive step")
+                .set_rounding(Rounding::Floor),
+        }
+    }
+}
+
+impl Diagram for ServoScenario {
+    fn run(&mut self, range: TimeRange) -> SimResult {
+        self.range = range;
+        ServoScenario::run(self)
+    }
+
+    /// At rest at the load position `setpoint`, no current, no integral parts
+    fn initialize_at(&mut self, setpoint: f64) -> Result<(), InitializationError> {
+        self.motor
+            .restore_state(&array![0.0, 0.0, setpoint * self.gear_ratio]);
+        self.gearbox = self.gearbox.reset(setpoint);
+        for controller in [
+            &mut self.position_controller,
+            &mut self.velocity_controller,
+            &mut self.current_controller,
+        ] {
+            controller.restore_state(&0.0);
+        }
+        Ok(())
+    }
+
+    fn parameters(&self) -> Vec<(String, String)> {
+        let parameter =
+            |name: &str, value: &dyn Display| (String::from(name), format!("{}", value));
+        vec![
+            parameter("setpoint", &self.setpoint),
+            parameter("position_controller", &self.position_controller),
+            parameter("velocity_controller", &self.velocity_controller),
+            parameter("current_controller", &self.current_controller),
+            parameter("motor", &self.motor),
+            parameter("gear_ratio", &self.gear_ratio),
+            parameter("gearbox", &self.gearbox),
+            parameter("encoder", &self.encoder),
+        ]
+    }
+}
+
+impl PartialEq for ServoScenario {
+    fn eq(&self, other: &Self) -> bool {
+        self.setpoint.eq(&other.setpoint)
+            && self.range == other.range
+            && self.position_controller == other.position_controller
+            && self.velocity_controller == other.velocity_controller
+            && self.current_controller == other.current_controller
+            && self.motor == other.motor
+            && self.gear_ratio == other.gear_ratio
+            && self.gearbox == other.gearbox
+            && self.encoder == other.encoder
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_ServoScenario_cascade_limits_and_quantization() {
+        let result = ServoScenario::default().run();
+        let voltage = &result.trace("voltage").unwrap().values;
+        assert!(voltage.iter().all(|v| v.abs() <= 24.0));
+        let current = &result.trace("current").unwrap().values;
+        assert!(current.iter().any(|i| i.abs() > 1.0));
+        let resolution = TAU / 4096.0;
+        let position = &result.trace("position").unwrap().values;
+        assert!(
+            position
+                .iter()
+                .all(|p| ((p / resolution) - (p / resolution).round()).abs() < 1e-6)
+        );
+        let settled = position[position.len() - 1];
+        assert!((settled - 1.0).abs() < 0.02, "{}", settled);
+    }
+
+    #[test]
+    fn test_ServoScenario_initialize_at_holds_position() {
+        let mut sut = ServoScenario {
+            setpoint: Box::new(StepFunction::default().pre(1.0).post(1.0)),
+            ..ServoScenario::default()
+        };
+        sut.initialize_at(1.0).unwrap();
+        let result = sut.run();
+        let position = &result.trace("position").unwrap().values;
+        let resolution = TAU / 4096.0;
+        assert!(position.iter().all(|p| (p - 1.0).abs() <= 2.0 * resolution));
+    }
+
+    #[test]
+    fn test_ServoScenario_without_backlash_settles_to_encoder_resolution() {
+        let mut sut = ServoScenario {
+            gearbox: Backlash::default(),
+            ..ServoScenario::default()
+        };
+        let result = sut.run();
+        let position = &result.trace("position").unwrap().values;
+        let resolution = TAU / 4096.0;
+        assert!((position[position.len() - 1] - 1.0).abs() <= resolution);
+    }
+}
@@ -0,0 +1,343 @@
+//! # Alignment of logs with absolute time stamps
+//!
+//! Field logs carry absolute time stamps, often from different devices in
+//! different time zones, starting at different times and with gaps where a
+//! logger was offline. `align` maps such logs onto one common relative time
+//! axis in seconds, ready for identification or replay:
+//!
+//! * the time origin is the latest start of all logs and the end the earliest
+//!   end, so every aligned signal is backed by data over the whole range
+//! * each log is resampled onto the common grid by linear interpolation
+//! * grid points within a gap, i.e. between two samples further apart than
+//!   `max_gap`, are `NaN` and listed in `Alignment::gaps`
+//!
+//! Time stamps in CSV text are read as RFC 3339 (`2024-03-01T12:00:00+01:00`),
+//! as local date and time in a given zone (`2024-03-01 12:00:00.250`) or as
+//! seconds since the Unix epoch. Requires the feature `chrono`.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use chrono::FixedOffset;
+//! use cb_simulation_util::signal::TimeSignal;
+//! use cb_simulation_util::signal::alignment::{TimestampedLog, align};
+//!
+//! fn main() {
+//!     let utc = FixedOffset::east_opt(0).unwrap();
+//!     // the same instants, logged in UTC and in UTC+01:00
+//!     let a = "time,value\n2024-03-01 10:00:00,0\n2024-03-01 10:00:10,10\n";
+//!     let b = "2024-03-01T11:00:05+01:00;1\n2024-03-01T11:00:20+01:00;4\n";
+//!     let logs = [
+//!         TimestampedLog::from_csv(a, 0, 1, utc).unwrap(),
+//!         TimestampedLog::from_csv(b, 0, 1, utc).unwrap(),
+//!     ];
+//!     let aligned = align(&logs, 1.0, 60.0).unwrap();
+//!     // overlap from 10:00:05 to 10:00:10
+//!     assert_eq!(aligned.time_range.end, 5.0);
+//!     assert_eq!(aligned.signals[0].time_to_signal(0.0), 5.0);
+//!     assert_eq!(aligned.signals[1].time_to_signal(5.0), 2.0);
+//! }
+//! ```
+
+use chrono::{DateTime, FixedOffset, NaiveDateTime, TimeZone, Utc};
+use std::string::String;
+use std::vec::Vec;
+
+use super::recorded::{RecordedSignal, RecordedSignalError};
+use super::time_range::TimeRange;
+use super::*;
+
+/// Samples of a log with absolute time stamps
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimestampedLog {
+    timestamps: Vec<DateTime<Utc>>,
+    values: Vec<f64>,
+}
+
+/// Parse a time stamp, see the module documentation
+fn parse_timestamp(field: &str, zone: FixedOffset) -> Option<DateTime<Utc>> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(field) {
+        return Some(time.with_timezone(&Utc));
+    }
+    for format in ["%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%dT%H:%M:%S%.f"] {
+        if let Ok(local) = NaiveDateTime::parse_from_str(field, format) {
+            return zone
+                .from_local_datetime(&local)
+                .single()
+                .map(|time| time.with_timezone(&Utc));
+        }
+    }
+    let seconds = field.parse::<f64>().ok()?;
+    let nanos = (seconds.fract() * 1e9).round() as u32;
+    DateTime::from_timestamp(seconds.floor() as i64, nanos.min(999_999_999))
+}
+
+impl TimestampedLog {
+    /// Fails if the lengths differ, the log is empty or the time stamps do
+    /// not strictly increase
+    pub fn new(
+        timestamps: Vec<DateTime<Utc>>,
+        values: Vec<f64>,
+    ) -> Result<Self, RecordedSignalError> {
+        if timestamps.len() != values.len() {
+            return Err(RecordedSignalError::LengthMismatch {
+                time: timestamps.len(),
+                values: values.len(),
+            });
+        }
+        if timestamps.is_empty() {
+            return Err(RecordedSignalError::Empty);
+        }
+        if let Some(index) = timestamps.windows(2).position(|w| w[1] <= w[0]) {
+            return Err(RecordedSignalError::NotAscending { index: index + 1 });
+        }
+        Ok(TimestampedLog { timestamps, values })
+    }
+
+    /// Read the columns `time_column` and `value_column` (0 based) of CSV text
+    ///
+    /// Time stamps without an offset are local times in `zone`. Separators,
+    /// comments and headers are handled like `RecordedSignal::from_csv`.
+    pub fn from_csv(
+        text: &str,
+        time_column: usize,
+        value_column: usize,
+        zone: FixedOffset,
+    ) -> Result<Self, RecordedSignalError> {
+        let mut timestamps = Vec::new();
+        let mut values = Vec::new();
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let fields: Vec<&str> = line.split([',', ';', '\t']).map(|f| f.trim()).collect();
+            let time = fields
+                .get(time_column)
+                .and_then(|f| parse_timestamp(f, zone));
+            let value = fields.get(value_column).and_then(|f| f.parse::<f64>().ok());
+            match (time, value) {
+                (Some(time), Some(value)) => {
+                    timestamps.push(time);
+                    values.push(value);
+                }
+                // header lines before the first data line
+                _ if timestamps.is_empty() => {}
+                (None, _) => {
+                    return Err(RecordedSignalError::Parse {
+                        line: i + 1,
+                        message: String::from("invalid time stamp"),
+                    });
+                }
+                (_, None) => {
+                    return Err(RecordedSignalError::Parse {
+                        line: i + 1,
+                        message: String::from("invalid number"),
+                    });
+                }
+            }
+        }
+        TimestampedLog::new(timestamps, values)
+    }
+
+    pub fn timestamps(&self) -> &[DateTime<Utc>] {
+        &self.timestamps
+    }
+
+    pub fn values(&self) -> &[f64] {
+        &self.values
+    }
+
+    pub fn start(&self) -> DateTime<Utc> {
+        self.timestamps[0]
+    }
+
+    pub fn end(&self) -> DateTime<Utc> {
+        self.timestamps[self.timestamps.len() - 1]
+    }
+
+    /// Time stamps in seconds relative to `origin`
+    fn relative_time(&self, origin: DateTime<Utc>) -> Vec<f64> {
+        self.timestamps
+            .iter()
+            .map(|t| seconds_between(origin, *t))
+            .collect()
+    }
+}
+
+fn seconds_between(from: DateTime<Utc>, to: DateTime<Utc>) -> f64 {
+    let delta = to - from;
+    delta.num_seconds() as f64 + delta.subsec_nanos() as f64 * 1e-9
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlignmentError {
+    /// No log given
+    NoLogs,
+    /// The logs do not overlap by at least one sample time
+    NoOverlap,
+    /// The sample time and the maximum gap must be > 0
+    InvalidSampleTime,
+}
+
+impl fmt::Display for AlignmentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AlignmentError::NoLogs => write!(f, "No logs to align"),
+            AlignmentError::NoOverlap => write!(f, "Logs do not overlap"),
+            AlignmentError::InvalidSampleTime => {
+                write!(f, "Sample time and maximum gap must be > 0")
+            }
+        }
+    }
+}
+
+/// A gap of a log on the relative time axis
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LogGap {
+    /// Index of the log
+    pub log: usize,
+    /// Last sample before the gap in s
+    pub start: f64,
+    /// First sample after the gap in s
+    pub end: f64,
+}
+
+/// Logs resampled onto a common relative time axis, see `align`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Alignment {
+    /// Absolute time of the relative time 0
+    pub origin: DateTime<Utc>,
+    /// Common time range in s
+    pub time_range: TimeRange,
+    /// One signal per log, in the order of the logs
+    pub signals: Vec<RecordedSignal>,
+    /// Gaps within the time range
+    pub gaps: Vec<LogGap>,
+}
+
+/// Resample `logs` onto their common time range with `sample_time` in s
+///
+/// Samples more than `max_gap` seconds apart mark a gap, see `Alignment::gaps`.
+pub fn align(
+    logs: &[TimestampedLog],
+    sample_time: f64,
+    max_gap: f64,
+) -> Result<Alignment, AlignmentError> {
+    if !(sample_time > 0.0 && max_gap > 0.0) {
+        return Err(AlignmentError::InvalidSampleTime);
+    }
+    let origin = logs
+        .iter()
+        .map(|log| log.start())
+        .max()
+        .ok_or(AlignmentError::NoLogs)?;
+    let end = logs.iter().map(|log| log.end()).min().unwrap_or(origin);
+    let duration = seconds_between(origin, end);
+    if duration < sample_time {
+        return Err(AlignmentError::NoOverlap);
+    }
+    let steps = (duration / sample_time + 1e-9).floor() as usize;
+    let grid: Vec<f64> = (0..=steps).map(|k| k as f64 * sample_time).collect();
+    let mut signals = Vec::with_capacity(logs.len());
+    let mut gaps = Vec::new();
+    for (index, log) in logs.iter().enumerate() {
+        let time = log.relative_time(origin);
+        let log_gaps: Vec<LogGap> = time
+            .windows(2)
+            .filter(|w| w[1] - w[0] > max_gap && w[1] > 0.0 && w[0] < duration)
+            .map(|w| LogGap {
+                log: index,
+                start: w[0],
+                end: w[1],
+            })
+            .collect();
+        let recorded = RecordedSignal::new(time, log.values.clone())
+            .expect("time stamps checked by TimestampedLog");
+        let values = grid
+            .iter()
+            .map(|t| {
+                if log_gaps.iter().any(|gap| gap.start < *t && *t < gap.end) {
+                    f64::NAN
+                } else {
+                    recorded.time_to_signal(*t)
+                }
+            })
+            .collect();
+        signals.push(RecordedSignal::new(grid.clone(), values).expect("grid strictly increases"));
+        gaps.extend(log_gaps);
+    }
+    Ok(Alignment {
+        origin,
+        time_range: TimeRange::default()
+            .set_unit_of_measurement("s")
+            .set_end(duration)
+            .set_sampling_interval(sample_time),
+        signals,
+        gaps,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn utc() -> FixedOffset {
+        FixedOffset::east_opt(0).unwrap()
+    }
+
+    #[test]
+    fn test_parse_timestamp_formats() {
+        let expected = Utc.with_ymd_and_hms(2024, 3, 1, 10, 0, 0).unwrap();
+        let cet = FixedOffset::east_opt(3600).unwrap();
+        assert_eq!(
+            parse_timestamp("2024-03-01T11:00:00+01:00", utc()),
+            Some(expected)
+        );
+        assert_eq!(parse_timestamp("2024-03-01 11:00:00", cet), Some(expected));
+        assert_eq!(
+            parse_timestamp("2024-03-01T10:00:00.000", utc()),
+            Some(expected)
+        );
+        assert_eq!(
+            parse_timestamp(&std::format!("{}", expected.timestamp()), cet),
+            Some(expected)
+        );
+        assert_eq!(parse_timestamp("time", utc()), None);
+    }
+
+    #[test]
+    fn test_align_gap_and_errors() {
+        // a one second log with a gap from 3 s to 7 s
+        let a = "0,0\n1,1\n2,2\n3,3\n7,7\n8,8\n9,9\n10,10\n";
+        let b = "2,0\n4,2\n6,4\n8,6\n10,8\n12,10\n";
+        let logs = [
+            TimestampedLog::from_csv(a, 0, 1, utc()).unwrap(),
+            TimestampedLog::from_csv(b, 0, 1, utc()).unwrap(),
+        ];
+        let aligned = align(&logs, 1.0, 2.0).unwrap();
+        assert_eq!(aligned.origin, logs[1].start());
+        assert_eq!(aligned.signals[0].len(), 9);
+        assert_eq!(
+            aligned.gaps,
+            [LogGap {
+                log: 0,
+                start: 1.0,
+                end: 5.0
+            }]
+        );
+        let values = aligned.signals[0].values();
+        assert_eq!(values[1], 3.0);
+        assert!(values[2..5].iter().all(|v| v.is_nan()));
+        assert_eq!(values[5], 7.0);
+        assert_eq!(aligned.signals[1].values()[8], 8.0);
+        assert_eq!(align(&[], 1.0, 1.0), Err(AlignmentError::NoLogs));
+        assert_eq!(align(&logs, 20.0, 1.0), Err(AlignmentError::NoOverlap));
+        assert_eq!(
+            align(&logs, 1.0, 0.0),
+            Err(AlignmentError::InvalidSampleTime)
+        );
+        assert!(TimestampedLog::from_csv("0,1\nx,2\n", 0, 1, utc()).is_err());
+    }
+}
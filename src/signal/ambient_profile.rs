@@ -0,0 +1,251 @@
+//! # Ambient Profile - Time Signal
+//!
+//! Environmental temperature profile for long-horizon HVAC and battery
+//! thermal simulations, composed of
+//!
+//! * a mean value with linear drift (e.g. climate trend, sensor aging)
+//! * a seasonal cosine over the year
+//! * a daily cosine with configurable time of the maximum
+//! * heat wave events, each a smooth $ \sin^2 $ bump added on top
+//! * smooth random weather noise, seeded and reproducible
+//!
+//! Time is counted from the start of the year. By default the time unit is
+//! seconds; use `day_length` for other time units.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use cb_simulation_util::signal::{AmbientProfile, HeatWave, TimeSignal};
+//!
+//! fn main () {
+//!   const DAY: f64 = 86400.0;
+//!   let profile = AmbientProfile::default()
+//!       .mean(10.0)
+//!       .seasonal(8.0, 200.0)
+//!       .daily(5.0, 15.0)
+//!       .heat_wave(HeatWave { start_day: 195.0, duration_days: 6.0, peak: 7.0 });
+//!   let afternoon = profile.time_to_signal(180.0 * DAY + 15.0 * 3600.0);
+//!   let night = profile.time_to_signal(180.0 * DAY + 3.0 * 3600.0);
+//!   assert!(afternoon - night > 9.0);
+//!   let heat_wave = profile.time_to_signal(198.0 * DAY + 15.0 * 3600.0);
+//!   assert!(heat_wave > 29.0);
+//! }
+//! ```
+
+use core::f64::consts::PI;
+use std::vec::Vec;
+
+pub use super::*;
+use crate::rng;
+
+/// A temporary temperature rise, e.g. a heat wave or a cold snap (negative peak)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HeatWave {
+    pub start_day: f64,
+    pub duration_days: f64,
+    /// Rise in the middle of the event
+    pub peak: f64,
+}
+
+impl HeatWave {
+    fn offset(&self, day: f64) -> f64 {
+        let phase = (day - self.start_day) / self.duration_days;
+        if (0.0..=1.0).contains(&phase) {
+            self.peak * (PI * phase).sin().powi(2)
+        } else {
+            0.0
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AmbientProfile {
+    /// Length of a day in the time unit of the simulation
+    pub day_length: f64,
+    pub days_per_year: f64,
+    pub mean: f64,
+    /// Change of the mean per day
+    pub drift_per_day: f64,
+    pub seasonal_amplitude: f64,
+    /// Day of the year with the seasonal maximum
+    pub seasonal_peak_day: f64,
+    pub daily_amplitude: f64,
+    /// Hour of the day with the daily maximum
+    pub daily_peak_hour: f64,
+    pub heat_waves: Vec<HeatWave>,
+    pub noise_amplitude: f64,
+    /// Time between two independent noise values, linearly interpolated in between
+    pub noise_interval_hours: f64,
+    pub seed: u64,
+}
+
+impl AmbientProfile {
+    pub fn day_length(self, day_length: f64) -> Self {
+        AmbientProfile { day_length, ..self }
+    }
+
+    pub fn mean(self, mean: f64) -> Self {
+        AmbientProfile { mean, ..self }
+    }
+
+    pub fn drift(self, drift_per_day: f64) -> Self {
+        AmbientProfile {
+            drift_per_day,
+            ..self
+        }
+    }
+
+    pub fn seasonal(self, seasonal_amplitude: f64, seasonal_peak_day: f64) -> Self {
+        AmbientProfile {
+            seasonal_amplitude,
+            seasonal_peak_day,
+            ..self
+        }
+    }
+
+    pub fn daily(self, daily_amplitude: f64, daily_peak_hour: f64) -> Self {
+        AmbientProfile {
+            daily_amplitude,
+            daily_peak_hour,
+            ..self
+        }
+    }
+
+    pub fn heat_wave(mut self, heat_wave: HeatWave) -> Self {
+        self.heat_waves.push(heat_wave);
+        self
+    }
+
+    pub fn noise(self, noise_amplitude: f64, noise_interval_hours: f64, seed: u64) -> Self {
+        AmbientProfile {
+            noise_amplitude,
+            noise_interval_hours,
+            seed,
+            ..self
+        }
+    }
+
+    /// Mid-latitude continental climate in °C, summer maximum end of July
+    pub fn continental() -> Self {
+        AmbientProfile::default()
+            .mean(9.0)
+            .seasonal(10.0, 200.0)
+            .daily(5.0, 15.0)
+            .noise(2.0, 6.0, 0)
+    }
+
+    /// Hot desert climate in °C with large daily swing
+    pub fn desert() -> Self {
+        AmbientProfile::default()
+            .mean(26.0)
+            .seasonal(8.0, 195.0)
+            .daily(9.0, 15.0)
+            .noise(1.0, 6.0, 0)
+    }
+
+    fn weather_noise(&self, day: f64) -> f64 {
+        if self.noise_amplitude == 0.0 || self.noise_interval_hours <= 0.0 {
+            return 0.0;
+        }
+        let position = day * 24.0 / self.noise_interval_hours;
+        let slot = position.floor();
+        let knot = |k: f64| 2.0 * rng::unit(rng::mix(self.seed ^ rng::mix(k as i64 as u64))) - 1.0;
+        let (a, b) = (knot(slot), knot(slot + 1.0));
+        self.noise_amplitude * (a + (b - a) * (position - slot))
+    }
+}
+
+impl Default for AmbientProfile {
+    fn default() -> Self {
+        AmbientProfile {
+            day_length: 86400.0,
+            days_per_year: 365.0,
+            mean: 0.0,
+            drift_per_day: 0.0,
+            seasonal_amplitude: 0.0,
+            seasonal_peak_day: 0.0,
+            daily_amplitude: 0.0,
+            daily_peak_hour: 0.0,
+            heat_waves: Vec::new(),
+            noise_amplitude: 0.0,
+            noise_interval_hours: 1.0,
+            seed: 0,
+        }
+    }
+}
+
+impl TimeSignal<f64> for AmbientProfile {
+    fn time_to_signal(&self, time: f64) -> f64 {
+        let day = time / self.day_length;
+        let seasonal = self.seasonal_amplitude
+            * (2.0 * PI * (day - self.seasonal_peak_day) / self.days_per_year).cos();
+        let daily = self.daily_amplitude * (2.0 * PI * (day - self.daily_peak_hour / 24.0)).cos();
+        let events: f64 = self.heat_waves.iter().map(|h| h.offset(day)).sum();
+        self.mean + self.drift_per_day * day + seasonal + daily + events + self.weather_noise(day)
+    }
+
+    fn short_type_name(&self) -> &'static str {
+        "AmbientProfile"
+    }
+}
+
+impl fmt::Display for AmbientProfile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}(mean={}, drift={}, seasonal={}@{}, daily={}@{}h, heat_waves={}, noise={})",
+            self.short_type_name(),
+            self.mean,
+            self.drift_per_day,
+            self.seasonal_amplitude,
+            self.seasonal_peak_day,
+            self.daily_amplitude,
+            self.daily_peak_hour,
+            self.heat_waves.len(),
+            self.noise_amplitude
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_ambient_profile_daily_peak() {
+        let sut = AmbientProfile::default().daily(4.0, 14.0).day_length(24.0);
+        assert!((sut.time_to_signal(14.0) - 4.0).abs() < 1e-9);
+        assert!((sut.time_to_signal(2.0) + 4.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_ambient_profile_heat_wave_and_drift() {
+        let sut = AmbientProfile::default()
+            .day_length(1.0)
+            .drift(0.01)
+            .heat_wave(HeatWave {
+                start_day: 10.0,
+                duration_days: 4.0,
+                peak: 6.0,
+            });
+        assert!((sut.time_to_signal(5.0) - 0.05).abs() < 1e-9);
+        assert!((sut.time_to_signal(12.0) - 6.12).abs() < 1e-9);
+        assert!((sut.time_to_signal(14.5) - 0.145).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_ambient_profile_noise_is_continuous_and_bounded() {
+        let sut = AmbientProfile::continental().noise(2.0, 6.0, 3);
+        let values: Vec<f64> = (0..24 * 30)
+            .map(|h| sut.weather_noise(h as f64 / 24.0))
+            .collect();
+        assert!(values.iter().all(|v| v.abs() <= 2.0));
+        assert!(
+            values
+                .windows(2)
+                .all(|w| (w[1] - w[0]).abs() <= 4.0 / 6.0 + 1e-9)
+        );
+        assert_eq!(sut.time_to_signal(1.0e6), sut.clone().time_to_signal(1.0e6));
+    }
+}
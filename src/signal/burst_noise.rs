@@ -0,0 +1,208 @@
+//! # Burst Noise - Time Signal
+//!
+//! Intermittent interference such as EMC bursts: a two-state Markov process
+//! (quiet / burst) gates a uniform white noise source. Time is divided into
+//! slots of `slot_time`; per slot a quiet process starts a burst with
+//! probability `burst_probability`, a burst ends with probability
+//! `recovery_probability`. The mean burst length is `slot_time / recovery_probability`.
+//!
+//! Within a burst the noise is uniform in `[-amplitude, amplitude]`,
+//! while quiet in `[-quiet_amplitude, quiet_amplitude]`.
+//!
+//! The value is a pure function of seed and time: the gate state of a slot is
+//! found by walking back to the most recent slot whose transition does not
+//! depend on the previous state, and replaying the chain from there.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use cb_simulation_util::signal::{BurstNoise, TimeSignal};
+//!
+//! fn main () {
+//!   let noise = BurstNoise::default()
+//!       .probabilities(0.02, 0.2)
+//!       .amplitude(5.0)
+//!       .seed(7);
+//!   let bursts = (0..1000).filter(|k| noise.is_burst(*k as f64)).count();
+//!   assert!(bursts > 0 && bursts < 300);
+//!   assert!(noise.time_to_signal(1.0).abs() <= 5.0);
+//! }
+//! ```
+
+pub use super::*;
+use crate::rng;
+
+/// Stream separation of gate and noise values drawn from the same seed
+const NOISE_STREAM: u64 = 0x6E6F_6973_6500_0000;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BurstNoise {
+    pub slot_time: f64,
+    /// Probability per slot of a quiet process to start a burst
+    pub burst_probability: f64,
+    /// Probability per slot of a burst to end
+    pub recovery_probability: f64,
+    pub amplitude: f64,
+    pub quiet_amplitude: f64,
+    pub seed: u64,
+}
+
+impl BurstNoise {
+    /// Falls back to the default of 1.0 if not > 0 and finite
+    pub fn slot_time(self, slot_time: f64) -> Self {
+        let valid = slot_time > 0.0 && slot_time.is_finite();
+        let slot_time = if valid { slot_time } else { 1.0 };
+        BurstNoise { slot_time, ..self }
+    }
+
+    /// Transition probabilities quiet → burst and burst → quiet, clamped to `[0, 1]`
+    pub fn probabilities(self, burst_probability: f64, recovery_probability: f64) -> Self {
+        BurstNoise {
+            burst_probability: burst_probability.clamp(0.0, 1.0),
+            recovery_probability: recovery_probability.clamp(0.0, 1.0),
+            ..self
+        }
+    }
+
+    pub fn amplitude(self, amplitude: f64) -> Self {
+        BurstNoise { amplitude, ..self }
+    }
+
+    pub fn quiet_amplitude(self, quiet_amplitude: f64) -> Self {
+        BurstNoise {
+            quiet_amplitude,
+            ..self
+        }
+    }
+
+    pub fn seed(self, seed: u64) -> Self {
+        BurstNoise { seed, ..self }
+    }
+
+    fn slot_unit(&self, slot: i64) -> f64 {
+        rng::unit(rng::mix(self.seed ^ rng::mix(slot as u64)))
+    }
+
+    /// Gate state of the slot containing `time`, slots before time 0 are quiet
+    pub fn is_burst(&self, time: f64) -> bool {
+        if time < 0.0 {
+            return false;
+        }
+        let slot = (time / self.slot_time).floor() as i64;
+        let stay = 1.0 - self.recovery_probability;
+        let (forced_burst, forced_quiet) = (
+            self.burst_probability.min(stay),
+            self.burst_probability.max(stay),
+        );
+        let mut start = slot;
+        let mut burst = false;
+        while start >= 0 {
+            let u = self.slot_unit(start);
+            if u < forced_burst {
+                burst = true;
+                break;
+            }
+            if u >= forced_quiet {
+                break;
+            }
+            start -= 1;
+        }
+        for k in (start + 1).max(0)..=slot {
+            let threshold = if burst { stay } else { self.burst_probability };
+            burst = self.slot_unit(k) < threshold;
+        }
+        burst
+    }
+}
+
+impl Default for BurstNoise {
+    fn default() -> Self {
+        BurstNoise {
+            slot_time: 1.0,
+            burst_probability: 0.01,
+            recovery_probability: 0.1,
+            amplitude: 1.0,
+            quiet_amplitude: 0.0,
+            seed: 0,
+        }
+    }
+}
+
+impl TimeSignal<f64> for BurstNoise {
+    fn time_to_signal(&self, time: f64) -> f64 {
+        let amplitude = if self.is_burst(time) {
+            self.amplitude
+        } else {
+            self.quiet_amplitude
+        };
+        amplitude * (2.0 * rng::unit_at(self.seed ^ NOISE_STREAM, time) - 1.0)
+    }
+
+    fn short_type_name(&self) -> &'static str {
+        "BurstNoise"
+    }
+}
+
+impl fmt::Display for BurstNoise {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}(slot_time={}, burst_probability={}, recovery_probability={}, amplitude={}, quiet_amplitude={}, seed={})",
+            self.short_type_name(),
+            self.slot_time,
+            self.burst_probability,
+            self.recovery_probability,
+            self.amplitude,
+            self.quiet_amplitude,
+            self.seed
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_burst_noise_duty_cycle() {
+        // stationary burst fraction p_on / (p_on + p_off) = 0.2
+        let sut = BurstNoise::default().probabilities(0.05, 0.2).seed(11);
+        let n = 20000;
+        let bursts = (0..n).filter(|k| sut.is_burst(*k as f64)).count();
+        let fraction = bursts as f64 / n as f64;
+        assert!((fraction - 0.2).abs() < 0.03, "{}", fraction);
+    }
+
+    #[test]
+    fn test_burst_noise_matches_sequential_chain() {
+        let sut = BurstNoise::default().probabilities(0.1, 0.3).seed(5);
+        let mut burst = false;
+        for k in 0..500 {
+            let threshold = if burst { 0.7 } else { 0.1 };
+            burst = sut.slot_unit(k) < threshold;
+            assert_eq!(sut.is_burst(k as f64 + 0.5), burst);
+        }
+    }
+
+    #[test]
+    fn test_burst_noise_invalid_slot_time() {
+        for slot_time in [0.0, -2.0, f64::NAN, f64::INFINITY] {
+            let sut = BurstNoise::default().slot_time(slot_time);
+            assert_eq!(sut.slot_time, 1.0);
+        }
+        let sut = BurstNoise::default().slot_time(0.5).seed(3);
+        let reference = BurstNoise::default().seed(3);
+        assert_eq!(sut.is_burst(10.25), reference.is_burst(20.5));
+    }
+
+    #[test]
+    fn test_burst_noise_quiet_amplitude() {
+        let sut = BurstNoise::default()
+            .probabilities(0.0, 1.0)
+            .quiet_amplitude(0.1);
+        for k in 0..100 {
+            assert!(sut.time_to_signal(k as f64 * 0.3).abs() <= 0.1);
+        }
+    }
+}
@@ -0,0 +1,90 @@
+//! # Drive cycles
+//!
+//! Loads standard drive-cycle speed profiles (WLTP, NEDC, FTP-75 style
+//! time-speed tables) as `RecordedSignal` for automotive controller testing.
+//!
+//! The table is expected as CSV with time in seconds in the first and speed
+//! in the second column, header and comment lines are skipped. The speed is
+//! converted to m/s and can be resampled to the simulation sample time.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use cb_simulation_util::signal::TimeSignal;
+//! use cb_simulation_util::signal::drive_cycle::{DriveCycle, SpeedUnit};
+//!
+//! fn main () {
+//!   let table = "# excerpt\ntime [s],speed [km/h]\n0,0\n1,0\n2,3.6\n3,7.2\n";
+//!   let cycle = DriveCycle::from_csv(table, SpeedUnit::KilometersPerHour)
+//!       .unwrap()
+//!       .resample(0.1)
+//!       .unwrap();
+//!   assert!((cycle.time_to_signal(2.5) - 1.5).abs() < 1e-9);
+//!   assert!((DriveCycle::distance(&cycle) - 2.0).abs() < 1e-9);
+//! }
+//! ```
+
+use super::RecordedSignal;
+use super::recorded::RecordedSignalError;
+
+/// Unit of the speed column
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SpeedUnit {
+    MetersPerSecond,
+    KilometersPerHour,
+    MilesPerHour,
+}
+
+impl SpeedUnit {
+    /// Factor converting into m/s
+    pub fn to_meters_per_second(&self) -> f64 {
+        match self {
+            SpeedUnit::MetersPerSecond => 1.0,
+            SpeedUnit::KilometersPerHour => 1.0 / 3.6,
+            SpeedUnit::MilesPerHour => 0.44704,
+        }
+    }
+}
+
+pub struct DriveCycle;
+
+impl DriveCycle {
+    /// Speed profile in m/s from a time-speed table
+    pub fn from_csv(text: &str, unit: SpeedUnit) -> Result<RecordedSignal, RecordedSignalError> {
+        Ok(RecordedSignal::from_csv(text, 0, 1)?.scale(unit.to_meters_per_second()))
+    }
+
+    /// Speed profile in m/s from a time-speed table file
+    pub fn from_file<P: AsRef<std::path::Path>>(
+        path: P,
+        unit: SpeedUnit,
+    ) -> Result<RecordedSignal, RecordedSignalError> {
+        Ok(RecordedSignal::from_file(path, 0, 1)?.scale(unit.to_meters_per_second()))
+    }
+
+    /// Distance in m covered by a speed profile in m/s, trapezoidal rule
+    pub fn distance(cycle: &RecordedSignal) -> f64 {
+        cycle
+            .time()
+            .windows(2)
+            .zip(cycle.values().windows(2))
+            .map(|(t, v)| (t[1] - t[0]) * (v[0] + v[1]) / 2.0)
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::signal::TimeSignal;
+
+    #[test]
+    fn test_drive_cycle_units() {
+        let sut = DriveCycle::from_csv("0\t0\n10\t36\n", SpeedUnit::KilometersPerHour).unwrap();
+        assert!((sut.time_to_signal(10.0) - 10.0).abs() < 1e-12);
+        assert!((DriveCycle::distance(&sut) - 50.0).abs() < 1e-12);
+        let sut = DriveCycle::from_csv("0;10\n", SpeedUnit::MilesPerHour).unwrap();
+        assert!((sut.time_to_signal(0.0) - 4.4704).abs() < 1e-12);
+    }
+}
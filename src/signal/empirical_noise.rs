@@ -0,0 +1,158 @@
+//! # Empirical Noise - Time Signal
+//!
+//! Noise drawn from a user supplied distribution, given either as histogram
+//! or as measured samples (empirical CDF), e.g. a recorded sensor noise
+//! distribution. Values are drawn by inverse transform sampling of the
+//! piecewise linear CDF.
+//!
+//! The value is a pure function of seed and time, so the same seed always
+//! yields the same signal.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use cb_simulation_util::signal::{EmpiricalNoise, TimeSignal};
+//!
+//! fn main () {
+//!   // most of the noise within [-0.1, 0.1], rare outliers up to 1.0
+//!   let noise = EmpiricalNoise::from_histogram(&[-1.0, -0.1, 0.1, 1.0], &[1.0, 98.0, 1.0])
+//!       .unwrap()
+//!       .seed(42);
+//!   let v = noise.time_to_signal(3.0);
+//!   assert!((-1.0..=1.0).contains(&v));
+//!   assert_eq!(v, noise.time_to_signal(3.0));
+//! }
+//! ```
+
+use std::vec::Vec;
+
+pub use super::*;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EmpiricalNoise {
+    /// Knots `(cumulative probability, value)` of the CDF, ascending
+    quantiles: Vec<(f64, f64)>,
+    pub seed: u64,
+}
+
+impl EmpiricalNoise {
+    /// Distribution from bin `edges` (ascending, one more than `counts`) and bin counts
+    pub fn from_histogram(edges: &[f64], counts: &[f64]) -> Result<Self, &'static str> {
+        if edges.len() != counts.len() + 1 || counts.is_empty() {
+            return Err("Invalid histogram: edges must have one element more than counts");
+        }
+        if edges.windows(2).any(|w| w[1] < w[0]) || counts.iter().any(|c| *c < 0.0) {
+            return Err("Invalid histogram: edges must ascend and counts must be >= 0");
+        }
+        let total: f64 = counts.iter().sum();
+        if total <= 0.0 {
+            return Err("Invalid histogram: counts sum up to zero");
+        }
+        let mut cumulative = 0.0;
+        let mut quantiles = Vec::with_capacity(edges.len());
+        quantiles.push((0.0, edges[0]));
+        for (count, edge) in counts.iter().zip(edges.iter().skip(1)) {
+            cumulative += count / total;
+            quantiles.push((cumulative, *edge));
+        }
+        Ok(EmpiricalNoise { quantiles, seed: 0 })
+    }
+
+    /// Distribution of measured samples, interpolating between sorted samples
+    pub fn from_samples(samples: &[f64]) -> Result<Self, &'static str> {
+        if samples.len() < 2 || samples.iter().any(|s| !s.is_finite()) {
+            return Err("Invalid samples: at least two finite samples required");
+        }
+        let mut sorted = samples.to_vec();
+        sorted.sort_by(f64::total_cmp);
+        let n = (sorted.len() - 1) as f64;
+        Ok(EmpiricalNoise {
+            quantiles: sorted
+                .iter()
+                .enumerate()
+                .map(|(i, v)| (i as f64 / n, *v))
+                .collect(),
+            seed: 0,
+        })
+    }
+
+    pub fn seed(self, seed: u64) -> Self {
+        EmpiricalNoise { seed, ..self }
+    }
+
+    /// Value below which a fraction `p` of the distribution lies
+    pub fn quantile(&self, p: f64) -> f64 {
+        let i = self
+            .quantiles
+            .partition_point(|(c, _)| *c < p)
+            .clamp(1, self.quantiles.len() - 1);
+        let (c0, x0) = self.quantiles[i - 1];
+        let (c1, x1) = self.quantiles[i];
+        if c1 > c0 {
+            x0 + (x1 - x0) * (p - c0) / (c1 - c0)
+        } else {
+            x1
+        }
+    }
+}
+
+impl TimeSignal<f64> for EmpiricalNoise {
+    fn time_to_signal(&self, time: f64) -> f64 {
+        self.quantile(crate::rng::unit_at(self.seed, time))
+    }
+
+    fn short_type_name(&self) -> &'static str {
+        "EmpiricalNoise"
+    }
+}
+
+impl fmt::Display for EmpiricalNoise {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}(seed={}, knots={})",
+            self.short_type_name(),
+            self.seed,
+            self.quantiles.len()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_empirical_noise_quantile() {
+        let sut = EmpiricalNoise::from_histogram(&[0.0, 1.0, 3.0], &[1.0, 1.0]).unwrap();
+        assert_eq!(sut.quantile(0.0), 0.0);
+        assert_eq!(sut.quantile(0.5), 1.0);
+        assert_eq!(sut.quantile(0.75), 2.0);
+        assert_eq!(sut.quantile(1.0), 3.0);
+    }
+
+    #[test]
+    fn test_empirical_noise_from_samples_mean() {
+        let sut = EmpiricalNoise::from_samples(&[1.0, 2.0, 3.0, 4.0, 5.0])
+            .unwrap()
+            .seed(3);
+        let n = 10000;
+        let mean: f64 = (0..n).map(|k| sut.time_to_signal(k as f64)).sum::<f64>() / n as f64;
+        assert!((mean - 3.0).abs() < 0.05);
+    }
+
+    #[test]
+    fn test_empirical_noise_invalid() {
+        assert!(EmpiricalNoise::from_histogram(&[0.0, 1.0], &[0.0]).is_err());
+        assert!(EmpiricalNoise::from_histogram(&[0.0, 1.0], &[1.0, 1.0]).is_err());
+        assert!(EmpiricalNoise::from_samples(&[1.0]).is_err());
+    }
+
+    #[test]
+    fn test_empirical_noise_boxed() {
+        let boxed: BoxedTimeSignal<f64> =
+            Box::new(EmpiricalNoise::from_samples(&[1.0, 2.0]).unwrap());
+        assert!(boxed == boxed.clone());
+    }
+}
@@ -0,0 +1,372 @@
+//! # Time signals from text
+//!
+//! A `SignalRegistry` parses a specification in the format the `Display`
+//! impls of the time signals print, e.g.
+//!
+//! `Step(step_time=5, pre=0, post=1)`
+//!
+//! so stimuli can be given on the command line or in a configuration file.
+//! Like in `plant::registry`, missing parameters keep the value of the
+//! `Default` signal and unknown ones are rejected. A `Superposition` of two
+//! specifications is parsed recursively:
+//!
+//! `Superposition(Step(step_time=1), Ramp(start_time=5, slope=0.5))`
+//!
+//! ## Example
+//!
+//! ```rust
+//! use cb_simulation_util::signal::factory::SignalRegistry;
+//! use cb_simulation_util::signal::StepFunction;
+//!
+//! fn main() {
+//!     let registry = SignalRegistry::builtin();
+//!     let step = registry.parse("Step(step_time=5, post=2)").unwrap();
+//!     assert_eq!(step.time_to_signal(6.0), 2.0);
+//!     // what is printed can be parsed again
+//!     let signal = StepFunction::default().step(1.5).pre(-1.0);
+//!     let parsed = registry.parse(&signal.to_string()).unwrap();
+//!     assert!(parsed.dyn_eq(&signal));
+//!     assert!(registry.parse("Step(step=5)").is_err());
+//! }
+//! ```
+
+use core::fmt;
+use std::boxed::Box;
+use std::string::String;
+use std::vec::Vec;
+
+use super::*;
+use crate::plant::registry::{BuildError, Parameters};
+
+const SUPERPOSITION: &str = "Superposition";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SpecError {
+    /// The text does not follow `Name(key=value, ...)`, at the byte `position`
+    Syntax {
+        position: usize,
+        expected: &'static str,
+    },
+    /// The signal could not be built from its parameters
+    Build(BuildError),
+}
+
+impl Display for SpecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SpecError::Syntax { position, expected } => {
+                write!(f, "Expected {} at position {}", expected, position)
+            }
+            SpecError::Build(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl From<BuildError> for SpecError {
+    fn from(error: BuildError) -> Self {
+        SpecError::Build(error)
+    }
+}
+
+/// Builds a time signal from its parameters
+pub type Constructor = fn(&mut Parameters) -> Result<BoxedTimeSignal<f64>, &'static str>;
+
+#[derive(Default)]
+pub struct SignalRegistry {
+    constructors: Vec<(&'static str, Constructor)>,
+}
+
+impl SignalRegistry {
+    /// Empty registry, `Superposition` is always supported
+    pub fn new() -> Self {
+        SignalRegistry::default()
+    }
+
+    /// Registry with the parametric time signals of this crate
+    pub fn builtin() -> Self {
+        let builtins: [(&'static str, Constructor); 5] = [
+            ("Step", |p| {
+                let d = StepFunction::<f64>::default();
+                Ok(Box::new(
+                    d.step(p.get_or("step_time", d.step_time))
+                        .pre(p.get_or("pre", d.pre_value))
+                        .post(p.get_or("post", d.post_value)),
+                ))
+            }),
+            ("Impulse", |p| {
+                let d = ImpulseFunction::<f64>::default();
+                Ok(Box::new(
+                    d.amplitude(p.get_or("amplitude", d.in_value))
+                        .duration(p.get_or("duration", d.duration))
+                        .start(p.get_or("start_time", d.start_time))
+                        .resting_level(p.get_or("rest_level", d.out_value)),
+                ))
+            }),
+            ("Ramp", |p| {
+                let d = RampFunction::<f64>::default();
+                Ok(Box::new(
+                    d.start(p.get_or("start_time", d.start_time))
+                        .offset(p.get_or("offset", d.offset))
+                        .slope(p.get_or("slope", d.slope)),
+                ))
+            }),
+            ("Square", |p| {
+                let d = SquareWave::<f64>::default();
+                Ok(Box::new(
+                    d.low(p.get_or("low", d.low))
+                        .high(p.get_or("high", d.high))
+                        .period(p.get_or("period", d.period))
+                        .duty_cycle(p.get_or("duty_cycle", d.duty_cycle))
+                        .start(p.get_or("start_time", d.start_time)),
+                ))
+            }),
+            ("Staircase", |p| {
+                let d = Staircase::<f64>::default();
+                let steps = p.get_or("steps", d.steps as f64);
+                if steps < 0.0 || steps > u32::MAX as f64 || steps.fract() != 0.0 {
+                    return Err("Invalid steps: Must be a non-negative integer");
+                }
+                Ok(Box::new(
+                    d.initial(p.get_or("initial", d.initial))
+                        .step_height(p.get_or("step_height", d.step_height))
+                        .step_duration(p.get_or("step_duration", d.step_duration))
+                        .steps(steps as u32)
+                        .start(p.get_or("start_time", d.start_time)),
+                ))
+            }),
+        ];
+        SignalRegistry {
+            constructors: builtins.to_vec(),
+        }
+    }
+
+    /// Add a signal type, fails if the name is already taken
+    ///
+    /// `name` should be the `short_type_name` of the built signals.
+    pub fn register(
+        &mut self,
+        name: &'static str,
+        constructor: Constructor,
+    ) -> Result<(), &'static str> {
+        if name == SUPERPOSITION || self.constructor(name).is_some() {
+            return Err("Signal name already registered");
+        }
+        self.constructors.push((name, constructor));
+        Ok(())
+    }
+
+    fn constructor(&self, name: &str) -> Option<&(&'static str, Constructor)> {
+        self.constructors.iter().find(|(n, _)| *n == name)
+    }
+
+    /// Names in registration order, without `Superposition`
+    pub fn names(&self) -> Vec<&'static str> {
+        self.constructors.iter().map(|(name, _)| *name).collect()
+    }
+
+    /// Build the signal of the specification `spec`
+    pub fn parse(&self, spec: &str) -> Result<BoxedTimeSignal<f64>, SpecError> {
+        let mut parser = Parser { text: spec, pos: 0 };
+        let signal = self.signal(&mut parser)?;
+        parser.skip_whitespace();
+        if parser.pos < spec.len() {
+            return Err(parser.error("end of text"));
+        }
+        Ok(signal)
+    }
+
+    fn signal(&self, parser: &mut Parser) -> Result<BoxedTimeSignal<f64>, SpecError> {
+        let name = parser.identifier()?;
+        parser.expect('(')?;
+        if name == SUPERPOSITION {
+            let first = self.signal(parser)?;
+            parser.expect(',')?;
+            let second = self.signal(parser)?;
+            parser.expect(')')?;
+            return Ok(Box::new(SuperPosition(first, second)));
+        }
+        let mut values = Vec::new();
+        if !parser.accept(')') {
+            loop {
+                let key = parser.identifier()?;
+                parser.expect('=')?;
+                values.push((key, parser.number()?));
+                if parser.accept(')') {
+                    break;
+                }
+                if !parser.accept(',') {
+                    return Err(parser.error("',' or ')'"));
+                }
+            }
+        }
+        let (element, constructor) = self
+            .constructor(name)
+            .ok_or_else(|| BuildError::UnknownType(String::from(name)))?;
+        Ok(Parameters::apply(element, &values, *constructor)?)
+    }
+}
+
+/// Position in the specification text
+struct Parser<'a> {
+    text: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn error(&self, expected: &'static str) -> SpecError {
+        SpecError::Syntax {
+            position: self.pos,
+            expected,
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = &self.text[self.pos..];
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    /// The next characters up to the first one not matching `part`
+    fn token(&mut self, part: fn(char) -> bool) -> &'a str {
+        self.skip_whitespace();
+        let rest = &self.text[self.pos..];
+        let len = rest.find(|c| !part(c)).unwrap_or(rest.len());
+        self.pos += len;
+        &rest[..len]
+    }
+
+    fn identifier(&mut self) -> Result<&'a str, SpecError> {
+        let identifier = self.token(|c| c.is_ascii_alphanumeric() || c == '_');
+        if identifier.is_empty() {
+            return Err(self.error("name"));
+        }
+        Ok(identifier)
+    }
+
+    fn number(&mut self) -> Result<f64, SpecError> {
+        let start = self.pos;
+        let number = self.token(|c| c.is_ascii_alphanumeric() || "+-.".contains(c));
+        number.parse().map_err(|_| {
+            self.pos = start;
+            self.skip_whitespace();
+            self.error("number")
+        })
+    }
+
+    fn accept(&mut self, c: char) -> bool {
+        self.skip_whitespace();
+        if self.text[self.pos..].starts_with(c) {
+            self.pos += c.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), SpecError> {
+        if self.accept(c) {
+            Ok(())
+        } else {
+            Err(self.error(match c {
+                '(' => "'('",
+                ')' => "')'",
+                ',' => "','",
+                _ => "'='",
+            }))
+        }
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use std::string::ToString;
+    use std::vec;
+
+    #[test]
+    fn test_SignalRegistry_parses_display_output() {
+        let signals: Vec<BoxedTimeSignal<f64>> = vec![
+            Box::new(StepFunction::default().step(2.5).pre(-1.0).post(3.0)),
+            Box::new(ImpulseFunction::default().amplitude(2.0).start(1.0)),
+            Box::new(RampFunction::default().start(1.0).offset(0.5).slope(-0.25)),
+            Box::new(SquareWave::default().period(4.0).duty_cycle(0.25).low(-1.0)),
+            Box::new(Staircase::default().steps(3).step_height(0.5).start(2.0)),
+            Box::new(SuperPosition(
+                Box::new(SuperPosition(
+                    Box::new(StepFunction::default().step(1.0)),
+                    Box::new(RampFunction::default().slope(f64::INFINITY)),
+                )),
+                Box::new(ImpulseFunction::default().resting_level(1e-9)),
+            )),
+        ];
+        let sut = SignalRegistry::builtin();
+        for signal in signals {
+            let parsed = sut.parse(&signal.to_string()).unwrap();
+            assert!(parsed.dyn_eq(signal.as_dyn_time_signal()), "{}", signal);
+        }
+        let step = sut.parse("  Step ( post = 2 )  ").unwrap();
+        assert_eq!(step.time_to_signal(1.0), 2.0);
+        assert_eq!(
+            sut.parse("Ramp()").unwrap().to_string(),
+            "Ramp(start_time=0, offset=0, slope=1)"
+        );
+    }
+
+    #[test]
+    fn test_SignalRegistry_errors() {
+        let mut sut = SignalRegistry::builtin();
+        assert_eq!(
+            sut.parse("Step(step_time=x)").err(),
+            Some(SpecError::Syntax {
+                position: 15,
+                expected: "number"
+            })
+        );
+        assert_eq!(
+            sut.parse("Step(post=1").err(),
+            Some(SpecError::Syntax {
+                position: 11,
+                expected: "',' or ')'"
+            })
+        );
+        assert!(matches!(
+            sut.parse("Superposition(Step())"),
+            Err(SpecError::Syntax { .. })
+        ));
+        assert!(matches!(
+            sut.parse("Step() 1"),
+            Err(SpecError::Syntax { .. })
+        ));
+        assert_eq!(
+            sut.parse("Sine(period=1)").err(),
+            Some(SpecError::Build(BuildError::UnknownType(String::from(
+                "Sine"
+            ))))
+        );
+        assert_eq!(
+            sut.parse("Ramp(slop=1)").err(),
+            Some(SpecError::Build(BuildError::UnknownParameter {
+                element: "Ramp",
+                name: String::from("slop")
+            }))
+        );
+        assert!(matches!(
+            sut.parse("Staircase(steps=1.5)"),
+            Err(SpecError::Build(BuildError::Invalid(_)))
+        ));
+        let constant: Constructor = |p| {
+            Ok(Box::new(
+                StepFunction::default()
+                    .pre(p.get_or("value", 0.0))
+                    .post(p.get_or("value", 0.0)),
+            ))
+        };
+        sut.register("Constant", constant).unwrap();
+        assert!(sut.register("Superposition", constant).is_err());
+        assert_eq!(
+            sut.parse("Constant(value=4)").unwrap().time_to_signal(9.0),
+            4.0
+        );
+    }
+}
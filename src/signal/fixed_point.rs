@@ -0,0 +1,101 @@
+//! # Fixed point scaling
+//!
+//! Integer signals represent a real value $x$ as $ x \cdot 2^{frac\_bits} $.
+//! `FixedPoint` converts the real valued parameters of a signal once, while
+//! building it; evaluating the signal then only uses integer values.
+//!
+//! The integer signals count time in ticks of `TIME_SCALING`, 2^-16 time
+//! units, so phases and ramps are computed in integer arithmetic, too.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use cb_simulation_util::signal::{FixedPoint, StepFunction, TimeSignal};
+//!
+//! fn main () {
+//!   // Q5.10, the scaling of the fixed point plant elements
+//!   let q10 = FixedPoint::new(10);
+//!   let step = StepFunction::<i16>::default().pre(0).post(q10.to_fixed(1.5));
+//!   assert_eq!(step.time_to_signal(1.0), 1536);
+//!   assert_eq!(q10.to_real(step.time_to_signal(1.0)), 1.5);
+//! }
+//! ```
+
+use num_traits::{Bounded, NumCast, ToPrimitive};
+
+pub use super::*;
+
+/// Resolution of the time in integer signals, Q47.16 ticks in an `i64`
+pub const TIME_SCALING: FixedPoint = FixedPoint { frac_bits: 16 };
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FixedPoint {
+    /// Number of bits after the binary point
+    pub frac_bits: u8,
+}
+
+impl FixedPoint {
+    pub fn new(frac_bits: u8) -> Self {
+        FixedPoint { frac_bits }
+    }
+
+    /// Value of the least significant bit
+    pub fn resolution(&self) -> f64 {
+        1.0 / self.scale()
+    }
+
+    fn scale(&self) -> f64 {
+        (1u64 << self.frac_bits) as f64
+    }
+
+    /// Nearest fixed point representation of `real`, saturating at the bounds of `S`
+    pub fn to_fixed<S: NumCast + Bounded + ToPrimitive>(&self, real: f64) -> S {
+        let scaled = round(real * self.scale());
+        let min = S::min_value().to_f64().unwrap_or(f64::MIN);
+        let max = S::max_value().to_f64().unwrap_or(f64::MAX);
+        if scaled <= min {
+            S::min_value()
+        } else if scaled >= max {
+            S::max_value()
+        } else {
+            NumCast::from(scaled).unwrap_or_else(S::min_value)
+        }
+    }
+
+    pub fn to_real<S: ToPrimitive>(&self, fixed: S) -> f64 {
+        fixed.to_f64().unwrap_or(f64::NAN) / self.scale()
+    }
+}
+
+/// Round half away from zero like `f64::round`, without the float functions of `std`
+fn round(value: f64) -> f64 {
+    // above 2^52 every f64 is an integer
+    if value.abs() >= 4_503_599_627_370_496.0 || value.is_nan() {
+        value
+    } else if value >= 0.0 {
+        (value + 0.5) as i64 as f64
+    } else {
+        (value - 0.5) as i64 as f64
+    }
+}
+
+impl fmt::Display for FixedPoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "FixedPoint(frac_bits={})", self.frac_bits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_fixed_point_saturates() {
+        let sut = FixedPoint::new(8);
+        assert_eq!(sut.to_fixed::<i16>(1000.0), i16::MAX);
+        assert_eq!(sut.to_fixed::<i16>(-1000.0), i16::MIN);
+        assert_eq!(sut.to_fixed::<i32>(-0.5), -128);
+        assert_eq!(sut.resolution(), 1.0 / 256.0);
+    }
+}
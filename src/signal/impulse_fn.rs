@@ -18,8 +18,11 @@
 use num_traits::{Num, one, zero};
 
 pub use super::*;
+use crate::parse::{Fields, ParseError};
+use core::str::FromStr;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ImpulseFunction<S: Debug + Display + Clone + Copy + PartialEq> {
     pub out_value: S,
     pub in_value: S,
@@ -82,7 +85,7 @@ impl<S: Num + Debug + Display + Clone + Copy + PartialEq + 'static> fmt::Display
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
             f,
-            "{}(amplitude={}, duration={}, start_time={}, rest_level={}",
+            "{}(amplitude={}, duration={}, start_time={}, rest_level={})",
             self.short_type_name(),
             self.in_value,
             self.duration,
@@ -92,15 +95,34 @@ impl<S: Num + Debug + Display + Clone + Copy + PartialEq + 'static> fmt::Display
     }
 }
 
+impl<S: FromStr + Debug + Display + Clone + Copy + PartialEq> FromStr for ImpulseFunction<S> {
+    type Err = ParseError;
+
+    /// Parse the `Display` output
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut fields = Fields::new(s, "Impulse")?;
+        let signal = ImpulseFunction {
+            in_value: fields.next("amplitude", "=")?,
+            duration: fields.next("duration", "=")?,
+            start_time: fields.next("start_time", "=")?,
+            out_value: fields.next("rest_level", "=")?,
+        };
+        fields.end()?;
+        Ok(signal)
+    }
+}
+
 // impl<S: Num + Debug + Display + Clone + Copy + PartialEq + 'static> DynTimeSignal<S>
 //     for ImpulseFunction<S>
 // {
 // }
 
+#[allow(non_snake_case)]
 #[cfg(test)]
 mod tests {
 
     use super::*;
+    use std::string::ToString;
 
     #[test]
     fn test_impulse_build() {
@@ -126,4 +148,21 @@ mod tests {
         assert_eq!(sut.time_to_signal(1.0), 1.0);
         assert_eq!(sut.time_to_signal(2.0), 0.0);
     }
+
+    #[test]
+    fn test_ImpulseFunction_from_str_round_trip() {
+        let sut = ImpulseFunction::<f64>::default()
+            .resting_level(-0.5)
+            .amplitude(3.0)
+            .start(1.25)
+            .duration(0.1);
+        assert_eq!(sut.to_string().parse::<ImpulseFunction<f64>>(), Ok(sut));
+        let sut = ImpulseFunction::<i32>::default().amplitude(7);
+        assert_eq!(sut.to_string().parse::<ImpulseFunction<i32>>(), Ok(sut));
+        assert_eq!(
+            "Impulse(amplitude=1, start_time=0, duration=1, rest_level=0)"
+                .parse::<ImpulseFunction<f64>>(),
+            Err(ParseError::Field("duration"))
+        );
+    }
 }
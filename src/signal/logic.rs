@@ -0,0 +1,375 @@
+//! # Boolean Time Signals
+//!
+//! Boolean signals model interlocks, enable and release conditions. They are
+//! built from continuous signals by comparators, combined by logic gates and
+//! shifted in time, and gate continuous signals again - so supervisory logic
+//! can be simulated together with the continuous loop.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use cb_simulation_util::signal::logic::{And, Comparison, Gated, Not, Threshold, Window};
+//! use cb_simulation_util::signal::{RampFunction, StepFunction, TimeSignal};
+//!
+//! fn main () {
+//!   // heater enabled between t=10 and t=50 unless the temperature exceeds 30
+//!   let overheated = Threshold::new(
+//!       Box::new(RampFunction::default().slope(0.5)),
+//!       Comparison::Above,
+//!       30.0,
+//!   );
+//!   let enable = And::new(Box::new(Window::new(10.0, 50.0)), Box::new(Not::new(Box::new(overheated))));
+//!   let heater = Gated::new(Box::new(enable), Box::new(StepFunction::default().post(100.0)), 0.0);
+//!   assert_eq!(heater.time_to_signal(5.0), 0.0);
+//!   assert_eq!(heater.time_to_signal(20.0), 100.0);
+//!   assert_eq!(heater.time_to_signal(61.0), 0.0);
+//! }
+//! ```
+
+pub use super::*;
+
+/// Comparison of a signal against a threshold
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Comparison {
+    Above,
+    AboveOrEqual,
+    Below,
+    BelowOrEqual,
+}
+
+/// True while `signal` compared with `threshold` holds
+#[derive(Debug, Clone)]
+pub struct Threshold {
+    pub signal: BoxedTimeSignal<f64>,
+    pub comparison: Comparison,
+    pub threshold: f64,
+}
+
+impl Threshold {
+    pub fn new(signal: BoxedTimeSignal<f64>, comparison: Comparison, threshold: f64) -> Self {
+        Threshold {
+            signal,
+            comparison,
+            threshold,
+        }
+    }
+}
+
+impl PartialEq for Threshold {
+    fn eq(&self, other: &Self) -> bool {
+        self.signal.eq(&other.signal)
+            && self.comparison == other.comparison
+            && self.threshold == other.threshold
+    }
+}
+
+impl TimeSignal<bool> for Threshold {
+    fn time_to_signal(&self, time: f64) -> bool {
+        let value = self.signal.time_to_signal(time);
+        match self.comparison {
+            Comparison::Above => value > self.threshold,
+            Comparison::AboveOrEqual => value >= self.threshold,
+            Comparison::Below => value < self.threshold,
+            Comparison::BelowOrEqual => value <= self.threshold,
+        }
+    }
+
+    fn short_type_name(&self) -> &'static str {
+        "Threshold"
+    }
+}
+
+impl fmt::Display for Threshold {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}({} {:?} {})",
+            self.short_type_name(),
+            self.signal,
+            self.comparison,
+            self.threshold
+        )
+    }
+}
+
+/// Logical and of two boolean signals
+#[derive(Debug, Clone)]
+pub struct And(pub BoxedTimeSignal<bool>, pub BoxedTimeSignal<bool>);
+
+impl And {
+    pub fn new(a: BoxedTimeSignal<bool>, b: BoxedTimeSignal<bool>) -> Self {
+        And(a, b)
+    }
+}
+
+/// Logical or of two boolean signals
+#[derive(Debug, Clone)]
+pub struct Or(pub BoxedTimeSignal<bool>, pub BoxedTimeSignal<bool>);
+
+impl Or {
+    pub fn new(a: BoxedTimeSignal<bool>, b: BoxedTimeSignal<bool>) -> Self {
+        Or(a, b)
+    }
+}
+
+/// Exclusive or of two boolean signals
+#[derive(Debug, Clone)]
+pub struct Xor(pub BoxedTimeSignal<bool>, pub BoxedTimeSignal<bool>);
+
+impl Xor {
+    pub fn new(a: BoxedTimeSignal<bool>, b: BoxedTimeSignal<bool>) -> Self {
+        Xor(a, b)
+    }
+}
+
+macro_rules! binary_gate {
+    ($gate:ident, $name:literal, $op:tt) => {
+        impl PartialEq for $gate {
+            fn eq(&self, other: &Self) -> bool {
+                self.0.eq(&other.0) && self.1.eq(&other.1)
+            }
+        }
+
+        impl TimeSignal<bool> for $gate {
+            fn time_to_signal(&self, time: f64) -> bool {
+                self.0.time_to_signal(time) $op self.1.time_to_signal(time)
+            }
+
+            fn short_type_name(&self) -> &'static str {
+                $name
+            }
+        }
+
+        impl fmt::Display for $gate {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "{}({}, {})", self.short_type_name(), self.0, self.1)
+            }
+        }
+    };
+}
+
+binary_gate!(And, "And", &);
+binary_gate!(Or, "Or", |);
+binary_gate!(Xor, "Xor", ^);
+
+/// Logical negation of a boolean signal
+#[derive(Debug, Clone)]
+pub struct Not(pub BoxedTimeSignal<bool>);
+
+impl Not {
+    pub fn new(signal: BoxedTimeSignal<bool>) -> Self {
+        Not(signal)
+    }
+}
+
+impl PartialEq for Not {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.eq(&other.0)
+    }
+}
+
+impl TimeSignal<bool> for Not {
+    fn time_to_signal(&self, time: f64) -> bool {
+        !self.0.time_to_signal(time)
+    }
+
+    fn short_type_name(&self) -> &'static str {
+        "Not"
+    }
+}
+
+impl fmt::Display for Not {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}({})", self.short_type_name(), self.0)
+    }
+}
+
+/// True within `[on_time, off_time)`, e.g. an operating schedule
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Window {
+    pub on_time: f64,
+    pub off_time: f64,
+}
+
+impl Window {
+    pub fn new(on_time: f64, off_time: f64) -> Self {
+        Window { on_time, off_time }
+    }
+}
+
+impl TimeSignal<bool> for Window {
+    fn time_to_signal(&self, time: f64) -> bool {
+        self.on_time <= time && time < self.off_time
+    }
+
+    fn short_type_name(&self) -> &'static str {
+        "Window"
+    }
+}
+
+impl fmt::Display for Window {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}(on_time={}, off_time={})",
+            self.short_type_name(),
+            self.on_time,
+            self.off_time
+        )
+    }
+}
+
+/// A signal shifted by `delay` in time, before `delay` the value at time 0 is held
+#[derive(Debug, Clone)]
+pub struct Delayed<S: Debug + Display + Clone + Copy + Sized + 'static + Send + Sync> {
+    pub signal: BoxedTimeSignal<S>,
+    pub delay: f64,
+}
+
+impl<S: Debug + Display + Clone + Copy + Sized + 'static + Send + Sync> Delayed<S> {
+    pub fn new(signal: BoxedTimeSignal<S>, delay: f64) -> Self {
+        Delayed { signal, delay }
+    }
+}
+
+impl<S: Debug + Display + Clone + Copy + Sized + 'static + Send + Sync> PartialEq for Delayed<S> {
+    fn eq(&self, other: &Self) -> bool {
+        self.signal.eq(&other.signal) && self.delay == other.delay
+    }
+}
+
+impl<S: Debug + Display + Clone + Copy + Sized + 'static + Send + Sync> TimeSignal<S>
+    for Delayed<S>
+{
+    fn time_to_signal(&self, time: f64) -> S {
+        self.signal.time_to_signal((time - self.delay).max(0.0))
+    }
+
+    fn short_type_name(&self) -> &'static str {
+        "Delayed"
+    }
+}
+
+impl<S: Debug + Display + Clone + Copy + Sized + 'static + Send + Sync> fmt::Display
+    for Delayed<S>
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}({}, delay={})",
+            self.short_type_name(),
+            self.signal,
+            self.delay
+        )
+    }
+}
+
+/// Passes `signal` while `enable` is true, else `disabled_value`
+#[derive(Debug, Clone)]
+pub struct Gated {
+    pub enable: BoxedTimeSignal<bool>,
+    pub signal: BoxedTimeSignal<f64>,
+    pub disabled_value: f64,
+}
+
+impl Gated {
+    pub fn new(
+        enable: BoxedTimeSignal<bool>,
+        signal: BoxedTimeSignal<f64>,
+        disabled_value: f64,
+    ) -> Self {
+        Gated {
+            enable,
+            signal,
+            disabled_value,
+        }
+    }
+}
+
+impl PartialEq for Gated {
+    fn eq(&self, other: &Self) -> bool {
+        self.enable.eq(&other.enable)
+            && self.signal.eq(&other.signal)
+            && self.disabled_value == other.disabled_value
+    }
+}
+
+impl TimeSignal<f64> for Gated {
+    fn time_to_signal(&self, time: f64) -> f64 {
+        if self.enable.time_to_signal(time) {
+            self.signal.time_to_signal(time)
+        } else {
+            self.disabled_value
+        }
+    }
+
+    fn short_type_name(&self) -> &'static str {
+        "Gated"
+    }
+}
+
+impl fmt::Display for Gated {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}(enable={}, signal={}, disabled_value={})",
+            self.short_type_name(),
+            self.enable,
+            self.signal,
+            self.disabled_value
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use std::format;
+
+    #[test]
+    fn test_logic_gates() {
+        let a: BoxedTimeSignal<bool> = Box::new(Window::new(0.0, 2.0));
+        let b: BoxedTimeSignal<bool> = Box::new(Window::new(1.0, 3.0));
+        let and = And::new(a.clone(), b.clone());
+        let or = Or::new(a.clone(), b.clone());
+        let xor = Xor::new(a.clone(), b.clone());
+        let truth: [(f64, bool, bool, bool); 4] = [
+            (0.5, false, true, true),
+            (1.5, true, true, false),
+            (2.5, false, true, true),
+            (3.5, false, false, false),
+        ];
+        for (t, expected_and, expected_or, expected_xor) in truth {
+            assert_eq!(and.time_to_signal(t), expected_and);
+            assert_eq!(or.time_to_signal(t), expected_or);
+            assert_eq!(xor.time_to_signal(t), expected_xor);
+        }
+        assert_eq!(
+            format!("{}", and),
+            "And(Window(on_time=0, off_time=2), Window(on_time=1, off_time=3))"
+        );
+    }
+
+    #[test]
+    fn test_delayed_boolean() {
+        let sut = Delayed::new(
+            Box::new(Window::new(0.0, 1.0)) as BoxedTimeSignal<bool>,
+            5.0,
+        );
+        assert!(sut.time_to_signal(3.0));
+        assert!(sut.time_to_signal(5.5));
+        assert!(!sut.time_to_signal(6.0));
+        let boxed: BoxedTimeSignal<bool> = Box::new(sut.clone());
+        assert!(boxed == boxed.clone());
+    }
+
+    #[test]
+    fn test_threshold_comparisons() {
+        let ramp: BoxedTimeSignal<f64> = Box::new(RampFunction::default().slope(1.0));
+        assert!(Threshold::new(ramp.clone(), Comparison::AboveOrEqual, 2.0).time_to_signal(2.0));
+        assert!(!Threshold::new(ramp.clone(), Comparison::Above, 2.0).time_to_signal(2.0));
+        assert!(Threshold::new(ramp.clone(), Comparison::BelowOrEqual, 2.0).time_to_signal(2.0));
+        assert!(!Threshold::new(ramp, Comparison::Below, 2.0).time_to_signal(2.0));
+    }
+}
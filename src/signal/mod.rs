@@ -1,24 +1,68 @@
 //! # Time Signals
 //!
 //!! This module provides the definition of time signals and their superposition.
+//!
+//! Without the `std` feature only the `TimeSignal` trait, `TimeRange`, the
+//! step, ramp, square wave and staircase signals and the `FixedPoint` scaling
+//! are available, evaluating them on integer types uses no float functions.
 //! ```
 
 use core::any::Any;
 use core::fmt;
 use core::fmt::Debug;
 use core::fmt::Display;
+#[cfg(feature = "std")]
 use core::ops::Add;
+#[cfg(feature = "std")]
 use dyn_clone::DynClone; // DynClone is a trait with clones a Box
+#[cfg(feature = "std")]
 use num_traits::Num;
 
+#[cfg(feature = "chrono")]
+pub mod alignment;
+#[cfg(feature = "std")]
+pub mod ambient_profile;
+#[cfg(feature = "std")]
+pub mod burst_noise;
+#[cfg(feature = "std")]
+pub mod drive_cycle;
+#[cfg(feature = "std")]
+pub mod empirical_noise;
+#[cfg(feature = "std")]
+pub mod factory;
+pub mod fixed_point;
+#[cfg(feature = "std")]
 pub mod impulse_fn;
+#[cfg(feature = "std")]
+pub mod logic;
+pub mod ramp_fn;
+#[cfg(feature = "std")]
+pub mod recorded;
+pub mod square_wave;
+pub mod staircase;
 pub mod step_fn;
-
+#[cfg(feature = "serde")]
+pub mod tagged;
+
+#[cfg(feature = "std")]
+pub use ambient_profile::*;
+#[cfg(feature = "std")]
+pub use burst_noise::*;
+#[cfg(feature = "std")]
+pub use empirical_noise::*;
+pub use fixed_point::*;
+#[cfg(feature = "std")]
 pub use impulse_fn::*;
+pub use ramp_fn::*;
+#[cfg(feature = "std")]
+pub use recorded::RecordedSignal;
+pub use square_wave::*;
+pub use staircase::*;
 pub use step_fn::*;
 
 pub mod time_range;
 
+#[cfg(feature = "std")]
 use std::boxed::Box;
 
 pub trait TimeSignal<S: Debug + Display + Clone + Copy + Sized>: Any {
@@ -29,6 +73,7 @@ pub trait TimeSignal<S: Debug + Display + Clone + Copy + Sized>: Any {
     fn short_type_name(&self) -> &'static str;
 }
 
+#[cfg(feature = "std")]
 pub trait DynTimeSignal<S: Debug + Display + Clone + Copy + Sized + Send + Sync>:
     TimeSignal<S> + Debug + Display + DynClone + 'static + Send + Sync
 {
@@ -37,9 +82,10 @@ pub trait DynTimeSignal<S: Debug + Display + Clone + Copy + Sized + Send + Sync>
     fn dyn_eq(&self, other: &dyn DynTimeSignal<S>) -> bool;
 }
 
+#[cfg(feature = "std")]
 impl<T, S> DynTimeSignal<S> for T
 where
-    T: TimeSignal<S> + Debug + Display + DynClone + Copy + 'static + PartialEq + Send + Sync,
+    T: TimeSignal<S> + Debug + Display + DynClone + 'static + PartialEq + Send + Sync,
     S: Debug + Display + Clone + Copy + Sized + 'static + Send + Sync,
 {
     fn as_any(&self) -> &dyn Any {
@@ -59,14 +105,17 @@ where
     }
 }
 
+#[cfg(feature = "std")]
 pub type BoxedTimeSignal<S> = Box<dyn DynTimeSignal<S> + 'static>;
 
+#[cfg(feature = "std")]
 impl<S> Clone for BoxedTimeSignal<S> {
     fn clone(&self) -> Self {
         dyn_clone::clone_box(&**self)
     }
 }
 
+#[cfg(feature = "std")]
 impl<S: Debug + Display + Clone + Copy + Sized + 'static + Send + Sync> PartialEq
     for BoxedTimeSignal<S>
 {
@@ -78,12 +127,23 @@ impl<S: Debug + Display + Clone + Copy + Sized + 'static + Send + Sync> PartialE
 #[allow(unused_imports)]
 pub use time_range::*;
 
+#[cfg(feature = "std")]
 #[derive(Debug, Clone)]
 pub struct SuperPosition<S: Num + Debug + Display + Clone + PartialEq>(
     pub Box<dyn DynTimeSignal<S>>,
     pub Box<dyn DynTimeSignal<S>>,
 );
 
+#[cfg(feature = "std")]
+impl<S: Num + Debug + Display + Clone + Copy + PartialEq + 'static + Send + Sync> PartialEq
+    for SuperPosition<S>
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.0.dyn_eq(other.0.as_dyn_time_signal()) && self.1.dyn_eq(other.1.as_dyn_time_signal())
+    }
+}
+
+#[cfg(feature = "std")]
 impl<S: Num + Debug + Display + Clone + Copy + PartialEq + 'static> fmt::Display
     for SuperPosition<S>
 {
@@ -92,6 +152,7 @@ impl<S: Num + Debug + Display + Clone + Copy + PartialEq + 'static> fmt::Display
     }
 }
 
+#[cfg(feature = "std")]
 impl<S: Add<Output = S> + Num + Debug + Display + Clone + Copy + PartialEq + 'static> TimeSignal<S>
     for SuperPosition<S>
 {
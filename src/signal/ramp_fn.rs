@@ -0,0 +1,156 @@
+//! # Ramp - Time Signal
+//!
+//! On `i16` and `i32` the elapsed time is counted in ticks of `TIME_SCALING`
+//! and the ramp is computed in integer arithmetic, rounded to the nearest
+//! value and saturated at the bounds of the type, e.g. a slope of 256 per
+//! time unit in Q5.10 rises by 0.25 per time unit.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use ndarray::{Array, Ix1};
+//! use cb_simulation_util::signal::{TimeRange, RampFunction, TimeSignal};
+//!
+//! fn main () {
+//!   let time: Array<f64, Ix1> = TimeRange::default().collect();
+//!   let ramp_fn = RampFunction::default().offset(2.0).slope(0.5).start(10.0);
+//!   let signal: Array<f64, Ix1> = time.iter().map(|v| ramp_fn.time_to_signal(*v)).collect();
+//!   assert_eq!(signal[0], 2.0);
+//!   assert_eq!(signal[19], 7.0);
+//! }
+//! ```
+
+use num_traits::{Num, one, zero};
+
+pub use super::*;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RampFunction<S: Debug + Display + Clone + Copy + PartialEq> {
+    pub offset: S,
+    pub slope: S,
+    pub start_time: f64,
+}
+
+impl<S: Num + Debug + Display + Clone + Copy + PartialEq> RampFunction<S> {
+    pub fn offset(self, offset: S) -> Self {
+        RampFunction::<S> { offset, ..self }
+    }
+
+    pub fn slope(self, slope: S) -> Self {
+        RampFunction::<S> { slope, ..self }
+    }
+
+    pub fn start(self, start_time: f64) -> Self {
+        RampFunction::<S> { start_time, ..self }
+    }
+}
+
+impl<S: Num + Debug + Display + Clone + Copy + PartialEq> Default for RampFunction<S> {
+    fn default() -> Self {
+        RampFunction::<S> {
+            offset: zero(),
+            slope: one(),
+            start_time: 0.0,
+        }
+    }
+}
+
+macro_rules! float_ramp {
+    ($($float:ty),*) => {$(
+        impl TimeSignal<$float> for RampFunction<$float> {
+            fn time_to_signal(&self, time: f64) -> $float {
+                if time <= self.start_time {
+                    self.offset
+                } else {
+                    self.offset + self.slope * (time - self.start_time) as $float
+                }
+            }
+
+            fn short_type_name(&self) -> &'static str {
+                "Ramp"
+            }
+        }
+    )*};
+}
+
+macro_rules! integer_ramp {
+    ($($int:ty),*) => {$(
+        impl TimeSignal<$int> for RampFunction<$int> {
+            fn time_to_signal(&self, time: f64) -> $int {
+                if time <= self.start_time {
+                    return self.offset;
+                }
+                let elapsed: i64 = TIME_SCALING.to_fixed(time - self.start_time);
+                let half = 1i128 << (TIME_SCALING.frac_bits - 1);
+                let rise = (i128::from(self.slope) * i128::from(elapsed) + half)
+                    >> TIME_SCALING.frac_bits;
+                (i128::from(self.offset) + rise).clamp(<$int>::MIN.into(), <$int>::MAX.into())
+                    as $int
+            }
+
+            fn short_type_name(&self) -> &'static str {
+                "Ramp"
+            }
+        }
+    )*};
+}
+
+float_ramp!(f32, f64);
+integer_ramp!(i16, i32);
+
+impl<S: Num + Debug + Display + Clone + Copy + PartialEq> fmt::Display for RampFunction<S>
+where
+    Self: TimeSignal<S>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}(start_time={}, offset={}, slope={})",
+            self.short_type_name(),
+            self.start_time,
+            self.offset,
+            self.slope
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_ramp_() {
+        let sut = RampFunction::<f64>::default().start(1.0).slope(-2.0);
+        assert_eq!(sut.time_to_signal(0.0), 0.0);
+        assert_eq!(sut.time_to_signal(1.0), 0.0);
+        assert_eq!(sut.time_to_signal(3.0), -4.0);
+    }
+
+    #[test]
+    fn test_ramp_i32_fixed_point_slope() {
+        // 0.25 per time unit in Q5.10
+        let q10 = FixedPoint::new(10);
+        let sut = RampFunction::<i32>::default()
+            .offset(q10.to_fixed(1.0))
+            .slope(q10.to_fixed(0.25))
+            .start(2.0);
+        assert_eq!(sut.time_to_signal(1.0), 1024);
+        assert_eq!(sut.time_to_signal(2.5), 1024 + 128);
+        assert_eq!(sut.time_to_signal(6.0), 2048);
+        // rounded to the nearest value, not truncated per time unit
+        assert_eq!(sut.time_to_signal(2.001), 1024);
+        assert_eq!(sut.time_to_signal(2.003), 1025);
+    }
+
+    #[test]
+    fn test_ramp_i16_saturates() {
+        let sut = RampFunction::<i16>::default().offset(-100).slope(-1000);
+        assert_eq!(sut.time_to_signal(0.5), -600);
+        assert_eq!(sut.time_to_signal(1.25), -1350);
+        assert_eq!(sut.time_to_signal(40.0), i16::MIN);
+        let sut = RampFunction::<i16>::default().slope(3);
+        assert_eq!(sut.time_to_signal(0.5), 2);
+        assert_eq!(sut.time_to_signal(1e6), i16::MAX);
+    }
+}
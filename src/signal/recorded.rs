@@ -0,0 +1,533 @@
+//! # Recorded - Time Signal
+//!
+//! Replays recorded samples (time, value), linearly interpolated in between.
+//! Before the first and after the last sample the boundary value is held.
+//!
+//! Samples are read from CSV text, the columns may be separated by `,`, `;`
+//! or tabs. Lines which do not parse as numbers (headers) and lines starting
+//! with `#` are skipped.
+//!
+//! Logs too large for memory, e.g. weeks sampled at 1 kHz, are read with
+//! `from_reader_resampled`: the CSV is streamed line by line and resampled on
+//! the fly, only the resampled values of the selected columns are kept.
+//!
+//! Raw field data is cleaned before identification: `fill_gaps` interpolates
+//! missing (`NaN`) values, `reject_outliers` replaces spikes by the local
+//! median (Hampel filter) and `detrend` removes a linear drift.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use cb_simulation_util::signal::{RecordedSignal, TimeSignal};
+//!
+//! fn main () {
+//!   let csv = "time;value\n0;0\n10;5\n20;5\n";
+//!   let recorded = RecordedSignal::from_csv(csv, 0, 1).unwrap();
+//!   assert_eq!(recorded.time_to_signal(4.0), 2.0);
+//!   assert_eq!(recorded.time_to_signal(30.0), 5.0);
+//!   let resampled = recorded.resample(2.5).unwrap();
+//!   assert_eq!(resampled.len(), 9);
+//! }
+//! ```
+
+use std::string::String;
+use std::vec::Vec;
+
+pub use super::*;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RecordedSignalError {
+    /// No data line found
+    Empty,
+    /// Time and value have different length
+    LengthMismatch { time: usize, values: usize },
+    /// Time stamps must strictly increase
+    NotAscending { index: usize },
+    /// Time stamps must be finite numbers
+    NonFiniteTime { index: usize },
+    /// A data line lacks a column or contains an invalid number
+    Parse { line: usize, message: String },
+    /// The sample time for resampling must be > 0 and finite
+    InvalidSampleTime,
+}
+
+impl fmt::Display for RecordedSignalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RecordedSignalError::Empty => write!(f, "Recorded signal without samples"),
+            RecordedSignalError::LengthMismatch { time, values } => write!(
+                f,
+                "Recorded signal with {} time stamps but {} values",
+                time, values
+            ),
+            RecordedSignalError::NotAscending { index } => {
+                write!(f, "Time stamp {} does not increase", index)
+            }
+            RecordedSignalError::NonFiniteTime { index } => {
+                write!(f, "Time stamp {} is not finite", index)
+            }
+            RecordedSignalError::Parse { line, message } => {
+                write!(f, "Line {}: {}", line, message)
+            }
+            RecordedSignalError::InvalidSampleTime => {
+                write!(f, "Sample time must be > 0 and finite")
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordedSignal {
+    time: Vec<f64>,
+    values: Vec<f64>,
+}
+
+impl RecordedSignal {
+    pub fn new(time: Vec<f64>, values: Vec<f64>) -> Result<Self, RecordedSignalError> {
+        if time.len() != values.len() {
+            return Err(RecordedSignalError::LengthMismatch {
+                time: time.len(),
+                values: values.len(),
+            });
+        }
+        if time.is_empty() {
+            return Err(RecordedSignalError::Empty);
+        }
+        if let Some(index) = time.iter().position(|t| !t.is_finite()) {
+            return Err(RecordedSignalError::NonFiniteTime { index });
+        }
+        if let Some(index) = time.windows(2).position(|w| w[1] <= w[0]) {
+            return Err(RecordedSignalError::NotAscending { index: index + 1 });
+        }
+        Ok(RecordedSignal { time, values })
+    }
+
+    /// Parse the `columns` of one CSV line
+    ///
+    /// Returns `None` for empty lines, comments and, as long as `header` is
+    /// true, lines which do not parse as numbers.
+    fn parse_line(
+        line: &str,
+        number: usize,
+        columns: &[usize],
+        header: bool,
+    ) -> Result<Option<Vec<f64>>, RecordedSignalError> {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            return Ok(None);
+        }
+        let fields: Vec<&str> = line
+            .split([',', ';', '\t'])
+            .map(|field| field.trim())
+            .collect();
+        let mut values = Vec::with_capacity(columns.len());
+        for column in columns {
+            match fields.get(*column).map(|f| f.parse::<f64>()) {
+                Some(Ok(v)) => values.push(v),
+                // header lines before the first data line
+                _ if header => return Ok(None),
+                None => {
+                    return Err(RecordedSignalError::Parse {
+                        line: number,
+                        message: String::from("missing column"),
+                    });
+                }
+                Some(Err(_)) => {
+                    return Err(RecordedSignalError::Parse {
+                        line: number,
+                        message: String::from("invalid number"),
+                    });
+                }
+            }
+        }
+        Ok(Some(values))
+    }
+
+    /// Read the columns `time_column` and `value_column` (0 based) of CSV text
+    pub fn from_csv(
+        text: &str,
+        time_column: usize,
+        value_column: usize,
+    ) -> Result<Self, RecordedSignalError> {
+        let mut time = Vec::new();
+        let mut values = Vec::new();
+        for (i, line) in text.lines().enumerate() {
+            let columns = [time_column, value_column];
+            if let Some(sample) = Self::parse_line(line, i + 1, &columns, time.is_empty())? {
+                time.push(sample[0]);
+                values.push(sample[1]);
+            }
+        }
+        RecordedSignal::new(time, values)
+    }
+
+    /// Stream CSV from `reader` and resample the `value_columns` on the fly
+    ///
+    /// Gives the same signals as `from_csv` followed by `resample`, one per
+    /// value column, but holds only the resampled values in memory.
+    pub fn from_reader_resampled<R: std::io::BufRead>(
+        mut reader: R,
+        time_column: usize,
+        value_columns: &[usize],
+        sample_time: f64,
+    ) -> Result<Vec<Self>, RecordedSignalError> {
+        if sample_time <= 0.0 || !sample_time.is_finite() {
+            return Err(RecordedSignalError::InvalidSampleTime);
+        }
+        let mut columns = std::vec![time_column];
+        columns.extend_from_slice(value_columns);
+        let mut grid: Vec<f64> = Vec::new();
+        let mut resampled: Vec<Vec<f64>> = std::vec![Vec::new(); value_columns.len()];
+        // first time stamp, previous sample and number of samples read
+        let mut start = 0.0;
+        let mut previous: Option<Vec<f64>> = None;
+        let mut samples = 0;
+        let mut line = String::new();
+        let mut number = 0;
+        loop {
+            line.clear();
+            let read = reader
+                .read_line(&mut line)
+                .map_err(|e| RecordedSignalError::Parse {
+                    line: number + 1,
+                    message: std::format!("{}", e),
+                })?;
+            if read == 0 {
+                break;
+            }
+            number += 1;
+            let Some(sample) = Self::parse_line(&line, number, &columns, previous.is_none())?
+            else {
+                continue;
+            };
+            let t1 = sample[0];
+            if !t1.is_finite() {
+                return Err(RecordedSignalError::NonFiniteTime { index: samples });
+            }
+            match &previous {
+                None => start = t1,
+                Some(p) if t1 <= p[0] => {
+                    return Err(RecordedSignalError::NotAscending { index: samples });
+                }
+                Some(p) => {
+                    // grid points up to the new sample, interpolated like `time_to_signal`
+                    loop {
+                        let t = start + grid.len() as f64 * sample_time;
+                        if t >= t1 {
+                            break;
+                        }
+                        let weight = (t - p[0]) / (t1 - p[0]);
+                        for (j, values) in resampled.iter_mut().enumerate() {
+                            values.push(p[j + 1] + (sample[j + 1] - p[j + 1]) * weight);
+                        }
+                        grid.push(t);
+                    }
+                }
+            }
+            samples += 1;
+            previous = Some(sample);
+        }
+        let last = previous.ok_or(RecordedSignalError::Empty)?;
+        // remaining grid points up to the last time stamp, like `resample`
+        let steps = ((last[0] - start) / sample_time + 1e-9).floor() as usize;
+        while grid.len() <= steps {
+            grid.push(start + grid.len() as f64 * sample_time);
+            for (j, values) in resampled.iter_mut().enumerate() {
+                values.push(last[j + 1]);
+            }
+        }
+        Ok(resampled
+            .into_iter()
+            .map(|values| RecordedSignal {
+                time: grid.clone(),
+                values,
+            })
+            .collect())
+    }
+
+    /// Stream a CSV file and resample it, see `from_reader_resampled`
+    pub fn from_file_resampled<P: AsRef<std::path::Path>>(
+        path: P,
+        time_column: usize,
+        value_columns: &[usize],
+        sample_time: f64,
+    ) -> Result<Vec<Self>, RecordedSignalError> {
+        let file = std::fs::File::open(path).map_err(|e| RecordedSignalError::Parse {
+            line: 0,
+            message: std::format!("{}", e),
+        })?;
+        RecordedSignal::from_reader_resampled(
+            std::io::BufReader::new(file),
+            time_column,
+            value_columns,
+            sample_time,
+        )
+    }
+
+    /// Read a CSV file, see `from_csv`
+    pub fn from_file<P: AsRef<std::path::Path>>(
+        path: P,
+        time_column: usize,
+        value_column: usize,
+    ) -> Result<Self, RecordedSignalError> {
+        let text = std::fs::read_to_string(path).map_err(|e| RecordedSignalError::Parse {
+            line: 0,
+            message: std::format!("{}", e),
+        })?;
+        RecordedSignal::from_csv(&text, time_column, value_column)
+    }
+
+    /// Resample on an equidistant grid from the first to the last time stamp
+    ///
+    /// Fails for a sample time not > 0 and finite.
+    pub fn resample(&self, sample_time: f64) -> Result<RecordedSignal, RecordedSignalError> {
+        if sample_time <= 0.0 || !sample_time.is_finite() {
+            return Err(RecordedSignalError::InvalidSampleTime);
+        }
+        let start = self.time[0];
+        let steps =
+            ((self.time[self.time.len() - 1] - start) / sample_time + 1e-9).floor() as usize;
+        let time: Vec<f64> = (0..=steps)
+            .map(|k| start + k as f64 * sample_time)
+            .collect();
+        let values = time.iter().map(|t| self.time_to_signal(*t)).collect();
+        Ok(RecordedSignal { time, values })
+    }
+
+    /// Multiply all values with `factor`, e.g. for unit conversion
+    pub fn scale(mut self, factor: f64) -> Self {
+        self.values.iter_mut().for_each(|v| *v *= factor);
+        self
+    }
+
+    /// Replace `NaN` values by linear interpolation between the valid neighbours
+    ///
+    /// Leading and trailing `NaN` values take the nearest valid value. A
+    /// signal without any valid value is returned unchanged.
+    pub fn fill_gaps(mut self) -> Self {
+        let valid: Vec<usize> = (0..self.values.len())
+            .filter(|i| !self.values[*i].is_nan())
+            .collect();
+        let (Some(first), Some(last)) = (valid.first().copied(), valid.last().copied()) else {
+            return self;
+        };
+        let (head, tail) = (self.values[first], self.values[last]);
+        self.values[..first].iter_mut().for_each(|v| *v = head);
+        self.values[last + 1..].iter_mut().for_each(|v| *v = tail);
+        for pair in valid.windows(2) {
+            let (a, b) = (pair[0], pair[1]);
+            let (ta, tb) = (self.time[a], self.time[b]);
+            let (va, vb) = (self.values[a], self.values[b]);
+            for i in a + 1..b {
+                self.values[i] = va + (vb - va) * (self.time[i] - ta) / (tb - ta);
+            }
+        }
+        self
+    }
+
+    /// Hampel filter: replace outliers by the median of their neighbourhood
+    ///
+    /// The neighbourhood are the `half_window` samples on both sides. A value
+    /// is an outlier if it deviates from the median by more than `threshold`
+    /// times the scaled median absolute deviation $ 1.4826 \cdot MAD $, the
+    /// robust estimate of the standard deviation; 3 is a common choice.
+    pub fn reject_outliers(mut self, half_window: usize, threshold: f64) -> Self {
+        fn median(values: &mut [f64]) -> f64 {
+            values.sort_by(f64::total_cmp);
+            let n = values.len();
+            if n % 2 == 1 {
+                values[n / 2]
+            } else {
+                0.5 * (values[n / 2 - 1] + values[n / 2])
+            }
+        }
+        let original = self.values.clone();
+        let n = original.len();
+        for (i, value) in self.values.iter_mut().enumerate() {
+            let window = &original[i.saturating_sub(half_window)..(i + half_window + 1).min(n)];
+            let mut neighbourhood: Vec<f64> =
+                window.iter().copied().filter(|v| !v.is_nan()).collect();
+            if neighbourhood.is_empty() {
+                continue;
+            }
+            let center = median(&mut neighbourhood);
+            let mut deviations: Vec<f64> =
+                neighbourhood.iter().map(|v| (v - center).abs()).collect();
+            let sigma = 1.4826 * median(&mut deviations);
+            if (*value - center).abs() > threshold * sigma {
+                *value = center;
+            }
+        }
+        self
+    }
+
+    /// Subtract the least-squares straight line through the samples
+    pub fn detrend(mut self) -> Self {
+        let n = self.time.len() as f64;
+        let t_mean = self.time.iter().sum::<f64>() / n;
+        let v_mean = self.values.iter().sum::<f64>() / n;
+        let (covariance, variance) = self
+            .time
+            .iter()
+            .zip(&self.values)
+            .fold((0.0, 0.0), |(c, v), (t, y)| {
+                (c + (t - t_mean) * (y - v_mean), v + (t - t_mean).powi(2))
+            });
+        let slope = if variance > 0.0 {
+            covariance / variance
+        } else {
+            0.0
+        };
+        for (t, y) in self.time.iter().zip(self.values.iter_mut()) {
+            *y -= v_mean + slope * (t - t_mean);
+        }
+        self
+    }
+
+    pub fn time(&self) -> &[f64] {
+        &self.time
+    }
+
+    pub fn values(&self) -> &[f64] {
+        &self.values
+    }
+
+    pub fn len(&self) -> usize {
+        self.time.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.time.is_empty()
+    }
+
+    pub fn duration(&self) -> f64 {
+        self.time[self.time.len() - 1] - self.time[0]
+    }
+}
+
+impl TimeSignal<f64> for RecordedSignal {
+    fn time_to_signal(&self, time: f64) -> f64 {
+        let i = self.time.partition_point(|t| *t <= time);
+        if i == 0 {
+            self.values[0]
+        } else if i == self.time.len() {
+            self.values[i - 1]
+        } else {
+            let (t0, t1) = (self.time[i - 1], self.time[i]);
+            let (v0, v1) = (self.values[i - 1], self.values[i]);
+            v0 + (v1 - v0) * (time - t0) / (t1 - t0)
+        }
+    }
+
+    fn short_type_name(&self) -> &'static str {
+        "Recorded"
+    }
+}
+
+impl fmt::Display for RecordedSignal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}(samples={}, start={}, end={})",
+            self.short_type_name(),
+            self.time.len(),
+            self.time[0],
+            self.time[self.time.len() - 1]
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use std::vec;
+
+    #[test]
+    fn test_recorded_signal_invalid() {
+        assert_eq!(
+            RecordedSignal::new(vec![0.0, 0.0], vec![1.0, 2.0]),
+            Err(RecordedSignalError::NotAscending { index: 1 })
+        );
+        assert_eq!(
+            RecordedSignal::new(vec![0.0, f64::NAN, 1.0], vec![1.0, 2.0, 3.0]),
+            Err(RecordedSignalError::NonFiniteTime { index: 1 })
+        );
+        assert_eq!(
+            RecordedSignal::from_csv("t,v\n", 0, 1),
+            Err(RecordedSignalError::Empty)
+        );
+        assert_eq!(
+            RecordedSignal::from_csv("0,1\n1,x\n", 0, 1),
+            Err(RecordedSignalError::Parse {
+                line: 2,
+                message: String::from("invalid number")
+            })
+        );
+    }
+
+    #[test]
+    fn test_recorded_signal_resample_keeps_end() {
+        let sut = RecordedSignal::new(vec![1.0, 2.0, 4.0], vec![0.0, 1.0, -1.0]).unwrap();
+        let resampled = sut.resample(0.5).unwrap();
+        assert_eq!(resampled.time(), &[1.0, 1.5, 2.0, 2.5, 3.0, 3.5, 4.0]);
+        assert_eq!(resampled.values()[5], -0.5);
+        assert_eq!(sut.time_to_signal(0.0), 0.0);
+        for sample_time in [0.0, -0.5, f64::NAN, f64::INFINITY] {
+            assert_eq!(
+                sut.resample(sample_time),
+                Err(RecordedSignalError::InvalidSampleTime)
+            );
+        }
+    }
+
+    #[test]
+    fn test_recorded_signal_preprocessing() {
+        let nan = f64::NAN;
+        let time = std::vec![0.0, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0];
+        let raw = std::vec![nan, 1.0, nan, nan, 4.0, 50.0, 6.0, nan];
+        let sut = RecordedSignal::new(time.clone(), raw).unwrap().fill_gaps();
+        assert_eq!(sut.values(), [1.0, 1.0, 2.0, 3.0, 4.0, 50.0, 6.0, 6.0]);
+        let sut = sut.reject_outliers(2, 3.0);
+        // replaced by the median of 3, 4, 50, 6, 6
+        assert_eq!(sut.values(), [1.0, 1.0, 2.0, 3.0, 4.0, 6.0, 6.0, 6.0]);
+        // a ramp with offset vanishes completely
+        let ramp = time.iter().map(|t| 2.0 + 0.5 * t).collect();
+        let sut = RecordedSignal::new(time, ramp).unwrap().detrend();
+        assert!(sut.values().iter().all(|v| v.abs() < 1e-12));
+    }
+
+    #[test]
+    fn test_recorded_signal_streamed_like_resample() {
+        let csv = "# log\ntime,a,b\n0.0,1,0\n0.3,2,3\n0.35,0,3\n1.0,4,-1\n1.25,4,0\n";
+        let streamed =
+            RecordedSignal::from_reader_resampled(csv.as_bytes(), 0, &[1, 2], 0.25).unwrap();
+        assert_eq!(streamed.len(), 2);
+        for (column, signal) in [(1, &streamed[0]), (2, &streamed[1])] {
+            let expected = RecordedSignal::from_csv(csv, 0, column)
+                .unwrap()
+                .resample(0.25)
+                .unwrap();
+            assert_eq!(signal.time(), expected.time());
+            for (a, b) in signal.values().iter().zip(expected.values()) {
+                assert!((a - b).abs() < 1e-12);
+            }
+        }
+        assert_eq!(
+            RecordedSignal::from_reader_resampled("0,1\n0,2\n".as_bytes(), 0, &[1], 1.0),
+            Err(RecordedSignalError::NotAscending { index: 1 })
+        );
+        // would never reach the next grid point
+        for row in ["nan", "inf"] {
+            let csv = std::format!("0,1\n1,2\n{},3\n4,4\n", row);
+            assert_eq!(
+                RecordedSignal::from_reader_resampled(csv.as_bytes(), 0, &[1], 1.0),
+                Err(RecordedSignalError::NonFiniteTime { index: 2 })
+            );
+        }
+        assert_eq!(
+            RecordedSignal::from_reader_resampled("0,1\n".as_bytes(), 0, &[1], 0.0),
+            Err(RecordedSignalError::InvalidSampleTime)
+        );
+    }
+}
@@ -0,0 +1,124 @@
+//! # Square Wave - Time Signal
+//!
+//! Alternates between `high` and `low`, starting with `high` at `start_time`.
+//! Before `start_time` the signal is `low`. The phase is computed in ticks of
+//! `TIME_SCALING`, integer arithmetic only.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use ndarray::{Array, Ix1};
+//! use cb_simulation_util::signal::{TimeRange, SquareWave, TimeSignal};
+//!
+//! fn main () {
+//!   let time: Array<f64, Ix1> = TimeRange::default().collect();
+//!   let square = SquareWave::<i32>::default().low(-100).high(100).period(10.0).duty_cycle(0.3);
+//!   let signal: Array<i32, Ix1> = time.iter().map(|v| square.time_to_signal(*v)).collect();
+//!   assert_eq!(signal[0], 100);
+//!   assert_eq!(signal[1], 100);
+//!   assert_eq!(signal[2], -100);
+//!   assert_eq!(signal[9], 100);
+//! }
+//! ```
+
+use num_traits::{Num, one, zero};
+
+pub use super::*;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SquareWave<S: Debug + Display + Clone + Copy + PartialEq> {
+    pub low: S,
+    pub high: S,
+    pub period: f64,
+    /// Fraction of the period the signal is `high`
+    pub duty_cycle: f64,
+    pub start_time: f64,
+}
+
+impl<S: Num + Debug + Display + Clone + Copy + PartialEq> SquareWave<S> {
+    pub fn low(self, low: S) -> Self {
+        SquareWave::<S> { low, ..self }
+    }
+
+    pub fn high(self, high: S) -> Self {
+        SquareWave::<S> { high, ..self }
+    }
+
+    pub fn period(self, period: f64) -> Self {
+        SquareWave::<S> { period, ..self }
+    }
+
+    pub fn duty_cycle(self, duty_cycle: f64) -> Self {
+        SquareWave::<S> {
+            duty_cycle: duty_cycle.clamp(0.0, 1.0),
+            ..self
+        }
+    }
+
+    pub fn start(self, start_time: f64) -> Self {
+        SquareWave::<S> { start_time, ..self }
+    }
+}
+
+impl<S: Num + Debug + Display + Clone + Copy + PartialEq> Default for SquareWave<S> {
+    fn default() -> Self {
+        SquareWave::<S> {
+            low: zero(),
+            high: one(),
+            period: 2.0,
+            duty_cycle: 0.5,
+            start_time: 0.0,
+        }
+    }
+}
+
+impl<S: Num + Debug + Display + Clone + Copy + PartialEq + 'static> TimeSignal<S>
+    for SquareWave<S>
+{
+    fn time_to_signal(&self, time: f64) -> S {
+        let period: i64 = TIME_SCALING.to_fixed(self.period);
+        if time < self.start_time || period <= 0 {
+            return self.low;
+        }
+        let phase = TIME_SCALING.to_fixed::<i64>(time - self.start_time) % period;
+        if phase < TIME_SCALING.to_fixed(self.duty_cycle * self.period) {
+            self.high
+        } else {
+            self.low
+        }
+    }
+
+    fn short_type_name(&self) -> &'static str {
+        "Square"
+    }
+}
+
+impl<S: Num + Debug + Display + Clone + Copy + PartialEq + 'static> fmt::Display for SquareWave<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}(low={}, high={}, period={}, duty_cycle={}, start_time={})",
+            self.short_type_name(),
+            self.low,
+            self.high,
+            self.period,
+            self.duty_cycle,
+            self.start_time
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_square_wave_i16_before_start() {
+        let sut = SquareWave::<i16>::default().low(-1).high(1).start(5.0);
+        assert_eq!(sut.time_to_signal(4.9), -1);
+        assert_eq!(sut.time_to_signal(5.0), 1);
+        assert_eq!(sut.time_to_signal(6.0), -1);
+        assert_eq!(sut.time_to_signal(7.5), 1);
+    }
+}
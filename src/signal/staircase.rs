@@ -0,0 +1,137 @@
+//! # Staircase - Time Signal
+//!
+//! Starts at `initial` and rises by `step_height` every `step_duration` after
+//! `start_time`, until `steps` steps are done. Like the step function, the
+//! first step is taken right after `start_time`. A negative `step_height`
+//! gives a falling staircase. The steps are counted in ticks of
+//! `TIME_SCALING`, integer arithmetic only.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use ndarray::{Array, Ix1};
+//! use cb_simulation_util::signal::{TimeRange, Staircase, TimeSignal};
+//!
+//! fn main () {
+//!   let time: Array<f64, Ix1> = TimeRange::default().collect();
+//!   let stairs = Staircase::<i32>::default().step_height(256).step_duration(10.0).steps(3);
+//!   let signal: Array<i32, Ix1> = time.iter().map(|v| stairs.time_to_signal(*v)).collect();
+//!   assert_eq!(signal[0], 256);
+//!   assert_eq!(signal[10], 512);
+//!   assert_eq!(signal[40], 768);
+//! }
+//! ```
+
+use num_traits::{Num, NumCast, one, zero};
+
+pub use super::*;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Staircase<S: Debug + Display + Clone + Copy + PartialEq> {
+    pub initial: S,
+    pub step_height: S,
+    pub step_duration: f64,
+    pub steps: u32,
+    pub start_time: f64,
+}
+
+impl<S: Num + Debug + Display + Clone + Copy + PartialEq> Staircase<S> {
+    pub fn initial(self, initial: S) -> Self {
+        Staircase::<S> { initial, ..self }
+    }
+
+    pub fn step_height(self, step_height: S) -> Self {
+        Staircase::<S> {
+            step_height,
+            ..self
+        }
+    }
+
+    pub fn step_duration(self, step_duration: f64) -> Self {
+        Staircase::<S> {
+            step_duration,
+            ..self
+        }
+    }
+
+    pub fn steps(self, steps: u32) -> Self {
+        Staircase::<S> { steps, ..self }
+    }
+
+    pub fn start(self, start_time: f64) -> Self {
+        Staircase::<S> { start_time, ..self }
+    }
+}
+
+impl<S: Num + Debug + Display + Clone + Copy + PartialEq> Default for Staircase<S> {
+    fn default() -> Self {
+        Staircase::<S> {
+            initial: zero(),
+            step_height: one(),
+            step_duration: 1.0,
+            steps: 5,
+            start_time: 0.0,
+        }
+    }
+}
+
+impl<S: Num + NumCast + Debug + Display + Clone + Copy + PartialEq + 'static> TimeSignal<S>
+    for Staircase<S>
+{
+    fn time_to_signal(&self, time: f64) -> S {
+        if time <= self.start_time {
+            return self.initial;
+        }
+        let elapsed: i64 = TIME_SCALING.to_fixed(time - self.start_time);
+        let duration: i64 = TIME_SCALING.to_fixed(self.step_duration);
+        let taken = match duration {
+            // ceil, a step is taken right after its start
+            d if d > 0 => (elapsed.max(1) - 1) / d + 1,
+            0 => self.steps as i64,
+            _ => 0,
+        };
+        let taken: S = NumCast::from(taken.min(self.steps as i64)).unwrap_or_else(zero);
+        self.initial + self.step_height * taken
+    }
+
+    fn short_type_name(&self) -> &'static str {
+        "Staircase"
+    }
+}
+
+impl<S: Num + NumCast + Debug + Display + Clone + Copy + PartialEq + 'static> fmt::Display
+    for Staircase<S>
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}(initial={}, step_height={}, step_duration={}, steps={}, start_time={})",
+            self.short_type_name(),
+            self.initial,
+            self.step_height,
+            self.step_duration,
+            self.steps,
+            self.start_time
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_staircase_falling_i16() {
+        let sut = Staircase::<i16>::default()
+            .initial(1000)
+            .step_height(-250)
+            .step_duration(2.0)
+            .steps(2)
+            .start(1.0);
+        assert_eq!(sut.time_to_signal(1.0), 1000);
+        assert_eq!(sut.time_to_signal(3.0), 750);
+        assert_eq!(sut.time_to_signal(3.1), 500);
+        assert_eq!(sut.time_to_signal(100.0), 500);
+    }
+}
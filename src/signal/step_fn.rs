@@ -18,8 +18,11 @@
 use num_traits::{Num, one, zero};
 
 pub use super::*;
+use crate::parse::{Fields, ParseError};
+use core::str::FromStr;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct StepFunction<S: Debug + Display + Clone + Copy + PartialEq> {
     pub pre_value: S,
     pub post_value: S,
@@ -72,7 +75,7 @@ impl<S: Num + Debug + Display + Clone + Copy + PartialEq + 'static> fmt::Display
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
             f,
-            "{}(step_time={}, pre={}, post={}",
+            "{}(step_time={}, pre={}, post={})",
             self.short_type_name(),
             self.step_time,
             self.pre_value,
@@ -80,3 +83,43 @@ impl<S: Num + Debug + Display + Clone + Copy + PartialEq + 'static> fmt::Display
         )
     }
 }
+
+impl<S: FromStr + Debug + Display + Clone + Copy + PartialEq> FromStr for StepFunction<S> {
+    type Err = ParseError;
+
+    /// Parse the `Display` output
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut fields = Fields::new(s, "Step")?;
+        let signal = StepFunction {
+            step_time: fields.next("step_time", "=")?,
+            pre_value: fields.next("pre", "=")?,
+            post_value: fields.next("post", "=")?,
+        };
+        fields.end()?;
+        Ok(signal)
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(all(test, feature = "std"))]
+mod tests {
+
+    use super::*;
+    use std::string::ToString;
+
+    #[test]
+    fn test_StepFunction_from_str_round_trip() {
+        let sut = StepFunction::<f64>::default()
+            .step(2.5)
+            .pre(-1.0)
+            .post(1e-3);
+        assert_eq!(sut.to_string(), "Step(step_time=2.5, pre=-1, post=0.001)");
+        assert_eq!(sut.to_string().parse::<StepFunction<f64>>(), Ok(sut));
+        let sut = StepFunction::<i32>::default().post(-4);
+        assert_eq!(sut.to_string().parse::<StepFunction<i32>>(), Ok(sut));
+        assert_eq!(
+            "Ramp(start_time=0, offset=0, slope=1)".parse::<StepFunction<f64>>(),
+            Err(ParseError::Type("Step"))
+        );
+    }
+}
@@ -0,0 +1,210 @@
+//! # Tagged serialization of boxed time signals
+//!
+//! The counterpart of `plant::tagged` for stimuli: a `TimeSignalRegistry`
+//! maps the `short_type_name` of each registered signal type to its serde
+//! implementation, a `BoxedTimeSignal` is written with the name as tag:
+//!
+//! `{"type": "Step", "signal": {"pre_value": 0.0, ...}}`
+//!
+//! A `SuperPosition` is tagged `"Superposition"` with the list of its two
+//! tagged summands, so test stimuli composed of several signals can be kept
+//! in data files. The tagged form is a `serde_json::Value`, any serde format
+//! can write and read it, e.g. JSON or TOML.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use cb_simulation_util::signal::tagged::TimeSignalRegistry;
+//! use cb_simulation_util::signal::{
+//!     BoxedTimeSignal, DynTimeSignal, ImpulseFunction, StepFunction, SuperPosition,
+//! };
+//!
+//! fn main() {
+//!     let stimulus: BoxedTimeSignal<f64> = Box::new(SuperPosition(
+//!         Box::new(StepFunction::default().step(5.0)),
+//!         Box::new(ImpulseFunction::default().start(8.0).amplitude(0.5)),
+//!     ));
+//!     let registry = TimeSignalRegistry::builtin();
+//!     let json = serde_json::to_string(&registry.to_value(&*stimulus).unwrap()).unwrap();
+//!     let restored = registry.from_value(&serde_json::from_str(&json).unwrap()).unwrap();
+//!     assert!(restored.dyn_eq(stimulus.as_dyn_time_signal()));
+//!     assert_eq!(restored.time_to_signal(8.5), 1.5);
+//! }
+//! ```
+
+use core::any::Any;
+use core::ops::Add;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value};
+use std::format;
+use std::string::{String, ToString};
+use std::vec;
+use std::vec::Vec;
+
+use super::*;
+use crate::plant::tagged::TaggedError;
+
+const TYPE_KEY: &str = "type";
+const SIGNAL_KEY: &str = "signal";
+const SUPERPOSITION_TAG: &str = "Superposition";
+
+type ToValue = fn(&dyn Any) -> Option<Result<Value, serde_json::Error>>;
+type FromValue<S> = fn(Value) -> Result<BoxedTimeSignal<S>, serde_json::Error>;
+
+struct Entry<S> {
+    tag: &'static str,
+    to_value: ToValue,
+    from_value: FromValue<S>,
+}
+
+/// Time signal types by their `short_type_name`, see the module documentation
+pub struct TimeSignalRegistry<S> {
+    entries: Vec<Entry<S>>,
+}
+
+impl<S> TimeSignalRegistry<S>
+where
+    S: Add<Output = S> + Num + Debug + Display + Clone + Copy + PartialEq + 'static + Send + Sync,
+{
+    /// Empty registry, `SuperPosition` is always supported
+    pub fn new() -> Self {
+        TimeSignalRegistry {
+            entries: Vec::new(),
+        }
+    }
+
+    /// Register `T` under `tag`, which must be the `short_type_name` of `T`
+    ///
+    /// A type registered before under the same tag is replaced.
+    pub fn register<T>(&mut self, tag: &'static str)
+    where
+        T: DynTimeSignal<S> + Serialize + DeserializeOwned,
+    {
+        self.entries.retain(|entry| entry.tag != tag);
+        self.entries.push(Entry {
+            tag,
+            to_value: |any| any.downcast_ref::<T>().map(serde_json::to_value),
+            from_value: |value| {
+                serde_json::from_value::<T>(value).map(|s| Box::new(s) as BoxedTimeSignal<S>)
+            },
+        });
+    }
+
+    /// Registered tags in the order of registration
+    pub fn tags(&self) -> Vec<&'static str> {
+        self.entries.iter().map(|entry| entry.tag).collect()
+    }
+
+    fn entry(&self, tag: &str) -> Result<&Entry<S>, TaggedError> {
+        self.entries
+            .iter()
+            .find(|entry| entry.tag == tag)
+            .ok_or_else(|| TaggedError::Unregistered(String::from(tag)))
+    }
+
+    /// Tagged value of `signal`, nested for a `SuperPosition`
+    pub fn to_value(&self, signal: &dyn DynTimeSignal<S>) -> Result<Value, TaggedError> {
+        let tag = TimeSignal::short_type_name(signal);
+        let any = DynTimeSignal::as_any(signal);
+        let body = match any.downcast_ref::<SuperPosition<S>>() {
+            Some(sum) => Value::Array(vec![self.to_value(&*sum.0)?, self.to_value(&*sum.1)?]),
+            None => (self.entry(tag)?.to_value)(any)
+                .ok_or_else(|| {
+                    TaggedError::Malformed(format!("{} is not of the registered type", tag))
+                })?
+                .map_err(|e| TaggedError::Malformed(e.to_string()))?,
+        };
+        let mut tagged = Map::new();
+        tagged.insert(String::from(TYPE_KEY), Value::from(tag));
+        tagged.insert(String::from(SIGNAL_KEY), body);
+        Ok(Value::Object(tagged))
+    }
+
+    /// Signal of a tagged value, see `to_value`
+    pub fn from_value(&self, value: &Value) -> Result<BoxedTimeSignal<S>, TaggedError> {
+        let tag = value
+            .get(TYPE_KEY)
+            .and_then(Value::as_str)
+            .ok_or_else(|| TaggedError::Malformed(format!("missing \"{}\"", TYPE_KEY)))?;
+        let body = value
+            .get(SIGNAL_KEY)
+            .ok_or_else(|| TaggedError::Malformed(format!("missing \"{}\"", SIGNAL_KEY)))?;
+        if tag == SUPERPOSITION_TAG {
+            return match body.as_array().map(Vec::as_slice) {
+                Some([first, second]) => Ok(Box::new(SuperPosition(
+                    self.from_value(first)?,
+                    self.from_value(second)?,
+                ))),
+                _ => Err(TaggedError::Malformed(String::from(
+                    "Superposition needs a list of two signals",
+                ))),
+            };
+        }
+        (self.entry(tag)?.from_value)(body.clone())
+            .map_err(|e| TaggedError::Malformed(format!("{}: {}", tag, e)))
+    }
+}
+
+impl<S> Default for TimeSignalRegistry<S>
+where
+    S: Add<Output = S> + Num + Debug + Display + Clone + Copy + PartialEq + 'static + Send + Sync,
+{
+    fn default() -> Self {
+        TimeSignalRegistry::new()
+    }
+}
+
+impl TimeSignalRegistry<f64> {
+    /// Registry of the time signals of this crate with serde support
+    pub fn builtin() -> Self {
+        let mut registry = TimeSignalRegistry::new();
+        registry.register::<StepFunction<f64>>("Step");
+        registry.register::<ImpulseFunction<f64>>("Impulse");
+        registry
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_TimeSignalRegistry_round_trips_nested_superposition() {
+        let sut: BoxedTimeSignal<f64> = Box::new(SuperPosition(
+            Box::new(SuperPosition(
+                Box::new(StepFunction::default().pre(1.0).post(2.0).step(3.0)),
+                Box::new(StepFunction::default().step(6.0)),
+            )),
+            Box::new(ImpulseFunction::default().start(4.0).duration(0.5)),
+        ));
+        let registry = TimeSignalRegistry::builtin();
+        let json = serde_json::to_string(&registry.to_value(&*sut).unwrap()).unwrap();
+        let restored = registry
+            .from_value(&serde_json::from_str(&json).unwrap())
+            .unwrap();
+        assert!(restored.dyn_eq(sut.as_dyn_time_signal()));
+        for time in [0.0, 3.5, 4.2, 7.0] {
+            assert_eq!(restored.time_to_signal(time), sut.time_to_signal(time));
+        }
+    }
+
+    #[test]
+    fn test_TimeSignalRegistry_errors() {
+        let registry = TimeSignalRegistry::<f64>::builtin();
+        assert_eq!(registry.tags(), vec!["Step", "Impulse"]);
+        let ramp = RampFunction::<f64>::default();
+        assert_eq!(
+            registry.to_value(&ramp),
+            Err(TaggedError::Unregistered(String::from("Ramp")))
+        );
+        let value: Value =
+            serde_json::from_str(r#"{"type": "Superposition", "signal": []}"#).unwrap();
+        assert!(matches!(
+            registry.from_value(&value),
+            Err(TaggedError::Malformed(_))
+        ));
+    }
+}
@@ -1,5 +1,10 @@
 //! # Time Range
 //!
+//! The `unit_of_measurement` applies to `start`, `end` and the sampling
+//! interval. Known time units are `us`, `ms`, `s` (or `sec`), `min` and `h`,
+//! `convert_to` rescales a range to another of them, e.g. to match the unit
+//! the sample times of the elements are declared in.
+//!
 //! ## Example
 //!
 //! ```rust
@@ -10,12 +15,52 @@
 //!   let range = TimeRange::default().set_start(-5.0).set_end(15.0).set_number_of_samples(Some(10));
 //!   assert_eq!(range.len(), 10);
 //!   let time: Array<f64, Ix1> = range.collect();
+//!   let seconds = range.convert_to("s").unwrap();
+//!   assert_eq!(seconds.sampling_interval, 0.002);
 //! }
 //! ```
 
 use core::default::Default;
+use core::fmt::{self, Display};
 use core::option::Option;
 
+/// Seconds per `unit`, `None` for an unknown time unit
+pub fn seconds_per_unit(unit: &str) -> Option<f64> {
+    match unit {
+        "us" | "µs" => Some(1.0e-6),
+        "ms" => Some(1.0e-3),
+        "s" | "sec" => Some(1.0),
+        "min" => Some(60.0),
+        "h" => Some(3600.0),
+        _ => None,
+    }
+}
+
+/// Convert the duration `value` from the time unit `from` to `to`
+pub fn convert_time(
+    value: f64,
+    from: &'static str,
+    to: &'static str,
+) -> Result<f64, TimeUnitError> {
+    let from_seconds = seconds_per_unit(from).ok_or(TimeUnitError::Unknown(from))?;
+    let to_seconds = seconds_per_unit(to).ok_or(TimeUnitError::Unknown(to))?;
+    Ok(value * from_seconds / to_seconds)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeUnitError {
+    /// Not one of the known time units, see `seconds_per_unit`
+    Unknown(&'static str),
+}
+
+impl Display for TimeUnitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TimeUnitError::Unknown(unit) => write!(f, "Unknown time unit {}", unit),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct TimeRange {
     pub unit_of_measurement: &'static str,
@@ -77,6 +122,29 @@ impl TimeRange {
         }
     }
 
+    /// Seconds per unit of the range
+    pub fn seconds_per_unit(&self) -> Result<f64, TimeUnitError> {
+        seconds_per_unit(self.unit_of_measurement)
+            .ok_or(TimeUnitError::Unknown(self.unit_of_measurement))
+    }
+
+    /// The same range expressed in the time unit `unit`
+    pub fn convert_to(self, unit: &'static str) -> Result<Self, TimeUnitError> {
+        let factor = convert_time(1.0, self.unit_of_measurement, unit)?;
+        Ok(TimeRange {
+            unit_of_measurement: unit,
+            start: self.start * factor,
+            end: self.end * factor,
+            sampling_interval: self.sampling_interval * factor,
+            current: self.current * factor,
+        })
+    }
+
+    /// The sampling interval in the time unit `unit`
+    pub fn sampling_interval_in(&self, unit: &'static str) -> Result<f64, TimeUnitError> {
+        convert_time(self.sampling_interval, self.unit_of_measurement, unit)
+    }
+
     pub fn set_sampling_interval(self, sampling_interval: f64) -> Self {
         if self.end - self.start < sampling_interval {
             panic!("Sampling interval too small")
@@ -119,6 +187,24 @@ mod tests {
         assert!("sec" == sut.unit_of_measurement);
     }
 
+    #[test]
+    fn time_range_convert_to() {
+        let sut = TimeRange::default()
+            .set_sampling_interval(0.5)
+            .convert_to("s")
+            .unwrap();
+        assert_eq!(sut.unit_of_measurement, "s");
+        assert_eq!(sut.end, 0.1);
+        assert_eq!(sut.sampling_interval, 0.0005);
+        assert_eq!(sut.len(), 200);
+        assert!((sut.sampling_interval_in("us").unwrap() - 500.0).abs() < 1e-9);
+        assert_eq!(
+            TimeRange::default().convert_to("fortnight"),
+            Err(TimeUnitError::Unknown("fortnight"))
+        );
+        assert_eq!(convert_time(1.5, "min", "sec"), Ok(90.0));
+    }
+
     #[test]
     fn time_range_start() {
         let sut = TimeRange::default();
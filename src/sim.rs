@@ -0,0 +1,830 @@
+//! # Simulation runs
+//!
+//! Feeds a time signal through a transfer element over a `TimeRange` and
+//! collects the result as labelled traces. Each trace carries its unit,
+//! the block it originates from and its sample interval, so exports are
+//! labelled without manual bookkeeping.
+//!
+//! Element sample times are compared with the sampling interval of the
+//! `TimeRange` by `check_sample_time` and the checked runs `run_checked` and
+//! `run_closed_loop_checked`, composites report the common sample time of
+//! their parts. `infer_sample_time` takes the step from the elements instead.
+//! If the elements are parameterized in another time unit than the range,
+//! e.g. time constants in s and the time axis in ms, declare it with
+//! `set_element_time_unit` and the step is converted, an unknown unit is
+//! reported as `SimulationError::TimeUnit`. `SimResult::convert_time_unit`
+//! rescales the time axis of a result, `SimResult::time_in` converts it.
+//!
+//! `SimResult::diff` compares two runs trace by trace, e.g. to check that a
+//! refactoring did not change the numerics or how far a fixed point
+//! implementation drifts from the f64 one.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use cb_simulation_util::plant::pt1::PT1;
+//! use cb_simulation_util::signal::{StepFunction, TimeRange};
+//! use cb_simulation_util::sim::Simulation;
+//!
+//! fn main() {
+//!     let mut plant = PT1::<f64>::default().set_t1_time_or_default(10.0);
+//!     let result = Simulation::new(TimeRange::default())
+//!         .set_input_unit("%")
+//!         .run(&StepFunction::default().step(5.0), &mut plant);
+//!     let output = result.trace("output").unwrap();
+//!     assert_eq!(output.meta.unit, "%");
+//!     assert_eq!(output.meta.source, "PT1");
+//!     assert_eq!(output.values.len(), result.time.len());
+//! }
+//! ```
+
+use core::fmt::{self, Display};
+use ndarray::Array1;
+use std::string::String;
+use std::vec::Vec;
+
+use crate::plant::{SampleTime, TransferTimeDomain, TypeIdentifier};
+use crate::signal::{TimeRange, TimeSignal, TimeUnitError, convert_time};
+
+/// Labelling information of a trace
+#[derive(Debug, Clone, PartialEq)]
+pub struct TraceMetadata {
+    /// Unit of measurement of the values
+    pub unit: &'static str,
+    /// Short type name of the block producing the values
+    pub source: &'static str,
+    /// Time between two values, in the unit of the time axis
+    pub sample_interval: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trace {
+    pub name: String,
+    pub meta: TraceMetadata,
+    pub values: Array1<f64>,
+}
+
+/// Result of a simulation run: a common time axis and a set of traces
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimResult {
+    pub time: Array1<f64>,
+    pub time_unit: &'static str,
+    pub traces: Vec<Trace>,
+}
+
+impl SimResult {
+    pub fn trace(&self, name: &str) -> Option<&Trace> {
+        self.traces.iter().find(|t| t.name == name)
+    }
+
+    /// Run `block` over the trace `source` and append its output as trace `name`
+    ///
+    /// Used for derived quantities like counters and totals. Fails if there
+    /// is no trace `source` or the block does not run at the sample interval
+    /// of the trace, e.g. the window of a `SwitchCounter` would span another
+    /// time than configured.
+    pub fn derive<E: TransferTimeDomain<f64> + SampleTime + ?Sized>(
+        &mut self,
+        name: &str,
+        source: &str,
+        block: &mut E,
+    ) -> Result<&Trace, SimulationError> {
+        let input = self
+            .trace(source)
+            .ok_or_else(|| SimulationError::UnknownTrace(String::from(source)))?;
+        check_blocks(
+            input.meta.sample_interval,
+            [(block.short_type_name(), block.sample_time())],
+        )?;
+        let trace = Trace {
+            name: String::from(name),
+            meta: TraceMetadata {
+                unit: block.output_unit(input.meta.unit),
+                source: block.short_type_name(),
+                sample_interval: input.meta.sample_interval,
+            },
+            values: input.values.mapv(|u| block.transfer_td(u)),
+        };
+        self.traces.push(trace);
+        Ok(&self.traces[self.traces.len() - 1])
+    }
+
+    /// The time axis in `unit`
+    pub fn time_in(&self, unit: &'static str) -> Result<Array1<f64>, TimeUnitError> {
+        let factor = convert_time(1.0, self.time_unit, unit)?;
+        Ok(&self.time * factor)
+    }
+
+    /// The same result with the time axis and the sample intervals in `unit`
+    pub fn convert_time_unit(self, unit: &'static str) -> Result<SimResult, TimeUnitError> {
+        let factor = convert_time(1.0, self.time_unit, unit)?;
+        let traces = self
+            .traces
+            .into_iter()
+            .map(|t| Trace {
+                meta: TraceMetadata {
+                    sample_interval: t.meta.sample_interval * factor,
+                    ..t.meta
+                },
+                ..t
+            })
+            .collect();
+        Ok(SimResult {
+            time: self.time * factor,
+            time_unit: unit,
+            traces,
+        })
+    }
+
+    /// CSV export, the header labels each column with its unit
+    pub fn to_csv(&self) -> String {
+        use core::fmt::Write;
+        let mut csv = String::new();
+        let _ = write!(csv, "time [{}]", self.time_unit);
+        for t in &self.traces {
+            let _ = write!(csv, ",{} [{}]", t.name, t.meta.unit);
+        }
+        csv.push('\n');
+        for (i, time) in self.time.iter().enumerate() {
+            let _ = write!(csv, "{}", time);
+            for t in &self.traces {
+                let _ = write!(csv, ",{}", t.values[i]);
+            }
+            csv.push('\n');
+        }
+        csv
+    }
+
+    /// Compare with `other`, e.g. a run after a refactoring or in fixed point
+    ///
+    /// Traces are matched by name and compared sample by sample over the
+    /// common length. The error traces hold `other - self`, traces present in
+    /// just one of both results are listed as unmatched.
+    pub fn diff(&self, other: &SimResult) -> SimDiff {
+        let samples = self.time.len().min(other.time.len());
+        let time = self.time.slice(ndarray::s![..samples]).to_owned();
+        let mut errors = Vec::new();
+        let mut deviations = Vec::new();
+        let mut unmatched = Vec::new();
+        for trace in &self.traces {
+            let Some(theirs) = other.trace(&trace.name) else {
+                unmatched.push(trace.name.clone());
+                continue;
+            };
+            let n = samples.min(trace.values.len()).min(theirs.values.len());
+            let error: Array1<f64> = (0..n).map(|i| theirs.values[i] - trace.values[i]).collect();
+            deviations.push(Deviation::of(&trace.name, &time, &error));
+            errors.push(Trace {
+                name: trace.name.clone(),
+                meta: trace.meta.clone(),
+                values: error,
+            });
+        }
+        for trace in &other.traces {
+            if self.trace(&trace.name).is_none() {
+                unmatched.push(trace.name.clone());
+            }
+        }
+        SimDiff {
+            errors: SimResult {
+                time,
+                time_unit: self.time_unit,
+                traces: errors,
+            },
+            deviations,
+            unmatched,
+        }
+    }
+}
+
+/// Summary statistics of the error of one trace
+#[derive(Debug, Clone, PartialEq)]
+pub struct Deviation {
+    pub name: String,
+    pub max_abs: f64,
+    pub rms: f64,
+    /// Time of the first sample differing at all, `None` if identical
+    ///
+    /// Like the maximum, it treats a `NaN` error as a difference.
+    pub first_divergence: Option<f64>,
+}
+
+impl Deviation {
+    fn of(name: &str, time: &Array1<f64>, error: &Array1<f64>) -> Self {
+        let max_abs = error.iter().fold(0.0, |m: f64, e| {
+            if m.is_nan() || e.is_nan() {
+                f64::NAN
+            } else {
+                m.max(e.abs())
+            }
+        });
+        let rms = if error.is_empty() {
+            0.0
+        } else {
+            (error.iter().map(|e| e * e).sum::<f64>() / error.len() as f64).sqrt()
+        };
+        Deviation {
+            name: String::from(name),
+            max_abs,
+            rms,
+            first_divergence: error.iter().position(|e| *e != 0.0).map(|i| time[i]),
+        }
+    }
+}
+
+/// Difference of two simulation runs, see `SimResult::diff`
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimDiff {
+    /// Error traces `other - self` on the common time axis
+    pub errors: SimResult,
+    pub deviations: Vec<Deviation>,
+    /// Names of traces present in only one of both results
+    pub unmatched: Vec<String>,
+}
+
+impl SimDiff {
+    pub fn deviation(&self, name: &str) -> Option<&Deviation> {
+        self.deviations.iter().find(|d| d.name == name)
+    }
+
+    /// True if all traces match exactly
+    pub fn is_identical(&self) -> bool {
+        self.unmatched.is_empty() && self.deviations.iter().all(|d| d.first_divergence.is_none())
+    }
+
+    /// True if no matched trace deviates by more than `tolerance`
+    pub fn within(&self, tolerance: f64) -> bool {
+        self.deviations.iter().all(|d| d.max_abs <= tolerance)
+    }
+
+    /// Earliest time any trace deviates by more than `tolerance`
+    pub fn first_divergence(&self, tolerance: f64) -> Option<f64> {
+        self.errors
+            .traces
+            .iter()
+            .filter_map(|t| {
+                t.values
+                    .iter()
+                    .position(|e| e.is_nan() || e.abs() > tolerance)
+                    .map(|i| self.errors.time[i])
+            })
+            .min_by(|a, b| a.total_cmp(b))
+    }
+}
+
+impl Display for SimDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "SimDiff(samples: {})", self.errors.time.len())?;
+        for d in &self.deviations {
+            write!(f, "  {}: max {}, rms {}", d.name, d.max_abs, d.rms)?;
+            match d.first_divergence {
+                Some(t) => writeln!(f, ", diverges at {} {}", t, self.errors.time_unit)?,
+                None => writeln!(f, ", identical")?,
+            }
+        }
+        for name in &self.unmatched {
+            writeln!(f, "  {}: unmatched", name)?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for SimResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SimResult(samples: {}, traces: [", self.time.len())?;
+        for (i, t) in self.traces.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{} [{}] from {}", t.name, t.meta.unit, t.meta.source)?;
+        }
+        write!(f, "])")
+    }
+}
+
+/// A block whose sample time differs from the simulation step
+#[derive(Debug, Clone, PartialEq)]
+pub struct SampleTimeMismatch {
+    /// Position of the block in the checked list
+    pub index: usize,
+    pub block: &'static str,
+    pub sample_time: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SampleTimeError {
+    /// The simulation step, i.e. the sampling interval of the `TimeRange`,
+    /// in the time unit of the elements
+    pub expected: f64,
+    pub mismatches: Vec<SampleTimeMismatch>,
+}
+
+impl Display for SampleTimeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Sample time mismatch, simulation step is {}:",
+            self.expected
+        )?;
+        for m in &self.mismatches {
+            write!(
+                f,
+                " block {} ({}) uses {};",
+                m.index, m.block, m.sample_time
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Why a simulation does not fit its elements
+#[derive(Debug, Clone, PartialEq)]
+pub enum SimulationError {
+    /// The range or the elements are declared in an unknown time unit
+    TimeUnit(TimeUnitError),
+    /// Blocks do not use the simulation step
+    SampleTime(SampleTimeError),
+    /// There is no trace of the name
+    UnknownTrace(String),
+}
+
+impl Display for SimulationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SimulationError::TimeUnit(error) => write!(f, "{}", error),
+            SimulationError::SampleTime(error) => write!(f, "{}", error),
+            SimulationError::UnknownTrace(name) => write!(f, "No trace named {}", name),
+        }
+    }
+}
+
+impl From<TimeUnitError> for SimulationError {
+    fn from(error: TimeUnitError) -> Self {
+        SimulationError::TimeUnit(error)
+    }
+}
+
+impl From<SampleTimeError> for SimulationError {
+    fn from(error: SampleTimeError) -> Self {
+        SimulationError::SampleTime(error)
+    }
+}
+
+/// Block which can take part in a sample time check
+pub trait TimedBlock: SampleTime + TypeIdentifier {}
+
+impl<T: SampleTime + TypeIdentifier> TimedBlock for T {}
+
+/// Blocks given by name and sample time which do not use `expected`
+fn check_blocks(
+    expected: f64,
+    blocks: impl IntoIterator<Item = (&'static str, Option<f64>)>,
+) -> Result<(), SampleTimeError> {
+    let mismatches: Vec<SampleTimeMismatch> = blocks
+        .into_iter()
+        .enumerate()
+        .filter_map(|(index, (block, sample_time))| match sample_time {
+            // NaN of a composite with disagreeing parts never matches
+            Some(ts) if ts.is_nan() || (ts - expected).abs() > 1e-9 * expected.abs() => {
+                Some(SampleTimeMismatch {
+                    index,
+                    block,
+                    sample_time: ts,
+                })
+            }
+            _ => None,
+        })
+        .collect();
+    if mismatches.is_empty() {
+        Ok(())
+    } else {
+        Err(SampleTimeError {
+            expected,
+            mismatches,
+        })
+    }
+}
+
+/// Configuration of a simulation run
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Simulation {
+    pub range: TimeRange,
+    pub input_unit: &'static str,
+    /// Time unit of the element parameters, `None` for the unit of the range
+    pub element_time_unit: Option<&'static str>,
+}
+
+impl From<TimeRange> for Simulation {
+    fn from(range: TimeRange) -> Self {
+        Simulation::new(range)
+    }
+}
+
+impl Simulation {
+    pub fn new(range: TimeRange) -> Self {
+        Simulation {
+            range,
+            input_unit: "1",
+            element_time_unit: None,
+        }
+    }
+
+    pub fn set_input_unit(self, input_unit: &'static str) -> Self {
+        Simulation { input_unit, ..self }
+    }
+
+    /// Time unit the sample times and time constants of the elements are declared in
+    ///
+    /// Fails if it or the unit of the range is not a known time unit.
+    pub fn set_element_time_unit(self, unit: &'static str) -> Result<Self, TimeUnitError> {
+        self.range.sampling_interval_in(unit)?;
+        Ok(Simulation {
+            element_time_unit: Some(unit),
+            ..self
+        })
+    }
+
+    /// The simulation step in the time unit of the elements
+    pub fn element_step(&self) -> Result<f64, TimeUnitError> {
+        match self.element_time_unit {
+            None => Ok(self.range.sampling_interval),
+            Some(unit) => self.range.sampling_interval_in(unit),
+        }
+    }
+
+    /// Check the sample time of `blocks` against the `TimeRange`
+    ///
+    /// Every block with internal state must use the sampling interval of the
+    /// `TimeRange`, otherwise its coefficients do not match the simulation step.
+    /// A composite whose parts disagree never matches. The step is converted
+    /// to the `element_time_unit`, fails if the range was changed to an unknown
+    /// unit since it was set.
+    /// Returns the common sample time or all mismatching blocks.
+    pub fn check_sample_time(&self, blocks: &[&dyn TimedBlock]) -> Result<f64, SimulationError> {
+        let expected = self.element_step()?;
+        check_blocks(
+            expected,
+            blocks
+                .iter()
+                .map(|b| (b.short_type_name(), b.sample_time())),
+        )?;
+        Ok(expected)
+    }
+
+    /// Use the common sample time of `blocks` as the simulation step
+    ///
+    /// The base rate is the sample time of the first block which has one,
+    /// converted from the `element_time_unit` to the unit of the range. The
+    /// range is kept if no block has a sample time. Fails with all blocks not
+    /// using the base rate.
+    ///
+    /// Panics like `TimeRange::set_sampling_interval` if the base rate exceeds
+    /// the range.
+    pub fn infer_sample_time(self, blocks: &[&dyn TimedBlock]) -> Result<Self, SimulationError> {
+        let Some(base) = blocks.iter().find_map(|b| b.sample_time()) else {
+            return Ok(self);
+        };
+        check_blocks(
+            base,
+            blocks
+                .iter()
+                .map(|b| (b.short_type_name(), b.sample_time())),
+        )?;
+        let step = match self.element_time_unit {
+            None => base,
+            Some(unit) => convert_time(base, unit, self.range.unit_of_measurement)?,
+        };
+        Ok(Simulation {
+            range: self.range.set_sampling_interval(step),
+            ..self
+        })
+    }
+
+    /// Run `signal` through `element`, recording the traces `input` and `output`
+    ///
+    /// The output unit is derived from the input unit by the element itself.
+    pub fn run<E: TransferTimeDomain<f64> + ?Sized>(
+        &self,
+        signal: &dyn TimeSignal<f64>,
+        element: &mut E,
+    ) -> SimResult {
+        #[cfg(feature = "tracing")]
+        let _run = tracing::info_span!(
+            "simulation",
+            signal = signal.short_type_name(),
+            element = element.short_type_name()
+        )
+        .entered();
+        let time: Array1<f64> = self.range.collect();
+        let input: Array1<f64> = time.iter().map(|t| signal.time_to_signal(*t)).collect();
+        let output: Array1<f64> = {
+            #[cfg(feature = "tracing")]
+            let _block = tracing::debug_span!("block", name = element.short_type_name()).entered();
+            input.iter().map(|u| element.transfer_td(*u)).collect()
+        };
+        #[cfg(feature = "tracing")]
+        tracing::debug!(samples = time.len(), "simulation finished");
+        SimResult {
+            time,
+            time_unit: self.range.unit_of_measurement,
+            traces: std::vec![
+                Trace {
+                    name: String::from("input"),
+                    meta: TraceMetadata {
+                        unit: self.input_unit,
+                        source: signal.short_type_name(),
+                        sample_interval: self.range.sampling_interval,
+                    },
+                    values: input,
+                },
+                Trace {
+                    name: String::from("output"),
+                    meta: TraceMetadata {
+                        unit: element.output_unit(self.input_unit),
+                        source: element.short_type_name(),
+                        sample_interval: self.range.sampling_interval,
+                    },
+                    values: output,
+                },
+            ],
+        }
+    }
+
+    /// `run` after checking the sample time of `element`, see `check_sample_time`
+    pub fn run_checked<E: TransferTimeDomain<f64> + SampleTime + ?Sized>(
+        &self,
+        signal: &dyn TimeSignal<f64>,
+        element: &mut E,
+    ) -> Result<SimResult, SimulationError> {
+        check_blocks(
+            self.element_step()?,
+            [(element.short_type_name(), element.sample_time())],
+        )?;
+        Ok(self.run(signal, element))
+    }
+
+    /// Run a unity feedback loop, recording `setpoint`, `error`, `control` and `output`
+    ///
+    /// $ e[k] = r[k] - y[k-1] $, $ u[k] = C(e[k]) $, $ y[k] = P(u[k]) $
+    pub fn run_closed_loop<C, P>(
+        &self,
+        setpoint: &dyn TimeSignal<f64>,
+        controller: &mut C,
+        plant: &mut P,
+    ) -> SimResult
+    where
+        C: TransferTimeDomain<f64> + ?Sized,
+        P: TransferTimeDomain<f64> + ?Sized,
+    {
+        #[cfg(feature = "tracing")]
+        let _run = tracing::info_span!(
+            "closed_loop_simulation",
+            setpoint = setpoint.short_type_name(),
+            controller = controller.short_type_name(),
+            plant = plant.short_type_name()
+        )
+        .entered();
+        let time: Array1<f64> = self.range.collect();
+        let n = time.len();
+        let (mut r, mut e, mut u, mut y) = (
+            Array1::zeros(n),
+            Array1::zeros(n),
+            Array1::zeros(n),
+            Array1::zeros(n),
+        );
+        let mut previous_output = 0.0;
+        for (k, t) in time.iter().enumerate() {
+            r[k] = setpoint.time_to_signal(*t);
+            e[k] = r[k] - previous_output;
+            u[k] = {
+                #[cfg(feature = "tracing")]
+                let _block =
+                    tracing::trace_span!("block", name = controller.short_type_name()).entered();
+                controller.transfer_td(e[k])
+            };
+            y[k] = {
+                #[cfg(feature = "tracing")]
+                let _block =
+                    tracing::trace_span!("block", name = plant.short_type_name()).entered();
+                plant.transfer_td(u[k])
+            };
+            previous_output = y[k];
+        }
+        #[cfg(feature = "tracing")]
+        tracing::debug!(samples = n, "closed loop simulation finished");
+        let control_unit = controller.output_unit(self.input_unit);
+        let trace = |name: &str, unit, source, values| Trace {
+            name: String::from(name),
+            meta: TraceMetadata {
+                unit,
+                source,
+                sample_interval: self.range.sampling_interval,
+            },
+            values,
+        };
+        SimResult {
+            time,
+            time_unit: self.range.unit_of_measurement,
+            traces: std::vec![
+                trace("setpoint", self.input_unit, setpoint.short_type_name(), r),
+                trace("error", self.input_unit, "Sum", e),
+                trace("control", control_unit, controller.short_type_name(), u),
+                trace(
+                    "output",
+                    plant.output_unit(control_unit),
+                    plant.short_type_name(),
+                    y
+                ),
+            ],
+        }
+    }
+
+    /// `run_closed_loop` after checking the sample times of `controller` and
+    /// `plant`, see `check_sample_time`
+    pub fn run_closed_loop_checked<C, P>(
+        &self,
+        setpoint: &dyn TimeSignal<f64>,
+        controller: &mut C,
+        plant: &mut P,
+    ) -> Result<SimResult, SimulationError>
+    where
+        C: TransferTimeDomain<f64> + SampleTime + ?Sized,
+        P: TransferTimeDomain<f64> + SampleTime + ?Sized,
+    {
+        check_blocks(
+            self.element_step()?,
+            [
+                (controller.short_type_name(), controller.sample_time()),
+                (plant.short_type_name(), plant.sample_time()),
+            ],
+        )?;
+        Ok(self.run_closed_loop(setpoint, controller, plant))
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::plant::unit_gain::{Quantity, Unit, UnitGain};
+    use crate::signal::StepFunction;
+    use std::vec;
+
+    #[test]
+    fn test_Simulation_unit_propagation() {
+        let mut gain = UnitGain::span(
+            Unit::new("%", Quantity::Ratio, 0.01, 0.0),
+            (0.0, 100.0),
+            Unit::new("mA", Quantity::Current, 1.0e-3, 0.0),
+            (4.0, 20.0),
+        )
+        .unwrap();
+        let result = Simulation::new(TimeRange::default().set_unit_of_measurement("s"))
+            .set_input_unit("%")
+            .run(&StepFunction::default(), &mut gain);
+        assert_eq!(result.time_unit, "s");
+        assert_eq!(result.trace("input").unwrap().meta.source, "Step");
+        assert_eq!(result.trace("output").unwrap().meta.unit, "mA");
+        assert_eq!(result.trace("output").unwrap().meta.sample_interval, 1.0);
+    }
+
+    #[test]
+    fn test_Simulation_check_sample_time() {
+        use crate::plant::{pt1::PT1, pt2::PT2};
+        let sim = Simulation::new(TimeRange::default().set_sampling_interval(0.5));
+        let pt1 = PT1::<f64>::default().set_sample_time_or_default(0.5);
+        let pt2 = PT2::<f64>::default();
+        let gain = UnitGain::default();
+        assert_eq!(sim.check_sample_time(&[&pt1, &gain]), Ok(0.5));
+        let Err(SimulationError::SampleTime(err)) = sim.check_sample_time(&[&pt1, &pt2, &gain])
+        else {
+            panic!("PT2 not reported");
+        };
+        assert_eq!(
+            err.mismatches,
+            vec![SampleTimeMismatch {
+                index: 1,
+                block: "PT2",
+                sample_time: 1.0
+            }]
+        );
+    }
+
+    #[test]
+    fn test_Simulation_checks_composites() {
+        use crate::plant::series::Series;
+        use crate::plant::{pt1::PT1, pt2::PT2};
+        use std::boxed::Box;
+        let pt1 = PT1::<f64>::default().set_sample_time_or_default(0.5);
+        let pt2 = PT2::<f64>::default().set_sample_time_or_default(0.5);
+        let mut series: Series<f64> = Series::new(vec![
+            Box::new(pt1),
+            Box::new(UnitGain::default()),
+            Box::new(pt2),
+        ]);
+        // the base rate is inferred from the elements
+        let sim = Simulation::new(TimeRange::default())
+            .infer_sample_time(&[&series])
+            .unwrap();
+        assert_eq!(sim.range.sampling_interval, 0.5);
+        assert!(
+            sim.run_checked(&StepFunction::default(), &mut series)
+                .is_ok()
+        );
+        series.push(Box::new(PT2::<f64>::default()));
+        assert!(series.sample_time().unwrap().is_nan());
+        assert!(matches!(
+            sim.run_checked(&StepFunction::default(), &mut series),
+            Err(SimulationError::SampleTime(_))
+        ));
+        // a disabled element does not take part
+        series.set_enabled(3, false);
+        assert!(
+            sim.run_checked(&StepFunction::default(), &mut series)
+                .is_ok()
+        );
+        assert!(matches!(
+            Simulation::new(TimeRange::default())
+                .infer_sample_time(&[&pt1, &PT2::<f64>::default()]),
+            Err(SimulationError::SampleTime(_))
+        ));
+        // converted to the time unit of the range
+        let sim = Simulation::new(TimeRange::default())
+            .set_element_time_unit("s")
+            .unwrap()
+            .infer_sample_time(&[&pt1.set_sample_time_or_default(0.01)])
+            .unwrap();
+        assert_eq!(sim.range.sampling_interval, 10.0);
+        let mut pt2 = pt2.set_sample_time_or_default(0.01);
+        assert!(
+            sim.run_closed_loop_checked(
+                &StepFunction::default(),
+                &mut UnitGain::default(),
+                &mut pt2
+            )
+            .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_Simulation_element_time_unit() {
+        use crate::plant::pt1::PT1;
+        // 10 ms steps, the time constant and sample time in s
+        let range = TimeRange::default().set_sampling_interval(10.0);
+        let pt1 = PT1::<f64>::default().set_sample_time_or_default(0.01);
+        let sim = Simulation::new(range);
+        assert!(sim.check_sample_time(&[&pt1]).is_err());
+        let sim = sim.set_element_time_unit("s").unwrap();
+        assert_eq!(sim.check_sample_time(&[&pt1]), Ok(0.01));
+        assert_eq!(
+            Simulation::new(range).set_element_time_unit("days"),
+            Err(TimeUnitError::Unknown("days"))
+        );
+        let mut changed = sim;
+        changed.range = range.set_unit_of_measurement("fortnight");
+        assert_eq!(
+            changed.check_sample_time(&[&pt1]),
+            Err(SimulationError::TimeUnit(TimeUnitError::Unknown(
+                "fortnight"
+            )))
+        );
+        let result = sim.run(&StepFunction::default(), &mut UnitGain::default());
+        assert!((result.time_in("s").unwrap()[0] - 0.01).abs() < 1e-12);
+        let result = result.convert_time_unit("s").unwrap();
+        assert_eq!(result.time_unit, "s");
+        assert!((result.time[0] - 0.01).abs() < 1e-12);
+        assert!((result.trace("output").unwrap().meta.sample_interval - 0.01).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_SimResult_to_csv_header() {
+        let mut gain = UnitGain::default();
+        let result = Simulation::new(TimeRange::default().set_end(2.0))
+            .run(&StepFunction::default(), &mut gain);
+        let csv = result.to_csv();
+        assert!(csv.starts_with("time [ms],input [1],output [1]\n"));
+    }
+
+    #[test]
+    fn test_SimResult_diff() {
+        let sim = Simulation::new(TimeRange::default().set_end(5.0));
+        let reference = sim.run(&StepFunction::default(), &mut UnitGain::default());
+        let mut other = sim.run(&StepFunction::default(), &mut UnitGain::default());
+        assert!(reference.diff(&other).is_identical());
+
+        other.traces[1].values[3] += 0.5;
+        other.traces[1].values[4] -= 0.25;
+        other.traces[0].name = String::from("setpoint");
+        let diff = reference.diff(&other);
+        assert!(!diff.is_identical());
+        assert_eq!(diff.unmatched, vec!["input", "setpoint"]);
+        let output = diff.deviation("output").unwrap();
+        assert_eq!(output.max_abs, 0.5);
+        assert_eq!(output.first_divergence, Some(reference.time[3]));
+        assert_eq!(diff.errors.trace("output").unwrap().values[4], -0.25);
+        assert_eq!(diff.first_divergence(0.2), Some(reference.time[3]));
+        assert!(diff.within(0.5));
+        assert!(!diff.within(0.4));
+    }
+}